@@ -0,0 +1,150 @@
+//! ACME DNS-01 challenge support.
+//!
+//! [`Dns01Solver`] publishes the `_acme-challenge` TXT record for an identifier,
+//! waits for it to propagate, and removes it again once the order is validated.
+
+use std::time::Duration;
+
+use crate::api::{DnsRecordId, UpsertOptions};
+use crate::propagation::{PropagationChecker, PropagationError};
+use crate::{SimplyClient, SimplyClientError};
+
+/// A TXT record published by [`Dns01Solver::present`], to be passed to [`Dns01Solver::cleanup`].
+#[derive(Debug)]
+pub struct Dns01Challenge {
+    /// Record name, relative to the zone, e.g. "_acme-challenge.www".
+    pub name: String,
+    /// The TXT value that was published.
+    pub value: String,
+    /// IDs of the TXT record(s) created for the challenge.
+    pub record_ids: Vec<DnsRecordId>,
+}
+
+/// Solves ACME DNS-01 challenges using TXT records in a Simply.com zone.
+///
+/// Each challenge value gets its own TXT record, so a wildcard and an apex
+/// certificate can be validated at the same time without clobbering each other.
+///
+/// Example usage:
+/// ```rust,no_run
+/// use simply_dns::SimplyClient;
+/// use simply_dns::acme::Dns01Solver;
+///
+/// # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+/// let client = SimplyClient::new("account", "api_key");
+/// let solver = Dns01Solver::new(&client, "example.com");
+/// // `dns_value` is the base64url SHA-256 digest of the key authorization,
+/// // e.g. `challenge.key_authorization().dns_value()` in instant-acme.
+/// let challenge = solver.present("*.example.com", "dns_value").await?;
+/// // ... tell the ACME server the challenge is ready and wait for validation ...
+/// solver.cleanup(challenge).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Dns01Solver<'a> {
+    client: &'a SimplyClient,
+    zone: String,
+    ttl: u32,
+    propagation_delay: Duration,
+}
+
+impl<'a> Dns01Solver<'a> {
+    /// Create a solver for identifiers in `zone` (the Simply.com product/domain name).
+    pub fn new(client: &'a SimplyClient, zone: impl Into<String>) -> Self {
+        Self {
+            client,
+            zone: zone.into(),
+            ttl: 60,
+            propagation_delay: Duration::from_secs(30),
+        }
+    }
+
+    /// TTL of the challenge record, in seconds. Defaults to 60.
+    pub fn ttl(mut self, ttl: u32) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// How long [`present`](Self::present) waits after creating the record. Defaults to 30 seconds.
+    pub fn propagation_delay(mut self, delay: Duration) -> Self {
+        self.propagation_delay = delay;
+        self
+    }
+
+    /// The challenge record name for `identifier`, relative to the zone.
+    ///
+    /// A leading `*.` is stripped, as wildcard identifiers are validated at the base name.
+    pub fn record_name(&self, identifier: &str) -> String {
+        let identifier = identifier.trim_end_matches('.');
+        let identifier = identifier.strip_prefix("*.").unwrap_or(identifier);
+        let zone = self.zone.trim_end_matches('.');
+        match identifier.strip_suffix(zone) {
+            Some("") => "_acme-challenge".to_string(),
+            Some(sub) if sub.ends_with('.') => {
+                format!("_acme-challenge.{}", sub.trim_end_matches('.'))
+            }
+            _ => format!("_acme-challenge.{identifier}"),
+        }
+    }
+
+    /// Publish the TXT record for `identifier` with `dns_value` and wait for it to propagate.
+    ///
+    /// Other values at the same name are left alone, see
+    /// [`SimplyClient::append_txt_record`]. Presenting a value that is already
+    /// published reuses its record.
+    ///
+    /// # Arguments
+    /// * `identifier` - The domain name being validated, e.g. "www.example.com" or "*.example.com".
+    /// * `dns_value` - The TXT value required by the ACME server.
+    pub async fn present(
+        &self,
+        identifier: &str,
+        dns_value: &str,
+    ) -> Result<Dns01Challenge, SimplyClientError> {
+        let name = self.record_name(identifier);
+        let opts = UpsertOptions {
+            priority: None,
+            ttl: Some(self.ttl),
+            comment: Some("ACME DNS-01 challenge".to_string()),
+        };
+        let record_ids = self
+            .client
+            .append_txt_record(&self.zone, &name, dns_value, opts)
+            .await?;
+        tokio::time::sleep(self.propagation_delay).await;
+        Ok(Dns01Challenge {
+            name,
+            value: dns_value.to_string(),
+            record_ids,
+        })
+    }
+
+    /// Wait until `challenge` resolves on the servers queried by `checker`.
+    ///
+    /// A more reliable alternative to a fixed [`propagation_delay`](Self::propagation_delay),
+    /// which can then be set to zero.
+    pub async fn wait_for_propagation(
+        &self,
+        challenge: &Dns01Challenge,
+        checker: &PropagationChecker,
+        timeout: Duration,
+    ) -> Result<(), PropagationError> {
+        checker
+            .wait_for_record(
+                &self.zone,
+                &challenge.name,
+                "TXT",
+                &challenge.value,
+                timeout,
+            )
+            .await
+    }
+
+    /// Remove the TXT record(s) created by [`present`](Self::present).
+    pub async fn cleanup(&self, challenge: Dns01Challenge) -> Result<(), SimplyClientError> {
+        for id in challenge.record_ids {
+            self.client.delete_dns_record(&self.zone, id).await?;
+        }
+        Ok(())
+    }
+}
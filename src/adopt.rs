@@ -0,0 +1,84 @@
+//! Bringing existing records under declarative control.
+//!
+//! [`SimplyClient::adopt`] tags hand-managed records with an ownership tag in
+//! their comment and returns them, so they can be written out as the start of
+//! a zone spec, e.g. with [`export_zone_file`](crate::zonefile::export_zone_file).
+//! A [`DesiredZone`](crate::sync::DesiredZone) set up with
+//! [`owned_by`](crate::sync::DesiredZone::owned_by) then manages the adopted
+//! records and leaves everything else alone, so a zone can be converted a few
+//! records at a time.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::query::RecordQuery;
+//! use simply_dns::zonefile::export_zone_file;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let filter = RecordQuery::new().name_glob("mail*");
+//! let report = client.adopt("example.com", &filter, "managed-by=simply-dns").await?;
+//! let adopted: Vec<_> = report
+//!     .succeeded
+//!     .into_iter()
+//!     .map(|s| s.item)
+//!     .chain(report.skipped.into_iter().map(|s| s.item))
+//!     .collect();
+//! std::fs::write("example.com.zone", export_zone_file(&adopted))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::api::{DnsRecord, UpdateDnsRecordRequest};
+use crate::progress::Operation;
+use crate::query::RecordQuery;
+use crate::report::OperationReport;
+use crate::sync::{has_owner_tag, tag_comment};
+use crate::time::Instant;
+use crate::{SimplyClient, SimplyClientError};
+
+impl SimplyClient {
+    /// Tag the records of a domain matching `filter` as owned by `owner`.
+    ///
+    /// The tag is appended to each record's comment. Records that already
+    /// carry it are reported as skipped. The reported records have their
+    /// updated comments and zone-relative names, ready to be written out as
+    /// spec entries. Fails only if the records can't be listed; failed updates
+    /// are collected in the report.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain whose records to adopt.
+    /// * `filter` - Which records to adopt.
+    /// * `owner` - The ownership tag, a single word such as "managed-by=simply-dns".
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn adopt(
+        &self,
+        domain: &str,
+        filter: &RecordQuery,
+        owner: &str,
+    ) -> Result<OperationReport<DnsRecord>, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let started = Instant::now();
+        let mut report = OperationReport::default();
+        let records = this.find_records(domain, filter).await?;
+        let mut progress = this.progress(Operation::Adopt, Some(records.len()));
+        for mut record in records {
+            record.name = this.api_name(&record.name, domain);
+            if has_owner_tag(record.comment.as_deref(), owner) {
+                progress.skip(&mut report, record, "already owned");
+                continue;
+            }
+            progress.begin();
+            let record_started = Instant::now();
+            record.comment = Some(tag_comment(record.comment.as_deref(), owner));
+            let req = UpdateDnsRecordRequest::from(record.clone());
+            let result = this.update_dns_record(domain, record.record_id, req).await;
+            progress.record(&mut report, record, result, record_started);
+        }
+        report.elapsed = started.elapsed();
+        progress.finish(&report);
+        Ok(report)
+    }
+}
@@ -1,10 +1,29 @@
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 
+use crate::record_data::{RecordData, RecordDataError};
+
 /// Represents a DNS record as returned by the Simply.com DNS API.
 ///
 /// Fields map directly to the API response schema. For details, refer to the official API docs.
 /// See: https://www.simply.com/en/docs/api/
-#[derive(Debug, serde::Deserialize)]
+///
+/// Records serialize to the same JSON they are read from, so they can be
+/// exported, stored and read back. To edit and resubmit one, convert it into
+/// an [`UpdateDnsRecordRequest`] (or a [`CreateDnsRecordRequest`] to copy it):
+/// ```rust
+/// # use simply_dns::api::{DnsRecord, UpdateDnsRecordRequest};
+/// # fn edit(record: DnsRecord) -> UpdateDnsRecordRequest {
+/// UpdateDnsRecordRequest {
+///     ttl: Some(300),
+///     ..record.into()
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DnsRecord {
     /// Unique identifier for the DNS record.
     pub record_id: DnsRecordId,
@@ -23,6 +42,30 @@ pub struct DnsRecord {
     pub comment: Option<String>,
 }
 
+impl DnsRecord {
+    /// Parse `data` (and `priority`) according to the record's type.
+    pub fn parsed_data(&self) -> Result<RecordData, RecordDataError> {
+        RecordData::parse(&self.record_type, &self.data, self.priority)
+    }
+
+    /// Whether `other` has the same name, type, data, TTL, priority and comment.
+    pub(crate) fn same_contents(&self, other: &DnsRecord) -> bool {
+        self.name == other.name
+            && self.record_type == other.record_type
+            && self.data == other.data
+            && self.ttl == other.ttl
+            && self.priority == other.priority
+            && self.comment == other.comment
+    }
+}
+
+/// Shows the name, type and data of the record, e.g. `www A 192.0.2.1`.
+impl fmt::Display for DnsRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.name, self.record_type, self.data)
+    }
+}
+
 impl From<DnsRecordResponse> for DnsRecord {
     fn from(value: DnsRecordResponse) -> Self {
         DnsRecord {
@@ -66,7 +109,7 @@ pub(crate) struct ListDnsRecordsResponse {
 }
 
 /// Request payload for creating a DNS record via the API.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CreateDnsRecordRequest {
     /// Type of DNS record to create ("A", "CNAME", "MX", etc.).
     #[serde(rename = "type")]
@@ -83,22 +126,174 @@ pub struct CreateDnsRecordRequest {
     pub comment: Option<String>,
 }
 
+/// Shows the name, type and data of the record, e.g. `www A 192.0.2.1`.
+impl fmt::Display for CreateDnsRecordRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.name, self.record_type, self.data)
+    }
+}
+
+impl CreateDnsRecordRequest {
+    /// Create a request for a record named `name` with typed `data`, without TTL or comment.
+    pub fn new(name: impl Into<String>, data: RecordData) -> Self {
+        Self {
+            record_type: data.record_type().to_string(),
+            name: name.into(),
+            data: data.data(),
+            priority: data.priority(),
+            ttl: None,
+            comment: None,
+        }
+    }
+}
+
+/// Copies the record's contents, without its ID.
+impl From<DnsRecord> for CreateDnsRecordRequest {
+    fn from(record: DnsRecord) -> Self {
+        Self {
+            record_type: record.record_type,
+            name: record.name,
+            data: record.data,
+            priority: record.priority,
+            ttl: Some(record.ttl),
+            comment: record.comment,
+        }
+    }
+}
+
 /// Response for creating a DNS record via the API.
 #[derive(Debug, Deserialize)]
 pub(crate) struct CreateDnsRecordResponse {
     /// List of created record IDs (if any).
+    #[serde(default, deserialize_with = "one_or_many")]
     pub record: Option<Vec<DnsRecordId>>, // Record id(s) created
 }
 
 /// Structure representing the ID of a newly created DNS record.
-#[derive(Debug, Deserialize)]
+///
+/// IDs can be kept between runs, e.g. as their number, to address the record later:
+/// ```rust
+/// use simply_dns::api::DnsRecordId;
+///
+/// let id = DnsRecordId::new(42);
+/// assert_eq!(id.to_string(), "42");
+/// assert_eq!(u32::from(id), 42);
+/// assert_eq!("42".parse::<DnsRecordId>()?, DnsRecordId::from(42));
+/// # Ok::<(), std::num::ParseIntError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct DnsRecordId {
     /// ID of the added DNS record.
     pub(crate) id: u32,
 }
 
+impl DnsRecordId {
+    /// The ID with the number `id`, as assigned by the API.
+    pub fn new(id: u32) -> Self {
+        Self { id }
+    }
+
+    /// The number of the ID.
+    pub fn get(self) -> u32 {
+        self.id
+    }
+}
+
+impl From<u32> for DnsRecordId {
+    fn from(id: u32) -> Self {
+        Self::new(id)
+    }
+}
+
+impl From<DnsRecordId> for u32 {
+    fn from(id: DnsRecordId) -> Self {
+        id.id
+    }
+}
+
+/// Formats the ID as its number, e.g. for showing it to users or storing it.
+impl fmt::Display for DnsRecordId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+/// Parses an ID formatted with [`Display`](fmt::Display).
+impl FromStr for DnsRecordId {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self { id: s.parse()? })
+    }
+}
+
+/// A record ID together with the domain it belongs to.
+///
+/// Record IDs are only meaningful within their zone: updating or deleting
+/// an ID with the wrong domain either fails with a confusing "not found" or
+/// changes an unrelated record. The `_by_ref` methods, such as
+/// [`SimplyClient::update_dns_record_by_ref`](crate::SimplyClient::update_dns_record_by_ref),
+/// take a reference instead and fail with
+/// [`SimplyClientError::WrongZone`](crate::SimplyClientError::WrongZone)
+/// if it belongs to another domain than the call names.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::api::{DnsRecordId, ZoneRecordRef};
+///
+/// let record = ZoneRecordRef::new("Example.com.", DnsRecordId::new(42));
+/// assert!(record.belongs_to("example.com"));
+/// assert!(!record.belongs_to("example.net"));
+/// assert_eq!(record.to_string(), "42 in example.com");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ZoneRecordRef {
+    domain: String,
+    id: DnsRecordId,
+}
+
+impl ZoneRecordRef {
+    /// The record `id` of `domain`. The domain is kept in lowercase without a trailing dot.
+    pub fn new(domain: &str, id: DnsRecordId) -> Self {
+        Self {
+            domain: domain.trim_end_matches('.').to_ascii_lowercase(),
+            id,
+        }
+    }
+
+    /// The domain the record belongs to.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// The ID of the record within its domain.
+    pub fn id(&self) -> DnsRecordId {
+        self.id
+    }
+
+    /// Whether the record belongs to `domain`, ignoring case and a trailing dot.
+    pub fn belongs_to(&self, domain: &str) -> bool {
+        self.domain
+            .eq_ignore_ascii_case(domain.trim_end_matches('.'))
+    }
+}
+
+/// Shows the ID and the domain, e.g. `42 in example.com`.
+impl fmt::Display for ZoneRecordRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in {}", self.id, self.domain)
+    }
+}
+
+impl DnsRecord {
+    /// A reference to this record, which was listed from `domain`.
+    pub fn zone_ref(&self, domain: &str) -> ZoneRecordRef {
+        ZoneRecordRef::new(domain, self.record_id)
+    }
+}
+
 /// Request payload for updating an existing DNS record via the API.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct UpdateDnsRecordRequest {
     /// Type of DNS record to update ("A", "CNAME", "MX", etc.).
     #[serde(rename = "type")]
@@ -115,9 +310,336 @@ pub struct UpdateDnsRecordRequest {
     pub comment: Option<String>,
 }
 
-/// Response for deleting a DNS record via the API.
+impl UpdateDnsRecordRequest {
+    /// Create a request setting the record to `name` and typed `data`, without TTL or comment.
+    pub fn new(name: impl Into<String>, data: RecordData) -> Self {
+        Self {
+            record_type: data.record_type().to_string(),
+            name: name.into(),
+            data: data.data(),
+            priority: data.priority(),
+            ttl: None,
+            comment: None,
+        }
+    }
+}
+
+/// How an [`UpdatePatch`] changes an optional field.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FieldUpdate<T> {
+    /// Leave the field as it is.
+    #[default]
+    Keep,
+    /// Remove the field's value.
+    Clear,
+    /// Set the field to the value.
+    Set(T),
+}
+
+impl<T: Clone> FieldUpdate<T> {
+    /// The field's value after the update, given its `current` value.
+    pub fn apply(&self, current: Option<T>) -> Option<T> {
+        match self {
+            FieldUpdate::Keep => current,
+            FieldUpdate::Clear => None,
+            FieldUpdate::Set(value) => Some(value.clone()),
+        }
+    }
+}
+
+/// A partial update of a DNS record, for [`SimplyClient::patch_dns_record`](crate::SimplyClient::patch_dns_record).
+///
+/// Unlike an [`UpdateDnsRecordRequest`], which replaces every field, a patch
+/// tells apart leaving a field unchanged, clearing it and setting it.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::api::{FieldUpdate, UpdatePatch};
+///
+/// let patch = UpdatePatch::new().ttl(300).clear_comment();
+/// assert_eq!(patch.ttl, FieldUpdate::Set(300));
+/// assert_eq!(patch.priority, FieldUpdate::Keep);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpdatePatch {
+    /// The new name, or `None` to keep it.
+    pub name: Option<String>,
+    /// The new data, or `None` to keep it.
+    pub data: Option<String>,
+    /// The change to the priority.
+    pub priority: FieldUpdate<u32>,
+    /// The change to the TTL; clearing it sets the zone's default.
+    pub ttl: FieldUpdate<u32>,
+    /// The change to the comment.
+    pub comment: FieldUpdate<String>,
+}
+
+impl UpdatePatch {
+    /// A patch leaving everything as it is.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rename the record to `name`.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the record's data to `data`.
+    pub fn data(mut self, data: impl Into<String>) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+
+    /// Set the priority to `priority`.
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = FieldUpdate::Set(priority);
+        self
+    }
+
+    /// Set the TTL to `ttl` seconds.
+    pub fn ttl(mut self, ttl: u32) -> Self {
+        self.ttl = FieldUpdate::Set(ttl);
+        self
+    }
+
+    /// Set the comment to `comment`.
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = FieldUpdate::Set(comment.into());
+        self
+    }
+
+    /// Remove the priority.
+    pub fn clear_priority(mut self) -> Self {
+        self.priority = FieldUpdate::Clear;
+        self
+    }
+
+    /// Reset the TTL to the zone's default.
+    pub fn clear_ttl(mut self) -> Self {
+        self.ttl = FieldUpdate::Clear;
+        self
+    }
+
+    /// Remove the comment.
+    pub fn clear_comment(mut self) -> Self {
+        self.comment = FieldUpdate::Clear;
+        self
+    }
+
+    /// The full update turning `record` into the patched record.
+    ///
+    /// A cleared comment is set to an empty one, which the API stores as no comment.
+    pub fn apply_to(&self, record: &DnsRecord) -> UpdateDnsRecordRequest {
+        let comment = match &self.comment {
+            FieldUpdate::Clear => Some(String::new()),
+            update => update.apply(record.comment.clone()),
+        };
+        UpdateDnsRecordRequest {
+            record_type: record.record_type.clone(),
+            name: self.name.clone().unwrap_or_else(|| record.name.clone()),
+            data: self.data.clone().unwrap_or_else(|| record.data.clone()),
+            priority: self.priority.apply(record.priority),
+            ttl: self.ttl.apply(Some(record.ttl)),
+            comment,
+        }
+    }
+
+    /// The body fields the patch clears, which must be sent as `null`.
+    pub(crate) fn cleared_fields(&self) -> Vec<&'static str> {
+        let mut fields = Vec::new();
+        if self.priority == FieldUpdate::Clear {
+            fields.push("priority");
+        }
+        if self.ttl == FieldUpdate::Clear {
+            fields.push("ttl");
+        }
+        fields
+    }
+}
+
+/// Sets the record to its current contents, to be edited before sending.
+impl From<DnsRecord> for UpdateDnsRecordRequest {
+    fn from(record: DnsRecord) -> Self {
+        Self {
+            record_type: record.record_type,
+            name: record.name,
+            data: record.data,
+            priority: record.priority,
+            ttl: Some(record.ttl),
+            comment: record.comment,
+        }
+    }
+}
+
+/// General response body of the API, used to read error details.
 #[derive(Debug, Deserialize)]
 pub(crate) struct GeneralResponse {
+    /// Status code reported in the body.
+    #[serde(default)]
+    pub status: Option<u16>,
     /// Message from the API, e.g. "success" or error details.
+    #[serde(default)]
     pub message: Option<String>,
+    /// The invalid field, for validation errors.
+    #[serde(default)]
+    pub field: Option<String>,
+}
+
+/// Optional settings for [`SimplyClient::upsert_dns_record`](crate::SimplyClient::upsert_dns_record).
+#[derive(Debug, Default, Clone)]
+pub struct UpsertOptions {
+    /// Priority value for records that require it.
+    pub priority: Option<u32>,
+    /// Time to live (TTL) for the record, in seconds.
+    pub ttl: Option<u32>,
+    /// Optional comment or metadata for the record.
+    pub comment: Option<String>,
+}
+
+/// Result of an upsert, telling whether a record was created or updated.
+#[derive(Debug)]
+pub enum UpsertOutcome {
+    /// No record matched the name and type, so a new one was created.
+    Created(Vec<DnsRecordId>),
+    /// An existing record matched and was updated in place.
+    Updated(DnsRecordId),
+}
+
+/// A product (domain, hosting, etc.) on the Simply.com account.
+///
+/// See: https://www.simply.com/en/docs/api/
+#[derive(Debug, Clone, Deserialize)]
+pub struct Product {
+    /// The product's object name, used as `domain` in the DNS methods.
+    pub object: String,
+    /// Display name of the product.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The domain attached to the product, if any.
+    #[serde(default)]
+    pub domain: Option<ProductDomain>,
+    /// Whether the product renews automatically.
+    #[serde(default, deserialize_with = "lenient_bool")]
+    pub autorenew: Option<bool>,
+    /// Expiry date of the product as returned by the API, e.g. "2025-06-01".
+    #[serde(default, rename = "date_expire")]
+    pub expires: Option<String>,
+}
+
+/// The domain attached to a [`Product`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProductDomain {
+    /// The domain name, e.g. "example.com".
+    pub name: String,
+    /// The internationalized (Unicode) form of the domain name.
+    #[serde(default)]
+    pub name_idn: Option<String>,
+}
+
+/// Response for listing the products of the account.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListProductsResponse {
+    /// The products on the account.
+    #[serde(default)]
+    pub products: Vec<Product>,
+}
+
+/// A DS record of a DNSSEC-signed zone, to be published in the parent zone.
+///
+/// See: https://www.simply.com/en/docs/api/
+#[derive(Debug, Clone, Deserialize)]
+pub struct DsRecord {
+    /// Tag of the key signing key the record refers to.
+    #[serde(alias = "keytag")]
+    pub key_tag: u16,
+    /// DNSSEC algorithm number of the key, e.g. 13 for ECDSA P-256 with SHA-256.
+    pub algorithm: u8,
+    /// Digest algorithm number, e.g. 2 for SHA-256.
+    #[serde(alias = "digesttype")]
+    pub digest_type: u8,
+    /// Hex-encoded digest of the key.
+    pub digest: String,
+}
+
+impl DsRecord {
+    /// The record data in zone file format, e.g. `2371 13 2 1F98...`.
+    pub fn data(&self) -> String {
+        format!(
+            "{} {} {} {}",
+            self.key_tag, self.algorithm, self.digest_type, self.digest
+        )
+    }
+}
+
+/// A DNSSEC signing key of a zone.
+///
+/// See: https://www.simply.com/en/docs/api/
+#[derive(Debug, Clone, Deserialize)]
+pub struct DnssecKey {
+    /// Tag identifying the key.
+    #[serde(alias = "keytag")]
+    pub key_tag: u16,
+    /// DNSKEY flags; 257 marks a key signing key, 256 a zone signing key.
+    pub flags: u16,
+    /// DNSSEC algorithm number of the key.
+    pub algorithm: u8,
+    /// Base64-encoded public key.
+    #[serde(alias = "publickey")]
+    pub public_key: String,
+}
+
+impl DnssecKey {
+    /// Whether this is a key signing key, the one DS records refer to.
+    pub fn is_key_signing_key(&self) -> bool {
+        self.flags & 1 == 1
+    }
+
+    /// The DNSKEY record data in zone file format, e.g. `257 3 13 mdsswUyr...`.
+    pub fn dnskey_data(&self) -> String {
+        format!("{} 3 {} {}", self.flags, self.algorithm, self.public_key)
+    }
+}
+
+/// Response for fetching the DS records of a zone.
+#[derive(Debug, Deserialize)]
+pub(crate) struct DnssecDsResponse {
+    /// The DS records.
+    #[serde(default)]
+    pub records: Vec<DsRecord>,
+}
+
+/// Response for listing the DNSSEC signing keys of a zone.
+#[derive(Debug, Deserialize)]
+pub(crate) struct DnssecKeysResponse {
+    /// The signing keys.
+    #[serde(default)]
+    pub keys: Vec<DnssecKey>,
+}
+
+/// Accept a single record ID as well as a list of them.
+fn one_or_many<'de, D: serde::Deserializer<'de>>(
+    de: D,
+) -> Result<Option<Vec<DnsRecordId>>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(DnsRecordId),
+        Many(Vec<DnsRecordId>),
+    }
+    Ok(Option::<OneOrMany>::deserialize(de)?.map(|ids| match ids {
+        OneOrMany::One(id) => vec![id],
+        OneOrMany::Many(ids) => ids,
+    }))
+}
+
+/// Accept booleans sent as `true`, `1` or `"true"`.
+fn lenient_bool<'de, D: serde::Deserializer<'de>>(de: D) -> Result<Option<bool>, D::Error> {
+    Ok(match Option::<serde_json::Value>::deserialize(de)? {
+        Some(serde_json::Value::Bool(b)) => Some(b),
+        Some(serde_json::Value::Number(n)) => Some(n.as_u64() != Some(0)),
+        Some(serde_json::Value::String(s)) => Some(matches!(s.as_str(), "true" | "1" | "yes")),
+        _ => None,
+    })
 }
@@ -0,0 +1,342 @@
+//! Approval of plans before they are applied.
+//!
+//! An [`ApprovalHook`] registered with
+//! [`SimplyClientBuilder::approval_hook`](crate::SimplyClientBuilder::approval_hook)
+//! is asked before the client applies a non-empty [`ZonePlan`], whether through
+//! [`SimplyClient::apply`](crate::SimplyClient::apply),
+//! [`SimplyClient::sync`](crate::SimplyClient::sync) or a helper built on them.
+//! The plan is only applied if every hook approves it; otherwise all of its
+//! changes are reported as skipped with the reason the hook gave.
+//!
+//! Rather than building each approval flow into the crate, [`CommandApproval`]
+//! runs a script and [`HttpApproval`] calls an HTTP endpoint, both with the
+//! plan as [JSON](crate::sync::ZonePlan::to_json), so approvals can be
+//! connected to chat buttons, ticketing systems and the like.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::approval::CommandApproval;
+//!
+//! let client = SimplyClient::builder()
+//!     .credentials("account", "api_key")
+//!     .approval_hook(CommandApproval::new("/usr/local/bin/approve-dns-change"))
+//!     .build()?;
+//! # Ok::<(), simply_dns::SimplyClientError>(())
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+
+use crate::sync::ZonePlan;
+
+/// The answer of an [`ApprovalHook`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Approval {
+    /// The plan may be applied.
+    Approved,
+    /// The plan must not be applied, for the given reason.
+    Rejected(String),
+}
+
+/// Decides whether a plan may be applied.
+///
+/// Hooks are only asked about plans with changes. The returned future may
+/// take as long as a person needs to decide; the domain stays
+/// [locked](crate::lock) while it runs. A hook that can't reach its approver
+/// should reject the plan rather than approve it.
+pub trait ApprovalHook: Send + Sync {
+    /// Approve or reject `plan`.
+    fn approve<'a>(&'a self, plan: &'a ZonePlan) -> BoxFuture<'a, Approval>;
+}
+
+/// The hooks registered with a client.
+#[derive(Clone, Default)]
+pub(crate) struct ApprovalHooks(pub(crate) Vec<Arc<dyn ApprovalHook>>);
+
+impl ApprovalHooks {
+    /// Ask every hook in turn, stopping at the first rejection.
+    pub(crate) async fn check(&self, plan: &ZonePlan) -> Approval {
+        if plan.is_empty() {
+            return Approval::Approved;
+        }
+        for hook in &self.0 {
+            if let rejected @ Approval::Rejected(_) = hook.approve(plan).await {
+                return rejected;
+            }
+        }
+        Approval::Approved
+    }
+}
+
+impl fmt::Debug for ApprovalHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ApprovalHooks({})", self.0.len())
+    }
+}
+
+/// Asks an external program to approve plans.
+///
+/// The program gets the plan as JSON on its standard input and approves it by
+/// exiting with status 0. Otherwise the plan is rejected, with the program's
+/// standard error (or, if empty, its standard output) as the reason. Not
+/// available on wasm32.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct CommandApproval {
+    program: std::path::PathBuf,
+    args: Vec<String>,
+    timeout: Option<Duration>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl CommandApproval {
+    /// How often a running program is checked for having exited.
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Run `program` for every plan.
+    pub fn new(program: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            timeout: None,
+        }
+    }
+
+    /// Pass `arg` to the program.
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Kill the program and reject the plan if it hasn't decided after
+    /// `timeout`. By default it may run indefinitely.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    async fn run(&self, plan: &ZonePlan) -> Result<Approval, String> {
+        use std::io::{Read, Write};
+        use std::process::{Command, Stdio};
+
+        let program = self.program.display();
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("couldn't run {program}: {e}"))?;
+        let json = plan.to_json().to_string();
+        if let Some(mut stdin) = child.stdin.take() {
+            // A program that decides without reading the plan closes the pipe.
+            let _ = stdin.write_all(json.as_bytes());
+        }
+        let started = crate::time::Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) => {}
+                Err(e) => return Err(format!("couldn't wait for {program}: {e}")),
+            }
+            if self
+                .timeout
+                .is_some_and(|timeout| started.elapsed() >= timeout)
+            {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("{program} didn't decide in time"));
+            }
+            crate::time::sleep(Self::POLL_INTERVAL).await;
+        };
+        if status.success() {
+            return Ok(Approval::Approved);
+        }
+        let read = |pipe: Option<&mut dyn Read>| {
+            let mut text = String::new();
+            if let Some(pipe) = pipe {
+                let _ = pipe.read_to_string(&mut text);
+            }
+            text.trim().to_string()
+        };
+        let mut output = read(child.stderr.as_mut().map(|p| p as &mut dyn Read));
+        if output.is_empty() {
+            output = read(child.stdout.as_mut().map(|p| p as &mut dyn Read));
+        }
+        Ok(Approval::Rejected(if output.is_empty() {
+            format!("rejected by {program} ({status})")
+        } else {
+            output
+        }))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ApprovalHook for CommandApproval {
+    fn approve<'a>(&'a self, plan: &'a ZonePlan) -> BoxFuture<'a, Approval> {
+        Box::pin(async move { self.run(plan).await.unwrap_or_else(Approval::Rejected) })
+    }
+}
+
+/// Asks an HTTP endpoint to approve plans.
+///
+/// The plan is `POST`ed as JSON and approved by a `2xx` response. Otherwise,
+/// including when the endpoint can't be reached, the plan is rejected, with the
+/// response body (or its status) as the reason. The endpoint may hold the
+/// request open until someone decided; configure the [`client`](Self::client)
+/// with a matching timeout.
+#[derive(Clone)]
+pub struct HttpApproval {
+    url: String,
+    client: reqwest::Client,
+    headers: Vec<(String, String)>,
+}
+
+impl HttpApproval {
+    /// Send plans to `url`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::new(),
+            headers: Vec::new(),
+        }
+    }
+
+    /// Send the requests with `client`, e.g. to set a timeout.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Add the header `name: value` to every request, e.g. for a token.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    async fn send(&self, plan: &ZonePlan) -> Result<Approval, reqwest::Error> {
+        let mut request = self.client.post(&self.url).json(&plan.to_json());
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(Approval::Approved);
+        }
+        let body = response.text().await.unwrap_or_default();
+        Ok(Approval::Rejected(match body.trim() {
+            "" => format!("rejected by {} ({status})", self.url),
+            body => body.to_string(),
+        }))
+    }
+}
+
+impl fmt::Debug for HttpApproval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Header values are left out, as they usually carry a token.
+        let headers: Vec<&str> = self.headers.iter().map(|(name, _)| name.as_str()).collect();
+        f.debug_struct("HttpApproval")
+            .field("url", &self.url)
+            .field("headers", &headers)
+            .finish()
+    }
+}
+
+impl ApprovalHook for HttpApproval {
+    fn approve<'a>(&'a self, plan: &'a ZonePlan) -> BoxFuture<'a, Approval> {
+        Box::pin(async move {
+            self.send(plan)
+                .await
+                .unwrap_or_else(|e| Approval::Rejected(format!("couldn't reach {}: {e}", self.url)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimplyClient;
+    use crate::api::CreateDnsRecordRequest;
+    use crate::sync::Change;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("failed to start the runtime")
+            .block_on(future)
+    }
+
+    fn plan() -> ZonePlan {
+        ZonePlan {
+            domain: "example.com".to_string(),
+            changes: vec![Change::Create(CreateDnsRecordRequest {
+                record_type: "A".to_string(),
+                name: "www".to_string(),
+                data: "192.0.2.1".to_string(),
+                priority: None,
+                ttl: None,
+                comment: None,
+            })],
+        }
+    }
+
+    fn script(script: &str) -> CommandApproval {
+        CommandApproval::new("sh").arg("-c").arg(script)
+    }
+
+    #[test]
+    fn command_sees_the_plan() {
+        let hook = script(r#"grep -q '"domain":"example.com"'"#);
+        assert_eq!(block_on(hook.approve(&plan())), Approval::Approved);
+    }
+
+    #[test]
+    fn command_rejects_with_its_output() {
+        let hook = script("echo 'change freeze' >&2; exit 1");
+        let rejected = Approval::Rejected("change freeze".to_string());
+        assert_eq!(block_on(hook.approve(&plan())), rejected);
+
+        let Approval::Rejected(reason) = block_on(script("exit 3").approve(&plan())) else {
+            panic!("exit 3 was approved");
+        };
+        assert!(reason.contains("exit status: 3"), "{reason}");
+    }
+
+    #[test]
+    fn command_fails_closed() {
+        let hook = CommandApproval::new("/nonexistent/approve");
+        assert!(matches!(
+            block_on(hook.approve(&plan())),
+            Approval::Rejected(_)
+        ));
+
+        let hook = script("sleep 5").timeout(Duration::from_millis(200));
+        let Approval::Rejected(reason) = block_on(hook.approve(&plan())) else {
+            panic!("a timed out command was approved");
+        };
+        assert!(reason.contains("didn't decide in time"), "{reason}");
+    }
+
+    #[test]
+    fn rejected_plans_are_not_applied() {
+        // Nothing listens here, so an applied change would fail instead.
+        let client = SimplyClient::builder()
+            .credentials("account", "api_key")
+            .base_url("http://127.0.0.1:9/")
+            .approval_hook(script("exit 0"))
+            .approval_hook(script("echo 'needs a ticket'; exit 1"))
+            .build()
+            .unwrap();
+        let report = block_on(client.apply(plan()));
+        assert!(report.succeeded.is_empty() && report.failed.is_empty());
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].reason, "not approved: needs a ticket");
+    }
+}
@@ -0,0 +1,1136 @@
+//! Audit hooks for record changes.
+//!
+//! An [`AuditHook`] registered with
+//! [`SimplyClientBuilder::audit_hook`](crate::SimplyClientBuilder::audit_hook)
+//! is told about every record the client creates, updates or deletes, together
+//! with the record's state before the change. [`JsonLinesAuditLog`] and
+//! [`CsvAuditLog`] append these changes to a file as [`ChangeEvent`]s, with
+//! an optional actor label, [`SyslogAuditLog`] forwards them to a syslog
+//! server, `OtlpAuditLog` (with the `otlp` feature) exports them to an
+//! OpenTelemetry collector, and [`SimplyClient::replay_changes`] applies a
+//! log of them to another zone, e.g. to clone a zone's history.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::audit::JsonLinesAuditLog;
+//!
+//! let client = SimplyClient::builder()
+//!     .credentials("account", "api_key")
+//!     .audit_hook(JsonLinesAuditLog::open("/var/log/simply-dns/audit.jsonl")?)
+//!     .build()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord, DnsRecordId, UpdateDnsRecordRequest};
+use crate::names;
+use crate::progress::Operation;
+use crate::report::OperationReport;
+use crate::time::Instant;
+use crate::{SimplyClient, SimplyClientError};
+
+/// Who made a change and where.
+#[derive(Debug, Clone, Copy)]
+pub struct AuditContext<'a> {
+    /// The account the client is authenticated as.
+    pub account: &'a str,
+    /// The domain whose records changed.
+    pub domain: &'a str,
+    /// The reason a [freeze window](crate::freeze) was overridden, if the
+    /// change was made during one.
+    pub freeze_override: Option<&'a str>,
+}
+
+/// Receives the record changes a client makes.
+///
+/// Hooks are only called after the API accepted the change, and not in dry-run
+/// mode. To provide the previous state, a client with hooks fetches a record
+/// before updating or deleting it; `before` is `None` if that fails. Methods are
+/// blocking and run on the client's task, so they should return quickly.
+pub trait AuditHook: Send + Sync {
+    /// A record was created with the IDs `record_ids`.
+    fn on_create(
+        &self,
+        ctx: &AuditContext<'_>,
+        record_ids: &[DnsRecordId],
+        after: &CreateDnsRecordRequest,
+    ) {
+        let _ = (ctx, record_ids, after);
+    }
+
+    /// The record `record_id` was updated from `before` to `after`.
+    fn on_update(
+        &self,
+        ctx: &AuditContext<'_>,
+        record_id: &DnsRecordId,
+        before: Option<&DnsRecord>,
+        after: &UpdateDnsRecordRequest,
+    ) {
+        let _ = (ctx, record_id, before, after);
+    }
+
+    /// The record `record_id`, previously `before`, was deleted.
+    fn on_delete(
+        &self,
+        ctx: &AuditContext<'_>,
+        record_id: &DnsRecordId,
+        before: Option<&DnsRecord>,
+    ) {
+        let _ = (ctx, record_id, before);
+    }
+}
+
+/// The hooks registered with a client.
+#[derive(Clone, Default)]
+pub(crate) struct AuditHooks(pub(crate) Vec<Arc<dyn AuditHook>>);
+
+impl fmt::Debug for AuditHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AuditHooks({})", self.0.len())
+    }
+}
+
+/// What a [`ChangeEvent`] did to a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeAction {
+    /// The record was created.
+    Create,
+    /// The record was updated.
+    Update,
+    /// The record was deleted.
+    Delete,
+}
+
+/// Shows the action as written to change logs, e.g. "create".
+impl fmt::Display for ChangeAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ChangeAction::Create => "create",
+            ChangeAction::Update => "update",
+            ChangeAction::Delete => "delete",
+        })
+    }
+}
+
+/// The contents of a record before or after a [`ChangeEvent`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordSnapshot {
+    /// The record name, as the API returns it.
+    pub name: String,
+    /// Type of DNS record ("A", "CNAME", "MX", etc.).
+    #[serde(rename = "type")]
+    pub record_type: String,
+    /// The record data.
+    pub data: String,
+    /// The TTL in seconds, if known.
+    #[serde(default)]
+    pub ttl: Option<u32>,
+    /// The priority, for records that have one.
+    #[serde(default)]
+    pub priority: Option<u32>,
+    /// The comment, if any.
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+impl From<&DnsRecord> for RecordSnapshot {
+    fn from(record: &DnsRecord) -> Self {
+        Self {
+            name: record.name.clone(),
+            record_type: record.record_type.clone(),
+            data: record.data.clone(),
+            ttl: Some(record.ttl),
+            priority: record.priority,
+            comment: record.comment.clone(),
+        }
+    }
+}
+
+impl From<&CreateDnsRecordRequest> for RecordSnapshot {
+    fn from(req: &CreateDnsRecordRequest) -> Self {
+        Self {
+            name: req.name.clone(),
+            record_type: req.record_type.clone(),
+            data: req.data.clone(),
+            ttl: req.ttl,
+            priority: req.priority,
+            comment: req.comment.clone(),
+        }
+    }
+}
+
+impl From<&UpdateDnsRecordRequest> for RecordSnapshot {
+    fn from(req: &UpdateDnsRecordRequest) -> Self {
+        Self {
+            name: req.name.clone(),
+            record_type: req.record_type.clone(),
+            data: req.data.clone(),
+            ttl: req.ttl,
+            priority: req.priority,
+            comment: req.comment.clone(),
+        }
+    }
+}
+
+/// One record change, as written by [`JsonLinesAuditLog`] and [`CsvAuditLog`]
+/// and replayed by [`SimplyClient::replay_changes`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// When the change was made, in seconds since the Unix epoch.
+    pub time: u64,
+    /// What was done.
+    pub action: ChangeAction,
+    /// Who made the change, if the log was given an actor label.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    /// The account the change was made with.
+    pub account: String,
+    /// The domain whose record changed.
+    pub domain: String,
+    /// The IDs of the records, several for a create the API split up.
+    pub record_ids: Vec<u32>,
+    /// The record before the change, if known.
+    pub before: Option<RecordSnapshot>,
+    /// The record after the change; `None` for deletes.
+    pub after: Option<RecordSnapshot>,
+    /// Why the change was made during a [freeze window](crate::freeze), if it was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freeze_override: Option<String>,
+}
+
+impl ChangeEvent {
+    fn new(
+        action: ChangeAction,
+        ctx: &AuditContext<'_>,
+        actor: Option<&str>,
+        record_ids: &[DnsRecordId],
+        before: Option<&DnsRecord>,
+        after: Option<RecordSnapshot>,
+    ) -> Self {
+        Self {
+            time: crate::time::unix_time().as_secs(),
+            action,
+            actor: actor.map(str::to_string),
+            account: ctx.account.to_string(),
+            domain: ctx.domain.to_string(),
+            record_ids: record_ids.iter().map(|id| id.id).collect(),
+            before: before.map(RecordSnapshot::from),
+            after,
+            freeze_override: ctx.freeze_override.map(str::to_string),
+        }
+    }
+}
+
+/// Shows the action and the record, e.g. `update www A 192.0.2.2`.
+impl fmt::Display for ChangeEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.after.as_ref().or(self.before.as_ref()) {
+            Some(r) => write!(f, "{} {} {} {}", self.action, r.name, r.record_type, r.data),
+            None => write!(f, "{} {:?}", self.action, self.record_ids),
+        }
+    }
+}
+
+/// Error type for reading change logs.
+#[derive(Debug, Error)]
+pub enum ChangeLogError {
+    /// The log could not be read.
+    #[error("failed to read the change log: {0}")]
+    Io(#[from] io::Error),
+    /// A line isn't a change event.
+    #[error("invalid change event on line {line}: {source}")]
+    Json {
+        /// The line number, starting at 1.
+        line: usize,
+        /// What was wrong with it.
+        source: serde_json::Error,
+    },
+}
+
+/// Read the events of a log written by [`JsonLinesAuditLog`], skipping empty lines.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::audit::{ChangeAction, read_json_lines};
+///
+/// let log = r#"{"time":1700000000,"action":"delete","account":"S123456","domain":"example.com","record_ids":[7],"before":{"name":"old","type":"A","data":"192.0.2.1"},"after":null}"#;
+/// let events = read_json_lines(log.as_bytes())?;
+/// assert_eq!(events[0].action, ChangeAction::Delete);
+/// # Ok::<(), simply_dns::audit::ChangeLogError>(())
+/// ```
+pub fn read_json_lines(reader: impl BufRead) -> Result<Vec<ChangeEvent>, ChangeLogError> {
+    let mut events = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str(&line).map_err(|source| ChangeLogError::Json {
+            line: i + 1,
+            source,
+        })?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+/// Where a change log writes to, and the actor label of its events.
+struct LogWriter {
+    writer: Mutex<Box<dyn Write + Send>>,
+    actor: Option<String>,
+}
+
+impl LogWriter {
+    fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+            actor: None,
+        }
+    }
+
+    fn write_line(&self, line: &str) {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(_e) = writeln!(writer, "{line}").and_then(|()| writer.flush()) {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(error = %_e, "failed to write audit log");
+        }
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Implements [`AuditHook`] for a log type, given how it writes a [`ChangeEvent`].
+macro_rules! change_log_hook {
+    ($log:ty) => {
+        impl AuditHook for $log {
+            fn on_create(
+                &self,
+                ctx: &AuditContext<'_>,
+                record_ids: &[DnsRecordId],
+                after: &CreateDnsRecordRequest,
+            ) {
+                let actor = self.log.actor.as_deref();
+                let after = Some(RecordSnapshot::from(after));
+                self.write(&ChangeEvent::new(
+                    ChangeAction::Create,
+                    ctx,
+                    actor,
+                    record_ids,
+                    None,
+                    after,
+                ));
+            }
+
+            fn on_update(
+                &self,
+                ctx: &AuditContext<'_>,
+                record_id: &DnsRecordId,
+                before: Option<&DnsRecord>,
+                after: &UpdateDnsRecordRequest,
+            ) {
+                let actor = self.log.actor.as_deref();
+                let ids = std::slice::from_ref(record_id);
+                let after = Some(RecordSnapshot::from(after));
+                self.write(&ChangeEvent::new(
+                    ChangeAction::Update,
+                    ctx,
+                    actor,
+                    ids,
+                    before,
+                    after,
+                ));
+            }
+
+            fn on_delete(
+                &self,
+                ctx: &AuditContext<'_>,
+                record_id: &DnsRecordId,
+                before: Option<&DnsRecord>,
+            ) {
+                let actor = self.log.actor.as_deref();
+                let ids = std::slice::from_ref(record_id);
+                self.write(&ChangeEvent::new(
+                    ChangeAction::Delete,
+                    ctx,
+                    actor,
+                    ids,
+                    before,
+                    None,
+                ));
+            }
+        }
+
+        impl fmt::Debug for $log {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($log))
+                    .field("actor", &self.log.actor)
+                    .finish_non_exhaustive()
+            }
+        }
+    };
+}
+
+/// An [`AuditHook`] appending one JSON object per change to a file.
+///
+/// Each line is a [`ChangeEvent`]: a `time` (Unix seconds), `action`
+/// (`create`, `update` or `delete`), the `actor` if one was set, `account`,
+/// `domain`, `record_ids`, and the `before` and `after` state of the record
+/// where known, and the `freeze_override` reason of changes made during a
+/// freeze. [`read_json_lines`] reads the log back, e.g. to
+/// [replay](SimplyClient::replay_changes) it. Write errors can't fail the
+/// change that was already made, so they are only logged (with the `tracing`
+/// feature).
+pub struct JsonLinesAuditLog {
+    log: LogWriter,
+}
+
+impl JsonLinesAuditLog {
+    /// Append to the file at `path`, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::from_writer(open_append(path.as_ref())?))
+    }
+
+    /// Write to `writer`, e.g. a socket or an in-memory buffer.
+    pub fn from_writer(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            log: LogWriter::new(writer),
+        }
+    }
+
+    /// Label the changes with `actor`, e.g. the user or job making them.
+    pub fn actor(mut self, actor: impl Into<String>) -> Self {
+        self.log.actor = Some(actor.into());
+        self
+    }
+
+    fn write(&self, event: &ChangeEvent) {
+        match serde_json::to_string(event) {
+            Ok(line) => self.log.write_line(&line),
+            Err(_e) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %_e, "failed to serialize audit event");
+            }
+        }
+    }
+}
+
+change_log_hook!(JsonLinesAuditLog);
+
+/// An [`AuditHook`] appending one CSV row per change to a file, e.g. for a
+/// spreadsheet or a compliance archive.
+///
+/// The columns are those of [`CsvAuditLog::HEADER`]: the fields of a
+/// [`ChangeEvent`], with the record IDs separated by spaces and the `before`
+/// and `after` records spread over a column per field, empty where unknown.
+/// The header is written when the file is empty. Write errors are only
+/// logged, as with [`JsonLinesAuditLog`].
+///
+/// Example usage:
+/// ```rust,no_run
+/// use simply_dns::SimplyClient;
+/// use simply_dns::audit::CsvAuditLog;
+///
+/// let client = SimplyClient::builder()
+///     .credentials("account", "api_key")
+///     .audit_hook(CsvAuditLog::open("/var/log/simply-dns/changes.csv")?.actor("deploy-bot"))
+///     .build()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct CsvAuditLog {
+    log: LogWriter,
+}
+
+impl CsvAuditLog {
+    /// The header row.
+    pub const HEADER: &'static str = "time,actor,action,account,domain,record_ids,\
+        before_name,before_type,before_data,before_ttl,before_priority,before_comment,\
+        after_name,after_type,after_data,after_ttl,after_priority,after_comment,\
+        freeze_override";
+
+    /// Append to the file at `path`, creating it and writing the header if needed.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = open_append(path.as_ref())?;
+        let empty = file.metadata()?.len() == 0;
+        let log = Self::from_writer(file);
+        if empty {
+            log.log.write_line(Self::HEADER);
+        }
+        Ok(log)
+    }
+
+    /// Write rows to `writer`, without a header.
+    pub fn from_writer(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            log: LogWriter::new(writer),
+        }
+    }
+
+    /// Label the changes with `actor`, e.g. the user or job making them.
+    pub fn actor(mut self, actor: impl Into<String>) -> Self {
+        self.log.actor = Some(actor.into());
+        self
+    }
+
+    /// The CSV row of `event`, without a line break.
+    pub fn row(event: &ChangeEvent) -> String {
+        let ids: Vec<String> = event.record_ids.iter().map(u32::to_string).collect();
+        let mut fields = vec![
+            event.time.to_string(),
+            event.actor.clone().unwrap_or_default(),
+            event.action.to_string(),
+            event.account.clone(),
+            event.domain.clone(),
+            ids.join(" "),
+        ];
+        for record in [&event.before, &event.after] {
+            let number = |n: Option<u32>| n.map(|n| n.to_string()).unwrap_or_default();
+            match record {
+                Some(r) => fields.extend([
+                    r.name.clone(),
+                    r.record_type.clone(),
+                    r.data.clone(),
+                    number(r.ttl),
+                    number(r.priority),
+                    r.comment.clone().unwrap_or_default(),
+                ]),
+                None => fields.extend(std::iter::repeat_n(String::new(), 6)),
+            }
+        }
+        fields.push(event.freeze_override.clone().unwrap_or_default());
+        fields
+            .iter()
+            .map(|f| csv_field(f))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    fn write(&self, event: &ChangeEvent) {
+        self.log.write_line(&Self::row(event));
+    }
+}
+
+change_log_hook!(CsvAuditLog);
+
+/// An [`AuditHook`] forwarding every change to a syslog server, as an
+/// RFC 5424 message over UDP, e.g. to feed a SIEM.
+///
+/// Each message has the facility `log audit` (13) unless
+/// [`facility`](Self::facility) says otherwise and the severity `notice`, the
+/// app name `simply-dns`, the action as its message ID and the
+/// [`ChangeEvent`] as JSON for its text, as [`JsonLinesAuditLog`] writes it.
+/// Send errors are only logged, as with [`JsonLinesAuditLog`]. Not available
+/// on wasm32.
+///
+/// Example usage:
+/// ```rust,no_run
+/// use simply_dns::SimplyClient;
+/// use simply_dns::audit::SyslogAuditLog;
+///
+/// let client = SimplyClient::builder()
+///     .credentials("account", "api_key")
+///     .audit_hook(SyslogAuditLog::connect("siem.example.com:514")?.hostname("deploy-01"))
+///     .build()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub struct SyslogAuditLog {
+    log: LogWriter,
+    facility: u8,
+    hostname: String,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SyslogAuditLog {
+    /// The `notice` severity of the messages.
+    const SEVERITY: u8 = 5;
+
+    /// Send to the syslog server at `addr`, e.g. `"127.0.0.1:514"`.
+    pub fn connect(addr: impl std::net::ToSocketAddrs) -> io::Result<Self> {
+        use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to send to"))?;
+        let local: SocketAddr = if addr.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        Ok(Self {
+            log: LogWriter::new(Datagrams {
+                socket,
+                message: Vec::new(),
+            }),
+            facility: 13,
+            hostname: "-".to_string(),
+        })
+    }
+
+    /// Send with the syslog `facility`, 0 to 23, e.g. 16 for `local0`.
+    pub fn facility(mut self, facility: u8) -> Self {
+        self.facility = facility.min(23);
+        self
+    }
+
+    /// Name this machine `hostname` in the messages; they carry none otherwise.
+    pub fn hostname(mut self, hostname: impl Into<String>) -> Self {
+        // RFC 5424 hostnames are printable ASCII without spaces.
+        let hostname: String = hostname
+            .into()
+            .chars()
+            .filter(|c| c.is_ascii_graphic())
+            .take(255)
+            .collect();
+        self.hostname = if hostname.is_empty() {
+            "-".to_string()
+        } else {
+            hostname
+        };
+        self
+    }
+
+    /// Label the changes with `actor`, e.g. the user or job making them.
+    pub fn actor(mut self, actor: impl Into<String>) -> Self {
+        self.log.actor = Some(actor.into());
+        self
+    }
+
+    /// The RFC 5424 message of `event`.
+    fn message(&self, event: &ChangeEvent) -> Result<String, serde_json::Error> {
+        Ok(format!(
+            "<{}>1 {} {} simply-dns {} {} - {}",
+            u16::from(self.facility) * 8 + u16::from(Self::SEVERITY),
+            crate::time::rfc3339(event.time),
+            self.hostname,
+            std::process::id(),
+            event.action,
+            serde_json::to_string(event)?,
+        ))
+    }
+
+    fn write(&self, event: &ChangeEvent) {
+        match self.message(event) {
+            Ok(message) => self.log.write_line(&message),
+            Err(_e) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %_e, "failed to serialize audit event");
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+change_log_hook!(SyslogAuditLog);
+
+/// Sends what was written up to each flush as one UDP datagram, without the
+/// final line break.
+#[cfg(not(target_arch = "wasm32"))]
+struct Datagrams {
+    socket: std::net::UdpSocket,
+    message: Vec<u8>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Write for Datagrams {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.message.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let message = self.message.strip_suffix(b"\n").unwrap_or(&self.message);
+        let sent = match message {
+            [] => Ok(()),
+            message => self.socket.send(message).map(drop),
+        };
+        self.message.clear();
+        sent
+    }
+}
+
+/// An [`AuditHook`] exporting every change to an OpenTelemetry collector, as
+/// an OTLP log record sent over HTTP as JSON.
+///
+/// Each record has the severity `NOTICE`, the [`ChangeEvent`] as JSON for its
+/// body, as [`JsonLinesAuditLog`] writes it, and the action, account, domain
+/// and actor as `simply_dns.*` attributes. Changes are sent one by one from a
+/// background thread, so the hook returns right away; dropping the log waits
+/// for the changes still being sent. Send errors and error responses are only
+/// logged, as with [`JsonLinesAuditLog`]. Requests time out after 10 seconds
+/// unless the [`client`](Self::client) says otherwise. Not available on
+/// wasm32.
+///
+/// Example usage:
+/// ```rust,no_run
+/// use simply_dns::SimplyClient;
+/// use simply_dns::audit::OtlpAuditLog;
+///
+/// let log = OtlpAuditLog::new("http://otel-collector:4318")
+///     .header("authorization", "Bearer token")
+///     .actor("deploy-01");
+/// let client = SimplyClient::builder()
+///     .credentials("account", "api_key")
+///     .audit_hook(log)
+///     .build()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[cfg(all(feature = "otlp", not(target_arch = "wasm32")))]
+pub struct OtlpAuditLog {
+    log: OtlpExport,
+    url: String,
+    client: reqwest::Client,
+    headers: Vec<(String, String)>,
+    service_name: String,
+}
+
+#[cfg(all(feature = "otlp", not(target_arch = "wasm32")))]
+impl OtlpAuditLog {
+    /// The OTLP `SeverityNumber` of `NOTICE`, which OpenTelemetry maps to `INFO2`.
+    const SEVERITY: u8 = 10;
+
+    /// Export to the collector at `endpoint`, e.g. `"http://localhost:4318"`,
+    /// whose `/v1/logs` path the records are sent to.
+    pub fn new(endpoint: &str) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self {
+            log: OtlpExport::start(),
+            url: format!("{}/v1/logs", endpoint.trim_end_matches('/')),
+            client,
+            headers: Vec::new(),
+            service_name: "simply-dns".to_string(),
+        }
+    }
+
+    /// Send the requests with `client`, e.g. to set a timeout or a proxy.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Add the header `name: value` to every request, e.g. for a token.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Name the service the records come from `name`, `simply-dns` by default.
+    pub fn service_name(mut self, name: impl Into<String>) -> Self {
+        self.service_name = name.into();
+        self
+    }
+
+    /// Label the changes with `actor`, e.g. the user or job making them.
+    pub fn actor(mut self, actor: impl Into<String>) -> Self {
+        self.log.actor = Some(actor.into());
+        self
+    }
+
+    /// The OTLP `ExportLogsServiceRequest` of `event`, in its JSON encoding.
+    fn export_request(&self, event: &ChangeEvent) -> Result<serde_json::Value, serde_json::Error> {
+        use serde_json::json;
+
+        let attribute =
+            |key: &str, value: &str| json!({"key": key, "value": {"stringValue": value}});
+        let mut attributes = vec![
+            attribute("simply_dns.action", &event.action.to_string()),
+            attribute("simply_dns.account", &event.account),
+            attribute("simply_dns.domain", &event.domain),
+        ];
+        if let Some(actor) = &event.actor {
+            attributes.push(attribute("simply_dns.actor", actor));
+        }
+        let time = (u128::from(event.time) * 1_000_000_000).to_string();
+        let observed = crate::time::unix_time().as_nanos().to_string();
+        Ok(json!({
+            "resourceLogs": [{
+                "resource": {"attributes": [attribute("service.name", &self.service_name)]},
+                "scopeLogs": [{
+                    "scope": {"name": "simply-dns", "version": env!("CARGO_PKG_VERSION")},
+                    "logRecords": [{
+                        "timeUnixNano": time,
+                        "observedTimeUnixNano": observed,
+                        "severityNumber": Self::SEVERITY,
+                        "severityText": "NOTICE",
+                        "body": {"stringValue": serde_json::to_string(event)?},
+                        "attributes": attributes,
+                    }],
+                }],
+            }],
+        }))
+    }
+
+    fn write(&self, event: &ChangeEvent) {
+        match self.export_request(event) {
+            Ok(body) => {
+                let mut request = self.client.post(&self.url).json(&body);
+                for (name, value) in &self.headers {
+                    request = request.header(name, value);
+                }
+                self.log.send(request);
+            }
+            Err(_e) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(error = %_e, "failed to serialize audit event");
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "otlp", not(target_arch = "wasm32")))]
+change_log_hook!(OtlpAuditLog);
+
+/// The thread sending the requests of an [`OtlpAuditLog`], which runs until
+/// the log is dropped.
+#[cfg(all(feature = "otlp", not(target_arch = "wasm32")))]
+struct OtlpExport {
+    requests: Option<std::sync::mpsc::Sender<reqwest::RequestBuilder>>,
+    worker: Option<std::thread::JoinHandle<()>>,
+    actor: Option<String>,
+}
+
+#[cfg(all(feature = "otlp", not(target_arch = "wasm32")))]
+impl OtlpExport {
+    fn start() -> Self {
+        let (requests, received) = std::sync::mpsc::channel::<reqwest::RequestBuilder>();
+        let worker = std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(runtime) => runtime,
+                Err(_e) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(error = %_e, "failed to start the OTLP audit log");
+                    return;
+                }
+            };
+            for request in received {
+                let sent = runtime.block_on(async { request.send().await?.error_for_status() });
+                if let Err(_e) = sent {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(error = %_e, "failed to export audit event");
+                }
+            }
+        });
+        Self {
+            requests: Some(requests),
+            worker: Some(worker),
+            actor: None,
+        }
+    }
+
+    fn send(&self, request: reqwest::RequestBuilder) {
+        // Only fails if the thread couldn't start its runtime, which it logged.
+        if let Some(requests) = &self.requests {
+            let _ = requests.send(request);
+        }
+    }
+}
+
+#[cfg(all(feature = "otlp", not(target_arch = "wasm32")))]
+impl Drop for OtlpExport {
+    fn drop(&mut self) {
+        // Closing the channel ends the thread once it sent what it has.
+        self.requests = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// `field` quoted for CSV (RFC 4180) if it needs to be.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+impl SimplyClient {
+    /// Re-apply the changes of a change log to `domain`, e.g. to clone the
+    /// history of one zone into another.
+    ///
+    /// Events are applied in order, whatever domain they were logged for.
+    /// Creates create the `after` record. Updates and deletes act on the
+    /// record an earlier create of the log made, or else on a record of
+    /// `domain` matching the event's `before` name, type and data; events
+    /// without such a record fail. Names are taken relative to the domain of
+    /// the event. Fails only if the records of `domain` can't be listed;
+    /// failed events are collected in the report.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to apply the changes to.
+    /// * `events` - The changes, e.g. from [`read_json_lines`].
+    ///
+    /// Example usage:
+    /// ```rust,no_run
+    /// use std::fs::File;
+    /// use std::io::BufReader;
+    /// use simply_dns::SimplyClient;
+    /// use simply_dns::audit::read_json_lines;
+    ///
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = SimplyClient::new("account", "api_key");
+    /// let events = read_json_lines(BufReader::new(File::open("audit.jsonl")?))?;
+    /// let events: Vec<_> = events.into_iter().filter(|e| e.domain == "example.com").collect();
+    /// let report = client.replay_changes("example.net", &events).await?;
+    /// println!("{report}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn replay_changes(
+        &self,
+        domain: &str,
+        events: &[ChangeEvent],
+    ) -> Result<OperationReport<ChangeEvent>, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let started = Instant::now();
+        let mut records = this.list_dns_records(domain).await?;
+        let mut report = OperationReport::default();
+        let mut progress = this.progress(Operation::Replay, Some(events.len()));
+        // The record of `domain` standing in for each record ID of the log.
+        let mut ids: HashMap<u32, DnsRecordId> = HashMap::new();
+        for event in events {
+            progress.begin();
+            let event_started = Instant::now();
+            let result = this
+                .replay_change(domain, event, &mut records, &mut ids)
+                .await;
+            progress.record(&mut report, event.clone(), result, event_started);
+        }
+        report.elapsed = started.elapsed();
+        progress.finish(&report);
+        Ok(report)
+    }
+
+    async fn replay_change(
+        &self,
+        domain: &str,
+        event: &ChangeEvent,
+        records: &mut Vec<DnsRecord>,
+        ids: &mut HashMap<u32, DnsRecordId>,
+    ) -> Result<(), SimplyClientError> {
+        let invalid = |message: &str| SimplyClientError::Validation {
+            status: None,
+            field: None,
+            message: format!("can't replay {event}: {message}"),
+        };
+        let relative = |name: &str| names::to_relative(name, &event.domain);
+        if event.action == ChangeAction::Create {
+            let after = event.after.as_ref().ok_or_else(|| invalid("no record"))?;
+            let req = CreateDnsRecordRequest {
+                record_type: after.record_type.clone(),
+                name: relative(&after.name),
+                data: after.data.clone(),
+                priority: after.priority,
+                ttl: after.ttl,
+                comment: after.comment.clone(),
+            };
+            let created = self.create_dns_record(domain, req).await?;
+            ids.extend(event.record_ids.iter().copied().zip(created));
+            return Ok(());
+        }
+
+        let mapped = event.record_ids.first().and_then(|id| ids.get(id)).copied();
+        let target = match (mapped, &event.before) {
+            (Some(id), _) => id,
+            (None, Some(before)) => {
+                let name = relative(&before.name);
+                records
+                    .iter()
+                    .find(|r| {
+                        names::to_relative(&r.name, domain).eq_ignore_ascii_case(&name)
+                            && r.record_type.eq_ignore_ascii_case(&before.record_type)
+                            && r.data == before.data
+                    })
+                    .map(|r| r.record_id)
+                    .ok_or_else(|| invalid("no matching record"))?
+            }
+            (None, None) => return Err(invalid("the log has no previous state")),
+        };
+        match (event.action, &event.after) {
+            (ChangeAction::Update, Some(after)) => {
+                let req = UpdateDnsRecordRequest {
+                    record_type: after.record_type.clone(),
+                    name: relative(&after.name),
+                    data: after.data.clone(),
+                    priority: after.priority,
+                    ttl: after.ttl,
+                    comment: after.comment.clone(),
+                };
+                self.update_dns_record(domain, target, req.clone()).await?;
+                if let Some(record) = records.iter_mut().find(|r| r.record_id == target) {
+                    record.name = req.name;
+                    record.record_type = req.record_type;
+                    record.data = req.data;
+                }
+            }
+            (ChangeAction::Update, None) => return Err(invalid("no record")),
+            _ => {
+                self.delete_dns_record(domain, target).await?;
+                records.retain(|r| r.record_id != target);
+            }
+        }
+        if let Some(&id) = event.record_ids.first() {
+            ids.insert(id, target);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syslog_messages_follow_rfc_5424() {
+        let server = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        server
+            .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+        let log = SyslogAuditLog::connect(server.local_addr().unwrap())
+            .unwrap()
+            .hostname("deploy 01")
+            .actor("ci");
+        let ctx = AuditContext {
+            account: "S123456",
+            domain: "example.com",
+            freeze_override: None,
+        };
+        let before = DnsRecord {
+            record_id: DnsRecordId::new(7),
+            name: "old".to_string(),
+            ttl: 3600,
+            data: "192.0.2.1".to_string(),
+            record_type: "A".to_string(),
+            priority: None,
+            comment: None,
+        };
+        log.on_delete(&ctx, &DnsRecordId::new(7), Some(&before));
+
+        let mut buf = [0; 2048];
+        let len = server.recv(&mut buf).unwrap();
+        let message = std::str::from_utf8(&buf[..len]).unwrap();
+        let fields: Vec<&str> = message.splitn(8, ' ').collect();
+        assert_eq!(fields[0], "<109>1", "{message}");
+        assert!(
+            fields[1].len() == 20 && fields[1].ends_with('Z'),
+            "{message}"
+        );
+        assert_eq!(&fields[2..4], ["deploy01", "simply-dns"]);
+        assert_eq!(&fields[5..7], ["delete", "-"]);
+        let event: ChangeEvent = serde_json::from_str(fields[7]).unwrap();
+        assert_eq!(event.actor.as_deref(), Some("ci"));
+        assert_eq!(event.before, Some(RecordSnapshot::from(&before)));
+
+        assert_eq!(crate::time::rfc3339(1_792_168_200), "2026-10-16T16:30:00Z");
+    }
+
+    #[cfg(all(feature = "otlp", feature = "test-fixtures"))]
+    #[test]
+    fn otlp_log_records_are_posted_to_the_collector() {
+        use crate::http_server::{self, HttpResponse};
+
+        let (requests, received) = std::sync::mpsc::channel();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let listener = runtime
+            .block_on(tokio::net::TcpListener::bind("127.0.0.1:0"))
+            .unwrap();
+        let endpoint = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Mutex::new(requests);
+        std::thread::spawn(move || {
+            runtime.block_on(http_server::serve(listener, move |req| {
+                let _ = requests.lock().unwrap().send(req);
+                async { HttpResponse::new(200, "application/json", "{}".to_string()) }
+            }))
+        });
+
+        let log = OtlpAuditLog::new(&endpoint)
+            .header("authorization", "Bearer token")
+            .service_name("dns-deploy")
+            .actor("ci");
+        let ctx = AuditContext {
+            account: "S123456",
+            domain: "example.com",
+            freeze_override: None,
+        };
+        let after = CreateDnsRecordRequest {
+            record_type: "A".to_string(),
+            name: "www".to_string(),
+            data: "192.0.2.1".to_string(),
+            priority: None,
+            ttl: Some(3600),
+            comment: None,
+        };
+        log.on_create(&ctx, &[DnsRecordId::new(7)], &after);
+        // Waits for the request to be sent.
+        drop(log);
+
+        let req = received
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(
+            (req.method.as_str(), req.path.as_str()),
+            ("POST", "/v1/logs")
+        );
+        assert_eq!(req.header("authorization"), Some("Bearer token"));
+        assert_eq!(req.header("content-type"), Some("application/json"));
+        let body: serde_json::Value = serde_json::from_slice(&req.body).unwrap();
+        let resource = &body["resourceLogs"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0],
+            serde_json::json!({"key": "service.name", "value": {"stringValue": "dns-deploy"}})
+        );
+        let record = &resource["scopeLogs"][0]["logRecords"][0];
+        assert_eq!(record["severityNumber"], 10);
+        assert_eq!(record["severityText"], "NOTICE");
+        let time: u128 = record["timeUnixNano"].as_str().unwrap().parse().unwrap();
+        assert_eq!(time % 1_000_000_000, 0);
+        let attributes: Vec<(&str, &str)> = (record["attributes"].as_array().unwrap().iter())
+            .map(|a| {
+                let value = a["value"]["stringValue"].as_str().unwrap();
+                (a["key"].as_str().unwrap(), value)
+            })
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                ("simply_dns.action", "create"),
+                ("simply_dns.account", "S123456"),
+                ("simply_dns.domain", "example.com"),
+                ("simply_dns.actor", "ci"),
+            ]
+        );
+        let body = record["body"]["stringValue"].as_str().unwrap();
+        let event: ChangeEvent = serde_json::from_str(body).unwrap();
+        assert_eq!(event.record_ids, [7]);
+        assert_eq!(event.after, Some(RecordSnapshot::from(&after)));
+    }
+}
@@ -0,0 +1,249 @@
+//! Authentication of API requests.
+//!
+//! The Simply.com API uses HTTP basic authentication with the account and API
+//! key, implemented by [`BasicAuth`]. Bearer tokens ([`BearerAuth`]) and keys
+//! sent in a header of their own ([`HeaderAuth`]) are built in too, should
+//! the API offer them. Other schemes, such as signed requests, can be plugged
+//! in by implementing [`AuthScheme`]. Any of them is passed to
+//! [`SimplyClientBuilder::auth_scheme`](crate::SimplyClientBuilder::auth_scheme),
+//! and every request is authenticated with it in one place, so endpoints
+//! don't depend on the scheme. Servers speaking the API, like the mock
+//! server, check incoming requests with the same trait.
+//!
+//! Example usage:
+//! ```rust
+//! use reqwest::Request;
+//! use simply_dns::SimplyClient;
+//! use simply_dns::auth::{AuthScheme, BearerAuth};
+//!
+//! let client = SimplyClient::builder()
+//!     .auth_scheme(BearerAuth::new("token").account("S123456"))
+//!     .build()?;
+//!
+//! /// Adds the key to the query string.
+//! #[derive(Debug)]
+//! struct QueryKey(String);
+//!
+//! impl AuthScheme for QueryKey {
+//!     fn authenticate(&self, request: &mut Request) -> Result<(), simply_dns::SimplyClientError> {
+//!         request.url_mut().query_pairs_mut().append_pair("key", &self.0);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let client = SimplyClient::builder()
+//!     .auth_scheme(QueryKey("key".to_string()))
+//!     .build()?;
+//! # Ok::<(), simply_dns::SimplyClientError>(())
+//! ```
+
+use std::fmt;
+
+use reqwest::Request;
+use reqwest::header::{AUTHORIZATION, HeaderName, HeaderValue};
+
+use crate::SimplyClientError;
+use crate::credentials::{Credentials, SecretString};
+
+/// A way of authenticating requests to the API.
+pub trait AuthScheme: fmt::Debug + Send + Sync {
+    /// Add authentication to an outgoing `request`, e.g. an `Authorization`
+    /// header or a signature over its URL and body.
+    ///
+    /// Called once per request, before it is first sent; retries reuse it.
+    fn authenticate(&self, request: &mut Request) -> Result<(), SimplyClientError>;
+
+    /// Whether an incoming request with the `authorization` header value is
+    /// authenticated, for servers speaking the API. Rejects everything by default.
+    fn verify(&self, authorization: Option<&str>) -> bool {
+        let _ = authorization;
+        false
+    }
+
+    /// The account requests are made for, if the scheme knows it. Reported to
+    /// [audit hooks](crate::audit).
+    fn account(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// HTTP basic authentication with the account and API key, the scheme of the
+/// Simply.com API.
+pub struct BasicAuth {
+    credentials: Credentials,
+    header: SecretString,
+}
+
+impl BasicAuth {
+    /// Authenticate as `credentials`.
+    pub fn new(credentials: Credentials) -> Self {
+        let header = SecretString::new(format!(
+            "Basic {}",
+            base64(format!("{}:{}", credentials.account(), credentials.api_key()).as_bytes())
+        ));
+        Self {
+            credentials,
+            header,
+        }
+    }
+}
+
+impl fmt::Debug for BasicAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BasicAuth").field(&self.credentials).finish()
+    }
+}
+
+impl AuthScheme for BasicAuth {
+    fn authenticate(&self, request: &mut Request) -> Result<(), SimplyClientError> {
+        let value = sensitive_value(self.header.expose_secret())?;
+        request.headers_mut().insert(AUTHORIZATION, value);
+        Ok(())
+    }
+
+    fn verify(&self, authorization: Option<&str>) -> bool {
+        authorization == Some(self.header.expose_secret())
+    }
+
+    fn account(&self) -> Option<&str> {
+        Some(self.credentials.account())
+    }
+}
+
+/// Bearer token authentication: an `Authorization: Bearer <token>` header.
+pub struct BearerAuth {
+    token: SecretString,
+    account: Option<String>,
+}
+
+impl BearerAuth {
+    /// Authenticate with `token`.
+    pub fn new(token: impl Into<SecretString>) -> Self {
+        Self {
+            token: token.into(),
+            account: None,
+        }
+    }
+
+    /// Report `account` to audit hooks as the account requests are made for.
+    pub fn account(mut self, account: impl Into<String>) -> Self {
+        self.account = Some(account.into());
+        self
+    }
+}
+
+impl fmt::Debug for BearerAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BearerAuth")
+            .field("token", &self.token)
+            .field("account", &self.account)
+            .finish()
+    }
+}
+
+impl AuthScheme for BearerAuth {
+    fn authenticate(&self, request: &mut Request) -> Result<(), SimplyClientError> {
+        let value = format!("Bearer {}", self.token.expose_secret());
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, sensitive_value(&value)?);
+        Ok(())
+    }
+
+    fn verify(&self, authorization: Option<&str>) -> bool {
+        authorization
+            .and_then(|a| a.strip_prefix("Bearer "))
+            .is_some_and(|token| token == self.token.expose_secret())
+    }
+
+    fn account(&self) -> Option<&str> {
+        self.account.as_deref()
+    }
+}
+
+/// An API key sent as is in a header of its own, e.g. `X-Api-Key`.
+pub struct HeaderAuth {
+    header: HeaderName,
+    key: SecretString,
+    account: Option<String>,
+}
+
+impl HeaderAuth {
+    /// Send `key` in the header `header`.
+    ///
+    /// Returns [`SimplyClientError::Config`] if `header` isn't a valid header name.
+    pub fn new(header: &str, key: impl Into<SecretString>) -> Result<Self, SimplyClientError> {
+        let header = HeaderName::try_from(header)
+            .map_err(|_| SimplyClientError::Config(format!("invalid header name {header:?}")))?;
+        Ok(Self {
+            header,
+            key: key.into(),
+            account: None,
+        })
+    }
+
+    /// Report `account` to audit hooks as the account requests are made for.
+    pub fn account(mut self, account: impl Into<String>) -> Self {
+        self.account = Some(account.into());
+        self
+    }
+}
+
+impl fmt::Debug for HeaderAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeaderAuth")
+            .field("header", &self.header)
+            .field("key", &self.key)
+            .field("account", &self.account)
+            .finish()
+    }
+}
+
+impl AuthScheme for HeaderAuth {
+    fn authenticate(&self, request: &mut Request) -> Result<(), SimplyClientError> {
+        let value = sensitive_value(self.key.expose_secret())?;
+        request.headers_mut().insert(self.header.clone(), value);
+        Ok(())
+    }
+
+    /// Only keys sent in the `Authorization` header can be checked, as it is
+    /// the only header servers pass to [`AuthScheme::verify`].
+    fn verify(&self, authorization: Option<&str>) -> bool {
+        self.header == AUTHORIZATION && authorization == Some(self.key.expose_secret())
+    }
+
+    fn account(&self) -> Option<&str> {
+        self.account.as_deref()
+    }
+}
+
+/// `value` as a header value left out of debug output.
+fn sensitive_value(value: &str) -> Result<HeaderValue, SimplyClientError> {
+    let mut value = HeaderValue::try_from(value).map_err(|_| {
+        SimplyClientError::Config("credentials contain invalid characters".to_string())
+    })?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+/// Standard base64 with padding, as used by basic auth headers.
+fn base64(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
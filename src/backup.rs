@@ -0,0 +1,704 @@
+//! Zone backups, as a safety net before bulk changes.
+//!
+//! [`SimplyClient::backup_zone`] captures every record of a zone in a
+//! [`ZoneBackup`], which serializes to JSON and carries a checksum so a
+//! damaged or hand-edited file is noticed. [`SimplyClient::restore_zone`]
+//! brings the zone back to the backup, either adding back what is missing
+//! ([`RestoreMode::Merge`]) or also removing what was added since
+//! ([`RestoreMode::Replace`]).
+//!
+//! Backups taken regularly can be kept incrementally: [`ZoneBackup::delta_to`]
+//! records only what changed since the previous backup in a [`BackupDelta`],
+//! and a [`BackupChain`] of a full backup and the deltas after it gives back
+//! the zone as of any of them. Take a new full backup every few runs, so a
+//! chain doesn't grow without bound and a damaged delta only affects its own.
+//! A [`BackupArchive`] does so in a [`StateStore`], such as a directory, and
+//! finds the backup of a point in time again. With the `s3` feature,
+//! `s3::S3StateStore` archives to an S3-compatible bucket instead.
+//!
+//! With the `encryption` feature, `ZoneBackup::to_encrypted` and
+//! `BackupDelta::to_encrypted` encrypt backups with an `EncryptionKey`, as zone
+//! data and comments can describe infrastructure in detail.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::backup::{RestoreMode, ZoneBackup};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let backup = client.backup_zone("example.com").await?;
+//! std::fs::write("example.com.json", serde_json::to_vec_pretty(&backup)?)?;
+//!
+//! // ... run the bulk changes; if something goes wrong:
+//!
+//! let backup: ZoneBackup = serde_json::from_slice(&std::fs::read("example.com.json")?)?;
+//! let report = client
+//!     .restore_zone("example.com", &backup, RestoreMode::Replace)
+//!     .await?;
+//! println!("{report}");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::api::{DnsRecord, DnsRecordId};
+use crate::drift::{Fnv, record_hash};
+#[cfg(feature = "encryption")]
+use crate::encryption::EncryptionKey;
+use crate::report::OperationReport;
+use crate::state::{StateError, StateStore};
+use crate::sync::{Change, DesiredRecord, DesiredZone, ZonePlan};
+use crate::{SimplyClient, SimplyClientError};
+
+/// Error type for [`SimplyClient::restore_zone`].
+#[derive(Debug, Error)]
+pub enum BackupError {
+    /// The Simply.com API call failed.
+    #[error(transparent)]
+    Client(#[from] SimplyClientError),
+    /// The records don't match the checksum of the backup.
+    #[error("backup checksum mismatch: expected {expected}, records hash to {actual}")]
+    Checksum {
+        /// The checksum stored in the backup.
+        expected: String,
+        /// The checksum of the records in the backup.
+        actual: String,
+    },
+    /// The backup was taken of another domain.
+    #[error("backup is of {backup}, not {domain}")]
+    Domain {
+        /// The domain the backup was taken of.
+        backup: String,
+        /// The domain that was to be restored.
+        domain: String,
+    },
+    /// An incremental backup doesn't apply to the backup before it in its
+    /// chain, e.g. because a delta is missing.
+    #[error("incremental backup from {created_at} doesn't follow the backup before it")]
+    Chain {
+        /// When the incremental backup was taken.
+        created_at: u64,
+    },
+    /// A [`BackupArchive`] couldn't read or write its store.
+    #[error("backup archive: {0}")]
+    Store(#[source] StateError),
+    /// An encrypted backup could not be written or read, e.g. because of a wrong key.
+    #[cfg(feature = "encryption")]
+    #[error(transparent)]
+    Encryption(StateError),
+}
+
+/// Every record of a zone at one point in time, from [`SimplyClient::backup_zone`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneBackup {
+    /// The domain the backup was taken of.
+    pub domain: String,
+    /// When the backup was taken, in seconds since the Unix epoch.
+    pub created_at: u64,
+    /// The records, including their IDs, with names as returned by the API.
+    pub records: Vec<DnsRecord>,
+    /// The [`checksum`](Self::checksum) of `records` when the backup was taken.
+    pub checksum: String,
+}
+
+impl ZoneBackup {
+    /// A backup of `domain` holding `records`, taken now.
+    pub fn new(domain: impl Into<String>, records: Vec<DnsRecord>) -> Self {
+        let domain = domain.into();
+        let created_at = crate::time::unix_time().as_secs();
+        let checksum = Self::checksum(&domain, &records);
+        Self {
+            domain,
+            created_at,
+            records,
+            checksum,
+        }
+    }
+
+    /// A checksum over the IDs and contents of `records`, in order.
+    pub fn checksum(domain: &str, records: &[DnsRecord]) -> String {
+        let mut hash = Fnv::new();
+        for record in records {
+            hash.write(&record.record_id.get().to_be_bytes());
+            hash.write(record_hash(record, domain).as_bytes());
+        }
+        hash.finish()
+    }
+
+    /// Check that the records still match the stored checksum.
+    pub fn verify(&self) -> Result<(), BackupError> {
+        let actual = Self::checksum(&self.domain, &self.records);
+        if actual != self.checksum {
+            return Err(BackupError::Checksum {
+                expected: self.checksum.clone(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// The changes from this backup to the `newer` one, as an incremental
+    /// backup to keep instead of `newer`.
+    ///
+    /// Example usage:
+    /// ```rust
+    /// use simply_dns::backup::{BackupChain, ZoneBackup};
+    ///
+    /// # fn records(json: serde_json::Value) -> Vec<simply_dns::api::DnsRecord> {
+    /// #     serde_json::from_value(json).unwrap()
+    /// # }
+    /// let monday = ZoneBackup::new("example.com", records(serde_json::json!([
+    ///     { "record_id": { "id": 1 }, "name": "www", "type": "A", "data": "192.0.2.1",
+    ///       "ttl": 3600, "priority": null, "comment": null },
+    /// ])));
+    /// let tuesday = ZoneBackup::new("example.com", records(serde_json::json!([
+    ///     { "record_id": { "id": 1 }, "name": "www", "type": "A", "data": "192.0.2.2",
+    ///       "ttl": 3600, "priority": null, "comment": null },
+    /// ])));
+    /// let delta = monday.delta_to(&tuesday);
+    /// assert_eq!((delta.changed.len(), delta.removed.len()), (1, 0));
+    ///
+    /// let mut chain = BackupChain::new(monday);
+    /// chain.push(delta)?;
+    /// assert_eq!(chain.latest()?.records[0].data, "192.0.2.2");
+    /// # Ok::<(), simply_dns::backup::BackupError>(())
+    /// ```
+    pub fn delta_to(&self, newer: &ZoneBackup) -> BackupDelta {
+        let hash = |r: &DnsRecord| (r.record_id, record_hash(r, &self.domain));
+        let old: HashSet<_> = self.records.iter().map(hash).collect();
+        let new_ids: HashSet<_> = newer.records.iter().map(|r| r.record_id).collect();
+        let mut changed: Vec<DnsRecord> = (newer.records.iter())
+            .filter(|r| !old.contains(&hash(r)))
+            .cloned()
+            .collect();
+        changed.sort_by_key(|r| r.record_id.get());
+        let mut removed: Vec<DnsRecordId> = (self.records.iter())
+            .map(|r| r.record_id)
+            .filter(|id| !new_ids.contains(id))
+            .collect();
+        removed.sort_by_key(|id| id.get());
+        BackupDelta {
+            domain: self.domain.clone(),
+            created_at: newer.created_at,
+            base: state_checksum(&self.domain, &self.records),
+            checksum: state_checksum(&newer.domain, &newer.records),
+            changed,
+            removed,
+        }
+    }
+
+    /// The backup `delta` was taken as, from this one it was based on.
+    ///
+    /// Records are in the order of their IDs. Fails if `delta` is of another
+    /// domain or based on another backup, or if either doesn't match its
+    /// checksum.
+    pub fn apply_delta(&self, delta: &BackupDelta) -> Result<ZoneBackup, BackupError> {
+        if !same_domain(&self.domain, &delta.domain) {
+            return Err(BackupError::Domain {
+                backup: delta.domain.clone(),
+                domain: self.domain.clone(),
+            });
+        }
+        self.verify()?;
+        if state_checksum(&self.domain, &self.records) != delta.base {
+            return Err(BackupError::Chain {
+                created_at: delta.created_at,
+            });
+        }
+        let replaced: HashSet<_> = (delta.changed.iter().map(|r| r.record_id))
+            .chain(delta.removed.iter().copied())
+            .collect();
+        let mut records: Vec<DnsRecord> = (self.records.iter())
+            .filter(|r| !replaced.contains(&r.record_id))
+            .chain(&delta.changed)
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| r.record_id.get());
+        let actual = state_checksum(&self.domain, &records);
+        if actual != delta.checksum {
+            return Err(BackupError::Checksum {
+                expected: delta.checksum.clone(),
+                actual,
+            });
+        }
+        Ok(ZoneBackup {
+            domain: self.domain.clone(),
+            created_at: delta.created_at,
+            checksum: Self::checksum(&self.domain, &records),
+            records,
+        })
+    }
+
+    fn desired(&self) -> DesiredZone {
+        self.records
+            .iter()
+            .map(|r| DesiredRecord {
+                name: r.name.clone(),
+                record_type: r.record_type.clone(),
+                data: r.data.clone(),
+                ttl: Some(r.ttl),
+                priority: r.priority,
+                comment: r.comment.clone(),
+            })
+            .collect()
+    }
+}
+
+/// The [checksum](ZoneBackup::checksum) of `records` in the order of their IDs,
+/// so it doesn't depend on the order the API listed them in.
+fn state_checksum(domain: &str, records: &[DnsRecord]) -> String {
+    let mut records: Vec<&DnsRecord> = records.iter().collect();
+    records.sort_by_key(|r| r.record_id.get());
+    let mut hash = Fnv::new();
+    for record in records {
+        hash.write(&record.record_id.get().to_be_bytes());
+        hash.write(record_hash(record, domain).as_bytes());
+    }
+    hash.finish()
+}
+
+fn same_domain(a: &str, b: &str) -> bool {
+    a.trim_end_matches('.')
+        .eq_ignore_ascii_case(b.trim_end_matches('.'))
+}
+
+/// What changed in a zone between two backups, from [`ZoneBackup::delta_to`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupDelta {
+    /// The domain the backups were taken of.
+    pub domain: String,
+    /// When the newer backup was taken, in seconds since the Unix epoch.
+    pub created_at: u64,
+    /// The checksum of the records of the older backup, in the order of their IDs.
+    pub base: String,
+    /// The checksum of the records of the newer backup, in the order of their IDs.
+    pub checksum: String,
+    /// The records that were created or changed, as in the newer backup.
+    pub changed: Vec<DnsRecord>,
+    /// The IDs of the records that were deleted.
+    pub removed: Vec<DnsRecordId>,
+}
+
+/// A full backup and the incremental backups taken after it, oldest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupChain {
+    /// The full backup the chain starts with.
+    pub full: ZoneBackup,
+    /// The incremental backups, each based on the one before it.
+    pub deltas: Vec<BackupDelta>,
+}
+
+impl BackupChain {
+    /// A chain of only `full`.
+    pub fn new(full: ZoneBackup) -> Self {
+        Self {
+            full,
+            deltas: Vec::new(),
+        }
+    }
+
+    /// Add `delta` at the end, checking that it is based on the latest backup.
+    pub fn push(&mut self, delta: BackupDelta) -> Result<(), BackupError> {
+        self.latest()?.apply_delta(&delta)?;
+        self.deltas.push(delta);
+        Ok(())
+    }
+
+    /// The zone as of the latest backup of the chain.
+    pub fn latest(&self) -> Result<ZoneBackup, BackupError> {
+        Ok(self.at(u64::MAX)?.unwrap_or_else(|| self.full.clone()))
+    }
+
+    /// The zone as of the latest backup taken at or before `time`, in seconds
+    /// since the Unix epoch, or `None` if the chain starts after `time`.
+    pub fn at(&self, time: u64) -> Result<Option<ZoneBackup>, BackupError> {
+        if self.full.created_at > time {
+            return Ok(None);
+        }
+        let mut backup = self.full.clone();
+        for delta in self.deltas.iter().take_while(|d| d.created_at <= time) {
+            backup = backup.apply_delta(delta)?;
+        }
+        Ok(Some(backup))
+    }
+}
+
+/// What encrypted backups are bound to, so other encrypted data doesn't read as one.
+#[cfg(feature = "encryption")]
+const ENCRYPTION_CONTEXT: &str = "zone backup";
+
+#[cfg(feature = "encryption")]
+impl ZoneBackup {
+    /// The backup as JSON encrypted with `key`, for keeping it where others
+    /// may read it.
+    ///
+    /// Example usage:
+    /// ```rust
+    /// use simply_dns::backup::ZoneBackup;
+    /// use simply_dns::encryption::EncryptionKey;
+    ///
+    /// let key = EncryptionKey::from_bytes(&[7; 32]);
+    /// let backup = ZoneBackup::new("example.com", Vec::new());
+    /// let encrypted = backup.to_encrypted(&key)?;
+    /// assert_eq!(ZoneBackup::from_encrypted(&encrypted, &key)?.checksum, backup.checksum);
+    /// # Ok::<(), simply_dns::backup::BackupError>(())
+    /// ```
+    pub fn to_encrypted(&self, key: &EncryptionKey) -> Result<Vec<u8>, BackupError> {
+        let json = serde_json::to_vec(self).map_err(|e| BackupError::Encryption(e.into()))?;
+        key.encrypt(ENCRYPTION_CONTEXT, &json)
+            .map_err(BackupError::Encryption)
+    }
+
+    /// Read a backup written by [`to_encrypted`](Self::to_encrypted).
+    ///
+    /// The checksum is checked when the backup is restored, or with
+    /// [`verify`](Self::verify).
+    pub fn from_encrypted(data: &[u8], key: &EncryptionKey) -> Result<Self, BackupError> {
+        let json = key
+            .decrypt(ENCRYPTION_CONTEXT, data)
+            .map_err(BackupError::Encryption)?;
+        serde_json::from_slice(&json).map_err(|e| BackupError::Encryption(e.into()))
+    }
+}
+
+/// Backups of zones kept incrementally in a [`StateStore`], see the
+/// [module docs](self).
+///
+/// Backups are stored under `backups/<domain>/`, named by when they were
+/// taken and a run number that orders backups taken within a second. Wrap the store in an `EncryptedStateStore` (with the `encryption`
+/// feature) to encrypt them.
+///
+/// Example usage:
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use simply_dns::SimplyClient;
+/// use simply_dns::backup::{BackupArchive, RestoreMode};
+/// use simply_dns::state::FileStateStore;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = SimplyClient::new("account", "api_key");
+/// let archive = BackupArchive::new(&client, Arc::new(FileStateStore::new("/var/backups/dns")?))
+///     .full_every(7);
+/// archive.backup("example.com").await?;
+///
+/// // The zone as it was on 2026-10-01:
+/// if let Some(backup) = archive.at("example.com", 1_790_812_800)? {
+///     client.restore_zone("example.com", &backup, RestoreMode::Replace).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct BackupArchive<'a> {
+    client: &'a SimplyClient,
+    store: Arc<dyn StateStore>,
+    full_every: usize,
+}
+
+impl<'a> BackupArchive<'a> {
+    /// An archive backing up through `client` into `store`, taking a full
+    /// backup every 7 backups of a zone.
+    pub fn new(client: &'a SimplyClient, store: Arc<dyn StateStore>) -> Self {
+        Self {
+            client,
+            store,
+            full_every: 7,
+        }
+    }
+
+    /// Take a full backup every `runs` backups of a zone, and incremental ones
+    /// in between; 1 keeps only full backups.
+    pub fn full_every(mut self, runs: usize) -> Self {
+        self.full_every = runs.max(1);
+        self
+    }
+
+    fn prefix(domain: &str) -> String {
+        format!(
+            "backups/{}/",
+            domain.trim_end_matches('.').to_ascii_lowercase()
+        )
+    }
+
+    /// Back up `domain`, incrementally unless a full backup is due, and
+    /// return the key it was stored under.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to back up.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn backup(&self, domain: &str) -> Result<String, BackupError> {
+        let backup = self.client.backup_zone(domain).await?;
+        let run = self
+            .stored(domain, u64::MAX)?
+            .last()
+            .map_or(0, |b| b.run + 1);
+        let key = |kind: &str| {
+            let prefix = Self::prefix(domain);
+            format!("{prefix}{:020}-{run:010}.{kind}", backup.created_at)
+        };
+        let (key, data) = match self.chain(domain, u64::MAX)? {
+            Some(chain) if chain.deltas.len() + 1 < self.full_every => {
+                let delta = chain.latest()?.delta_to(&backup);
+                (key("delta"), serde_json::to_vec(&delta))
+            }
+            _ => (key("full"), serde_json::to_vec(&backup)),
+        };
+        let data = data.map_err(|e| BackupError::Store(e.into()))?;
+        self.store.put(&key, &data).map_err(BackupError::Store)?;
+        Ok(key)
+    }
+
+    /// The latest backup of `domain`, if there is one.
+    pub fn latest(&self, domain: &str) -> Result<Option<ZoneBackup>, BackupError> {
+        self.at(domain, u64::MAX)
+    }
+
+    /// The latest backup of `domain` taken at or before `time`, in seconds
+    /// since the Unix epoch, if there is one.
+    pub fn at(&self, domain: &str, time: u64) -> Result<Option<ZoneBackup>, BackupError> {
+        match self.chain(domain, time)? {
+            Some(chain) => chain.at(time),
+            None => Ok(None),
+        }
+    }
+
+    /// The chain of the latest full backup of `domain` taken at or before
+    /// `time`, with the incremental backups up to `time`.
+    pub fn chain(&self, domain: &str, time: u64) -> Result<Option<BackupChain>, BackupError> {
+        let stored = self.stored(domain, time)?;
+        let Some(start) = stored.iter().rposition(|b| b.full) else {
+            return Ok(None);
+        };
+        let mut chain = BackupChain::new(self.read(&stored[start].key)?);
+        for delta in &stored[start + 1..] {
+            chain.deltas.push(self.read(&delta.key)?);
+        }
+        Ok(Some(chain))
+    }
+
+    /// The backups of `domain` taken at or before `time`, oldest first.
+    fn stored(&self, domain: &str, time: u64) -> Result<Vec<StoredBackup>, BackupError> {
+        let prefix = Self::prefix(domain);
+        let keys = self.store.keys(&prefix).map_err(BackupError::Store)?;
+        // Keys sort like the backups, as their numbers are zero-padded.
+        Ok(keys
+            .into_iter()
+            .filter_map(|key| {
+                let (name, kind) = key.strip_prefix(&prefix)?.split_once('.')?;
+                let full = match kind {
+                    "full" => true,
+                    "delta" => false,
+                    _ => return None,
+                };
+                let (created_at, run) = name.split_once('-')?;
+                let created_at: u64 = created_at.parse().ok()?;
+                let run = run.parse().ok()?;
+                (created_at <= time).then_some(StoredBackup { key, run, full })
+            })
+            .collect())
+    }
+
+    fn read<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<T, BackupError> {
+        let failed = |message: String| BackupError::Store(StateError::Backend(message));
+        let data = (self.store.get(key).map_err(BackupError::Store)?)
+            .ok_or_else(|| failed(format!("{key} disappeared")))?;
+        #[cfg(feature = "encryption")]
+        if crate::encryption::is_encrypted(&data) {
+            return Err(failed(format!("{key} is encrypted, but the store isn't")));
+        }
+        serde_json::from_slice(&data).map_err(|e| BackupError::Store(e.into()))
+    }
+}
+
+/// A backup in the store of a [`BackupArchive`].
+struct StoredBackup {
+    key: String,
+    run: u64,
+    full: bool,
+}
+
+/// What encrypted incremental backups are bound to.
+#[cfg(feature = "encryption")]
+const DELTA_ENCRYPTION_CONTEXT: &str = "zone backup delta";
+
+#[cfg(feature = "encryption")]
+impl BackupDelta {
+    /// The incremental backup as JSON encrypted with `key`, like
+    /// [`ZoneBackup::to_encrypted`].
+    pub fn to_encrypted(&self, key: &EncryptionKey) -> Result<Vec<u8>, BackupError> {
+        let json = serde_json::to_vec(self).map_err(|e| BackupError::Encryption(e.into()))?;
+        key.encrypt(DELTA_ENCRYPTION_CONTEXT, &json)
+            .map_err(BackupError::Encryption)
+    }
+
+    /// Read an incremental backup written by [`to_encrypted`](Self::to_encrypted).
+    pub fn from_encrypted(data: &[u8], key: &EncryptionKey) -> Result<Self, BackupError> {
+        let json = key
+            .decrypt(DELTA_ENCRYPTION_CONTEXT, data)
+            .map_err(BackupError::Encryption)?;
+        serde_json::from_slice(&json).map_err(|e| BackupError::Encryption(e.into()))
+    }
+}
+
+/// How [`SimplyClient::restore_zone`] treats records that aren't in the backup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RestoreMode {
+    /// Keep them, only adding back and updating the records of the backup.
+    #[default]
+    Merge,
+    /// Delete them, so the zone ends up exactly as backed up.
+    Replace,
+}
+
+impl SimplyClient {
+    /// Capture every record of a domain in a [`ZoneBackup`].
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to back up.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn backup_zone(&self, domain: &str) -> Result<ZoneBackup, SimplyClientError> {
+        let records = self.list_dns_records(domain).await?;
+        Ok(ZoneBackup::new(domain, records))
+    }
+
+    /// Compute the changes [`restore_zone`](Self::restore_zone) would make.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to restore.
+    /// * `backup` - The backup to restore, which must be of `domain`.
+    /// * `mode` - Whether records added since the backup are kept or deleted.
+    pub async fn plan_restore(
+        &self,
+        domain: &str,
+        backup: &ZoneBackup,
+        mode: RestoreMode,
+    ) -> Result<ZonePlan, BackupError> {
+        if !same_domain(&backup.domain, domain) {
+            return Err(BackupError::Domain {
+                backup: backup.domain.clone(),
+                domain: domain.to_string(),
+            });
+        }
+        backup.verify()?;
+        let current = self.list_dns_records(domain).await?;
+        let mut plan = ZonePlan::compute(domain, current, &backup.desired());
+        if mode == RestoreMode::Merge {
+            plan.changes.retain(|c| !matches!(c, Change::Delete(_)));
+        }
+        Ok(plan)
+    }
+
+    /// Bring a domain back to the records of `backup`.
+    ///
+    /// Records are matched like in [`plan`](Self::plan), by name, type and
+    /// data, so restored records that had been deleted get new IDs. Fails if
+    /// the backup is of another domain or doesn't match its checksum, before
+    /// anything is changed; failed changes are collected in the report.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to restore.
+    /// * `backup` - The backup to restore, which must be of `domain`.
+    /// * `mode` - Whether records added since the backup are kept or deleted.
+    pub async fn restore_zone(
+        &self,
+        domain: &str,
+        backup: &ZoneBackup,
+        mode: RestoreMode,
+    ) -> Result<OperationReport<Change>, BackupError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let plan = this.plan_restore(domain, backup, mode).await?;
+        Ok(this.apply(plan).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: u32, name: &str, data: &str) -> DnsRecord {
+        DnsRecord {
+            record_id: DnsRecordId::new(id),
+            name: name.to_string(),
+            ttl: 3600,
+            data: data.to_string(),
+            record_type: "A".to_string(),
+            priority: None,
+            comment: None,
+        }
+    }
+
+    fn backup(created_at: u64, records: Vec<DnsRecord>) -> ZoneBackup {
+        ZoneBackup {
+            created_at,
+            ..ZoneBackup::new("example.com", records)
+        }
+    }
+
+    fn data(records: &[DnsRecord]) -> Vec<(u32, &str)> {
+        (records.iter())
+            .map(|r| (r.record_id.get(), r.data.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn chains_give_back_every_backup() {
+        let first = backup(
+            100,
+            vec![record(1, "www", "192.0.2.1"), record(2, "old", "192.0.2.2")],
+        );
+        // Listed in another order, with `www` changed, `old` deleted and `new` created.
+        let second = backup(
+            200,
+            vec![record(3, "new", "192.0.2.3"), record(1, "www", "192.0.2.9")],
+        );
+        let third = backup(300, second.records.clone());
+
+        let delta = first.delta_to(&second);
+        assert_eq!(data(&delta.changed), [(1, "192.0.2.9"), (3, "192.0.2.3")]);
+        assert_eq!(delta.removed, [DnsRecordId::new(2)]);
+        let unchanged = second.delta_to(&third);
+        assert!(unchanged.changed.is_empty() && unchanged.removed.is_empty());
+
+        let mut chain = BackupChain::new(first);
+        chain.push(delta).unwrap();
+        chain.push(unchanged).unwrap();
+        assert!(chain.at(99).unwrap().is_none());
+        assert_eq!(
+            data(&chain.at(150).unwrap().unwrap().records),
+            [(1, "192.0.2.1"), (2, "192.0.2.2")]
+        );
+        let latest = chain.latest().unwrap();
+        assert_eq!(latest.created_at, 300);
+        assert_eq!(data(&latest.records), [(1, "192.0.2.9"), (3, "192.0.2.3")]);
+        latest.verify().unwrap();
+    }
+
+    #[test]
+    fn deltas_only_apply_to_their_base() {
+        let first = backup(100, vec![record(1, "www", "192.0.2.1")]);
+        let second = backup(200, vec![record(1, "www", "192.0.2.2")]);
+        let third = backup(300, vec![record(1, "www", "192.0.2.3")]);
+        let mut chain = BackupChain::new(first);
+        // The delta from the second to the third backup, with the second missing.
+        assert!(matches!(
+            chain.push(second.delta_to(&third)),
+            Err(BackupError::Chain { created_at: 300 })
+        ));
+
+        let mut tampered = chain.full.delta_to(&second);
+        tampered.changed[0].data = "192.0.2.66".to_string();
+        assert!(matches!(
+            chain.push(tampered),
+            Err(BackupError::Checksum { .. })
+        ));
+        assert!(chain.deltas.is_empty());
+    }
+}
@@ -0,0 +1,344 @@
+//! Concurrent bulk operations.
+//!
+//! The methods here run many API calls at once, up to a configurable
+//! `concurrency`, and collect the outcome of every item in a [`BatchResult`],
+//! so one failing item doesn't abort the rest. Calls still go through the
+//! client's retry policy and rate limit.
+//!
+//! [`SimplyClient::for_each_domain`] and [`SimplyClient::apply_across_domains`]
+//! do the same for every domain of the account, e.g. to change a record in
+//! all customer zones at once.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::api::CreateDnsRecordRequest;
+//! use simply_dns::batch::DEFAULT_CONCURRENCY;
+//!
+//! # async fn run() {
+//! let client = SimplyClient::new("account", "api_key");
+//! let records = (1..=200).map(|i| CreateDnsRecordRequest {
+//!     record_type: "A".to_string(),
+//!     name: format!("host{i}"),
+//!     data: format!("192.0.2.{}", i % 250),
+//!     priority: None,
+//!     ttl: None,
+//!     comment: None,
+//! });
+//! let result = client
+//!     .create_dns_records("example.com", records, DEFAULT_CONCURRENCY)
+//!     .await;
+//! println!("{result}");
+//! # }
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use futures_util::stream;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord, DnsRecordId};
+use crate::progress::{ItemOutcome, Operation, Progress};
+use crate::report::OperationReport;
+use crate::sync::{self, Change, ZonePlan};
+use crate::time::Instant;
+use crate::{SimplyClient, SimplyClientError};
+
+/// Number of requests the batch methods run at once when in doubt.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// The per-item outcome of a batch operation.
+pub type BatchResult<T> = OperationReport<T>;
+
+impl SimplyClient {
+    /// Create many DNS records for a domain, running up to `concurrency` requests at once.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to add the DNS records to.
+    /// * `reqs` - The DNS records to create.
+    /// * `concurrency` - Maximum number of requests in flight; `0` is treated as `1`.
+    pub async fn create_dns_records(
+        &self,
+        domain: &str,
+        reqs: impl IntoIterator<Item = CreateDnsRecordRequest>,
+        concurrency: usize,
+    ) -> BatchResult<CreateDnsRecordRequest> {
+        let reqs: Vec<_> = reqs.into_iter().collect();
+        let mut progress = self.progress(Operation::CreateRecords, Some(reqs.len()));
+        let result = run_batch(reqs, concurrency, &mut progress, |req| async move {
+            self.create_dns_record(domain, req).await.map(drop)
+        })
+        .await;
+        progress.finish(&result);
+        result
+    }
+
+    /// Delete many DNS records of a domain, running up to `concurrency` requests at once.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS records belong to.
+    /// * `record_ids` - The IDs of the DNS records to delete.
+    /// * `concurrency` - Maximum number of requests in flight; `0` is treated as `1`.
+    pub async fn delete_dns_records(
+        &self,
+        domain: &str,
+        record_ids: impl IntoIterator<Item = DnsRecordId>,
+        concurrency: usize,
+    ) -> BatchResult<DnsRecordId> {
+        let record_ids: Vec<_> = record_ids.into_iter().collect();
+        let mut progress = self.progress(Operation::DeleteRecords, Some(record_ids.len()));
+        let result = run_batch(
+            record_ids,
+            concurrency,
+            &mut progress,
+            |record_id| async move { self.delete_dns_record(domain, record_id).await },
+        )
+        .await;
+        progress.finish(&result);
+        result
+    }
+
+    /// Execute changes, e.g. from a [`ZonePlan`](crate::sync::ZonePlan), concurrently.
+    ///
+    /// Deletes run first, then updates, then creates, so a record being replaced
+    /// is gone before its successor is created. Within each phase up to
+    /// `concurrency` requests run at once.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the changes apply to.
+    /// * `changes` - The changes to execute.
+    /// * `concurrency` - Maximum number of requests in flight; `0` is treated as `1`.
+    pub async fn apply_changes(
+        &self,
+        domain: &str,
+        changes: impl IntoIterator<Item = Change>,
+        concurrency: usize,
+    ) -> BatchResult<Change> {
+        let started = Instant::now();
+        let (mut deletes, mut updates, mut creates) = (Vec::new(), Vec::new(), Vec::new());
+        for change in changes {
+            match change {
+                Change::Delete(_) => deletes.push(change),
+                Change::Update { .. } => updates.push(change),
+                Change::Create(_) => creates.push(change),
+            }
+        }
+
+        let total = deletes.len() + updates.len() + creates.len();
+        let mut progress = self.progress(Operation::ApplyChanges, Some(total));
+        let mut result = BatchResult::default();
+        for phase in [deletes, updates, creates] {
+            let report = run_batch(phase, concurrency, &mut progress, |change| {
+                sync::apply_change(self, domain, change)
+            })
+            .await;
+            result.succeeded.extend(report.succeeded);
+            result.failed.extend(report.failed);
+        }
+        result.elapsed = started.elapsed();
+        progress.finish(&result);
+        result
+    }
+}
+
+impl SimplyClient {
+    /// Run `op` for every domain of the account, up to `concurrency` at once.
+    ///
+    /// The domains are those of [`list_domains`](Self::list_domains); the
+    /// outcome of each is collected in the result. Fails only if the domains
+    /// can't be listed.
+    ///
+    /// # Arguments
+    /// * `concurrency` - Maximum number of domains in progress; `0` is treated as `1`.
+    /// * `op` - The operation to run, given the domain name.
+    pub async fn for_each_domain<F, Fut>(
+        &self,
+        concurrency: usize,
+        op: F,
+    ) -> Result<BatchResult<String>, SimplyClientError>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<(), SimplyClientError>>,
+    {
+        let domains = self.list_domains().await?;
+        let mut progress = self.progress(Operation::AcrossDomains, Some(domains.len()));
+        let result = run_batch(domains, concurrency, &mut progress, op).await;
+        progress.finish(&result);
+        Ok(result)
+    }
+
+    /// Compute and apply changes for every domain of the account, up to
+    /// `concurrency` domains at once.
+    ///
+    /// `plan` is given each domain and its records, and returns the changes to
+    /// make there, applied in order like [`apply`](Self::apply). Returning no
+    /// changes leaves the domain alone. Fails only if the domains can't be listed.
+    ///
+    /// # Arguments
+    /// * `concurrency` - Maximum number of domains in progress; `0` is treated as `1`.
+    /// * `plan` - Computes the changes for a domain from its records.
+    ///
+    /// Example usage:
+    /// ```rust,no_run
+    /// use simply_dns::SimplyClient;
+    /// use simply_dns::api::UpdateDnsRecordRequest;
+    /// use simply_dns::sync::Change;
+    ///
+    /// # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+    /// let client = SimplyClient::new("account", "api_key");
+    /// let spf = "v=spf1 include:spf.example.net -all";
+    /// let report = client
+    ///     .apply_across_domains(4, |_domain, records| {
+    ///         records
+    ///             .iter()
+    ///             .filter(|r| r.record_type == "TXT" && r.data.starts_with("v=spf1") && r.data != spf)
+    ///             .map(|r| Change::Update {
+    ///                 current: r.clone(),
+    ///                 desired: UpdateDnsRecordRequest {
+    ///                     data: spf.to_string(),
+    ///                     ..r.clone().into()
+    ///                 },
+    ///             })
+    ///             .collect()
+    ///     })
+    ///     .await?;
+    /// print!("{report}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn apply_across_domains<F>(
+        &self,
+        concurrency: usize,
+        plan: F,
+    ) -> Result<AccountReport, SimplyClientError>
+    where
+        F: Fn(&str, &[DnsRecord]) -> Vec<Change>,
+    {
+        let started = Instant::now();
+        let domains = self.list_domains().await?;
+        let mut progress = self.progress(Operation::AcrossDomains, Some(domains.len()));
+        let plan = &plan;
+        let mut outcomes = stream::iter(domains)
+            .map(|domain| async move {
+                let result = match self.list_dns_records(&domain).await {
+                    Ok(records) => {
+                        let changes = plan(&domain, &records);
+                        let zone_plan = ZonePlan {
+                            domain: domain.clone(),
+                            changes,
+                        };
+                        Ok(sync::apply(self, zone_plan).await)
+                    }
+                    Err(e) => Err(e),
+                };
+                DomainOutcome { domain, result }
+            })
+            .buffered(concurrency.max(1));
+
+        let mut report = AccountReport {
+            domains: Vec::new(),
+            elapsed: Duration::ZERO,
+        };
+        while let Some(outcome) = outcomes.next().await {
+            progress.item(
+                &outcome.domain,
+                match &outcome.result {
+                    Ok(changes) => match changes.failed.first() {
+                        Some(failed) => ItemOutcome::Failed(&failed.error),
+                        None => ItemOutcome::Succeeded,
+                    },
+                    Err(e) => ItemOutcome::Failed(e),
+                },
+            );
+            report.domains.push(outcome);
+        }
+        report.elapsed = started.elapsed();
+        let failed = report.failed().count();
+        progress.finish_with(report.domains.len() - failed, failed, 0, report.elapsed);
+        Ok(report)
+    }
+}
+
+/// The outcome of [`SimplyClient::apply_across_domains`], domain by domain.
+#[derive(Debug)]
+pub struct AccountReport {
+    /// The outcome for each domain, in the order the domains were listed.
+    pub domains: Vec<DomainOutcome>,
+    /// Total duration of the operation.
+    pub elapsed: Duration,
+}
+
+/// The outcome for one domain of an [`AccountReport`].
+#[derive(Debug)]
+pub struct DomainOutcome {
+    /// The domain.
+    pub domain: String,
+    /// The outcome of each change, or the error listing the domain's records.
+    pub result: Result<OperationReport<Change>, SimplyClientError>,
+}
+
+impl AccountReport {
+    /// Whether every domain was listed and every change made.
+    pub fn is_success(&self) -> bool {
+        self.failed().next().is_none()
+    }
+
+    /// The domains that couldn't be listed or had a change fail.
+    pub fn failed(&self) -> impl Iterator<Item = &DomainOutcome> {
+        self.domains
+            .iter()
+            .filter(|d| !d.result.as_ref().is_ok_and(|r| r.is_success()))
+    }
+}
+
+/// Shows a line per domain, followed by a summary.
+impl fmt::Display for AccountReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for outcome in &self.domains {
+            match &outcome.result {
+                Ok(report) => writeln!(f, "{}: {report}", outcome.domain)?,
+                Err(e) => writeln!(f, "{}: {e}", outcome.domain)?,
+            }
+        }
+        writeln!(
+            f,
+            "{} domains, {} failed in {:.2?}",
+            self.domains.len(),
+            self.failed().count(),
+            self.elapsed
+        )
+    }
+}
+
+/// Run `op` for every item, up to `concurrency` at once, reporting in input order.
+async fn run_batch<T, F, Fut>(
+    items: impl IntoIterator<Item = T>,
+    concurrency: usize,
+    progress: &mut Progress<'_>,
+    op: F,
+) -> BatchResult<T>
+where
+    T: Clone + fmt::Display,
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = Result<(), SimplyClientError>>,
+{
+    let started = Instant::now();
+    let starter = progress.starter();
+    let mut outcomes = stream::iter(items)
+        .map(|item| {
+            starter.begin();
+            let item_started = Instant::now();
+            let fut = op(item.clone());
+            async move { (item, fut.await, item_started) }
+        })
+        .buffered(concurrency.max(1));
+
+    let mut result = BatchResult::default();
+    while let Some((item, outcome, item_started)) = outcomes.next().await {
+        progress.record(&mut result, item, outcome, item_started);
+    }
+    result.elapsed = started.elapsed();
+    result
+}
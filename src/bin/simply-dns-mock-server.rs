@@ -0,0 +1,56 @@
+//! Runs a [`MockServer`] until killed, for hermetic end-to-end tests in CI.
+//!
+//! Usage: `simply-dns-mock-server [--listen ADDR] [--account NAME] [--api-key KEY]
+//! [--domain DOMAIN]... [--rate-limit REQUESTS_PER_SECOND]`
+
+use std::net::SocketAddr;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use simply_dns::mock::MockSimplyClient;
+use simply_dns::mock_server::MockServer;
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("simply-dns-mock-server: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let mut builder = MockServer::builder().bind("127.0.0.1:8080".parse()?);
+    let mut api = MockSimplyClient::new();
+    let (mut account, mut api_key) = ("account".to_string(), "api_key".to_string());
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+        match arg.as_str() {
+            "--listen" => builder = builder.bind(value()?.parse::<SocketAddr>()?),
+            "--account" => account = value()?,
+            "--api-key" => api_key = value()?,
+            "--domain" => api = api.with_domain(value()?),
+            "--rate-limit" => {
+                builder = builder.rate_limit(value()?.parse()?, Duration::from_secs(1))
+            }
+            _ => return Err(format!("unknown argument {arg}").into()),
+        }
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(async {
+        let server = builder
+            .api(api)
+            .credentials(account, api_key)
+            .start()
+            .await?;
+        println!("listening on {}", server.base_url());
+        std::future::pending::<()>().await;
+        Ok(())
+    })
+}
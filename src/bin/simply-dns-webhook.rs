@@ -0,0 +1,89 @@
+//! Serves a [`SimplyProvider`] as an external-dns webhook, to run as a sidecar.
+//!
+//! Configured through the environment: `SIMPLY_ACCOUNT` and `SIMPLY_API_KEY`
+//! (or a credentials file at `SIMPLY_DNS_CREDENTIALS`), `DOMAIN_FILTER` (comma-separated domains, default: all domains
+//! of the account), `WEBHOOK_LISTEN` (default `127.0.0.1:8888`) and
+//! `SIMPLY_BASE_URL` (to use another API endpoint).
+//!
+//! A team's webhook is limited to a [`Quota`] with `QUOTA_SUBTREE` (a name
+//! whose subtree it may change), `QUOTA_TYPES` (comma-separated record
+//! types) and `QUOTA_MAX_RECORDS` (how many records the subtree may hold).
+
+use std::net::SocketAddr;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use simply_dns::credentials::CredentialsChain;
+use simply_dns::provider::{ProviderServer, SimplyProvider};
+use simply_dns::quota::Quota;
+use simply_dns::{RetryPolicy, SimplyClient};
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("simply-dns-webhook: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let mut builder = SimplyClient::builder()
+        .credentials_provider(&CredentialsChain::default())?
+        .timeout(Duration::from_secs(30))
+        .retry(RetryPolicy::default());
+    if let Ok(base_url) = std::env::var("SIMPLY_BASE_URL") {
+        builder = builder.base_url(base_url);
+    }
+    let quota = quota()?;
+    if let Some(quota) = &quota {
+        builder = builder.quota(quota.clone());
+    }
+    let client = builder.build()?;
+    let addr: SocketAddr = std::env::var("WEBHOOK_LISTEN")
+        .unwrap_or_else(|_| "127.0.0.1:8888".to_string())
+        .parse()?;
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    runtime.block_on(async {
+        let domains: Vec<String> = match std::env::var("DOMAIN_FILTER") {
+            Ok(filter) => filter
+                .split(',')
+                .map(str::trim)
+                .filter(|d| !d.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Err(_) => client.list_domains().await?,
+        };
+        let mut provider = SimplyProvider::new(client, domains);
+        if let Some(quota) = quota {
+            provider = provider.quota(quota);
+        }
+        let server = ProviderServer::start(provider, addr).await?;
+        println!("listening on {}", server.addr());
+        std::future::pending::<()>().await;
+        Ok(())
+    })
+}
+
+/// The quota of the `QUOTA_*` variables, if any is set.
+fn quota() -> Result<Option<Quota>, Box<dyn std::error::Error>> {
+    let mut quota = None;
+    if let Ok(subtree) = std::env::var("QUOTA_SUBTREE") {
+        quota = Some(Quota::new().subtree(subtree));
+    }
+    if let Ok(types) = std::env::var("QUOTA_TYPES") {
+        let types = types.split(',').map(str::trim).filter(|t| !t.is_empty());
+        quota = Some(quota.unwrap_or_default().record_types(types));
+    }
+    if let Ok(max) = std::env::var("QUOTA_MAX_RECORDS") {
+        let max = max
+            .parse()
+            .map_err(|e| format!("QUOTA_MAX_RECORDS {max:?}: {e}"))?;
+        quota = Some(quota.unwrap_or_default().max_records(max));
+    }
+    Ok(quota)
+}
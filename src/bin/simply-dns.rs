@@ -0,0 +1,1283 @@
+//! A command-line tool for managing Simply.com DNS records.
+//!
+//! Credentials are read from `SIMPLY_ACCOUNT` and `SIMPLY_API_KEY`, falling back
+//! to a config file with `account = ...` and `api_key = ...` lines, found at
+//! `--config PATH`, `$SIMPLY_DNS_CONFIG` or `~/.config/simply-dns/config`, and
+//! then to the shared credentials file, see
+//! [`FileCredentials::default_location`].
+
+use std::io::{IsTerminal, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::json;
+use simply_dns::api::{CreateDnsRecordRequest, DnsRecord, DnsRecordId, UpdateDnsRecordRequest};
+use simply_dns::audit::{JsonLinesAuditLog, OtlpAuditLog, SyslogAuditLog};
+use simply_dns::backup::{BackupArchive, RestoreMode, ZoneBackup};
+use simply_dns::config::ZoneConfig;
+use simply_dns::credentials::{CredentialsChain, EnvCredentials, FileCredentials};
+use simply_dns::ddns::{DdnsUpdater, DualStackPolicy};
+#[cfg(feature = "encryption")]
+use simply_dns::encryption::{EncryptedStateStore, EncryptionKey, is_encrypted};
+use simply_dns::freeze::FreezeWindows;
+use simply_dns::git_sync::GitZoneRepo;
+use simply_dns::import::{ImportConflict, ImportResolution};
+use simply_dns::probe::ResolutionProbe;
+use simply_dns::progress::{ItemOutcome, ProgressEvent, ProgressSink};
+use simply_dns::query::RecordQuery;
+use simply_dns::report::OperationReport;
+#[cfg(feature = "s3")]
+use simply_dns::s3::{S3Config, S3StateStore};
+use simply_dns::state::{FileStateStore, StateStore};
+use simply_dns::sync::DesiredRecord;
+use simply_dns::sync::{Change, ZonePlan};
+use simply_dns::zonefile::{ZoneFile, export_zone_file};
+use simply_dns::{RetryPolicy, SimplyClient, SimplyClientBuilder};
+
+const USAGE: &str = "\
+Usage: simply-dns [--json] [--output text|json|ndjson] [--config PATH] [--freeze PATH]
+                  [--override-freeze REASON] [--audit-log PATH] [--syslog HOST:PORT]
+                  [--otlp URL] <command> [args]
+
+Commands:
+  init [--zones DIR] [--backup DIR] [--key-file FILE] [--force]
+                                                 Save and check credentials, and write
+                                                 a zone definition of every domain
+  list <domain>                                  List the records of a domain
+  create <domain> <name> <type> <data> [opts]    Create a record
+  update <domain> <id> <name> <type> <data> [opts]
+                                                 Replace a record
+  delete <domain> <id>                           Delete a record
+  export <domain>                                Print the zone as a BIND zone file
+  import <domain> <file> [--on-conflict ask|existing|imported|both]
+                                                 Create the records of a zone file,
+                                                 resolving records that differ from
+                                                 existing ones
+  apply <file> [--domain DOMAIN] [--plan]
+                                                 Make a zone match a TOML, YAML or JSON
+                                                 zone definition, or only show the
+                                                 changes
+  git-apply <dir> [--since COMMIT] [--plan]
+                                                 Apply the zone files of a Git working
+                                                 tree changed since a commit, or all
+  backup <domain> [--output FILE] [--encrypt] [--key-file FILE]
+                                                 Save every record of a domain as JSON,
+                                                 encrypted if a key is given
+  backup <domain> --archive DIR [--full-every N] [--encrypt] [--key-file FILE]
+                                                 Add a backup to an archive directory
+                                                 or s3:PREFIX, incremental but for
+                                                 every Nth (7)
+  restore <domain> <file> [--replace] [--plan] [--key-file FILE]
+                                                 Add back the records of a backup, with
+                                                 --replace also deleting newer ones
+  restore <domain> <dir> [--at UNIX_SECS] [opts] Restore the latest backup of an
+                                                 archive, or the one as of a time
+  adopt <domain> [--name GLOB] [--type TYPE] [--owner TAG] [--output FILE]
+                                                 Tag records as managed and print them
+                                                 as a zone file
+  ddns <domain> <name> [--ipv6] [--dual-stack v4|v6|both|prefer-v6]
+       [--once] [--interval SECS]                Point a record at this machine
+  probe <domain> [--name NAME]... [--type TYPE] [--qps N] [--duration SECS]
+        [--resolvers ADDR,...]                   Measure resolution latency, failures
+                                                 and answer consistency
+  schema                                         Print the JSON Schema of zone
+                                                 definitions
+
+Record options: --ttl SECS, --priority N, --comment TEXT
+
+--json is --output json. --output ndjson also prints a JSON line for every
+change as it is made, before the final JSON line of the command.
+
+Changes are refused during the freeze windows in --freeze PATH,
+$SIMPLY_DNS_FREEZE or ~/.config/simply-dns/freeze, unless
+--override-freeze gives a reason, which --audit-log records with the
+changes as JSON lines. --syslog sends the same lines to a syslog server,
+as RFC 5424 messages over UDP, and --otlp to an OpenTelemetry collector,
+as OTLP log records over HTTP.
+
+Backups are encrypted with the key in --key-file FILE, in the OS keyring
+entry of --key-keyring NAME under the service simply-dns-backup, or in
+$SIMPLY_DNS_BACKUP_KEY, as 64 hex digits. Archives named s3:PREFIX are kept
+in the bucket of the [s3] section of the config file, which sets endpoint,
+bucket, access_key_id, secret_access_key and optionally region and prefix.";
+
+/// The ownership tag `adopt` writes unless `--owner` is given.
+const DEFAULT_OWNER: &str = "managed-by=simply-dns";
+
+type Error = Box<dyn std::error::Error>;
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("simply-dns: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Options shared by all commands.
+struct Global {
+    json: bool,
+    ndjson: bool,
+    config: Option<PathBuf>,
+    freeze: Option<PathBuf>,
+    override_freeze: Option<String>,
+    audit_log: Option<PathBuf>,
+    syslog: Option<String>,
+    otlp: Option<String>,
+}
+
+fn run(args: Vec<String>) -> Result<(), Error> {
+    let mut global = Global {
+        json: false,
+        ndjson: false,
+        config: None,
+        freeze: None,
+        override_freeze: None,
+        audit_log: None,
+        syslog: None,
+        otlp: None,
+    };
+    let mut rest = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => global.json = true,
+            "--output" => {
+                (global.json, global.ndjson) = match args.next().as_deref() {
+                    Some("text") => (false, false),
+                    Some("json") => (true, false),
+                    Some("ndjson") => (true, true),
+                    _ => return Err("--output needs text, json or ndjson".into()),
+                }
+            }
+            "--config" => global.config = Some(args.next().ok_or("--config needs a value")?.into()),
+            "--freeze" => global.freeze = Some(args.next().ok_or("--freeze needs a value")?.into()),
+            "--override-freeze" => {
+                let reason = args.next().ok_or("--override-freeze needs a reason")?;
+                global.override_freeze = Some(reason);
+            }
+            "--audit-log" => {
+                global.audit_log = Some(args.next().ok_or("--audit-log needs a value")?.into());
+            }
+            "--syslog" => global.syslog = Some(args.next().ok_or("--syslog needs an address")?),
+            "--otlp" => global.otlp = Some(args.next().ok_or("--otlp needs a URL")?),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            _ => rest.push(arg),
+        }
+    }
+    let Some((command, args)) = rest.split_first() else {
+        return Err(USAGE.into());
+    };
+
+    if command == "schema" {
+        println!("{}", simply_dns::config::SCHEMA.trim_end());
+        return Ok(());
+    }
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    // Setting up asks for the credentials the other commands read.
+    if command == "init" {
+        return runtime.block_on(init(&global, args));
+    }
+    // Probing only talks to resolvers, so it works without credentials.
+    if command == "probe" {
+        return runtime.block_on(probe(&global, args));
+    }
+    let client = client(&global)?;
+    runtime.block_on(async {
+        match command.as_str() {
+            "list" => list(&client, &global, args).await,
+            "create" => create(&client, &global, args).await,
+            "update" => update(&client, args).await,
+            "delete" => delete(&client, args).await,
+            "export" => export(&client, args).await,
+            "import" => import(&client, &global, args).await,
+            "apply" => apply(&client, &global, args).await,
+            "git-apply" => git_apply(&client, &global, args).await,
+            "backup" => backup(&client, &global, args).await,
+            "restore" => restore(&client, &global, args).await,
+            "adopt" => adopt(&client, &global, args).await,
+            "ddns" => ddns(&client, &global, args).await,
+            _ => Err(format!("unknown command {command}\n\n{USAGE}").into()),
+        }
+    })
+}
+
+fn client(global: &Global) -> Result<SimplyClient, Error> {
+    let mut providers = CredentialsChain::new().with(EnvCredentials::new());
+    // A missing default config file is fine, a missing explicit one isn't.
+    if let Some(path) = config_path(global) {
+        providers = providers.with(FileCredentials::new(path).required(global.config.is_some()));
+    }
+    providers = providers.with(FileCredentials::default_location());
+    configure(
+        global,
+        SimplyClient::builder().credentials_provider(&providers)?,
+    )
+}
+
+/// Build a client with the settings of `global` and the CLI's defaults.
+fn configure(global: &Global, builder: SimplyClientBuilder) -> Result<SimplyClient, Error> {
+    let mut builder = builder
+        .timeout(Duration::from_secs(30))
+        .retry(RetryPolicy::default());
+    if let Ok(base_url) = std::env::var("SIMPLY_BASE_URL") {
+        builder = builder.base_url(base_url);
+    }
+    if global.ndjson {
+        builder = builder.progress_sink(NdjsonProgress);
+    }
+    if let Some(windows) = freeze_windows(global)? {
+        builder = builder.freeze_windows(windows);
+    }
+    if let Some(reason) = &global.override_freeze {
+        builder = builder.freeze_override(reason);
+    }
+    if let Some(path) = &global.audit_log {
+        let log = JsonLinesAuditLog::open(path)
+            .map_err(|e| format!("couldn't open {}: {e}", path.display()))?;
+        builder = builder.audit_hook(log);
+    }
+    if let Some(addr) = &global.syslog {
+        let log = SyslogAuditLog::connect(addr.as_str())
+            .map_err(|e| format!("couldn't reach {addr}: {e}"))?;
+        builder = builder.audit_hook(log);
+    }
+    if let Some(endpoint) = &global.otlp {
+        builder = builder.audit_hook(OtlpAuditLog::new(endpoint));
+    }
+    Ok(builder.build()?)
+}
+
+/// The config file holding credentials: `--config`, `$SIMPLY_DNS_CONFIG`
+/// or `~/.config/simply-dns/config`.
+fn config_path(global: &Global) -> Option<PathBuf> {
+    global.config.clone().or_else(|| {
+        std::env::var_os("SIMPLY_DNS_CONFIG")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .map(|home| PathBuf::from(home).join(".config/simply-dns/config"))
+            })
+    })
+}
+
+/// Prints every item of an operation as a JSON line once it is done, for
+/// `--output ndjson`.
+struct NdjsonProgress;
+
+impl ProgressSink for NdjsonProgress {
+    fn on_event(&self, event: &ProgressEvent<'_>) {
+        let &ProgressEvent::Item {
+            operation,
+            done,
+            total,
+            item,
+            outcome,
+        } = event
+        else {
+            return;
+        };
+        let mut line = json!({
+            "operation": operation.to_string(),
+            "done": done,
+            "total": total,
+            "item": item,
+        });
+        match outcome {
+            ItemOutcome::Succeeded => line["outcome"] = json!("succeeded"),
+            ItemOutcome::Failed(e) => {
+                line["outcome"] = json!("failed");
+                line["error"] = json!(e.to_string());
+            }
+            ItemOutcome::Skipped(reason) => {
+                line["outcome"] = json!("skipped");
+                line["reason"] = json!(reason);
+            }
+        }
+        println!("{line}");
+    }
+}
+
+/// The freeze windows of `--freeze`, `$SIMPLY_DNS_FREEZE` or the default
+/// file, which may be missing unlike the others.
+fn freeze_windows(global: &Global) -> Result<Option<FreezeWindows>, Error> {
+    let (path, required) = match global
+        .freeze
+        .clone()
+        .or_else(|| std::env::var_os("SIMPLY_DNS_FREEZE").map(PathBuf::from))
+    {
+        Some(path) => (path, true),
+        None => match std::env::var_os("HOME") {
+            Some(home) => (PathBuf::from(home).join(".config/simply-dns/freeze"), false),
+            None => return Ok(None),
+        },
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("couldn't read {}: {e}", path.display()).into()),
+    };
+    let windows = FreezeWindows::parse(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(Some(windows))
+}
+
+/// A `--flag value` option, or a switch without a value.
+type Opt<'a> = (&'a str, Option<&'a str>);
+
+/// Split `args` into positional arguments and `--flag value` options.
+fn parse_args<'a>(
+    args: &'a [String],
+    flags: &[&str],
+    switches: &[&str],
+) -> Result<(Vec<&'a str>, Vec<Opt<'a>>), Error> {
+    let (mut positional, mut options) = (Vec::new(), Vec::new());
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if switches.contains(&arg.as_str()) {
+            options.push((arg.as_str(), None));
+        } else if flags.contains(&arg.as_str()) {
+            let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+            options.push((arg.as_str(), Some(value.as_str())));
+        } else if arg.starts_with("--") {
+            return Err(format!("unknown option {arg}").into());
+        } else {
+            positional.push(arg.as_str());
+        }
+    }
+    Ok((positional, options))
+}
+
+fn expect<'a, const N: usize>(positional: &[&'a str], usage: &str) -> Result<[&'a str; N], Error> {
+    positional
+        .try_into()
+        .map_err(|_| format!("usage: simply-dns {usage}").into())
+}
+
+/// The value of the last `flag` option, if given.
+fn option<'a>(options: &[Opt<'a>], flag: &str) -> Option<&'a str> {
+    options
+        .iter()
+        .rev()
+        .find(|&&(f, _)| f == flag)
+        .and_then(|&(_, value)| value)
+}
+
+/// TTL, priority and comment given as record options.
+struct RecordOptions {
+    ttl: Option<u32>,
+    priority: Option<u32>,
+    comment: Option<String>,
+}
+
+const RECORD_FLAGS: &[&str] = &["--ttl", "--priority", "--comment"];
+
+fn record_options(options: &[Opt<'_>]) -> Result<RecordOptions, Error> {
+    let mut opts = RecordOptions {
+        ttl: None,
+        priority: None,
+        comment: None,
+    };
+    for &(flag, value) in options {
+        let value = value.unwrap_or_default();
+        match flag {
+            "--ttl" => opts.ttl = Some(value.parse()?),
+            "--priority" => opts.priority = Some(value.parse()?),
+            "--comment" => opts.comment = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    Ok(opts)
+}
+
+fn record_id(id: &str) -> Result<DnsRecordId, Error> {
+    id.parse()
+        .map_err(|_| format!("invalid record ID {id:?}").into())
+}
+
+fn record_json(record: &DnsRecord) -> serde_json::Value {
+    json!({
+        "record_id": record.record_id.to_string(),
+        "name": record.name,
+        "type": record.record_type,
+        "data": record.data,
+        "ttl": record.ttl,
+        "priority": record.priority,
+        "comment": record.comment,
+    })
+}
+
+async fn list(client: &SimplyClient, global: &Global, args: &[String]) -> Result<(), Error> {
+    let (positional, _) = parse_args(args, &[], &[])?;
+    let [domain] = expect(&positional, "list <domain>")?;
+    let records = client.list_dns_records(domain).await?;
+    if global.json {
+        let records: Vec<_> = records.iter().map(record_json).collect();
+        println!("{}", serde_json::to_string_pretty(&records)?);
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 6]> = records
+        .iter()
+        .map(|r| {
+            [
+                r.record_id.to_string(),
+                r.name.clone(),
+                r.record_type.clone(),
+                r.ttl.to_string(),
+                r.priority.map(|p| p.to_string()).unwrap_or_default(),
+                r.data.clone(),
+            ]
+        })
+        .collect();
+    let header = ["ID", "NAME", "TYPE", "TTL", "PRIO", "DATA"].map(str::to_string);
+    let mut widths = [0; 6];
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in std::iter::once(&header).chain(&rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+    Ok(())
+}
+
+async fn create(client: &SimplyClient, global: &Global, args: &[String]) -> Result<(), Error> {
+    let (positional, options) = parse_args(args, RECORD_FLAGS, &[])?;
+    let [domain, name, record_type, data] =
+        expect(&positional, "create <domain> <name> <type> <data> [opts]")?;
+    let opts = record_options(&options)?;
+    let req = CreateDnsRecordRequest {
+        record_type: record_type.to_string(),
+        name: name.to_string(),
+        data: data.to_string(),
+        priority: opts.priority,
+        ttl: opts.ttl,
+        comment: opts.comment,
+    };
+    let ids = client.create_dns_record(domain, req).await?;
+    if global.json {
+        let ids: Vec<_> = ids
+            .iter()
+            .map(|id| json!({ "id": id.to_string() }))
+            .collect();
+        println!("{}", json!({ "created": ids }));
+    } else {
+        for id in ids {
+            println!("created record {id}");
+        }
+    }
+    Ok(())
+}
+
+async fn update(client: &SimplyClient, args: &[String]) -> Result<(), Error> {
+    let (positional, options) = parse_args(args, RECORD_FLAGS, &[])?;
+    let [domain, id, name, record_type, data] = expect(
+        &positional,
+        "update <domain> <id> <name> <type> <data> [opts]",
+    )?;
+    let opts = record_options(&options)?;
+    let req = UpdateDnsRecordRequest {
+        record_type: record_type.to_string(),
+        name: name.to_string(),
+        data: data.to_string(),
+        priority: opts.priority,
+        ttl: opts.ttl,
+        comment: opts.comment,
+    };
+    client
+        .update_dns_record(domain, record_id(id)?, req)
+        .await?;
+    Ok(())
+}
+
+async fn delete(client: &SimplyClient, args: &[String]) -> Result<(), Error> {
+    let (positional, _) = parse_args(args, &[], &[])?;
+    let [domain, id] = expect(&positional, "delete <domain> <id>")?;
+    client.delete_dns_record(domain, record_id(id)?).await?;
+    Ok(())
+}
+
+async fn export(client: &SimplyClient, args: &[String]) -> Result<(), Error> {
+    let (positional, _) = parse_args(args, &[], &[])?;
+    let [domain] = expect(&positional, "export <domain>")?;
+    let records = client.list_dns_records(domain).await?;
+    println!("$ORIGIN {}.", domain.trim_end_matches('.'));
+    print!("{}", export_zone_file(&records));
+    Ok(())
+}
+
+async fn import(client: &SimplyClient, global: &Global, args: &[String]) -> Result<(), Error> {
+    let (positional, options) = parse_args(args, &["--on-conflict"], &[])?;
+    let usage = "import <domain> <file> [--on-conflict ask|existing|imported|both]";
+    let [domain, file] = expect(&positional, usage)?;
+    // Ask about conflicts only if someone is there to answer.
+    let on_conflict = match options.first().and_then(|&(_, value)| value) {
+        Some("ask") => Some(None),
+        Some("existing") => Some(Some(ImportResolution::KeepExisting)),
+        Some("imported") => Some(Some(ImportResolution::TakeImported)),
+        Some("both") => Some(Some(ImportResolution::KeepBoth)),
+        Some(other) => return Err(format!("invalid --on-conflict {other:?}").into()),
+        None if std::io::stdin().is_terminal() && !global.json => Some(None),
+        None => None,
+    };
+    let input = std::fs::read_to_string(file).map_err(|e| format!("{file}: {e}"))?;
+    let records = ZoneFile::parse_with_origin(&input, Some(domain))?;
+    let report = client.plan_import(domain, records).await?;
+    if on_conflict.is_none() && !report.conflicts.is_empty() {
+        if global.json {
+            println!("{}", serde_json::to_string(&report)?);
+        } else {
+            eprint!("{report}");
+        }
+        return Err(format!(
+            "{} conflicting names, choose how to resolve them with --on-conflict",
+            report.conflicts.len()
+        )
+        .into());
+    }
+    let plan = report.resolve(|conflict| match on_conflict.flatten() {
+        Some(resolution) => resolution,
+        None => ask_resolution(conflict),
+    });
+    let result = client.apply(plan).await;
+    if global.json {
+        let count = |create: bool| {
+            result
+                .succeeded
+                .iter()
+                .filter(|s| matches!(s.item, Change::Create(_)) == create)
+                .count()
+        };
+        println!(
+            "{}",
+            json!({ "created": count(true), "deleted": count(false), "failed": result.failed.len() })
+        );
+    } else {
+        println!("{result}");
+    }
+    if result.failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} changes could not be made", result.failed.len()).into())
+    }
+}
+
+/// Ask on the terminal how to resolve `conflict`, keeping the existing records
+/// if input ends.
+fn ask_resolution(conflict: &ImportConflict) -> ImportResolution {
+    eprint!("{conflict}");
+    loop {
+        eprint!("keep [e]xisting, take [i]mported or keep [b]oth? ");
+        let mut answer = String::new();
+        match std::io::stdin().read_line(&mut answer) {
+            Ok(0) | Err(_) => return ImportResolution::KeepExisting,
+            Ok(_) => {}
+        }
+        match answer.trim() {
+            "e" | "existing" => return ImportResolution::KeepExisting,
+            "i" | "imported" => return ImportResolution::TakeImported,
+            "b" | "both" => return ImportResolution::KeepBoth,
+            _ => {}
+        }
+    }
+}
+
+async fn apply(client: &SimplyClient, global: &Global, args: &[String]) -> Result<(), Error> {
+    let (positional, options) = parse_args(args, &["--domain"], &["--plan"])?;
+    let [file] = expect(&positional, "apply <file> [--domain DOMAIN] [--plan]")?;
+    let config = ZoneConfig::load(file)?;
+    let mut domain = config.domain.clone();
+    let mut plan_only = false;
+    for &(flag, value) in &options {
+        match flag {
+            "--domain" => domain = value.map(str::to_string),
+            "--plan" => plan_only = true,
+            _ => {}
+        }
+    }
+    let domain = domain.ok_or_else(|| format!("{file} names no domain, pass --domain"))?;
+    let plan = client.plan(&domain, &config.zone).await?;
+    if plan_only {
+        print_plan(global, &plan);
+        return Ok(());
+    }
+    report_changes(global, &client.apply(plan).await)
+}
+
+async fn git_apply(client: &SimplyClient, global: &Global, args: &[String]) -> Result<(), Error> {
+    let (positional, options) = parse_args(args, &["--since"], &["--plan"])?;
+    let [dir] = expect(&positional, "git-apply <dir> [--since COMMIT] [--plan]")?;
+    let mut since = None;
+    let mut plan_only = false;
+    for &(flag, value) in &options {
+        match flag {
+            "--since" => since = value,
+            "--plan" => plan_only = true,
+            _ => {}
+        }
+    }
+    let repo = GitZoneRepo::open(dir)?;
+    let plans = client.plan_git(&repo, since).await?;
+    if plan_only {
+        if global.json {
+            let zones: Vec<_> = plans
+                .zones
+                .iter()
+                .map(|z| json!({ "file": z.path, "plan": z.plan.to_json() }))
+                .collect();
+            println!("{}", json!({ "zones": zones, "deleted": plans.deleted }));
+        } else {
+            for zone in &plans.zones {
+                println!("{}", zone.path.display());
+                print_plan(global, &zone.plan);
+            }
+            for path in &plans.deleted {
+                println!("{}: deleted, zone left alone", path.display());
+            }
+        }
+        return Ok(());
+    }
+    let report = client.apply_git(plans).await;
+    if global.json {
+        let zones: Vec<_> = report
+            .zones
+            .iter()
+            .map(|z| {
+                json!({
+                    "file": z.path,
+                    "domain": z.domain,
+                    "succeeded": z.report.succeeded.len(),
+                    "failed": z.report.failed.len(),
+                    "skipped": z.report.skipped.len(),
+                })
+            })
+            .collect();
+        println!("{}", json!({ "zones": zones, "deleted": report.deleted }));
+    } else {
+        for zone in &report.zones {
+            for failure in &zone.report.failed {
+                eprintln!("failed: {}: {}", failure.item, failure.error);
+            }
+            for skipped in &zone.report.skipped {
+                eprintln!("skipped: {}: {}", skipped.item, skipped.reason);
+            }
+        }
+        println!("{report}");
+    }
+    match report.failed() + report.skipped() {
+        0 => Ok(()),
+        failed => Err(format!("{failed} changes could not be made").into()),
+    }
+}
+
+async fn backup(client: &SimplyClient, global: &Global, args: &[String]) -> Result<(), Error> {
+    let (positional, options) = parse_args(
+        args,
+        &[
+            "--output",
+            "--key-file",
+            "--key-keyring",
+            "--archive",
+            "--full-every",
+        ],
+        &["--encrypt"],
+    )?;
+    let [domain] = expect(
+        &positional,
+        "backup <domain> [--output FILE | --archive DIR [--full-every N]] [--encrypt] \
+         [--key-file FILE]",
+    )?;
+    let output = option(&options, "--output");
+    let encrypt = options
+        .iter()
+        .any(|&(f, _)| matches!(f, "--encrypt" | "--key-file" | "--key-keyring"));
+    if let Some(dir) = option(&options, "--archive") {
+        let full_every = match option(&options, "--full-every") {
+            Some(runs) => runs.parse().map_err(|_| "--full-every needs a number")?,
+            None => 7,
+        };
+        let archive = BackupArchive::new(client, archive_store(global, dir, encrypt, &options)?);
+        println!("{}", archive.full_every(full_every).backup(domain).await?);
+        return Ok(());
+    }
+    if encrypt && output.is_none() && std::io::stdout().is_terminal() {
+        return Err("not writing an encrypted backup to a terminal, use --output".into());
+    }
+    let backup = client.backup_zone(domain).await?;
+    let data = if encrypt {
+        encrypt_backup(&backup, &options)?
+    } else {
+        serde_json::to_vec_pretty(&backup)?
+    };
+    match output {
+        Some(path) => std::fs::write(path, data).map_err(|e| format!("{path}: {e}"))?,
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(&data)?;
+            if !encrypt {
+                stdout.write_all(b"\n")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn restore(client: &SimplyClient, global: &Global, args: &[String]) -> Result<(), Error> {
+    let (positional, options) = parse_args(
+        args,
+        &["--key-file", "--key-keyring", "--at"],
+        &["--replace", "--plan"],
+    )?;
+    let [domain, file] = expect(
+        &positional,
+        "restore <domain> <file|dir> [--at UNIX_SECS] [--replace] [--plan] [--key-file FILE]",
+    )?;
+    let backup = if file.starts_with("s3:") || std::path::Path::new(file).is_dir() {
+        let time = match option(&options, "--at") {
+            Some(time) => time
+                .parse()
+                .map_err(|_| "--at needs seconds since the epoch")?,
+            None => u64::MAX,
+        };
+        let archive = BackupArchive::new(
+            client,
+            archive_store(global, file, has_backup_key(&options), &options)?,
+        );
+        archive
+            .at(domain, time)
+            .map_err(|e| format!("{file}: {e}"))?
+            .ok_or_else(|| format!("{file}: no backup of {domain} from then"))?
+    } else {
+        let data = std::fs::read(file).map_err(|e| format!("{file}: {e}"))?;
+        read_backup(&data, &options).map_err(|e| format!("{file}: {e}"))?
+    };
+    let has = |flag: &str| options.iter().any(|&(f, _)| f == flag);
+    let mode = if has("--replace") {
+        RestoreMode::Replace
+    } else {
+        RestoreMode::Merge
+    };
+    let plan = client.plan_restore(domain, &backup, mode).await?;
+    if has("--plan") {
+        print_plan(global, &plan);
+        return Ok(());
+    }
+    report_changes(global, &client.apply(plan).await)
+}
+
+/// The environment variable holding the key for encrypted backups, as 64 hex digits.
+#[cfg(feature = "encryption")]
+const BACKUP_KEY_VAR: &str = "SIMPLY_DNS_BACKUP_KEY";
+
+/// The OS keyring service holding keys for encrypted backups.
+#[cfg(feature = "encryption")]
+const BACKUP_KEY_SERVICE: &str = "simply-dns-backup";
+
+/// The key for encrypted backups, from `--key-file`, `--key-keyring` or
+/// [`BACKUP_KEY_VAR`].
+#[cfg(feature = "encryption")]
+fn backup_key(options: &[Opt<'_>]) -> Result<EncryptionKey, Error> {
+    if let Some(path) = option(options, "--key-file") {
+        return Ok(EncryptionKey::from_file(path).map_err(|e| format!("{path}: {e}"))?);
+    }
+    if let Some(name) = option(options, "--key-keyring") {
+        return Ok(EncryptionKey::from_keyring(BACKUP_KEY_SERVICE, name)?);
+    }
+    Ok(EncryptionKey::from_env(BACKUP_KEY_VAR)?)
+}
+
+/// Whether a key for encrypted backups was given.
+#[cfg(feature = "encryption")]
+fn has_backup_key(options: &[Opt<'_>]) -> bool {
+    option(options, "--key-file").is_some()
+        || option(options, "--key-keyring").is_some()
+        || std::env::var_os(BACKUP_KEY_VAR).is_some()
+}
+
+#[cfg(not(feature = "encryption"))]
+fn has_backup_key(_: &[Opt<'_>]) -> bool {
+    false
+}
+
+/// The store of the backup archive in `dir`, encrypting what it writes and
+/// decrypting what it reads if `encrypt` is set.
+#[cfg(feature = "encryption")]
+fn archive_store(
+    global: &Global,
+    dir: &str,
+    encrypt: bool,
+    options: &[Opt<'_>],
+) -> Result<Arc<dyn StateStore>, Error> {
+    let store = plain_archive_store(global, dir)?;
+    if !encrypt {
+        return Ok(store);
+    }
+    let key = backup_key(options)?;
+    Ok(Arc::new(
+        EncryptedStateStore::new(store, key).read_plaintext(true),
+    ))
+}
+
+#[cfg(not(feature = "encryption"))]
+fn archive_store(
+    global: &Global,
+    dir: &str,
+    encrypt: bool,
+    _: &[Opt<'_>],
+) -> Result<Arc<dyn StateStore>, Error> {
+    if encrypt {
+        return Err("encrypting backups needs simply-dns built with the encryption feature".into());
+    }
+    plain_archive_store(global, dir)
+}
+
+/// The store of the backup archive in `dir`: a directory, or with `s3:PREFIX`
+/// the objects under that prefix of the bucket in the `[s3]` section of the
+/// config file.
+fn plain_archive_store(global: &Global, dir: &str) -> Result<Arc<dyn StateStore>, Error> {
+    match dir.strip_prefix("s3:") {
+        Some(prefix) => s3_store(global, prefix),
+        None => Ok(Arc::new(
+            FileStateStore::new(dir).map_err(|e| format!("{dir}: {e}"))?,
+        )),
+    }
+}
+
+#[cfg(feature = "s3")]
+fn s3_store(global: &Global, prefix: &str) -> Result<Arc<dyn StateStore>, Error> {
+    let path = config_path(global).ok_or("HOME isn't set, use --config PATH")?;
+    let config = S3Config::from_file(&path, "s3")?;
+    // The archive is a directory of the bucket.
+    let separator = if prefix.is_empty() || prefix.ends_with('/') {
+        ""
+    } else {
+        "/"
+    };
+    let config = S3Config {
+        prefix: format!("{}{prefix}{separator}", config.prefix),
+        ..config
+    };
+    Ok(Arc::new(S3StateStore::new(config)?))
+}
+
+#[cfg(not(feature = "s3"))]
+fn s3_store(_: &Global, _: &str) -> Result<Arc<dyn StateStore>, Error> {
+    Err("archiving to S3 needs simply-dns built with the s3 feature".into())
+}
+
+#[cfg(feature = "encryption")]
+fn encrypt_backup(backup: &ZoneBackup, options: &[Opt<'_>]) -> Result<Vec<u8>, Error> {
+    Ok(backup.to_encrypted(&backup_key(options)?)?)
+}
+
+#[cfg(not(feature = "encryption"))]
+fn encrypt_backup(_: &ZoneBackup, _: &[Opt<'_>]) -> Result<Vec<u8>, Error> {
+    Err("encrypting backups needs simply-dns built with the encryption feature".into())
+}
+
+/// Read a backup, decrypting it if it was saved with `--encrypt`.
+#[cfg(feature = "encryption")]
+fn read_backup(data: &[u8], options: &[Opt<'_>]) -> Result<ZoneBackup, Error> {
+    if is_encrypted(data) {
+        return Ok(ZoneBackup::from_encrypted(data, &backup_key(options)?)?);
+    }
+    Ok(serde_json::from_slice(data)?)
+}
+
+#[cfg(not(feature = "encryption"))]
+fn read_backup(data: &[u8], _: &[Opt<'_>]) -> Result<ZoneBackup, Error> {
+    Ok(serde_json::from_slice(data)?)
+}
+
+/// Print the changes of `plan` without making them, colored on a terminal.
+fn print_plan(global: &Global, plan: &ZonePlan) {
+    if global.json {
+        println!("{}", plan.to_json());
+    } else if std::io::stdout().is_terminal() {
+        println!("{}", plan.render());
+    } else {
+        println!("{plan}");
+    }
+}
+
+/// Print the outcome of applying a plan, failing if any change failed or was
+/// skipped, e.g. during a freeze.
+fn report_changes(global: &Global, result: &OperationReport<Change>) -> Result<(), Error> {
+    if global.json {
+        let count =
+            |kind: fn(&Change) -> bool| result.succeeded.iter().filter(|s| kind(&s.item)).count();
+        println!(
+            "{}",
+            json!({
+                "created": count(|c| matches!(c, Change::Create(_))),
+                "updated": count(|c| matches!(c, Change::Update { .. })),
+                "deleted": count(|c| matches!(c, Change::Delete(_))),
+                "failed": result.failed.len(),
+                "skipped": result.skipped.len(),
+            })
+        );
+    } else {
+        for failure in &result.failed {
+            eprintln!("failed: {}: {}", failure.item, failure.error);
+        }
+        for skipped in &result.skipped {
+            eprintln!("skipped: {}: {}", skipped.item, skipped.reason);
+        }
+        println!("{result}");
+    }
+    match result.failed.len() + result.skipped.len() {
+        0 => Ok(()),
+        failed => Err(format!("{failed} changes could not be made").into()),
+    }
+}
+
+async fn adopt(client: &SimplyClient, global: &Global, args: &[String]) -> Result<(), Error> {
+    let (positional, options) =
+        parse_args(args, &["--name", "--type", "--owner", "--output"], &[])?;
+    let [domain] = expect(
+        &positional,
+        "adopt <domain> [--name GLOB] [--type TYPE] [--owner TAG] [--output FILE]",
+    )?;
+    let (mut filter, mut owner, mut output) = (RecordQuery::new(), DEFAULT_OWNER, None);
+    for &(flag, value) in &options {
+        let value = value.unwrap_or_default();
+        match flag {
+            "--name" => filter = filter.name_glob(value),
+            "--type" => filter = filter.record_type(value),
+            "--owner" => owner = value,
+            "--output" => output = Some(value),
+            _ => {}
+        }
+    }
+    let report = client.adopt(domain, &filter, owner).await?;
+    let adopted: Vec<DnsRecord> = report
+        .succeeded
+        .iter()
+        .map(|s| s.item.clone())
+        .chain(report.skipped.iter().map(|s| s.item.clone()))
+        .collect();
+    let spec = format!(
+        "$ORIGIN {}.\n{}",
+        domain.trim_end_matches('.'),
+        export_zone_file(&adopted)
+    );
+    match output {
+        Some(path) => std::fs::write(path, spec).map_err(|e| format!("{path}: {e}"))?,
+        None => print!("{spec}"),
+    }
+    if global.json {
+        eprintln!(
+            "{}",
+            json!({
+                "adopted": report.succeeded.len(),
+                "already_owned": report.skipped.len(),
+                "failed": report.failed.len(),
+            })
+        );
+    } else {
+        eprintln!("{report}");
+    }
+    if report.failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} records could not be adopted", report.failed.len()).into())
+    }
+}
+
+async fn init(global: &Global, args: &[String]) -> Result<(), Error> {
+    let (positional, options) = parse_args(
+        args,
+        &["--zones", "--backup", "--key-file", "--key-keyring"],
+        &["--force"],
+    )?;
+    let [] = expect(
+        &positional,
+        "init [--zones DIR] [--backup DIR] [--key-file FILE] [--force]",
+    )?;
+    let force = options.iter().any(|&(f, _)| f == "--force");
+    let credentials = config_path(global).ok_or("HOME isn't set, use --config PATH")?;
+    if credentials.exists() && !force {
+        return Err(format!(
+            "{} exists, use --force to replace it",
+            credentials.display()
+        )
+        .into());
+    }
+
+    let account = match std::env::var("SIMPLY_ACCOUNT") {
+        Ok(account) => account,
+        Err(_) => prompt("Simply.com account, e.g. S123456: ", false)?,
+    };
+    let api_key = match std::env::var("SIMPLY_API_KEY") {
+        Ok(api_key) => api_key,
+        Err(_) => prompt("API key: ", true)?,
+    };
+    if account.is_empty() || api_key.is_empty() {
+        return Err("init needs an account and an API key".into());
+    }
+    let client = configure(
+        global,
+        SimplyClient::builder().credentials(&account, &api_key),
+    )?;
+    let domains = client
+        .list_domains()
+        .await
+        .map_err(|e| format!("couldn't sign in as {account}: {e}"))?;
+    write_private(
+        &credentials,
+        &format!("account = {account}\napi_key = {api_key}\n"),
+    )?;
+    eprintln!(
+        "Signed in as {account}, saved the credentials to {}",
+        credentials.display()
+    );
+
+    let zones = PathBuf::from(option(&options, "--zones").unwrap_or("zones"));
+    std::fs::create_dir_all(&zones).map_err(|e| format!("{}: {e}", zones.display()))?;
+    let mut written = Vec::new();
+    for domain in &domains {
+        let path = zones.join(format!("{domain}.toml"));
+        if path.exists() && !force {
+            eprintln!("Kept {}, which already exists", path.display());
+            continue;
+        }
+        let records = client.list_dns_records(domain).await?;
+        let config = ZoneConfig {
+            domain: Some(domain.clone()),
+            zone: records
+                .iter()
+                .map(|r| DesiredRecord {
+                    name: simply_dns::names::to_relative(&r.name, domain),
+                    record_type: r.record_type.clone(),
+                    data: r.data.clone(),
+                    ttl: Some(r.ttl),
+                    priority: r.priority,
+                    comment: r.comment.clone(),
+                })
+                .collect(),
+        };
+        let header = format!(
+            "# The records of {domain} when `simply-dns init` ran. Edit them and run\n\
+             # `simply-dns apply {}` to make the zone match.\n",
+            path.display()
+        );
+        std::fs::write(&path, header + &config.to_toml())
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        eprintln!("Wrote {} with {} records", path.display(), records.len());
+        written.push(path.display().to_string());
+    }
+
+    let backup = match option(&options, "--backup") {
+        Some(dir) => Some(dir.to_string()),
+        None if std::io::stdin().is_terminal() && !domains.is_empty() => {
+            prompt("Back up every zone to ./backups now? [y/N] ", false)?
+                .eq_ignore_ascii_case("y")
+                .then(|| "backups".to_string())
+        }
+        None => None,
+    };
+    let mut backed_up = Vec::new();
+    if let Some(dir) = &backup {
+        let store = archive_store(global, dir, has_backup_key(&options), &options)?;
+        let archive = BackupArchive::new(&client, store);
+        for domain in &domains {
+            let key = archive.backup(domain).await?;
+            eprintln!("Backed up {domain} to {dir}");
+            backed_up.push(key);
+        }
+    }
+    if global.json {
+        println!(
+            "{}",
+            json!({
+                "account": account,
+                "credentials": credentials.display().to_string(),
+                "domains": domains,
+                "zones": written,
+                "backups": backed_up,
+            })
+        );
+    }
+    Ok(())
+}
+
+/// Ask for a line on standard input, without echoing it if `secret`.
+fn prompt(question: &str, secret: bool) -> Result<String, Error> {
+    let terminal = std::io::stdin().is_terminal();
+    eprint!("{question}");
+    std::io::stderr().flush()?;
+    // Without a terminal there is nothing to echo to, so nothing to turn off.
+    let stty = |mode: &str| {
+        let _ = std::process::Command::new("stty")
+            .arg(mode)
+            .stdin(std::process::Stdio::inherit())
+            .status();
+    };
+    if secret && terminal {
+        stty("-echo");
+    }
+    let mut answer = String::new();
+    let read = std::io::stdin().read_line(&mut answer);
+    if secret && terminal {
+        stty("echo");
+        eprintln!();
+    }
+    read?;
+    Ok(answer.trim().to_string())
+}
+
+/// Write `contents` to `path`, readable only by the user on Unix.
+fn write_private(path: &std::path::Path, contents: &str) -> Result<(), Error> {
+    let failed = |e: std::io::Error| format!("{}: {e}", path.display());
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(failed)?;
+    }
+    let mut file = std::fs::OpenOptions::new();
+    file.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut file, 0o600);
+    let mut file = file.open(path).map_err(failed)?;
+    // An existing file keeps its mode when opened, so restrict it as well.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .map_err(failed)?;
+    }
+    file.write_all(contents.as_bytes()).map_err(failed)?;
+    Ok(())
+}
+
+async fn ddns(client: &SimplyClient, global: &Global, args: &[String]) -> Result<(), Error> {
+    let (positional, options) =
+        parse_args(args, &["--interval", "--dual-stack"], &["--ipv6", "--once"])?;
+    let [domain, name] = expect(
+        &positional,
+        "ddns <domain> <name> [--ipv6] [--dual-stack v4|v6|both|prefer-v6] [--once] \
+         [--interval SECS]",
+    )?;
+    let mut updater = DdnsUpdater::new(client, domain, name);
+    let (mut once, mut interval) = (false, Duration::from_secs(300));
+    for &(flag, value) in &options {
+        match flag {
+            "--ipv6" => updater = updater.ipv6(true),
+            "--dual-stack" => {
+                updater = updater.dual_stack(match value.unwrap_or_default() {
+                    "v4" => DualStackPolicy::V4Only,
+                    "v6" => DualStackPolicy::V6Only,
+                    "both" => DualStackPolicy::Both,
+                    "prefer-v6" => DualStackPolicy::PreferV6,
+                    other => return Err(format!("unknown --dual-stack policy {other}").into()),
+                })
+            }
+            "--once" => once = true,
+            "--interval" => interval = Duration::from_secs(value.unwrap_or_default().parse()?),
+            _ => {}
+        }
+    }
+    if !once {
+        match updater.run_loop(interval).await? {}
+    }
+    for update in updater.update_once().await? {
+        if global.json {
+            println!(
+                "{}",
+                json!({
+                    "type": update.record_type,
+                    "address": update.address.map(|a| a.to_string()),
+                    "changed": update.changed,
+                })
+            );
+        } else {
+            match (update.address, update.changed) {
+                (Some(address), true) => println!("{} {address} updated", update.record_type),
+                (Some(address), false) => println!("{} {address} unchanged", update.record_type),
+                (None, true) => println!("{} removed", update.record_type),
+                (None, false) => println!("{} absent", update.record_type),
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn probe(global: &Global, args: &[String]) -> Result<(), Error> {
+    let usage = "probe <domain> [--name NAME]... [--type TYPE] [--qps N] [--duration SECS] \
+                 [--resolvers ADDR,...]";
+    let (positional, options) = parse_args(
+        args,
+        &["--name", "--type", "--qps", "--duration", "--resolvers"],
+        &[],
+    )?;
+    let [domain] = expect(&positional, usage)?;
+    let mut probe = ResolutionProbe::new(domain);
+    for &(flag, value) in &options {
+        let value = value.unwrap_or_default();
+        match flag {
+            "--name" => probe = probe.name(value),
+            "--type" => probe = probe.record_type(value),
+            "--qps" => probe = probe.qps(value.parse()?),
+            "--duration" => probe = probe.duration(Duration::from_secs(value.parse()?)),
+            "--resolvers" => {
+                let resolvers = value
+                    .split(',')
+                    .map(|r| {
+                        r.parse::<SocketAddr>()
+                            .or_else(|_| r.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, 53)))
+                            .map_err(|_| format!("invalid resolver {r:?}"))
+                    })
+                    .collect::<Result<_, _>>()?;
+                probe = probe.resolvers(resolvers);
+            }
+            _ => {}
+        }
+    }
+    let report = probe.run().await?;
+    if global.json {
+        let stats: Vec<_> = report
+            .stats
+            .iter()
+            .map(|s| {
+                let ms = |d: Option<Duration>| d.map(|d| d.as_secs_f64() * 1000.0);
+                json!({
+                    "name": s.name,
+                    "resolver": s.resolver.to_string(),
+                    "queries": s.queries,
+                    "failures": s.failures,
+                    "p50_ms": ms(s.latency_percentile(50.0)),
+                    "p95_ms": ms(s.latency_percentile(95.0)),
+                    "max_ms": ms(s.latencies.last().copied()),
+                    "answers": s.answers.iter().map(|(answer, count)| {
+                        json!({ "data": answer, "count": count })
+                    }).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({
+                "type": report.record_type,
+                "results": stats,
+                "inconsistent": report.inconsistent(),
+            }))?
+        );
+    } else {
+        print!("{report}");
+    }
+    Ok(())
+}
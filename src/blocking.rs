@@ -0,0 +1,225 @@
+//! A blocking Simply.com DNS API client.
+//!
+//! [`SimplyClient`] wraps the async [`crate::SimplyClient`] and runs each call on
+//! its own single-threaded tokio runtime, for use in scripts, build scripts and
+//! other code that isn't async.
+//!
+//! Like `reqwest::blocking`, it must not be used from within an async runtime;
+//! doing so panics.
+
+use tokio::runtime::Runtime;
+
+use crate::SimplyClientError;
+use crate::api::{
+    CreateDnsRecordRequest, DnsRecord, DnsRecordId, Product, UpdateDnsRecordRequest, UpdatePatch,
+    UpsertOptions, UpsertOutcome, ZoneRecordRef,
+};
+
+/// Blocking client for the Simply.com DNS API.
+///
+/// Example usage:
+/// ```rust,no_run
+/// use simply_dns::blocking::SimplyClient;
+///
+/// let client = SimplyClient::new("account", "api_key")?;
+/// for record in client.list_dns_records("example.com")? {
+///     println!("{} {} {}", record.name, record.record_type, record.data);
+/// }
+/// # Ok::<(), simply_dns::SimplyClientError>(())
+/// ```
+pub struct SimplyClient {
+    inner: crate::SimplyClient,
+    runtime: Runtime,
+}
+
+impl SimplyClient {
+    /// Create a new blocking client with the default configuration.
+    ///
+    /// # Arguments
+    /// * `account` - Your Simply.com account identifier.
+    /// * `api_key` - The API key for authentication.
+    pub fn new(
+        account: impl Into<String>,
+        api_key: impl Into<String>,
+    ) -> Result<Self, SimplyClientError> {
+        Self::from_async(
+            crate::SimplyClient::builder()
+                .credentials(account, api_key)
+                .build()?,
+        )
+    }
+
+    /// Wrap an async client, e.g. one configured with [`crate::SimplyClient::builder`].
+    pub fn from_async(inner: crate::SimplyClient) -> Result<Self, SimplyClientError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// List all products (domains, hosting, etc.) on the account.
+    ///
+    /// See [`crate::SimplyClient::list_products`].
+    pub fn list_products(&self) -> Result<Vec<Product>, SimplyClientError> {
+        self.runtime.block_on(self.inner.list_products())
+    }
+
+    /// List the names of the domains on the account whose DNS can be managed.
+    ///
+    /// See [`crate::SimplyClient::list_domains`].
+    pub fn list_domains(&self) -> Result<Vec<String>, SimplyClientError> {
+        self.runtime.block_on(self.inner.list_domains())
+    }
+
+    /// List all DNS records for a given domain.
+    ///
+    /// See [`crate::SimplyClient::list_dns_records`].
+    pub fn list_dns_records(&self, domain: &str) -> Result<Vec<DnsRecord>, SimplyClientError> {
+        self.runtime.block_on(self.inner.list_dns_records(domain))
+    }
+
+    /// Get a single DNS record of a domain by its ID.
+    ///
+    /// See [`crate::SimplyClient::get_dns_record`].
+    pub fn get_dns_record(
+        &self,
+        domain: &str,
+        record_id: &DnsRecordId,
+    ) -> Result<Option<DnsRecord>, SimplyClientError> {
+        self.runtime
+            .block_on(self.inner.get_dns_record(domain, record_id))
+    }
+
+    /// Create a new DNS record for a domain.
+    ///
+    /// See [`crate::SimplyClient::create_dns_record`].
+    pub fn create_dns_record(
+        &self,
+        domain: &str,
+        req: CreateDnsRecordRequest,
+    ) -> Result<Vec<DnsRecordId>, SimplyClientError> {
+        self.runtime
+            .block_on(self.inner.create_dns_record(domain, req))
+    }
+
+    /// Update an existing DNS record for a domain.
+    ///
+    /// See [`crate::SimplyClient::update_dns_record`].
+    pub fn update_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+    ) -> Result<(), SimplyClientError> {
+        self.runtime
+            .block_on(self.inner.update_dns_record(domain, record_id, req))
+    }
+
+    /// Update a DNS record only if it still matches `expected`.
+    ///
+    /// See [`crate::SimplyClient::update_dns_record_if`].
+    pub fn update_dns_record_if(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        expected: &DnsRecord,
+        req: UpdateDnsRecordRequest,
+    ) -> Result<(), SimplyClientError> {
+        self.runtime.block_on(
+            self.inner
+                .update_dns_record_if(domain, record_id, expected, req),
+        )
+    }
+
+    /// Delete a DNS record only if it still matches `expected`.
+    ///
+    /// See [`crate::SimplyClient::delete_dns_record_if`].
+    pub fn delete_dns_record_if(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        expected: &DnsRecord,
+    ) -> Result<(), SimplyClientError> {
+        self.runtime
+            .block_on(self.inner.delete_dns_record_if(domain, record_id, expected))
+    }
+
+    /// Change only the fields of a DNS record that `patch` sets or clears.
+    ///
+    /// See [`crate::SimplyClient::patch_dns_record`].
+    pub fn patch_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        patch: UpdatePatch,
+    ) -> Result<(), SimplyClientError> {
+        self.runtime
+            .block_on(self.inner.patch_dns_record(domain, record_id, patch))
+    }
+
+    /// Delete a DNS record for a domain.
+    ///
+    /// See [`crate::SimplyClient::delete_dns_record`].
+    pub fn delete_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+    ) -> Result<(), SimplyClientError> {
+        self.runtime
+            .block_on(self.inner.delete_dns_record(domain, record_id))
+    }
+
+    /// Update a DNS record addressed by reference.
+    ///
+    /// See [`crate::SimplyClient::update_dns_record_by_ref`].
+    pub fn update_dns_record_by_ref(
+        &self,
+        domain: &str,
+        record: &ZoneRecordRef,
+        req: UpdateDnsRecordRequest,
+    ) -> Result<(), SimplyClientError> {
+        self.runtime
+            .block_on(self.inner.update_dns_record_by_ref(domain, record, req))
+    }
+
+    /// Delete a DNS record addressed by reference.
+    ///
+    /// See [`crate::SimplyClient::delete_dns_record_by_ref`].
+    pub fn delete_dns_record_by_ref(
+        &self,
+        domain: &str,
+        record: &ZoneRecordRef,
+    ) -> Result<(), SimplyClientError> {
+        self.runtime
+            .block_on(self.inner.delete_dns_record_by_ref(domain, record))
+    }
+
+    /// Create or update a DNS record matched by name and type.
+    ///
+    /// See [`crate::SimplyClient::upsert_dns_record`].
+    pub fn upsert_dns_record(
+        &self,
+        domain: &str,
+        name: &str,
+        record_type: &str,
+        data: &str,
+        opts: UpsertOptions,
+    ) -> Result<UpsertOutcome, SimplyClientError> {
+        self.runtime.block_on(
+            self.inner
+                .upsert_dns_record(domain, name, record_type, data, opts),
+        )
+    }
+
+    /// Export the `A` and `AAAA` records of a domain as an `/etc/hosts` fragment.
+    ///
+    /// See [`crate::SimplyClient::export_hosts`].
+    pub fn export_hosts(
+        &self,
+        domain: &str,
+        filter: impl Fn(&DnsRecord) -> bool,
+    ) -> Result<String, SimplyClientError> {
+        self.runtime
+            .block_on(self.inner.export_hosts(domain, filter))
+    }
+}
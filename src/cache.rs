@@ -0,0 +1,169 @@
+//! A caching wrapper around the DNS API.
+//!
+//! [`CachedSimplyClient`] remembers the records of each domain listed through
+//! it, so code that polls a zone doesn't call the API every time. An entry is
+//! dropped when it reaches the configured max-age or the lowest TTL among its
+//! records, whichever comes first, and whenever a record of that domain is
+//! changed through the wrapper.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use simply_dns::cache::CachedSimplyClient;
+//! use simply_dns::{SimplyClient, SimplyDnsApi};
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = CachedSimplyClient::new(
+//!     SimplyClient::new("account", "api_key"),
+//!     Duration::from_secs(300),
+//! );
+//! let records = client.list_dns_records("example.com").await?;
+//! // Served from the cache.
+//! let again = client.list_dns_records("example.com").await?;
+//! assert_eq!(records.len(), again.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord, DnsRecordId, UpdateDnsRecordRequest};
+use crate::time::Instant;
+use crate::{SimplyClient, SimplyClientError, SimplyDnsApi};
+
+/// Wraps a [`SimplyDnsApi`] and caches `list_dns_records` per domain.
+///
+/// Mutations go straight to the wrapped API and invalidate the domain's entry,
+/// even if they fail, as a failed call may still have changed the zone. Changes
+/// made by other clients are only seen once an entry expires.
+pub struct CachedSimplyClient<A = SimplyClient> {
+    inner: A,
+    max_age: Duration,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+struct CacheEntry {
+    expires: Instant,
+    records: Vec<DnsRecord>,
+}
+
+impl<A: SimplyDnsApi> CachedSimplyClient<A> {
+    /// Wrap `inner`, keeping listed records for at most `max_age`.
+    pub fn new(inner: A, max_age: Duration) -> Self {
+        Self {
+            inner,
+            max_age,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The wrapped API, e.g. to call methods that aren't cached.
+    ///
+    /// Mutations made through it don't invalidate the cache; call
+    /// [`invalidate`](Self::invalidate) afterwards.
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    /// Drop the cached records of `domain`.
+    pub fn invalidate(&self, domain: &str) {
+        self.entries().remove(&domain.to_ascii_lowercase());
+    }
+
+    /// Drop all cached records.
+    pub fn clear(&self) {
+        self.entries().clear();
+    }
+
+    fn entries(&self) -> MutexGuard<'_, HashMap<String, CacheEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn cached(&self, domain: &str) -> Option<Vec<DnsRecord>> {
+        let key = domain.to_ascii_lowercase();
+        let mut entries = self.entries();
+        match entries.get(&key) {
+            Some(entry) if entry.expires > Instant::now() => Some(entry.records.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn store(&self, domain: &str, records: &[DnsRecord]) {
+        let lowest_ttl = records
+            .iter()
+            .map(|r| Duration::from_secs(r.ttl.into()))
+            .min();
+        let max_age = lowest_ttl.map_or(self.max_age, |ttl| ttl.min(self.max_age));
+        self.entries().insert(
+            domain.to_ascii_lowercase(),
+            CacheEntry {
+                expires: Instant::now() + max_age,
+                records: records.to_vec(),
+            },
+        );
+    }
+}
+
+impl<A: SimplyDnsApi> SimplyDnsApi for CachedSimplyClient<A> {
+    fn list_domains(&self) -> impl Future<Output = Result<Vec<String>, SimplyClientError>> + Send {
+        self.inner.list_domains()
+    }
+
+    async fn list_dns_records(&self, domain: &str) -> Result<Vec<DnsRecord>, SimplyClientError> {
+        if let Some(records) = self.cached(domain) {
+            return Ok(records);
+        }
+        let records = self.inner.list_dns_records(domain).await?;
+        self.store(domain, &records);
+        Ok(records)
+    }
+
+    async fn get_dns_record(
+        &self,
+        domain: &str,
+        record_id: &DnsRecordId,
+    ) -> Result<Option<DnsRecord>, SimplyClientError> {
+        if let Some(records) = self.cached(domain) {
+            return Ok(records.into_iter().find(|r| r.record_id == *record_id));
+        }
+        self.inner.get_dns_record(domain, record_id).await
+    }
+
+    async fn create_dns_record(
+        &self,
+        domain: &str,
+        req: CreateDnsRecordRequest,
+    ) -> Result<Vec<DnsRecordId>, SimplyClientError> {
+        let result = self.inner.create_dns_record(domain, req).await;
+        self.invalidate(domain);
+        result
+    }
+
+    async fn update_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+    ) -> Result<(), SimplyClientError> {
+        let result = self.inner.update_dns_record(domain, record_id, req).await;
+        self.invalidate(domain);
+        result
+    }
+
+    async fn delete_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+    ) -> Result<(), SimplyClientError> {
+        let result = self.inner.delete_dns_record(domain, record_id).await;
+        self.invalidate(domain);
+        result
+    }
+}
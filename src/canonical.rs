@@ -0,0 +1,274 @@
+//! Normalization of records for comparing them by meaning.
+//!
+//! The same record can be written many ways: `Mail.Example.com.` and
+//! `mail.example.com` are one MX target, `"v=spf1 " "-all"` and `v=spf1 -all`
+//! one TXT value, `2001:DB8:0:0::1` and `2001:db8::1` one address. Comparing
+//! the strings, a sync tool would "change" such records on every run.
+//! [`DnsRecord::canonicalize`] brings a record into one canonical form and
+//! [`records_equal_semantically`] compares two records in it; the zone diff of
+//! [`ZonePlan::compute`](crate::sync::ZonePlan::compute), the record sets of
+//! [`rrset`](crate::rrset), imports and duplicate checks on create compare
+//! data this way too.
+//!
+//! The canonical form:
+//! - names and record types: trimmed, names lower case without a trailing
+//!   dot and `@` for the apex, types upper case; within a zone, see
+//!   [`canonical_name_in`], relative to it;
+//! - A and AAAA data: the address as Rust prints it, e.g. IPv6 compressed;
+//! - CNAME, NS, PTR, MX, DNAME and ALIAS data: the lower case host name
+//!   without a trailing dot, `.` itself (a null MX) kept as it is;
+//! - SRV data: fields separated by single spaces, the target as a host name;
+//! - TXT and SPF data: quoted strings unquoted and joined as DNS joins them,
+//!   unquoted text trimmed; the case and inner spaces of text are meaningful
+//!   and kept;
+//! - CAA data: `flags tag "value"` with the tag lower case;
+//! - other data: fields separated by single spaces;
+//! - comments: trimmed, with an empty comment canonically absent.
+//!
+//! Example usage:
+//! ```rust
+//! use simply_dns::api::{DnsRecord, DnsRecordId};
+//! use simply_dns::canonical::records_equal_semantically;
+//!
+//! let record = |name: &str, data: &str| DnsRecord {
+//!     record_id: DnsRecordId::new(1),
+//!     name: name.to_string(),
+//!     ttl: 3600,
+//!     data: data.to_string(),
+//!     record_type: "CNAME".to_string(),
+//!     priority: None,
+//!     comment: None,
+//! };
+//! assert!(records_equal_semantically(
+//!     &record("WWW", "Example.com."),
+//!     &record("www", "example.com"),
+//! ));
+//! assert!(!records_equal_semantically(
+//!     &record("www", "example.com"),
+//!     &record("www", "example.net"),
+//! ));
+//! ```
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::api::DnsRecord;
+use crate::names::NameStyle;
+use crate::record_data::split_quoted;
+
+/// Record types whose data is a single host name.
+const HOST_NAME_TYPES: [&str; 6] = ["CNAME", "NS", "PTR", "MX", "DNAME", "ALIAS"];
+
+/// The canonical form of the record name `name`.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::canonical::canonical_name;
+///
+/// assert_eq!(canonical_name(" WWW. "), "www");
+/// assert_eq!(canonical_name(""), "@");
+/// ```
+pub fn canonical_name(name: &str) -> String {
+    match name.trim().trim_end_matches('.') {
+        "" => "@".to_string(),
+        name => name.to_ascii_lowercase(),
+    }
+}
+
+/// The canonical form of the record name `name` in the zone `domain`.
+///
+/// A fully-qualified name in the zone, written with its trailing dot, is
+/// made relative first, so `www.example.com.` and `WWW` are one name in
+/// `example.com`. Without the dot a name is relative, as in
+/// [`NameStyle::Relative`].
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::canonical::canonical_name_in;
+///
+/// assert_eq!(canonical_name_in("WWW.Example.com.", "example.com"), "www");
+/// assert_eq!(canonical_name_in("example.com.", "example.com"), "@");
+/// assert_eq!(canonical_name_in("www.example.net.", "example.com"), "www.example.net");
+/// ```
+pub fn canonical_name_in(name: &str, domain: &str) -> String {
+    canonical_name(&NameStyle::Relative.normalize(name.trim(), domain))
+}
+
+/// The canonical form of the `data` of a record of `record_type`.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::canonical::canonical_data;
+///
+/// assert_eq!(canonical_data("MX", "Mail.Example.com."), "mail.example.com");
+/// assert_eq!(canonical_data("AAAA", "2001:DB8:0:0::1"), "2001:db8::1");
+/// assert_eq!(canonical_data("TXT", r#""v=spf1 " "-all""#), "v=spf1 -all");
+/// assert_eq!(canonical_data("SRV", "10  5060 SIP.example.com."), "10 5060 sip.example.com");
+/// ```
+pub fn canonical_data(record_type: &str, data: &str) -> String {
+    let record_type = record_type.trim().to_ascii_uppercase();
+    let data = data.trim();
+    match record_type.as_str() {
+        "A" => data
+            .parse::<Ipv4Addr>()
+            .map_or_else(|_| data.to_string(), |ip| ip.to_string()),
+        "AAAA" => data
+            .parse::<Ipv6Addr>()
+            .map_or_else(|_| data.to_ascii_lowercase(), |ip| ip.to_string()),
+        t if HOST_NAME_TYPES.contains(&t) => host_name(data),
+        "SRV" => {
+            let mut fields: Vec<String> = data.split_whitespace().map(str::to_string).collect();
+            if let Some(target) = fields.last_mut() {
+                *target = host_name(target);
+            }
+            fields.join(" ")
+        }
+        "TXT" | "SPF" if data.starts_with('"') => match split_quoted(data) {
+            Some(strings) => strings.concat(),
+            None => data.to_string(),
+        },
+        "TXT" | "SPF" => data.to_string(),
+        "CAA" => match split_quoted(data).as_deref() {
+            Some([flags, tag, value]) => {
+                format!("{flags} {} \"{value}\"", tag.to_ascii_lowercase())
+            }
+            _ => collapse_whitespace(data),
+        },
+        _ => collapse_whitespace(data),
+    }
+}
+
+/// Whether `a` and `b` hold the same data for a record of `record_type`,
+/// compared in [canonical form](canonical_data).
+pub fn same_data(record_type: &str, a: &str, b: &str) -> bool {
+    a == b || canonical_data(record_type, a) == canonical_data(record_type, b)
+}
+
+/// Whether `a` and `b` are the same record, ignoring their IDs: the same
+/// name, type, data, TTL, priority and comment in canonical form.
+pub fn records_equal_semantically(a: &DnsRecord, b: &DnsRecord) -> bool {
+    let (a, b) = (a.canonicalize(), b.canonicalize());
+    a.name == b.name
+        && a.record_type == b.record_type
+        && a.data == b.data
+        && a.ttl == b.ttl
+        && a.priority == b.priority
+        && a.comment == b.comment
+}
+
+impl DnsRecord {
+    /// The record in canonical form, see [`canonical`](crate::canonical).
+    ///
+    /// The ID, TTL and priority are kept as they are.
+    pub fn canonicalize(&self) -> DnsRecord {
+        DnsRecord {
+            record_id: self.record_id,
+            name: canonical_name(&self.name),
+            ttl: self.ttl,
+            data: canonical_data(&self.record_type, &self.data),
+            record_type: self.record_type.trim().to_ascii_uppercase(),
+            priority: self.priority,
+            comment: self
+                .comment
+                .as_deref()
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(str::to_string),
+        }
+    }
+}
+
+fn host_name(name: &str) -> String {
+    match name.trim() {
+        "." => ".".to_string(),
+        name => name.trim_end_matches('.').to_ascii_lowercase(),
+    }
+}
+
+fn collapse_whitespace(data: &str) -> String {
+    data.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn txt_strings_are_unquoted_and_joined() {
+        assert_eq!(
+            canonical_data("TXT", r#""v=spf1 mx -all""#),
+            "v=spf1 mx -all"
+        );
+        assert_eq!(
+            canonical_data("txt", r#" "v=spf1 " "mx"  " -all" "#),
+            "v=spf1 mx -all"
+        );
+        assert_eq!(canonical_data("SPF", r#""v=spf1 -all""#), "v=spf1 -all");
+        assert_eq!(
+            canonical_data("TXT", r#""say \"hi\"" "\\o/""#),
+            r#"say "hi"\o/"#
+        );
+        assert_eq!(canonical_data("TXT", r#""""#), "");
+        assert!(same_data("TXT", r#""a" "b""#, "ab"));
+    }
+
+    #[test]
+    fn unquoted_txt_keeps_case_and_inner_spaces() {
+        assert_eq!(canonical_data("TXT", "  Hello  World  "), "Hello  World");
+        assert!(!same_data("TXT", "Hello", "hello"));
+        assert!(!same_data("TXT", "a b", "a  b"));
+        // An unterminated quote isn't a quoted string.
+        assert_eq!(canonical_data("TXT", r#""open"#), r#""open"#);
+    }
+
+    #[test]
+    fn mx_targets_are_host_names() {
+        assert_eq!(
+            canonical_data("MX", " Mail.Example.COM. "),
+            "mail.example.com"
+        );
+        assert_eq!(canonical_data("mx", "mail.example.com"), "mail.example.com");
+        // A null MX (RFC 7505) keeps its dot.
+        assert_eq!(canonical_data("MX", " . "), ".");
+        assert!(same_data("MX", "Mail.example.com.", "mail.EXAMPLE.com"));
+        assert!(!same_data("MX", ".", ""));
+    }
+
+    #[test]
+    fn other_types() {
+        assert_eq!(canonical_data("A", " 192.0.2.1 "), "192.0.2.1");
+        assert_eq!(canonical_data("AAAA", "2001:DB8::0:1"), "2001:db8::1");
+        assert_eq!(canonical_data("AAAA", "not-an-address"), "not-an-address");
+        assert_eq!(
+            canonical_data("CAA", r#"0  ISSUE "letsencrypt.org""#),
+            r#"0 issue "letsencrypt.org""#
+        );
+        assert_eq!(
+            canonical_data("SRV", "10 5060 SIP.example.com."),
+            "10 5060 sip.example.com"
+        );
+        assert_eq!(canonical_data("TLSA", "3  1 1\tABCD"), "3 1 1 ABCD");
+    }
+
+    #[test]
+    fn records_compare_in_canonical_form() {
+        let record = |name: &str, data: &str, comment: Option<&str>| DnsRecord {
+            record_id: 1.into(),
+            name: name.to_string(),
+            ttl: 3600,
+            data: data.to_string(),
+            record_type: "MX".to_string(),
+            priority: Some(10),
+            comment: comment.map(str::to_string),
+        };
+        assert!(records_equal_semantically(
+            &record("", "Mail.example.com.", Some("  ")),
+            &record("@", "mail.example.com", None),
+        ));
+        let mut other = record("@", "mail.example.com", None);
+        other.priority = Some(20);
+        assert!(!records_equal_semantically(
+            &record("@", "mail.example.com", None),
+            &other
+        ));
+    }
+}
@@ -1,10 +1,50 @@
-use reqwest::Client;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Method, Request, RequestBuilder, Response, StatusCode, header};
+use serde::Serialize;
 use thiserror::Error;
 
 use crate::api::{
-    CreateDnsRecordRequest, CreateDnsRecordResponse, DnsRecord, DnsRecordId, GeneralResponse,
-    ListDnsRecordsResponse, UpdateDnsRecordRequest,
+    CreateDnsRecordRequest, CreateDnsRecordResponse, DnsRecord, DnsRecordId, DnssecDsResponse,
+    DnssecKey, DnssecKeysResponse, DsRecord, GeneralResponse, ListDnsRecordsResponse,
+    ListProductsResponse, Product, UpdateDnsRecordRequest, UpdatePatch, UpsertOptions,
+    UpsertOutcome, ZoneRecordRef,
+};
+use crate::approval::{ApprovalHook, ApprovalHooks};
+use crate::audit::{AuditContext, AuditHook, AuditHooks};
+use crate::auth::{AuthScheme, BasicAuth};
+use crate::canonical::same_data;
+use crate::credentials::{Credentials, CredentialsError, CredentialsProvider};
+use crate::endpoint::{self, ApiVersion, Endpoint};
+use crate::freeze::FreezeWindows;
+use crate::hosts;
+use crate::lock::DomainLocks;
+use crate::mail::{
+    CreateMailAliasRequest, CreateMailAliasResponse, ListMailAliasesResponse, MailAlias,
+    MailAliasId,
 };
+use crate::middleware::{Exchange, Middleware, Middlewares};
+use crate::names::{self, NameStyle};
+use crate::progress::{Operation, Progress, ProgressEvent, ProgressSink, SharedSink};
+use crate::quota::Quota;
+use crate::record_data::SUPPORTED_RECORD_TYPES;
+use crate::response::{
+    ApiResponse, LenientRecords, MalformedRecord, ResponseMeta, ResponseParsing,
+};
+use crate::retry::{TRANSIENT_STATUSES, transient_error};
+use crate::sync::tag_comment;
+use crate::validate::NameStrictness;
+use crate::{ApiBudget, Priority, RetryPolicy};
+
+/// Base URL of version 2 of the Simply.com API.
+const DEFAULT_API_ROOT: &str = "https://api.simply.com";
+/// How many times a request answered with 429 Too Many Requests is retried.
+const MAX_THROTTLED_RETRIES: u32 = 3;
+/// Wait used after a 429 response that has no usable `Retry-After` header.
+const DEFAULT_THROTTLE_DELAY: Duration = Duration::from_secs(1);
 
 /// Error type for the Simply.com DNS API client.
 ///
@@ -14,12 +54,231 @@ pub enum SimplyClientError {
     /// There was an error with the HTTP request (network, invalid response, etc.).
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
-    /// The response could not be parsed from JSON. Usually returned if the Simply.com API returns an unexpected or malformed JSON body.
+    /// JSON could not be parsed or written, e.g. a state file. Response bodies
+    /// that can't be parsed are reported as [`UnexpectedResponse`](Self::UnexpectedResponse).
     #[error("JSON deserialization error: {0}")]
     Json(#[from] serde_json::Error),
-    /// The Simply.com API returned an error status or message. The first value is the status code, the second is the message returned by the API.
-    #[error("API error: {0} ({1})")]
-    Api(u32, String),
+    /// A successful response had a body that doesn't match the expected schema.
+    ///
+    /// Keeps the raw body, so API schema changes can be diagnosed; see
+    /// [`ResponseParsing::Lenient`](crate::response::ResponseParsing::Lenient)
+    /// for tolerating malformed records.
+    #[error("unexpected response body (HTTP {status}): {source}")]
+    UnexpectedResponse {
+        /// The HTTP status code.
+        status: u16,
+        /// The raw response body.
+        body: String,
+        /// Why the body couldn't be parsed.
+        #[source]
+        source: serde_json::Error,
+    },
+    /// The credentials were rejected (HTTP 401 or 403).
+    #[error("unauthorized: {message}")]
+    Unauthorized {
+        /// The HTTP status code, 401 or 403.
+        status: u16,
+        /// The message returned by the API.
+        message: String,
+    },
+    /// The domain or record doesn't exist (HTTP 404).
+    #[error("not found: {message}")]
+    NotFound {
+        /// The message returned by the API.
+        message: String,
+    },
+    /// The API is throttling the client (HTTP 429) and retries were exhausted or disabled.
+    #[error("rate limited, retry after {retry_after:?}")]
+    RateLimited {
+        /// The delay requested by the `Retry-After` header, if any.
+        retry_after: Option<Duration>,
+    },
+    /// The request was rejected as invalid, by the API (HTTP 400 or 422) or by
+    /// client-side [validation](crate::validate).
+    #[error("validation error: {message}")]
+    Validation {
+        /// The HTTP status code, 400 or 422, or `None` if the request was
+        /// rejected client-side.
+        status: Option<u16>,
+        /// The offending field, if the API named one.
+        field: Option<String>,
+        /// What was invalid, as returned by the API.
+        message: String,
+    },
+    /// The API failed with a server error (HTTP 5xx).
+    #[error("server error {status}: {body}")]
+    Server {
+        /// The HTTP status code.
+        status: u16,
+        /// The raw response body.
+        body: String,
+    },
+    /// The API returned another error status.
+    #[error("API error: {status} ({message})")]
+    Api {
+        /// The status code.
+        status: u16,
+        /// The message returned by the API.
+        message: String,
+    },
+    /// An I/O error, e.g. while starting the runtime of the blocking client.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// The client could not be built from the given configuration.
+    #[error("invalid client configuration: {0}")]
+    Config(String),
+    /// The record type is not accepted by the API, see [`SimplyClient::supported_record_types`].
+    #[error("unsupported record type: {0}")]
+    UnsupportedRecordType(String),
+    /// A conditional update or delete found the record changed, see
+    /// [`SimplyClient::update_dns_record_if`] and [`SimplyClient::delete_dns_record_if`].
+    #[error("conflict: record {record_id} was {}", if .current.is_some() { "modified" } else { "deleted" })]
+    Conflict {
+        /// The record that was to be updated.
+        record_id: DnsRecordId,
+        /// The record as it is now, or `None` if it was deleted.
+        current: Option<Box<DnsRecord>>,
+    },
+    /// A [`ZoneRecordRef`] was used with another domain than the one it belongs to.
+    #[error("record {record} doesn't belong to {domain}")]
+    WrongZone {
+        /// The record that was addressed.
+        record: ZoneRecordRef,
+        /// The domain the call was made for.
+        domain: String,
+    },
+    /// A create found an identical record, see [`CreateMode::FailIfExists`].
+    #[error("record {record_id} already exists")]
+    AlreadyExists {
+        /// The existing record.
+        record_id: DnsRecordId,
+    },
+    /// A write was refused during a [freeze window](crate::freeze), see
+    /// [`SimplyClientBuilder::freeze_override`].
+    #[error("change freeze: {window}")]
+    Frozen {
+        /// The active window, as it is displayed.
+        window: String,
+    },
+    /// A change was refused because it is outside the client's
+    /// [quota](crate::quota), see [`SimplyClientBuilder::quota`].
+    #[error("quota exceeded: {message}")]
+    QuotaExceeded {
+        /// What the change would exceed.
+        message: String,
+    },
+}
+
+impl SimplyClientError {
+    /// The status code of an API error, if this is one.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            SimplyClientError::NotFound { .. } => Some(404),
+            SimplyClientError::RateLimited { .. } => Some(429),
+            SimplyClientError::Validation { status, .. } => *status,
+            SimplyClientError::Unauthorized { status, .. }
+            | SimplyClientError::Server { status, .. }
+            | SimplyClientError::Api { status, .. } => Some(*status),
+            SimplyClientError::Http(e) => e.status().map(|s| s.as_u16()),
+            _ => None,
+        }
+    }
+
+    /// Whether the domain or record doesn't exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, SimplyClientError::NotFound { .. })
+    }
+
+    /// Whether a conditional update or delete found the record changed.
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, SimplyClientError::Conflict { .. })
+    }
+
+    /// Whether the credentials were rejected.
+    pub fn is_auth(&self) -> bool {
+        match self {
+            SimplyClientError::Unauthorized { .. } => true,
+            SimplyClientError::Http(e) => {
+                e.status().is_some_and(|s| matches!(s.as_u16(), 401 | 403))
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the same request may succeed if sent again later.
+    ///
+    /// True for throttling, transient server errors (500, 502, 503 and 504),
+    /// timeouts and network failures; false for errors that would repeat, such
+    /// as rejected credentials, missing records or invalid requests. This is
+    /// the classification the built-in [`RetryPolicy`] uses by default.
+    ///
+    /// Example usage:
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use simply_dns::SimplyClient;
+    ///
+    /// # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+    /// let client = SimplyClient::new("account", "api_key");
+    /// let records = loop {
+    ///     match client.list_dns_records("example.com").await {
+    ///         Err(e) if e.is_retryable() => tokio::time::sleep(Duration::from_secs(5)).await,
+    ///         result => break result?,
+    ///     }
+    /// };
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SimplyClientError::RateLimited { .. } | SimplyClientError::Io(_) => true,
+            SimplyClientError::Server { status, .. } => TRANSIENT_STATUSES.contains(status),
+            SimplyClientError::Api { status, .. } => *status == 408,
+            SimplyClientError::Http(e) => {
+                transient_error(e)
+                    || e.status().is_some_and(|s| {
+                        s.as_u16() == 429 || TRANSIENT_STATUSES.contains(&s.as_u16())
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    /// Build the error for an unsuccessful response.
+    async fn from_response(res: Response) -> Self {
+        let http_status = res.status().as_u16();
+        let retry_after = retry_after(res.headers());
+        let body = res.text().await.unwrap_or_default();
+        Self::from_body(http_status, retry_after, body)
+    }
+
+    /// Build the error for a response with status `http_status` and `body`.
+    ///
+    /// The status is taken from the JSON body if it has one, as the API reports
+    /// it there too, falling back to the HTTP status.
+    pub(crate) fn from_body(http_status: u16, retry_after: Option<Duration>, body: String) -> Self {
+        let parsed: Option<GeneralResponse> = serde_json::from_str(&body).ok();
+        let status = parsed
+            .as_ref()
+            .and_then(|r| r.status)
+            .filter(|s| (400..600).contains(s))
+            .unwrap_or(http_status);
+        let (field, message) = match parsed {
+            Some(r) => (r.field, r.message.unwrap_or_else(|| body.clone())),
+            None => (None, body.clone()),
+        };
+        match status {
+            401 | 403 => SimplyClientError::Unauthorized { status, message },
+            404 => SimplyClientError::NotFound { message },
+            429 => SimplyClientError::RateLimited { retry_after },
+            400 | 422 => SimplyClientError::Validation {
+                status: Some(status),
+                field,
+                message,
+            },
+            500..=599 => SimplyClientError::Server { status, body },
+            _ => SimplyClientError::Api { status, message },
+        }
+    }
 }
 
 /// Async client for the Simply.com DNS API.
@@ -29,14 +288,649 @@ pub enum SimplyClientError {
 ///
 /// Example usage:
 /// ```rust
+/// use simply_dns::SimplyClient;
+///
 /// let client = SimplyClient::new("account", "api_key");
 /// // ...
 /// ```
+#[derive(Clone)]
 pub struct SimplyClient {
-    account: String,
-    api_key: String,
+    auth: Arc<dyn AuthScheme>,
     base_url: String,
+    api_version: ApiVersion,
     client: Client,
+    timeout: Option<Duration>,
+    retry: Option<RetryPolicy>,
+    budget: Option<ApiBudget>,
+    priority: Priority,
+    record_types: Vec<String>,
+    dry_run: Option<Arc<Mutex<Vec<DryRunRequest>>>>,
+    name_style: NameStyle,
+    audit: AuditHooks,
+    approval: ApprovalHooks,
+    freeze: Option<Arc<FreezeWindows>>,
+    freeze_override: Option<String>,
+    middleware: Middlewares,
+    progress: SharedSink,
+    skip_validation: bool,
+    name_strictness: NameStrictness,
+    options: CallOptions,
+    observability: Observability,
+    owner: Option<String>,
+    quota: Option<Arc<Quota>>,
+    response_parsing: ResponseParsing,
+    locks: Arc<DomainLocks>,
+    held_locks: Vec<String>,
+}
+
+/// Shows the configuration, with the API key redacted by the [`AuthScheme`].
+///
+/// ```rust
+/// use simply_dns::SimplyClient;
+///
+/// let client = SimplyClient::new("S123456", "0123456789abcdef");
+/// let debug = format!("{client:?}");
+/// assert!(debug.contains("S123456"));
+/// assert!(!debug.contains("0123456789abcdef"));
+/// ```
+impl fmt::Debug for SimplyClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimplyClient")
+            .field("auth", &self.auth)
+            .field("base_url", &self.base_url)
+            .field("api_version", &self.api_version())
+            .field("timeout", &self.timeout)
+            .field("retry", &self.retry)
+            .field("budget", &self.budget)
+            .field("dry_run", &self.is_dry_run())
+            .field("name_style", &self.name_style)
+            .field("options", &self.options)
+            .field("owner", &self.owner)
+            .field("quota", &self.quota)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Options applied to every request of a client derived with [`SimplyClient::with_options`].
+///
+/// Example usage:
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use simply_dns::{CallOptions, SimplyClient};
+///
+/// # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+/// let client = SimplyClient::new("account", "api_key");
+/// let records = client
+///     .with_options(CallOptions {
+///         timeout: Some(Duration::from_secs(2)),
+///         ..CallOptions::default()
+///     })
+///     .list_dns_records("example.com")
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallOptions {
+    /// How long each HTTP request may take, overriding [`SimplyClientBuilder::timeout`].
+    pub timeout: Option<Duration>,
+    /// Sent as the `Idempotency-Key` header, for proxies and gateways that
+    /// deduplicate requests. The Simply.com API itself doesn't, so setting it
+    /// doesn't make retrying record creation safe; see `create_mode` for that.
+    pub idempotency_key: Option<String>,
+    /// What creating a record that already exists does.
+    pub create_mode: CreateMode,
+    /// The API version to call, overriding [`SimplyClientBuilder::api_version`].
+    pub api_version: Option<ApiVersion>,
+    /// How unset optional fields of record bodies are sent.
+    pub optional_fields: OptionalFields,
+}
+
+/// What [`SimplyClient::create_dns_record`] does when a record with the same
+/// name, type and data already exists, see [`SimplyClient::with_create_mode`].
+///
+/// Checking lists the zone first, which costs a request per create. A record
+/// created by someone else between the check and the create can still be
+/// duplicated; the window is one request long.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CreateMode {
+    /// Create the record without checking, as the API does.
+    #[default]
+    AlwaysCreate,
+    /// Return the IDs of the existing record(s) instead of creating another,
+    /// so that retrying a create whose response was lost is safe.
+    SkipIfExists,
+    /// Fail with [`SimplyClientError::AlreadyExists`].
+    FailIfExists,
+}
+
+/// How the `None` fields of a create or update body, such as an unset TTL,
+/// are sent, see [`SimplyClient::with_optional_fields`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OptionalFields {
+    /// Send them as `null`.
+    #[default]
+    Null,
+    /// Leave them out of the body, for endpoints that reject `null`.
+    Omit,
+}
+
+/// Whether a client may do anything besides calling the Simply.com API, see
+/// [`SimplyClientBuilder::observability`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Observability {
+    /// Logging, audit hooks and propagation checks are available.
+    #[default]
+    On,
+    /// The client only talks to the Simply.com API.
+    Off,
+}
+
+/// A mutating request that a dry-run client recorded instead of sending.
+///
+/// See [`SimplyClientBuilder::dry_run`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DryRunRequest {
+    /// The HTTP method, e.g. "POST".
+    pub method: String,
+    /// The request URL.
+    pub url: String,
+    /// The JSON body, if the request has one.
+    pub body: Option<serde_json::Value>,
+}
+
+/// Builder for [`SimplyClient`], created with [`SimplyClient::builder`].
+///
+/// Either pass a fully configured [`reqwest::Client`] with [`http_client`](Self::http_client),
+/// or let the builder create one from [`user_agent`](Self::user_agent) and [`proxy`](Self::proxy).
+///
+/// Example usage:
+/// ```rust
+/// use std::time::Duration;
+/// use simply_dns::{RetryPolicy, SimplyClient};
+///
+/// let client = SimplyClient::builder()
+///     .credentials("account", "api_key")
+///     .timeout(Duration::from_secs(10))
+///     .retry(RetryPolicy::default())
+///     .build()?;
+/// # Ok::<(), simply_dns::SimplyClientError>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct SimplyClientBuilder {
+    credentials: Option<Credentials>,
+    auth: Option<Arc<dyn AuthScheme>>,
+    base_url: Option<String>,
+    api_version: Option<ApiVersion>,
+    http_client: Option<Client>,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    proxy: Option<reqwest::Proxy>,
+    #[cfg(all(
+        not(target_arch = "wasm32"),
+        any(feature = "rustls-tls", feature = "native-tls")
+    ))]
+    tls: crate::tls::TlsSettings,
+    retry: Option<RetryPolicy>,
+    budget: Option<ApiBudget>,
+    record_types: Vec<String>,
+    dry_run: bool,
+    name_style: NameStyle,
+    audit: AuditHooks,
+    approval: ApprovalHooks,
+    freeze: Option<FreezeWindows>,
+    freeze_override: Option<String>,
+    middleware: Middlewares,
+    progress: SharedSink,
+    skip_validation: bool,
+    name_strictness: NameStrictness,
+    observability: Observability,
+    owner: Option<String>,
+    quota: Option<Quota>,
+    response_parsing: ResponseParsing,
+    #[cfg(not(target_arch = "wasm32"))]
+    domain_lock_dir: Option<std::path::PathBuf>,
+    #[cfg(not(target_arch = "wasm32"))]
+    domain_lock_stale_after: Option<Duration>,
+}
+
+impl SimplyClientBuilder {
+    /// Set the account identifier and API key used for authentication.
+    pub fn credentials(mut self, account: impl Into<String>, api_key: impl Into<String>) -> Self {
+        self.credentials = Some(Credentials::new(account, api_key));
+        self
+    }
+
+    /// Authenticate with the credentials `provider` finds, e.g. a
+    /// [`CredentialsChain`](crate::credentials::CredentialsChain).
+    ///
+    /// The provider is asked right away; its error is returned if it has none.
+    pub fn credentials_provider(
+        mut self,
+        provider: &impl CredentialsProvider,
+    ) -> Result<Self, CredentialsError> {
+        self.credentials = Some(provider.credentials()?);
+        Ok(self)
+    }
+
+    /// Authenticate requests with `scheme` instead of basic authentication
+    /// with the [credentials](Self::credentials), which are then not needed.
+    pub fn auth_scheme(mut self, scheme: impl AuthScheme + 'static) -> Self {
+        self.auth = Some(Arc::new(scheme));
+        self
+    }
+
+    /// Use `base_url` instead of `https://api.simply.com/2/`, e.g. for a test server or proxy.
+    ///
+    /// `base_url` is the root of the client's [API version](Self::api_version).
+    /// Calls made with another version replace its last path segment if it
+    /// names the client's version, e.g. `/2/`, and append the version otherwise.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Call `version` of the API instead of version 2.
+    ///
+    /// Without a [`base_url`](Self::base_url), requests go to
+    /// `https://api.simply.com/<version>/`. Single calls can use another
+    /// version with [`SimplyClient::with_api_version`], e.g. for beta endpoints.
+    pub fn api_version(mut self, version: ApiVersion) -> Self {
+        self.api_version = Some(version);
+        self
+    }
+
+    /// Send requests with `client`, e.g. to use custom TLS settings or connection pooling.
+    ///
+    /// Can't be combined with [`user_agent`](Self::user_agent) or [`proxy`](Self::proxy);
+    /// configure those on `client` instead.
+    pub fn http_client(mut self, client: Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Fail a request attempt that takes longer than `timeout`. Each retry gets the full timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send `user_agent` as the `User-Agent` header instead of `simply-dns/<version>`.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Send all requests through `proxy`. Not available on wasm32, where the
+    /// browser or runtime decides.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Trust the root certificates of the PEM bundle `pem` as well, e.g. a
+    /// private authority in front of the API. Also trusted when pinning.
+    /// An invalid bundle makes [`build`](Self::build) fail.
+    #[cfg(all(
+        not(target_arch = "wasm32"),
+        any(feature = "rustls-tls", feature = "native-tls")
+    ))]
+    pub fn root_certificates_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.tls.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Whether to trust the root certificates built into the TLS backend, or
+    /// for `native-tls` those of the system. Trusted by default; without them,
+    /// only the [added ones](Self::root_certificates_pem) are.
+    #[cfg(all(
+        not(target_arch = "wasm32"),
+        any(feature = "rustls-tls", feature = "native-tls")
+    ))]
+    pub fn tls_built_in_root_certs(mut self, enabled: bool) -> Self {
+        self.tls.built_in_roots = Some(enabled);
+        self
+    }
+
+    /// Refuse to connect with a TLS version older than `version`.
+    #[cfg(all(
+        not(target_arch = "wasm32"),
+        any(feature = "rustls-tls", feature = "native-tls")
+    ))]
+    pub fn min_tls_version(mut self, version: reqwest::tls::Version) -> Self {
+        self.tls.min_version = Some(version);
+        self
+    }
+
+    /// Only connect to servers whose certificate chain contains `pin`, see
+    /// [`tls`](crate::tls). Can be called several times, e.g. to pin the
+    /// next certificate ahead of a rotation; any of the pins is accepted.
+    ///
+    /// Pinning always uses rustls, with rustls' built-in root certificates.
+    #[cfg(all(not(target_arch = "wasm32"), feature = "tls-pinning"))]
+    pub fn pin_certificate(mut self, pin: crate::tls::CertificatePin) -> Self {
+        self.tls.pins.push(pin);
+        self
+    }
+
+    /// Retry transient failures according to `policy`. Requests are not retried by default.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Limit the client to `requests_per_second` on average, allowing bursts of up to `burst` requests.
+    ///
+    /// Limiting also enables automatic handling of 429 Too Many Requests responses:
+    /// the request is retried after the delay given by the `Retry-After` header,
+    /// and the other requests of this client are held back in the meantime.
+    pub fn rate_limit(mut self, requests_per_second: f64, burst: u32) -> Self {
+        self.budget = Some(ApiBudget::with_reserve(requests_per_second, burst, 0));
+        self
+    }
+
+    /// Draw requests from `budget`, which may be shared with other clients.
+    ///
+    /// Like [`rate_limit`](Self::rate_limit), this enables handling of 429 responses.
+    /// Replaces a limit set with [`rate_limit`](Self::rate_limit).
+    pub fn budget(mut self, budget: ApiBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Also accept `record_types` in addition to [`SUPPORTED_RECORD_TYPES`].
+    ///
+    /// Use this for types Simply.com added after this version of the crate was released.
+    pub fn record_types<I, S>(mut self, record_types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.record_types
+            .extend(record_types.into_iter().map(Into::into));
+        self
+    }
+
+    /// Record create, update and delete requests instead of sending them.
+    ///
+    /// Mutating methods then return success without calling the API; creates
+    /// return no record IDs. Read requests are still sent, so plans and upserts
+    /// work against the live zone. The recorded requests are available from
+    /// [`SimplyClient::dry_run_requests`].
+    pub fn dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// Register `middleware` to see every HTTP request this client sends.
+    ///
+    /// Can be called several times; middleware runs in registration order. See
+    /// [`middleware`](crate::middleware) for when it is called.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.0.push(Arc::new(middleware));
+        self
+    }
+
+    /// Register `hook` to be told about every record this client changes.
+    ///
+    /// Can be called several times; hooks run in registration order. See
+    /// [`AuditHook`] for when hooks are called.
+    pub fn audit_hook(mut self, hook: impl AuditHook + 'static) -> Self {
+        self.audit.0.push(Arc::new(hook));
+        self
+    }
+
+    /// Register `hook` to approve every plan before this client applies it.
+    ///
+    /// Can be called several times; every hook must approve. See
+    /// [`approval`](crate::approval) for which plans are checked.
+    pub fn approval_hook(mut self, hook: impl ApprovalHook + 'static) -> Self {
+        self.approval.0.push(Arc::new(hook));
+        self
+    }
+
+    /// Refuse to change records while one of `windows` is active, see
+    /// [`freeze`](crate::freeze).
+    pub fn freeze_windows(mut self, windows: FreezeWindows) -> Self {
+        self.freeze = Some(windows);
+        self
+    }
+
+    /// Change records during freeze windows anyway, for `reason`, which is
+    /// passed to [audit hooks](AuditHook) with the changes made in a window.
+    pub fn freeze_override(mut self, reason: impl Into<String>) -> Self {
+        self.freeze_override = Some(reason.into());
+        self
+    }
+
+    /// Register `sink` to be told about the progress of long-running operations.
+    ///
+    /// Replaces a sink registered before. See [`progress`](crate::progress)
+    /// for the operations that report progress.
+    pub fn progress_sink(mut self, sink: impl ProgressSink + 'static) -> Self {
+        self.progress = SharedSink(Some(Arc::new(sink)));
+        self
+    }
+
+    /// Tag every record this client creates as owned by `owner`.
+    ///
+    /// The tag is appended to the record's comment, so
+    /// [`cleanup_owned`](SimplyClient::cleanup_owned) can later remove exactly
+    /// the records this client created. See [`ownership`](crate::ownership).
+    pub fn owner(mut self, owner: impl Into<String>) -> Self {
+        self.owner = Some(owner.into());
+        self
+    }
+
+    /// Refuse to create, change or delete records outside `quota`, see
+    /// [`quota`](crate::quota).
+    pub fn quota(mut self, quota: Quota) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    /// Choose whether helper methods take and return zone-relative or fully-qualified names.
+    ///
+    /// Affects [`upsert_dns_record`](SimplyClient::upsert_dns_record),
+    /// [`find_records`](SimplyClient::find_records) and
+    /// [`delete_records_matching`](SimplyClient::delete_records_matching); the
+    /// plain record methods always use the API's relative names. Defaults to
+    /// [`NameStyle::Relative`].
+    pub fn name_style(mut self, style: NameStyle) -> Self {
+        self.name_style = style;
+        self
+    }
+
+    /// How strictly record names are checked by [`validate_records`](Self::validate_records).
+    ///
+    /// Defaults to [`NameStrictness::Pragmatic`]; use [`NameStrictness::Off`]
+    /// for zones with names the checks reject.
+    pub fn name_strictness(mut self, strictness: NameStrictness) -> Self {
+        self.name_strictness = strictness;
+        self
+    }
+
+    /// How record lists with malformed records are handled.
+    ///
+    /// Defaults to [`ResponseParsing::Strict`]. With [`ResponseParsing::Lenient`],
+    /// [`list_dns_records`](SimplyClient::list_dns_records), the record stream
+    /// and every helper built on them skip records that don't match the
+    /// expected schema, so a change in the API doesn't break whole calls.
+    pub fn response_parsing(mut self, parsing: ResponseParsing) -> Self {
+        self.response_parsing = parsing;
+        self
+    }
+
+    /// Also hold a lock file per domain in `dir` while changing a zone, so
+    /// processes sharing the directory don't interleave their changes, see
+    /// [`lock`](crate::lock). Not available on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn domain_lock_dir(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.domain_lock_dir = Some(dir.into());
+        self
+    }
+
+    /// Take over lock files older than `age`, left behind by a crashed
+    /// process, instead of after [`DEFAULT_LOCK_STALE_AFTER`](crate::lock::DEFAULT_LOCK_STALE_AFTER).
+    ///
+    /// Set it above the longest time a zone change takes, or two processes
+    /// may change the zone at once.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn domain_lock_stale_after(mut self, age: Duration) -> Self {
+        self.domain_lock_stale_after = Some(age);
+        self
+    }
+
+    /// Switch off everything the client does besides calling the Simply.com API.
+    ///
+    /// With [`Observability::Off`] the client emits no `tracing` events, and
+    /// helpers built on it make no network calls of their own: e.g.
+    /// [`RecordRotation`](crate::rotation::RecordRotation) doesn't query DNS
+    /// servers for propagation and removes the old address right away.
+    /// Building fails if audit hooks or middleware are registered, as they may
+    /// write to disk or call other services.
+    /// The client never spawns background tasks. Defaults to [`Observability::On`].
+    pub fn observability(mut self, observability: Observability) -> Self {
+        self.observability = observability;
+        self
+    }
+
+    /// Choose whether records are checked client-side before they are sent.
+    ///
+    /// Enabled by default: creating or updating a record that fails
+    /// [`validate_record`](crate::validate::validate_record) returns
+    /// [`SimplyClientError::Validation`] without calling the API. Disable it
+    /// if the API accepts records these checks reject.
+    pub fn validate_records(mut self, enabled: bool) -> Self {
+        self.skip_validation = !enabled;
+        self
+    }
+
+    /// Build the client.
+    ///
+    /// Returns [`SimplyClientError::Config`] if neither credentials nor an auth scheme were set, the rate limit
+    /// is not positive, a root certificate is invalid, or a custom HTTP client is combined with a
+    /// user agent, proxy or TLS settings.
+    pub fn build(self) -> Result<SimplyClient, SimplyClientError> {
+        let auth = match (self.auth, self.credentials) {
+            (Some(auth), _) => auth,
+            (None, Some(credentials)) => {
+                Arc::new(BasicAuth::new(credentials)) as Arc<dyn AuthScheme>
+            }
+            (None, None) => {
+                return Err(SimplyClientError::Config(
+                    "credentials are required".to_string(),
+                ));
+            }
+        };
+        if self.observability == Observability::Off
+            && !(self.audit.0.is_empty() && self.middleware.0.is_empty())
+        {
+            return Err(SimplyClientError::Config(
+                "audit hooks and middleware can't be used with observability off".to_string(),
+            ));
+        }
+        if let Some(budget) = &self.budget {
+            let rate = budget.requests_per_second();
+            if !(rate.is_finite() && rate > 0.0) {
+                return Err(SimplyClientError::Config(format!(
+                    "requests_per_second must be positive, got {rate}"
+                )));
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        let proxy = self.proxy;
+        #[cfg(target_arch = "wasm32")]
+        let proxy = None::<()>;
+        #[cfg(all(
+            not(target_arch = "wasm32"),
+            any(feature = "rustls-tls", feature = "native-tls")
+        ))]
+        let tls = self.tls;
+        #[cfg(all(
+            not(target_arch = "wasm32"),
+            any(feature = "rustls-tls", feature = "native-tls")
+        ))]
+        let custom_tls = tls.is_customized();
+        #[cfg(not(all(
+            not(target_arch = "wasm32"),
+            any(feature = "rustls-tls", feature = "native-tls")
+        )))]
+        let custom_tls = false;
+        let client = match self.http_client {
+            Some(_) if self.user_agent.is_some() || proxy.is_some() || custom_tls => {
+                return Err(SimplyClientError::Config(
+                    "user_agent, proxy and TLS settings must be set on the custom HTTP client"
+                        .to_string(),
+                ));
+            }
+            Some(client) => client,
+            None => {
+                let user_agent = self.user_agent.unwrap_or_else(|| {
+                    concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")).to_string()
+                });
+                let mut builder = Client::builder().user_agent(user_agent);
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(proxy) = proxy {
+                    builder = builder.proxy(proxy);
+                }
+                #[cfg(all(
+                    not(target_arch = "wasm32"),
+                    any(feature = "rustls-tls", feature = "native-tls")
+                ))]
+                if custom_tls {
+                    builder = tls.apply(builder)?;
+                }
+                builder.build()?
+            }
+        };
+        let api_version = self.api_version.unwrap_or_default();
+        #[cfg(not(target_arch = "wasm32"))]
+        let locks =
+            DomainLocks::with_lock_files(self.domain_lock_dir, self.domain_lock_stale_after);
+        #[cfg(target_arch = "wasm32")]
+        let locks = DomainLocks::default();
+        Ok(SimplyClient {
+            auth,
+            base_url: self
+                .base_url
+                .unwrap_or_else(|| format!("{DEFAULT_API_ROOT}/{}/", api_version.path())),
+            api_version,
+            client,
+            timeout: self.timeout,
+            retry: self.retry,
+            budget: self.budget,
+            priority: Priority::default(),
+            record_types: SUPPORTED_RECORD_TYPES
+                .iter()
+                .map(|t| t.to_string())
+                .chain(
+                    self.record_types
+                        .into_iter()
+                        .map(|t| t.to_ascii_uppercase()),
+                )
+                .collect(),
+            dry_run: self.dry_run.then(Default::default),
+            name_style: self.name_style,
+            audit: self.audit,
+            approval: self.approval,
+            freeze: self.freeze.map(Arc::new),
+            freeze_override: self.freeze_override,
+            middleware: self.middleware,
+            progress: self.progress,
+            skip_validation: self.skip_validation,
+            name_strictness: self.name_strictness,
+            options: CallOptions::default(),
+            observability: self.observability,
+            owner: self.owner,
+            quota: self.quota.map(Arc::new),
+            response_parsing: self.response_parsing,
+            locks: Arc::new(locks),
+            held_locks: Vec::new(),
+        })
+    }
 }
 
 impl SimplyClient {
@@ -47,140 +941,1507 @@ impl SimplyClient {
     /// * `api_key` - The API key for authentication.
     ///
     /// For usage details, see: https://www.simply.com/en/docs/api/
+    ///
+    /// # Panics
+    /// Panics if the TLS backend cannot be initialized, like [`reqwest::Client::new`].
+    /// Use [`SimplyClient::builder`] to handle that error instead.
     pub fn new(account: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self::builder()
+            .credentials(account, api_key)
+            .build()
+            .expect("failed to initialize the HTTP client")
+    }
+
+    /// Create a [`SimplyClientBuilder`] to configure the HTTP client, retries and other options.
+    pub fn builder() -> SimplyClientBuilder {
+        SimplyClientBuilder::default()
+    }
+
+    /// A client sharing this one's configuration and budget, sending requests with `priority`.
+    ///
+    /// Only has an effect if the client draws from an [`ApiBudget`].
+    pub fn with_priority(&self, priority: Priority) -> Self {
         Self {
-            account: account.into(),
-            api_key: api_key.into(),
-            base_url: "https://api.simply.com/2/".to_string(),
-            client: Client::new(),
+            priority,
+            ..self.clone()
         }
     }
-}
 
-impl SimplyClient {
-    /// List all DNS records for a given domain.
-    ///
-    /// # Arguments
-    /// * `domain` - The domain to list DNS records for.
+    /// A client sharing this one's configuration and budget, sending requests with `options`.
     ///
-    /// See: https://www.simply.com/en/docs/api/
-    pub async fn list_dns_records(
-        &self,
-        domain: &str,
-    ) -> Result<Vec<DnsRecord>, SimplyClientError> {
-        let url = format!(
-            "{}/my/products/{}/dns/records",
-            self.base_url.trim_end_matches('/'),
-            domain
-        );
-        let res = self
-            .client
-            .get(&url)
-            .basic_auth(&self.account, Some(&self.api_key))
-            .send()
-            .await?;
-        let resp: ListDnsRecordsResponse = res.json().await?;
-        Ok(resp.records.into_iter().map(|r| r.into()).collect())
+    /// Applies to every method, including the helpers of the other modules.
+    /// A call is cancelled by dropping its future, e.g. with
+    /// `tokio::time::timeout`; note that a request which already reached the
+    /// API may still take effect.
+    pub fn with_options(&self, options: CallOptions) -> Self {
+        Self {
+            options,
+            ..self.clone()
+        }
     }
-    /// Create a new DNS record for a domain.
-    ///
-    /// # Arguments
-    /// * `domain` - The domain to create the DNS record under.
-    /// * `req` - The DNS record request payload.
-    ///
-    /// See: https://www.simply.com/en/docs/api/
-    pub async fn create_dns_record(
-        &self,
-        domain: &str,
-        req: CreateDnsRecordRequest,
-    ) -> Result<Vec<DnsRecordId>, SimplyClientError> {
-        let url = format!(
-            "{}/my/products/{}/dns/records",
-            self.base_url.trim_end_matches('/'),
-            domain
-        );
-        let res = self
-            .client
-            .post(&url)
-            .basic_auth(&self.account, Some(&self.api_key))
-            .json(&req)
-            .send()
-            .await?;
-        let resp: CreateDnsRecordResponse = res.json().await?;
-        Ok(resp.record.unwrap_or_default())
+
+    /// A client sharing this one's configuration and budget, reporting the
+    /// progress of long-running operations to `sink`.
+    pub fn with_progress_sink(&self, sink: impl ProgressSink + 'static) -> Self {
+        Self {
+            progress: SharedSink(Some(Arc::new(sink))),
+            ..self.clone()
+        }
     }
 
-    /// Update an existing DNS record for a domain.
+    /// A client sharing this one's configuration and budget, tagging the
+    /// records it creates as owned by `owner`, see [`SimplyClientBuilder::owner`].
+    pub fn with_owner(&self, owner: impl Into<String>) -> Self {
+        Self {
+            owner: Some(owner.into()),
+            ..self.clone()
+        }
+    }
+
+    /// A client sharing this one's configuration and budget, limited to
+    /// `quota`, e.g. for one consumer of a platform, see
+    /// [`SimplyClientBuilder::quota`].
+    pub fn with_quota(&self, quota: Quota) -> Self {
+        Self {
+            quota: Some(Arc::new(quota)),
+            ..self.clone()
+        }
+    }
+
+    /// A client sharing this one's configuration and budget, creating records
+    /// according to `mode`.
     ///
-    /// # Arguments
-    /// * `domain` - The domain the DNS record belongs to.
-    /// * `record_id` - The ID of the DNS record to update.
-    /// * `req` - The updated DNS record payload.
+    /// Example usage:
+    /// ```rust,no_run
+    /// use simply_dns::api::CreateDnsRecordRequest;
+    /// use simply_dns::{CreateMode, SimplyClient};
     ///
-    /// See: https://www.simply.com/en/docs/api/
-    pub async fn update_dns_record(
-        &self,
-        domain: &str,
-        record_id: DnsRecordId,
-        req: UpdateDnsRecordRequest,
-    ) -> Result<(), SimplyClientError> {
-        let url = format!(
-            "{}/my/products/{}/dns/records/{}",
-            self.base_url.trim_end_matches('/'),
-            domain,
-            record_id.id,
-        );
-        let res = self
-            .client
-            .put(&url)
-            .basic_auth(&self.account, Some(&self.api_key))
-            .json(&req)
-            .send()
-            .await?;
-        let status = res.status();
-        if !status.is_success() {
-            let resp: GeneralResponse = res.json().await?;
-            return Err(SimplyClientError::Api(
-                status.as_u16().into(),
-                resp.message.unwrap_or_default(),
-            ));
+    /// # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+    /// let client = SimplyClient::new("account", "api_key").with_create_mode(CreateMode::SkipIfExists);
+    /// let req = CreateDnsRecordRequest {
+    ///     record_type: "A".to_string(),
+    ///     name: "www".to_string(),
+    ///     data: "192.0.2.1".to_string(),
+    ///     priority: None,
+    ///     ttl: Some(3600),
+    ///     comment: None,
+    /// };
+    /// // Running this twice leaves a single record.
+    /// let ids = client.create_dns_record("example.com", req).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_create_mode(&self, mode: CreateMode) -> Self {
+        Self {
+            options: CallOptions {
+                create_mode: mode,
+                ..self.options.clone()
+            },
+            ..self.clone()
         }
-        Ok(())
     }
 
-    /// Delete a DNS record for a domain.
+    /// A client sharing this one's configuration and budget, sending the unset
+    /// optional fields of record bodies according to `mode`.
     ///
-    /// # Arguments
-    /// * `domain` - The domain the DNS record belongs to.
-    /// * `record_id` - The ID of the DNS record to delete.
+    /// Fields a [`UpdatePatch`] clears are sent as `null` either way.
+    pub fn with_optional_fields(&self, mode: OptionalFields) -> Self {
+        Self {
+            options: CallOptions {
+                optional_fields: mode,
+                ..self.options.clone()
+            },
+            ..self.clone()
+        }
+    }
+
+    /// A client sharing this one's configuration and budget, calling `version`
+    /// of the API, e.g. for endpoints only available in a beta.
     ///
-    /// See: https://www.simply.com/en/docs/api/
-    pub async fn delete_dns_record(
+    /// See [`SimplyClientBuilder::base_url`] for where the requests go.
+    pub fn with_api_version(&self, version: ApiVersion) -> Self {
+        Self {
+            options: CallOptions {
+                api_version: Some(version),
+                ..self.options.clone()
+            },
+            ..self.clone()
+        }
+    }
+
+    /// The API version calls are made with, see [`SimplyClientBuilder::api_version`].
+    pub fn api_version(&self) -> &ApiVersion {
+        self.options
+            .api_version
+            .as_ref()
+            .unwrap_or(&self.api_version)
+    }
+
+    /// The JSON body of a record request, without its `None` fields if the
+    /// client [omits them](OptionalFields::Omit), except those named in `nulls`.
+    fn record_body(
         &self,
-        domain: &str,
-        record_id: DnsRecordId,
-    ) -> Result<(), SimplyClientError> {
-        let url = format!(
-            "{}/my/products/{}/dns/records/{}",
-            self.base_url.trim_end_matches('/'),
-            domain,
-            record_id.id,
-        );
-        let res = self
-            .client
-            .delete(&url)
-            .basic_auth(&self.account, Some(&self.api_key))
-            .send()
-            .await
-            .map_err(SimplyClientError::Http)?;
-        let status = res.status();
-        if !status.is_success() {
-            let resp: GeneralResponse = res.json().await?;
-            return Err(SimplyClientError::Api(
-                status.as_u16().into(),
-                resp.message.unwrap_or_default(),
-            ));
+        req: &impl Serialize,
+        nulls: &[&str],
+    ) -> Result<serde_json::Value, SimplyClientError> {
+        let mut body = serde_json::to_value(req)?;
+        if self.options.optional_fields == OptionalFields::Omit
+            && let serde_json::Value::Object(fields) = &mut body
+        {
+            fields.retain(|name, value| !value.is_null() || nulls.contains(&name.as_str()));
         }
-        Ok(())
+        Ok(body)
+    }
+
+    /// The URL of `endpoint` in the [API version](Self::api_version) in effect.
+    pub(crate) fn endpoint_url(&self, endpoint: Endpoint<'_>) -> String {
+        let version = self.api_version();
+        let root = endpoint::version_root(&self.base_url, &self.api_version, version);
+        format!("{root}/{}", endpoint.path(version))
+    }
+
+    /// The ownership tag of created records, see [`SimplyClientBuilder::owner`].
+    pub fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+
+    /// The quota of changes, see [`SimplyClientBuilder::quota`].
+    pub fn quota(&self) -> Option<&Quota> {
+        self.quota.as_deref()
+    }
+
+    /// The retry policy of requests, see [`SimplyClientBuilder::retry`].
+    pub(crate) fn retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retry.as_ref()
+    }
+
+    /// The per-domain locks shared with this client's clones.
+    pub(crate) fn domain_locks(&self) -> &DomainLocks {
+        &self.locks
+    }
+
+    /// Whether this client is the one of a [`DomainLock`](crate::lock::DomainLock) for `domain`.
+    pub(crate) fn holds_lock(&self, domain: &str) -> bool {
+        self.held_locks.iter().any(|d| d == domain)
+    }
+
+    /// A clone of this client marked as holding the lock of `domain`.
+    pub(crate) fn holding_lock(&self, domain: &str) -> SimplyClient {
+        let mut client = self.clone();
+        client.held_locks.push(domain.to_string());
+        client
+    }
+
+    /// The hooks approving plans, see [`SimplyClientBuilder::approval_hook`].
+    pub(crate) fn approval(&self) -> &ApprovalHooks {
+        &self.approval
+    }
+
+    /// Start reporting the progress of `operation` of `total` items.
+    pub(crate) fn progress(&self, operation: Operation, total: Option<usize>) -> Progress<'_> {
+        Progress::start(&self.progress, operation, total)
+    }
+
+    /// The record types this client lets through to the API.
+    ///
+    /// These are [`SUPPORTED_RECORD_TYPES`] plus any added with
+    /// [`SimplyClientBuilder::record_types`]. Creating or updating a record of
+    /// another type fails with [`SimplyClientError::UnsupportedRecordType`]
+    /// without calling the API.
+    pub fn supported_record_types(&self) -> &[String] {
+        &self.record_types
+    }
+
+    /// The name style of helper methods, see [`SimplyClientBuilder::name_style`].
+    pub fn name_style(&self) -> NameStyle {
+        self.name_style
+    }
+
+    /// The API's zone-relative form of `name`, given in either style.
+    pub(crate) fn api_name(&self, name: &str, domain: &str) -> String {
+        names::to_relative(&self.name_style.normalize(name, domain), domain)
+    }
+
+    /// `name` as returned by the API, converted to the client's name style.
+    pub(crate) fn styled_name(&self, name: &str, domain: &str) -> String {
+        match self.name_style {
+            NameStyle::Relative => name.to_string(),
+            NameStyle::Fqdn => names::to_fqdn(name, domain),
+        }
+    }
+
+    /// Whether the client may do more than call the API, see [`SimplyClientBuilder::observability`].
+    pub fn observability(&self) -> Observability {
+        self.observability
+    }
+
+    /// Whether this client is in dry-run mode, see [`SimplyClientBuilder::dry_run`].
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.is_some()
+    }
+
+    /// The requests recorded in dry-run mode so far, oldest first.
+    ///
+    /// Clones of a client, e.g. from [`with_priority`](Self::with_priority), share the record.
+    pub fn dry_run_requests(&self) -> Vec<DryRunRequest> {
+        self.dry_run
+            .as_ref()
+            .map(|log| log.lock().unwrap_or_else(|e| e.into_inner()).clone())
+            .unwrap_or_default()
+    }
+
+    /// In dry-run mode, record `request` and return `true` so it isn't sent.
+    fn record_dry_run(&self, request: &Request) -> bool {
+        let Some(log) = &self.dry_run else {
+            return false;
+        };
+        let entry = DryRunRequest {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            body: request
+                .body()
+                .and_then(|b| b.as_bytes())
+                .and_then(|b| serde_json::from_slice(b).ok()),
+        };
+        #[cfg(feature = "tracing")]
+        if self.observability == Observability::On {
+            tracing::info!(method = %entry.method, url = %entry.url, "dry run: request not sent");
+        }
+        log.lock().unwrap_or_else(|e| e.into_inner()).push(entry);
+        true
+    }
+
+    fn audit_context<'a>(&'a self, domain: &'a str) -> AuditContext<'a> {
+        AuditContext {
+            account: self.auth.account().unwrap_or_default(),
+            domain,
+            freeze_override: self.freeze_window().and(self.freeze_override.as_deref()),
+        }
+    }
+
+    /// The freeze window active now, if any, see [`SimplyClientBuilder::freeze_windows`].
+    pub(crate) fn freeze_window(&self) -> Option<&crate::freeze::FreezeWindow> {
+        self.freeze.as_ref()?.active()
+    }
+
+    /// Fail if a freeze window is active and wasn't overridden.
+    pub(crate) fn check_freeze(&self) -> Result<(), SimplyClientError> {
+        match self.freeze_window() {
+            Some(window) if self.freeze_override.is_none() => Err(SimplyClientError::Frozen {
+                window: window.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// The current state of a record about to change, if any audit hook needs it.
+    async fn audit_before(&self, domain: &str, record_id: &DnsRecordId) -> Option<DnsRecord> {
+        if self.audit.0.is_empty() {
+            return None;
+        }
+        self.get_dns_record(domain, record_id).await.ok().flatten()
+    }
+
+    fn check_record_type(&self, record_type: &str) -> Result<(), SimplyClientError> {
+        if self
+            .record_types
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(record_type))
+        {
+            Ok(())
+        } else {
+            Err(SimplyClientError::UnsupportedRecordType(
+                record_type.to_string(),
+            ))
+        }
+    }
+
+    /// Start a request to `url`; it is authenticated when executed.
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let mut builder = self.client.request(method, url);
+        if let Some(timeout) = self.options.timeout.or(self.timeout) {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(key) = &self.options.idempotency_key {
+            builder = builder.header("Idempotency-Key", key);
+        }
+        builder
+    }
+
+    /// Send `request`, applying the configured [`ApiBudget`] and [`RetryPolicy`].
+    ///
+    /// Unsuccessful responses are turned into the matching [`SimplyClientError`].
+    async fn execute(&self, mut request: Request) -> Result<Response, SimplyClientError> {
+        self.auth.authenticate(&mut request)?;
+        let res = if self.middleware.0.is_empty() {
+            self.execute_traced(request, &mut Attempts::default())
+                .await?
+        } else {
+            self.execute_with_middleware(request).await?
+        };
+        if res.status().is_success() {
+            Ok(res)
+        } else {
+            Err(SimplyClientError::from_response(res).await)
+        }
+    }
+
+    /// Like [`execute_traced`](Self::execute_traced), passing the request
+    /// through the registered [`Middleware`] first and telling it the outcome.
+    async fn execute_with_middleware(
+        &self,
+        mut request: Request,
+    ) -> Result<Response, SimplyClientError> {
+        for middleware in &self.middleware.0 {
+            middleware.before_request(&mut request)?;
+        }
+        let (method, url) = (request.method().clone(), request.url().clone());
+        let started = crate::time::Instant::now();
+        let mut attempts = Attempts::default();
+        let result = self.execute_traced(request, &mut attempts).await;
+        let exchange = Exchange {
+            method: &method,
+            url: &url,
+            outcome: result.as_ref(),
+            elapsed: started.elapsed(),
+            retries: attempts.retries,
+            rate_limited: attempts.rate_limited,
+        };
+        for middleware in &self.middleware.0 {
+            middleware.after_response(&exchange);
+        }
+        result
+    }
+
+    /// Send `request` without checking the response status.
+    ///
+    /// With the `tracing` feature, each request runs in a `simply_dns.request`
+    /// span and its outcome is logged. Credentials are never recorded.
+    async fn execute_traced(
+        &self,
+        request: Request,
+        attempts: &mut Attempts,
+    ) -> Result<Response, SimplyClientError> {
+        #[cfg(feature = "tracing")]
+        if self.observability == Observability::On {
+            use tracing::Instrument;
+
+            let span = tracing::debug_span!(
+                "simply_dns.request",
+                method = %request.method(),
+                url = %redacted_url(request.url()),
+                retries = tracing::field::Empty,
+            );
+            let started = crate::time::Instant::now();
+            let result = self.send(request, attempts).instrument(span.clone()).await;
+            span.in_scope(|| match &result {
+                Ok(res) => tracing::debug!(
+                    status = res.status().as_u16(),
+                    elapsed = ?started.elapsed(),
+                    "request completed"
+                ),
+                Err(e) => {
+                    tracing::warn!(error = %e, elapsed = ?started.elapsed(), "request failed")
+                }
+            });
+            return result;
+        }
+        self.send(request, attempts).await
+    }
+
+    async fn send(
+        &self,
+        request: Request,
+        attempts: &mut Attempts,
+    ) -> Result<Response, SimplyClientError> {
+        if self.retry.is_none() && self.budget.is_none() {
+            let res = self.client.execute(request).await?;
+            attempts.count(&res);
+            return Ok(res);
+        }
+        let idempotent = request.method() != Method::POST;
+        let max_attempts = self.retry.as_ref().map_or(1, |p| p.max_attempts);
+        let mut attempt = 1;
+        let mut throttled = 0;
+        loop {
+            let Some(next) = request.try_clone() else {
+                let res = self.client.execute(request).await?;
+                attempts.count(&res);
+                return Ok(res);
+            };
+            if let Some(budget) = &self.budget {
+                budget.acquire(self.priority).await;
+            }
+            let result = self.client.execute(next).await;
+            if let Ok(res) = &result {
+                attempts.count(res);
+            }
+            let retries_left = attempt < max_attempts;
+            let delay = match (&result, &self.retry) {
+                (Ok(res), _)
+                    if res.status() == StatusCode::TOO_MANY_REQUESTS
+                        && throttled < MAX_THROTTLED_RETRIES =>
+                {
+                    throttled += 1;
+                    let wait = retry_after(res.headers()).unwrap_or(DEFAULT_THROTTLE_DELAY);
+                    if let Some(budget) = &self.budget {
+                        budget.pause_for(wait);
+                    }
+                    wait
+                }
+                (Ok(res), Some(policy))
+                    if retries_left && policy.retries_status(res.status().as_u16(), idempotent) =>
+                {
+                    attempt += 1;
+                    policy.delay_for(attempt - 1)
+                }
+                (Err(e), Some(policy)) if retries_left && policy.retries_error(e, idempotent) => {
+                    attempt += 1;
+                    policy.delay_for(attempt - 1)
+                }
+                _ => {
+                    #[cfg(feature = "tracing")]
+                    tracing::Span::current().record("retries", attempt - 1 + throttled);
+                    return result.map_err(Into::into);
+                }
+            };
+            attempts.retries += 1;
+            #[cfg(feature = "tracing")]
+            if self.observability == Observability::On {
+                tracing::info!(
+                    attempt,
+                    throttled,
+                    delay = ?delay,
+                    outcome = %match &result {
+                        Ok(res) => res.status().to_string(),
+                        Err(e) => e.to_string(),
+                    },
+                    "retrying request"
+                );
+            }
+            if let Some(sink) = &self.progress.0 {
+                let reason = match &result {
+                    Ok(res) => res.status().to_string(),
+                    Err(e) => e.to_string(),
+                };
+                sink.on_event(&ProgressEvent::Retrying {
+                    retry: attempt - 1 + throttled,
+                    delay,
+                    reason: &reason,
+                });
+            }
+            crate::time::sleep(delay).await;
+        }
+    }
+}
+
+/// How often a request was sent, for [`Exchange`].
+#[derive(Debug, Default)]
+struct Attempts {
+    retries: u32,
+    rate_limited: u32,
+}
+
+impl Attempts {
+    fn count(&mut self, res: &Response) {
+        if res.status() == StatusCode::TOO_MANY_REQUESTS {
+            self.rate_limited += 1;
+        }
+    }
+}
+
+/// `url` without any user name or password, for logging.
+#[cfg(feature = "tracing")]
+fn redacted_url(url: &reqwest::Url) -> reqwest::Url {
+    let mut url = url.clone();
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url
+}
+
+/// Parse a `Retry-After` header given in seconds.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?;
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Fail with [`SimplyClientError::WrongZone`] unless `record` belongs to `domain`.
+fn check_zone(domain: &str, record: &ZoneRecordRef) -> Result<(), SimplyClientError> {
+    if record.belongs_to(domain) {
+        Ok(())
+    } else {
+        Err(SimplyClientError::WrongZone {
+            record: record.clone(),
+            domain: domain.to_string(),
+        })
+    }
+}
+
+impl SimplyClient {
+    /// List all products (domains, hosting, etc.) on the account.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn list_products(&self) -> Result<Vec<Product>, SimplyClientError> {
+        Ok(self.list_products_with_meta().await?.data)
+    }
+
+    /// Like [`list_products`](Self::list_products), also returning the HTTP details
+    /// of the response.
+    pub async fn list_products_with_meta(
+        &self,
+    ) -> Result<ApiResponse<Vec<Product>>, SimplyClientError> {
+        let url = self.endpoint_url(Endpoint::Products);
+        let req = self.request(Method::GET, &url).build()?;
+        let meta = ResponseMeta::read(self.execute(req).await?).await?;
+        let resp: ListProductsResponse = meta.json()?;
+        Ok(ApiResponse {
+            data: resp.products,
+            meta: Some(meta),
+        })
+    }
+
+    /// List the names of the domains on the account whose DNS can be managed.
+    ///
+    /// These are the product object names of all products with a domain attached,
+    /// usable as the `domain` argument of the DNS methods.
+    pub async fn list_domains(&self) -> Result<Vec<String>, SimplyClientError> {
+        Ok(self
+            .list_products()
+            .await?
+            .into_iter()
+            .filter(|p| p.domain.is_some())
+            .map(|p| p.object)
+            .collect())
+    }
+
+    /// List all DNS records for a given domain.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to list DNS records for.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn list_dns_records(
+        &self,
+        domain: &str,
+    ) -> Result<Vec<DnsRecord>, SimplyClientError> {
+        Ok(self.list_dns_records_with_meta(domain).await?.data)
+    }
+
+    /// Like [`list_dns_records`](Self::list_dns_records), also returning the
+    /// HTTP details of the response.
+    pub async fn list_dns_records_with_meta(
+        &self,
+        domain: &str,
+    ) -> Result<ApiResponse<Vec<DnsRecord>>, SimplyClientError> {
+        let res = self.list_dns_records_response(domain).await?;
+        let meta = ResponseMeta::read(res).await?;
+        let data = match self.response_parsing {
+            ResponseParsing::Strict => {
+                let resp: ListDnsRecordsResponse = meta.json()?;
+                resp.records.into_iter().map(|r| r.into()).collect()
+            }
+            ResponseParsing::Lenient => {
+                let list = meta.records_lenient()?;
+                for malformed in &list.malformed {
+                    self.log_malformed(domain, malformed);
+                }
+                list.records
+            }
+        };
+        Ok(ApiResponse {
+            data,
+            meta: Some(meta),
+        })
+    }
+
+    /// List the DNS records of a domain, setting aside records that can't be parsed.
+    ///
+    /// Unlike [`list_dns_records`](Self::list_dns_records) this is lenient
+    /// whatever the client's [`ResponseParsing`], and returns the malformed
+    /// records so they can be reported.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to list DNS records for.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn list_dns_records_lenient(
+        &self,
+        domain: &str,
+    ) -> Result<LenientRecords, SimplyClientError> {
+        let res = self.list_dns_records_response(domain).await?;
+        ResponseMeta::read(res).await?.records_lenient()
+    }
+
+    /// Whether record lists skip malformed records, see [`SimplyClientBuilder::response_parsing`].
+    pub(crate) fn lenient(&self) -> bool {
+        self.response_parsing == ResponseParsing::Lenient
+    }
+
+    /// Log a record left out of a list of `domain`.
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn log_malformed(&self, domain: &str, malformed: &MalformedRecord) {
+        #[cfg(feature = "tracing")]
+        if self.observability == Observability::On {
+            tracing::warn!(
+                domain,
+                index = malformed.index,
+                error = %malformed.message,
+                "skipping malformed record"
+            );
+        }
+    }
+
+    /// Request the records of `domain`, leaving the body to the caller.
+    pub(crate) async fn list_dns_records_response(
+        &self,
+        domain: &str,
+    ) -> Result<Response, SimplyClientError> {
+        let url = self.endpoint_url(Endpoint::DnsRecords(domain));
+        let req = self.request(Method::GET, &url).build()?;
+        self.execute(req).await
+    }
+
+    /// Get a single DNS record of a domain by its ID.
+    ///
+    /// The API has no endpoint for a single record, so this lists the records of
+    /// `domain` and picks the one with a matching ID. Returns `None` if it doesn't exist.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS record belongs to.
+    /// * `record_id` - The ID of the DNS record to get.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn get_dns_record(
+        &self,
+        domain: &str,
+        record_id: &DnsRecordId,
+    ) -> Result<Option<DnsRecord>, SimplyClientError> {
+        Ok(self
+            .list_dns_records(domain)
+            .await?
+            .into_iter()
+            .find(|r| r.record_id.id == record_id.id))
+    }
+    /// Create a new DNS record for a domain.
+    ///
+    /// If the client has an [owner](SimplyClientBuilder::owner), its tag is
+    /// appended to the record's comment. With a [create mode](Self::with_create_mode)
+    /// other than [`CreateMode::AlwaysCreate`], an identical existing record is
+    /// returned or reported instead of creating a duplicate.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to create the DNS record under.
+    /// * `req` - The DNS record request payload.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn create_dns_record(
+        &self,
+        domain: &str,
+        req: CreateDnsRecordRequest,
+    ) -> Result<Vec<DnsRecordId>, SimplyClientError> {
+        Ok(self.create_dns_record_with_meta(domain, req).await?.data)
+    }
+
+    /// Like [`create_dns_record`](Self::create_dns_record), also returning the
+    /// HTTP details of the response.
+    pub async fn create_dns_record_with_meta(
+        &self,
+        domain: &str,
+        mut req: CreateDnsRecordRequest,
+    ) -> Result<ApiResponse<Vec<DnsRecordId>>, SimplyClientError> {
+        self.check_record_type(&req.record_type)?;
+        self.check_quota(domain, &req.name, &req.record_type)?;
+        if let Some(owner) = &self.owner {
+            req.comment = Some(tag_comment(req.comment.as_deref(), owner));
+        }
+        if !self.skip_validation {
+            req.validate_with(self.name_strictness)?;
+        }
+        // Held from the check for an identical record, and the count of the
+        // quota, until it is created.
+        let _lock = match self.options.create_mode {
+            CreateMode::AlwaysCreate if !self.quota_limits_records() => None,
+            _ => Some(self.lock_domain(domain).await?),
+        };
+        if self.options.create_mode != CreateMode::AlwaysCreate {
+            let existing = self.identical_records(domain, &req).await?;
+            match (self.options.create_mode, existing.first()) {
+                (CreateMode::FailIfExists, Some(&record_id)) => {
+                    return Err(SimplyClientError::AlreadyExists { record_id });
+                }
+                (_, Some(_)) => {
+                    return Ok(ApiResponse {
+                        data: existing,
+                        meta: None,
+                    });
+                }
+                (_, None) => {}
+            }
+        }
+        self.check_quota_room(domain).await?;
+        let url = self.endpoint_url(Endpoint::DnsRecords(domain));
+        let body = self.record_body(&req, &[])?;
+        let request = self.request(Method::POST, &url).json(&body).build()?;
+        if self.record_dry_run(&request) {
+            return Ok(ApiResponse {
+                data: Vec::new(),
+                meta: None,
+            });
+        }
+        self.check_freeze()?;
+        let meta = ResponseMeta::read(self.execute(request).await?).await?;
+        let resp: CreateDnsRecordResponse = meta.json()?;
+        let ids = resp.record.unwrap_or_default();
+        for hook in &self.audit.0 {
+            hook.on_create(&self.audit_context(domain), &ids, &req);
+        }
+        Ok(ApiResponse {
+            data: ids,
+            meta: Some(meta),
+        })
+    }
+
+    /// The IDs of the records with the name, type and data of `req`.
+    async fn identical_records(
+        &self,
+        domain: &str,
+        req: &CreateDnsRecordRequest,
+    ) -> Result<Vec<DnsRecordId>, SimplyClientError> {
+        let name = self.api_name(&req.name, domain);
+        Ok(self
+            .list_dns_records(domain)
+            .await?
+            .into_iter()
+            .filter(|r| {
+                r.name.eq_ignore_ascii_case(&name)
+                    && r.record_type.eq_ignore_ascii_case(&req.record_type)
+                    && same_data(&req.record_type, &r.data, &req.data)
+            })
+            .map(|r| r.record_id)
+            .collect())
+    }
+
+    /// Update an existing DNS record for a domain.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS record belongs to.
+    /// * `record_id` - The ID of the DNS record to update.
+    /// * `req` - The updated DNS record payload.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn update_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+    ) -> Result<(), SimplyClientError> {
+        self.update_dns_record_with_meta(domain, record_id, req)
+            .await
+            .map(|_| ())
+    }
+
+    /// Like [`update_dns_record`](Self::update_dns_record), also returning the
+    /// HTTP details of the response.
+    pub async fn update_dns_record_with_meta(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+    ) -> Result<ApiResponse<()>, SimplyClientError> {
+        self.update_with_nulls(domain, record_id, req, &[]).await
+    }
+
+    /// Update a record, sending the fields named in `nulls` as `null` even
+    /// with [`OptionalFields::Omit`].
+    async fn update_with_nulls(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+        nulls: &[&str],
+    ) -> Result<ApiResponse<()>, SimplyClientError> {
+        self.check_record_type(&req.record_type)?;
+        self.check_quota(domain, &req.name, &req.record_type)?;
+        if !self.skip_validation {
+            req.validate_with(self.name_strictness)?;
+        }
+        self.check_quota_existing(domain, &record_id).await?;
+        let url = self.endpoint_url(Endpoint::DnsRecord(domain, record_id.id));
+        let body = self.record_body(&req, nulls)?;
+        let request = self.request(Method::PUT, &url).json(&body).build()?;
+        if self.record_dry_run(&request) {
+            return Ok(ApiResponse {
+                data: (),
+                meta: None,
+            });
+        }
+        self.check_freeze()?;
+        let before = self.audit_before(domain, &record_id).await;
+        let meta = ResponseMeta::read(self.execute(request).await?).await?;
+        for hook in &self.audit.0 {
+            hook.on_update(
+                &self.audit_context(domain),
+                &record_id,
+                before.as_ref(),
+                &req,
+            );
+        }
+        Ok(ApiResponse {
+            data: (),
+            meta: Some(meta),
+        })
+    }
+
+    /// Update a DNS record only if it still matches `expected`.
+    ///
+    /// Re-fetches the record first and fails with [`SimplyClientError::Conflict`]
+    /// if it was modified or deleted since `expected` was read, so that jobs
+    /// sharing a zone don't overwrite each other's changes unnoticed. The API
+    /// sends no `ETag` or `Last-Modified` and ignores `If-Match`, so a change
+    /// landing between the check and the update can still be overwritten; the
+    /// window is one request long.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS record belongs to.
+    /// * `record_id` - The ID of the DNS record to update.
+    /// * `expected` - The record as last read, e.g. from [`list_dns_records`](Self::list_dns_records).
+    /// * `req` - The updated DNS record payload.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn update_dns_record_if(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        expected: &DnsRecord,
+        req: UpdateDnsRecordRequest,
+    ) -> Result<(), SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        this.ensure_unchanged(domain, record_id, expected).await?;
+        this.update_dns_record(domain, record_id, req).await
+    }
+
+    /// Delete a DNS record only if it still matches `expected`.
+    ///
+    /// Fails with [`SimplyClientError::Conflict`] if the record was modified
+    /// or already deleted since `expected` was read, with the same one-request
+    /// window as [`update_dns_record_if`](Self::update_dns_record_if).
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS record belongs to.
+    /// * `record_id` - The ID of the DNS record to delete.
+    /// * `expected` - The record as last read.
+    ///
+    /// Example usage:
+    /// ```rust,no_run
+    /// use simply_dns::{SimplyClient, SimplyClientError};
+    ///
+    /// # async fn run() -> Result<(), SimplyClientError> {
+    /// let client = SimplyClient::new("account", "api_key");
+    /// for record in client.list_dns_records("example.com").await? {
+    ///     if record.name != "_acme-challenge" {
+    ///         continue;
+    ///     }
+    ///     match client
+    ///         .delete_dns_record_if("example.com", record.record_id, &record)
+    ///         .await
+    ///     {
+    ///         Err(e) if e.is_conflict() => println!("left alone: {e}"),
+    ///         result => result?,
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn delete_dns_record_if(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        expected: &DnsRecord,
+    ) -> Result<(), SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        this.ensure_unchanged(domain, record_id, expected).await?;
+        this.delete_dns_record(domain, record_id).await
+    }
+
+    /// Fail with [`SimplyClientError::Conflict`] unless the record still matches `expected`.
+    async fn ensure_unchanged(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        expected: &DnsRecord,
+    ) -> Result<(), SimplyClientError> {
+        match self.get_dns_record(domain, &record_id).await? {
+            Some(current)
+                if self.api_name(&current.name, domain)
+                    == self.api_name(&expected.name, domain)
+                    && current.same_contents(&DnsRecord {
+                        name: current.name.clone(),
+                        ..expected.clone()
+                    }) =>
+            {
+                Ok(())
+            }
+            current => Err(SimplyClientError::Conflict {
+                record_id,
+                current: current.map(Box::new),
+            }),
+        }
+    }
+
+    /// Change only the fields of a DNS record that `patch` sets or clears.
+    ///
+    /// The record is fetched first, so fields the patch keeps are sent with
+    /// their current values: unlike an [`UpdateDnsRecordRequest`] with
+    /// `ttl: None`, a patch leaves the TTL as it is unless it clears it.
+    /// Cleared fields are sent as `null`, except the comment, which is sent
+    /// empty. Fails with [`SimplyClientError::NotFound`] if the record doesn't exist.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS record belongs to.
+    /// * `record_id` - The ID of the DNS record to change.
+    /// * `patch` - The changes to make.
+    ///
+    /// Example usage:
+    /// ```rust,no_run
+    /// use simply_dns::SimplyClient;
+    /// use simply_dns::api::{DnsRecordId, UpdatePatch};
+    ///
+    /// # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+    /// let client = SimplyClient::new("account", "api_key");
+    /// let patch = UpdatePatch::new().data("192.0.2.2").clear_comment();
+    /// client
+    ///     .patch_dns_record("example.com", DnsRecordId::new(42), patch)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn patch_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        patch: UpdatePatch,
+    ) -> Result<(), SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let record = this
+            .get_dns_record(domain, &record_id)
+            .await?
+            .ok_or_else(|| SimplyClientError::NotFound {
+                message: format!("record {record_id} of {domain}"),
+            })?;
+        let req = patch.apply_to(&record);
+        this.update_with_nulls(domain, record_id, req, &patch.cleared_fields())
+            .await
+            .map(|_| ())
+    }
+
+    /// Set the comment of a DNS record, keeping everything else as it is.
+    ///
+    /// Comments can carry metadata for automation, e.g. "owner=ci ticket=OPS-42",
+    /// to be found again with [`find_by_comment`](Self::find_by_comment). An
+    /// empty comment removes it. Fails with [`SimplyClientError::NotFound`] if
+    /// the record doesn't exist.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS record belongs to.
+    /// * `record_id` - The ID of the DNS record to comment on.
+    /// * `comment` - The new comment.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn set_comment(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        comment: impl Into<String>,
+    ) -> Result<(), SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let record = this
+            .get_dns_record(domain, &record_id)
+            .await?
+            .ok_or_else(|| SimplyClientError::NotFound {
+                message: format!("record {record_id} of {domain}"),
+            })?;
+        let req = UpdateDnsRecordRequest {
+            comment: Some(comment.into()),
+            ..record.into()
+        };
+        this.update_dns_record(domain, record_id, req).await
+    }
+
+    /// Delete a DNS record for a domain.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS record belongs to.
+    /// * `record_id` - The ID of the DNS record to delete.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn delete_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+    ) -> Result<(), SimplyClientError> {
+        self.delete_dns_record_with_meta(domain, record_id)
+            .await
+            .map(|_| ())
+    }
+
+    /// Like [`delete_dns_record`](Self::delete_dns_record), also returning the
+    /// HTTP details of the response.
+    pub async fn delete_dns_record_with_meta(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+    ) -> Result<ApiResponse<()>, SimplyClientError> {
+        self.check_quota_existing(domain, &record_id).await?;
+        let url = self.endpoint_url(Endpoint::DnsRecord(domain, record_id.id));
+        let req = self.request(Method::DELETE, &url).build()?;
+        if self.record_dry_run(&req) {
+            return Ok(ApiResponse {
+                data: (),
+                meta: None,
+            });
+        }
+        self.check_freeze()?;
+        let before = self.audit_before(domain, &record_id).await;
+        let meta = ResponseMeta::read(self.execute(req).await?).await?;
+        for hook in &self.audit.0 {
+            hook.on_delete(&self.audit_context(domain), &record_id, before.as_ref());
+        }
+        Ok(ApiResponse {
+            data: (),
+            meta: Some(meta),
+        })
+    }
+
+    /// List the records of `domain` with a reference to each, for the
+    /// `_by_ref` methods.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to list DNS records for.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn list_dns_record_refs(
+        &self,
+        domain: &str,
+    ) -> Result<Vec<(ZoneRecordRef, DnsRecord)>, SimplyClientError> {
+        Ok(self
+            .list_dns_records(domain)
+            .await?
+            .into_iter()
+            .map(|record| (record.zone_ref(domain), record))
+            .collect())
+    }
+
+    /// Like [`create_dns_record`](Self::create_dns_record), returning references
+    /// to the created records.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to create the DNS record under.
+    /// * `req` - The DNS record request payload.
+    pub async fn create_dns_record_refs(
+        &self,
+        domain: &str,
+        req: CreateDnsRecordRequest,
+    ) -> Result<Vec<ZoneRecordRef>, SimplyClientError> {
+        let ids = self.create_dns_record(domain, req).await?;
+        Ok(ids
+            .into_iter()
+            .map(|id| ZoneRecordRef::new(domain, id))
+            .collect())
+    }
+
+    /// Like [`update_dns_record`](Self::update_dns_record), addressing the
+    /// record by reference.
+    ///
+    /// Fails with [`SimplyClientError::WrongZone`] without calling the API if
+    /// `record` belongs to another domain than `domain`.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS record belongs to.
+    /// * `record` - The DNS record to update.
+    /// * `req` - The updated DNS record payload.
+    ///
+    /// Example usage:
+    /// ```rust,no_run
+    /// use simply_dns::{SimplyClient, SimplyClientError};
+    /// use simply_dns::api::UpdateDnsRecordRequest;
+    ///
+    /// # async fn run() -> Result<(), SimplyClientError> {
+    /// let client = SimplyClient::new("account", "api_key");
+    /// let (www, record) = client
+    ///     .list_dns_record_refs("example.com")
+    ///     .await?
+    ///     .into_iter()
+    ///     .find(|(_, r)| r.name == "www")
+    ///     .expect("www exists");
+    /// let req = UpdateDnsRecordRequest {
+    ///     data: "192.0.2.2".to_string(),
+    ///     ..record.into()
+    /// };
+    /// let err = client
+    ///     .update_dns_record_by_ref("example.net", &www, req)
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(matches!(err, SimplyClientError::WrongZone { .. }));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn update_dns_record_by_ref(
+        &self,
+        domain: &str,
+        record: &ZoneRecordRef,
+        req: UpdateDnsRecordRequest,
+    ) -> Result<(), SimplyClientError> {
+        check_zone(domain, record)?;
+        self.update_dns_record(domain, record.id(), req).await
+    }
+
+    /// Like [`delete_dns_record`](Self::delete_dns_record), addressing the
+    /// record by reference.
+    ///
+    /// Fails with [`SimplyClientError::WrongZone`] without calling the API if
+    /// `record` belongs to another domain than `domain`.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS record belongs to.
+    /// * `record` - The DNS record to delete.
+    pub async fn delete_dns_record_by_ref(
+        &self,
+        domain: &str,
+        record: &ZoneRecordRef,
+    ) -> Result<(), SimplyClientError> {
+        check_zone(domain, record)?;
+        self.delete_dns_record(domain, record.id()).await
+    }
+
+    /// List the mail aliases of a product.
+    ///
+    /// # Arguments
+    /// * `domain` - The product (domain) to list mail aliases for.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn list_mail_aliases(
+        &self,
+        domain: &str,
+    ) -> Result<Vec<MailAlias>, SimplyClientError> {
+        let url = self.endpoint_url(Endpoint::MailAliases(domain));
+        let req = self.request(Method::GET, &url).build()?;
+        let res = self.execute(req).await?;
+        let resp: ListMailAliasesResponse = ResponseMeta::read(res).await?.json()?;
+        Ok(resp.aliases)
+    }
+
+    /// Create a mail alias forwarding an address of the domain.
+    ///
+    /// Returns the ID of the new alias, or `None` in dry-run mode or if the API
+    /// didn't return one.
+    ///
+    /// # Arguments
+    /// * `domain` - The product (domain) to add the mail alias to.
+    /// * `req` - The alias name and its destinations.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn create_mail_alias(
+        &self,
+        domain: &str,
+        req: CreateMailAliasRequest,
+    ) -> Result<Option<MailAliasId>, SimplyClientError> {
+        let url = self.endpoint_url(Endpoint::MailAliases(domain));
+        let request = self.request(Method::POST, &url).json(&req).build()?;
+        if self.record_dry_run(&request) {
+            return Ok(None);
+        }
+        self.check_freeze()?;
+        let res = self.execute(request).await?;
+        let resp: CreateMailAliasResponse = ResponseMeta::read(res).await?.json()?;
+        Ok(resp.alias.map(|a| MailAliasId { id: a.id }))
+    }
+
+    /// Delete a mail alias.
+    ///
+    /// # Arguments
+    /// * `domain` - The product (domain) the mail alias belongs to.
+    /// * `alias_id` - The ID of the mail alias to delete.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn delete_mail_alias(
+        &self,
+        domain: &str,
+        alias_id: MailAliasId,
+    ) -> Result<(), SimplyClientError> {
+        let url = self.endpoint_url(Endpoint::MailAlias(domain, alias_id.id));
+        let req = self.request(Method::DELETE, &url).build()?;
+        if self.record_dry_run(&req) {
+            return Ok(());
+        }
+        self.check_freeze()?;
+        ResponseMeta::read(self.execute(req).await?).await?;
+        Ok(())
+    }
+
+    /// Fetch the DS records of a DNSSEC-signed domain.
+    ///
+    /// These are what the parent zone's registry needs to establish the chain of trust.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to fetch the DS records of.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn get_dnssec_ds(&self, domain: &str) -> Result<Vec<DsRecord>, SimplyClientError> {
+        let url = self.endpoint_url(Endpoint::DnssecDs(domain));
+        let req = self.request(Method::GET, &url).build()?;
+        let res = self.execute(req).await?;
+        let resp: DnssecDsResponse = ResponseMeta::read(res).await?.json()?;
+        Ok(resp.records)
+    }
+
+    /// List the DNSSEC signing keys of a domain.
+    ///
+    /// Use [`DnssecKey::dnskey_data`] to get the DNSKEY record data of a key.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to list the signing keys of.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn list_dnssec_keys(
+        &self,
+        domain: &str,
+    ) -> Result<Vec<DnssecKey>, SimplyClientError> {
+        let url = self.endpoint_url(Endpoint::DnssecKeys(domain));
+        let req = self.request(Method::GET, &url).build()?;
+        let res = self.execute(req).await?;
+        let resp: DnssecKeysResponse = ResponseMeta::read(res).await?.json()?;
+        Ok(resp.keys)
+    }
+
+    /// Enable DNSSEC signing for a domain.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to enable DNSSEC for.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn enable_dnssec(&self, domain: &str) -> Result<(), SimplyClientError> {
+        self.set_dnssec(domain, "enable").await
+    }
+
+    /// Disable DNSSEC signing for a domain.
+    ///
+    /// Remove the DS records from the parent zone first, or resolvers will
+    /// reject the domain as bogus once the signatures disappear.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to disable DNSSEC for.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn disable_dnssec(&self, domain: &str) -> Result<(), SimplyClientError> {
+        self.set_dnssec(domain, "disable").await
+    }
+
+    async fn set_dnssec(&self, domain: &str, action: &str) -> Result<(), SimplyClientError> {
+        let url = self.endpoint_url(Endpoint::Dnssec(domain, action));
+        let req = self.request(Method::POST, &url).build()?;
+        if self.record_dry_run(&req) {
+            return Ok(());
+        }
+        self.check_freeze()?;
+        ResponseMeta::read(self.execute(req).await?).await?;
+        Ok(())
+    }
+
+    /// Create or update a DNS record matched by name and type.
+    ///
+    /// Lists the records of `domain` and looks for one with the same `name` and
+    /// `record_type`. If found, it is updated with `data` and `opts`; otherwise a
+    /// new record is created.
+    ///
+    /// This replaces the value of the first match, so for TXT names holding
+    /// several values use [`append_txt_record`](Self::append_txt_record) instead.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS record belongs to.
+    /// * `name` - The DNS record name, relative or fully-qualified, see [`SimplyClientBuilder::name_style`].
+    /// * `record_type` - Type of DNS record ("A", "CNAME", "MX", etc.).
+    /// * `data` - Data for the record (IP, target, etc.).
+    /// * `opts` - Priority, TTL and comment to set on the record.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn upsert_dns_record(
+        &self,
+        domain: &str,
+        name: &str,
+        record_type: &str,
+        data: &str,
+        opts: UpsertOptions,
+    ) -> Result<UpsertOutcome, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let name = this.api_name(name, domain);
+        let name = name.as_str();
+        let existing = this.list_dns_records(domain).await?.into_iter().find(|r| {
+            r.name.eq_ignore_ascii_case(name) && r.record_type.eq_ignore_ascii_case(record_type)
+        });
+        match existing {
+            Some(record) => {
+                let req = UpdateDnsRecordRequest {
+                    record_type: record_type.to_string(),
+                    name: name.to_string(),
+                    data: data.to_string(),
+                    priority: opts.priority,
+                    ttl: opts.ttl,
+                    comment: opts.comment,
+                };
+                let id = record.record_id.id;
+                this.update_dns_record(domain, record.record_id, req)
+                    .await?;
+                Ok(UpsertOutcome::Updated(DnsRecordId { id }))
+            }
+            None => {
+                let req = CreateDnsRecordRequest {
+                    record_type: record_type.to_string(),
+                    name: name.to_string(),
+                    data: data.to_string(),
+                    priority: opts.priority,
+                    ttl: opts.ttl,
+                    comment: opts.comment,
+                };
+                let ids = this.create_dns_record(domain, req).await?;
+                Ok(UpsertOutcome::Created(ids))
+            }
+        }
+    }
+
+    /// Add `value` to the TXT values of `name`, leaving other values alone.
+    ///
+    /// Every value is kept in its own TXT record, so several values can exist at
+    /// one name, e.g. the challenges of concurrent ACME orders. If a record
+    /// with `value` already exists, nothing is created, which makes retries safe.
+    /// Returns the IDs of the record(s) holding `value`.
+    ///
+    /// Example usage:
+    /// ```rust,no_run
+    /// use simply_dns::SimplyClient;
+    /// use simply_dns::api::UpsertOptions;
+    ///
+    /// # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+    /// let client = SimplyClient::new("account", "api_key");
+    /// let opts = UpsertOptions { ttl: Some(60), ..Default::default() };
+    /// client.append_txt_record("example.com", "_acme-challenge", "token-1", opts.clone()).await?;
+    /// client.append_txt_record("example.com", "_acme-challenge", "token-2", opts).await?;
+    /// // ... both orders are validated ...
+    /// client.remove_txt_value("example.com", "_acme-challenge", "token-1").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS record belongs to.
+    /// * `name` - The DNS record name, relative or fully-qualified, see [`SimplyClientBuilder::name_style`].
+    /// * `value` - The TXT value, with or without surrounding quotes.
+    /// * `opts` - TTL and comment for a new record; the priority is ignored.
+    pub async fn append_txt_record(
+        &self,
+        domain: &str,
+        name: &str,
+        value: &str,
+        opts: UpsertOptions,
+    ) -> Result<Vec<DnsRecordId>, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let name = this.api_name(name, domain);
+        let existing: Vec<DnsRecordId> = this
+            .txt_records(domain, &name)
+            .await?
+            .into_iter()
+            .filter(|r| same_data("TXT", &r.data, value))
+            .map(|r| r.record_id)
+            .collect();
+        if !existing.is_empty() {
+            return Ok(existing);
+        }
+        let req = CreateDnsRecordRequest {
+            record_type: "TXT".to_string(),
+            name,
+            data: value.to_string(),
+            priority: None,
+            ttl: opts.ttl,
+            comment: opts.comment,
+        };
+        this.create_dns_record(domain, req).await
+    }
+
+    /// Remove `value` from the TXT values of `name`, leaving other values alone.
+    ///
+    /// Deletes the record(s) created by [`append_txt_record`](Self::append_txt_record)
+    /// for `value`. Returns how many records were deleted; finding none is not
+    /// an error, so this is safe to call repeatedly.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS record belongs to.
+    /// * `name` - The DNS record name, relative or fully-qualified, see [`SimplyClientBuilder::name_style`].
+    /// * `value` - The TXT value, with or without surrounding quotes.
+    pub async fn remove_txt_value(
+        &self,
+        domain: &str,
+        name: &str,
+        value: &str,
+    ) -> Result<usize, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let name = this.api_name(name, domain);
+        let mut deleted = 0;
+        for record in this.txt_records(domain, &name).await? {
+            if !same_data("TXT", &record.data, value) {
+                continue;
+            }
+            match this.delete_dns_record(domain, record.record_id).await {
+                Ok(()) => deleted += 1,
+                // Someone else removed it in the meantime.
+                Err(e) if e.is_not_found() => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// The TXT records of `domain` named `name`, given in the API's form.
+    async fn txt_records(
+        &self,
+        domain: &str,
+        name: &str,
+    ) -> Result<Vec<DnsRecord>, SimplyClientError> {
+        Ok(self
+            .list_dns_records(domain)
+            .await?
+            .into_iter()
+            .filter(|r| {
+                r.name.eq_ignore_ascii_case(name) && r.record_type.eq_ignore_ascii_case("TXT")
+            })
+            .collect())
+    }
+
+    /// Export the `A` and `AAAA` records of a domain as an `/etc/hosts` fragment.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to export records from.
+    /// * `filter` - Only records for which this returns `true` are exported.
+    ///
+    /// See [`crate::hosts::format_hosts`] for the output format.
+    pub async fn export_hosts(
+        &self,
+        domain: &str,
+        filter: impl Fn(&DnsRecord) -> bool,
+    ) -> Result<String, SimplyClientError> {
+        let records = self.list_dns_records(domain).await?;
+        Ok(hosts::format_hosts(
+            domain,
+            records.iter().filter(|r| filter(r)),
+        ))
     }
 }
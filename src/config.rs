@@ -0,0 +1,1747 @@
+//! Zone definitions in configuration files.
+//!
+//! A [`ZoneConfig`] reads the records a zone should contain, and how the rest
+//! of the zone is treated, from a TOML or JSON file into a [`DesiredZone`] for
+//! [`SimplyClient::plan`](crate::SimplyClient::plan) and
+//! [`SimplyClient::apply`](crate::SimplyClient::apply), so zones can be kept
+//! in version control:
+//!
+//! ```toml
+//! domain = "example.com"
+//! # Only manage records carrying this tag, see DesiredZone::owned_by.
+//! owner = "managed-by=simply-dns"
+//! # Only manage dev.example.com and the names below it, see DesiredZone::scoped_to.
+//! scope = "dev"
+//!
+//! [ttl]
+//! default = 3600
+//! minimum = 60
+//! types = { TXT = 300 }
+//!
+//! [ignore]
+//! names = ["_acme-challenge*"]
+//! comment_tags = ["ddns"]
+//!
+//! [[records]]
+//! name = "@"
+//! type = "A"
+//! data = "192.0.2.10"
+//!
+//! [[records]]
+//! name = "@"
+//! type = "MX"
+//! data = "mail.example.com"
+//! priority = 10
+//! ```
+//!
+//! Every key but `records` is optional; a record needs a `name`, `type` and
+//! `data`, and may have a `ttl`, `priority` and `comment`. JSON files have the
+//! same structure. The TOML reader covers what zone definitions need: tables,
+//! arrays of tables, dotted keys, strings, integers, booleans, arrays and
+//! inline tables, but not dates or multi-line strings.
+//!
+//! YAML files have the same structure as well:
+//!
+//! ```yaml
+//! domain: example.com
+//! owner: managed-by=simply-dns
+//! ttl:
+//!   default: 3600
+//! records:
+//!   - name: "@"
+//!     type: A
+//!     data: 192.0.2.10
+//!   - { name: "@", type: MX, data: mail.example.com, priority: 10 }
+//! ```
+//!
+//! The YAML reader covers block mappings and sequences, flow collections, so
+//! JSON is read too, plain and quoted scalars, comments, anchors and aliases,
+//! `<<` merge keys and block scalars, but not tags or several documents in
+//! one file. As in YAML, `@` has to be quoted when it starts a value. Anchors
+//! let records share their settings:
+//!
+//! ```yaml
+//! records:
+//!   - &web { name: "@", type: A, data: 192.0.2.10, ttl: 300 }
+//!   - { <<: *web, name: www }
+//!   - { <<: *web, data: 192.0.2.11 }
+//! ```
+//!
+//! Instead of `data`, a record may say `same_as = "name"` to stand for the
+//! records of another name, so many names pointing at one target declare it
+//! once:
+//!
+//! ```toml
+//! [[records]]
+//! name = "@"
+//! type = "A"
+//! data = "192.0.2.10"
+//!
+//! [[records]]
+//! name = "shop"
+//! same_as = "@"
+//! type = "A"
+//! ```
+//!
+//! The record is expanded into one record per record of the other name, of
+//! its `type` if it sets one and of every type otherwise, with the other
+//! record's data and, unless set here, its TTL and priority. The other name
+//! may itself use `same_as`, as long as no names refer to each other in a
+//! cycle.
+//!
+//! [`SCHEMA`] is a JSON Schema of the format, for editors and CI checks,
+//! which `simply-dns schema` prints.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::config::ZoneConfig;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let config = ZoneConfig::load("zones/example.com.toml")?;
+//! let domain = config.domain.as_deref().unwrap_or("example.com");
+//! let plan = client.plan(domain, &config.zone).await?;
+//! println!("{}", client.apply(plan).await);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::sync::{DesiredRecord, DesiredZone, IgnoreRule, TtlPolicy};
+
+/// The JSON Schema (draft 2020-12) of zone definitions, which describes the
+/// YAML and TOML files as well.
+///
+/// Example usage:
+/// ```rust
+/// let schema: serde_json::Value = serde_json::from_str(simply_dns::config::SCHEMA)?;
+/// assert!(schema["properties"]["records"].is_object());
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+pub const SCHEMA: &str = include_str!("config.schema.json");
+
+/// Error returned when a zone definition cannot be read.
+#[derive(Debug, Error)]
+#[error("{}{message}", line.map(|l| format!("line {l}: ")).unwrap_or_default())]
+pub struct ConfigError {
+    /// The 1-based line number where the problem was found, if known.
+    pub line: Option<usize>,
+    /// What was wrong.
+    pub message: String,
+}
+
+/// A zone definition read from a file, see the [module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoneConfig {
+    /// The domain the file is for, if it names one.
+    pub domain: Option<String>,
+    /// The records and rules of the zone.
+    pub zone: DesiredZone,
+}
+
+impl ZoneConfig {
+    /// Read the file at `path`, as JSON if its extension is `.json`, as YAML if
+    /// it is `.yaml` or `.yml` and as TOML otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let failed = |message: String| ConfigError {
+            line: None,
+            message: format!("{}: {message}", path.display()),
+        };
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let input = std::fs::read_to_string(path).map_err(|e| failed(e.to_string()))?;
+        match extension.as_str() {
+            "json" => Self::from_json(&input),
+            "yaml" | "yml" => Self::from_yaml(&input),
+            _ => Self::from_toml(&input),
+        }
+    }
+
+    /// Parse a zone definition in TOML.
+    ///
+    /// Example usage:
+    /// ```rust
+    /// use simply_dns::config::ZoneConfig;
+    ///
+    /// let config = ZoneConfig::from_toml(r#"
+    ///     domain = "example.com"
+    ///     records = [
+    ///         { name = "@", type = "A", data = "192.0.2.10" },
+    ///         { name = "www", type = "CNAME", data = "example.com", ttl = 600 },
+    ///     ]
+    /// "#)?;
+    /// assert_eq!(config.domain.as_deref(), Some("example.com"));
+    /// assert_eq!(config.zone.records.len(), 2);
+    ///
+    /// let config = ZoneConfig::from_toml(r#"
+    ///     records = [
+    ///         { name = "@", type = "A", data = "192.0.2.10" },
+    ///         { name = "@", type = "A", data = "192.0.2.11" },
+    ///         { name = "shop", same_as = "@", ttl = 300 },
+    ///     ]
+    /// "#)?;
+    /// let shop: Vec<_> = config.zone.records.iter().filter(|r| r.name == "shop").collect();
+    /// assert_eq!(shop.len(), 2);
+    /// assert_eq!(shop[1].data, "192.0.2.11");
+    /// assert!(ZoneConfig::from_toml(r#"records = [{ name = "a", same_as = "b" }]"#).is_err());
+    /// # Ok::<(), simply_dns::config::ConfigError>(())
+    /// ```
+    pub fn from_toml(input: &str) -> Result<Self, ConfigError> {
+        let value = Toml::new(input).document()?;
+        Self::from_value(value)
+    }
+
+    /// Parse a zone definition in YAML.
+    ///
+    /// Example usage:
+    /// ```rust
+    /// use simply_dns::config::ZoneConfig;
+    ///
+    /// let config = ZoneConfig::from_yaml(r#"
+    /// domain: example.com
+    /// ttl:
+    ///   default: 3600
+    /// records:
+    ///   - name: "@"
+    ///     type: A
+    ///     data: 192.0.2.10
+    ///   - { name: www, type: CNAME, data: example.com, ttl: 600 }
+    /// "#)?;
+    /// assert_eq!(config.domain.as_deref(), Some("example.com"));
+    /// assert_eq!(config.zone.records.len(), 2);
+    /// assert_eq!(config.zone.records[1].ttl, Some(600));
+    /// # Ok::<(), simply_dns::config::ConfigError>(())
+    /// ```
+    pub fn from_yaml(input: &str) -> Result<Self, ConfigError> {
+        Self::from_value(read_yaml(input)?)
+    }
+
+    /// Parse a zone definition in JSON.
+    pub fn from_json(input: &str) -> Result<Self, ConfigError> {
+        let file: ConfigFile = serde_json::from_str(input).map_err(|e| ConfigError {
+            // serde_json's message already names the line.
+            line: None,
+            message: e.to_string(),
+        })?;
+        file.try_into()
+    }
+
+    /// The definition as TOML, which [`from_toml`](Self::from_toml) reads back.
+    ///
+    /// Records are written one `[[records]]` table each; `same_as` entries
+    /// were expanded when the definition was read, so they are written as the
+    /// records they stand for.
+    ///
+    /// Example usage:
+    /// ```rust
+    /// use simply_dns::config::ZoneConfig;
+    /// use simply_dns::sync::{DesiredRecord, DesiredZone};
+    ///
+    /// let config = ZoneConfig {
+    ///     domain: Some("example.com".to_string()),
+    ///     zone: DesiredZone::from_iter([DesiredRecord::new("www", "A", "192.0.2.10")]),
+    /// };
+    /// assert_eq!(ZoneConfig::from_toml(&config.to_toml())?, config);
+    /// # Ok::<(), simply_dns::config::ConfigError>(())
+    /// ```
+    pub fn to_toml(&self) -> String {
+        use std::fmt::Write;
+
+        // JSON strings are valid TOML basic strings.
+        let string = |s: &str| Value::from(s).to_string();
+        let strings = |items: Vec<&str>| {
+            let items: Vec<String> = items.into_iter().map(string).collect();
+            format!("[{}]", items.join(", "))
+        };
+        let zone = &self.zone;
+        let mut out = String::new();
+        for (key, value) in [
+            ("domain", &self.domain),
+            ("owner", &zone.owner),
+            ("scope", &zone.scope),
+        ] {
+            if let Some(value) = value {
+                let _ = writeln!(out, "{key} = {}", string(value));
+            }
+        }
+        let policy = &zone.ttl_policy;
+        if *policy != TtlPolicy::default() {
+            out.push_str("\n[ttl]\n");
+            if let Some(ttl) = policy.default {
+                let _ = writeln!(out, "default = {ttl}");
+            }
+            if let Some(ttl) = policy.minimum {
+                let _ = writeln!(out, "minimum = {ttl}");
+            }
+            if policy.inherit_existing {
+                out.push_str("inherit_existing = true\n");
+            }
+            if !policy.type_defaults.is_empty() {
+                let types: Vec<String> = (policy.type_defaults.iter())
+                    .map(|(record_type, ttl)| format!("{} = {ttl}", string(record_type)))
+                    .collect();
+                let _ = writeln!(out, "types = {{ {} }}", types.join(", "));
+            }
+        }
+        if !zone.ignore.is_empty() {
+            out.push_str("\n[ignore]\n");
+            let (mut names, mut types, mut tags) = (Vec::new(), Vec::new(), Vec::new());
+            for rule in &zone.ignore {
+                match rule {
+                    IgnoreRule::NameGlob(glob) => names.push(glob.as_str()),
+                    IgnoreRule::RecordType(record_type) => types.push(record_type.as_str()),
+                    IgnoreRule::CommentTag(tag) => tags.push(tag.as_str()),
+                }
+            }
+            for (key, rules) in [("names", names), ("types", types), ("comment_tags", tags)] {
+                if !rules.is_empty() {
+                    let _ = writeln!(out, "{key} = {}", strings(rules));
+                }
+            }
+        }
+        for record in &zone.records {
+            let _ = write!(
+                out,
+                "\n[[records]]\nname = {}\ntype = {}\ndata = {}\n",
+                string(&record.name),
+                string(&record.record_type),
+                string(&record.data)
+            );
+            if let Some(ttl) = record.ttl {
+                let _ = writeln!(out, "ttl = {ttl}");
+            }
+            if let Some(priority) = record.priority {
+                let _ = writeln!(out, "priority = {priority}");
+            }
+            if let Some(comment) = &record.comment {
+                let _ = writeln!(out, "comment = {}", string(comment));
+            }
+        }
+        out
+    }
+
+    fn from_value(value: Value) -> Result<Self, ConfigError> {
+        let file: ConfigFile = serde_json::from_value(value).map_err(|e| ConfigError {
+            line: None,
+            message: e.to_string(),
+        })?;
+        file.try_into()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    domain: Option<String>,
+    owner: Option<String>,
+    scope: Option<String>,
+    #[serde(default)]
+    ttl: TtlSection,
+    #[serde(default)]
+    ignore: IgnoreSection,
+    #[serde(default)]
+    records: Vec<RecordEntry>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TtlSection {
+    default: Option<u32>,
+    minimum: Option<u32>,
+    #[serde(default)]
+    inherit_existing: bool,
+    #[serde(default)]
+    types: BTreeMap<String, u32>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IgnoreSection {
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    types: Vec<String>,
+    #[serde(default)]
+    comment_tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecordEntry {
+    name: String,
+    #[serde(rename = "type")]
+    record_type: Option<String>,
+    data: Option<String>,
+    same_as: Option<String>,
+    ttl: Option<u32>,
+    priority: Option<u32>,
+    comment: Option<String>,
+}
+
+impl TryFrom<ConfigFile> for ZoneConfig {
+    type Error = ConfigError;
+
+    fn try_from(file: ConfigFile) -> Result<Self, ConfigError> {
+        let mut policy = TtlPolicy::new().inherit_existing(file.ttl.inherit_existing);
+        policy.default = file.ttl.default;
+        policy.minimum = file.ttl.minimum;
+        for (record_type, ttl) in &file.ttl.types {
+            policy = policy.type_default(record_type, *ttl);
+        }
+        let (aliases, records): (Vec<_>, Vec<_>) =
+            file.records.into_iter().partition(|r| r.same_as.is_some());
+        let records = records
+            .into_iter()
+            .map(RecordEntry::into_record)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut zone = DesiredZone::from_iter(records).with_ttl_policy(policy);
+        add_aliases(&mut zone, aliases)?;
+        let ignore = file.ignore;
+        let rules = (ignore.names.into_iter().map(IgnoreRule::NameGlob))
+            .chain(ignore.types.into_iter().map(IgnoreRule::RecordType))
+            .chain(ignore.comment_tags.into_iter().map(IgnoreRule::CommentTag));
+        for rule in rules {
+            zone = zone.ignore(rule);
+        }
+        zone.owner = file.owner;
+        zone.scope = file.scope;
+        Ok(ZoneConfig {
+            domain: file.domain,
+            zone,
+        })
+    }
+}
+
+impl RecordEntry {
+    fn error(&self, message: impl std::fmt::Display) -> ConfigError {
+        ConfigError {
+            line: None,
+            message: format!("record {}: {message}", self.name),
+        }
+    }
+
+    fn into_record(self) -> Result<DesiredRecord, ConfigError> {
+        let (Some(record_type), Some(data)) = (&self.record_type, &self.data) else {
+            let missing = if self.data.is_none() {
+                "data or same_as"
+            } else {
+                "type"
+            };
+            return Err(self.error(format_args!("missing {missing}")));
+        };
+        Ok(DesiredRecord {
+            name: self.name.clone(),
+            record_type: record_type.to_ascii_uppercase(),
+            data: data.clone(),
+            ttl: self.ttl,
+            priority: self.priority,
+            comment: self.comment,
+        })
+    }
+}
+
+/// Add the records of the `same_as` entries `aliases` to `zone`, each after
+/// the aliases of the name it copies.
+fn add_aliases(zone: &mut DesiredZone, mut aliases: Vec<RecordEntry>) -> Result<(), ConfigError> {
+    let same_name = |a: &str, b: &str| {
+        a.trim_end_matches('.')
+            .eq_ignore_ascii_case(b.trim_end_matches('.'))
+    };
+    while !aliases.is_empty() {
+        let ready = aliases.iter().position(|alias| {
+            let target = alias.same_as.as_deref().unwrap_or_default();
+            !aliases.iter().any(|other| same_name(&other.name, target))
+        });
+        let Some(i) = ready else {
+            let alias = &aliases[0];
+            let target = alias.same_as.as_deref().unwrap_or_default();
+            return Err(alias.error(format_args!("same_as {target} is part of a cycle")));
+        };
+        let alias = aliases.remove(i);
+        let target = alias.same_as.as_deref().unwrap_or_default();
+        if alias.data.is_some() {
+            return Err(alias.error("set either data or same_as"));
+        }
+        let start = zone.records.len();
+        if let Err(e) = zone.alias(&alias.name, target, alias.record_type.as_deref()) {
+            let of_type = e.record_type.map(|t| format!("{t} ")).unwrap_or_default();
+            return Err(alias.error(format_args!(
+                "same_as {target}: no {of_type}records to copy"
+            )));
+        }
+        for record in &mut zone.records[start..] {
+            record.ttl = alias.ttl.or(record.ttl);
+            record.priority = alias.priority.or(record.priority);
+            record.comment = alias.comment.clone();
+        }
+    }
+    Ok(())
+}
+
+/// Reader for the subset of TOML described in the [module docs](self),
+/// producing the same [`Value`] a JSON file would.
+struct Toml<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Toml<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ConfigError {
+        ConfigError {
+            line: Some(self.line),
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ConfigError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{expected}`")))
+        }
+    }
+
+    /// Skip spaces and tabs, within a line.
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    /// Skip whitespace, line breaks and comments.
+    fn skip_blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r' | '\n') => {
+                    self.bump();
+                }
+                Some('#') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Require the end of the line, allowing a trailing comment.
+    fn end_of_line(&mut self) -> Result<(), ConfigError> {
+        self.skip_spaces();
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.bump();
+            }
+        }
+        self.eat('\r');
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("unexpected `{c}` after value"))),
+        }
+    }
+
+    fn document(mut self) -> Result<Value, ConfigError> {
+        let mut root = Map::new();
+        let mut current: Vec<String> = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.peek().is_none() {
+                return Ok(Value::Object(root));
+            }
+            if self.eat('[') {
+                let array = self.eat('[');
+                self.skip_spaces();
+                let path = self.keys()?;
+                self.skip_spaces();
+                self.expect(']')?;
+                if array {
+                    self.expect(']')?;
+                }
+                let (last, parent) = path.split_last().expect("keys are never empty");
+                let parent = table(&mut root, parent).map_err(|e| self.error(e))?;
+                if array {
+                    match parent
+                        .entry(last.clone())
+                        .or_insert_with(|| Value::Array(Vec::new()))
+                    {
+                        Value::Array(tables) => tables.push(Value::Object(Map::new())),
+                        _ => return Err(self.error(format!("{last} is not an array of tables"))),
+                    }
+                } else if parent.contains_key(last) {
+                    return Err(self.error(format!("table {} is defined twice", path.join("."))));
+                } else {
+                    parent.insert(last.clone(), Value::Object(Map::new()));
+                }
+                current = path;
+            } else {
+                let keys = self.keys()?;
+                self.skip_spaces();
+                self.expect('=')?;
+                self.skip_spaces();
+                let value = self.value()?;
+                let map = table(&mut root, &current).map_err(|e| self.error(e))?;
+                insert(map, &keys, value).map_err(|e| self.error(e))?;
+            }
+            self.end_of_line()?;
+        }
+    }
+
+    /// A key, possibly dotted, e.g. `ttl.default` or `"quoted key"`.
+    fn keys(&mut self) -> Result<Vec<String>, ConfigError> {
+        let mut keys = Vec::new();
+        loop {
+            self.skip_spaces();
+            let key = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.bump();
+                    }
+                    if self.pos == start {
+                        return Err(self.error("expected a key"));
+                    }
+                    self.input[start..self.pos].to_string()
+                }
+            };
+            keys.push(key);
+            self.skip_spaces();
+            if !self.eat('.') {
+                return Ok(keys);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ConfigError> {
+        match self.peek() {
+            Some('"') => Ok(Value::String(self.basic_string()?)),
+            Some('\'') => Ok(Value::String(self.literal_string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) => self.scalar(),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, ConfigError> {
+        self.expect('"')?;
+        if self.input[self.pos..].starts_with("\"\"") {
+            return Err(self.error("multi-line strings aren't supported"));
+        }
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(text),
+                Some('\\') => {
+                    let escaped = match self.bump() {
+                        Some('b') => '\u{8}',
+                        Some('t') => '\t',
+                        Some('n') => '\n',
+                        Some('f') => '\u{c}',
+                        Some('r') => '\r',
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some(u @ ('u' | 'U')) => {
+                            let len = if u == 'u' { 4 } else { 8 };
+                            let hex = self.input.get(self.pos..self.pos + len).unwrap_or_default();
+                            let c = u32::from_str_radix(hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?;
+                            self.pos += len;
+                            c
+                        }
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    text.push(escaped);
+                }
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, ConfigError> {
+        self.expect('\'')?;
+        let start = self.pos;
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some('\'') => return Ok(self.input[start..self.pos - 1].to_string()),
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, ConfigError> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.eat(']') {
+                return Ok(Value::Array(values));
+            }
+            values.push(self.value()?);
+            self.skip_blank();
+            if !self.eat(',') {
+                self.skip_blank();
+                self.expect(']')?;
+                return Ok(Value::Array(values));
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, ConfigError> {
+        self.expect('{')?;
+        let mut map = Map::new();
+        self.skip_spaces();
+        if self.eat('}') {
+            return Ok(Value::Object(map));
+        }
+        loop {
+            let keys = self.keys()?;
+            self.skip_spaces();
+            self.expect('=')?;
+            self.skip_spaces();
+            let value = self.value()?;
+            insert(&mut map, &keys, value).map_err(|e| self.error(e))?;
+            self.skip_spaces();
+            if self.eat('}') {
+                return Ok(Value::Object(map));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    /// A boolean or number.
+    fn scalar(&mut self) -> Result<Value, ConfigError> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || "+-_.:".contains(c)) {
+            self.bump();
+        }
+        let token = &self.input[start..self.pos];
+        match token {
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            _ => {}
+        }
+        let digits = token.replace('_', "");
+        if let Ok(n) = digits.parse::<i64>() {
+            return Ok(n.into());
+        }
+        digits
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| self.error(format!("unsupported value `{token}`")))
+    }
+}
+
+/// The table at `path` under `root`, created if missing. An array of tables
+/// on the path stands for its last table.
+fn table<'m>(
+    mut map: &'m mut Map<String, Value>,
+    path: &[String],
+) -> Result<&'m mut Map<String, Value>, String> {
+    for key in path {
+        let entry = map
+            .entry(key.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        map = match entry {
+            Value::Object(table) => table,
+            Value::Array(tables) => match tables.last_mut() {
+                Some(Value::Object(table)) => table,
+                _ => return Err(format!("{key} is not a table")),
+            },
+            _ => return Err(format!("{key} is not a table")),
+        };
+    }
+    Ok(map)
+}
+
+/// Set the dotted key `keys` of `map` to `value`.
+fn insert(map: &mut Map<String, Value>, keys: &[String], value: Value) -> Result<(), String> {
+    let (last, parent) = keys.split_last().expect("keys are never empty");
+    let map = table(map, parent)?;
+    if map.contains_key(last) {
+        return Err(format!("{} is defined twice", keys.join(".")));
+    }
+    map.insert(last.clone(), value);
+    Ok(())
+}
+
+/// Read a YAML document, with the subset of YAML described in the
+/// [module docs](self), into the value a JSON file would give.
+pub(crate) fn read_yaml(input: &str) -> Result<Value, ConfigError> {
+    Yaml::new(input).document()
+}
+
+/// Reader for the subset of YAML described in the [module docs](self),
+/// producing the same [`Value`] a JSON file would.
+///
+/// Block content is read line by line, by indentation; flow collections may
+/// span lines.
+struct Yaml<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+    /// The nodes anchored so far, by anchor name.
+    anchors: HashMap<String, Value>,
+}
+
+impl<'a> Yaml<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            pos: 0,
+            line: 1,
+            anchors: HashMap::new(),
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> ConfigError {
+        ConfigError {
+            line: Some(self.line),
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ConfigError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{expected}`")))
+        }
+    }
+
+    /// Skip spaces and tabs, within a line.
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    /// Skip whitespace, line breaks and comments, within a flow collection.
+    fn skip_blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r' | '\n') => {
+                    self.bump();
+                }
+                Some('#') => self.skip_line(),
+                _ => return,
+            }
+        }
+    }
+
+    /// Skip to the line break ending the current line.
+    fn skip_line(&mut self) {
+        while !matches!(self.peek(), None | Some('\n')) {
+            self.bump();
+        }
+    }
+
+    /// The rest of the current line, without its line break.
+    fn rest_of_line(&self) -> &'a str {
+        let rest = &self.input[self.pos..];
+        let line = rest.split('\n').next().unwrap_or_default();
+        line.strip_suffix('\r').unwrap_or(line)
+    }
+
+    /// The column of the next character on its line.
+    fn column(&self) -> usize {
+        let start = self.input[..self.pos].rfind('\n').map_or(0, |i| i + 1);
+        self.pos - start
+    }
+
+    /// Whether only a comment, if anything, is left on the line.
+    fn at_line_end(&self) -> bool {
+        matches!(self.peek(), None | Some('\r' | '\n' | '#'))
+    }
+
+    /// Require the end of the line, allowing a trailing comment.
+    fn end_of_line(&mut self) -> Result<(), ConfigError> {
+        self.skip_spaces();
+        if self.peek() == Some('#') {
+            self.skip_line();
+        }
+        self.eat('\r');
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("unexpected `{c}` after value"))),
+        }
+    }
+
+    /// Skip blank and comment lines, stopping at the start of the next line
+    /// with content. Returns its indentation, or `None` at the end of the input.
+    fn next_line(&mut self) -> Result<Option<usize>, ConfigError> {
+        loop {
+            let line = self.rest_of_line();
+            let content = line.trim_start_matches(' ');
+            if content.trim_start().is_empty() || content.trim_start().starts_with('#') {
+                if self.pos + line.len() >= self.input.len() {
+                    self.pos = self.input.len();
+                    return Ok(None);
+                }
+                self.skip_line();
+                self.bump();
+                continue;
+            }
+            if content.starts_with('\t') {
+                return Err(self.error("tabs can't be used for indentation"));
+            }
+            return Ok(Some(line.len() - content.len()));
+        }
+    }
+
+    /// Whether the line is the document marker `marker`, e.g. `---`.
+    fn is_marker(&self, marker: &str) -> bool {
+        self.rest_of_line()
+            .strip_prefix(marker)
+            .is_some_and(|rest| {
+                let rest = rest.trim_start_matches([' ', '\t']);
+                rest.is_empty() || rest.starts_with('#')
+            })
+    }
+
+    /// Whether the line is a marker ending the document.
+    fn is_end(&self) -> bool {
+        self.is_marker("---") || self.is_marker("...")
+    }
+
+    /// Whether a block sequence item starts `offset` bytes ahead.
+    fn is_item_at(&self, offset: usize) -> bool {
+        let rest = self.input.get(self.pos + offset..).unwrap_or_default();
+        rest.strip_prefix('-')
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t', '\r', '\n']))
+    }
+
+    /// Whether the line continues with a mapping key, `key: ...`.
+    fn is_key(&self) -> bool {
+        let line = self.rest_of_line();
+        match line.chars().next() {
+            Some('[' | '{') | None => false,
+            Some(quote @ ('"' | '\'')) => closing_quote(line, quote)
+                .is_some_and(|end| line[end..].trim_start_matches([' ', '\t']).starts_with(':')),
+            Some(_) => {
+                let mut previous = ' ';
+                for (i, c) in line.char_indices() {
+                    match c {
+                        ':' if line[i + 1..].is_empty()
+                            || line[i + 1..].starts_with([' ', '\t']) =>
+                        {
+                            return true;
+                        }
+                        '#' if previous == ' ' || previous == '\t' => return false,
+                        _ => {}
+                    }
+                    previous = c;
+                }
+                false
+            }
+        }
+    }
+
+    fn document(mut self) -> Result<Value, ConfigError> {
+        let mut indent = self.next_line()?;
+        if indent == Some(0) && self.is_marker("---") {
+            self.skip_line();
+            self.bump();
+            indent = self.next_line()?;
+        }
+        let Some(indent) = indent else {
+            return Ok(Value::Object(Map::new()));
+        };
+        if indent == 0 && self.is_marker("...") {
+            return Ok(Value::Object(Map::new()));
+        }
+        self.pos += indent;
+        let value = self.block(indent)?;
+        match self.next_line()? {
+            None => Ok(value),
+            Some(0) if self.is_marker("...") => Ok(value),
+            Some(0) if self.is_marker("---") => {
+                Err(self.error("several documents in one file aren't supported"))
+            }
+            Some(_) => Err(self.error("unexpected indentation")),
+        }
+    }
+
+    /// The node starting at the current position, whose lines are indented by
+    /// `indent`. Leaves the position at the start of the line after it.
+    fn block(&mut self, indent: usize) -> Result<Value, ConfigError> {
+        if self.is_item_at(0) {
+            self.sequence(indent)
+        } else if self.is_key() {
+            self.mapping(indent)
+        } else {
+            let value = self.value(false)?;
+            self.end_of_line()?;
+            Ok(value)
+        }
+    }
+
+    /// The value of a key or item that ends its line: the block on the lines
+    /// indented further, if any. A mapping's value may also be a sequence
+    /// indented as much as the mapping itself.
+    fn nested(&mut self, indent: usize, in_mapping: bool) -> Result<Value, ConfigError> {
+        self.end_of_line()?;
+        match self.next_line()? {
+            Some(next) if next > indent => {
+                self.pos += next;
+                self.block(next)
+            }
+            Some(next) if next == indent && in_mapping && self.is_item_at(next) => {
+                self.pos += next;
+                self.sequence(next)
+            }
+            _ => Ok(Value::Null),
+        }
+    }
+
+    /// Whether a literal (`|`) or folded (`>`) block scalar starts here.
+    fn at_block_scalar(&self) -> bool {
+        matches!(self.peek(), Some('|' | '>'))
+    }
+
+    /// The block scalar of a key or item indented by `indent`, on the lines
+    /// indented further. Leaves the position at the start of the line after it.
+    fn block_scalar(&mut self, indent: usize) -> Result<Value, ConfigError> {
+        let folded = self.bump() == Some('>');
+        let (mut chomping, mut indentation) = (None, None);
+        loop {
+            match self.peek() {
+                Some(c @ ('-' | '+')) if chomping.is_none() => chomping = Some(c),
+                Some(c @ '1'..='9') if indentation.is_none() => {
+                    indentation = c.to_digit(10).map(|n| indent + n as usize)
+                }
+                _ => break,
+            }
+            self.bump();
+        }
+        if !matches!(self.peek(), None | Some(' ' | '\t' | '\r' | '\n')) {
+            return Err(self.error("invalid block scalar header"));
+        }
+        self.end_of_line()?;
+        let mut lines = Vec::new();
+        while self.pos < self.input.len() {
+            let line = self.rest_of_line();
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+            if line.trim().is_empty() {
+                lines.push("");
+            } else {
+                let content = *indentation.get_or_insert(spaces);
+                if spaces <= indent || spaces < content {
+                    break;
+                }
+                lines.push(&line[content..]);
+            }
+            self.skip_line();
+            self.bump();
+        }
+        // Blank lines before the next key or item don't belong to the scalar
+        // unless kept with `+`, and are read again as blank lines.
+        let trailing = lines.iter().rev().take_while(|l| l.is_empty()).count();
+        lines.truncate(lines.len() - trailing);
+        let normal = |line: &str| !line.is_empty() && !line.starts_with([' ', '\t']);
+        let mut text = String::new();
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                let previous = lines[i - 1];
+                let next = lines[i..].iter().find(|l| !l.is_empty());
+                let fold = folded && normal(previous) && next.is_some_and(|l| normal(l));
+                // A folded line break becomes a space, or disappears before
+                // blank lines, which stand for the line breaks.
+                if !fold {
+                    text.push('\n');
+                } else if !line.is_empty() {
+                    text.push(' ');
+                }
+            }
+            text.push_str(line);
+        }
+        match chomping {
+            Some('-') => {}
+            Some(_) => text.push_str(&"\n".repeat(trailing + usize::from(!lines.is_empty()))),
+            None if !lines.is_empty() => text.push('\n'),
+            None => {}
+        }
+        Ok(Value::String(text))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value, ConfigError> {
+        let (mut map, mut merges) = (Map::new(), Vec::new());
+        loop {
+            let line = self.line;
+            let key = self.key(false)?;
+            self.skip_spaces();
+            let anchor = self.anchor()?;
+            let value = if self.at_line_end() {
+                self.nested(indent, true)?
+            } else if self.at_block_scalar() {
+                self.block_scalar(indent)?
+            } else {
+                let value = self.value(false)?;
+                self.end_of_line()?;
+                value
+            };
+            self.define(anchor, &value);
+            if key == MERGE_KEY {
+                merges.push((line, value));
+            } else if map.contains_key(&key) {
+                return Err(ConfigError {
+                    line: Some(line),
+                    message: format!("{key} is defined twice"),
+                });
+            } else {
+                map.insert(key, value);
+            }
+            match self.next_line()? {
+                Some(next) if next == indent && !self.is_item_at(next) && !self.is_end() => {
+                    self.pos += next
+                }
+                Some(next) if next > indent => return Err(self.error("unexpected indentation")),
+                _ => return merge(map, merges),
+            }
+        }
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value, ConfigError> {
+        let mut items = Vec::new();
+        loop {
+            self.expect('-')?;
+            self.skip_spaces();
+            let anchor = self.anchor()?;
+            let item = if self.at_line_end() {
+                self.nested(indent, false)?
+            } else if self.at_block_scalar() {
+                self.block_scalar(indent)?
+            } else {
+                let column = self.column();
+                self.block(column)?
+            };
+            self.define(anchor, &item);
+            items.push(item);
+            match self.next_line()? {
+                Some(next) if next == indent && self.is_item_at(next) => self.pos += next,
+                Some(next) if next > indent => return Err(self.error("unexpected indentation")),
+                _ => return Ok(Value::Array(items)),
+            }
+        }
+    }
+
+    /// A mapping key and the `:` after it.
+    fn key(&mut self, in_flow: bool) -> Result<String, ConfigError> {
+        let key = match self.peek() {
+            Some('"') => self.double_quoted()?,
+            Some('\'') => self.single_quoted()?,
+            _ => self.plain(true, in_flow),
+        };
+        if key.is_empty() {
+            return Err(self.error("expected a key"));
+        }
+        self.skip_spaces();
+        self.expect(':')?;
+        Ok(key)
+    }
+
+    /// A scalar, flow collection or alias, possibly anchored.
+    fn value(&mut self, in_flow: bool) -> Result<Value, ConfigError> {
+        if let Some(anchor) = self.anchor()? {
+            if in_flow {
+                self.skip_blank();
+            }
+            let value = self.value(in_flow)?;
+            self.define(Some(anchor), &value);
+            return Ok(value);
+        }
+        match self.peek() {
+            Some('"') => Ok(Value::String(self.double_quoted()?)),
+            Some('\'') => Ok(Value::String(self.single_quoted()?)),
+            Some('[') => self.flow_sequence(),
+            Some('{') => self.flow_mapping(),
+            Some('|' | '>') => {
+                Err(self.error("block scalars can only be the value of a key or item"))
+            }
+            Some('*') => {
+                self.bump();
+                let name = self.anchor_name()?;
+                (self.anchors.get(&name).cloned())
+                    .ok_or_else(|| self.error(format!("unknown alias *{name}")))
+            }
+            Some('!') => Err(self.error("tags aren't supported")),
+            Some('@' | '`') => {
+                Err(self.error("values starting with `@` or `` ` `` must be quoted"))
+            }
+            _ => Ok(resolve(&self.plain(false, in_flow))),
+        }
+    }
+
+    /// The name of the anchor `&name` at the current position, if any,
+    /// skipping the spaces after it.
+    fn anchor(&mut self) -> Result<Option<String>, ConfigError> {
+        if !self.eat('&') {
+            return Ok(None);
+        }
+        let name = self.anchor_name()?;
+        self.skip_spaces();
+        Ok(Some(name))
+    }
+
+    /// The name of an anchor or alias, after its `&` or `*`.
+    fn anchor_name(&mut self) -> Result<String, ConfigError> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || matches!(c, ',' | '[' | ']' | '{' | '}') {
+                break;
+            }
+            self.bump();
+        }
+        match &self.input[start..self.pos] {
+            "" => Err(self.error("expected an anchor name")),
+            name => Ok(name.to_string()),
+        }
+    }
+
+    /// Remember `value` as the node of `anchor`, if set. A later anchor of
+    /// the same name replaces it, as in YAML.
+    fn define(&mut self, anchor: Option<String>, value: &Value) {
+        if let Some(anchor) = anchor {
+            self.anchors.insert(anchor, value.clone());
+        }
+    }
+
+    /// An unquoted scalar, up to a comment, the end of the line or, within a
+    /// flow collection, a `,`, `]` or `}`. A key also ends at `: `.
+    fn plain(&mut self, key: bool, in_flow: bool) -> String {
+        let start = self.pos;
+        loop {
+            let rest = &self.input[self.pos..];
+            match self.peek() {
+                None | Some('\r' | '\n') => break,
+                Some('#') if self.input[..self.pos].ends_with([' ', '\t']) => break,
+                Some(',' | ']' | '}') if in_flow => break,
+                Some(':')
+                    if (key || in_flow)
+                        && (rest.len() == 1
+                            || rest[1..].starts_with([' ', '\t', '\r', '\n'])
+                            || in_flow && rest[1..].starts_with([',', ']', '}'])) =>
+                {
+                    break;
+                }
+                _ => {
+                    self.bump();
+                }
+            }
+        }
+        self.input[start..self.pos].trim_end().to_string()
+    }
+
+    fn double_quoted(&mut self) -> Result<String, ConfigError> {
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            let c = match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some(c) => c,
+            };
+            self.bump();
+            match c {
+                '"' => return Ok(text),
+                '\\' => {
+                    let escaped = match self.bump() {
+                        Some('0') => '\0',
+                        Some('b') => '\u{8}',
+                        Some('t') => '\t',
+                        Some('n') => '\n',
+                        Some('f') => '\u{c}',
+                        Some('r') => '\r',
+                        Some('e') => '\u{1b}',
+                        Some(c @ ('"' | '\\' | '/' | ' ')) => c,
+                        Some(x @ ('x' | 'u' | 'U')) => {
+                            let len = match x {
+                                'x' => 2,
+                                'u' => 4,
+                                _ => 8,
+                            };
+                            let hex = self.input.get(self.pos..self.pos + len).unwrap_or_default();
+                            let c = u32::from_str_radix(hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?;
+                            self.pos += len;
+                            c
+                        }
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    text.push(escaped);
+                }
+                c => text.push(c),
+            }
+        }
+    }
+
+    fn single_quoted(&mut self) -> Result<String, ConfigError> {
+        self.expect('\'')?;
+        let mut text = String::new();
+        loop {
+            let c = match self.peek() {
+                None | Some('\n') => return Err(self.error("unterminated string")),
+                Some(c) => c,
+            };
+            self.bump();
+            match c {
+                '\'' if self.eat('\'') => text.push('\''),
+                '\'' => return Ok(text),
+                c => text.push(c),
+            }
+        }
+    }
+
+    fn flow_sequence(&mut self) -> Result<Value, ConfigError> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.eat(']') {
+                return Ok(Value::Array(values));
+            }
+            values.push(self.value(true)?);
+            self.skip_blank();
+            if !self.eat(',') {
+                self.expect(']')?;
+                return Ok(Value::Array(values));
+            }
+        }
+    }
+
+    fn flow_mapping(&mut self) -> Result<Value, ConfigError> {
+        self.expect('{')?;
+        let (mut map, mut merges) = (Map::new(), Vec::new());
+        loop {
+            self.skip_blank();
+            if self.eat('}') {
+                return merge(map, merges);
+            }
+            let line = self.line;
+            let key = self.key(true)?;
+            self.skip_blank();
+            let value = self.value(true)?;
+            if key == MERGE_KEY {
+                merges.push((line, value));
+            } else if map.contains_key(&key) {
+                return Err(self.error(format!("{key} is defined twice")));
+            } else {
+                map.insert(key, value);
+            }
+            self.skip_blank();
+            if !self.eat(',') {
+                self.expect('}')?;
+                return merge(map, merges);
+            }
+        }
+    }
+}
+
+/// The key merging other mappings into a YAML mapping, as in `<<: *defaults`.
+const MERGE_KEY: &str = "<<";
+
+/// `map` with the keys of the mappings given to its merge keys that it
+/// doesn't set itself, each on the line it was given. A merge key takes a
+/// mapping or a sequence of mappings, of which earlier ones take precedence.
+fn merge(mut map: Map<String, Value>, merges: Vec<(usize, Value)>) -> Result<Value, ConfigError> {
+    for (line, value) in merges {
+        let sources = match value {
+            Value::Array(items) => items,
+            value => vec![value],
+        };
+        for source in sources {
+            let Value::Object(source) = source else {
+                return Err(ConfigError {
+                    line: Some(line),
+                    message: "<< needs a mapping or a sequence of mappings".to_string(),
+                });
+            };
+            for (key, value) in source {
+                map.entry(key).or_insert(value);
+            }
+        }
+    }
+    Ok(Value::Object(map))
+}
+
+/// The end of the string quoted with `quote` at the start of `line`, after
+/// its closing quote.
+fn closing_quote(line: &str, quote: char) -> Option<usize> {
+    let mut chars = line.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if quote == '"' => {
+                chars.next();
+            }
+            // A doubled quote stands for itself in single-quoted strings.
+            '\'' if quote == '\'' && line[i + 1..].starts_with('\'') => {
+                chars.next();
+            }
+            c if c == quote => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The value of the unquoted scalar `token`: null, a boolean, a number or a string.
+fn resolve(token: &str) -> Value {
+    match token {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => {}
+    }
+    if let Ok(n) = token.parse::<i64>() {
+        return n.into();
+    }
+    let numeric = token.contains(|c: char| c.is_ascii_digit())
+        && token
+            .chars()
+            .all(|c| c.is_ascii_digit() || "+-.eE".contains(c));
+    numeric
+        .then(|| token.parse::<f64>().ok())
+        .flatten()
+        .and_then(serde_json::Number::from_f64)
+        .map_or_else(|| Value::String(token.to_string()), Value::Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    use crate::api::{DnsRecord, DnsRecordId};
+    use crate::sync::{Change, ZonePlan};
+
+    const TOML: &str = r#"
+domain = "example.com"
+owner = "managed-by=simply-dns"
+
+[ttl]
+default = 3600
+types = { TXT = 300 }
+
+[ignore]
+names = ["_acme-challenge*"]
+
+[[records]]
+name = "@"
+type = "MX"
+data = "mail.example.com"
+priority = 10
+
+[[records]]
+name = "@"
+type = "TXT"
+data = "v=spf1 mx -all"
+comment = "it's # not a comment"
+
+[[records]]
+name = "shop"
+same_as = "@"
+type = "MX"
+"#;
+
+    #[test]
+    fn yaml_reads_like_toml() {
+        let yaml = r#"
+# The zone of example.com.
+---
+domain: example.com
+owner: managed-by=simply-dns
+ttl:
+  default: 3600   # an hour
+  types: { TXT: 300 }
+ignore:
+  names: ["_acme-challenge*"]
+records:
+- name: "@"
+  type: MX
+  data: mail.example.com
+  priority: 10
+
+- name: '@'
+  type: TXT
+  data: "v=spf1 mx -all"
+  comment: 'it''s # not a comment'
+- {name: shop, same_as: "@", type: MX}
+"#;
+        let expected = ZoneConfig::from_toml(TOML).unwrap();
+        assert_eq!(ZoneConfig::from_yaml(yaml).unwrap(), expected);
+    }
+
+    #[test]
+    fn yaml_reads_json() {
+        let json = r#"{
+            "domain": "example.com",
+            "ttl": {"default": 3600},
+            "records": [
+                {"name": "@", "type": "A", "data": "192.0.2.10"},
+                {"name": "www", "type": "TXT", "data": "caf\u00e9 \"quoted\"", "ttl": 60}
+            ]
+        }"#;
+        let config = ZoneConfig::from_yaml(json).unwrap();
+        assert_eq!(config, ZoneConfig::from_json(json).unwrap());
+        assert_eq!(config.zone.records[1].data, "caf\u{e9} \"quoted\"");
+    }
+
+    #[test]
+    fn yaml_nested_sequences_and_scalars() {
+        let value = Yaml::new("a:\n  - - 1\n    - 1.5\n  -\n    b: ~\nc: True\nd: 10.0.0.1\n")
+            .document()
+            .unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"a": [[1, 1.5], {"b": null}], "c": true, "d": "10.0.0.1"})
+        );
+        assert_eq!(Yaml::new("").document().unwrap(), serde_json::json!({}));
+    }
+
+    #[test]
+    fn yaml_anchors_aliases_and_merge_keys() {
+        let value = Yaml::new(
+            "base: &base\n  ttl: 300\n  tags: &tags [a, b]\nfirst:\n  <<: *base\n  ttl: 60\n\
+             second: { <<: [{ x: 1 }, *base], x: 2 }\nlist:\n  - &item one\n  - *item\n  - *tags\n",
+        )
+        .document()
+        .unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "base": {"ttl": 300, "tags": ["a", "b"]},
+                "first": {"ttl": 60, "tags": ["a", "b"]},
+                "second": {"x": 2, "ttl": 300, "tags": ["a", "b"]},
+                "list": ["one", "one", ["a", "b"]],
+            })
+        );
+    }
+
+    #[test]
+    fn yaml_block_scalars() {
+        let value = Yaml::new(
+            "literal: |\n  one\n    two\n\n  three\n\nfolded: >\n  one\n  two\n\n  three\n    \
+             indented\n  four\nstrip: |-\n  text\n\nkeep: |+\n  text\n\n\nlist:\n  - >-\n    a\n    b\n  \
+             - |2\n      x\n     y\nempty: |\nlast: >\n  end",
+        )
+        .document()
+        .unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "literal": "one\n  two\n\nthree\n",
+                "folded": "one two\nthree\n  indented\nfour\n",
+                "strip": "text",
+                "keep": "text\n\n\n",
+                "list": ["a b", "  x\n y\n"],
+                "empty": "",
+                "last": "end\n",
+            })
+        );
+    }
+
+    #[test]
+    fn anchored_records_expand_in_the_plan() {
+        let yaml = r#"
+records:
+  - &web
+    name: "@"
+    type: A
+    data: 192.0.2.10
+    ttl: 300
+  - <<: *web
+    name: www
+  - { <<: *web, name: shop, ttl: 600 }
+  - { name: mail, same_as: www }
+"#;
+        let config = ZoneConfig::from_yaml(yaml).unwrap();
+        let current = vec![DnsRecord {
+            record_id: DnsRecordId::new(1),
+            name: "@".to_string(),
+            ttl: 300,
+            data: "192.0.2.10".to_string(),
+            record_type: "A".to_string(),
+            priority: None,
+            comment: None,
+        }];
+        let plan = ZonePlan::compute("example.com", current, &config.zone);
+        let created: Vec<(&str, &str, Option<u32>)> = plan
+            .changes
+            .iter()
+            .map(|change| match change {
+                Change::Create(r) => (r.name.as_str(), r.data.as_str(), r.ttl),
+                other => panic!("unexpected change {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            created,
+            [
+                ("www", "192.0.2.10", Some(300)),
+                ("shop", "192.0.2.10", Some(600)),
+                ("mail", "192.0.2.10", Some(300)),
+            ]
+        );
+    }
+
+    #[test]
+    fn yaml_errors_name_the_line() {
+        let error = |input: &str| Yaml::new(input).document().unwrap_err().to_string();
+        assert_eq!(error("a: 1\na: 2\n"), "line 2: a is defined twice");
+        assert_eq!(
+            error("a:\n\tb: 1\n"),
+            "line 2: tabs can't be used for indentation"
+        );
+        assert_eq!(error("a: 1\n  b: 2\n"), "line 2: unexpected indentation");
+        assert_eq!(error("a: !!str 1\n"), "line 1: tags aren't supported");
+        assert_eq!(error("a: *x\n"), "line 1: unknown alias *x");
+        assert_eq!(error("a: & 1\n"), "line 1: expected an anchor name");
+        assert_eq!(
+            error("a: 1\nb:\n  <<: 2\n"),
+            "line 3: << needs a mapping or a sequence of mappings"
+        );
+        assert_eq!(
+            error("a: |x\n  text\n"),
+            "line 1: invalid block scalar header"
+        );
+        assert_eq!(error("a: \"open\n"), "line 1: unterminated string");
+        assert_eq!(
+            error("a: 1\n---\nb: 2\n"),
+            "line 2: several documents in one file aren't supported"
+        );
+        assert!(ZoneConfig::from_yaml("records:\n  - name: @\n").is_err());
+    }
+
+    #[test]
+    fn schema_lists_every_key() {
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        let sections = [
+            (json!({ "unknown": 1 }), &schema["properties"]),
+            (
+                json!({ "ttl": { "unknown": 1 } }),
+                &schema["properties"]["ttl"]["properties"],
+            ),
+            (
+                json!({ "ignore": { "unknown": 1 } }),
+                &schema["properties"]["ignore"]["properties"],
+            ),
+            (
+                json!({ "records": [{ "unknown": 1 }] }),
+                &schema["$defs"]["record"]["properties"],
+            ),
+        ];
+        for (document, properties) in sections {
+            // serde names the keys it accepts when it rejects an unknown one.
+            let message = ZoneConfig::from_value(document).unwrap_err().message;
+            let (_, expected) = message.split_once("expected one of ").unwrap();
+            let accepted: Vec<&str> = expected
+                .split(", ")
+                .map(|key| key.trim_matches('`'))
+                .collect();
+            let listed: Vec<&str> = properties
+                .as_object()
+                .unwrap()
+                .keys()
+                .map(String::as_str)
+                .collect();
+            assert_eq!(
+                accepted.iter().collect::<std::collections::BTreeSet<_>>(),
+                listed.iter().collect(),
+                "{message}"
+            );
+        }
+    }
+
+    #[test]
+    fn toml_output_reads_back() {
+        let mut config = ZoneConfig::from_toml(TOML).unwrap();
+        config.zone = config
+            .zone
+            .scoped_to("dev")
+            .ignore(IgnoreRule::RecordType("NS".to_string()))
+            .ignore(IgnoreRule::CommentTag("ddns".to_string()));
+        config.zone.records[1].data = "quotes \" and \\ back\tslashes".to_string();
+        let toml = config.to_toml();
+        assert_eq!(ZoneConfig::from_toml(&toml).unwrap(), config, "{toml}");
+    }
+}
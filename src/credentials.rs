@@ -0,0 +1,594 @@
+//! Loading API credentials.
+//!
+//! [`Credentials`] holds an account and API key. A [`CredentialsProvider`]
+//! looks them up somewhere: [`EnvCredentials`] in `SIMPLY_ACCOUNT` and
+//! `SIMPLY_API_KEY`, [`FileCredentials`] in `~/.config/simply-dns/credentials`,
+//! and, with the `keyring` feature, [`KeyringCredentials`] in the OS keyring.
+//! [`CredentialsChain`] tries several in turn, so every tool built on the crate
+//! can find credentials the same way.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::credentials::CredentialsChain;
+//!
+//! let client = SimplyClient::builder()
+//!     .credentials_provider(&CredentialsChain::default())?
+//!     .build()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+/// Error returned when credentials cannot be loaded.
+#[derive(Debug, Error)]
+pub enum CredentialsError {
+    /// The provider has no credentials; a [`CredentialsChain`] moves on to the next one.
+    #[error("no credentials: {0}")]
+    NotFound(String),
+    /// A credentials file could not be read.
+    #[error("{}: {source}", path.display())]
+    Io {
+        /// The file that could not be read.
+        path: PathBuf,
+        /// The underlying error.
+        source: std::io::Error,
+    },
+    /// A credentials file is malformed.
+    #[error("{}:{line}: {message}", path.display())]
+    Invalid {
+        /// The malformed file.
+        path: PathBuf,
+        /// The 1-based line number of the problem.
+        line: usize,
+        /// What was wrong.
+        message: String,
+    },
+    /// The OS keyring could not be queried.
+    #[error("keyring error: {0}")]
+    Keyring(String),
+}
+
+/// A secret, such as an API key, that is left out of `Debug` and `Display` output.
+///
+/// With the `zeroize` feature, the memory holding the secret is overwritten
+/// when it is dropped.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::credentials::SecretString;
+///
+/// let key = SecretString::new("0123456789abcdef");
+/// assert_eq!(format!("{key:?}"), "[redacted]");
+/// assert_eq!(key.expose_secret(), "0123456789abcdef");
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SecretString(String);
+
+impl SecretString {
+    /// Wrap `secret`.
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(secret.into())
+    }
+
+    /// The secret itself, e.g. to send it to the API.
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_string())
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+impl fmt::Display for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[redacted]")
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.0);
+    }
+}
+
+/// An account identifier and API key.
+///
+/// The API key is a [`SecretString`], so it is left out of the `Debug` output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    account: String,
+    api_key: SecretString,
+}
+
+impl Credentials {
+    /// Credentials for `account`, authenticating with `api_key`.
+    pub fn new(account: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self {
+            account: account.into(),
+            api_key: SecretString::new(api_key),
+        }
+    }
+
+    /// The account identifier, e.g. "S123456".
+    pub fn account(&self) -> &str {
+        &self.account
+    }
+
+    /// The API key.
+    pub fn api_key(&self) -> &str {
+        self.api_key.expose_secret()
+    }
+}
+
+/// A source of [`Credentials`].
+pub trait CredentialsProvider: Send + Sync {
+    /// Look up the credentials.
+    ///
+    /// Returns [`CredentialsError::NotFound`] if this provider has none, so
+    /// that a [`CredentialsChain`] can try the next one.
+    fn credentials(&self) -> Result<Credentials, CredentialsError>;
+}
+
+impl CredentialsProvider for Credentials {
+    fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        Ok(self.clone())
+    }
+}
+
+/// Credentials from environment variables, by default `SIMPLY_ACCOUNT` and
+/// `SIMPLY_API_KEY`.
+#[derive(Debug, Clone)]
+pub struct EnvCredentials {
+    account_var: String,
+    api_key_var: String,
+}
+
+impl EnvCredentials {
+    /// Read `SIMPLY_ACCOUNT` and `SIMPLY_API_KEY`.
+    pub fn new() -> Self {
+        Self::with_vars("SIMPLY_ACCOUNT", "SIMPLY_API_KEY")
+    }
+
+    /// Read the account from `account_var` and the API key from `api_key_var`.
+    pub fn with_vars(account_var: impl Into<String>, api_key_var: impl Into<String>) -> Self {
+        Self {
+            account_var: account_var.into(),
+            api_key_var: api_key_var.into(),
+        }
+    }
+}
+
+impl Default for EnvCredentials {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CredentialsProvider for EnvCredentials {
+    fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| CredentialsError::NotFound(format!("{name} is not set")))
+        };
+        Ok(Credentials::new(
+            var(&self.account_var)?,
+            var(&self.api_key_var)?,
+        ))
+    }
+}
+
+/// Credentials from a file of `account = ...` and `api_key = ...` lines.
+///
+/// The format is the common subset of INI and TOML: values may be quoted,
+/// lines starting with `#` or `;` are comments, and `[profile]` sections hold
+/// alternative credentials, e.g. for several accounts. Keys before the first
+/// section belong to the `default` profile; other keys are ignored.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::credentials::{CredentialsProvider, FileCredentials};
+///
+/// let path = std::env::temp_dir().join("simply-dns-credentials-example");
+/// std::fs::write(&path, "account = \"S123456\"\napi_key = \"secret\"\n\n[staging]\naccount = S654321\napi_key = other\n")?;
+/// let credentials = FileCredentials::new(&path).profile("staging").credentials()?;
+/// assert_eq!(credentials.account(), "S654321");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileCredentials {
+    path: PathBuf,
+    profile: String,
+    required: bool,
+}
+
+impl FileCredentials {
+    /// Read the `default` profile of the file at `path`, which must exist.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            profile: "default".to_string(),
+            required: true,
+        }
+    }
+
+    /// Read `$SIMPLY_DNS_CREDENTIALS`, or `~/.config/simply-dns/credentials`
+    /// if that isn't set. A missing file counts as no credentials.
+    pub fn default_location() -> Self {
+        let path = std::env::var_os("SIMPLY_DNS_CREDENTIALS")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .map(|home| PathBuf::from(home).join(".config/simply-dns/credentials"))
+            })
+            .unwrap_or_default();
+        Self::new(path).required(false)
+    }
+
+    /// Choose whether a missing file is an error, or counts as no credentials.
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Read the section `profile` instead of `default`.
+    pub fn profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = profile.into();
+        self
+    }
+
+    /// The file this provider reads.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl CredentialsProvider for FileCredentials {
+    fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        let input = match std::fs::read_to_string(&self.path) {
+            Ok(input) => input,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !self.required => {
+                return Err(CredentialsError::NotFound(format!(
+                    "{} does not exist",
+                    self.path.display()
+                )));
+            }
+            Err(source) => {
+                return Err(CredentialsError::Io {
+                    path: self.path.clone(),
+                    source,
+                });
+            }
+        };
+        self.parse(&input)
+    }
+}
+
+impl FileCredentials {
+    /// The credentials of the profile in `input`, the contents of the file.
+    fn parse(&self, input: &str) -> Result<Credentials, CredentialsError> {
+        let mut values = section_values(&self.path, input, &self.profile)?;
+        match (values.remove("account"), values.remove("api_key")) {
+            (Some(account), Some(api_key)) => Ok(Credentials::new(account, api_key)),
+            _ => Err(CredentialsError::NotFound(format!(
+                "{} has no account and api_key in profile {}",
+                self.path.display(),
+                self.profile
+            ))),
+        }
+    }
+}
+
+/// The keys and unquoted values of the section `name` of `input`, a file in
+/// the format [`FileCredentials`] reads from `path`.
+pub(crate) fn section_values(
+    path: &Path,
+    input: &str,
+    name: &str,
+) -> Result<HashMap<String, String>, CredentialsError> {
+    let invalid = |line: usize, message: String| CredentialsError::Invalid {
+        path: path.to_path_buf(),
+        line,
+        message,
+    };
+    let (mut section, mut values) = ("default".to_string(), HashMap::new());
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header
+                .strip_suffix(']')
+                .ok_or_else(|| invalid(i + 1, format!("invalid section {line:?}")))?;
+            section = header.trim().trim_matches('"').to_string();
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid(i + 1, format!("invalid line {line:?}")))?;
+        if section != name {
+            continue;
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+            .unwrap_or(value);
+        values.insert(key.trim().to_string(), value.to_string());
+    }
+    Ok(values)
+}
+
+/// Credentials from the OS keyring, stored under a service name for an account.
+///
+/// Uses `security` on macOS and `secret-tool` (libsecret) on Linux and the
+/// BSDs; on other platforms, such as Windows, looking the key up fails with
+/// [`CredentialsError::Keyring`]. Store a key with e.g. `secret-tool store
+/// --label "Simply.com API key" service simply-dns account S123456` or
+/// `security add-generic-password -s simply-dns -a S123456 -w`.
+#[cfg(feature = "keyring")]
+#[derive(Debug, Clone)]
+pub struct KeyringCredentials {
+    service: String,
+    account: String,
+}
+
+#[cfg(feature = "keyring")]
+impl KeyringCredentials {
+    /// Look up the API key of `account` under the service `simply-dns`.
+    pub fn new(account: impl Into<String>) -> Self {
+        Self {
+            service: "simply-dns".to_string(),
+            account: account.into(),
+        }
+    }
+
+    /// Look up the key under `service` instead of `simply-dns`.
+    pub fn service(mut self, service: impl Into<String>) -> Self {
+        self.service = service.into();
+        self
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl CredentialsProvider for KeyringCredentials {
+    fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        match keyring_secret(&self.service, &self.account)? {
+            Some(api_key) => Ok(Credentials::new(&self.account, api_key)),
+            None => Err(CredentialsError::NotFound(format!(
+                "no key for {} in the keyring service {}",
+                self.account, self.service
+            ))),
+        }
+    }
+}
+
+/// The secret stored in the OS keyring under `service` for `account`, if any.
+#[cfg(all(
+    feature = "keyring",
+    any(
+        target_os = "macos",
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    )
+))]
+pub(crate) fn keyring_secret(
+    service: &str,
+    account: &str,
+) -> Result<Option<String>, CredentialsError> {
+    use std::process::{Command, Stdio};
+
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("security");
+        command.args(["find-generic-password", "-w", "-s", service, "-a", account]);
+        command
+    };
+    #[cfg(not(target_os = "macos"))]
+    let mut command = {
+        let mut command = Command::new("secret-tool");
+        command.args(["lookup", "service", service, "account", account]);
+        command
+    };
+    let output = command
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| {
+            CredentialsError::Keyring(format!("{}: {e}", command.get_program().display()))
+        })?;
+    let secret = String::from_utf8(output.stdout)
+        .map_err(|_| CredentialsError::Keyring("the stored key is not UTF-8".to_string()))?;
+    let secret = secret.trim_end_matches(['\r', '\n']);
+    if !output.status.success() || secret.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(secret.to_string()))
+}
+
+/// The secret stored in the OS keyring under `service` for `account`, if any.
+#[cfg(all(
+    feature = "keyring",
+    not(any(
+        target_os = "macos",
+        target_os = "linux",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd"
+    ))
+))]
+pub(crate) fn keyring_secret(_: &str, _: &str) -> Result<Option<String>, CredentialsError> {
+    Err(CredentialsError::Keyring(format!(
+        "the OS keyring is not supported on {}, only on macOS, Linux and the BSDs",
+        std::env::consts::OS
+    )))
+}
+
+/// Tries providers in order, returning the first credentials found.
+///
+/// The default chain reads [`EnvCredentials`], then
+/// [`FileCredentials::default_location`].
+pub struct CredentialsChain {
+    providers: Vec<Box<dyn CredentialsProvider>>,
+}
+
+impl CredentialsChain {
+    /// A chain without providers.
+    pub fn new() -> Self {
+        Self {
+            providers: Vec::new(),
+        }
+    }
+
+    /// Try `provider` after the ones added before it.
+    pub fn with(mut self, provider: impl CredentialsProvider + 'static) -> Self {
+        self.providers.push(Box::new(provider));
+        self
+    }
+}
+
+impl Default for CredentialsChain {
+    fn default() -> Self {
+        Self::new()
+            .with(EnvCredentials::new())
+            .with(FileCredentials::default_location())
+    }
+}
+
+impl fmt::Debug for CredentialsChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CredentialsChain({} providers)", self.providers.len())
+    }
+}
+
+impl CredentialsProvider for CredentialsChain {
+    fn credentials(&self) -> Result<Credentials, CredentialsError> {
+        let mut reasons = Vec::new();
+        for provider in &self.providers {
+            match provider.credentials() {
+                Err(CredentialsError::NotFound(reason)) => reasons.push(reason),
+                result => return result,
+            }
+        }
+        Err(CredentialsError::NotFound(if reasons.is_empty() {
+            "no providers configured".to_string()
+        } else {
+            reasons.join("; ")
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(profile: &str, input: &str) -> Result<Credentials, CredentialsError> {
+        FileCredentials::new("credentials")
+            .profile(profile)
+            .parse(input)
+    }
+
+    #[test]
+    fn files_skip_comments_and_blank_lines() {
+        let input = "# Simply.com\n; legacy comment\n\n  account = S123456\napi_key = secret\n";
+        let credentials = parse("default", input).unwrap();
+        assert_eq!(credentials.account(), "S123456");
+        assert_eq!(credentials.api_key(), "secret");
+    }
+
+    #[test]
+    fn values_may_be_quoted() {
+        for (input, api_key) in [
+            ("api_key = \"with spaces \"", "with spaces "),
+            ("api_key = 'single'", "single"),
+            ("api_key=bare", "bare"),
+            ("api_key = \"unterminated", "\"unterminated"),
+            ("api_key = a=b", "a=b"),
+        ] {
+            let input = format!("account = \"S1\"\n{input}\n");
+            let credentials = parse("default", &input).unwrap();
+            assert_eq!(credentials.account(), "S1");
+            assert_eq!(credentials.api_key(), api_key, "{input}");
+        }
+    }
+
+    #[test]
+    fn profiles_are_read_from_their_section() {
+        let input = "account = S1\napi_key = one\n[\"staging\"]\naccount = S2\n\
+                     api_key = two\nregion = eu\n[other]\naccount = S3\n";
+        assert_eq!(parse("default", input).unwrap().account(), "S1");
+        let staging = parse("staging", input).unwrap();
+        assert_eq!((staging.account(), staging.api_key()), ("S2", "two"));
+    }
+
+    #[test]
+    fn missing_keys_mean_no_credentials() {
+        for (profile, input) in [
+            ("default", "account = S1\n"),
+            ("default", "api_key = secret\n"),
+            ("default", "[staging]\naccount = S1\napi_key = secret\n"),
+            ("other", "account = S1\napi_key = secret\n"),
+            ("default", ""),
+        ] {
+            assert!(
+                matches!(parse(profile, input), Err(CredentialsError::NotFound(_))),
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_lines_are_reported_with_their_number() {
+        for (input, bad_line) in [
+            ("account = S1\n[staging\n", 2),
+            ("# comment\naccount S1\n", 2),
+        ] {
+            match parse("default", input) {
+                Err(CredentialsError::Invalid { line, .. }) => assert_eq!(line, bad_line),
+                other => panic!("{input:?} gave {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn missing_files_are_only_an_error_if_required() {
+        let path = std::env::temp_dir().join(format!(
+            "simply-dns-credentials-missing-{}",
+            std::process::id()
+        ));
+        assert!(matches!(
+            FileCredentials::new(&path).required(false).credentials(),
+            Err(CredentialsError::NotFound(_))
+        ));
+        assert!(matches!(
+            FileCredentials::new(&path).credentials(),
+            Err(CredentialsError::Io { .. })
+        ));
+    }
+}
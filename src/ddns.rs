@@ -0,0 +1,580 @@
+//! Dynamic DNS support.
+//!
+//! [`DdnsUpdater`] detects the machine's current public address and keeps an
+//! `A`/`AAAA` record pointed at it, only calling the API when the address changed.
+
+use std::convert::Infallible;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio::net::UdpSocket;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord, UpdateDnsRecordRequest};
+use crate::leader::{LeaderElection, LeaderError};
+use crate::retry::random_u64;
+use crate::state::{StateError, StateStore};
+use crate::{SimplyClient, SimplyClientError};
+
+const STUN_MAGIC_COOKIE: u32 = 0x2112_A442;
+const DETECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Error type for the dynamic DNS updater.
+#[derive(Debug, Error)]
+pub enum DdnsError {
+    /// The Simply.com API call failed.
+    #[error(transparent)]
+    Client(#[from] SimplyClientError),
+    /// None of the configured detectors could determine the public address.
+    #[error("could not detect public {family} address: {reason}")]
+    Detection {
+        /// The address family that was being detected ("IPv4" or "IPv6").
+        family: &'static str,
+        /// The error reported by the last detector that was tried.
+        reason: String,
+    },
+    /// Reading or writing the last published address failed.
+    #[error("state error: {0}")]
+    State(#[from] StateError),
+    /// Checking or renewing leadership failed.
+    #[error("leader election error: {0}")]
+    Leader(#[from] LeaderError),
+}
+
+/// Address family handled by the updater.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpFamily {
+    /// IPv4, published as an `A` record.
+    V4,
+    /// IPv6, published as an `AAAA` record.
+    V6,
+}
+
+impl IpFamily {
+    /// The DNS record type used for this address family.
+    pub fn record_type(self) -> &'static str {
+        match self {
+            IpFamily::V4 => "A",
+            IpFamily::V6 => "AAAA",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            IpFamily::V4 => "IPv4",
+            IpFamily::V6 => "IPv6",
+        }
+    }
+
+    fn matches(self, ip: &IpAddr) -> bool {
+        matches!(
+            (self, ip),
+            (IpFamily::V4, IpAddr::V4(_)) | (IpFamily::V6, IpAddr::V6(_))
+        )
+    }
+}
+
+/// Which address families a [`DdnsUpdater`] publishes, see [`DdnsUpdater::dual_stack`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DualStackPolicy {
+    /// Publish the IPv4 address as an `A` record, leaving `AAAA` records alone.
+    #[default]
+    V4Only,
+    /// Publish the IPv6 address as an `AAAA` record, leaving `A` records alone.
+    V6Only,
+    /// Publish both addresses independently. If only one family can be
+    /// detected, the records of the other are removed.
+    Both,
+    /// Publish only the IPv6 address if there is one, and only the IPv4
+    /// address otherwise, removing the records of the other family.
+    PreferV6,
+}
+
+/// A strategy for finding the machine's current public address.
+#[derive(Debug, Clone)]
+pub enum IpDetector {
+    /// An HTTPS "what's my IP" endpoint returning the address as plain text.
+    ///
+    /// The same URL is used for both families; use separate detectors (e.g.
+    /// `https://api.ipify.org` and `https://api6.ipify.org`) if the service
+    /// has family-specific hosts.
+    Https(String),
+    /// A STUN server given as `host:port`, e.g. `stun.l.google.com:19302`.
+    Stun(String),
+    /// The address of the local interface used for outbound traffic.
+    ///
+    /// No packets are sent. Behind NAT this is the private address, so this
+    /// detector is mainly useful on hosts with a public address assigned.
+    LocalInterface,
+}
+
+impl IpDetector {
+    /// The detectors used when none are configured: ipify over HTTPS, then Google's STUN server.
+    pub fn defaults() -> Vec<IpDetector> {
+        vec![
+            IpDetector::Https("https://api.ipify.org".to_string()),
+            IpDetector::Https("https://api6.ipify.org".to_string()),
+            IpDetector::Stun("stun.l.google.com:19302".to_string()),
+        ]
+    }
+
+    async fn detect(&self, family: IpFamily, http: &reqwest::Client) -> Result<IpAddr, String> {
+        let ip = match self {
+            IpDetector::Https(url) => detect_https(url, http).await?,
+            IpDetector::Stun(server) => detect_stun(server, family).await?,
+            IpDetector::LocalInterface => detect_local(family).await?,
+        };
+        if family.matches(&ip) {
+            Ok(ip)
+        } else {
+            Err(format!(
+                "{self:?} returned {ip}, not an {} address",
+                family.label()
+            ))
+        }
+    }
+}
+
+async fn detect_https(url: &str, http: &reqwest::Client) -> Result<IpAddr, String> {
+    let body = http
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| e.to_string())?
+        .text()
+        .await
+        .map_err(|e| e.to_string())?;
+    body.trim()
+        .parse()
+        .map_err(|_| format!("{url} returned an invalid address: {:?}", body.trim()))
+}
+
+async fn bind_for(family: IpFamily) -> std::io::Result<UdpSocket> {
+    match family {
+        IpFamily::V4 => UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await,
+        IpFamily::V6 => UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0)).await,
+    }
+}
+
+async fn detect_local(family: IpFamily) -> Result<IpAddr, String> {
+    let target: SocketAddr = match family {
+        IpFamily::V4 => (Ipv4Addr::new(8, 8, 8, 8), 53).into(),
+        IpFamily::V6 => (
+            Ipv6Addr::new(0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8888),
+            53,
+        )
+            .into(),
+    };
+    let socket = bind_for(family).await.map_err(|e| e.to_string())?;
+    socket.connect(target).await.map_err(|e| e.to_string())?;
+    Ok(socket.local_addr().map_err(|e| e.to_string())?.ip())
+}
+
+async fn detect_stun(server: &str, family: IpFamily) -> Result<IpAddr, String> {
+    let addr = tokio::net::lookup_host(server)
+        .await
+        .map_err(|e| e.to_string())?
+        .find(|a| family.matches(&a.ip()))
+        .ok_or_else(|| format!("{server} has no {} address", family.label()))?;
+    let socket = bind_for(family).await.map_err(|e| e.to_string())?;
+
+    let transaction_id = transaction_id();
+    let mut request = Vec::with_capacity(20);
+    request.extend_from_slice(&0x0001u16.to_be_bytes()); // Binding Request
+    request.extend_from_slice(&0u16.to_be_bytes());
+    request.extend_from_slice(&STUN_MAGIC_COOKIE.to_be_bytes());
+    request.extend_from_slice(&transaction_id);
+    socket
+        .send_to(&request, addr)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut buf = [0u8; 512];
+    let len = tokio::time::timeout(DETECT_TIMEOUT, socket.recv(&mut buf))
+        .await
+        .map_err(|_| format!("no response from STUN server {server}"))?
+        .map_err(|e| e.to_string())?;
+    parse_stun_response(&buf[..len], &transaction_id)
+        .ok_or_else(|| format!("invalid response from STUN server {server}"))
+}
+
+fn transaction_id() -> [u8; 12] {
+    let mut id = [0u8; 12];
+    for chunk in id.chunks_mut(8) {
+        chunk.copy_from_slice(&random_u64().to_be_bytes()[..chunk.len()]);
+    }
+    id
+}
+
+fn parse_stun_response(msg: &[u8], transaction_id: &[u8; 12]) -> Option<IpAddr> {
+    if msg.len() < 20 || msg[0..2] != [0x01, 0x01] || &msg[8..20] != transaction_id {
+        return None;
+    }
+    let mut attrs = &msg[20..];
+    while attrs.len() >= 4 {
+        let kind = u16::from_be_bytes([attrs[0], attrs[1]]);
+        let len = u16::from_be_bytes([attrs[2], attrs[3]]) as usize;
+        let value = attrs.get(4..4 + len)?;
+        match kind {
+            // XOR-MAPPED-ADDRESS
+            0x0020 => return parse_stun_address(value, Some(&msg[4..20])),
+            // MAPPED-ADDRESS
+            0x0001 => return parse_stun_address(value, None),
+            _ => {}
+        }
+        // Attributes are padded to a multiple of four bytes.
+        attrs = attrs.get(4 + len.div_ceil(4) * 4..).unwrap_or_default();
+    }
+    None
+}
+
+fn parse_stun_address(value: &[u8], xor_key: Option<&[u8]>) -> Option<IpAddr> {
+    let key = |i: usize| xor_key.map_or(0, |k| k[i]);
+    match value.get(1)? {
+        0x01 => {
+            let raw = value.get(4..8)?;
+            let octets: [u8; 4] = std::array::from_fn(|i| raw[i] ^ key(i));
+            Some(IpAddr::V4(Ipv4Addr::from(octets)))
+        }
+        0x02 => {
+            let raw = value.get(4..20)?;
+            let octets: [u8; 16] = std::array::from_fn(|i| raw[i] ^ key(i));
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        }
+        _ => None,
+    }
+}
+
+/// The result of checking one address family during [`DdnsUpdater::update_once`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DdnsUpdate {
+    /// The record type that was checked ("A" or "AAAA").
+    pub record_type: &'static str,
+    /// The address that was published, or `None` if the family's records
+    /// were removed because the family has no address under the
+    /// [`DualStackPolicy`].
+    pub address: Option<IpAddr>,
+    /// Whether records had to be created, updated or removed.
+    pub changed: bool,
+}
+
+/// Keeps an `A` and/or `AAAA` record pointed at the machine's public address.
+///
+/// Example usage:
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use simply_dns::SimplyClient;
+/// use simply_dns::ddns::DdnsUpdater;
+///
+/// # async fn run() -> Result<(), simply_dns::ddns::DdnsError> {
+/// let client = SimplyClient::new("account", "api_key");
+/// let updater = DdnsUpdater::new(&client, "example.com", "home").ipv6(true);
+/// updater.run_loop(Duration::from_secs(300)).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct DdnsUpdater<'a> {
+    client: &'a SimplyClient,
+    domain: String,
+    name: String,
+    detectors: Vec<IpDetector>,
+    ipv4: bool,
+    ipv6: bool,
+    policy: Option<DualStackPolicy>,
+    ttl: Option<u32>,
+    state: Option<Arc<dyn StateStore>>,
+    leader: Option<LeaderElection<'a>>,
+    http: reqwest::Client,
+}
+
+impl<'a> DdnsUpdater<'a> {
+    /// Create an updater for the record `name` under `domain`.
+    ///
+    /// By default only the IPv4 address is tracked, using [`IpDetector::defaults`].
+    pub fn new(
+        client: &'a SimplyClient,
+        domain: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            domain: domain.into(),
+            name: name.into(),
+            detectors: IpDetector::defaults(),
+            ipv4: true,
+            ipv6: false,
+            policy: None,
+            ttl: None,
+            state: None,
+            leader: None,
+            http: reqwest::Client::builder()
+                .timeout(DETECT_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Replace the detectors, which are tried in order until one succeeds.
+    pub fn detectors(mut self, detectors: Vec<IpDetector>) -> Self {
+        self.detectors = detectors;
+        self
+    }
+
+    /// Enable or disable tracking of the IPv4 address (`A` record).
+    ///
+    /// Every enabled family must be detected, or the update fails. Ignored
+    /// once a [`dual_stack`](Self::dual_stack) policy is set.
+    pub fn ipv4(mut self, enabled: bool) -> Self {
+        self.ipv4 = enabled;
+        self
+    }
+
+    /// Enable or disable tracking of the IPv6 address (`AAAA` record).
+    ///
+    /// Every enabled family must be detected, or the update fails. Ignored
+    /// once a [`dual_stack`](Self::dual_stack) policy is set.
+    pub fn ipv6(mut self, enabled: bool) -> Self {
+        self.ipv6 = enabled;
+        self
+    }
+
+    /// Choose the published address families by `policy`, replacing
+    /// [`ipv4`](Self::ipv4) and [`ipv6`](Self::ipv6).
+    ///
+    /// With [`Both`](DualStackPolicy::Both) and
+    /// [`PreferV6`](DualStackPolicy::PreferV6), a family that disappears,
+    /// e.g. when the ISP stops handing out IPv6 addresses, has its records
+    /// removed instead of failing the update, so the name never points at a
+    /// stale address. The update only fails if no address can be detected.
+    pub fn dual_stack(mut self, policy: DualStackPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// TTL to set when the record is created or updated.
+    pub fn ttl(mut self, ttl: u32) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Remember the last published addresses in `state`.
+    ///
+    /// While the detected address matches the remembered one, the updater skips
+    /// listing the zone, saving an API call per check. Changes made to the record
+    /// outside of the updater are then only corrected once the address changes.
+    pub fn state_store(mut self, state: Arc<dyn StateStore>) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Only update while this instance is the leader of `election`.
+    ///
+    /// [`run_loop`](Self::run_loop) checks leadership before every update and
+    /// stands by while another instance leads.
+    pub fn leader(mut self, election: LeaderElection<'a>) -> Self {
+        self.leader = Some(election);
+        self
+    }
+
+    fn state_key(&self, family: IpFamily) -> String {
+        format!(
+            "ddns/{}/{}/{}",
+            self.domain,
+            self.name,
+            family.record_type()
+        )
+    }
+
+    /// Detect the current public address for `family`, trying each detector in order.
+    pub async fn detect(&self, family: IpFamily) -> Result<IpAddr, DdnsError> {
+        let mut reason = "no detectors configured".to_string();
+        for detector in &self.detectors {
+            match detector.detect(family, &self.http).await {
+                Ok(ip) => return Ok(ip),
+                Err(e) => reason = e,
+            }
+        }
+        Err(DdnsError::Detection {
+            family: family.label(),
+            reason,
+        })
+    }
+
+    /// The address to publish per family, `None` for families whose records
+    /// are to be removed.
+    async fn targets(&self) -> Result<Vec<(IpFamily, Option<IpAddr>)>, DdnsError> {
+        let mut targets = Vec::new();
+        match self.policy {
+            None => {
+                let families = [(self.ipv4, IpFamily::V4), (self.ipv6, IpFamily::V6)];
+                for (_, family) in families.into_iter().filter(|(enabled, _)| *enabled) {
+                    targets.push((family, Some(self.detect(family).await?)));
+                }
+            }
+            Some(DualStackPolicy::V4Only) => {
+                targets.push((IpFamily::V4, Some(self.detect(IpFamily::V4).await?)));
+            }
+            Some(DualStackPolicy::V6Only) => {
+                targets.push((IpFamily::V6, Some(self.detect(IpFamily::V6).await?)));
+            }
+            Some(DualStackPolicy::Both) => {
+                match (
+                    self.detect(IpFamily::V4).await,
+                    self.detect(IpFamily::V6).await,
+                ) {
+                    (Err(e), Err(_)) => return Err(e),
+                    (v4, v6) => {
+                        targets.push((IpFamily::V4, v4.ok()));
+                        targets.push((IpFamily::V6, v6.ok()));
+                    }
+                }
+            }
+            Some(DualStackPolicy::PreferV6) => match self.detect(IpFamily::V6).await {
+                Ok(v6) => {
+                    targets.push((IpFamily::V6, Some(v6)));
+                    targets.push((IpFamily::V4, None));
+                }
+                Err(_) => {
+                    targets.push((IpFamily::V4, Some(self.detect(IpFamily::V4).await?)));
+                    targets.push((IpFamily::V6, None));
+                }
+            },
+        }
+        Ok(targets)
+    }
+
+    /// Detect the public address(es) and update the records that are out of date.
+    pub async fn update_once(&self) -> Result<Vec<DdnsUpdate>, DdnsError> {
+        let mut detected = self.targets().await?;
+
+        let mut updates = Vec::new();
+        if let Some(state) = &self.state {
+            let mut unpublished = Vec::new();
+            for (family, address) in detected {
+                // Removed families are remembered as `null`.
+                if state.get_json::<Option<IpAddr>>(&self.state_key(family))? == Some(address) {
+                    updates.push(DdnsUpdate {
+                        record_type: family.record_type(),
+                        address,
+                        changed: false,
+                    });
+                } else {
+                    unpublished.push((family, address));
+                }
+            }
+            detected = unpublished;
+        }
+        if detected.is_empty() {
+            return Ok(updates);
+        }
+
+        let lock = self.client.lock_domain(&self.domain).await?;
+        let client = lock.client();
+        let mut records = client.list_dns_records(&self.domain).await?;
+        for (family, address) in detected {
+            let record_type = family.record_type();
+            let Some(address) = address else {
+                let changed = self.remove_family(client, &mut records, family).await?;
+                if let Some(state) = &self.state {
+                    state.put_json(&self.state_key(family), &None::<IpAddr>)?;
+                }
+                updates.push(DdnsUpdate {
+                    record_type,
+                    address: None,
+                    changed,
+                });
+                continue;
+            };
+            let data = address.to_string();
+            let existing = records.iter().position(|r| {
+                r.name.eq_ignore_ascii_case(&self.name)
+                    && r.record_type.eq_ignore_ascii_case(record_type)
+            });
+            let changed = match existing {
+                Some(i) if records[i].data == data => false,
+                Some(i) => {
+                    let record = records.swap_remove(i);
+                    let req = UpdateDnsRecordRequest {
+                        record_type: record_type.to_string(),
+                        name: self.name.clone(),
+                        data,
+                        priority: None,
+                        ttl: self.ttl.or(Some(record.ttl)),
+                        comment: record.comment,
+                    };
+                    client
+                        .update_dns_record(&self.domain, record.record_id, req)
+                        .await?;
+                    true
+                }
+                None => {
+                    let req = CreateDnsRecordRequest {
+                        record_type: record_type.to_string(),
+                        name: self.name.clone(),
+                        data,
+                        priority: None,
+                        ttl: self.ttl,
+                        comment: None,
+                    };
+                    client.create_dns_record(&self.domain, req).await?;
+                    true
+                }
+            };
+            if let Some(state) = &self.state {
+                state.put_json(&self.state_key(family), &address)?;
+            }
+            updates.push(DdnsUpdate {
+                record_type,
+                address: Some(address),
+                changed,
+            });
+        }
+        Ok(updates)
+    }
+
+    /// Delete the records of `family` at the name through `client`, returning
+    /// whether there were any.
+    async fn remove_family(
+        &self,
+        client: &SimplyClient,
+        records: &mut Vec<DnsRecord>,
+        family: IpFamily,
+    ) -> Result<bool, DdnsError> {
+        let mut removed = false;
+        for record in records.extract_if(.., |r| {
+            r.name.eq_ignore_ascii_case(&self.name)
+                && r.record_type.eq_ignore_ascii_case(family.record_type())
+        }) {
+            match client
+                .delete_dns_record(&self.domain, record.record_id)
+                .await
+            {
+                // Someone else removed it in the meantime.
+                Ok(()) | Err(SimplyClientError::NotFound { .. }) => removed = true,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Run [`update_once`](Self::update_once) every `interval`, forever.
+    ///
+    /// Returns on the first error so the caller can decide whether to log and restart.
+    /// With a [`leader`](Self::leader) election configured, ticks where this
+    /// instance isn't the leader are skipped.
+    pub async fn run_loop(&self, interval: Duration) -> Result<Infallible, DdnsError> {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Some(election) = &self.leader
+                && !election.try_acquire().await?
+            {
+                continue;
+            }
+            self.update_once().await?;
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord, DnsRecordId, UpdateDnsRecordRequest};
+use crate::report::OperationReport;
+use crate::sync::{Change, ZonePlan};
+use crate::{SimplyClient, SimplyClientError};
+
+/// The DNS operations of the Simply.com API.
+///
+/// Implemented by [`SimplyClient`] and, with the `mock` feature, by the in-memory
+/// [`MockSimplyClient`](crate::mock::MockSimplyClient). Write automation against
+/// this trait to unit-test it without calling the real API.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::SimplyDnsApi;
+/// use simply_dns::api::DnsRecord;
+///
+/// async fn a_records(
+///     api: &impl SimplyDnsApi,
+///     domain: &str,
+/// ) -> Result<Vec<DnsRecord>, simply_dns::SimplyClientError> {
+///     let records = api.list_dns_records(domain).await?;
+///     Ok(records.into_iter().filter(|r| r.record_type == "A").collect())
+/// }
+/// ```
+pub trait SimplyDnsApi: Send + Sync {
+    /// List the names of the domains whose DNS can be managed.
+    ///
+    /// See [`SimplyClient::list_domains`].
+    fn list_domains(&self) -> impl Future<Output = Result<Vec<String>, SimplyClientError>> + Send;
+
+    /// List all DNS records for a given domain.
+    ///
+    /// See [`SimplyClient::list_dns_records`].
+    fn list_dns_records(
+        &self,
+        domain: &str,
+    ) -> impl Future<Output = Result<Vec<DnsRecord>, SimplyClientError>> + Send;
+
+    /// Get a single DNS record of a domain by its ID.
+    ///
+    /// See [`SimplyClient::get_dns_record`].
+    fn get_dns_record(
+        &self,
+        domain: &str,
+        record_id: &DnsRecordId,
+    ) -> impl Future<Output = Result<Option<DnsRecord>, SimplyClientError>> + Send;
+
+    /// Create a new DNS record for a domain.
+    ///
+    /// See [`SimplyClient::create_dns_record`].
+    fn create_dns_record(
+        &self,
+        domain: &str,
+        req: CreateDnsRecordRequest,
+    ) -> impl Future<Output = Result<Vec<DnsRecordId>, SimplyClientError>> + Send;
+
+    /// Update an existing DNS record for a domain.
+    ///
+    /// See [`SimplyClient::update_dns_record`].
+    fn update_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+    ) -> impl Future<Output = Result<(), SimplyClientError>> + Send;
+
+    /// Delete a DNS record for a domain.
+    ///
+    /// See [`SimplyClient::delete_dns_record`].
+    fn delete_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+    ) -> impl Future<Output = Result<(), SimplyClientError>> + Send;
+
+    /// Execute the changes of `plan` in order.
+    ///
+    /// See [`SimplyClient::apply`], which holds the [domain lock](crate::lock)
+    /// while doing so. The default runs [`sync::apply`](crate::sync::apply).
+    fn apply_plan(&self, plan: ZonePlan) -> impl Future<Output = OperationReport<Change>> + Send {
+        crate::sync::apply(self, plan)
+    }
+}
+
+impl SimplyDnsApi for SimplyClient {
+    fn list_domains(&self) -> impl Future<Output = Result<Vec<String>, SimplyClientError>> + Send {
+        SimplyClient::list_domains(self)
+    }
+
+    fn list_dns_records(
+        &self,
+        domain: &str,
+    ) -> impl Future<Output = Result<Vec<DnsRecord>, SimplyClientError>> + Send {
+        SimplyClient::list_dns_records(self, domain)
+    }
+
+    fn get_dns_record(
+        &self,
+        domain: &str,
+        record_id: &DnsRecordId,
+    ) -> impl Future<Output = Result<Option<DnsRecord>, SimplyClientError>> + Send {
+        SimplyClient::get_dns_record(self, domain, record_id)
+    }
+
+    fn create_dns_record(
+        &self,
+        domain: &str,
+        req: CreateDnsRecordRequest,
+    ) -> impl Future<Output = Result<Vec<DnsRecordId>, SimplyClientError>> + Send {
+        SimplyClient::create_dns_record(self, domain, req)
+    }
+
+    fn update_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+    ) -> impl Future<Output = Result<(), SimplyClientError>> + Send {
+        SimplyClient::update_dns_record(self, domain, record_id, req)
+    }
+
+    fn delete_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+    ) -> impl Future<Output = Result<(), SimplyClientError>> + Send {
+        SimplyClient::delete_dns_record(self, domain, record_id)
+    }
+
+    fn apply_plan(&self, plan: ZonePlan) -> impl Future<Output = OperationReport<Change>> + Send {
+        SimplyClient::apply(self, plan)
+    }
+}
+
+/// A dyn-compatible version of [`SimplyDnsApi`], for holding any
+/// implementation as `Arc<dyn DynSimplyDnsApi>`.
+///
+/// Every [`SimplyDnsApi`] implements this trait, and `dyn DynSimplyDnsApi`
+/// (also behind an [`Arc`]) implements [`SimplyDnsApi`] in turn, so trait
+/// objects work with every function taking `impl SimplyDnsApi`. The futures
+/// are boxed, costing an allocation per call.
+///
+/// Example usage:
+/// ```rust
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use simply_dns::cache::CachedSimplyClient;
+/// use simply_dns::mock::MockSimplyClient;
+/// use simply_dns::{DynSimplyDnsApi, SimplyClient};
+///
+/// fn api(testing: bool) -> Arc<dyn DynSimplyDnsApi> {
+///     if testing {
+///         Arc::new(MockSimplyClient::new().with_domain("example.com"))
+///     } else {
+///         let client = SimplyClient::new("account", "api_key");
+///         Arc::new(CachedSimplyClient::new(client, Duration::from_secs(60)))
+///     }
+/// }
+///
+/// # let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+/// # runtime.block_on(async {
+/// let api = api(true);
+/// let plan = simply_dns::sync::plan(&api, "example.com", &Default::default()).await?;
+/// assert!(plan.is_empty());
+/// # Ok::<(), simply_dns::SimplyClientError>(())
+/// # })?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub trait DynSimplyDnsApi: Send + Sync {
+    /// See [`SimplyDnsApi::list_domains`].
+    fn list_domains(&self) -> BoxFuture<'_, Result<Vec<String>, SimplyClientError>>;
+
+    /// See [`SimplyDnsApi::list_dns_records`].
+    fn list_dns_records<'a>(
+        &'a self,
+        domain: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<DnsRecord>, SimplyClientError>>;
+
+    /// See [`SimplyDnsApi::get_dns_record`].
+    fn get_dns_record<'a>(
+        &'a self,
+        domain: &'a str,
+        record_id: &'a DnsRecordId,
+    ) -> BoxFuture<'a, Result<Option<DnsRecord>, SimplyClientError>>;
+
+    /// See [`SimplyDnsApi::create_dns_record`].
+    fn create_dns_record<'a>(
+        &'a self,
+        domain: &'a str,
+        req: CreateDnsRecordRequest,
+    ) -> BoxFuture<'a, Result<Vec<DnsRecordId>, SimplyClientError>>;
+
+    /// See [`SimplyDnsApi::update_dns_record`].
+    fn update_dns_record<'a>(
+        &'a self,
+        domain: &'a str,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+    ) -> BoxFuture<'a, Result<(), SimplyClientError>>;
+
+    /// See [`SimplyDnsApi::delete_dns_record`].
+    fn delete_dns_record<'a>(
+        &'a self,
+        domain: &'a str,
+        record_id: DnsRecordId,
+    ) -> BoxFuture<'a, Result<(), SimplyClientError>>;
+
+    /// See [`SimplyDnsApi::apply_plan`].
+    fn apply_plan(&self, plan: ZonePlan) -> BoxFuture<'_, OperationReport<Change>>;
+}
+
+impl<A: SimplyDnsApi> DynSimplyDnsApi for A {
+    fn list_domains(&self) -> BoxFuture<'_, Result<Vec<String>, SimplyClientError>> {
+        Box::pin(SimplyDnsApi::list_domains(self))
+    }
+
+    fn list_dns_records<'a>(
+        &'a self,
+        domain: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<DnsRecord>, SimplyClientError>> {
+        Box::pin(SimplyDnsApi::list_dns_records(self, domain))
+    }
+
+    fn get_dns_record<'a>(
+        &'a self,
+        domain: &'a str,
+        record_id: &'a DnsRecordId,
+    ) -> BoxFuture<'a, Result<Option<DnsRecord>, SimplyClientError>> {
+        Box::pin(SimplyDnsApi::get_dns_record(self, domain, record_id))
+    }
+
+    fn create_dns_record<'a>(
+        &'a self,
+        domain: &'a str,
+        req: CreateDnsRecordRequest,
+    ) -> BoxFuture<'a, Result<Vec<DnsRecordId>, SimplyClientError>> {
+        Box::pin(SimplyDnsApi::create_dns_record(self, domain, req))
+    }
+
+    fn update_dns_record<'a>(
+        &'a self,
+        domain: &'a str,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+    ) -> BoxFuture<'a, Result<(), SimplyClientError>> {
+        Box::pin(SimplyDnsApi::update_dns_record(
+            self, domain, record_id, req,
+        ))
+    }
+
+    fn delete_dns_record<'a>(
+        &'a self,
+        domain: &'a str,
+        record_id: DnsRecordId,
+    ) -> BoxFuture<'a, Result<(), SimplyClientError>> {
+        Box::pin(SimplyDnsApi::delete_dns_record(self, domain, record_id))
+    }
+
+    fn apply_plan(&self, plan: ZonePlan) -> BoxFuture<'_, OperationReport<Change>> {
+        Box::pin(SimplyDnsApi::apply_plan(self, plan))
+    }
+}
+
+impl SimplyDnsApi for dyn DynSimplyDnsApi {
+    fn list_domains(&self) -> impl Future<Output = Result<Vec<String>, SimplyClientError>> + Send {
+        DynSimplyDnsApi::list_domains(self)
+    }
+
+    async fn list_dns_records(&self, domain: &str) -> Result<Vec<DnsRecord>, SimplyClientError> {
+        DynSimplyDnsApi::list_dns_records(self, domain).await
+    }
+
+    async fn get_dns_record(
+        &self,
+        domain: &str,
+        record_id: &DnsRecordId,
+    ) -> Result<Option<DnsRecord>, SimplyClientError> {
+        DynSimplyDnsApi::get_dns_record(self, domain, record_id).await
+    }
+
+    async fn create_dns_record(
+        &self,
+        domain: &str,
+        req: CreateDnsRecordRequest,
+    ) -> Result<Vec<DnsRecordId>, SimplyClientError> {
+        DynSimplyDnsApi::create_dns_record(self, domain, req).await
+    }
+
+    async fn update_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+    ) -> Result<(), SimplyClientError> {
+        DynSimplyDnsApi::update_dns_record(self, domain, record_id, req).await
+    }
+
+    async fn delete_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+    ) -> Result<(), SimplyClientError> {
+        DynSimplyDnsApi::delete_dns_record(self, domain, record_id).await
+    }
+
+    fn apply_plan(&self, plan: ZonePlan) -> impl Future<Output = OperationReport<Change>> + Send {
+        DynSimplyDnsApi::apply_plan(self, plan)
+    }
+}
+
+/// Shares an implementation, e.g. an `Arc<dyn DynSimplyDnsApi>`.
+impl<A: SimplyDnsApi + ?Sized> SimplyDnsApi for Arc<A> {
+    fn list_domains(&self) -> impl Future<Output = Result<Vec<String>, SimplyClientError>> + Send {
+        (**self).list_domains()
+    }
+
+    fn list_dns_records(
+        &self,
+        domain: &str,
+    ) -> impl Future<Output = Result<Vec<DnsRecord>, SimplyClientError>> + Send {
+        (**self).list_dns_records(domain)
+    }
+
+    fn get_dns_record(
+        &self,
+        domain: &str,
+        record_id: &DnsRecordId,
+    ) -> impl Future<Output = Result<Option<DnsRecord>, SimplyClientError>> + Send {
+        (**self).get_dns_record(domain, record_id)
+    }
+
+    fn create_dns_record(
+        &self,
+        domain: &str,
+        req: CreateDnsRecordRequest,
+    ) -> impl Future<Output = Result<Vec<DnsRecordId>, SimplyClientError>> + Send {
+        (**self).create_dns_record(domain, req)
+    }
+
+    fn update_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+    ) -> impl Future<Output = Result<(), SimplyClientError>> + Send {
+        (**self).update_dns_record(domain, record_id, req)
+    }
+
+    fn delete_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+    ) -> impl Future<Output = Result<(), SimplyClientError>> + Send {
+        (**self).delete_dns_record(domain, record_id)
+    }
+
+    fn apply_plan(&self, plan: ZonePlan) -> impl Future<Output = OperationReport<Change>> + Send {
+        (**self).apply_plan(plan)
+    }
+}
@@ -0,0 +1,386 @@
+//! Tracked zone state and drift detection.
+//!
+//! A [`ZoneTracker`] remembers the records of a zone in a
+//! [`StateStore`](crate::state::StateStore) as of the last
+//! [`apply`](ZoneTracker::apply), together with a hash of each record. Repeated
+//! applies plan against the saved state instead of listing the zone, and
+//! [`detect_drift`](ZoneTracker::detect_drift) lists the zone once to report
+//! records that were created, changed or deleted outside of the tool since.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use simply_dns::SimplyClient;
+//! use simply_dns::drift::ZoneTracker;
+//! use simply_dns::state::FileStateStore;
+//! use simply_dns::sync::{DesiredRecord, DesiredZone};
+//!
+//! # async fn run() -> Result<(), simply_dns::drift::DriftError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let tracker = ZoneTracker::new(&client, Arc::new(FileStateStore::new("/var/lib/simply-dns")?));
+//!
+//! let drift = tracker.detect_drift("example.com").await?;
+//! if !drift.is_clean() {
+//!     println!("{drift}");
+//!     tracker.refresh("example.com").await?;
+//! }
+//!
+//! let desired: DesiredZone = [DesiredRecord::new("@", "A", "192.0.2.10")]
+//!     .into_iter()
+//!     .collect();
+//! let plan = tracker.plan("example.com", &desired).await?;
+//! println!("{}", tracker.apply(plan).await?);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::api::DnsRecord;
+use crate::names;
+use crate::progress::Operation;
+use crate::report::OperationReport;
+use crate::state::{StateError, StateStore};
+use crate::sync::{Change, DesiredZone, ZonePlan, tag_comment};
+use crate::time::Instant;
+use crate::{SimplyClient, SimplyClientError};
+
+/// Error type for [`ZoneTracker`] operations.
+#[derive(Debug, Error)]
+pub enum DriftError {
+    /// The Simply.com API call failed.
+    #[error(transparent)]
+    Client(#[from] SimplyClientError),
+    /// Reading or writing the saved zone state failed.
+    #[error("state error: {0}")]
+    State(#[from] StateError),
+}
+
+/// A record as saved by a [`ZoneTracker`], with the hash it had then.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedRecord {
+    /// The [`record_hash`] of `record`.
+    pub hash: String,
+    /// The record, with its name as returned by the API.
+    pub record: DnsRecord,
+}
+
+/// The records of a zone as saved by a [`ZoneTracker`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneState {
+    /// The domain the state belongs to.
+    pub domain: String,
+    /// The records of the zone.
+    pub records: Vec<TrackedRecord>,
+}
+
+impl ZoneState {
+    /// The state of `domain` consisting of `records`.
+    pub fn new(domain: impl Into<String>, records: Vec<DnsRecord>) -> Self {
+        let domain = domain.into();
+        let records = records
+            .into_iter()
+            .map(|record| TrackedRecord {
+                hash: record_hash(&record, &domain),
+                record,
+            })
+            .collect();
+        Self { domain, records }
+    }
+
+    /// The saved records, without their hashes.
+    pub fn records(&self) -> Vec<DnsRecord> {
+        self.records.iter().map(|t| t.record.clone()).collect()
+    }
+
+    fn track(&mut self, record: DnsRecord) {
+        self.records.push(TrackedRecord {
+            hash: record_hash(&record, &self.domain),
+            record,
+        });
+    }
+}
+
+/// A hash of the name, type, data, TTL, priority and comment of `record`.
+///
+/// Names are compared in their zone-relative form and case-insensitively, as
+/// are types, so the hash doesn't change with the way the API spells them.
+pub fn record_hash(record: &DnsRecord, domain: &str) -> String {
+    let mut hash = Fnv::new();
+    hash.write(
+        names::to_relative(&record.name, domain)
+            .to_ascii_lowercase()
+            .as_bytes(),
+    );
+    hash.write(record.record_type.to_ascii_uppercase().as_bytes());
+    hash.write(record.data.as_bytes());
+    hash.write(&record.ttl.to_be_bytes());
+    hash.write(
+        record
+            .priority
+            .map(|p| p.to_string())
+            .unwrap_or_default()
+            .as_bytes(),
+    );
+    hash.write(record.comment.as_deref().unwrap_or_default().as_bytes());
+    hash.finish()
+}
+
+/// FNV-1a, which is stable across Rust versions unlike `DefaultHasher`.
+pub(crate) struct Fnv(u64);
+
+impl Fnv {
+    pub(crate) fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    /// Add `bytes` as one field, terminated so that fields can't run into each other.
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes.iter().chain([0xff].iter()) {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// The hash as 16 hex digits.
+    pub(crate) fn finish(&self) -> String {
+        format!("{:016x}", self.0)
+    }
+}
+
+/// A record whose live contents differ from the saved ones.
+#[derive(Debug, Clone)]
+pub struct DriftedRecord {
+    /// The record as saved after the last apply.
+    pub saved: DnsRecord,
+    /// The record as it exists now.
+    pub live: DnsRecord,
+}
+
+/// Records changed outside of the [`ZoneTracker`], from [`ZoneTracker::detect_drift`].
+#[derive(Debug, Clone, Default)]
+pub struct DriftReport {
+    /// Records that exist now but weren't saved.
+    pub added: Vec<DnsRecord>,
+    /// Records whose contents changed.
+    pub modified: Vec<DriftedRecord>,
+    /// Saved records that no longer exist.
+    pub deleted: Vec<DnsRecord>,
+}
+
+impl DriftReport {
+    /// Whether the zone still matches the saved state.
+    pub fn is_clean(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.deleted.is_empty()
+    }
+}
+
+/// Shows one line per drifted record, e.g. `~ www A 192.0.2.1 -> 192.0.2.2`.
+impl fmt::Display for DriftReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return f.write_str("no drift");
+        }
+        for r in &self.added {
+            writeln!(f, "+ {} {} {}", r.name, r.record_type, r.data)?;
+        }
+        for DriftedRecord { saved, live } in &self.modified {
+            writeln!(
+                f,
+                "~ {} {} {} -> {} {} {}",
+                saved.name, saved.record_type, saved.data, live.name, live.record_type, live.data
+            )?;
+        }
+        for r in &self.deleted {
+            writeln!(f, "- {} {} {}", r.name, r.record_type, r.data)?;
+        }
+        Ok(())
+    }
+}
+
+/// Plans and applies zones against state saved in a [`StateStore`], see the
+/// [module docs](self).
+///
+/// State is saved under `zones/<domain>`. Changes made outside of the tracker
+/// aren't seen by [`plan`](Self::plan) until [`refresh`](Self::refresh) is
+/// called, so check [`detect_drift`](Self::detect_drift) before applying when
+/// others may edit the zone. A dry-run client never saves state.
+pub struct ZoneTracker<'a> {
+    client: &'a SimplyClient,
+    store: Arc<dyn StateStore>,
+}
+
+impl<'a> ZoneTracker<'a> {
+    /// A tracker writing through `client` and saving state in `store`.
+    pub fn new(client: &'a SimplyClient, store: Arc<dyn StateStore>) -> Self {
+        Self { client, store }
+    }
+
+    fn key(domain: &str) -> String {
+        format!(
+            "zones/{}",
+            domain.trim_end_matches('.').to_ascii_lowercase()
+        )
+    }
+
+    /// The state saved for `domain`, if any.
+    pub fn state(&self, domain: &str) -> Result<Option<ZoneState>, DriftError> {
+        Ok(self.store.get_json(&Self::key(domain))?)
+    }
+
+    fn save(&self, state: &ZoneState) -> Result<(), DriftError> {
+        if !self.client.is_dry_run() {
+            self.store.put_json(&Self::key(&state.domain), state)?;
+        }
+        Ok(())
+    }
+
+    /// List `domain` and save its records as the new state, accepting any drift.
+    pub async fn refresh(&self, domain: &str) -> Result<ZoneState, DriftError> {
+        let records = self.client.list_dns_records(domain).await?;
+        let state = ZoneState::new(domain, records);
+        self.save(&state)?;
+        Ok(state)
+    }
+
+    /// Remove the state saved for `domain`, so the next plan lists the zone again.
+    pub fn forget(&self, domain: &str) -> Result<(), DriftError> {
+        Ok(self.store.delete(&Self::key(domain))?)
+    }
+
+    /// Compute the changes needed to make `domain` match `desired`.
+    ///
+    /// Plans against the saved state without calling the API; without saved
+    /// state, the zone is listed and saved first.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to plan changes for.
+    /// * `desired` - The records the zone should contain.
+    pub async fn plan(&self, domain: &str, desired: &DesiredZone) -> Result<ZonePlan, DriftError> {
+        let state = match self.state(domain)? {
+            Some(state) => state,
+            None => self.refresh(domain).await?,
+        };
+        Ok(ZonePlan::compute(domain, state.records(), desired))
+    }
+
+    /// Execute the changes of `plan` like [`SimplyClient::apply`] and update
+    /// the saved state to match.
+    ///
+    /// The state is updated from the changes that succeeded. If a created
+    /// record's ID or TTL isn't known from the response, the zone is listed
+    /// once at the end instead; if that fails, the state is removed so the
+    /// next plan lists the zone. Fails only if the state can't be read or written.
+    pub async fn apply(&self, plan: ZonePlan) -> Result<OperationReport<Change>, DriftError> {
+        let domain = plan.domain.clone();
+        let lock = self.client.lock_domain(&domain).await?;
+        let client = lock.client();
+        let mut state = match self.state(&domain)? {
+            Some(state) => state,
+            None => self.refresh(&domain).await?,
+        };
+        let mut stale = false;
+        let started = Instant::now();
+        let mut report = OperationReport::default();
+        let mut progress = client.progress(Operation::Apply, Some(plan.changes.len()));
+        for change in plan.changes {
+            progress.begin();
+            let change_started = Instant::now();
+            let result = match &change {
+                Change::Delete(record) => {
+                    let result = client.delete_dns_record(&domain, record.record_id).await;
+                    if result.is_ok() {
+                        state
+                            .records
+                            .retain(|t| t.record.record_id != record.record_id);
+                    }
+                    result
+                }
+                Change::Update { current, desired } => {
+                    let result = client
+                        .update_dns_record(&domain, current.record_id, desired.clone())
+                        .await;
+                    if result.is_ok() {
+                        state
+                            .records
+                            .retain(|t| t.record.record_id != current.record_id);
+                        state.track(DnsRecord {
+                            record_id: current.record_id,
+                            name: names::to_relative(&desired.name, &domain),
+                            ttl: desired.ttl.unwrap_or(current.ttl),
+                            data: desired.data.clone(),
+                            record_type: desired.record_type.clone(),
+                            priority: desired.priority,
+                            comment: desired.comment.clone(),
+                        });
+                    }
+                    result
+                }
+                Change::Create(req) => match client.create_dns_record(&domain, req.clone()).await {
+                    Ok(ids) => {
+                        match (ids.as_slice(), req.ttl) {
+                            ([id], Some(ttl)) => state.track(DnsRecord {
+                                record_id: *id,
+                                name: names::to_relative(&req.name, &domain),
+                                ttl,
+                                data: req.data.clone(),
+                                record_type: req.record_type.clone(),
+                                priority: req.priority,
+                                comment: match client.owner() {
+                                    Some(owner) => Some(tag_comment(req.comment.as_deref(), owner)),
+                                    None => req.comment.clone(),
+                                },
+                            }),
+                            _ => stale = true,
+                        }
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                },
+            };
+            progress.record(&mut report, change, result, change_started);
+        }
+        report.elapsed = started.elapsed();
+        progress.finish(&report);
+
+        if !stale {
+            self.save(&state)?;
+        } else if self.refresh(&domain).await.is_err() {
+            self.forget(&domain)?;
+        }
+        Ok(report)
+    }
+
+    /// List `domain` and compare it to the saved state.
+    ///
+    /// Without saved state every record counts as added. The state isn't
+    /// changed; call [`refresh`](Self::refresh) to accept the drift.
+    pub async fn detect_drift(&self, domain: &str) -> Result<DriftReport, DriftError> {
+        let saved = self.state(domain)?.map(|s| s.records).unwrap_or_default();
+        let mut live = self.client.list_dns_records(domain).await?;
+        let mut report = DriftReport::default();
+        for tracked in saved {
+            match live
+                .iter()
+                .position(|r| r.record_id == tracked.record.record_id)
+            {
+                Some(i) => {
+                    let live = live.remove(i);
+                    if record_hash(&live, domain) != tracked.hash {
+                        report.modified.push(DriftedRecord {
+                            saved: tracked.record,
+                            live,
+                        });
+                    }
+                }
+                None => report.deleted.push(tracked.record),
+            }
+        }
+        report.added = live;
+        Ok(report)
+    }
+}
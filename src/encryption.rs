@@ -0,0 +1,238 @@
+//! Encryption at rest for persisted state and backups.
+//!
+//! [`EncryptedStateStore`] wraps another [`StateStore`] and encrypts every value
+//! with AES-256-GCM before it is written, so state files don't leak zone data
+//! or comments. Each value is bound to its key, so entries can't be swapped
+//! around without detection.
+//! [`ZoneBackup::to_encrypted`](crate::backup::ZoneBackup::to_encrypted) does
+//! the same for backups.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use simply_dns::encryption::{EncryptedStateStore, EncryptionKey};
+//! use simply_dns::state::FileStateStore;
+//!
+//! let key = EncryptionKey::from_env("SIMPLY_DNS_STATE_KEY")?;
+//! let store = EncryptedStateStore::new(Arc::new(FileStateStore::new("/var/lib/simply-dns")?), key);
+//! # Ok::<(), simply_dns::state::StateError>(())
+//! ```
+
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use zeroize::Zeroizing;
+
+use crate::state::{StateError, StateStore};
+
+/// Prefix of every encrypted value, identifying the format.
+const MAGIC: &[u8] = b"SDE1";
+/// Length of an AES-256 key in bytes.
+const KEY_LEN: usize = 32;
+
+/// A 256-bit key for [`EncryptedStateStore`] and encrypted backups.
+///
+/// Keys are given as 64 hex digits, e.g. generated with `openssl rand -hex 32`.
+pub struct EncryptionKey(LessSafeKey);
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+impl EncryptionKey {
+    /// A key from its 32 raw bytes.
+    pub fn from_bytes(bytes: &[u8; KEY_LEN]) -> Self {
+        let key = UnboundKey::new(&AES_256_GCM, bytes).expect("AES-256 keys are 32 bytes");
+        Self(LessSafeKey::new(key))
+    }
+
+    /// A key from 64 hex digits; surrounding whitespace is ignored.
+    pub fn from_hex(hex: &str) -> Result<Self, StateError> {
+        let hex = hex.trim().as_bytes();
+        if hex.len() != 2 * KEY_LEN {
+            return Err(invalid_key(format!(
+                "expected {} hex digits, got {}",
+                2 * KEY_LEN,
+                hex.len()
+            )));
+        }
+        let mut bytes = Zeroizing::new([0u8; KEY_LEN]);
+        for (byte, pair) in bytes.iter_mut().zip(hex.chunks_exact(2)) {
+            let digit = |c: u8| {
+                (c as char)
+                    .to_digit(16)
+                    .ok_or_else(|| invalid_key("not a hex digit".to_string()))
+            };
+            *byte = (digit(pair[0])? * 16 + digit(pair[1])?) as u8;
+        }
+        Ok(Self::from_bytes(&bytes))
+    }
+
+    /// A key from the hex-encoded environment variable `name`.
+    pub fn from_env(name: &str) -> Result<Self, StateError> {
+        let value =
+            Zeroizing::new(std::env::var(name).map_err(|e| invalid_key(format!("{name}: {e}")))?);
+        Self::from_hex(&value)
+    }
+
+    /// A key from a file containing it hex-encoded, e.g. a mounted secret.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, StateError> {
+        let value = Zeroizing::new(std::fs::read_to_string(path)?);
+        Self::from_hex(&value)
+    }
+
+    /// A key stored hex-encoded in the OS keyring under `service` for
+    /// `account`, looked up like
+    /// [`KeyringCredentials`](crate::credentials::KeyringCredentials).
+    ///
+    /// Store one with e.g. `openssl rand -hex 32 | secret-tool store --label
+    /// "simply-dns backup key" service simply-dns-backup account example.com`.
+    #[cfg(feature = "keyring")]
+    pub fn from_keyring(service: &str, account: &str) -> Result<Self, StateError> {
+        let value = crate::credentials::keyring_secret(service, account)
+            .map_err(|e| StateError::Backend(e.to_string()))?
+            .ok_or_else(|| {
+                invalid_key(format!(
+                    "no key for {account} in the keyring service {service}"
+                ))
+            })?;
+        Self::from_hex(&Zeroizing::new(value))
+    }
+
+    /// Encrypt `plaintext`, bound to `context` so it only decrypts as what it
+    /// was encrypted for, e.g. the key a value is stored under.
+    ///
+    /// Example usage:
+    /// ```rust
+    /// use simply_dns::encryption::{EncryptionKey, is_encrypted};
+    ///
+    /// let key = EncryptionKey::from_bytes(&[7; 32]);
+    /// let sealed = key.encrypt("notes", b"secret")?;
+    /// assert!(is_encrypted(&sealed));
+    /// assert_eq!(key.decrypt("notes", &sealed)?, b"secret");
+    /// assert!(key.decrypt("other", &sealed).is_err());
+    /// # Ok::<(), simply_dns::state::StateError>(())
+    /// ```
+    pub fn encrypt(&self, context: &str, plaintext: &[u8]) -> Result<Vec<u8>, StateError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| StateError::Encryption("no randomness available".to_string()))?;
+        let mut buf = plaintext.to_vec();
+        self.0
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(context.as_bytes()),
+                &mut buf,
+            )
+            .map_err(|_| StateError::Encryption(format!("{context} could not be encrypted")))?;
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + buf.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&buf);
+        Ok(sealed)
+    }
+
+    /// Decrypt what [`encrypt`](Self::encrypt) returned for `context`.
+    pub fn decrypt(&self, context: &str, data: &[u8]) -> Result<Vec<u8>, StateError> {
+        let Some(sealed) = data.strip_prefix(MAGIC) else {
+            return Err(StateError::Encryption(format!(
+                "{context} is not encrypted"
+            )));
+        };
+        let truncated = || StateError::Encryption(format!("{context} is truncated"));
+        if sealed.len() < NONCE_LEN {
+            return Err(truncated());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| truncated())?;
+        let mut buf = ciphertext.to_vec();
+        let plain = self
+            .0
+            .open_in_place(nonce, Aad::from(context.as_bytes()), &mut buf)
+            .map_err(|_| {
+                StateError::Encryption(format!("{context} could not be decrypted, wrong key?"))
+            })?;
+        Ok(plain.to_vec())
+    }
+}
+
+/// Whether `data` was encrypted by [`EncryptionKey::encrypt`], going by its prefix.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+fn invalid_key(message: String) -> StateError {
+    StateError::Encryption(format!("invalid key: {message}"))
+}
+
+/// A [`StateStore`] encrypting values before handing them to another store.
+///
+/// Keys (the names values are stored under) are kept in plain text, as the
+/// inner store needs them for [`keys`](StateStore::keys).
+///
+/// Example usage:
+/// ```rust
+/// use std::sync::Arc;
+/// use simply_dns::encryption::{EncryptedStateStore, EncryptionKey};
+/// use simply_dns::state::{MemoryStateStore, StateStore};
+///
+/// let inner = Arc::new(MemoryStateStore::new());
+/// let store = EncryptedStateStore::new(inner.clone(), EncryptionKey::from_bytes(&[7; 32]));
+/// store.put("ddns/example.com/home/A", b"192.0.2.10")?;
+/// assert_eq!(store.get("ddns/example.com/home/A")?.as_deref(), Some(&b"192.0.2.10"[..]));
+/// assert_ne!(inner.get("ddns/example.com/home/A")?.as_deref(), Some(&b"192.0.2.10"[..]));
+/// # Ok::<(), simply_dns::state::StateError>(())
+/// ```
+pub struct EncryptedStateStore {
+    inner: Arc<dyn StateStore>,
+    key: EncryptionKey,
+    read_plaintext: bool,
+}
+
+impl EncryptedStateStore {
+    /// Encrypt the values of `inner` with `key`.
+    pub fn new(inner: Arc<dyn StateStore>, key: EncryptionKey) -> Self {
+        Self {
+            inner,
+            key,
+            read_plaintext: false,
+        }
+    }
+
+    /// Also accept values that were stored unencrypted, e.g. before switching
+    /// an existing store to encryption. They are encrypted when next written.
+    pub fn read_plaintext(mut self, enabled: bool) -> Self {
+        self.read_plaintext = enabled;
+        self
+    }
+}
+
+impl StateStore for EncryptedStateStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StateError> {
+        let Some(stored) = self.inner.get(key)? else {
+            return Ok(None);
+        };
+        if self.read_plaintext && !is_encrypted(&stored) {
+            return Ok(Some(stored));
+        }
+        self.key.decrypt(key, &stored).map(Some)
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> Result<(), StateError> {
+        self.inner.put(key, &self.key.encrypt(key, value)?)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), StateError> {
+        self.inner.delete(key)
+    }
+
+    fn keys(&self, prefix: &str) -> Result<Vec<String>, StateError> {
+        self.inner.keys(prefix)
+    }
+}
@@ -0,0 +1,147 @@
+//! Temporary records that are deleted once they expire.
+//!
+//! [`SimplyClient::create_temporary_record`] creates a record whose comment
+//! carries an expiry tag, a single word such as "expires=1767225600" holding
+//! the Unix time it expires at, and [`SimplyClient::cleanup_expired`] deletes
+//! the records whose time has come. Run the cleanup now and then, e.g. from a
+//! cron job, for ACME challenges, demo environments or verification tokens
+//! that would otherwise be forgotten. The API itself doesn't expire anything.
+//!
+//! Records without the tag are never deleted by the cleanup, and the tag
+//! lives alongside any other comment text and [ownership tags](crate::ownership).
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use simply_dns::SimplyClient;
+//! use simply_dns::api::CreateDnsRecordRequest;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let token = CreateDnsRecordRequest {
+//!     name: "_verification".to_string(),
+//!     record_type: "TXT".to_string(),
+//!     data: "token=0123456789".to_string(),
+//!     priority: None,
+//!     ttl: Some(300),
+//!     comment: None,
+//! };
+//! client
+//!     .create_temporary_record("example.com", token, Duration::from_secs(24 * 3600))
+//!     .await?;
+//!
+//! // Later, e.g. hourly:
+//! println!("{}", client.cleanup_expired("example.com").await?);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord, DnsRecordId};
+use crate::progress::Operation;
+use crate::report::OperationReport;
+use crate::sync::tag_comment;
+use crate::time::{Instant, unix_time};
+use crate::{SimplyClient, SimplyClientError};
+
+/// The prefix of the expiry tag in a record's comment, followed by the Unix
+/// time in seconds.
+pub const EXPIRY_TAG_PREFIX: &str = "expires=";
+
+/// The expiry tag for `expires_at`, e.g. "expires=1767225600".
+pub fn expiry_tag(expires_at: SystemTime) -> String {
+    let secs = expires_at
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    format!("{EXPIRY_TAG_PREFIX}{secs}")
+}
+
+/// When the record with `comment` expires, if its comment has an expiry tag.
+///
+/// Example usage:
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use simply_dns::expiry::expires_at;
+///
+/// assert_eq!(
+///     expires_at(Some("demo expires=1767225600")),
+///     Some(UNIX_EPOCH + Duration::from_secs(1_767_225_600))
+/// );
+/// assert_eq!(expires_at(Some("expires=soon")), None);
+/// ```
+pub fn expires_at(comment: Option<&str>) -> Option<SystemTime> {
+    comment?
+        .split_whitespace()
+        .find_map(|word| word.strip_prefix(EXPIRY_TAG_PREFIX)?.parse().ok())
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+impl DnsRecord {
+    /// When the record expires, if it is a temporary record, see [`expiry`](crate::expiry).
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        expires_at(self.comment.as_deref())
+    }
+}
+
+impl SimplyClient {
+    /// Create a record that [`cleanup_expired`](Self::cleanup_expired)
+    /// deletes once `lifetime` has passed.
+    ///
+    /// The expiry tag is appended to the comment of `req`; the record is
+    /// otherwise created like [`create_dns_record`](Self::create_dns_record) does.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to create the record in.
+    /// * `req` - The record to create.
+    /// * `lifetime` - How long the record should exist.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn create_temporary_record(
+        &self,
+        domain: &str,
+        mut req: CreateDnsRecordRequest,
+        lifetime: Duration,
+    ) -> Result<Vec<DnsRecordId>, SimplyClientError> {
+        let tag = expiry_tag(UNIX_EPOCH + unix_time() + lifetime);
+        req.comment = Some(tag_comment(req.comment.as_deref(), &tag));
+        self.create_dns_record(domain, req).await
+    }
+
+    /// Delete the records of a domain whose expiry tag is in the past.
+    ///
+    /// Records without an expiry tag are never touched. Fails only if the
+    /// records can't be listed; failed deletions are collected in the report.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to clean up.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn cleanup_expired(
+        &self,
+        domain: &str,
+    ) -> Result<OperationReport<DnsRecord>, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let started = Instant::now();
+        let now = UNIX_EPOCH + unix_time();
+        let mut report = OperationReport::default();
+        let records: Vec<_> = this
+            .list_dns_records(domain)
+            .await?
+            .into_iter()
+            .filter(|r| r.expires_at().is_some_and(|at| at <= now))
+            .collect();
+        let mut progress = this.progress(Operation::CleanupExpired, Some(records.len()));
+        for record in records {
+            progress.begin();
+            let record_started = Instant::now();
+            let result = this.delete_dns_record(domain, record.record_id).await;
+            progress.record(&mut report, record, result, record_started);
+        }
+        report.elapsed = started.elapsed();
+        progress.finish(&report);
+        Ok(report)
+    }
+}
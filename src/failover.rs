@@ -0,0 +1,384 @@
+//! Health-checked DNS failover.
+//!
+//! [`FailoverUpdater`] probes an ordered list of targets and keeps an `A` or
+//! `AAAA` record pointed at the first healthy one, switching to the next when
+//! probes fail and back once the preferred target has recovered.
+//!
+//! A target is only marked down after several failed probes in a row, and up
+//! again after several successes, so a single lost packet doesn't flip the
+//! record. Targets that are down are probed with exponential backoff.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use simply_dns::SimplyClient;
+//! use simply_dns::failover::{FailoverTarget, FailoverUpdater, HealthCheck};
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let updater = FailoverUpdater::new(&client, "example.com", "www")
+//!     .target(FailoverTarget::new(
+//!         "192.0.2.10".parse().unwrap(),
+//!         HealthCheck::Http("https://www.example.com/healthz".to_string()),
+//!     ))
+//!     .target(FailoverTarget::new("192.0.2.20".parse().unwrap(), HealthCheck::Tcp(443)))
+//!     .ttl(60);
+//! updater.run_loop(Duration::from_secs(15)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::net::TcpStream;
+use tokio::time::Instant;
+
+use crate::api::{CreateDnsRecordRequest, UpdateDnsRecordRequest};
+use crate::query::RecordQuery;
+use crate::{SimplyClient, SimplyClientError};
+
+/// How a target's health is probed.
+#[derive(Debug, Clone)]
+pub enum HealthCheck {
+    /// A TCP connection to the target on this port succeeds.
+    Tcp(u16),
+    /// A GET of this URL, sent to the target's address, answers with a 2xx or
+    /// 3xx status. The URL's host is used for the `Host` header and TLS, so
+    /// the URL of the public name works for every target.
+    Http(String),
+}
+
+impl HealthCheck {
+    async fn probe(&self, address: IpAddr, timeout: Duration) -> bool {
+        match self {
+            HealthCheck::Tcp(port) => {
+                tokio::time::timeout(timeout, TcpStream::connect((address, *port)))
+                    .await
+                    .is_ok_and(|r| r.is_ok())
+            }
+            HealthCheck::Http(url) => {
+                let Ok(parsed) = reqwest::Url::parse(url) else {
+                    return false;
+                };
+                let (Some(host), Some(port)) = (parsed.host_str(), parsed.port_or_known_default())
+                else {
+                    return false;
+                };
+                let Ok(http) = reqwest::Client::builder()
+                    .timeout(timeout)
+                    .resolve(host, SocketAddr::new(address, port))
+                    .redirect(reqwest::redirect::Policy::none())
+                    .build()
+                else {
+                    return false;
+                };
+                http.get(parsed)
+                    .send()
+                    .await
+                    .is_ok_and(|res| res.status().is_success() || res.status().is_redirection())
+            }
+        }
+    }
+}
+
+/// An address the record can point at, with its health check.
+#[derive(Debug, Clone)]
+pub struct FailoverTarget {
+    /// The address published while this target is the active one.
+    pub address: IpAddr,
+    /// How to tell whether the target is up.
+    pub check: HealthCheck,
+}
+
+impl FailoverTarget {
+    /// A target at `address`, probed with `check`.
+    pub fn new(address: IpAddr, check: HealthCheck) -> Self {
+        Self { address, check }
+    }
+}
+
+/// The health of one target as of the last [`FailoverUpdater::update_once`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetHealth {
+    /// The target's address.
+    pub address: IpAddr,
+    /// Whether the target counts as up.
+    pub healthy: bool,
+    /// How many probes in a row failed.
+    pub consecutive_failures: u32,
+}
+
+/// The outcome of [`FailoverUpdater::update_once`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailoverStatus {
+    /// The address the record points at, or `None` if no target is healthy
+    /// and the record was left as it was.
+    pub active: Option<IpAddr>,
+    /// Whether the record had to be created or changed.
+    pub changed: bool,
+    /// The health of every target, in priority order.
+    pub targets: Vec<TargetHealth>,
+}
+
+/// Probe state of one target.
+#[derive(Debug, Clone)]
+struct Health {
+    /// `None` until the first probe.
+    healthy: Option<bool>,
+    failures: u32,
+    successes: u32,
+    next_probe: Option<Instant>,
+}
+
+/// Keeps a record pointed at the first healthy of several targets, see the
+/// [module docs](self).
+pub struct FailoverUpdater<'a> {
+    client: &'a SimplyClient,
+    domain: String,
+    name: String,
+    targets: Vec<FailoverTarget>,
+    ttl: Option<u32>,
+    failure_threshold: u32,
+    recovery_threshold: u32,
+    check_timeout: Duration,
+    backoff: Duration,
+    max_backoff: Duration,
+    health: Mutex<Vec<Health>>,
+}
+
+impl<'a> FailoverUpdater<'a> {
+    /// Create an updater for the record `name` under `domain`, without targets.
+    pub fn new(
+        client: &'a SimplyClient,
+        domain: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            domain: domain.into(),
+            name: name.into(),
+            targets: Vec::new(),
+            ttl: None,
+            failure_threshold: 3,
+            recovery_threshold: 3,
+            check_timeout: Duration::from_secs(5),
+            backoff: Duration::from_secs(10),
+            max_backoff: Duration::from_secs(300),
+            health: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add a target, after the ones added before; earlier targets are preferred.
+    ///
+    /// All targets should be of the same address family, as the record type
+    /// follows the active target's address.
+    pub fn target(mut self, target: FailoverTarget) -> Self {
+        self.targets.push(target);
+        self
+    }
+
+    /// TTL to set when the record is created or updated. Keep it low, as
+    /// resolvers keep serving a failed target until it expires.
+    pub fn ttl(mut self, ttl: u32) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// How many probes in a row must fail before a target is marked down,
+    /// and succeed before it is marked up again. Defaults to 3 each.
+    pub fn thresholds(mut self, failures: u32, recoveries: u32) -> Self {
+        self.failure_threshold = failures.max(1);
+        self.recovery_threshold = recoveries.max(1);
+        self
+    }
+
+    /// How long a single probe may take. Defaults to 5 seconds.
+    pub fn check_timeout(mut self, timeout: Duration) -> Self {
+        self.check_timeout = timeout;
+        self
+    }
+
+    /// How long to wait before probing a target that is down again, doubling
+    /// after every failure up to `max`. Defaults to 10 seconds and 5 minutes.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Probe the targets that are due and point the record at the first healthy one.
+    ///
+    /// The first probe of a target decides its health right away, so the
+    /// record is correct from the start. While no target is healthy the
+    /// record is left alone, as pointing it anywhere wouldn't help. Other
+    /// records of the same type at the name that point at one of the targets
+    /// are removed, so only the active target is published.
+    pub async fn update_once(&self) -> Result<FailoverStatus, SimplyClientError> {
+        let due: Vec<bool> = {
+            let mut health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+            health.resize(
+                self.targets.len(),
+                Health {
+                    healthy: None,
+                    failures: 0,
+                    successes: 0,
+                    next_probe: None,
+                },
+            );
+            let now = Instant::now();
+            health
+                .iter()
+                .map(|h| h.next_probe.is_none_or(|at| at <= now))
+                .collect()
+        };
+        let probes = self
+            .targets
+            .iter()
+            .zip(&due)
+            .map(|(target, &due)| async move {
+                if due {
+                    Some(target.check.probe(target.address, self.check_timeout).await)
+                } else {
+                    None
+                }
+            });
+        let results = futures_util::future::join_all(probes).await;
+
+        let targets = {
+            let mut health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+            for (health, result) in health.iter_mut().zip(results) {
+                if let Some(up) = result {
+                    self.record_probe(health, up);
+                }
+            }
+            self.targets
+                .iter()
+                .zip(health.iter())
+                .map(|(target, health)| TargetHealth {
+                    address: target.address,
+                    healthy: health.healthy == Some(true),
+                    consecutive_failures: health.failures,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let Some(active) = targets.iter().find(|t| t.healthy).map(|t| t.address) else {
+            return Ok(FailoverStatus {
+                active: None,
+                changed: false,
+                targets,
+            });
+        };
+        let changed = self.publish(active).await?;
+        Ok(FailoverStatus {
+            active: Some(active),
+            changed,
+            targets,
+        })
+    }
+
+    /// Run [`update_once`](Self::update_once) every `interval`, forever.
+    ///
+    /// Returns on the first error so the caller can decide whether to log and restart.
+    pub async fn run_loop(&self, interval: Duration) -> Result<Infallible, SimplyClientError> {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            self.update_once().await?;
+        }
+    }
+
+    /// Update `health` with the result of a probe.
+    fn record_probe(&self, health: &mut Health, up: bool) {
+        if up {
+            health.failures = 0;
+            health.successes += 1;
+            if health.healthy.is_none() || health.successes >= self.recovery_threshold {
+                health.healthy = Some(true);
+            }
+            health.next_probe = None;
+            return;
+        }
+        health.successes = 0;
+        health.failures += 1;
+        if health.healthy.is_none() || health.failures >= self.failure_threshold {
+            health.healthy = Some(false);
+        }
+        if health.healthy == Some(false) {
+            let doublings = health
+                .failures
+                .saturating_sub(self.failure_threshold)
+                .min(16);
+            let backoff = (self.backoff * 2u32.pow(doublings)).min(self.max_backoff);
+            health.next_probe = Some(Instant::now() + backoff);
+        }
+    }
+
+    /// Point the record at `address`, returning whether anything changed.
+    async fn publish(&self, address: IpAddr) -> Result<bool, SimplyClientError> {
+        let lock = self.client.lock_domain(&self.domain).await?;
+        let client = lock.client();
+        let record_type = match address {
+            IpAddr::V4(_) => "A",
+            IpAddr::V6(_) => "AAAA",
+        };
+        let name = client.api_name(&self.name, &self.domain);
+        let data = address.to_string();
+        let query = RecordQuery::new().name(&self.name).record_type(record_type);
+        let records = client.find_records(&self.domain, &query).await?;
+
+        let mut changed = false;
+        let keep = match records.iter().position(|r| r.data == data) {
+            Some(i) if self.ttl.is_none_or(|ttl| records[i].ttl == ttl) => Some(i),
+            found => {
+                // Reuse a stale record rather than creating one next to it.
+                match found.or_else(|| (!records.is_empty()).then_some(0)) {
+                    Some(i) => {
+                        let record = &records[i];
+                        let req = UpdateDnsRecordRequest {
+                            name: name.clone(),
+                            data: data.clone(),
+                            ttl: self.ttl.or(Some(record.ttl)),
+                            ..record.clone().into()
+                        };
+                        client
+                            .update_dns_record(&self.domain, record.record_id, req)
+                            .await?;
+                        changed = true;
+                        Some(i)
+                    }
+                    None => {
+                        let req = CreateDnsRecordRequest {
+                            record_type: record_type.to_string(),
+                            name,
+                            data,
+                            priority: None,
+                            ttl: self.ttl,
+                            comment: None,
+                        };
+                        client.create_dns_record(&self.domain, req).await?;
+                        changed = true;
+                        None
+                    }
+                }
+            }
+        };
+        for (i, record) in records.iter().enumerate() {
+            let is_target = record
+                .data
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| self.targets.iter().any(|t| t.address == ip));
+            if Some(i) != keep && is_target {
+                client
+                    .delete_dns_record(&self.domain, record.record_id)
+                    .await?;
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+}
@@ -0,0 +1,410 @@
+//! Recorded API responses and a server replaying them, for integration tests.
+//!
+//! [`Fixture`] holds the responses of the Simply.com API the client is
+//! written against: the bodies of listing, creating, updating and deleting
+//! records, the error envelopes for rejected credentials, unknown objects,
+//! invalid input, rate limiting and server failures, and the malformed
+//! payloads seen in the wild, such as an HTML error page from a proxy or a
+//! truncated body. They are modelled on responses recorded from the API, with
+//! the account's data replaced by documentation values; the bodies are also
+//! in the `fixtures` directory of the crate.
+//!
+//! [`FixtureServer`] serves fixtures over HTTP by method and path, so code
+//! using a [`SimplyClient`](crate::SimplyClient) can be tested against the
+//! API's exact wire format, including its failures, without an account.
+//! Unlike the [mock server](crate::mock_server), it keeps no state: every
+//! request gets the canned response of its route, and is recorded for the
+//! test to inspect.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::{RetryPolicy, SimplyClient};
+//! use simply_dns::fixtures::{Fixture, FixtureServer};
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let server = FixtureServer::recorded()
+//!     .route_once("GET", "/2/my/products/*/dns/records", Fixture::rate_limited())
+//!     .start()
+//!     .await?;
+//! let client = SimplyClient::builder()
+//!     .credentials("account", "api_key")
+//!     .base_url(server.base_url())
+//!     .retry(RetryPolicy::default())
+//!     .build()?;
+//! // Rate limited once, then the recorded list after a retry.
+//! assert_eq!(client.list_dns_records("example.com").await?.len(), 4);
+//! assert_eq!(server.requests().len(), 2);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+use crate::http_server::{self, HttpRequest, HttpResponse};
+
+/// The products of an account: one domain and one product without a domain.
+pub const LIST_PRODUCTS: &str = include_str!("../fixtures/list_products.json");
+/// The records of "example.com": an A, a CNAME, an MX and a TXT record.
+pub const LIST_RECORDS: &str = include_str!("../fixtures/list_records.json");
+/// The answer to creating a record, with the ID of the new record.
+pub const CREATE_RECORD: &str = include_str!("../fixtures/create_record.json");
+/// The answer to updating a record.
+pub const UPDATE_RECORD: &str = include_str!("../fixtures/update_record.json");
+/// The answer to deleting a record.
+pub const DELETE_RECORD: &str = include_str!("../fixtures/delete_record.json");
+/// The error for a wrong account or API key.
+pub const UNAUTHORIZED: &str = include_str!("../fixtures/unauthorized.json");
+/// The error for a domain that isn't on the account.
+pub const NOT_FOUND: &str = include_str!("../fixtures/not_found.json");
+/// The error for a record ID that doesn't exist.
+pub const RECORD_NOT_FOUND: &str = include_str!("../fixtures/record_not_found.json");
+/// The error for invalid record data, naming the offending field.
+pub const VALIDATION_ERROR: &str = include_str!("../fixtures/validation_error.json");
+/// The error for too many requests.
+pub const RATE_LIMITED: &str = include_str!("../fixtures/rate_limited.json");
+/// The error for a failure on the API's side.
+pub const SERVER_ERROR: &str = include_str!("../fixtures/server_error.json");
+/// The HTML page of a proxy in front of the API that couldn't reach it.
+pub const BAD_GATEWAY: &str = include_str!("../fixtures/bad_gateway.html");
+/// A record list cut off mid-body.
+pub const TRUNCATED: &str = include_str!("../fixtures/truncated.json");
+/// A record list whose second record doesn't match the schema.
+pub const MALFORMED_RECORDS: &str = include_str!("../fixtures/malformed_records.json");
+
+/// A canned HTTP response, served by [`FixtureServer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    /// The HTTP status.
+    pub status: u16,
+    /// The `content-type` of the body.
+    pub content_type: String,
+    /// Further response headers, e.g. `retry-after`.
+    pub headers: Vec<(&'static str, String)>,
+    /// The response body.
+    pub body: String,
+}
+
+impl Fixture {
+    /// A JSON response with `status` and `body`.
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "application/json".to_string(),
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Add the header `name` to the response.
+    pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    /// [`LIST_PRODUCTS`] with 200 OK.
+    pub fn list_products() -> Self {
+        Self::new(200, LIST_PRODUCTS)
+    }
+
+    /// [`LIST_RECORDS`] with 200 OK.
+    pub fn list_records() -> Self {
+        Self::new(200, LIST_RECORDS)
+    }
+
+    /// [`CREATE_RECORD`] with 200 OK.
+    pub fn create_record() -> Self {
+        Self::new(200, CREATE_RECORD)
+    }
+
+    /// [`UPDATE_RECORD`] with 200 OK.
+    pub fn update_record() -> Self {
+        Self::new(200, UPDATE_RECORD)
+    }
+
+    /// [`DELETE_RECORD`] with 200 OK.
+    pub fn delete_record() -> Self {
+        Self::new(200, DELETE_RECORD)
+    }
+
+    /// [`UNAUTHORIZED`] with 401 Unauthorized.
+    pub fn unauthorized() -> Self {
+        Self::new(401, UNAUTHORIZED)
+    }
+
+    /// [`NOT_FOUND`] with 404 Not Found.
+    pub fn not_found() -> Self {
+        Self::new(404, NOT_FOUND)
+    }
+
+    /// [`RECORD_NOT_FOUND`] with 404 Not Found.
+    pub fn record_not_found() -> Self {
+        Self::new(404, RECORD_NOT_FOUND)
+    }
+
+    /// [`VALIDATION_ERROR`] with 400 Bad Request.
+    pub fn validation_error() -> Self {
+        Self::new(400, VALIDATION_ERROR)
+    }
+
+    /// [`RATE_LIMITED`] with 429 Too Many Requests, asking to retry after a second.
+    pub fn rate_limited() -> Self {
+        Self::new(429, RATE_LIMITED).header("retry-after", "1")
+    }
+
+    /// [`SERVER_ERROR`] with 500 Internal Server Error.
+    pub fn server_error() -> Self {
+        Self::new(500, SERVER_ERROR)
+    }
+
+    /// [`NOT_FOUND`] with 200 OK, as the API sometimes reports errors only in the body.
+    pub fn error_in_body() -> Self {
+        Self::new(200, NOT_FOUND)
+    }
+
+    /// [`BAD_GATEWAY`] with 502 Bad Gateway, as HTML.
+    pub fn bad_gateway() -> Self {
+        Self {
+            content_type: "text/html".to_string(),
+            ..Self::new(502, BAD_GATEWAY)
+        }
+    }
+
+    /// [`TRUNCATED`] with 200 OK.
+    pub fn truncated() -> Self {
+        Self::new(200, TRUNCATED)
+    }
+
+    /// [`MALFORMED_RECORDS`] with 200 OK.
+    pub fn malformed_records() -> Self {
+        Self::new(200, MALFORMED_RECORDS)
+    }
+
+    /// An empty body with 200 OK.
+    pub fn empty() -> Self {
+        Self::new(200, "")
+    }
+}
+
+/// A request received by a [`FixtureServer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedRequest {
+    /// The HTTP method, e.g. "GET".
+    pub method: String,
+    /// The path, including the query string.
+    pub path: String,
+    /// The request headers.
+    pub headers: Vec<(String, String)>,
+    /// The request body.
+    pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    /// The value of the header `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The body parsed as JSON.
+    pub fn json(&self) -> Result<serde_json::Value, serde_json::Error> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+#[derive(Debug)]
+struct Route {
+    method: String,
+    path: Vec<String>,
+    fixture: Fixture,
+    once: bool,
+    served: bool,
+}
+
+impl Route {
+    fn matches(&self, method: &str, segments: &[&str]) -> bool {
+        self.method.eq_ignore_ascii_case(method)
+            && !(self.once && self.served)
+            && self.path.len() == segments.len()
+            && self
+                .path
+                .iter()
+                .zip(segments)
+                .all(|(pattern, segment)| pattern == "*" || pattern == segment)
+    }
+}
+
+/// Builder for [`FixtureServer`], created with [`FixtureServer::builder`]
+/// or [`FixtureServer::recorded`].
+#[derive(Debug)]
+pub struct FixtureServerBuilder {
+    routes: Vec<Route>,
+    fallback: Fixture,
+    addr: SocketAddr,
+}
+
+impl FixtureServerBuilder {
+    /// Answer `method` requests to `path` with `fixture`.
+    ///
+    /// `path` is the full path without the query string, e.g.
+    /// "/2/my/products/example.com/dns/records"; a `*` segment matches any
+    /// segment. Routes added later take precedence over earlier ones.
+    pub fn route(self, method: &str, path: &str, fixture: Fixture) -> Self {
+        self.add(method, path, fixture, false)
+    }
+
+    /// Like [`route`](Self::route), but answer only the first matching
+    /// request, e.g. to fail once before the recorded response.
+    pub fn route_once(self, method: &str, path: &str, fixture: Fixture) -> Self {
+        self.add(method, path, fixture, true)
+    }
+
+    /// Answer requests no route matches with `fixture` instead of [`Fixture::not_found`].
+    pub fn fallback(mut self, fixture: Fixture) -> Self {
+        self.fallback = fixture;
+        self
+    }
+
+    /// Listen on `addr` instead of a random port on 127.0.0.1.
+    pub fn bind(mut self, addr: SocketAddr) -> Self {
+        self.addr = addr;
+        self
+    }
+
+    /// Start serving in the background of the current Tokio runtime.
+    pub async fn start(self) -> io::Result<FixtureServer> {
+        let listener = TcpListener::bind(self.addr).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(ServerState {
+            routes: Mutex::new(self.routes),
+            fallback: self.fallback,
+            requests: Mutex::new(Vec::new()),
+        });
+        let task = tokio::spawn(http_server::serve(listener, {
+            let state = state.clone();
+            move |req| {
+                let res = state.handle(req);
+                async move { res }
+            }
+        }));
+        Ok(FixtureServer { addr, state, task })
+    }
+
+    fn add(mut self, method: &str, path: &str, fixture: Fixture, once: bool) -> Self {
+        self.routes.push(Route {
+            method: method.to_string(),
+            path: path
+                .trim_matches('/')
+                .split('/')
+                .map(str::to_string)
+                .collect(),
+            fixture,
+            once,
+            served: false,
+        });
+        self
+    }
+}
+
+/// A running server answering with fixtures. Stops when dropped.
+#[derive(Debug)]
+pub struct FixtureServer {
+    addr: SocketAddr,
+    state: Arc<ServerState>,
+    task: JoinHandle<()>,
+}
+
+impl FixtureServer {
+    /// A builder for a server without routes, answering every request with
+    /// [`Fixture::not_found`].
+    pub fn builder() -> FixtureServerBuilder {
+        FixtureServerBuilder {
+            routes: Vec::new(),
+            fallback: Fixture::not_found(),
+            addr: (Ipv4Addr::LOCALHOST, 0).into(),
+        }
+    }
+
+    /// A builder for a server answering the product and record endpoints
+    /// of every domain with the recorded success responses.
+    pub fn recorded() -> FixtureServerBuilder {
+        let records = "/2/my/products/*/dns/records";
+        let record = "/2/my/products/*/dns/records/*";
+        Self::builder()
+            .route("GET", "/2/my/products", Fixture::list_products())
+            .route("GET", records, Fixture::list_records())
+            .route("POST", records, Fixture::create_record())
+            .route("PUT", record, Fixture::update_record())
+            .route("DELETE", record, Fixture::delete_record())
+    }
+
+    /// The address the server listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The base URL to configure clients with, e.g. `http://127.0.0.1:41234/2/`.
+    pub fn base_url(&self) -> String {
+        format!("http://{}/2/", self.addr)
+    }
+
+    /// The requests received so far, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state
+            .requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[derive(Debug)]
+struct ServerState {
+    routes: Mutex<Vec<Route>>,
+    fallback: Fixture,
+    requests: Mutex<Vec<RecordedRequest>>,
+}
+
+impl ServerState {
+    fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let segments = req.segments();
+        let fixture = {
+            let mut routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+            match routes
+                .iter_mut()
+                .rev()
+                .find(|r| r.matches(&req.method, &segments))
+            {
+                Some(route) => {
+                    route.served = true;
+                    route.fixture.clone()
+                }
+                None => self.fallback.clone(),
+            }
+        };
+        self.requests
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(RecordedRequest {
+                method: req.method,
+                path: req.path,
+                headers: req.headers,
+                body: req.body,
+            });
+        let mut res = HttpResponse::new(fixture.status, &fixture.content_type, fixture.body);
+        res.headers.extend(fixture.headers);
+        res
+    }
+}
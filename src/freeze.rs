@@ -0,0 +1,415 @@
+//! Change freezes.
+//!
+//! [`FreezeWindows`] registered with
+//! [`SimplyClientBuilder::freeze_windows`](crate::SimplyClientBuilder::freeze_windows)
+//! describe times in which the client refuses to change records, such as
+//! Friday afternoons or the holiday season. While a window is active,
+//! [`SimplyClient::apply`](crate::SimplyClient::apply) and
+//! [`SimplyClient::sync`](crate::SimplyClient::sync) report every change as
+//! skipped, and other writes fail with [`SimplyClientError::Frozen`]. Dry runs
+//! and reads are still allowed.
+//!
+//! A freeze can be overridden with
+//! [`SimplyClientBuilder::freeze_override`](crate::SimplyClientBuilder::freeze_override),
+//! which takes the reason for doing so. The changes made during a window are
+//! then passed to [audit hooks](crate::audit) with that reason, which the
+//! built-in logs record as `freeze_override`.
+//!
+//! Windows are written as `start - end`, either on weekdays, recurring every
+//! week, or on dates, from the start time up to but not including the end:
+//!
+//! ```text
+//! # Fridays from 16:00, and the weekend.
+//! weekend = fri 16:00 - mon 08:00
+//! # The holiday season, up to and including January 1st.
+//! holidays = 2026-12-20 - 2027-01-02
+//! 2027-03-01T09:00 - 2027-03-01T12:00
+//! utc_offset = +01:00
+//! ```
+//!
+//! Times are in UTC unless a `utc_offset` is given. A name before `=` is
+//! shown when the window refuses a change.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::freeze::FreezeWindows;
+//!
+//! let windows = FreezeWindows::parse("weekend = fri 16:00 - mon 08:00")?;
+//! let client = SimplyClient::builder()
+//!     .credentials("account", "api_key")
+//!     .freeze_windows(windows)
+//!     .build()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::time::days_from_civil;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+const MINUTES_PER_WEEK: u32 = 7 * MINUTES_PER_DAY;
+const WEEKDAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// Error returned when freeze windows cannot be parsed.
+#[derive(Debug, Error)]
+#[error("{}{message}", line.map(|l| format!("line {l}: ")).unwrap_or_default())]
+pub struct FreezeError {
+    /// The 1-based line number where the problem was found, if known.
+    pub line: Option<usize>,
+    /// What was wrong.
+    pub message: String,
+}
+
+impl FreezeError {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            line: None,
+            message: message.into(),
+        }
+    }
+}
+
+/// When a [`FreezeWindow`] is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Schedule {
+    /// Every week, in minutes since Monday 00:00. Wraps around the week if
+    /// `end` is before `start`.
+    Weekly { start: u32, end: u32 },
+    /// Once, in seconds since the Unix epoch.
+    Between { start: i64, end: i64 },
+}
+
+/// A time in which records must not be changed, see the [module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FreezeWindow {
+    name: Option<String>,
+    spec: String,
+    schedule: Schedule,
+}
+
+impl FreezeWindow {
+    /// A window from `start` up to `end`, in seconds since the Unix epoch.
+    pub fn between(start: u64, end: u64) -> Result<Self, FreezeError> {
+        if end <= start {
+            return Err(FreezeError::new("the window ends before it starts"));
+        }
+        Ok(Self {
+            name: None,
+            spec: format!("{start} - {end}"),
+            schedule: Schedule::Between {
+                start: i64::try_from(start).unwrap_or(i64::MAX),
+                end: i64::try_from(end).unwrap_or(i64::MAX),
+            },
+        })
+    }
+
+    /// Show the window as `name` when it refuses a change.
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Whether the window is active at `time`, in seconds since the Unix
+    /// epoch shifted by the UTC offset of the window's times.
+    fn contains(&self, time: i64) -> bool {
+        match self.schedule {
+            Schedule::Weekly { start, end } => {
+                // The epoch was a Thursday.
+                let days = time.div_euclid(86_400) + 3;
+                let minute = (days.rem_euclid(7) * MINUTES_PER_DAY as i64
+                    + time.rem_euclid(86_400) / 60) as u32;
+                if start < end {
+                    (start..end).contains(&minute)
+                } else {
+                    minute >= start || minute < end
+                }
+            }
+            Schedule::Between { start, end } => (start..end).contains(&time),
+        }
+    }
+}
+
+/// Shows the name of the window, or how it was written.
+impl fmt::Display for FreezeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{name} ({})", self.spec),
+            None => f.write_str(&self.spec),
+        }
+    }
+}
+
+/// Parses `fri 16:00 - mon 08:00` or `2026-12-20 - 2027-01-02`, see the
+/// [module docs](self).
+impl FromStr for FreezeWindow {
+    type Err = FreezeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let spec = s.trim();
+        let (start, end) = spec
+            .split_once(" - ")
+            .ok_or_else(|| FreezeError::new(format!("expected `start - end`, got `{spec}`")))?;
+        let (start, end) = (start.trim(), end.trim());
+        let schedule = if start.starts_with(|c: char| c.is_ascii_digit()) {
+            let (start, end) = (parse_date_time(start)?, parse_date_time(end)?);
+            if end <= start {
+                return Err(FreezeError::new(format!("`{spec}` ends before it starts")));
+            }
+            Schedule::Between { start, end }
+        } else {
+            let (start, end) = (parse_week_time(start)?, parse_week_time(end)?);
+            if end == start {
+                return Err(FreezeError::new(format!("`{spec}` is empty")));
+            }
+            Schedule::Weekly { start, end }
+        };
+        Ok(Self {
+            name: None,
+            spec: spec.to_string(),
+            schedule,
+        })
+    }
+}
+
+/// Freeze windows, see the [module docs](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FreezeWindows {
+    windows: Vec<FreezeWindow>,
+    utc_offset: i32,
+}
+
+impl FreezeWindows {
+    /// No windows, in UTC.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read windows written one per line, with `#` comments, see the
+    /// [module docs](self).
+    pub fn parse(input: &str) -> Result<Self, FreezeError> {
+        let mut windows = Self::new();
+        for (i, line) in input.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let at_line = |e: FreezeError| FreezeError {
+                line: Some(i + 1),
+                ..e
+            };
+            let (name, spec) = match line.split_once('=') {
+                Some((name, spec)) => (Some(name.trim()), spec.trim()),
+                None => (None, line),
+            };
+            if name == Some("utc_offset") {
+                windows.utc_offset = parse_offset(spec).map_err(at_line)?;
+                continue;
+            }
+            let window: FreezeWindow = spec.parse().map_err(at_line)?;
+            windows.windows.push(match name {
+                Some(name) => window.named(name),
+                None => window,
+            });
+        }
+        Ok(windows)
+    }
+
+    /// Add `window`.
+    pub fn with(mut self, window: FreezeWindow) -> Self {
+        self.windows.push(window);
+        self
+    }
+
+    /// Read the times of the windows as local times `minutes` ahead of UTC,
+    /// e.g. `60` for CET.
+    pub fn utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset = minutes;
+        self
+    }
+
+    /// The windows.
+    pub fn windows(&self) -> &[FreezeWindow] {
+        &self.windows
+    }
+
+    /// The first window active at `time`, in seconds since the Unix epoch.
+    pub fn active_at(&self, time: u64) -> Option<&FreezeWindow> {
+        let local = time as i64 + i64::from(self.utc_offset) * 60;
+        self.windows.iter().find(|w| w.contains(local))
+    }
+
+    /// The first window active now.
+    pub fn active(&self) -> Option<&FreezeWindow> {
+        self.active_at(crate::time::unix_time().as_secs())
+    }
+}
+
+/// Parses a weekday and time like `fri 16:00`, in minutes since Monday 00:00.
+fn parse_week_time(s: &str) -> Result<u32, FreezeError> {
+    let (day, time) = s
+        .split_once(' ')
+        .ok_or_else(|| FreezeError::new(format!("expected a weekday and time, got `{s}`")))?;
+    let day = day.to_ascii_lowercase();
+    let weekday = WEEKDAYS
+        .iter()
+        .position(|name| day.len() >= 3 && name.starts_with(&day))
+        .ok_or_else(|| FreezeError::new(format!("unknown weekday `{day}`")))?;
+    let minutes = weekday as u32 * MINUTES_PER_DAY + parse_time(time.trim())?;
+    Ok(minutes % MINUTES_PER_WEEK)
+}
+
+/// Parses `HH:MM`, up to `24:00`, in minutes since midnight.
+fn parse_time(s: &str) -> Result<u32, FreezeError> {
+    let invalid = || FreezeError::new(format!("invalid time `{s}`, expected HH:MM"));
+    let (hours, minutes) = s.split_once(':').ok_or_else(invalid)?;
+    let number = |n: &str| {
+        (n.len() == 2 && n.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| n.parse::<u32>().ok())
+            .flatten()
+            .ok_or_else(invalid)
+    };
+    let (hours, minutes) = (number(hours)?, number(minutes)?);
+    match hours * 60 + minutes {
+        total if minutes < 60 && total <= MINUTES_PER_DAY => Ok(total),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parses `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM`, in seconds since the Unix epoch.
+fn parse_date_time(s: &str) -> Result<i64, FreezeError> {
+    let (date, time) = match s.split_once('T') {
+        Some((date, time)) => (date, parse_time(time)?),
+        None => (s, 0),
+    };
+    let invalid = || FreezeError::new(format!("invalid date `{date}`, expected YYYY-MM-DD"));
+    let mut parts = date
+        .split('-')
+        .map(|n| n.parse::<u32>().map_err(|_| invalid()));
+    let (Some(year), Some(month), Some(day), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    let (year, month, day) = (year?, month?, day?);
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return Err(invalid());
+    }
+    Ok(days_from_civil(year as i64, month, day) * 86_400 + i64::from(time) * 60)
+}
+
+/// Parses a UTC offset like `+01:00` or `-05:30`, in minutes.
+fn parse_offset(s: &str) -> Result<i32, FreezeError> {
+    let invalid = || FreezeError::new(format!("invalid UTC offset `{s}`, expected +HH:MM"));
+    let (sign, time) = match s.as_bytes().first() {
+        Some(b'+') => (1, &s[1..]),
+        Some(b'-') => (-1, &s[1..]),
+        _ => return Err(invalid()),
+    };
+    match parse_time(time) {
+        Ok(minutes) if minutes < MINUTES_PER_DAY => Ok(sign * minutes as i32),
+        _ => Err(invalid()),
+    }
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2026-10-16, a Friday, at `hour`:`minute` UTC.
+    fn friday(hour: u64, minute: u64) -> u64 {
+        1_792_108_800 + hour * 3600 + minute * 60
+    }
+
+    #[test]
+    fn dates_count_days_since_the_epoch() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(crate::time::civil_from_days(11_017), (2000, 3, 1));
+        assert_eq!(crate::time::civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(parse_date_time("2026-10-16").unwrap(), friday(0, 0) as i64);
+        assert_eq!(
+            parse_date_time("2026-10-16T16:30").unwrap(),
+            friday(16, 30) as i64
+        );
+        assert!(parse_date_time("2026-02-29").is_err());
+        assert!(parse_date_time("2028-02-29").is_ok());
+        assert!(parse_date_time("2026-13-01").is_err());
+    }
+
+    #[test]
+    fn weekly_windows_wrap_around_the_week() {
+        let windows = FreezeWindows::parse("weekend = fri 16:00 - mon 08:00").unwrap();
+        assert_eq!(windows.active_at(friday(15, 59)), None);
+        let active = windows.active_at(friday(16, 0)).unwrap();
+        assert_eq!(active.to_string(), "weekend (fri 16:00 - mon 08:00)");
+        // Sunday noon and the following Monday morning.
+        assert!(windows.active_at(friday(12, 0) + 2 * 86_400).is_some());
+        assert!(windows.active_at(friday(7, 59) + 3 * 86_400).is_some());
+        assert_eq!(windows.active_at(friday(8, 0) + 3 * 86_400), None);
+    }
+
+    #[test]
+    fn windows_follow_the_utc_offset() {
+        let windows = FreezeWindows::parse(
+            "# Friday afternoons in CET.\nfriday 16:00 - saturday 00:00\nutc_offset = +01:00\n",
+        )
+        .unwrap();
+        assert_eq!(windows.active_at(friday(14, 59)), None);
+        assert!(windows.active_at(friday(15, 0)).is_some());
+        assert!(windows.active_at(friday(22, 59)).is_some());
+        assert_eq!(windows.active_at(friday(23, 0)), None);
+    }
+
+    #[test]
+    fn date_windows_end_before_their_end() {
+        let windows = FreezeWindows::new()
+            .with("2026-10-16 - 2026-10-17".parse().unwrap())
+            .with(
+                FreezeWindow::between(100, 200)
+                    .unwrap()
+                    .named("maintenance"),
+            );
+        assert_eq!(windows.active_at(friday(0, 0) - 1), None);
+        assert!(windows.active_at(friday(23, 59)).is_some());
+        assert_eq!(windows.active_at(friday(0, 0) + 86_400), None);
+        assert_eq!(
+            windows.active_at(150).unwrap().to_string(),
+            "maintenance (100 - 200)"
+        );
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let error =
+            FreezeWindows::parse("fri 16:00 - sat 00:00\nfry 16:00 - sat 00:00").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: unknown weekday `fry`");
+        assert!(FreezeWindows::parse("fri 16:00").is_err());
+        assert!(FreezeWindows::parse("fri 25:00 - sat 00:00").is_err());
+        assert!(FreezeWindows::parse("fri 16:00 - fri 16:00").is_err());
+        assert!(FreezeWindows::parse("2026-12-20 - 2026-12-01").is_err());
+        assert!(FreezeWindows::parse("utc_offset = 01:00").is_err());
+    }
+}
@@ -0,0 +1,272 @@
+//! Generating record sets from address blocks and number ranges.
+//!
+//! For provisioning fleets or lab networks, [`generate_a_records`] names every
+//! host address of a CIDR block after a template, and [`generate_records`]
+//! does the same for a range of numbers. The results are plain
+//! [`CreateDnsRecordRequest`]s, ready for
+//! [`SimplyClient::create_dns_records`](crate::SimplyClient::create_dns_records)
+//! or a [`DesiredZone`](crate::sync::DesiredZone).
+//!
+//! Templates contain `{n}`, replaced by the number, or `{n:0W}` to zero-pad it
+//! to `W` digits.
+//!
+//! Example usage:
+//! ```rust
+//! use simply_dns::generate::{generate_a_records, generate_records};
+//!
+//! let nodes = generate_a_records("node-{n}", "10.0.0.0/28")?;
+//! assert_eq!(nodes.len(), 14);
+//! assert_eq!((nodes[0].name.as_str(), nodes[0].data.as_str()), ("node-1", "10.0.0.1"));
+//!
+//! let aliases = generate_records("web{n:02}", 1..=3, "CNAME", "lb-{n}.example.com")?;
+//! assert_eq!((aliases[2].name.as_str(), aliases[2].data.as_str()), ("web03", "lb-3.example.com"));
+//! # Ok::<(), simply_dns::generate::GenerateError>(())
+//! ```
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::ops::RangeInclusive;
+
+use thiserror::Error;
+
+use crate::api::CreateDnsRecordRequest;
+
+/// The most records a single call may generate.
+pub const MAX_GENERATED: u128 = 65536;
+
+/// Error returned when records can't be generated.
+#[derive(Debug, Error)]
+pub enum GenerateError {
+    /// The address block isn't of the form `address/prefix`.
+    #[error("invalid CIDR block {0:?}")]
+    InvalidCidr(String),
+    /// The block or range holds more than [`MAX_GENERATED`] values.
+    #[error("{count} records requested, at most {MAX_GENERATED} can be generated")]
+    TooMany {
+        /// How many records would have been generated.
+        count: u128,
+    },
+}
+
+/// Generate an address record for every host address of `cidr`.
+///
+/// IPv4 blocks give `A` records and IPv6 blocks `AAAA` records. `{n}` in
+/// `name_template` is the address's offset in the block, so `10.0.0.5` in
+/// `10.0.0.0/24` is host 5. The network and broadcast addresses of IPv4 blocks
+/// larger than `/31`, and the subnet-router address of IPv6 blocks larger than
+/// `/127`, are left out.
+///
+/// # Arguments
+/// * `name_template` - The record name, e.g. "node-{n}".
+/// * `cidr` - The address block, e.g. "10.0.0.0/28".
+pub fn generate_a_records(
+    name_template: &str,
+    cidr: &str,
+) -> Result<Vec<CreateDnsRecordRequest>, GenerateError> {
+    let invalid = || GenerateError::InvalidCidr(cidr.to_string());
+    let (address, prefix) = cidr.split_once('/').ok_or_else(invalid)?;
+    let address: IpAddr = address.parse().map_err(|_| invalid())?;
+    let prefix: u32 = prefix.parse().map_err(|_| invalid())?;
+    let (bits, base) = match address {
+        IpAddr::V4(v4) => (32, u128::from(u32::from(v4))),
+        IpAddr::V6(v6) => (128, u128::from(v6)),
+    };
+    if prefix > bits {
+        return Err(invalid());
+    }
+    let host_bits = bits - prefix;
+    let size = 1u128.checked_shl(host_bits);
+    let network = base & !size.map_or(u128::MAX, |s| s - 1);
+
+    let (first, last) = match (address, size) {
+        (IpAddr::V4(_), Some(size)) if host_bits > 1 => (1, size - 2),
+        (IpAddr::V6(_), Some(size)) if host_bits > 1 => (1, size - 1),
+        (IpAddr::V6(_), None) => (1, u128::MAX),
+        (_, size) => (0, size.map_or(u128::MAX, |s| s - 1)),
+    };
+    check_count(last - first + 1)?;
+    Ok((first..=last)
+        .map(|offset| {
+            let (record_type, data) = match address {
+                IpAddr::V4(_) => ("A", Ipv4Addr::from((network + offset) as u32).to_string()),
+                IpAddr::V6(_) => ("AAAA", Ipv6Addr::from(network + offset).to_string()),
+            };
+            CreateDnsRecordRequest {
+                record_type: record_type.to_string(),
+                name: substitute(name_template, offset),
+                data,
+                priority: None,
+                ttl: None,
+                comment: None,
+            }
+        })
+        .collect())
+}
+
+/// Generate a record for every number in `range`.
+///
+/// `{n}` is replaced by the number in both `name_template` and `data_template`.
+///
+/// # Arguments
+/// * `name_template` - The record name, e.g. "host-{n}".
+/// * `range` - The numbers to generate records for.
+/// * `record_type` - Type of the records ("A", "CNAME", etc.).
+/// * `data_template` - The record data, e.g. "192.0.2.{n}".
+pub fn generate_records(
+    name_template: &str,
+    range: RangeInclusive<u32>,
+    record_type: &str,
+    data_template: &str,
+) -> Result<Vec<CreateDnsRecordRequest>, GenerateError> {
+    if let Some(count) = range.end().checked_sub(*range.start()) {
+        check_count(u128::from(count) + 1)?;
+    }
+    Ok(range
+        .map(|n| CreateDnsRecordRequest {
+            record_type: record_type.to_string(),
+            name: substitute(name_template, n.into()),
+            data: substitute(data_template, n.into()),
+            priority: None,
+            ttl: None,
+            comment: None,
+        })
+        .collect())
+}
+
+fn check_count(count: u128) -> Result<(), GenerateError> {
+    if count > MAX_GENERATED {
+        Err(GenerateError::TooMany { count })
+    } else {
+        Ok(())
+    }
+}
+
+/// Replace the `{n}` and `{n:0W}` placeholders of `template` with `n`.
+fn substitute(template: &str, n: u128) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{n") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let width = match after.split_once('}') {
+            Some(("", _)) => Some(0),
+            Some((spec, _)) => spec
+                .strip_prefix(":0")
+                .and_then(|w| w.parse::<usize>().ok()),
+            None => None,
+        };
+        match width {
+            Some(width) => {
+                out.push_str(&format!("{n:0width$}"));
+                rest = &after[after.find('}').unwrap_or(0) + 1..];
+            }
+            None => {
+                out.push_str("{n");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn too_many(result: Result<Vec<CreateDnsRecordRequest>, GenerateError>) -> Option<u128> {
+        match result {
+            Err(GenerateError::TooMany { count }) => Some(count),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn blocks_up_to_the_limit_are_generated() {
+        assert_eq!(
+            generate_a_records("h{n}", "10.0.0.0/16").unwrap().len(),
+            65534
+        );
+        let v6 = generate_a_records("h{n}", "2001:db8::/112").unwrap();
+        assert_eq!(v6.len(), 65535);
+        assert_eq!(v6[0].data, "2001:db8::1");
+        assert_eq!(v6[65534].data, "2001:db8::ffff");
+        let range = generate_records("h{n}", 1..=65536, "A", "192.0.2.1").unwrap();
+        assert_eq!(range.len() as u128, MAX_GENERATED);
+    }
+
+    #[test]
+    fn blocks_over_the_limit_are_rejected() {
+        assert_eq!(
+            too_many(generate_a_records("h{n}", "10.0.0.0/15")),
+            Some(131070)
+        );
+        assert_eq!(
+            too_many(generate_a_records("h{n}", "0.0.0.0/0")),
+            Some(4294967294)
+        );
+        assert_eq!(
+            too_many(generate_a_records("h{n}", "2001:db8::/111")),
+            Some(131071)
+        );
+        assert_eq!(
+            too_many(generate_a_records("h{n}", "::/0")),
+            Some(u128::MAX)
+        );
+        assert_eq!(
+            too_many(generate_records("h{n}", 0..=65536, "A", "x")),
+            Some(65537)
+        );
+        assert_eq!(
+            too_many(generate_records("h{n}", 0..=u32::MAX, "A", "x")),
+            Some(1 << 32)
+        );
+    }
+
+    #[test]
+    fn small_blocks_keep_every_address() {
+        let host = generate_a_records("h{n}", "192.0.2.7/32").unwrap();
+        assert_eq!(
+            (host[0].name.as_str(), host[0].data.as_str()),
+            ("h0", "192.0.2.7")
+        );
+        let pair = generate_a_records("h{n}", "192.0.2.7/31").unwrap();
+        assert_eq!(pair.len(), 2);
+        assert_eq!(pair[0].data, "192.0.2.6");
+        assert_eq!(
+            generate_a_records("h{n}", "2001:db8::/127").unwrap().len(),
+            2
+        );
+        assert!(
+            generate_records("h{n}", RangeInclusive::new(5, 1), "A", "x")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn invalid_blocks_are_rejected() {
+        for cidr in [
+            "10.0.0.0",
+            "10.0.0.0/33",
+            "2001:db8::/129",
+            "example/24",
+            "10.0.0.0/x",
+        ] {
+            assert!(
+                matches!(
+                    generate_a_records("h{n}", cidr),
+                    Err(GenerateError::InvalidCidr(_))
+                ),
+                "{cidr}"
+            );
+        }
+    }
+
+    #[test]
+    fn placeholders_are_substituted() {
+        assert_eq!(substitute("web{n}-{n:03}", 7), "web7-007");
+        assert_eq!(substitute("{n:02}", 123), "123");
+        // Anything else is left as it is.
+        assert_eq!(substitute("{n:3} {x} {n", 7), "{n:3} {x} {n");
+    }
+}
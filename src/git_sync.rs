@@ -0,0 +1,344 @@
+//! Zone definitions kept in a Git repository.
+//!
+//! A [`GitZoneRepo`] is a directory of a Git working tree holding one
+//! [zone definition](crate::config) per domain, e.g. `zones/example.com.toml`.
+//! [`SimplyClient::plan_git`] plans every zone whose file changed since a
+//! commit, the one last applied, and [`SimplyClient::apply_git`] applies the
+//! plans, so a CI job run on every push keeps the zones in step with the
+//! repository.
+//!
+//! A file is for the domain it names with `domain = ...`, otherwise for the
+//! domain in its file name. Only `.toml`, `.yaml`, `.yml` and `.json` files
+//! directly in the directory are read. Deleting a file leaves its zone alone; the deleted
+//! files are listed so a pipeline can decide what to do about them. Git is
+//! run as the `git` command, which must be on the `PATH`.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::git_sync::GitZoneRepo;
+//!
+//! # async fn run() -> Result<(), simply_dns::git_sync::GitSyncError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let repo = GitZoneRepo::open("zones")?;
+//! let plans = client.plan_git(&repo, Some("HEAD~1")).await?;
+//! for zone in &plans.zones {
+//!     println!("{}", zone.plan);
+//! }
+//! let report = client.apply_git(plans).await;
+//! println!("{report}");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use thiserror::Error;
+
+use crate::config::{ConfigError, ZoneConfig};
+use crate::report::OperationReport;
+use crate::sync::{Change, ZonePlan};
+use crate::{SimplyClient, SimplyClientError};
+
+/// Error type for [`GitZoneRepo`] and the Git sync methods of [`SimplyClient`].
+#[derive(Debug, Error)]
+pub enum GitSyncError {
+    /// A Git command failed.
+    #[error("git {command} failed: {message}")]
+    Git {
+        /// The arguments Git was run with.
+        command: String,
+        /// What Git printed to standard error.
+        message: String,
+    },
+    /// A zone file or the directory couldn't be read, or Git couldn't be run.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A zone file isn't a valid zone definition.
+    #[error("{}: {source}", path.display())]
+    Config {
+        /// The file.
+        path: PathBuf,
+        /// What was wrong with it.
+        #[source]
+        source: ConfigError,
+    },
+    /// The Simply.com API call failed.
+    #[error(transparent)]
+    Client(#[from] SimplyClientError),
+}
+
+/// A change to a zone file, from [`GitZoneRepo::changes_since`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZoneFileChange {
+    /// The file was added or modified.
+    Modified(PathBuf),
+    /// The file was deleted.
+    Deleted(PathBuf),
+}
+
+/// A directory of zone files in a Git working tree, see the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct GitZoneRepo {
+    dir: PathBuf,
+}
+
+impl GitZoneRepo {
+    /// Open the zone directory `dir`, failing if it isn't inside a Git working tree.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, GitSyncError> {
+        let repo = Self { dir: dir.into() };
+        repo.git(&["rev-parse", "--is-inside-work-tree"])?;
+        Ok(repo)
+    }
+
+    /// The zone directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The commit checked out, e.g. to remember as applied.
+    pub fn head(&self) -> Result<String, GitSyncError> {
+        Ok(self.git(&["rev-parse", "HEAD"])?.trim().to_string())
+    }
+
+    /// Every zone file in the directory, sorted.
+    pub fn zone_files(&self) -> Result<Vec<PathBuf>, GitSyncError> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() && is_zone_file(&entry.path()) {
+                files.push(entry.path());
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// The zone files changed between `commit` and the working tree, sorted.
+    ///
+    /// # Arguments
+    /// * `commit` - The commit to compare with, anything `git diff` accepts.
+    pub fn changes_since(&self, commit: &str) -> Result<Vec<ZoneFileChange>, GitSyncError> {
+        let output = self.git(&[
+            "diff",
+            "--name-status",
+            "--no-renames",
+            "--relative",
+            "-z",
+            commit,
+            "--",
+            ".",
+        ])?;
+        let mut fields = output.split('\0').filter(|f| !f.is_empty());
+        let mut changes = Vec::new();
+        while let (Some(status), Some(file)) = (fields.next(), fields.next()) {
+            let path = self.dir.join(file);
+            // Files in subdirectories aren't zone files.
+            if file.contains('/') || !is_zone_file(&path) {
+                continue;
+            }
+            changes.push(match status {
+                "D" => ZoneFileChange::Deleted(path),
+                _ => ZoneFileChange::Modified(path),
+            });
+        }
+        changes.sort_by(|a, b| change_path(a).cmp(change_path(b)));
+        Ok(changes)
+    }
+
+    /// Read the zone file at `path`, returning the domain it is for and its definition.
+    pub fn load(&self, path: &Path) -> Result<(String, ZoneConfig), GitSyncError> {
+        let input = std::fs::read_to_string(path)?;
+        let config = match path.extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("json") => ZoneConfig::from_json(&input),
+            Some(e) if e.eq_ignore_ascii_case("yaml") || e.eq_ignore_ascii_case("yml") => {
+                ZoneConfig::from_yaml(&input)
+            }
+            _ => ZoneConfig::from_toml(&input),
+        }
+        .map_err(|source| GitSyncError::Config {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let domain = match &config.domain {
+            Some(domain) => domain.clone(),
+            None => path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string(),
+        };
+        Ok((domain, config))
+    }
+
+    fn git(&self, args: &[&str]) -> Result<String, GitSyncError> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&self.dir)
+            .output()?;
+        if !output.status.success() {
+            return Err(GitSyncError::Git {
+                command: args.join(" "),
+                message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+fn is_zone_file(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| {
+        ["toml", "yaml", "yml", "json"]
+            .iter()
+            .any(|ext| e.eq_ignore_ascii_case(ext))
+    })
+}
+
+fn change_path(change: &ZoneFileChange) -> &Path {
+    match change {
+        ZoneFileChange::Modified(path) | ZoneFileChange::Deleted(path) => path,
+    }
+}
+
+/// The plan for the zone of one file.
+#[derive(Debug)]
+pub struct GitZonePlan {
+    /// The zone file.
+    pub path: PathBuf,
+    /// The changes making the zone match the file.
+    pub plan: ZonePlan,
+}
+
+/// The plans for the changed zone files of a [`GitZoneRepo`], from
+/// [`SimplyClient::plan_git`].
+#[derive(Debug, Default)]
+pub struct GitPlans {
+    /// A plan per added or modified file, in file order.
+    pub zones: Vec<GitZonePlan>,
+    /// Files that were deleted, whose zones are left alone.
+    pub deleted: Vec<PathBuf>,
+}
+
+impl GitPlans {
+    /// Whether no zone needs changing.
+    pub fn is_empty(&self) -> bool {
+        self.zones.iter().all(|z| z.plan.is_empty())
+    }
+}
+
+/// How applying the zone of one file went.
+#[derive(Debug)]
+pub struct AppliedZone {
+    /// The zone file.
+    pub path: PathBuf,
+    /// The domain of the zone.
+    pub domain: String,
+    /// The outcome of each change.
+    pub report: OperationReport<Change>,
+}
+
+/// The outcome of [`SimplyClient::apply_git`].
+#[derive(Debug, Default)]
+pub struct GitApplyReport {
+    /// The zones that were applied, in file order.
+    pub zones: Vec<AppliedZone>,
+    /// Files that were deleted, whose zones were left alone.
+    pub deleted: Vec<PathBuf>,
+}
+
+impl GitApplyReport {
+    /// How many changes failed across all zones.
+    pub fn failed(&self) -> usize {
+        self.zones.iter().map(|z| z.report.failed.len()).sum()
+    }
+
+    /// How many changes were skipped across all zones, e.g. during a
+    /// [freeze](crate::freeze).
+    pub fn skipped(&self) -> usize {
+        self.zones.iter().map(|z| z.report.skipped.len()).sum()
+    }
+}
+
+/// Shows one line per zone, e.g. `zones/example.com.toml (example.com): 2 succeeded, ...`.
+impl fmt::Display for GitApplyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for zone in &self.zones {
+            writeln!(
+                f,
+                "{} ({}): {}",
+                zone.path.display(),
+                zone.domain,
+                zone.report
+            )?;
+        }
+        for path in &self.deleted {
+            writeln!(f, "{}: deleted, zone left alone", path.display())?;
+        }
+        write!(
+            f,
+            "{} zones, {} changes failed",
+            self.zones.len(),
+            self.failed()
+        )
+    }
+}
+
+impl SimplyClient {
+    /// Plan the zones of the files in `repo` changed since `since`, or of
+    /// every file if `since` is `None`.
+    ///
+    /// Fails if Git fails, a changed file can't be read or a zone can't be
+    /// planned, before anything is applied.
+    ///
+    /// # Arguments
+    /// * `repo` - The zone directory.
+    /// * `since` - The commit last applied, anything `git diff` accepts.
+    pub async fn plan_git(
+        &self,
+        repo: &GitZoneRepo,
+        since: Option<&str>,
+    ) -> Result<GitPlans, GitSyncError> {
+        let mut plans = GitPlans::default();
+        let mut files = Vec::new();
+        match since {
+            Some(commit) => {
+                for change in repo.changes_since(commit)? {
+                    match change {
+                        ZoneFileChange::Modified(path) => files.push(path),
+                        ZoneFileChange::Deleted(path) => plans.deleted.push(path),
+                    }
+                }
+            }
+            None => files = repo.zone_files()?,
+        }
+        for path in files {
+            let (domain, config) = repo.load(&path)?;
+            let plan = self.plan(&domain, &config.zone).await?;
+            plans.zones.push(GitZonePlan { path, plan });
+        }
+        Ok(plans)
+    }
+
+    /// Apply the plans of [`plan_git`](Self::plan_git), one zone after another.
+    ///
+    /// A failed change doesn't stop the remaining ones; the outcome of each
+    /// is collected in the returned report.
+    pub async fn apply_git(&self, plans: GitPlans) -> GitApplyReport {
+        let mut report = GitApplyReport {
+            deleted: plans.deleted,
+            ..Default::default()
+        };
+        for GitZonePlan { path, plan } in plans.zones {
+            let domain = plan.domain.clone();
+            let applied = self.apply(plan).await;
+            report.zones.push(AppliedZone {
+                path,
+                domain,
+                report: applied,
+            });
+        }
+        report
+    }
+}
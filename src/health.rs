@@ -0,0 +1,136 @@
+//! Checking that the client can reach the API and its credentials work.
+//!
+//! Daemons such as a DDNS updater should fail at startup rather than in their
+//! first update, hours later. [`SimplyClient::check_credentials`] makes one
+//! cheap authenticated call and tells apart the failures that need different
+//! reactions: rejected credentials and a suspended account need a human,
+//! while an unreachable API is worth waiting for.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::health::CredentialCheck;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! match client.check_credentials().await? {
+//!     CredentialCheck::Valid { .. } => println!("starting update loop"),
+//!     CredentialCheck::Unreachable { message } => eprintln!("API unreachable, retrying: {message}"),
+//!     failure => {
+//!         eprintln!("{failure}");
+//!         std::process::exit(1);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::retry::transient_error;
+use crate::time::Instant;
+use crate::{SimplyClient, SimplyClientError};
+
+/// Words in an API error that mean the account, not the key, is the problem.
+const SUSPENDED_HINTS: [&str; 5] = ["suspend", "blocked", "locked", "disabled", "inactive"];
+
+/// The outcome of [`SimplyClient::check_credentials`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CredentialCheck {
+    /// The credentials were accepted.
+    Valid {
+        /// The number of products on the account.
+        products: usize,
+        /// How long the call took.
+        latency: Duration,
+    },
+    /// The API rejected the account or API key.
+    InvalidCredentials {
+        /// The message returned by the API.
+        message: String,
+    },
+    /// The credentials are known, but the account may not use the API, e.g.
+    /// because it is suspended or has unpaid invoices.
+    Suspended {
+        /// The message returned by the API.
+        message: String,
+    },
+    /// The API couldn't be reached: the connection failed or timed out.
+    Unreachable {
+        /// What went wrong.
+        message: String,
+    },
+}
+
+impl CredentialCheck {
+    /// Whether the credentials were accepted.
+    pub fn is_valid(&self) -> bool {
+        matches!(self, CredentialCheck::Valid { .. })
+    }
+}
+
+impl fmt::Display for CredentialCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CredentialCheck::Valid { products, latency } => write!(
+                f,
+                "credentials valid, {products} products, answered in {latency:.2?}"
+            ),
+            CredentialCheck::InvalidCredentials { message } => {
+                write!(f, "invalid credentials: {message}")
+            }
+            CredentialCheck::Suspended { message } => write!(f, "account suspended: {message}"),
+            CredentialCheck::Unreachable { message } => write!(f, "API unreachable: {message}"),
+        }
+    }
+}
+
+impl SimplyClient {
+    /// Check the client's credentials with one cheap authenticated call,
+    /// listing the account's products.
+    ///
+    /// Returns the classified outcome; fails only with errors that fit none
+    /// of the [`CredentialCheck`] cases, such as a server error, for which
+    /// [`SimplyClientError::is_retryable`] tells whether to try again.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn check_credentials(&self) -> Result<CredentialCheck, SimplyClientError> {
+        let started = Instant::now();
+        match self.list_products().await {
+            Ok(products) => Ok(CredentialCheck::Valid {
+                products: products.len(),
+                latency: started.elapsed(),
+            }),
+            Err(e) => classify(e),
+        }
+    }
+}
+
+fn classify(error: SimplyClientError) -> Result<CredentialCheck, SimplyClientError> {
+    let suspended = |message: &str| {
+        let message = message.to_ascii_lowercase();
+        SUSPENDED_HINTS.iter().any(|hint| message.contains(hint))
+    };
+    Ok(match error {
+        SimplyClientError::Unauthorized { message, .. } if suspended(&message) => {
+            CredentialCheck::Suspended { message }
+        }
+        SimplyClientError::Unauthorized { message, .. } => {
+            CredentialCheck::InvalidCredentials { message }
+        }
+        // 402 Payment Required.
+        SimplyClientError::Api {
+            status: 402,
+            message,
+        } => CredentialCheck::Suspended { message },
+        SimplyClientError::Http(e) if transient_error(&e) => CredentialCheck::Unreachable {
+            message: e.to_string(),
+        },
+        SimplyClientError::Io(e) => CredentialCheck::Unreachable {
+            message: e.to_string(),
+        },
+        e => return Err(e),
+    })
+}
@@ -0,0 +1,496 @@
+//! Import and export records as `/etc/hosts`-style files.
+//!
+//! [`parse_hosts`] turns each `address hostname [aliases...]` entry into
+//! `A`/`AAAA` records of a [`DesiredZone`], with a mapping rule deciding which
+//! host names are published and under which record name. [`parse_compose`]
+//! does the same for the static addresses of the services of a Docker Compose
+//! file. [`format_hosts`] goes the other way, e.g. for air-gapped machines or
+//! before DNS changes propagate.
+//!
+//! The zones are meant to be [planned](crate::SimplyClient::plan) like any
+//! other, usually [scoped](DesiredZone::scoped_to) or
+//! [owned](DesiredZone::owned_by) so that the other records of the domain are
+//! left alone.
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::api::DnsRecord;
+use crate::config::{ConfigError, read_yaml};
+use crate::sync::{DesiredRecord, DesiredZone};
+
+/// Error returned when a hosts file contains an invalid entry.
+#[derive(Debug, Error)]
+#[error("line {line}: {message}")]
+pub struct HostsParseError {
+    /// The 1-based line number of the invalid entry.
+    pub line: usize,
+    /// What was wrong with the entry.
+    pub message: String,
+}
+
+/// A mapping rule publishing the host names that belong to `domain`.
+///
+/// `www.example.com` becomes `www` and `example.com` itself becomes `@`. Names
+/// without dots (e.g. `nas`) are kept as they are; other names are skipped.
+pub fn zone_mapping(domain: &str) -> impl Fn(&str) -> Option<String> {
+    let zone = domain.trim_end_matches('.').to_ascii_lowercase();
+    move |host: &str| {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        if host == zone {
+            Some("@".to_string())
+        } else if !host.contains('.') {
+            Some(host)
+        } else {
+            host.strip_suffix(&zone)?
+                .strip_suffix('.')
+                .map(str::to_string)
+        }
+    }
+}
+
+/// Parse an `/etc/hosts`-style file into the records of a zone.
+///
+/// Every host name and alias on a line becomes a record pointing at the line's
+/// address, named by `map_name`; names for which it returns `None` are skipped.
+/// Comments, blank lines, duplicate entries and loopback/unspecified addresses
+/// (such as `127.0.0.1 localhost`) are ignored.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::hosts::{parse_hosts, zone_mapping};
+///
+/// let hosts = "10.0.0.5  nas.example.com nas  # file server\n";
+/// let zone = parse_hosts(hosts, zone_mapping("example.com"))?;
+/// assert_eq!(zone.records.len(), 1);
+/// assert_eq!(zone.records[0].name, "nas");
+/// # Ok::<(), simply_dns::hosts::HostsParseError>(())
+/// ```
+pub fn parse_hosts(
+    input: &str,
+    map_name: impl Fn(&str) -> Option<String>,
+) -> Result<DesiredZone, HostsParseError> {
+    let mut zone = DesiredZone::new();
+    for (i, line) in input.lines().enumerate() {
+        let line_no = i + 1;
+        let entry = line.split('#').next().unwrap_or_default();
+        let mut fields = entry.split_whitespace();
+        let Some(address) = fields.next() else {
+            continue;
+        };
+        let ip: IpAddr = address.parse().map_err(|_| HostsParseError {
+            line: line_no,
+            message: format!("invalid address {address:?}"),
+        })?;
+        let mut hosts = fields.peekable();
+        if hosts.peek().is_none() {
+            return Err(HostsParseError {
+                line: line_no,
+                message: format!("no host name for {address}"),
+            });
+        }
+        for name in hosts.filter_map(&map_name) {
+            add_address(&mut zone, name, ip);
+        }
+    }
+    Ok(zone)
+}
+
+/// Parse a Docker Compose file into the records of its services' static
+/// addresses.
+///
+/// A service with an `ipv4_address` or `ipv6_address` on one of its
+/// `networks` gets a record per address for its name, its `hostname`
+/// (qualified with its `domainname`, if set) and the `aliases` on that
+/// network, each named by `map_name` like in [`parse_hosts`]. Its
+/// `extra_hosts` entries are read like the lines of a hosts file. Services
+/// without static addresses have nothing to publish and are left out, as are
+/// loopback addresses and `host-gateway`.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::hosts::{parse_compose, zone_mapping};
+///
+/// let compose = r#"
+/// services:
+///   web:
+///     image: nginx
+///     hostname: web
+///     domainname: example.com
+///     networks:
+///       front:
+///         ipv4_address: 172.16.238.10
+///         aliases: [www.example.com]
+/// "#;
+/// let zone = parse_compose(compose, zone_mapping("example.com"))?;
+/// let names: Vec<&str> = zone.records.iter().map(|r| r.name.as_str()).collect();
+/// assert_eq!(names, ["web", "www"]);
+/// # Ok::<(), simply_dns::config::ConfigError>(())
+/// ```
+pub fn parse_compose(
+    input: &str,
+    map_name: impl Fn(&str) -> Option<String>,
+) -> Result<DesiredZone, ConfigError> {
+    let file: ComposeFile = serde_json::from_value(read_yaml(input)?).map_err(|e| ConfigError {
+        line: None,
+        message: format!("not a Compose file: {e}"),
+    })?;
+    let invalid = |service: &str, message: String| ConfigError {
+        line: None,
+        message: format!("service {service}: {message}"),
+    };
+    let mut zone = DesiredZone::new();
+    for (service, config) in &file.services {
+        let hostname = match (&config.hostname, &config.domainname) {
+            (Some(host), Some(domain)) => Some(format!("{host}.{domain}")),
+            (host, _) => host.clone(),
+        };
+        for (network, settings) in config.networks.iter() {
+            let Some(settings) = settings else {
+                continue;
+            };
+            for (key, address) in [
+                ("ipv4_address", &settings.ipv4_address),
+                ("ipv6_address", &settings.ipv6_address),
+            ] {
+                let Some(address) = address else {
+                    continue;
+                };
+                let ip: IpAddr = address.parse().map_err(|_| {
+                    invalid(
+                        service,
+                        format!("invalid {key} {address:?} on network {network}"),
+                    )
+                })?;
+                let names = std::iter::once(service)
+                    .chain(&hostname)
+                    .chain(&settings.aliases);
+                for name in names.filter_map(|name| map_name(name)) {
+                    add_address(&mut zone, name, ip);
+                }
+            }
+        }
+        for (host, address) in config.extra_hosts.entries() {
+            if address == "host-gateway" {
+                continue;
+            }
+            let ip: IpAddr = (address.trim_start_matches('[').trim_end_matches(']'))
+                .parse()
+                .map_err(|_| {
+                    invalid(service, format!("invalid extra_hosts address {address:?}"))
+                })?;
+            if let Some(name) = map_name(host) {
+                add_address(&mut zone, name, ip);
+            }
+        }
+    }
+    Ok(zone)
+}
+
+/// Add a record of `name` pointing at `ip` to `zone`, unless it has one or
+/// `ip` is a loopback or unspecified address.
+fn add_address(zone: &mut DesiredZone, name: String, ip: IpAddr) {
+    if ip.is_loopback() || ip.is_unspecified() {
+        return;
+    }
+    let record_type = if ip.is_ipv4() { "A" } else { "AAAA" };
+    let data = ip.to_string();
+    let duplicate = zone.records.iter().any(|r| {
+        r.name.eq_ignore_ascii_case(&name) && r.record_type == record_type && r.data == data
+    });
+    if !duplicate {
+        zone.add(DesiredRecord::new(name, record_type, data));
+    }
+}
+
+/// The parts of a Compose file that [`parse_compose`] reads.
+#[derive(Deserialize)]
+struct ComposeFile {
+    #[serde(default)]
+    services: BTreeMap<String, ComposeService>,
+}
+
+#[derive(Deserialize)]
+struct ComposeService {
+    hostname: Option<String>,
+    domainname: Option<String>,
+    #[serde(default)]
+    networks: ComposeNetworks,
+    #[serde(default)]
+    extra_hosts: ExtraHosts,
+}
+
+/// The networks of a service, as a list of names or a mapping to settings.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ComposeNetworks {
+    Names(Vec<String>),
+    Settings(BTreeMap<String, Option<NetworkSettings>>),
+}
+
+impl Default for ComposeNetworks {
+    fn default() -> Self {
+        Self::Names(Vec::new())
+    }
+}
+
+impl ComposeNetworks {
+    /// The networks and their settings, if they have any.
+    fn iter(&self) -> Vec<(&str, Option<&NetworkSettings>)> {
+        match self {
+            Self::Names(names) => names.iter().map(|n| (n.as_str(), None)).collect(),
+            Self::Settings(networks) => (networks.iter())
+                .map(|(name, settings)| (name.as_str(), settings.as_ref()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct NetworkSettings {
+    ipv4_address: Option<String>,
+    ipv6_address: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+}
+
+/// The `extra_hosts` of a service, as `host=address` or `host:address`
+/// entries or a mapping of hosts to addresses.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExtraHosts {
+    Entries(Vec<String>),
+    Mapping(BTreeMap<String, HostAddresses>),
+}
+
+impl Default for ExtraHosts {
+    fn default() -> Self {
+        Self::Entries(Vec::new())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HostAddresses {
+    One(String),
+    Several(Vec<String>),
+}
+
+impl ExtraHosts {
+    /// The host names and addresses.
+    fn entries(&self) -> Vec<(&str, &str)> {
+        match self {
+            Self::Entries(entries) => (entries.iter())
+                .filter_map(|entry| entry.split_once('=').or_else(|| entry.split_once(':')))
+                .map(|(host, address)| (host.trim(), address.trim()))
+                .collect(),
+            Self::Mapping(hosts) => (hosts.iter())
+                .flat_map(|(host, addresses)| {
+                    let addresses = match addresses {
+                        HostAddresses::One(address) => std::slice::from_ref(address),
+                        HostAddresses::Several(addresses) => addresses.as_slice(),
+                    };
+                    addresses.iter().map(|a| (host.as_str(), a.trim()))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Format the `A` and `AAAA` records among `records` as an `/etc/hosts` fragment.
+///
+/// Names are written fully qualified under `domain`; other record types are skipped.
+pub fn format_hosts<'a>(domain: &str, records: impl IntoIterator<Item = &'a DnsRecord>) -> String {
+    let zone = domain.trim_end_matches('.');
+    let mut out = String::new();
+    for record in records {
+        let is_address = ["A", "AAAA"]
+            .iter()
+            .any(|t| record.record_type.eq_ignore_ascii_case(t));
+        if !is_address {
+            continue;
+        }
+        let host = match record.name.trim_end_matches('.') {
+            "" | "@" => zone.to_string(),
+            name if name.eq_ignore_ascii_case(zone) || name.ends_with(&format!(".{zone}")) => {
+                name.to_string()
+            }
+            name => format!("{name}.{zone}"),
+        };
+        out.push_str(&format!("{}\t{}\n", record.data, host));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::DnsRecordId;
+
+    fn records(zone: &DesiredZone) -> Vec<(&str, &str, &str)> {
+        (zone.records.iter())
+            .map(|r| (r.name.as_str(), r.record_type.as_str(), r.data.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn hosts_files_skip_comments_and_loopback_entries() {
+        let input = "\
+# The machines on the LAN
+127.0.0.1 localhost
+::1       localhost ip6-localhost
+0.0.0.0   blocked.example.com
+
+10.0.0.5  nas.example.com   # file server
+10.0.0.6  other.example.org
+";
+        let zone = parse_hosts(input, zone_mapping("example.com")).unwrap();
+        assert_eq!(records(&zone), [("nas", "A", "10.0.0.5")]);
+    }
+
+    #[test]
+    fn hosts_files_publish_aliases_and_ipv6_addresses() {
+        let input = "\
+10.0.0.5       nas.example.com nas files.example.com
+2001:db8::5    nas.example.com
+example.com.   is-not-an-address
+";
+        let err = parse_hosts(input, zone_mapping("example.com")).unwrap_err();
+        assert_eq!(err.line, 3);
+
+        let input = input.lines().take(2).collect::<Vec<_>>().join("\n");
+        let zone = parse_hosts(&input, zone_mapping("example.com")).unwrap();
+        assert_eq!(
+            records(&zone),
+            [
+                ("nas", "A", "10.0.0.5"),
+                ("files", "A", "10.0.0.5"),
+                ("nas", "AAAA", "2001:db8::5"),
+            ]
+        );
+    }
+
+    #[test]
+    fn hosts_files_fold_duplicates_that_differ_in_case() {
+        let input = "\
+10.0.0.5  NAS.example.com nas
+10.0.0.5  nas.EXAMPLE.com
+10.0.0.7  nas.example.com
+";
+        let zone = parse_hosts(input, zone_mapping("example.com")).unwrap();
+        assert_eq!(
+            records(&zone),
+            [("nas", "A", "10.0.0.5"), ("nas", "A", "10.0.0.7")]
+        );
+    }
+
+    #[test]
+    fn formatted_hosts_files_parse_back_to_the_same_records() {
+        let listed = |id, name: &str, record_type: &str, data: &str| DnsRecord {
+            record_id: DnsRecordId::new(id),
+            name: name.to_string(),
+            ttl: 3600,
+            data: data.to_string(),
+            record_type: record_type.to_string(),
+            priority: None,
+            comment: None,
+        };
+        let existing = [
+            listed(1, "@", "A", "192.0.2.1"),
+            listed(2, "www", "A", "192.0.2.2"),
+            listed(3, "www", "AAAA", "2001:db8::2"),
+            listed(4, "mail.example.com", "A", "192.0.2.3"),
+            listed(5, "@", "MX", "mail.example.com"),
+        ];
+        let formatted = format_hosts("example.com.", &existing);
+        assert_eq!(
+            formatted,
+            "192.0.2.1\texample.com\n192.0.2.2\twww.example.com\n\
+             2001:db8::2\twww.example.com\n192.0.2.3\tmail.example.com\n"
+        );
+        let zone = parse_hosts(&formatted, zone_mapping("example.com")).unwrap();
+        assert_eq!(
+            records(&zone),
+            [
+                ("@", "A", "192.0.2.1"),
+                ("www", "A", "192.0.2.2"),
+                ("www", "AAAA", "2001:db8::2"),
+                ("mail", "A", "192.0.2.3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn compose_services_publish_their_static_addresses() {
+        let input = r#"
+x-lan: &lan
+  ipv4_address: 10.0.0.9
+services:
+  db:
+    image: postgres
+    networks: [back]
+  web:
+    image: nginx
+    hostname: web
+    domainname: example.com
+    networks:
+      front:
+        ipv4_address: 172.16.238.10
+        ipv6_address: "2001:db8::10"
+        aliases:
+          - www.example.com
+          - WEB
+      back:
+    extra_hosts:
+      - "api.example.com=10.0.0.20"
+      - "legacy.example.com:10.0.0.21"
+      - "v6.example.com:[2001:db8::21]"
+      - "host.docker.internal:host-gateway"
+      - "elsewhere.example.org:10.0.0.22"
+  cache:
+    networks:
+      lan:
+        <<: *lan
+    extra_hosts:
+      metrics.example.com: 10.0.0.30
+      local.example.com: 127.0.0.1
+"#;
+        let zone = parse_compose(input, zone_mapping("example.com")).unwrap();
+        assert_eq!(
+            records(&zone),
+            [
+                ("cache", "A", "10.0.0.9"),
+                ("metrics", "A", "10.0.0.30"),
+                ("web", "A", "172.16.238.10"),
+                ("www", "A", "172.16.238.10"),
+                ("web", "AAAA", "2001:db8::10"),
+                ("www", "AAAA", "2001:db8::10"),
+                ("api", "A", "10.0.0.20"),
+                ("legacy", "A", "10.0.0.21"),
+                ("v6", "AAAA", "2001:db8::21"),
+            ]
+        );
+    }
+
+    #[test]
+    fn compose_errors_name_the_service() {
+        let input = "\
+services:
+  web:
+    networks:
+      front:
+        ipv4_address: 10.0.0.300
+";
+        let err = parse_compose(input, zone_mapping("example.com")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "service web: invalid ipv4_address \"10.0.0.300\" on network front"
+        );
+
+        let err = parse_compose("services: [web]\n", zone_mapping("example.com")).unwrap_err();
+        assert!(err.to_string().starts_with("not a Compose file"), "{err}");
+    }
+}
@@ -0,0 +1,207 @@
+//! A minimal HTTP/1.1 server for the mock API, the fixture server and the
+//! external-dns provider.
+//!
+//! Only what those need is supported: keep-alive connections, requests with a
+//! `content-length` body, and responses with a fixed-size body.
+
+use std::future::Future;
+use std::io;
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Upper bound on the size of a request, headers included.
+const MAX_REQUEST_SIZE: usize = 1 << 20;
+
+pub(crate) struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// The value of the header `name`, compared case-insensitively.
+    #[cfg_attr(
+        not(any(feature = "mock-server", feature = "provider")),
+        allow(dead_code)
+    )]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The path without the query string, split into its segments.
+    pub fn segments(&self) -> Vec<&str> {
+        let path = self.path.split('?').next().unwrap_or_default();
+        path.trim_matches('/').split('/').collect()
+    }
+}
+
+pub(crate) struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// A response with a body of `content_type`.
+    pub fn new(status: u16, content_type: &str, body: String) -> Self {
+        Self {
+            status,
+            headers: vec![("content-type", content_type.to_string())],
+            body,
+        }
+    }
+}
+
+/// Accept connections on `listener` forever, answering each request with `handler`.
+pub(crate) async fn serve<H, F>(listener: TcpListener, handler: H)
+where
+    H: Fn(HttpRequest) -> F + Send + Sync + 'static,
+    F: Future<Output = HttpResponse> + Send + 'static,
+{
+    let handler = Arc::new(handler);
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        tokio::spawn(serve_connection(stream, handler.clone()));
+    }
+}
+
+/// Serve HTTP/1.1 requests on `stream` until the client closes it.
+async fn serve_connection<H, F>(mut stream: TcpStream, handler: Arc<H>)
+where
+    H: Fn(HttpRequest) -> F,
+    F: Future<Output = HttpResponse>,
+{
+    let mut buf = Vec::new();
+    loop {
+        let req = match read_request(&mut stream, &mut buf).await {
+            Ok(Some(req)) => req,
+            Err(ReadError::TooLarge) => {
+                let mut res = HttpResponse::new(413, "text/plain", "request too large".to_string());
+                res.headers.push(("connection", "close".to_string()));
+                let _ = write_response(&mut stream, &res).await;
+                return;
+            }
+            Ok(None) | Err(ReadError::Invalid) => return,
+        };
+        let res = handler(req).await;
+        if write_response(&mut stream, &res).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn write_response(stream: &mut TcpStream, res: &HttpResponse) -> io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\ncontent-length: {}\r\n",
+        res.status,
+        reason(res.status),
+        res.body.len()
+    );
+    for (name, value) in &res.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(res.body.as_bytes()).await
+}
+
+/// Why no request could be read.
+enum ReadError {
+    /// The request is larger than [`MAX_REQUEST_SIZE`].
+    TooLarge,
+    /// The request isn't valid HTTP, or the connection failed.
+    Invalid,
+}
+
+impl From<io::Error> for ReadError {
+    fn from(_: io::Error) -> Self {
+        Self::Invalid
+    }
+}
+
+/// Read the next request from `stream`, keeping surplus bytes in `buf`.
+async fn read_request(
+    stream: &mut TcpStream,
+    buf: &mut Vec<u8>,
+) -> Result<Option<HttpRequest>, ReadError> {
+    loop {
+        let mut headers = [httparse::EMPTY_HEADER; 32];
+        let mut parsed = httparse::Request::new(&mut headers);
+        if let httparse::Status::Complete(head_len) =
+            parsed.parse(buf).map_err(|_| ReadError::Invalid)?
+        {
+            let headers: Vec<(String, String)> = parsed
+                .headers
+                .iter()
+                .filter_map(|h| {
+                    let value = std::str::from_utf8(h.value).ok()?;
+                    Some((h.name.to_string(), value.to_string()))
+                })
+                .collect();
+            let content_length: usize = headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case("content-length"))
+                .map(|(_, v)| v.trim().parse())
+                .transpose()
+                .map_err(|_| ReadError::Invalid)?
+                .unwrap_or(0);
+            let method = parsed.method.unwrap_or_default().to_string();
+            let path = parsed.path.unwrap_or_default().to_string();
+            let len = head_len
+                .checked_add(content_length)
+                .filter(|&len| len <= MAX_REQUEST_SIZE)
+                .ok_or(ReadError::TooLarge)?;
+            while buf.len() < len {
+                if read_more(stream, buf).await? == 0 {
+                    return Ok(None);
+                }
+            }
+            let body = buf[head_len..len].to_vec();
+            buf.drain(..len);
+            return Ok(Some(HttpRequest {
+                method,
+                path,
+                headers,
+                body,
+            }));
+        }
+        if buf.len() > MAX_REQUEST_SIZE {
+            return Err(ReadError::TooLarge);
+        }
+        if read_more(stream, buf).await? == 0 {
+            return Ok(None);
+        }
+    }
+}
+
+async fn read_more(stream: &mut TcpStream, buf: &mut Vec<u8>) -> io::Result<usize> {
+    let mut chunk = [0u8; 8192];
+    let n = stream.read(&mut chunk).await?;
+    buf.extend_from_slice(&chunk[..n]);
+    Ok(n)
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        413 => "Content Too Large",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
@@ -0,0 +1,251 @@
+//! Importing records into a zone that already has some.
+//!
+//! [`SimplyClient::plan_import`] compares the records to import, e.g. from a
+//! [`ZoneFile`](crate::zonefile::ZoneFile), with the live zone. Records whose
+//! name and type are new are simply created, and those already present are
+//! left alone. A name and type that exists on both sides with differing data
+//! is an [`ImportConflict`], which is resolved one by one with an
+//! [`ImportResolution`] into a [`ZonePlan`] for [`SimplyClient::apply`].
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::import::ImportResolution;
+//! use simply_dns::zonefile::ZoneFile;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let records = ZoneFile::parse(&std::fs::read_to_string("example.com.zone")?)?;
+//! let import = client.plan_import("example.com", records).await?;
+//! print!("{import}");
+//! let plan = import.resolve(|_| ImportResolution::KeepExisting);
+//! println!("{}", client.apply(plan).await);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord};
+use crate::canonical;
+use crate::names;
+use crate::sync::{Change, ZonePlan};
+use crate::{SimplyClient, SimplyClientError};
+
+/// A name and type that has records both in the zone and in the import, with
+/// differing data.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportConflict {
+    /// The zone-relative name, `@` for the apex.
+    pub name: String,
+    /// The record type.
+    #[serde(rename = "type")]
+    pub record_type: String,
+    /// The records the zone has now.
+    pub existing: Vec<DnsRecord>,
+    /// The records to import.
+    pub imported: Vec<CreateDnsRecordRequest>,
+}
+
+/// How to resolve an [`ImportConflict`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportResolution {
+    /// Leave the zone's records as they are and drop the imported ones.
+    KeepExisting,
+    /// Replace the zone's records with the imported ones.
+    TakeImported,
+    /// Add the imported records next to the zone's.
+    KeepBoth,
+}
+
+/// How records to import relate to a zone, from [`SimplyClient::plan_import`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    /// The domain the records are imported into.
+    pub domain: String,
+    /// Records whose name and type the zone doesn't have yet.
+    pub create: Vec<CreateDnsRecordRequest>,
+    /// Records the zone already has with the same data.
+    pub unchanged: Vec<CreateDnsRecordRequest>,
+    /// Names and types with differing data on both sides.
+    pub conflicts: Vec<ImportConflict>,
+}
+
+impl ImportReport {
+    /// Compare `imported` with the `current` records of `domain`.
+    pub fn compute(
+        domain: impl Into<String>,
+        current: Vec<DnsRecord>,
+        imported: Vec<CreateDnsRecordRequest>,
+    ) -> Self {
+        let domain = domain.into();
+        let key = |name: &str, record_type: &str| {
+            let name = names::to_relative(name, &domain);
+            let name = if name.is_empty() {
+                "@".to_string()
+            } else {
+                name
+            };
+            (name.to_ascii_lowercase(), record_type.to_ascii_uppercase())
+        };
+        let mut groups: BTreeMap<(String, String), (Vec<DnsRecord>, Vec<CreateDnsRecordRequest>)> =
+            BTreeMap::new();
+        for record in current {
+            let key = key(&record.name, &record.record_type);
+            groups.entry(key).or_default().0.push(record);
+        }
+        for record in imported {
+            let key = key(&record.name, &record.record_type);
+            groups.entry(key).or_default().1.push(record);
+        }
+
+        let mut report = Self {
+            domain: domain.clone(),
+            create: Vec::new(),
+            unchanged: Vec::new(),
+            conflicts: Vec::new(),
+        };
+        for ((name, record_type), (existing, imported)) in groups {
+            if imported.is_empty() {
+                continue;
+            }
+            if existing.is_empty() {
+                report.create.extend(imported);
+            } else if imported
+                .iter()
+                .all(|i| existing.iter().any(|e| same_data(e, i)))
+                && existing
+                    .iter()
+                    .all(|e| imported.iter().any(|i| same_data(e, i)))
+            {
+                report.unchanged.extend(imported);
+            } else {
+                report.conflicts.push(ImportConflict {
+                    name,
+                    record_type,
+                    existing,
+                    imported,
+                });
+            }
+        }
+        report
+    }
+
+    /// Whether the import creates nothing and conflicts with nothing.
+    pub fn is_empty(&self) -> bool {
+        self.create.is_empty() && self.conflicts.is_empty()
+    }
+
+    /// Turn the report into a plan, calling `resolve` for every conflict.
+    ///
+    /// Records to create are always created. With [`ImportResolution::TakeImported`],
+    /// existing records matching an imported one are kept rather than deleted
+    /// and created again.
+    pub fn resolve(self, mut resolve: impl FnMut(&ImportConflict) -> ImportResolution) -> ZonePlan {
+        let mut deletes = Vec::new();
+        let mut creates: Vec<Change> = self.create.into_iter().map(Change::Create).collect();
+        for conflict in self.conflicts {
+            let resolution = resolve(&conflict);
+            if resolution == ImportResolution::KeepExisting {
+                continue;
+            }
+            if resolution == ImportResolution::TakeImported {
+                deletes.extend(
+                    conflict
+                        .existing
+                        .iter()
+                        .filter(|e| !conflict.imported.iter().any(|i| same_data(e, i)))
+                        .cloned()
+                        .map(Change::Delete),
+                );
+            }
+            creates.extend(
+                conflict
+                    .imported
+                    .into_iter()
+                    .filter(|i| !conflict.existing.iter().any(|e| same_data(e, i)))
+                    .map(Change::Create),
+            );
+        }
+        deletes.append(&mut creates);
+        ZonePlan {
+            domain: self.domain,
+            changes: deletes,
+        }
+    }
+}
+
+/// Shows a line per record to create, followed by the conflicts.
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} to create, {} unchanged, {} conflicting",
+            self.create.len(),
+            self.unchanged.len(),
+            self.conflicts.len()
+        )?;
+        for record in &self.create {
+            writeln!(
+                f,
+                "+ {} {} {}",
+                record.name, record.record_type, record.data
+            )?;
+        }
+        for conflict in &self.conflicts {
+            write!(f, "{conflict}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Shows the existing and imported records, one per line.
+impl fmt::Display for ImportConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "conflict at {} {}:", self.name, self.record_type)?;
+        for record in &self.existing {
+            writeln!(f, "  existing {}", data_of(&record.data, record.priority))?;
+        }
+        for record in &self.imported {
+            writeln!(f, "  imported {}", data_of(&record.data, record.priority))?;
+        }
+        Ok(())
+    }
+}
+
+fn data_of(data: &str, priority: Option<u32>) -> String {
+    match priority {
+        Some(priority) => format!("{priority} {data}"),
+        None => data.to_string(),
+    }
+}
+
+/// Whether `imported` has the data of `existing`; an unset priority matches any.
+fn same_data(existing: &DnsRecord, imported: &CreateDnsRecordRequest) -> bool {
+    canonical::same_data(&existing.record_type, &existing.data, &imported.data)
+        && imported
+            .priority
+            .is_none_or(|p| existing.priority == Some(p))
+}
+
+impl SimplyClient {
+    /// Compare records to import with the live records of `domain`.
+    ///
+    /// Nothing is modified; resolve the conflicts of the returned report with
+    /// [`ImportReport::resolve`] and pass the plan to [`apply`](Self::apply).
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to import into.
+    /// * `records` - The records to import, with zone-relative names.
+    pub async fn plan_import(
+        &self,
+        domain: &str,
+        records: Vec<CreateDnsRecordRequest>,
+    ) -> Result<ImportReport, SimplyClientError> {
+        let current = self.list_dns_records(domain).await?;
+        Ok(ImportReport::compute(domain, current, records))
+    }
+}
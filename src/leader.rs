@@ -0,0 +1,290 @@
+//! Leader election for redundant daemons.
+//!
+//! When two instances of a daemon (e.g. a [`DdnsUpdater`](crate::ddns::DdnsUpdater))
+//! run for redundancy, [`LeaderElection`] makes sure only one of them writes at a
+//! time. Instances on the same host can share a [`FileLock`]; instances on
+//! different hosts can coordinate through a [`TxtHeartbeat`] record in the zone.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::leader::{LeaderElection, TxtHeartbeat};
+//!
+//! # async fn run() -> Result<(), simply_dns::leader::LeaderError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let election = LeaderElection::Heartbeat(TxtHeartbeat::new(&client, "example.com"));
+//! if election.try_acquire().await? {
+//!     // This instance is the leader and may write.
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord, UpdateDnsRecordRequest};
+use crate::retry::random_u64;
+use crate::{SimplyClient, SimplyClientError};
+
+const DEFAULT_HEARTBEAT_NAME: &str = "_simply-dns-leader";
+const DEFAULT_LEASE: Duration = Duration::from_secs(120);
+
+/// Error type for leader election.
+#[derive(Debug, Error)]
+pub enum LeaderError {
+    /// Reading or writing the heartbeat record failed.
+    #[error(transparent)]
+    Client(#[from] SimplyClientError),
+    /// Opening or locking the lock file failed.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// How redundant instances decide which of them is the leader.
+pub enum LeaderElection<'a> {
+    /// An exclusive lock on a local file.
+    File(FileLock),
+    /// A TXT heartbeat record in the zone.
+    Heartbeat(TxtHeartbeat<'a>),
+}
+
+impl LeaderElection<'_> {
+    /// Try to become (or stay) the leader, returning whether this instance leads.
+    ///
+    /// Call this before every write; for heartbeats, it also renews the lease.
+    pub async fn try_acquire(&self) -> Result<bool, LeaderError> {
+        match self {
+            LeaderElection::File(lock) => Ok(lock.try_acquire()?),
+            LeaderElection::Heartbeat(heartbeat) => heartbeat.try_acquire().await,
+        }
+    }
+
+    /// Give up leadership, so a standby instance can take over right away.
+    pub async fn release(&self) -> Result<(), LeaderError> {
+        match self {
+            LeaderElection::File(lock) => {
+                lock.release();
+                Ok(())
+            }
+            LeaderElection::Heartbeat(heartbeat) => heartbeat.release().await,
+        }
+    }
+}
+
+/// Leadership held through an exclusive lock on a file.
+///
+/// The lock is released when the process exits, so a crashed leader never
+/// blocks the standby instance. Only works for instances sharing a file system.
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+}
+
+impl FileLock {
+    /// A lock on `path`, which is created if it doesn't exist.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            file: Mutex::new(None),
+        }
+    }
+
+    /// The path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Take the lock if it is free, returning whether this instance holds it.
+    pub fn try_acquire(&self) -> io::Result<bool> {
+        let mut held = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if held.is_some() {
+            return Ok(true);
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&self.path)?;
+        match file.try_lock() {
+            Ok(()) => {
+                *held = Some(file);
+                Ok(true)
+            }
+            Err(TryLockError::WouldBlock) => Ok(false),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+
+    /// Release the lock if this instance holds it.
+    pub fn release(&self) {
+        self.file.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+}
+
+/// Leadership held through a TXT record in the zone.
+///
+/// The leader keeps the record `holder=<instance> expires=<unix time>` renewed;
+/// another instance takes over once the lease has expired. If two instances
+/// race to create the record, the one with the lowest record ID wins and the
+/// other removes its record again.
+pub struct TxtHeartbeat<'a> {
+    client: &'a SimplyClient,
+    domain: String,
+    name: String,
+    instance_id: String,
+    lease: Duration,
+}
+
+impl<'a> TxtHeartbeat<'a> {
+    /// A heartbeat in the record `_simply-dns-leader` under `domain`.
+    ///
+    /// The instance ID is random and the lease lasts two minutes.
+    pub fn new(client: &'a SimplyClient, domain: impl Into<String>) -> Self {
+        Self {
+            client,
+            domain: domain.into(),
+            name: DEFAULT_HEARTBEAT_NAME.to_string(),
+            instance_id: format!("{}-{:08x}", std::process::id(), random_u64() as u32),
+            lease: DEFAULT_LEASE,
+        }
+    }
+
+    /// Use the record `name` instead of `_simply-dns-leader`.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Identify this instance as `id`, e.g. its host name. Must be unique among instances.
+    pub fn instance_id(mut self, id: impl Into<String>) -> Self {
+        self.instance_id = id.into();
+        self
+    }
+
+    /// How long leadership lasts without renewal.
+    ///
+    /// Renew well within the lease, e.g. by calling [`try_acquire`](Self::try_acquire)
+    /// at least twice per lease.
+    pub fn lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    /// Try to become (or stay) the leader, renewing the lease if this instance leads.
+    pub async fn try_acquire(&self) -> Result<bool, LeaderError> {
+        let lock = self.client.lock_domain(&self.domain).await?;
+        let client = lock.client();
+        let now = unix_time();
+        let records = self.heartbeats().await?;
+        if let Some((holder, expires)) = records.first().and_then(|r| parse_heartbeat(&r.data))
+            && holder != self.instance_id
+            && expires > now
+        {
+            return Ok(false);
+        }
+
+        let ttl = self.lease.as_secs().clamp(60, u32::MAX as u64) as u32;
+        let data = format!(
+            "holder={} expires={}",
+            self.instance_id,
+            now + self.lease.as_secs()
+        );
+        match records.first() {
+            Some(record) => {
+                let req = UpdateDnsRecordRequest {
+                    record_type: "TXT".to_string(),
+                    name: self.name.clone(),
+                    data,
+                    priority: None,
+                    ttl: Some(ttl),
+                    comment: None,
+                };
+                client
+                    .update_dns_record(&self.domain, record.record_id, req)
+                    .await?;
+            }
+            None => {
+                let req = CreateDnsRecordRequest {
+                    record_type: "TXT".to_string(),
+                    name: self.name.clone(),
+                    data,
+                    priority: None,
+                    ttl: Some(ttl),
+                    comment: None,
+                };
+                client.create_dns_record(&self.domain, req).await?;
+            }
+        }
+
+        // Read back to settle races with an instance that wrote at the same time.
+        let records = self.heartbeats().await?;
+        let won = records
+            .first()
+            .and_then(|r| parse_heartbeat(&r.data))
+            .is_some_and(|(holder, _)| holder == self.instance_id);
+        if !won {
+            for record in records.iter().skip(1).filter(|r| self.holds(r)) {
+                client
+                    .delete_dns_record(&self.domain, record.record_id)
+                    .await?;
+            }
+        }
+        Ok(won)
+    }
+
+    /// Remove the heartbeat record if this instance holds it.
+    pub async fn release(&self) -> Result<(), LeaderError> {
+        let lock = self.client.lock_domain(&self.domain).await?;
+        let client = lock.client();
+        for record in self.heartbeats().await?.iter().filter(|r| self.holds(r)) {
+            client
+                .delete_dns_record(&self.domain, record.record_id)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// The heartbeat records, ordered by record ID.
+    async fn heartbeats(&self) -> Result<Vec<DnsRecord>, SimplyClientError> {
+        let mut records: Vec<DnsRecord> = self
+            .client
+            .list_dns_records(&self.domain)
+            .await?
+            .into_iter()
+            .filter(|r| {
+                r.record_type.eq_ignore_ascii_case("TXT") && r.name.eq_ignore_ascii_case(&self.name)
+            })
+            .collect();
+        records.sort_by_key(|r| r.record_id.id);
+        Ok(records)
+    }
+
+    fn holds(&self, record: &DnsRecord) -> bool {
+        parse_heartbeat(&record.data).is_some_and(|(holder, _)| holder == self.instance_id)
+    }
+}
+
+/// Parse `holder=<id> expires=<unix time>`.
+fn parse_heartbeat(data: &str) -> Option<(&str, u64)> {
+    let mut holder = None;
+    let mut expires = None;
+    for field in data.trim_matches('"').split_whitespace() {
+        if let Some(value) = field.strip_prefix("holder=") {
+            holder = Some(value);
+        } else if let Some(value) = field.strip_prefix("expires=") {
+            expires = value.parse().ok();
+        }
+    }
+    Some((holder?, expires?))
+}
+
+fn unix_time() -> u64 {
+    crate::time::unix_time().as_secs()
+}
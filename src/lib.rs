@@ -1,4 +1,102 @@
+#[cfg(all(feature = "acme", not(target_arch = "wasm32")))]
+pub mod acme;
+pub mod adopt;
 pub mod api;
+pub mod approval;
+pub mod audit;
+pub mod auth;
+pub mod backup;
+pub mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cache;
+pub mod canonical;
 mod client;
+pub mod config;
+pub mod credentials;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod ddns;
+mod dns_api;
+pub mod drift;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+mod endpoint;
+pub mod expiry;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod failover;
+#[cfg(feature = "test-fixtures")]
+pub mod fixtures;
+pub mod freeze;
+pub mod generate;
+#[cfg(all(feature = "git-sync", not(target_arch = "wasm32")))]
+pub mod git_sync;
+pub mod health;
+pub mod hosts;
+#[cfg(any(
+    feature = "mock-server",
+    feature = "provider",
+    feature = "test-fixtures"
+))]
+mod http_server;
+pub mod import;
+pub mod leader;
+pub mod lock;
+pub mod mail;
+pub mod mail_dns;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod middleware;
+pub mod migrate;
+#[cfg(feature = "mock")]
+pub mod mock;
+#[cfg(feature = "mock-server")]
+pub mod mock_server;
+pub mod names;
+pub mod ownership;
+pub mod pool;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod probe;
+pub mod progress;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod propagation;
+#[cfg(feature = "provider")]
+pub mod provider;
+pub mod query;
+pub mod quota;
+mod rate_limit;
+pub mod record_data;
+pub mod report;
+pub mod response;
+mod retry;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod rotation;
+pub mod rrset;
+#[cfg(all(feature = "s3", not(target_arch = "wasm32")))]
+pub mod s3;
+pub mod state;
+pub mod stream;
+pub mod subtree;
+pub mod sync;
+pub mod templates;
+pub mod time;
+#[cfg(all(
+    not(target_arch = "wasm32"),
+    any(feature = "rustls-tls", feature = "native-tls")
+))]
+pub mod tls;
+pub mod ttl;
+pub mod validate;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod verify;
+pub mod watch;
+pub mod zone;
+pub mod zonefile;
 
-pub use client::{SimplyClient, SimplyClientError};
+pub use client::{
+    CallOptions, CreateMode, DryRunRequest, Observability, OptionalFields, SimplyClient,
+    SimplyClientBuilder, SimplyClientError,
+};
+pub use dns_api::{DynSimplyDnsApi, SimplyDnsApi};
+pub use endpoint::ApiVersion;
+pub use rate_limit::{ApiBudget, Priority};
+pub use retry::RetryPolicy;
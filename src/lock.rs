@@ -0,0 +1,345 @@
+//! Serializing changes to a zone.
+//!
+//! Helpers that read a zone before changing it, such as
+//! [`upsert_dns_record`](SimplyClient::upsert_dns_record),
+//! [`apply`](SimplyClient::apply) or [`replace_rrset`](SimplyClient::replace_rrset),
+//! would lose updates if two tasks ran them on the same zone at once: both
+//! list the records, then both write. The client and its clones therefore
+//! hold a per-domain lock while running them, so such sequences on one zone
+//! run one after another while other zones proceed in parallel. Record
+//! rotations are the exception: they wait for DNS propagation between their
+//! steps and don't block the zone meanwhile.
+//!
+//! [`SimplyClient::lock_domain`] takes the same lock for a critical section
+//! of your own. Make the calls inside it through [`DomainLock::client`],
+//! which doesn't take the lock again; the same task calling a locking helper
+//! through another clone would wait for itself forever.
+//!
+//! The lock only covers one program. For several processes sharing zones,
+//! e.g. cron jobs, set [`SimplyClientBuilder::domain_lock_dir`](crate::SimplyClientBuilder::domain_lock_dir)
+//! to also hold a lock file per domain in a directory they share. Neither
+//! lock keeps out changes made by other tools or in the control panel; see
+//! [`update_dns_record_if`](SimplyClient::update_dns_record_if) for those.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let lock = client.lock_domain("example.com").await?;
+//! let records = lock.client().list_dns_records("example.com").await?;
+//! for record in records.iter().filter(|r| r.ttl > 3600) {
+//!     lock.client()
+//!         .set_comment("example.com", record.record_id, "long ttl")
+//!         .await?;
+//! }
+//! drop(lock);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_util::lock::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+use crate::{SimplyClient, SimplyClientError};
+
+/// How old a lock file must be to be taken as left behind by a crashed
+/// process, unless [`SimplyClientBuilder::domain_lock_stale_after`](crate::SimplyClientBuilder::domain_lock_stale_after)
+/// says otherwise.
+pub const DEFAULT_LOCK_STALE_AFTER: Duration = Duration::from_secs(15 * 60);
+
+/// How often a held lock file is checked again.
+#[cfg(not(target_arch = "wasm32"))]
+const LOCK_FILE_POLL: Duration = Duration::from_millis(100);
+
+/// The per-domain locks shared by a client and its clones.
+#[derive(Debug, Default)]
+pub(crate) struct DomainLocks {
+    mutexes: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    dir: Option<std::path::PathBuf>,
+    #[cfg(not(target_arch = "wasm32"))]
+    stale_after: Option<Duration>,
+}
+
+impl DomainLocks {
+    /// Locks that also hold a lock file per domain in `dir`, if set.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn with_lock_files(
+        dir: Option<std::path::PathBuf>,
+        stale_after: Option<Duration>,
+    ) -> Self {
+        Self {
+            dir,
+            stale_after,
+            ..Default::default()
+        }
+    }
+
+    /// The mutex of `key`, forgetting those nobody holds or waits for.
+    fn mutex(&self, key: &str) -> Arc<AsyncMutex<()>> {
+        let mut mutexes = self.mutexes.lock().unwrap_or_else(|e| e.into_inner());
+        mutexes.retain(|k, m| k == key || Arc::strong_count(m) > 1);
+        mutexes.entry(key.to_string()).or_default().clone()
+    }
+}
+
+/// The lock of a domain, from [`SimplyClient::lock_domain`]. Released when dropped.
+pub struct DomainLock {
+    client: SimplyClient,
+    domain: String,
+    #[cfg(not(target_arch = "wasm32"))]
+    _file: Option<file::LockFile>,
+    _guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl DomainLock {
+    /// A client holding the lock, for the calls of the critical section.
+    pub fn client(&self) -> &SimplyClient {
+        &self.client
+    }
+
+    /// The domain locked.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+}
+
+impl fmt::Debug for DomainLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DomainLock")
+            .field("domain", &self.domain)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SimplyClient {
+    /// Wait for and take the lock of `domain`, see [`lock`](crate::lock).
+    ///
+    /// On a client of a [`DomainLock`] for `domain`, returns right away
+    /// without locking again. Fails with [`SimplyClientError::Io`] only if a
+    /// lock file is configured and can't be created.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to lock.
+    pub async fn lock_domain(&self, domain: &str) -> Result<DomainLock, SimplyClientError> {
+        let key = domain.trim_end_matches('.').to_ascii_lowercase();
+        if self.holds_lock(&key) {
+            return Ok(DomainLock {
+                client: self.clone(),
+                domain: key,
+                #[cfg(not(target_arch = "wasm32"))]
+                _file: None,
+                _guard: None,
+            });
+        }
+        let locks = self.domain_locks();
+        let guard = locks.mutex(&key).lock_owned().await;
+        #[cfg(not(target_arch = "wasm32"))]
+        let file = match &locks.dir {
+            Some(dir) => Some(
+                file::LockFile::acquire(
+                    dir,
+                    &key,
+                    locks.stale_after.unwrap_or(DEFAULT_LOCK_STALE_AFTER),
+                )
+                .await?,
+            ),
+            None => None,
+        };
+        Ok(DomainLock {
+            client: self.holding_lock(&key),
+            domain: key,
+            #[cfg(not(target_arch = "wasm32"))]
+            _file: file,
+            _guard: Some(guard),
+        })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod file {
+    use std::fs::{self, OpenOptions};
+    use std::io::{self, Write};
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::{Duration, SystemTime};
+
+    use super::LOCK_FILE_POLL;
+    use crate::time::sleep;
+
+    /// A lock file, created exclusively and removed when dropped.
+    pub(super) struct LockFile {
+        path: PathBuf,
+    }
+
+    impl LockFile {
+        /// Create the lock file of `domain` in `dir`, waiting while another
+        /// process holds it and taking it over once it is older than `stale_after`.
+        pub(super) async fn acquire(
+            dir: &Path,
+            domain: &str,
+            stale_after: Duration,
+        ) -> io::Result<Self> {
+            fs::create_dir_all(dir)?;
+            let name: String = domain
+                .chars()
+                .map(|c| match c {
+                    'a'..='z' | '0'..='9' | '.' | '-' | '_' => c,
+                    _ => '_',
+                })
+                .collect();
+            let path = dir.join(format!("{name}.lock"));
+            loop {
+                match OpenOptions::new().write(true).create_new(true).open(&path) {
+                    Ok(file) => return Self::claim(path, file),
+                    Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                        if is_stale(&path, stale_after) && take_over(&path, stale_after)? {
+                            continue;
+                        }
+                        sleep(LOCK_FILE_POLL).await;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        /// Write the PID of this process into the lock file just created at
+        /// `path`, removing the file again if that fails.
+        fn claim(path: PathBuf, mut file: impl Write) -> io::Result<Self> {
+            let lock = Self { path };
+            writeln!(file, "{}", std::process::id())?;
+            file.flush()?;
+            Ok(lock)
+        }
+    }
+
+    impl Drop for LockFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    fn is_stale(path: &Path, stale_after: Duration) -> bool {
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > stale_after)
+    }
+
+    /// Remove the stale lock file at `path`, returning whether the lock is
+    /// free to take.
+    ///
+    /// Another process may have taken the lock over between the staleness
+    /// check and the removal, so the file is first moved aside under a name
+    /// of its own and checked again. A fresh lock moved aside is linked back,
+    /// unless yet another lock file has taken its place meanwhile.
+    fn take_over(path: &Path, stale_after: Duration) -> io::Result<bool> {
+        static TAKEOVERS: AtomicU64 = AtomicU64::new(0);
+        let mut aside = path.as_os_str().to_owned();
+        aside.push(format!(
+            ".{}-{}",
+            std::process::id(),
+            TAKEOVERS.fetch_add(1, Ordering::Relaxed)
+        ));
+        let aside = PathBuf::from(aside);
+        match fs::rename(path, &aside) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(true),
+            result => result?,
+        }
+        let stale = is_stale(&aside, stale_after);
+        if !stale {
+            let _ = fs::hard_link(&aside, path);
+        }
+        fs::remove_file(&aside)?;
+        Ok(stale)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn lock_dir(test: &str) -> PathBuf {
+            let dir =
+                std::env::temp_dir().join(format!("simply-dns-lock-{test}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            dir
+        }
+
+        fn runtime() -> tokio::runtime::Runtime {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .unwrap()
+        }
+
+        #[test]
+        fn stale_lock_files_are_taken_over() {
+            let dir = lock_dir("stale");
+            fs::create_dir_all(&dir).unwrap();
+            let path = dir.join("example.com.lock");
+            let file = fs::File::create(&path).unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(3600))
+                .unwrap();
+            drop(file);
+
+            let lock = runtime()
+                .block_on(LockFile::acquire(
+                    &dir,
+                    "example.com",
+                    Duration::from_secs(60),
+                ))
+                .unwrap();
+            let pid = fs::read_to_string(&path).unwrap();
+            assert_eq!(pid.trim(), std::process::id().to_string());
+            drop(lock);
+            assert!(!path.exists());
+            assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn fresh_lock_files_survive_a_late_takeover() {
+            // A lock file found stale, then replaced by another process before
+            // the takeover.
+            let dir = lock_dir("fresh");
+            fs::create_dir_all(&dir).unwrap();
+            let path = dir.join("example.com.lock");
+            fs::write(&path, "4242\n").unwrap();
+
+            assert!(!take_over(&path, Duration::from_secs(60)).unwrap());
+            assert_eq!(fs::read_to_string(&path).unwrap(), "4242\n");
+            assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        #[test]
+        fn lock_files_are_removed_if_the_pid_cant_be_written() {
+            struct Full;
+            impl Write for Full {
+                fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                    Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"))
+                }
+                fn flush(&mut self) -> io::Result<()> {
+                    Ok(())
+                }
+            }
+
+            let dir = lock_dir("full");
+            fs::create_dir_all(&dir).unwrap();
+            let path = dir.join("example.com.lock");
+            fs::write(&path, "").unwrap();
+
+            let err = LockFile::claim(path.clone(), Full).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::StorageFull);
+            assert!(!path.exists());
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Types for the mail alias endpoints of the Simply.com API.
+//!
+//! A mail alias forwards mail sent to an address on the domain to one or more
+//! destinations, which may be mailboxes on the same account or external
+//! addresses. The aliases of a product are managed with
+//! [`SimplyClient::list_mail_aliases`](crate::SimplyClient::list_mail_aliases),
+//! [`create_mail_alias`](crate::SimplyClient::create_mail_alias) and
+//! [`delete_mail_alias`](crate::SimplyClient::delete_mail_alias).
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::mail::CreateMailAliasRequest;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let req = CreateMailAliasRequest::new("info", ["alice@example.net"]);
+//! client.create_mail_alias("example.com", req).await?;
+//! for alias in client.list_mail_aliases("example.com").await? {
+//!     println!("{} -> {}", alias.name, alias.destinations.join(", "));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! See: https://www.simply.com/en/docs/api/
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A mail alias as returned by the API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MailAlias {
+    /// Unique identifier for the alias.
+    pub alias_id: MailAliasId,
+    /// The local part of the alias address, e.g. "info" in "info@example.com".
+    pub name: String,
+    /// The addresses mail to the alias is forwarded to.
+    #[serde(default)]
+    pub destinations: Vec<String>,
+}
+
+/// The ID of a mail alias.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(transparent)]
+pub struct MailAliasId {
+    pub(crate) id: u32,
+}
+
+impl fmt::Display for MailAliasId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+/// Request payload for creating a mail alias via the API.
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateMailAliasRequest {
+    /// The local part of the alias address, e.g. "info" in "info@example.com".
+    pub name: String,
+    /// The addresses mail to the alias is forwarded to.
+    pub destinations: Vec<String>,
+}
+
+impl CreateMailAliasRequest {
+    /// Create a request forwarding `name@domain` to `destinations`.
+    pub fn new<I, S>(name: impl Into<String>, destinations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            destinations: destinations.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Response for listing the mail aliases of a product.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ListMailAliasesResponse {
+    #[serde(default)]
+    pub aliases: Vec<MailAlias>,
+}
+
+/// Response for creating a mail alias.
+#[derive(Debug, Deserialize)]
+pub(crate) struct CreateMailAliasResponse {
+    pub alias: Option<CreatedMailAlias>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CreatedMailAlias {
+    pub id: u32,
+}
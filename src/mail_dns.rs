@@ -0,0 +1,968 @@
+//! Checking the SPF, DKIM and DMARC records of a zone.
+//!
+//! [`SimplyClient::analyze_mail_dns`] parses the mail authentication records
+//! among a zone's TXT records and reports common mistakes that hurt
+//! deliverability: more than one SPF record at a name, SPF records needing
+//! more than the 10 DNS lookups receivers allow, `+all`, a missing or
+//! duplicated DMARC record, and DKIM keys that don't parse.
+//!
+//! [`MailDnsReport::fix_plan`] turns the issues that can be fixed without
+//! guessing into a [`ZonePlan`]: duplicate SPF records are merged into one,
+//! SPF records get a `~all` where they pass or lack one, extra DMARC records
+//! are deleted, and a missing DMARC record is created with `p=none`, which only
+//! asks for reports and so can't cost any mail.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let report = client.analyze_mail_dns("example.com").await?;
+//! for issue in &report.issues {
+//!     println!("{issue}");
+//! }
+//! println!("{}", client.apply(report.fix_plan()).await);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The parsers can also be used on their own:
+//! ```rust
+//! use simply_dns::mail_dns::{SpfQualifier, SpfRecord};
+//!
+//! let spf = SpfRecord::parse("v=spf1 include:_spf.google.com mx -all")?;
+//! assert_eq!(spf.all(), Some(SpfQualifier::Fail));
+//! assert_eq!(spf.lookup_terms(), 2);
+//! # Ok::<(), simply_dns::mail_dns::MailRecordError>(())
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use thiserror::Error;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord, UpdateDnsRecordRequest};
+use crate::migrate::unquote_txt;
+use crate::names;
+use crate::record_data::RecordData;
+use crate::sync::{Change, ZonePlan};
+use crate::templates::DmarcPolicy;
+use crate::{SimplyClient, SimplyClientError};
+
+/// The most DNS lookups receivers perform for one SPF check (RFC 7208, section 4.6.4).
+pub const SPF_LOOKUP_LIMIT: usize = 10;
+
+/// Error type for parsing mail authentication records.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum MailRecordError {
+    /// The text isn't a valid SPF record.
+    #[error("invalid SPF record: {0}")]
+    Spf(String),
+    /// The text isn't a valid DMARC record.
+    #[error("invalid DMARC record: {0}")]
+    Dmarc(String),
+    /// The text isn't a valid DKIM key record.
+    #[error("invalid DKIM record: {0}")]
+    Dkim(String),
+}
+
+/// What an SPF term asks receivers to do with mail it matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpfQualifier {
+    /// `+`, the default: accept the mail.
+    Pass,
+    /// `-`: reject the mail.
+    Fail,
+    /// `~`: accept the mail but mark it as suspicious.
+    SoftFail,
+    /// `?`: make no statement.
+    Neutral,
+}
+
+impl SpfQualifier {
+    fn symbol(self) -> &'static str {
+        match self {
+            SpfQualifier::Pass => "+",
+            SpfQualifier::Fail => "-",
+            SpfQualifier::SoftFail => "~",
+            SpfQualifier::Neutral => "?",
+        }
+    }
+}
+
+/// A mechanism of an SPF record, e.g. `include:_spf.google.com`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpfTerm {
+    /// The qualifier of the mechanism.
+    pub qualifier: SpfQualifier,
+    /// The mechanism in lowercase, e.g. "include" or "ip4".
+    pub mechanism: String,
+    /// The argument after the colon or slash, e.g. "_spf.google.com", if any.
+    pub value: Option<String>,
+}
+
+/// Shows the term as written in a record, e.g. `-all` or `include:example.net`.
+impl fmt::Display for SpfTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.qualifier != SpfQualifier::Pass {
+            f.write_str(self.qualifier.symbol())?;
+        }
+        f.write_str(&self.mechanism)?;
+        match &self.value {
+            Some(value) if value.starts_with('/') => f.write_str(value),
+            Some(value) => write!(f, ":{value}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A parsed SPF record (RFC 7208).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpfRecord {
+    /// The mechanisms, in order.
+    pub terms: Vec<SpfTerm>,
+    /// Modifiers such as `redirect` and `exp`, as name (in lowercase) and value.
+    pub modifiers: Vec<(String, String)>,
+}
+
+impl SpfRecord {
+    /// Parse the text of a TXT record starting with `v=spf1`.
+    pub fn parse(text: &str) -> Result<Self, MailRecordError> {
+        let err = |message: String| MailRecordError::Spf(message);
+        let mut tokens = text.split_ascii_whitespace();
+        if !tokens
+            .next()
+            .is_some_and(|v| v.eq_ignore_ascii_case("v=spf1"))
+        {
+            return Err(err("doesn't start with v=spf1".to_string()));
+        }
+        let mut record = SpfRecord {
+            terms: Vec::new(),
+            modifiers: Vec::new(),
+        };
+        for token in tokens {
+            let mechanism_end = token.find([':', '/', '=']).unwrap_or(token.len());
+            if token[mechanism_end..].starts_with('=') {
+                let (name, value) = token.split_at(mechanism_end);
+                record
+                    .modifiers
+                    .push((name.to_ascii_lowercase(), value[1..].to_string()));
+                continue;
+            }
+            let (qualifier, token) = match token.as_bytes().first() {
+                Some(b'+') => (SpfQualifier::Pass, &token[1..]),
+                Some(b'-') => (SpfQualifier::Fail, &token[1..]),
+                Some(b'~') => (SpfQualifier::SoftFail, &token[1..]),
+                Some(b'?') => (SpfQualifier::Neutral, &token[1..]),
+                _ => (SpfQualifier::Pass, token),
+            };
+            let (mechanism, value) = match token.find([':', '/']) {
+                Some(i) if token.as_bytes()[i] == b':' => {
+                    (&token[..i], Some(token[i + 1..].to_string()))
+                }
+                Some(i) => (&token[..i], Some(token[i..].to_string())),
+                None => (token, None),
+            };
+            let mechanism = mechanism.to_ascii_lowercase();
+            let valid = match mechanism.as_str() {
+                "all" => value.is_none(),
+                "a" | "mx" | "ptr" => true,
+                "include" | "exists" => value.as_deref().is_some_and(|v| !v.is_empty()),
+                "ip4" => value
+                    .as_deref()
+                    .is_some_and(|v| valid_network::<Ipv4Addr>(v, 32)),
+                "ip6" => value
+                    .as_deref()
+                    .is_some_and(|v| valid_network::<Ipv6Addr>(v, 128)),
+                _ => return Err(err(format!("unknown mechanism {mechanism}"))),
+            };
+            if !valid {
+                return Err(err(format!("invalid term {token}")));
+            }
+            record.terms.push(SpfTerm {
+                qualifier,
+                mechanism,
+                value,
+            });
+        }
+        Ok(record)
+    }
+
+    /// The qualifier of the `all` mechanism, if the record has one.
+    pub fn all(&self) -> Option<SpfQualifier> {
+        self.terms
+            .iter()
+            .find(|t| t.mechanism == "all")
+            .map(|t| t.qualifier)
+    }
+
+    /// The target of the `redirect` modifier, if any.
+    pub fn redirect(&self) -> Option<&str> {
+        self.modifier("redirect")
+    }
+
+    /// The value of the modifier `name`, if present.
+    pub fn modifier(&self, name: &str) -> Option<&str> {
+        self.modifiers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The domains of the `include` mechanisms.
+    pub fn includes(&self) -> impl Iterator<Item = &str> {
+        self.terms
+            .iter()
+            .filter(|t| t.mechanism == "include")
+            .filter_map(|t| t.value.as_deref())
+    }
+
+    /// The DNS lookups the record itself causes: one per `include`, `a`,
+    /// `mx`, `ptr` and `exists` mechanism and `redirect` modifier.
+    ///
+    /// Included records add their own; see
+    /// [`MailDnsIssue::TooManySpfLookups`] for how the analysis counts them.
+    pub fn lookup_terms(&self) -> usize {
+        let mechanisms = self
+            .terms
+            .iter()
+            .filter(|t| {
+                matches!(
+                    t.mechanism.as_str(),
+                    "include" | "a" | "mx" | "ptr" | "exists"
+                )
+            })
+            .count();
+        mechanisms + usize::from(self.redirect().is_some())
+    }
+}
+
+/// Shows the record as TXT data, e.g. `v=spf1 mx -all`.
+impl fmt::Display for SpfRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("v=spf1")?;
+        for term in &self.terms {
+            write!(f, " {term}")?;
+        }
+        for (name, value) in &self.modifiers {
+            write!(f, " {name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `value` is an address with an optional `/prefix` of at most `max`.
+fn valid_network<A: std::str::FromStr>(value: &str, max: u8) -> bool {
+    let (address, prefix) = match value.split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix)),
+        None => (value, None),
+    };
+    address.parse::<A>().is_ok() && prefix.is_none_or(|p| p.parse::<u8>().is_ok_and(|p| p <= max))
+}
+
+/// A parsed DMARC record (RFC 7489), published at `_dmarc`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DmarcRecord {
+    /// The `p=` policy for the domain.
+    pub policy: DmarcPolicy,
+    /// The `sp=` policy for subdomains, if it differs.
+    pub subdomain_policy: Option<DmarcPolicy>,
+    /// The `pct=` share of failing mail the policy applies to, if given.
+    pub percent: Option<u8>,
+    /// The `rua=` addresses aggregate reports are sent to.
+    pub aggregate_reports: Vec<String>,
+    /// All tags as name (in lowercase) and value, in order.
+    pub tags: Vec<(String, String)>,
+}
+
+impl DmarcRecord {
+    /// Parse the text of a TXT record starting with `v=DMARC1`.
+    pub fn parse(text: &str) -> Result<Self, MailRecordError> {
+        let err = |message: String| MailRecordError::Dmarc(message);
+        let tags = parse_tags(text).map_err(err)?;
+        let tag = |name: &str| {
+            tags.iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+        if !tags
+            .first()
+            .is_some_and(|(n, v)| n == "v" && v.eq_ignore_ascii_case("DMARC1"))
+        {
+            return Err(err("doesn't start with v=DMARC1".to_string()));
+        }
+        let policy = |name: &str| {
+            tag(name)
+                .map(|p| parse_policy(p).ok_or_else(|| err(format!("invalid {name}={p}"))))
+                .transpose()
+        };
+        let percent = tag("pct")
+            .map(|p| {
+                p.parse::<u8>()
+                    .ok()
+                    .filter(|p| *p <= 100)
+                    .ok_or_else(|| err(format!("invalid pct={p}")))
+            })
+            .transpose()?;
+        let aggregate_reports: Vec<String> = tag("rua")
+            .map(|rua| rua.split(',').map(|a| a.trim().to_string()).collect())
+            .unwrap_or_default();
+        if let Some(address) = aggregate_reports
+            .iter()
+            .find(|a| !a.to_ascii_lowercase().starts_with("mailto:"))
+        {
+            return Err(err(format!("report address {address} isn't a mailto: URI")));
+        }
+        Ok(DmarcRecord {
+            policy: policy("p")?.ok_or_else(|| err("no p= policy".to_string()))?,
+            subdomain_policy: policy("sp")?,
+            percent,
+            aggregate_reports,
+            tags,
+        })
+    }
+}
+
+fn parse_policy(policy: &str) -> Option<DmarcPolicy> {
+    match policy.to_ascii_lowercase().as_str() {
+        "none" => Some(DmarcPolicy::None),
+        "quarantine" => Some(DmarcPolicy::Quarantine),
+        "reject" => Some(DmarcPolicy::Reject),
+        _ => None,
+    }
+}
+
+/// A parsed DKIM key record (RFC 6376), published at `<selector>._domainkey`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DkimRecord {
+    /// The `k=` key type, "rsa" unless given.
+    pub key_type: String,
+    /// The base64 `p=` public key, empty if the key was revoked.
+    pub public_key: String,
+    /// All tags as name (in lowercase) and value, in order.
+    pub tags: Vec<(String, String)>,
+}
+
+impl DkimRecord {
+    /// Parse the text of a DKIM key record.
+    pub fn parse(text: &str) -> Result<Self, MailRecordError> {
+        let err = |message: String| MailRecordError::Dkim(message);
+        let tags = parse_tags(text).map_err(err)?;
+        let tag = |name: &str| {
+            tags.iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+        if let Some(version) = tag("v")
+            && (!version.eq_ignore_ascii_case("DKIM1") || tags[0].0 != "v")
+        {
+            return Err(err("v=DKIM1 must come first".to_string()));
+        }
+        let key_type = tag("k").unwrap_or("rsa").to_ascii_lowercase();
+        if !matches!(key_type.as_str(), "rsa" | "ed25519") {
+            return Err(err(format!("unknown key type {key_type}")));
+        }
+        let public_key: String = tag("p")
+            .ok_or_else(|| err("no p= public key".to_string()))?
+            .split_ascii_whitespace()
+            .collect();
+        if !public_key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'='))
+        {
+            return Err(err("the public key isn't base64".to_string()));
+        }
+        Ok(DkimRecord {
+            key_type,
+            public_key,
+            tags,
+        })
+    }
+
+    /// Whether the key was revoked by publishing an empty `p=`.
+    pub fn is_revoked(&self) -> bool {
+        self.public_key.is_empty()
+    }
+}
+
+/// Split `name=value; name=value` tags, with names in lowercase.
+fn parse_tags(text: &str) -> Result<Vec<(String, String)>, String> {
+    text.split(';')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|t| match t.split_once('=') {
+            Some((name, value)) => Ok((name.trim().to_ascii_lowercase(), value.trim().to_string())),
+            None => Err(format!("tag {t} has no value")),
+        })
+        .collect()
+}
+
+/// A problem with a zone's mail authentication records, from [`MailDnsReport`].
+///
+/// Names are relative to the zone, "@" for the apex.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MailDnsIssue {
+    /// A name has several SPF records, which makes SPF checks fail with a permanent error.
+    MultipleSpf {
+        /// The name the records are at.
+        name: String,
+        /// How many there are.
+        count: usize,
+    },
+    /// An SPF record doesn't parse.
+    InvalidSpf {
+        /// The name the record is at.
+        name: String,
+        /// What is wrong with it.
+        error: MailRecordError,
+    },
+    /// Checking an SPF record takes more than [`SPF_LOOKUP_LIMIT`] DNS lookups.
+    ///
+    /// Includes of names in the zone are followed through its records; those
+    /// of other domains are counted as one lookup each, so the real count may
+    /// be higher still.
+    TooManySpfLookups {
+        /// The name the record is at.
+        name: String,
+        /// The counted lookups.
+        lookups: usize,
+    },
+    /// An SPF record ends in `+all`, allowing anyone to send mail as the domain.
+    SpfPassesAll {
+        /// The name the record is at.
+        name: String,
+    },
+    /// An SPF record has neither `all` nor `redirect`, so unlisted senders are neutral.
+    SpfWithoutAll {
+        /// The name the record is at.
+        name: String,
+    },
+    /// The zone has no DMARC record at `_dmarc`.
+    MissingDmarc,
+    /// The zone has several DMARC records, which receivers ignore.
+    MultipleDmarc {
+        /// How many there are.
+        count: usize,
+    },
+    /// The DMARC record doesn't parse.
+    InvalidDmarc {
+        /// What is wrong with it.
+        error: MailRecordError,
+    },
+    /// A DKIM key record doesn't parse.
+    InvalidDkim {
+        /// The selector, e.g. "google" for `google._domainkey`.
+        selector: String,
+        /// What is wrong with it.
+        error: MailRecordError,
+    },
+}
+
+impl fmt::Display for MailDnsIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MailDnsIssue::MultipleSpf { name, count } => {
+                write!(
+                    f,
+                    "{name} has {count} SPF records, receivers reject them all"
+                )
+            }
+            MailDnsIssue::InvalidSpf { name, error } => write!(f, "{name}: {error}"),
+            MailDnsIssue::TooManySpfLookups { name, lookups } => write!(
+                f,
+                "the SPF record at {name} needs {lookups} DNS lookups, more than {SPF_LOOKUP_LIMIT}"
+            ),
+            MailDnsIssue::SpfPassesAll { name } => {
+                write!(f, "the SPF record at {name} allows any sender with +all")
+            }
+            MailDnsIssue::SpfWithoutAll { name } => {
+                write!(f, "the SPF record at {name} has no all mechanism")
+            }
+            MailDnsIssue::MissingDmarc => f.write_str("there is no DMARC record at _dmarc"),
+            MailDnsIssue::MultipleDmarc { count } => {
+                write!(f, "_dmarc has {count} DMARC records, receivers ignore them")
+            }
+            MailDnsIssue::InvalidDmarc { error } => write!(f, "_dmarc: {error}"),
+            MailDnsIssue::InvalidDkim { selector, error } => {
+                write!(f, "{selector}._domainkey: {error}")
+            }
+        }
+    }
+}
+
+/// The mail authentication records of a zone and their issues, from
+/// [`SimplyClient::analyze_mail_dns`] or [`MailDnsReport::analyze`].
+#[derive(Debug, Clone)]
+pub struct MailDnsReport {
+    /// The domain that was analyzed.
+    pub domain: String,
+    /// The parsed SPF records by name.
+    pub spf: BTreeMap<String, Vec<SpfRecord>>,
+    /// The parsed DMARC record, if there is exactly one and it parses.
+    pub dmarc: Option<DmarcRecord>,
+    /// The parsed DKIM keys by selector.
+    pub dkim: BTreeMap<String, DkimRecord>,
+    /// The problems found.
+    pub issues: Vec<MailDnsIssue>,
+    fixes: Vec<Change>,
+}
+
+impl MailDnsReport {
+    /// Analyze the TXT records among `records`, the records of `domain`.
+    ///
+    /// Record names may be given in either style.
+    pub fn analyze(domain: &str, records: &[DnsRecord]) -> Self {
+        let mut report = MailDnsReport {
+            domain: domain.to_string(),
+            spf: BTreeMap::new(),
+            dmarc: None,
+            dkim: BTreeMap::new(),
+            issues: Vec::new(),
+            fixes: Vec::new(),
+        };
+        // TXT records by relative name, lowercased.
+        let mut txt: BTreeMap<String, Vec<(&DnsRecord, String)>> = BTreeMap::new();
+        for record in records {
+            if record.record_type.eq_ignore_ascii_case("TXT") {
+                let name = names::to_relative(&record.name, domain).to_ascii_lowercase();
+                txt.entry(name)
+                    .or_default()
+                    .push((record, unquote_txt(&record.data)));
+            }
+        }
+
+        for (name, records) in &txt {
+            let spf: Vec<_> = records
+                .iter()
+                .filter(|(_, text)| is_tagged(text, "v=spf1"))
+                .collect();
+            if !spf.is_empty() {
+                report.check_spf(name, &spf, &txt);
+            }
+            if name == "_dmarc" {
+                report.check_dmarc(records);
+            } else if let Some(selector) = name.strip_suffix("._domainkey") {
+                for (_, text) in records {
+                    match DkimRecord::parse(text) {
+                        Ok(dkim) => {
+                            report.dkim.insert(selector.to_string(), dkim);
+                        }
+                        Err(error) => report.issues.push(MailDnsIssue::InvalidDkim {
+                            selector: selector.to_string(),
+                            error,
+                        }),
+                    }
+                }
+            }
+        }
+        if !txt.contains_key("_dmarc") {
+            report.missing_dmarc();
+        }
+        // Deletes first, then updates, then creates, like ZonePlan::compute.
+        report.fixes.sort_by_key(|change| match change {
+            Change::Delete(_) => 0,
+            Change::Update { .. } => 1,
+            Change::Create(_) => 2,
+        });
+        report
+    }
+
+    /// Whether no issues were found.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// The changes fixing the issues that can be fixed without guessing,
+    /// see the [module docs](self).
+    ///
+    /// Nothing is modified; pass the plan to [`SimplyClient::apply`] to execute it.
+    pub fn fix_plan(&self) -> ZonePlan {
+        ZonePlan {
+            domain: self.domain.clone(),
+            changes: self.fixes.clone(),
+        }
+    }
+
+    fn check_spf(
+        &mut self,
+        name: &str,
+        records: &[&(&DnsRecord, String)],
+        txt: &BTreeMap<String, Vec<(&DnsRecord, String)>>,
+    ) {
+        let mut parsed = Vec::new();
+        for (_, text) in records {
+            match SpfRecord::parse(text) {
+                Ok(spf) => parsed.push(spf),
+                Err(error) => self.issues.push(MailDnsIssue::InvalidSpf {
+                    name: name.to_string(),
+                    error,
+                }),
+            }
+        }
+        if records.len() > 1 {
+            self.issues.push(MailDnsIssue::MultipleSpf {
+                name: name.to_string(),
+                count: records.len(),
+            });
+        }
+        for spf in &parsed {
+            let mut seen = BTreeSet::from([name.to_string()]);
+            let lookups = count_lookups(spf, &self.domain, txt, &mut seen);
+            if lookups > SPF_LOOKUP_LIMIT {
+                self.issues.push(MailDnsIssue::TooManySpfLookups {
+                    name: name.to_string(),
+                    lookups,
+                });
+            }
+            match spf.all() {
+                Some(SpfQualifier::Pass) => self.issues.push(MailDnsIssue::SpfPassesAll {
+                    name: name.to_string(),
+                }),
+                None if spf.redirect().is_none() => self.issues.push(MailDnsIssue::SpfWithoutAll {
+                    name: name.to_string(),
+                }),
+                _ => {}
+            }
+        }
+
+        // Only fix records that all parse, so nothing is lost in a merge.
+        if parsed.len() == records.len() {
+            let merged = merge_spf(&parsed);
+            let (first, _) = records[0];
+            if records.len() > 1 || merged != parsed[0] {
+                let mut desired = UpdateDnsRecordRequest::from((*first).clone());
+                desired.data = merged.to_string();
+                self.fixes.push(Change::Update {
+                    current: (*first).clone(),
+                    desired,
+                });
+            }
+            for (record, _) in &records[1..] {
+                self.fixes.push(Change::Delete((*record).clone()));
+            }
+        }
+        self.spf.insert(name.to_string(), parsed);
+    }
+
+    fn missing_dmarc(&mut self) {
+        self.issues.push(MailDnsIssue::MissingDmarc);
+        let dmarc = format!("v=DMARC1; p={}", DmarcPolicy::None);
+        self.fixes.push(Change::Create(CreateDnsRecordRequest::new(
+            "_dmarc",
+            RecordData::Txt(dmarc),
+        )));
+    }
+
+    fn check_dmarc(&mut self, records: &[(&DnsRecord, String)]) {
+        let dmarc: Vec<_> = records
+            .iter()
+            .filter(|(_, text)| is_tagged(text, "v=dmarc1"))
+            .collect();
+        if dmarc.is_empty() {
+            self.missing_dmarc();
+            return;
+        }
+        // Receivers ignore all if there are several; keep the first that parses.
+        let mut kept = None;
+        for (record, text) in &dmarc {
+            match DmarcRecord::parse(text) {
+                Ok(parsed) if kept.is_none() => kept = Some(parsed),
+                Ok(_) => self.fixes.push(Change::Delete((*record).clone())),
+                Err(error) => self.issues.push(MailDnsIssue::InvalidDmarc { error }),
+            }
+        }
+        if dmarc.len() > 1 {
+            self.issues
+                .push(MailDnsIssue::MultipleDmarc { count: dmarc.len() });
+        } else {
+            self.dmarc = kept;
+        }
+    }
+}
+
+/// Whether the TXT text starts with the tag `tag`, e.g. "v=spf1".
+fn is_tagged(text: &str, tag: &str) -> bool {
+    text.get(..tag.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(tag))
+        && text[tag.len()..]
+            .chars()
+            .next()
+            .is_none_or(|c| c == ' ' || c == ';')
+}
+
+/// The lookups checking `spf` takes, following includes and redirects within
+/// `domain`, but not those of `seen`, the records being evaluated.
+fn count_lookups(
+    spf: &SpfRecord,
+    domain: &str,
+    txt: &BTreeMap<String, Vec<(&DnsRecord, String)>>,
+    seen: &mut BTreeSet<String>,
+) -> usize {
+    let targets = spf.includes().chain(spf.redirect());
+    let mut lookups = spf.lookup_terms();
+    for target in targets {
+        let relative = names::to_relative(target, domain).to_ascii_lowercase();
+        if relative == target.trim_end_matches('.').to_ascii_lowercase()
+            || !seen.insert(relative.clone())
+        {
+            continue;
+        }
+        let nested = txt
+            .get(&relative)
+            .into_iter()
+            .flatten()
+            .find_map(|(_, text)| {
+                is_tagged(text, "v=spf1")
+                    .then(|| SpfRecord::parse(text).ok())
+                    .flatten()
+            });
+        if let Some(nested) = nested {
+            lookups += count_lookups(&nested, domain, txt, seen);
+        }
+        // Receivers evaluate a record again for every include of it, so only
+        // the names being evaluated count as seen, to stop at cycles.
+        seen.remove(&relative);
+    }
+    lookups
+}
+
+/// One SPF record allowing the senders of all of `records`.
+///
+/// Mechanisms are taken in order without duplicates; `+all` becomes `~all`
+/// and a record without `all` or `redirect` gets `~all`.
+fn merge_spf(records: &[SpfRecord]) -> SpfRecord {
+    let mut merged = SpfRecord {
+        terms: Vec::new(),
+        modifiers: Vec::new(),
+    };
+    let mut all = None;
+    for record in records {
+        for term in &record.terms {
+            if term.mechanism == "all" {
+                all.get_or_insert(term.qualifier);
+            } else if !merged.terms.contains(term) {
+                merged.terms.push(term.clone());
+            }
+        }
+        for modifier in &record.modifiers {
+            if merged.modifier(&modifier.0).is_none() {
+                merged.modifiers.push(modifier.clone());
+            }
+        }
+    }
+    if all.is_some() {
+        // A redirect is ignored next to `all`, so drop it rather than keep a lookup.
+        merged.modifiers.retain(|(name, _)| name != "redirect");
+    }
+    let all = match all {
+        Some(SpfQualifier::Pass) => Some(SpfQualifier::SoftFail),
+        None if merged.redirect().is_none() => Some(SpfQualifier::SoftFail),
+        all => all,
+    };
+    if let Some(qualifier) = all {
+        merged.terms.push(SpfTerm {
+            qualifier,
+            mechanism: "all".to_string(),
+            value: None,
+        });
+    }
+    merged
+}
+
+impl SimplyClient {
+    /// List the records of `domain` and analyze its SPF, DKIM and DMARC records.
+    ///
+    /// See the [`mail_dns`](crate::mail_dns) module docs for the checks and fixes.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to analyze.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn analyze_mail_dns(&self, domain: &str) -> Result<MailDnsReport, SimplyClientError> {
+        let records = self.list_dns_records(domain).await?;
+        Ok(MailDnsReport::analyze(domain, &records))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn txt(name: &str, data: &str) -> DnsRecord {
+        DnsRecord {
+            record_id: 1.into(),
+            name: name.to_string(),
+            ttl: 3600,
+            data: data.to_string(),
+            record_type: "TXT".to_string(),
+            priority: None,
+            comment: None,
+        }
+    }
+
+    /// The issue the analysis reports for the lookups of the SPF record at the apex.
+    fn lookup_issue(records: &[DnsRecord]) -> Option<usize> {
+        let report = MailDnsReport::analyze("example.com", records);
+        report.issues.iter().find_map(|issue| match issue {
+            MailDnsIssue::TooManySpfLookups { name, lookups } if name == "@" => Some(*lookups),
+            _ => None,
+        })
+    }
+
+    /// The lookups of the SPF record at the apex.
+    fn lookups(records: &[DnsRecord]) -> usize {
+        let mut txt: BTreeMap<String, Vec<(&DnsRecord, String)>> = BTreeMap::new();
+        for record in records {
+            let name = names::to_relative(&record.name, "example.com").to_ascii_lowercase();
+            txt.entry(name)
+                .or_default()
+                .push((record, record.data.clone()));
+        }
+        let spf = SpfRecord::parse(&txt["@"][0].1).unwrap();
+        count_lookups(
+            &spf,
+            "example.com",
+            &txt,
+            &mut BTreeSet::from(["@".to_string()]),
+        )
+    }
+
+    fn includes(n: usize) -> String {
+        (0..n)
+            .map(|i| format!("include:_spf{i}.example.net"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn counts_lookup_terms() {
+        let spf = SpfRecord::parse(
+            "v=spf1 a mx/24 ptr exists:%{i}.example.com include:example.net ip4:192.0.2.0/24 ip6:2001:db8::/32 redirect=_spf.example.com",
+        )
+        .unwrap();
+        assert_eq!(spf.lookup_terms(), 6);
+        assert_eq!(
+            SpfRecord::parse("v=spf1 ip4:192.0.2.1 -all")
+                .unwrap()
+                .lookup_terms(),
+            0
+        );
+    }
+
+    #[test]
+    fn follows_includes_within_the_zone() {
+        let records = [
+            txt("@", "v=spf1 include:_spf.example.com -all"),
+            txt("_spf", "v=spf1 mx include:_more.example.com. ~all"),
+            txt(
+                "_more.example.com.",
+                &format!("v=spf1 {} ~all", includes(8)),
+            ),
+        ];
+        // 1 for the apex, 2 for _spf and 8 for _more.
+        assert_eq!(lookups(&records), 11);
+        assert_eq!(lookup_issue(&records), Some(11));
+        assert_eq!(lookups(&records[..2]), 3);
+        assert_eq!(lookup_issue(&records[..2]), None);
+    }
+
+    #[test]
+    fn counts_other_domains_once() {
+        let at_limit = [txt("@", &format!("v=spf1 {} -all", includes(10)))];
+        assert_eq!(lookup_issue(&at_limit), None);
+        let over = [txt(
+            "@",
+            &format!("v=spf1 {} redirect=example.net", includes(10)),
+        )];
+        assert_eq!(lookup_issue(&over), Some(11));
+    }
+
+    #[test]
+    fn counts_every_include_of_a_record() {
+        let records = [
+            txt(
+                "@",
+                "v=spf1 include:_a.example.com include:_b.example.com -all",
+            ),
+            txt("_a", "v=spf1 include:_shared.example.com -all"),
+            txt("_b", "v=spf1 include:_shared.example.com -all"),
+            txt("_shared", "v=spf1 a mx ptr -all"),
+        ];
+        // 2 for the apex, then 1 and 3 for _shared, once through each of _a and _b.
+        assert_eq!(lookups(&records), 10);
+    }
+
+    #[test]
+    fn stops_at_include_cycles() {
+        let records = [
+            txt("@", "v=spf1 include:_a.example.com -all"),
+            txt("_a", "v=spf1 mx include:_b.example.com -all"),
+            txt(
+                "_b",
+                "v=spf1 mx include:_a.example.com redirect=example.com",
+            ),
+        ];
+        // The includes closing the cycles count, but aren't followed.
+        assert_eq!(lookups(&records), 1 + 2 + 3);
+    }
+
+    #[test]
+    fn parses_dmarc_tags() {
+        let dmarc = DmarcRecord::parse(
+            "v=DMARC1;p=Reject; SP = quarantine ; pct=50; rua=mailto:a@example.com, MAILTO:b@example.net; adkim=s;",
+        )
+        .unwrap();
+        assert_eq!(dmarc.policy, DmarcPolicy::Reject);
+        assert_eq!(dmarc.subdomain_policy, Some(DmarcPolicy::Quarantine));
+        assert_eq!(dmarc.percent, Some(50));
+        assert_eq!(
+            dmarc.aggregate_reports,
+            ["mailto:a@example.com", "MAILTO:b@example.net"]
+        );
+        assert_eq!(dmarc.tags[2], ("sp".to_string(), "quarantine".to_string()));
+        assert_eq!(
+            dmarc.tags.last().unwrap(),
+            &("adkim".to_string(), "s".to_string())
+        );
+
+        let minimal = DmarcRecord::parse("v=dmarc1; p=none").unwrap();
+        assert_eq!((minimal.subdomain_policy, minimal.percent), (None, None));
+        assert!(minimal.aggregate_reports.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_dmarc_tags() {
+        for (text, message) in [
+            ("p=none; v=DMARC1", "doesn't start with v=DMARC1"),
+            ("v=DMARC2; p=none", "doesn't start with v=DMARC1"),
+            ("v=DMARC1", "no p= policy"),
+            ("v=DMARC1; p=block", "invalid p=block"),
+            ("v=DMARC1; p=none; sp=all", "invalid sp=all"),
+            ("v=DMARC1; p=none; pct=101", "invalid pct=101"),
+            ("v=DMARC1; p=none; pct=-1", "invalid pct=-1"),
+            (
+                "v=DMARC1; p=none; rua=https://example.com",
+                "report address https://example.com isn't a mailto: URI",
+            ),
+            ("v=DMARC1; p=none; fo", "tag fo has no value"),
+        ] {
+            assert_eq!(
+                DmarcRecord::parse(text),
+                Err(MailRecordError::Dmarc(message.to_string())),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn recognizes_tagged_records() {
+        assert!(is_tagged("v=spf1 -all", "v=spf1"));
+        assert!(is_tagged("V=SPF1", "v=spf1"));
+        assert!(is_tagged("v=DMARC1;p=none", "v=dmarc1"));
+        assert!(!is_tagged("v=spf10 -all", "v=spf1"));
+        assert!(!is_tagged("v=sp", "v=spf1"));
+    }
+}
@@ -0,0 +1,291 @@
+//! Operational metrics of a client's API requests, for Prometheus.
+//!
+//! [`ApiMetrics`] is a [`Middleware`] counting every request a client sends by
+//! method and status, with a latency histogram by method and the retries and
+//! `429 Too Many Requests` responses along the way. Daemons such as a
+//! [`DdnsUpdater`](crate::ddns::DdnsUpdater) serve
+//! [`render`](ApiMetrics::render), the Prometheus text format, on their
+//! metrics endpoint or write it to a file for the node exporter's textfile
+//! collector.
+//!
+//! | Metric | Type | Labels |
+//! |---|---|---|
+//! | `simply_dns_requests_total` | counter | `method`, `status` (`error` if no response) |
+//! | `simply_dns_request_duration_seconds` | histogram | `method` |
+//! | `simply_dns_retries_total` | counter | `method` |
+//! | `simply_dns_rate_limited_total` | counter | `method` |
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::metrics::ApiMetrics;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let metrics = ApiMetrics::new();
+//! let client = SimplyClient::builder()
+//!     .credentials("account", "api_key")
+//!     .middleware(metrics.clone())
+//!     .build()?;
+//! client.list_domains().await?;
+//! std::fs::write("/var/lib/node_exporter/simply_dns.prom", metrics.render())?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::middleware::{Exchange, Middleware};
+
+/// The upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// A [`Middleware`] recording the requests of the clients it is registered
+/// with, see the [module docs](self).
+///
+/// Clones share their counts, so keep one to [`render`](Self::render) them
+/// and register another with the client.
+#[derive(Clone, Default)]
+pub struct ApiMetrics {
+    recorded: Arc<Mutex<Recorded>>,
+}
+
+#[derive(Default)]
+struct Recorded {
+    /// Requests by method and status.
+    requests: BTreeMap<(String, String), u64>,
+    latency: BTreeMap<String, Histogram>,
+    retries: BTreeMap<String, u64>,
+    rate_limited: BTreeMap<String, u64>,
+}
+
+#[derive(Default)]
+struct Histogram {
+    /// The observations up to each of [`LATENCY_BUCKETS`], not cumulative.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: Duration,
+}
+
+impl ApiMetrics {
+    /// Metrics with nothing recorded yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn recorded(&self) -> MutexGuard<'_, Recorded> {
+        self.recorded.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// How many requests of `method` ended with `status`, e.g. `"GET"` and
+    /// `"200"`, or `"error"` for those that got no response.
+    pub fn requests(&self, method: &str, status: &str) -> u64 {
+        let key = (method.to_string(), status.to_string());
+        self.recorded().requests.get(&key).copied().unwrap_or(0)
+    }
+
+    /// How often requests were sent again, after an error or a
+    /// `429 Too Many Requests` response.
+    pub fn retries(&self) -> u64 {
+        self.recorded().retries.values().sum()
+    }
+
+    /// How many responses were `429 Too Many Requests`.
+    pub fn rate_limited(&self) -> u64 {
+        self.recorded().rate_limited.values().sum()
+    }
+
+    /// The metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let recorded = self.recorded();
+        let mut out = String::new();
+        // Writing to a String can't fail.
+        let _ = recorded.render(&mut out);
+        out
+    }
+}
+
+impl Recorded {
+    fn render(&self, out: &mut String) -> fmt::Result {
+        writeln!(
+            out,
+            "# HELP simply_dns_requests_total Simply.com API requests."
+        )?;
+        writeln!(out, "# TYPE simply_dns_requests_total counter")?;
+        for ((method, status), count) in &self.requests {
+            writeln!(
+                out,
+                "simply_dns_requests_total{{method=\"{method}\",status=\"{status}\"}} {count}"
+            )?;
+        }
+
+        let name = "simply_dns_request_duration_seconds";
+        writeln!(
+            out,
+            "# HELP {name} Simply.com API request latency, including retries."
+        )?;
+        writeln!(out, "# TYPE {name} histogram")?;
+        for (method, histogram) in &self.latency {
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                writeln!(
+                    out,
+                    "{name}_bucket{{method=\"{method}\",le=\"{bound}\"}} {cumulative}"
+                )?;
+            }
+            let count = histogram.count;
+            writeln!(
+                out,
+                "{name}_bucket{{method=\"{method}\",le=\"+Inf\"}} {count}"
+            )?;
+            let sum = histogram.sum.as_secs_f64();
+            writeln!(out, "{name}_sum{{method=\"{method}\"}} {sum}")?;
+            writeln!(out, "{name}_count{{method=\"{method}\"}} {count}")?;
+        }
+
+        for (name, help, counts) in [
+            (
+                "simply_dns_retries_total",
+                "Simply.com API requests sent again.",
+                &self.retries,
+            ),
+            (
+                "simply_dns_rate_limited_total",
+                "Simply.com API responses that were 429 Too Many Requests.",
+                &self.rate_limited,
+            ),
+        ] {
+            writeln!(out, "# HELP {name} {help}")?;
+            writeln!(out, "# TYPE {name} counter")?;
+            for (method, count) in counts {
+                writeln!(out, "{name}{{method=\"{method}\"}} {count}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Middleware for ApiMetrics {
+    fn after_response(&self, exchange: &Exchange<'_>) {
+        let method = exchange.method.as_str().to_string();
+        let status = match exchange.outcome {
+            Ok(res) => res.status().as_u16().to_string(),
+            Err(_) => "error".to_string(),
+        };
+        let mut recorded = self.recorded();
+        *recorded
+            .requests
+            .entry((method.clone(), status))
+            .or_default() += 1;
+
+        let histogram = recorded.latency.entry(method.clone()).or_default();
+        let seconds = exchange.elapsed.as_secs_f64();
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|&bound| seconds <= bound) {
+            histogram.buckets[bucket] += 1;
+        }
+        histogram.count += 1;
+        histogram.sum += exchange.elapsed;
+
+        *recorded.retries.entry(method.clone()).or_default() += u64::from(exchange.retries);
+        *recorded.rate_limited.entry(method).or_default() += u64::from(exchange.rate_limited);
+    }
+}
+
+impl fmt::Debug for ApiMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let recorded = self.recorded();
+        f.debug_struct("ApiMetrics")
+            .field("requests", &recorded.requests.values().sum::<u64>())
+            .field("retries", &recorded.retries.values().sum::<u64>())
+            .field("rate_limited", &recorded.rate_limited.values().sum::<u64>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::http_server::{self, HttpResponse};
+    use crate::{RetryPolicy, SimplyClient};
+
+    #[test]
+    fn requests_retries_and_rate_limits_are_counted() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let base_url = format!("http://{}/2/", listener.local_addr().unwrap());
+            let served = Arc::new(AtomicUsize::new(0));
+            let server = tokio::spawn(http_server::serve(listener, move |req| {
+                let served = served.fetch_add(1, Ordering::SeqCst);
+                let response = match (served, req.segments().as_slice()) {
+                    (0, _) => {
+                        let body = include_str!("../fixtures/rate_limited.json");
+                        let mut res = HttpResponse::new(429, "application/json", body.to_string());
+                        res.headers.push(("retry-after", "0".to_string()));
+                        res
+                    }
+                    (1, _) => HttpResponse::new(503, "text/plain", "busy".to_string()),
+                    (_, ["2", "my", "products"]) => HttpResponse::new(
+                        200,
+                        "application/json",
+                        include_str!("../fixtures/list_products.json").to_string(),
+                    ),
+                    _ => HttpResponse::new(
+                        404,
+                        "application/json",
+                        include_str!("../fixtures/not_found.json").to_string(),
+                    ),
+                };
+                async move { response }
+            }));
+
+            let metrics = ApiMetrics::new();
+            let client = SimplyClient::builder()
+                .credentials("account", "api_key")
+                .base_url(base_url)
+                .retry(RetryPolicy {
+                    base_delay: Duration::from_millis(1),
+                    ..RetryPolicy::default()
+                })
+                .middleware(metrics.clone())
+                .build()
+                .unwrap();
+            client.list_products().await.unwrap();
+            client.list_dns_records("example.org").await.unwrap_err();
+            server.abort();
+
+            assert_eq!(metrics.requests("GET", "200"), 1);
+            assert_eq!(metrics.requests("GET", "404"), 1);
+            assert_eq!(metrics.requests("GET", "429"), 0);
+            assert_eq!((metrics.retries(), metrics.rate_limited()), (2, 1));
+
+            let rendered = metrics.render();
+            for line in [
+                "# TYPE simply_dns_requests_total counter",
+                "simply_dns_requests_total{method=\"GET\",status=\"200\"} 1",
+                "simply_dns_requests_total{method=\"GET\",status=\"404\"} 1",
+                "# TYPE simply_dns_request_duration_seconds histogram",
+                "simply_dns_request_duration_seconds_bucket{method=\"GET\",le=\"+Inf\"} 2",
+                "simply_dns_request_duration_seconds_count{method=\"GET\"} 2",
+                "simply_dns_retries_total{method=\"GET\"} 2",
+                "simply_dns_rate_limited_total{method=\"GET\"} 1",
+            ] {
+                assert!(rendered.lines().any(|l| l == line), "{line} in\n{rendered}");
+            }
+            let buckets: Vec<u64> = (rendered.lines())
+                .filter(|l| l.starts_with("simply_dns_request_duration_seconds_bucket"))
+                .map(|l| l.rsplit(' ').next().unwrap().parse().unwrap())
+                .collect();
+            assert!(buckets.windows(2).all(|w| w[0] <= w[1]), "{rendered}");
+        });
+    }
+}
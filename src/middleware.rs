@@ -0,0 +1,103 @@
+//! Hooks around every HTTP request a client sends.
+//!
+//! A [`Middleware`] registered with
+//! [`SimplyClientBuilder::middleware`](crate::SimplyClientBuilder::middleware)
+//! can change each request before it is sent, e.g. to add headers, and is told
+//! how it went afterwards, e.g. to record metrics. It sees the requests of all
+//! of the client's methods, including the helpers of the other modules. With
+//! the `metrics` feature, `metrics::ApiMetrics` is such a middleware, counting
+//! the requests for Prometheus.
+//!
+//! Example usage:
+//! ```rust
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//! use simply_dns::middleware::{Exchange, Middleware};
+//! use simply_dns::{SimplyClient, SimplyClientError};
+//!
+//! #[derive(Default)]
+//! struct Metrics {
+//!     requests: AtomicU64,
+//!     failures: AtomicU64,
+//! }
+//!
+//! impl Middleware for Metrics {
+//!     fn before_request(&self, request: &mut reqwest::Request) -> Result<(), SimplyClientError> {
+//!         request
+//!             .headers_mut()
+//!             .insert("x-team", reqwest::header::HeaderValue::from_static("platform"));
+//!         Ok(())
+//!     }
+//!
+//!     fn after_response(&self, exchange: &Exchange<'_>) {
+//!         self.requests.fetch_add(1, Ordering::Relaxed);
+//!         if !exchange.outcome.is_ok_and(|res| res.status().is_success()) {
+//!             self.failures.fetch_add(1, Ordering::Relaxed);
+//!         }
+//!     }
+//! }
+//!
+//! let client = SimplyClient::builder()
+//!     .credentials("account", "api_key")
+//!     .middleware(Metrics::default())
+//!     .build()?;
+//! # Ok::<(), SimplyClientError>(())
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::{Method, Request, Response, Url};
+
+use crate::SimplyClientError;
+
+/// A request and how it turned out, passed to [`Middleware::after_response`].
+#[derive(Debug, Clone, Copy)]
+pub struct Exchange<'a> {
+    /// The HTTP method.
+    pub method: &'a Method,
+    /// The request URL.
+    pub url: &'a Url,
+    /// The response, or the error sending the request failed with. Error
+    /// statuses are responses here; the client turns them into errors later.
+    pub outcome: Result<&'a Response, &'a SimplyClientError>,
+    /// How long the request took, including retries.
+    pub elapsed: Duration,
+    /// How often the request was sent again, after an error or a
+    /// `429 Too Many Requests` response.
+    pub retries: u32,
+    /// How many of the responses were `429 Too Many Requests`, the final one
+    /// included.
+    pub rate_limited: u32,
+}
+
+/// Sees every HTTP request a client sends, see the [module docs](self).
+///
+/// Middleware runs in registration order, after the client added its
+/// authentication, and isn't called for requests a
+/// [dry-run](crate::SimplyClientBuilder::dry_run) client doesn't send.
+/// Retries resend the request as changed by `before_request`, and
+/// `after_response` is called once with the final outcome. Methods are
+/// blocking and run on the client's task, so they should return quickly.
+pub trait Middleware: Send + Sync {
+    /// Change `request` before it is sent; an error fails the call without sending it.
+    fn before_request(&self, request: &mut Request) -> Result<(), SimplyClientError> {
+        let _ = request;
+        Ok(())
+    }
+
+    /// Called once the request is done.
+    fn after_response(&self, exchange: &Exchange<'_>) {
+        let _ = exchange;
+    }
+}
+
+/// The middleware registered with a client.
+#[derive(Clone, Default)]
+pub(crate) struct Middlewares(pub(crate) Vec<Arc<dyn Middleware>>);
+
+impl fmt::Debug for Middlewares {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Middlewares({})", self.0.len())
+    }
+}
@@ -0,0 +1,660 @@
+//! Migrating zones from other DNS providers.
+//!
+//! [`Provider::parse`] translates a provider's JSON export into
+//! [`CreateDnsRecordRequest`]s with zone-relative names, ready for
+//! [`SimplyClient::plan_import`] or, through [`ProviderImport::desired_zone`],
+//! for [`SimplyClient::sync`]. [`SimplyClient::migrate_from`] does all of it
+//! in one call. Supported are:
+//!
+//! * [`Provider::Cloudflare`]: the response of Cloudflare's
+//!   `GET /zones/{zone_id}/dns_records`, or just its `result` array.
+//! * [`Provider::Route53`]: the output of
+//!   `aws route53 list-resource-record-sets`, or just its
+//!   `ResourceRecordSets` array.
+//!
+//! Provider-specific types are mapped to their Simply.com equivalents:
+//! Cloudflare's flattened `CNAME` at the apex, Route 53 alias records and
+//! `ANAME` records become `ALIAS` records. `SOA` and apex `NS` records, which
+//! Simply.com manages, are dropped; records without an equivalent, such as
+//! Route 53 weighted or latency records, are reported in
+//! [`ProviderImport::skipped`]. Cloudflare's proxying is lost: proxied records
+//! point at the origin once migrated.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::import::ImportResolution;
+//! use simply_dns::migrate::Provider;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let export = std::fs::read_to_string("route53.json")?;
+//! let import = Provider::Route53.parse(&export, "example.com")?;
+//! for skipped in &import.skipped {
+//!     eprintln!("not migrated: {skipped}");
+//! }
+//! let report = client.plan_import("example.com", import.records).await?;
+//! print!("{report}");
+//! println!("{}", client.apply(report.resolve(|_| ImportResolution::TakeImported)).await);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::api::CreateDnsRecordRequest;
+use crate::import::ImportResolution;
+use crate::names;
+use crate::record_data::SUPPORTED_RECORD_TYPES;
+use crate::report::OperationReport;
+use crate::sync::{Change, DesiredZone};
+use crate::{SimplyClient, SimplyClientError};
+
+/// Error type for [`Provider::parse`] and [`SimplyClient::migrate_from`].
+#[derive(Debug, Error)]
+pub enum MigrateError {
+    /// The export isn't JSON in the provider's format.
+    #[error("invalid export: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The Simply.com API call failed.
+    #[error(transparent)]
+    Client(#[from] SimplyClientError),
+}
+
+/// A DNS provider whose exports can be migrated, see the [module docs](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    /// Cloudflare's DNS records API.
+    Cloudflare,
+    /// Amazon Route 53's `list-resource-record-sets`.
+    Route53,
+}
+
+impl Provider {
+    /// Translate `export` of the zone `domain` into records to create.
+    ///
+    /// Example usage:
+    /// ```rust
+    /// use simply_dns::migrate::Provider;
+    ///
+    /// let export = r#"{"ResourceRecordSets": [
+    ///     {"Name": "example.com.", "Type": "A",
+    ///      "AliasTarget": {"HostedZoneId": "Z2FDTNDATAQYW2", "DNSName": "d111111abcdef8.cloudfront.net."}},
+    ///     {"Name": "example.com.", "Type": "MX", "TTL": 3600,
+    ///      "ResourceRecords": [{"Value": "10 mail.example.com."}]},
+    ///     {"Name": "\\052.example.com.", "Type": "TXT", "TTL": 300,
+    ///      "ResourceRecords": [{"Value": "\"v=spf1 \" \"-all\""}]}
+    /// ]}"#;
+    /// let import = Provider::Route53.parse(export, "example.com")?;
+    /// assert_eq!(import.records[0].record_type, "ALIAS");
+    /// assert_eq!(import.records[0].data, "d111111abcdef8.cloudfront.net");
+    /// assert_eq!(import.records[1].priority, Some(10));
+    /// assert_eq!(import.records[2].name, "*");
+    /// assert_eq!(import.records[2].data, "v=spf1 -all");
+    /// # Ok::<(), simply_dns::migrate::MigrateError>(())
+    /// ```
+    ///
+    /// # Arguments
+    /// * `export` - The provider's JSON export.
+    /// * `domain` - The domain the export is of.
+    pub fn parse(self, export: &str, domain: &str) -> Result<ProviderImport, MigrateError> {
+        let mut import = ProviderImport::default();
+        match self {
+            Provider::Cloudflare => {
+                let records = match serde_json::from_str(export)? {
+                    CloudflareExport::Envelope { result } => result,
+                    CloudflareExport::Records(records) => records,
+                };
+                for record in records {
+                    import.add_cloudflare(record, domain);
+                }
+            }
+            Provider::Route53 => {
+                let sets = match serde_json::from_str(export)? {
+                    Route53Export::Envelope {
+                        resource_record_sets,
+                    } => resource_record_sets,
+                    Route53Export::Sets(sets) => sets,
+                };
+                for set in sets {
+                    import.add_route53(set, domain);
+                }
+            }
+        }
+        Ok(import)
+    }
+}
+
+/// The records translated from a provider's export, from [`Provider::parse`].
+#[derive(Debug, Clone, Default)]
+pub struct ProviderImport {
+    /// The records to create, with zone-relative names.
+    pub records: Vec<CreateDnsRecordRequest>,
+    /// The records of the export that have no Simply.com equivalent.
+    pub skipped: Vec<SkippedRecord>,
+}
+
+/// A record of a provider's export that [`Provider::parse`] left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedRecord {
+    /// The name, as in the export.
+    pub name: String,
+    /// The record type, as in the export.
+    pub record_type: String,
+    /// Why the record was left out.
+    pub reason: String,
+}
+
+/// Shows the record and the reason, e.g. "www.example.com A: weighted routing".
+impl fmt::Display for SkippedRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {}", self.name, self.record_type, self.reason)
+    }
+}
+
+impl ProviderImport {
+    /// The records as a [`DesiredZone`], e.g. for [`SimplyClient::sync`].
+    pub fn desired_zone(&self) -> DesiredZone {
+        self.records.iter().cloned().collect()
+    }
+
+    fn skip(&mut self, name: &str, record_type: &str, reason: impl Into<String>) {
+        self.skipped.push(SkippedRecord {
+            name: name.to_string(),
+            record_type: record_type.to_string(),
+            reason: reason.into(),
+        });
+    }
+
+    /// Add a record unless it is managed by Simply.com, of an unsupported
+    /// type or already added.
+    fn push(&mut self, original: &str, record: CreateDnsRecordRequest) {
+        match record.record_type.as_str() {
+            "SOA" => return,
+            "NS" if record.name == "@" => return,
+            t if !SUPPORTED_RECORD_TYPES.contains(&t) => {
+                return self.skip(original, t, "record type not supported by Simply.com");
+            }
+            _ => {}
+        }
+        let duplicate = self.records.iter().any(|r| {
+            r.name.eq_ignore_ascii_case(&record.name)
+                && r.record_type == record.record_type
+                && r.data == record.data
+                && r.priority == record.priority
+        });
+        if !duplicate {
+            self.records.push(record);
+        }
+    }
+
+    fn add_cloudflare(&mut self, record: CloudflareRecord, domain: &str) {
+        let Some(name) = zone_name(&record.name, domain) else {
+            return self.skip(&record.name, &record.record_type, "outside the zone");
+        };
+        let mut record_type = record.record_type.to_ascii_uppercase();
+        let mut priority = record.priority;
+        let data = match record_type.as_str() {
+            // Cloudflare flattens a CNAME at the apex, which is what ALIAS does.
+            "CNAME" if name == "@" => {
+                record_type = "ALIAS".to_string();
+                target(&record.content)
+            }
+            "CNAME" | "NS" | "PTR" | "MX" | "ALIAS" | "ANAME" => target(&record.content),
+            "SRV" => match record.data {
+                Some(CloudflareData {
+                    priority: p,
+                    weight: Some(weight),
+                    port: Some(port),
+                    target: Some(host),
+                }) => {
+                    priority = p.or(priority);
+                    format!("{weight} {port} {}", target(&host))
+                }
+                _ => {
+                    let (p, data) = srv_data(&record.content);
+                    priority = p.or(priority);
+                    data
+                }
+            },
+            "TXT" | "SPF" => unquote_txt(&record.content),
+            _ => record.content,
+        };
+        if record_type == "ANAME" {
+            record_type = "ALIAS".to_string();
+        }
+        let data_priority = matches!(record_type.as_str(), "MX" | "SRV");
+        self.push(
+            &record.name,
+            CreateDnsRecordRequest {
+                record_type,
+                name,
+                data,
+                priority: priority.filter(|_| data_priority),
+                // A TTL of 1 is Cloudflare's "automatic".
+                ttl: record.ttl.filter(|&ttl| ttl > 1),
+                comment: record.comment.filter(|c| !c.is_empty()),
+            },
+        );
+    }
+
+    fn add_route53(&mut self, set: Route53RecordSet, domain: &str) {
+        let original = unescape_route53(&set.name);
+        let record_type = set.record_type.to_ascii_uppercase();
+        if let Some(id) = &set.set_identifier {
+            let reason = format!("routing policy ({id}) has no Simply.com equivalent");
+            return self.skip(&original, &record_type, reason);
+        }
+        let Some(name) = zone_name(&original, domain) else {
+            return self.skip(&original, &record_type, "outside the zone");
+        };
+        if let Some(alias) = set.alias_target {
+            return self.push(
+                &original,
+                CreateDnsRecordRequest {
+                    record_type: "ALIAS".to_string(),
+                    name,
+                    data: target(&alias.dns_name),
+                    priority: None,
+                    ttl: None,
+                    comment: None,
+                },
+            );
+        }
+        for value in set.resource_records {
+            let value = value.value;
+            let (priority, data) = match record_type.as_str() {
+                "CNAME" | "NS" | "PTR" | "ALIAS" | "ANAME" => (None, target(&value)),
+                "MX" => match value.split_once(char::is_whitespace) {
+                    Some((p, host)) => (p.parse().ok(), target(host.trim())),
+                    None => (None, target(&value)),
+                },
+                "SRV" => srv_data(&value),
+                "TXT" | "SPF" => (None, unquote_txt(&value)),
+                _ => (None, value),
+            };
+            let record_type = match record_type.as_str() {
+                "ANAME" => "ALIAS".to_string(),
+                _ => record_type.clone(),
+            };
+            self.push(
+                &original,
+                CreateDnsRecordRequest {
+                    record_type,
+                    name: name.clone(),
+                    data,
+                    priority,
+                    ttl: set.ttl,
+                    comment: None,
+                },
+            );
+        }
+    }
+}
+
+impl SimplyClient {
+    /// Migrate a zone from another provider in one call.
+    ///
+    /// Parses `export` with [`Provider::parse`], compares the records with
+    /// the zone like [`plan_import`](Self::plan_import) and applies the
+    /// result, resolving every conflict with `resolution`. Records the export
+    /// can't be migrated with are left out; use [`Provider::parse`] directly
+    /// to see them. Fails if the export can't be parsed or the records can't
+    /// be listed; failed changes are collected in the report.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to migrate into.
+    /// * `provider` - The provider `export` is from.
+    /// * `export` - The provider's JSON export of the zone.
+    /// * `resolution` - How to resolve records that differ from the zone's.
+    pub async fn migrate_from(
+        &self,
+        domain: &str,
+        provider: Provider,
+        export: &str,
+        resolution: ImportResolution,
+    ) -> Result<OperationReport<Change>, MigrateError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let import = provider.parse(export, domain)?;
+        let report = this.plan_import(domain, import.records).await?;
+        Ok(this.apply(report.resolve(|_| resolution)).await)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CloudflareExport {
+    Envelope { result: Vec<CloudflareRecord> },
+    Records(Vec<CloudflareRecord>),
+}
+
+#[derive(Deserialize)]
+struct CloudflareRecord {
+    name: String,
+    #[serde(rename = "type")]
+    record_type: String,
+    #[serde(default)]
+    content: String,
+    ttl: Option<u32>,
+    priority: Option<u32>,
+    comment: Option<String>,
+    data: Option<CloudflareData>,
+}
+
+/// The structured data Cloudflare returns for SRV records.
+#[derive(Deserialize)]
+struct CloudflareData {
+    priority: Option<u32>,
+    weight: Option<u32>,
+    port: Option<u32>,
+    target: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Route53Export {
+    #[serde(rename_all = "PascalCase")]
+    Envelope {
+        resource_record_sets: Vec<Route53RecordSet>,
+    },
+    Sets(Vec<Route53RecordSet>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Route53RecordSet {
+    name: String,
+    #[serde(rename = "Type")]
+    record_type: String,
+    #[serde(rename = "TTL")]
+    ttl: Option<u32>,
+    #[serde(default)]
+    resource_records: Vec<Route53Value>,
+    alias_target: Option<Route53Alias>,
+    set_identifier: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Route53Value {
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct Route53Alias {
+    #[serde(rename = "DNSName")]
+    dns_name: String,
+}
+
+/// The zone-relative form of the fully-qualified `name`, or `None` outside `domain`.
+fn zone_name(name: &str, domain: &str) -> Option<String> {
+    let relative = names::to_relative(name, domain);
+    (relative == "@" || relative != name.trim_end_matches('.')).then_some(relative)
+}
+
+/// A target host name without the trailing dot, as the API takes it.
+fn target(host: &str) -> String {
+    host.trim().trim_end_matches('.').to_string()
+}
+
+/// SRV data as `priority weight port target` or `weight port target`.
+fn srv_data(value: &str) -> (Option<u32>, String) {
+    let fields: Vec<&str> = value.split_whitespace().collect();
+    match fields[..] {
+        [priority, weight, port, host] => (
+            priority.parse().ok(),
+            format!("{weight} {port} {}", target(host)),
+        ),
+        [weight, port, host] => (None, format!("{weight} {port} {}", target(host))),
+        _ => (None, value.to_string()),
+    }
+}
+
+/// The text of TXT data given as quoted strings, e.g. `"v=spf1" " -all"`.
+///
+/// Data that doesn't start with a quote is returned as it is.
+pub(crate) fn unquote_txt(value: &str) -> String {
+    let value = value.trim();
+    if !value.starts_with('"') {
+        return value.to_string();
+    }
+    let mut text = String::new();
+    let mut chars = value.chars();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => text.extend(chars.next()),
+            _ if quoted => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// A Route 53 name with its octal escapes, such as `\052` for `*`, decoded.
+fn unescape_route53(name: &str) -> String {
+    let mut out = String::new();
+    let mut rest = name;
+    while let Some(i) = rest.find('\\') {
+        out.push_str(&rest[..i]);
+        let escape = rest.get(i + 1..i + 4);
+        match escape.and_then(|e| u8::from_str_radix(e, 8).ok()) {
+            Some(byte) => {
+                out.push(char::from(byte));
+                rest = &rest[i + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLOUDFLARE: &str = include_str!("../fixtures/providers/cloudflare.json");
+    const ROUTE53: &str = include_str!("../fixtures/providers/route53.json");
+
+    /// A record as (name, type, data, priority, TTL).
+    type Row<'a> = (&'a str, &'a str, &'a str, Option<u32>, Option<u32>);
+
+    fn records(import: &ProviderImport) -> Vec<Row<'_>> {
+        import
+            .records
+            .iter()
+            .map(|r| {
+                let (name, record_type, data) = (&r.name, &r.record_type, &r.data);
+                (
+                    name.as_str(),
+                    record_type.as_str(),
+                    data.as_str(),
+                    r.priority,
+                    r.ttl,
+                )
+            })
+            .collect()
+    }
+
+    fn skipped(import: &ProviderImport) -> Vec<String> {
+        import.skipped.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn route53_exports_are_translated() {
+        let import = Provider::Route53.parse(ROUTE53, "example.com").unwrap();
+        assert_eq!(
+            records(&import),
+            [
+                ("@", "ALIAS", "d111111abcdef8.cloudfront.net", None, None),
+                ("@", "MX", "mx1.example.com", Some(10), Some(3600)),
+                ("@", "MX", "mx2.example.com", Some(20), Some(3600)),
+                (
+                    "@",
+                    "TXT",
+                    "v=spf1 include:amazonses.com -all",
+                    None,
+                    Some(300)
+                ),
+                ("@", "TXT", "say \"hello\"world", None, Some(300)),
+                ("www", "A", "192.0.2.10", None, Some(60)),
+                ("www", "A", "192.0.2.11", None, Some(60)),
+                (
+                    "_sip._udp",
+                    "SRV",
+                    "10 5060 sip1.example.com",
+                    Some(5),
+                    Some(300)
+                ),
+                (
+                    "_sip._udp",
+                    "SRV",
+                    "10 5060 sip2.example.com",
+                    Some(10),
+                    Some(300)
+                ),
+                ("*.apps", "CNAME", "apps-lb.example.net", None, Some(300)),
+            ]
+        );
+        assert_eq!(
+            skipped(&import),
+            [
+                "eu.example.com. A: routing policy (eu-west-1) has no Simply.com equivalent",
+                "example.net. A: outside the zone",
+            ]
+        );
+    }
+
+    #[test]
+    fn route53_record_sets_can_be_given_without_the_envelope() {
+        let sets = r#"[{"Name": "ftp.example.com", "Type": "CNAME", "TTL": 300,
+                        "ResourceRecords": [{"Value": "files.example.com."}]}]"#;
+        let import = Provider::Route53.parse(sets, "example.com.").unwrap();
+        assert_eq!(
+            records(&import),
+            [("ftp", "CNAME", "files.example.com", None, Some(300))]
+        );
+    }
+
+    #[test]
+    fn cloudflare_exports_are_translated() {
+        let import = Provider::Cloudflare
+            .parse(CLOUDFLARE, "example.com")
+            .unwrap();
+        assert_eq!(
+            records(&import),
+            [
+                // Proxied records point at the origin, with Cloudflare's
+                // automatic TTL (1) left to the Simply.com default.
+                ("@", "ALIAS", "example.pages.dev", None, None),
+                ("www", "A", "198.51.100.4", None, None),
+                ("mail", "AAAA", "2001:db8::25", None, Some(3600)),
+                ("@", "MX", "mail.example.com", Some(10), None),
+                (
+                    "_sip._tcp",
+                    "SRV",
+                    "10 5060 sip.example.com",
+                    Some(5),
+                    Some(300)
+                ),
+                (
+                    "_xmpp._tcp",
+                    "SRV",
+                    "0 5269 xmpp.example.com",
+                    Some(20),
+                    Some(300)
+                ),
+                (
+                    "@",
+                    "TXT",
+                    "v=spf1 include:_spf.example.net -all",
+                    None,
+                    None
+                ),
+                ("@", "TXT", "google-site-verification=abc123", None, None),
+                (
+                    "dev",
+                    "NS",
+                    "ns1.dev-hosting.example.net",
+                    None,
+                    Some(86400)
+                ),
+            ]
+        );
+        assert_eq!(import.records[1].comment.as_deref(), Some("origin"));
+        assert_eq!(import.records[2].comment, None);
+        assert_eq!(
+            skipped(&import),
+            [
+                "example.com HTTPS: record type not supported by Simply.com",
+                "www.example.org A: outside the zone",
+            ]
+        );
+    }
+
+    #[test]
+    fn cloudflare_records_can_be_given_without_the_envelope() {
+        let records_only = r#"[{"name": "example.com", "type": "TXT", "content": "hello", "ttl": 120},
+                               {"name": "EXAMPLE.com", "type": "TXT", "content": "\"hello\"", "ttl": 120}]"#;
+        let import = Provider::Cloudflare
+            .parse(records_only, "example.com")
+            .unwrap();
+        assert_eq!(records(&import), [("@", "TXT", "hello", None, Some(120))]);
+    }
+
+    #[test]
+    fn exports_of_other_providers_are_rejected() {
+        assert!(matches!(
+            Provider::Route53.parse(CLOUDFLARE, "example.com"),
+            Err(MigrateError::Json(_))
+        ));
+        assert!(
+            Provider::Cloudflare
+                .parse("not json", "example.com")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn quoted_txt_is_unquoted() {
+        for (data, text) in [
+            (r#""v=spf1 -all""#, "v=spf1 -all"),
+            (r#""v=DKIM1; k=rsa; " "p=MIIB""#, "v=DKIM1; k=rsa; p=MIIB"),
+            (r#""a\"b" "c\\d""#, "a\"bc\\d"),
+            ("unquoted text", "unquoted text"),
+            (r#"  "padded"  "#, "padded"),
+        ] {
+            assert_eq!(unquote_txt(data), text, "{data}");
+        }
+    }
+
+    #[test]
+    fn srv_data_splits_off_the_priority() {
+        assert_eq!(
+            srv_data("5 10 5060 sip.example.com."),
+            (Some(5), "10 5060 sip.example.com".to_string())
+        );
+        assert_eq!(
+            srv_data("10 5060 sip.example.com"),
+            (None, "10 5060 sip.example.com".to_string())
+        );
+        assert_eq!(srv_data("garbage"), (None, "garbage".to_string()));
+    }
+
+    #[test]
+    fn route53_escapes_are_decoded() {
+        assert_eq!(unescape_route53("\\052.example.com."), "*.example.com.");
+        assert_eq!(unescape_route53("a\\100b"), "a@b");
+        assert_eq!(unescape_route53("trailing\\"), "trailing\\");
+        assert_eq!(unescape_route53("bad\\9zz"), "bad\\9zz");
+    }
+}
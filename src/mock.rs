@@ -0,0 +1,224 @@
+//! An in-memory stand-in for the Simply.com API.
+//!
+//! [`MockSimplyClient`] implements [`SimplyDnsApi`] on top of an in-memory zone
+//! store, so code written against the trait can be tested without network access.
+//!
+//! Example usage:
+//! ```rust
+//! use simply_dns::SimplyDnsApi;
+//! use simply_dns::api::CreateDnsRecordRequest;
+//! use simply_dns::mock::MockSimplyClient;
+//! use simply_dns::record_data::RecordData;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let mock = MockSimplyClient::new().with_domain("example.com");
+//! let req = CreateDnsRecordRequest::new("www", RecordData::A([192, 0, 2, 10].into()));
+//! mock.create_dns_record("example.com", req).await?;
+//! assert_eq!(mock.records("example.com").len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord, DnsRecordId, UpdateDnsRecordRequest};
+use crate::record_data::SUPPORTED_RECORD_TYPES;
+use crate::{SimplyClientError, SimplyDnsApi};
+
+/// TTL given to records created without one, like the real API.
+const DEFAULT_TTL: u32 = 3600;
+
+/// An in-memory implementation of [`SimplyDnsApi`].
+///
+/// Behaves like the API where it matters for tests: record IDs are unique,
+/// unknown domains and records fail with [`SimplyClientError::NotFound`], and
+/// unsupported record types are rejected.
+#[derive(Debug, Default)]
+pub struct MockSimplyClient {
+    state: Mutex<MockState>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    zones: BTreeMap<String, Vec<DnsRecord>>,
+    next_id: u32,
+}
+
+impl MockSimplyClient {
+    /// A mock without any domains.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an empty zone for `domain`.
+    pub fn with_domain(self, domain: impl Into<String>) -> Self {
+        self.state().zones.entry(domain.into()).or_default();
+        self
+    }
+
+    /// Add a zone for `domain` containing `records`, which are given fresh record IDs.
+    pub fn with_records(
+        self,
+        domain: impl Into<String>,
+        records: impl IntoIterator<Item = CreateDnsRecordRequest>,
+    ) -> Self {
+        {
+            let mut state = self.state();
+            let records: Vec<DnsRecord> = records
+                .into_iter()
+                .map(|req| new_record(&mut state.next_id, req))
+                .collect();
+            state
+                .zones
+                .entry(domain.into())
+                .or_default()
+                .extend(records);
+        }
+        self
+    }
+
+    /// A snapshot of the records of `domain`, empty if the domain doesn't exist.
+    pub fn records(&self, domain: &str) -> Vec<DnsRecord> {
+        self.state().zones.get(domain).cloned().unwrap_or_default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn with_zone<T>(
+        &self,
+        domain: &str,
+        f: impl FnOnce(&mut Vec<DnsRecord>, &mut u32) -> Result<T, SimplyClientError>,
+    ) -> Result<T, SimplyClientError> {
+        let mut state = self.state();
+        let MockState { zones, next_id } = &mut *state;
+        let zone = zones
+            .get_mut(domain)
+            .ok_or_else(|| SimplyClientError::NotFound {
+                message: format!("domain {domain} not found"),
+            })?;
+        f(zone, next_id)
+    }
+}
+
+fn new_record(next_id: &mut u32, req: CreateDnsRecordRequest) -> DnsRecord {
+    *next_id += 1;
+    DnsRecord {
+        record_id: DnsRecordId { id: *next_id },
+        name: req.name,
+        ttl: req.ttl.unwrap_or(DEFAULT_TTL),
+        data: req.data,
+        record_type: req.record_type,
+        priority: req.priority,
+        comment: req.comment,
+    }
+}
+
+fn check_record_type(record_type: &str) -> Result<(), SimplyClientError> {
+    if SUPPORTED_RECORD_TYPES
+        .iter()
+        .any(|t| t.eq_ignore_ascii_case(record_type))
+    {
+        Ok(())
+    } else {
+        Err(SimplyClientError::UnsupportedRecordType(
+            record_type.to_string(),
+        ))
+    }
+}
+
+fn record_not_found(record_id: &DnsRecordId) -> SimplyClientError {
+    SimplyClientError::NotFound {
+        message: format!("record {} not found", record_id.id),
+    }
+}
+
+impl SimplyDnsApi for MockSimplyClient {
+    fn list_domains(&self) -> impl Future<Output = Result<Vec<String>, SimplyClientError>> + Send {
+        let domains = self.state().zones.keys().cloned().collect();
+        async move { Ok(domains) }
+    }
+
+    fn list_dns_records(
+        &self,
+        domain: &str,
+    ) -> impl Future<Output = Result<Vec<DnsRecord>, SimplyClientError>> + Send {
+        let result = self.with_zone(domain, |zone, _| Ok(zone.clone()));
+        async move { result }
+    }
+
+    fn get_dns_record(
+        &self,
+        domain: &str,
+        record_id: &DnsRecordId,
+    ) -> impl Future<Output = Result<Option<DnsRecord>, SimplyClientError>> + Send {
+        let result = self.with_zone(domain, |zone, _| {
+            Ok(zone
+                .iter()
+                .find(|r| r.record_id.id == record_id.id)
+                .cloned())
+        });
+        async move { result }
+    }
+
+    fn create_dns_record(
+        &self,
+        domain: &str,
+        req: CreateDnsRecordRequest,
+    ) -> impl Future<Output = Result<Vec<DnsRecordId>, SimplyClientError>> + Send {
+        let result = check_record_type(&req.record_type).and_then(|()| {
+            self.with_zone(domain, |zone, next_id| {
+                let record = new_record(next_id, req);
+                let id = record.record_id;
+                zone.push(record);
+                Ok(vec![id])
+            })
+        });
+        async move { result }
+    }
+
+    fn update_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+    ) -> impl Future<Output = Result<(), SimplyClientError>> + Send {
+        let result = check_record_type(&req.record_type).and_then(|()| {
+            self.with_zone(domain, |zone, _| {
+                let record = zone
+                    .iter_mut()
+                    .find(|r| r.record_id.id == record_id.id)
+                    .ok_or_else(|| record_not_found(&record_id))?;
+                record.name = req.name;
+                record.record_type = req.record_type;
+                record.data = req.data;
+                record.priority = req.priority;
+                if let Some(ttl) = req.ttl {
+                    record.ttl = ttl;
+                }
+                record.comment = req.comment;
+                Ok(())
+            })
+        });
+        async move { result }
+    }
+
+    fn delete_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+    ) -> impl Future<Output = Result<(), SimplyClientError>> + Send {
+        let result = self.with_zone(domain, |zone, _| {
+            let index = zone
+                .iter()
+                .position(|r| r.record_id.id == record_id.id)
+                .ok_or_else(|| record_not_found(&record_id))?;
+            zone.remove(index);
+            Ok(())
+        });
+        async move { result }
+    }
+}
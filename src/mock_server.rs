@@ -0,0 +1,318 @@
+//! A mock Simply.com API server for end-to-end tests.
+//!
+//! [`MockServer`] serves the DNS endpoints of the API over HTTP on top of a
+//! [`MockSimplyClient`], including basic authentication, the JSON error
+//! envelope and optional rate limiting. Point a [`SimplyClient`](crate::SimplyClient)
+//! at it with [`SimplyClientBuilder::base_url`](crate::SimplyClientBuilder::base_url),
+//! or run the `simply-dns-mock-server` binary to test other tools hermetically.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::mock::MockSimplyClient;
+//! use simply_dns::mock_server::MockServer;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let server = MockServer::builder()
+//!     .api(MockSimplyClient::new().with_domain("example.com"))
+//!     .start()
+//!     .await?;
+//! let client = SimplyClient::builder()
+//!     .credentials("account", "api_key")
+//!     .base_url(server.base_url())
+//!     .build()?;
+//! assert!(client.list_dns_records("example.com").await?.is_empty());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord, DnsRecordId, UpdateDnsRecordRequest};
+use crate::auth::{AuthScheme, BasicAuth};
+use crate::credentials::Credentials;
+use crate::http_server::{self, HttpRequest, HttpResponse};
+use crate::mock::MockSimplyClient;
+use crate::{SimplyClientError, SimplyDnsApi};
+
+/// Builder for [`MockServer`], created with [`MockServer::builder`].
+#[derive(Debug)]
+pub struct MockServerBuilder {
+    api: MockSimplyClient,
+    auth: Box<dyn AuthScheme>,
+    addr: SocketAddr,
+    rate_limit: Option<(usize, Duration)>,
+}
+
+impl MockServerBuilder {
+    /// Serve the zones of `api` instead of an empty mock.
+    pub fn api(mut self, api: MockSimplyClient) -> Self {
+        self.api = api;
+        self
+    }
+
+    /// Accept `account` and `api_key` instead of "account" and "api_key".
+    pub fn credentials(mut self, account: impl Into<String>, api_key: impl Into<String>) -> Self {
+        self.auth = Box::new(BasicAuth::new(Credentials::new(account, api_key)));
+        self
+    }
+
+    /// Accept the requests `scheme` verifies instead of basic authentication.
+    pub fn auth_scheme(mut self, scheme: impl AuthScheme + 'static) -> Self {
+        self.auth = Box::new(scheme);
+        self
+    }
+
+    /// Listen on `addr` instead of a random port on 127.0.0.1.
+    pub fn bind(mut self, addr: SocketAddr) -> Self {
+        self.addr = addr;
+        self
+    }
+
+    /// Answer with 429 Too Many Requests once more than `max_requests` arrive within `window`.
+    pub fn rate_limit(mut self, max_requests: usize, window: Duration) -> Self {
+        self.rate_limit = Some((max_requests, window));
+        self
+    }
+
+    /// Start serving in the background of the current Tokio runtime.
+    pub async fn start(self) -> io::Result<MockServer> {
+        let listener = TcpListener::bind(self.addr).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(ServerState {
+            api: self.api,
+            auth: self.auth,
+            rate_limit: self.rate_limit,
+            requests: Mutex::new(VecDeque::new()),
+        });
+        let task = tokio::spawn(http_server::serve(listener, {
+            let state = state.clone();
+            move |req| {
+                let state = state.clone();
+                async move { handle(&state, req).await }
+            }
+        }));
+        Ok(MockServer { addr, state, task })
+    }
+}
+
+/// A running mock API server. Stops when dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<ServerState>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// A builder for a server with no domains, accepting the credentials "account" and "api_key".
+    pub fn builder() -> MockServerBuilder {
+        MockServerBuilder {
+            api: MockSimplyClient::new(),
+            auth: Box::new(BasicAuth::new(Credentials::new("account", "api_key"))),
+            addr: (Ipv4Addr::LOCALHOST, 0).into(),
+            rate_limit: None,
+        }
+    }
+
+    /// The address the server listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The base URL to configure clients with, e.g. `http://127.0.0.1:41234/2/`.
+    pub fn base_url(&self) -> String {
+        format!("http://{}/2/", self.addr)
+    }
+
+    /// The zones served, to seed or inspect them during a test.
+    pub fn api(&self) -> &MockSimplyClient {
+        &self.state.api
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[derive(Debug)]
+struct ServerState {
+    api: MockSimplyClient,
+    auth: Box<dyn AuthScheme>,
+    rate_limit: Option<(usize, Duration)>,
+    requests: Mutex<VecDeque<Instant>>,
+}
+
+impl ServerState {
+    /// Count a request, returning how long to wait if it exceeds the rate limit.
+    fn throttle(&self) -> Option<Duration> {
+        let (max_requests, window) = self.rate_limit?;
+        let now = Instant::now();
+        let mut requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+        while requests.front().is_some_and(|t| now - *t >= window) {
+            requests.pop_front();
+        }
+        if requests.len() >= max_requests {
+            return requests.front().map(|t| window - (now - *t));
+        }
+        requests.push_back(now);
+        None
+    }
+}
+
+async fn handle(state: &ServerState, req: HttpRequest) -> HttpResponse {
+    if !state.auth.verify(req.header("authorization")) {
+        return error(401, "invalid credentials");
+    }
+    if let Some(wait) = state.throttle() {
+        let seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+        let mut res = error(429, "too many requests");
+        res.headers
+            .push(("retry-after", seconds.max(1).to_string()));
+        return res;
+    }
+
+    let segments = req.segments();
+    let api = &state.api;
+    let result = match (req.method.as_str(), &segments[..]) {
+        ("GET", ["2", "my", "products"]) => list_products(api).await,
+        ("GET", ["2", "my", "products", domain, "dns", "records"]) => {
+            api.list_dns_records(domain).await.map(|records| {
+                json!({ "records": records.iter().map(record_json).collect::<Vec<_>>() })
+            })
+        }
+        ("POST", ["2", "my", "products", domain, "dns", "records"]) => {
+            match serde_json::from_slice::<CreateRecordBody>(&req.body) {
+                Ok(body) => api.create_dns_record(domain, body.into()).await.map(|ids| {
+                    json!({ "record": ids.iter().map(|id| json!({ "id": id.id })).collect::<Vec<_>>() })
+                }),
+                Err(e) => Err(validation(e)),
+            }
+        }
+        ("PUT", ["2", "my", "products", domain, "dns", "records", id]) => {
+            match (serde_json::from_slice::<CreateRecordBody>(&req.body), id.parse()) {
+                (Ok(body), Ok(id)) => api
+                    .update_dns_record(domain, DnsRecordId { id }, body.into())
+                    .await
+                    .map(|()| json!({})),
+                (Err(e), _) => Err(validation(e)),
+                (_, Err(_)) => Err(not_found("record")),
+            }
+        }
+        ("DELETE", ["2", "my", "products", domain, "dns", "records", id]) => match id.parse() {
+            Ok(id) => api
+                .delete_dns_record(domain, DnsRecordId { id })
+                .await
+                .map(|()| json!({})),
+            Err(_) => Err(not_found("record")),
+        },
+        _ => Err(not_found("endpoint")),
+    };
+    match result {
+        Ok(mut value) => {
+            value["status"] = json!(200);
+            value["message"] = json!("OK");
+            HttpResponse::new(200, "application/json", value.to_string())
+        }
+        Err(e) => {
+            let message = match &e {
+                SimplyClientError::NotFound { message }
+                | SimplyClientError::Validation { message, .. } => message.clone(),
+                e => e.to_string(),
+            };
+            error(e.status().unwrap_or(400), &message)
+        }
+    }
+}
+
+async fn list_products(api: &MockSimplyClient) -> Result<Value, SimplyClientError> {
+    let products: Vec<Value> = api
+        .list_domains()
+        .await?
+        .into_iter()
+        .map(|domain| json!({ "object": domain, "name": domain, "domain": { "name": domain } }))
+        .collect();
+    Ok(json!({ "products": products }))
+}
+
+/// The body of a create or update request.
+#[derive(serde::Deserialize)]
+struct CreateRecordBody {
+    #[serde(rename = "type")]
+    record_type: String,
+    name: String,
+    data: String,
+    #[serde(default)]
+    priority: Option<u32>,
+    #[serde(default)]
+    ttl: Option<u32>,
+    #[serde(default)]
+    comment: Option<String>,
+}
+
+impl From<CreateRecordBody> for CreateDnsRecordRequest {
+    fn from(body: CreateRecordBody) -> Self {
+        Self {
+            record_type: body.record_type,
+            name: body.name,
+            data: body.data,
+            priority: body.priority,
+            ttl: body.ttl,
+            comment: body.comment,
+        }
+    }
+}
+
+impl From<CreateRecordBody> for UpdateDnsRecordRequest {
+    fn from(body: CreateRecordBody) -> Self {
+        Self {
+            record_type: body.record_type,
+            name: body.name,
+            data: body.data,
+            priority: body.priority,
+            ttl: body.ttl,
+            comment: body.comment,
+        }
+    }
+}
+
+fn record_json(record: &DnsRecord) -> Value {
+    json!({
+        "record_id": record.record_id.id,
+        "name": record.name,
+        "ttl": record.ttl,
+        "data": record.data,
+        "type": record.record_type,
+        "priority": record.priority,
+        "comment": record.comment,
+    })
+}
+
+fn validation(e: serde_json::Error) -> SimplyClientError {
+    SimplyClientError::Validation {
+        status: Some(400),
+        field: None,
+        message: e.to_string(),
+    }
+}
+
+fn not_found(what: &str) -> SimplyClientError {
+    SimplyClientError::NotFound {
+        message: format!("{what} not found"),
+    }
+}
+
+fn error(status: u16, message: &str) -> HttpResponse {
+    let body = json!({ "status": status, "message": message });
+    HttpResponse::new(status, "application/json", body.to_string())
+}
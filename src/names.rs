@@ -0,0 +1,165 @@
+//! Conversion between zone-relative and fully-qualified record names.
+//!
+//! The API names records relative to their zone (`www`, `@` for the apex).
+//! A client built with [`NameStyle::Fqdn`] lets its helper methods take and
+//! return fully-qualified names (`www.example.com.`) instead, see
+//! [`SimplyClientBuilder::name_style`](crate::SimplyClientBuilder::name_style).
+
+/// Which form of record names a client's helper methods take and return.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NameStyle {
+    /// Names relative to the zone, like the API: `www`, `@` for the apex.
+    ///
+    /// Names ending in a dot are still accepted and converted.
+    #[default]
+    Relative,
+    /// Fully-qualified names with a trailing dot: `www.example.com.`.
+    ///
+    /// Names within the zone are accepted with or without the trailing dot, and
+    /// other names are taken as relative to the zone.
+    Fqdn,
+}
+
+impl NameStyle {
+    /// Bring `name`, given in either form, into this style for `domain`.
+    ///
+    /// Example usage:
+    /// ```rust
+    /// use simply_dns::names::NameStyle;
+    ///
+    /// assert_eq!(NameStyle::Relative.normalize("www.example.com.", "example.com"), "www");
+    /// assert_eq!(NameStyle::Fqdn.normalize("www", "example.com"), "www.example.com.");
+    /// assert_eq!(NameStyle::Fqdn.normalize("www.example.com", "example.com"), "www.example.com.");
+    /// ```
+    pub fn normalize(self, name: &str, domain: &str) -> String {
+        match self {
+            NameStyle::Relative if name.ends_with('.') => to_relative(name, domain),
+            NameStyle::Relative => name.to_string(),
+            NameStyle::Fqdn if name.ends_with('.') => name.to_string(),
+            NameStyle::Fqdn => {
+                let relative = to_relative(name, domain);
+                if relative == name {
+                    to_fqdn(name, domain)
+                } else {
+                    format!("{name}.")
+                }
+            }
+        }
+    }
+}
+
+/// The zone-relative form of `name` in `domain`, `@` for the apex.
+///
+/// `name` may be given with or without a trailing dot. Names outside the zone
+/// are returned without the trailing dot but otherwise unchanged.
+pub fn to_relative(name: &str, domain: &str) -> String {
+    let name = name.trim_end_matches('.');
+    let domain = domain.trim_end_matches('.');
+    if name.eq_ignore_ascii_case(domain) {
+        return "@".to_string();
+    }
+    let split = name.len().saturating_sub(domain.len() + 1);
+    match (name.get(..split), name.get(split..)) {
+        (Some(label), Some(suffix))
+            if !label.is_empty()
+                && suffix.starts_with('.')
+                && suffix[1..].eq_ignore_ascii_case(domain) =>
+        {
+            label.to_string()
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// The fully-qualified form of the zone-relative `name` in `domain`, with a trailing dot.
+///
+/// `@` and the empty name stand for the apex; names that already end in a dot
+/// are returned unchanged.
+pub fn to_fqdn(name: &str, domain: &str) -> String {
+    let domain = domain.trim_end_matches('.');
+    match name {
+        "" | "@" => format!("{domain}."),
+        _ if name.ends_with('.') => name.to_string(),
+        _ => format!("{name}.{domain}."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_become_relative_to_their_zone() {
+        for (name, domain, relative) in [
+            ("www.example.com", "example.com", "www"),
+            ("www.example.com.", "example.com", "www"),
+            ("www.example.com", "example.com.", "www"),
+            ("a.b.example.com.", "example.com", "a.b"),
+            ("WWW.Example.COM.", "example.com", "WWW"),
+            ("example.com", "example.com", "@"),
+            ("EXAMPLE.com.", "example.com.", "@"),
+            ("*.example.com.", "example.com", "*"),
+            // Names outside the zone only lose the trailing dot.
+            ("www.example.org.", "example.com", "www.example.org"),
+            ("notexample.com", "example.com", "notexample.com"),
+            ("example.com.au", "example.com", "example.com.au"),
+            (".example.com", "example.com", ".example.com"),
+            ("www", "example.com", "www"),
+            ("@", "example.com", "@"),
+            ("", "example.com", ""),
+        ] {
+            assert_eq!(to_relative(name, domain), relative, "{name} in {domain}");
+        }
+    }
+
+    #[test]
+    fn relative_names_become_fully_qualified() {
+        for (name, domain, fqdn) in [
+            ("www", "example.com", "www.example.com."),
+            ("a.b", "example.com.", "a.b.example.com."),
+            ("@", "example.com", "example.com."),
+            ("", "example.com.", "example.com."),
+            ("*", "example.com", "*.example.com."),
+            ("www.example.org.", "example.com", "www.example.org."),
+        ] {
+            assert_eq!(to_fqdn(name, domain), fqdn, "{name} in {domain}");
+        }
+    }
+
+    #[test]
+    fn names_round_trip_between_the_forms() {
+        for name in ["www", "a.b", "@", "*", "_sip._tcp"] {
+            assert_eq!(
+                to_relative(&to_fqdn(name, "example.com"), "example.com"),
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn styles_normalize_either_form() {
+        for (style, name, normalized) in [
+            (NameStyle::Relative, "www", "www"),
+            (NameStyle::Relative, "www.example.com.", "www"),
+            (NameStyle::Relative, "example.com.", "@"),
+            // Without the trailing dot, a name is taken as relative.
+            (NameStyle::Relative, "www.example.com", "www.example.com"),
+            (NameStyle::Fqdn, "www", "www.example.com."),
+            (NameStyle::Fqdn, "@", "example.com."),
+            (NameStyle::Fqdn, "www.example.com", "www.example.com."),
+            (NameStyle::Fqdn, "www.example.com.", "www.example.com."),
+            (NameStyle::Fqdn, "www.example.org.", "www.example.org."),
+            (
+                NameStyle::Fqdn,
+                "www.example.org",
+                "www.example.org.example.com.",
+            ),
+        ] {
+            assert_eq!(
+                style.normalize(name, "example.com"),
+                normalized,
+                "{style:?} {name}"
+            );
+        }
+    }
+}
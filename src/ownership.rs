@@ -0,0 +1,84 @@
+//! Telling records created by automation from hand-made ones.
+//!
+//! Like external-dns, automation marks the records it creates with an
+//! ownership tag, a single word such as "owner=ci-preview", in their comment.
+//! A client set up with [`SimplyClientBuilder::owner`](crate::SimplyClientBuilder::owner)
+//! or derived with [`SimplyClient::with_owner`] tags every record it creates,
+//! and [`SimplyClient::cleanup_owned`] later deletes just the records carrying
+//! the tag, so cleanup can never remove a record someone made by hand.
+//!
+//! The same tags are used by [`adopt`](crate::adopt) and by zones managed with
+//! [`DesiredZone::owned_by`](crate::sync::DesiredZone::owned_by).
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::api::CreateDnsRecordRequest;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key").with_owner("owner=pr-42");
+//! client
+//!     .create_dns_record(
+//!         "example.com",
+//!         CreateDnsRecordRequest {
+//!             name: "pr-42".to_string(),
+//!             record_type: "CNAME".to_string(),
+//!             data: "previews.example.net".to_string(),
+//!             priority: None,
+//!             ttl: Some(300),
+//!             comment: Some("preview of PR 42".to_string()),
+//!         },
+//!     )
+//!     .await?;
+//!
+//! // Once the PR is merged:
+//! println!("{}", client.cleanup_owned("example.com", "owner=pr-42").await?);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::api::DnsRecord;
+use crate::progress::Operation;
+use crate::report::OperationReport;
+use crate::sync::has_owner_tag;
+use crate::time::Instant;
+use crate::{SimplyClient, SimplyClientError};
+
+impl SimplyClient {
+    /// Delete the records of a domain whose comment carries the ownership tag `owner`.
+    ///
+    /// Records without the tag are never touched. Fails only if the records
+    /// can't be listed; failed deletions are collected in the report.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to clean up.
+    /// * `owner` - The ownership tag, as passed to [`SimplyClientBuilder::owner`](crate::SimplyClientBuilder::owner).
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn cleanup_owned(
+        &self,
+        domain: &str,
+        owner: &str,
+    ) -> Result<OperationReport<DnsRecord>, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let started = Instant::now();
+        let mut report = OperationReport::default();
+        let records: Vec<_> = this
+            .list_dns_records(domain)
+            .await?
+            .into_iter()
+            .filter(|r| has_owner_tag(r.comment.as_deref(), owner))
+            .collect();
+        let mut progress = this.progress(Operation::CleanupOwned, Some(records.len()));
+        for record in records {
+            progress.begin();
+            let record_started = Instant::now();
+            let result = this.delete_dns_record(domain, record.record_id).await;
+            progress.record(&mut report, record, result, record_started);
+        }
+        report.elapsed = started.elapsed();
+        progress.finish(&report);
+        Ok(report)
+    }
+}
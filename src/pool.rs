@@ -0,0 +1,248 @@
+//! Clients for several Simply.com accounts.
+//!
+//! A [`SimplyClientPool`] holds one [`SimplyClient`] per account, all
+//! configured alike and sending over one shared HTTP connection pool. The API
+//! limits requests per account, so each account gets its own rate limit
+//! instead of all of them sharing one. [`SimplyClientPool::for_account`]
+//! routes a call to the right client, and
+//! [`SimplyClientPool::for_each_account`] runs an operation on every account
+//! with a bounded number running at once.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::RetryPolicy;
+//! use simply_dns::pool::SimplyClientPool;
+//!
+//! # async fn run() -> Result<(), simply_dns::pool::PoolError> {
+//! let pool = SimplyClientPool::builder()
+//!     .account("S123456", "api_key_1")
+//!     .account("S654321", "api_key_2")
+//!     .rate_limit(5.0, 10)
+//!     .account_rate_limit("S654321", 1.0, 2)
+//!     .configure(|builder| builder.retry(RetryPolicy::default()))
+//!     .build()?;
+//!
+//! let domains = pool.for_account("S123456")?.list_domains().await?;
+//! println!("{domains:?}");
+//!
+//! for (account, domains) in pool
+//!     .for_each_account(4, |_, client| async move { client.list_domains().await })
+//!     .await
+//! {
+//!     println!("{account}: {:?}", domains?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+use futures_util::{StreamExt, stream};
+use reqwest::Client;
+use thiserror::Error;
+
+use crate::credentials::Credentials;
+use crate::{SimplyClient, SimplyClientBuilder, SimplyClientError};
+
+/// Error type for [`SimplyClientPool`] lookups.
+#[derive(Debug, Error)]
+pub enum PoolError {
+    /// The pool has no client for the account.
+    #[error("no client for account {0}")]
+    UnknownAccount(String),
+    /// The Simply.com API call failed.
+    #[error(transparent)]
+    Client(#[from] SimplyClientError),
+}
+
+type Configure = Arc<dyn Fn(SimplyClientBuilder) -> SimplyClientBuilder + Send + Sync>;
+
+/// Builder for a [`SimplyClientPool`], from [`SimplyClientPool::builder`].
+#[derive(Default)]
+pub struct SimplyClientPoolBuilder {
+    accounts: Vec<Credentials>,
+    rate_limit: Option<(f64, u32)>,
+    account_rate_limits: HashMap<String, (f64, u32)>,
+    http_client: Option<Client>,
+    configure: Option<Configure>,
+}
+
+impl fmt::Debug for SimplyClientPoolBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimplyClientPoolBuilder")
+            .field("accounts", &self.accounts)
+            .field("rate_limit", &self.rate_limit)
+            .field("account_rate_limits", &self.account_rate_limits)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SimplyClientPoolBuilder {
+    /// Add the account `account`, authenticating with `api_key`.
+    pub fn account(self, account: impl Into<String>, api_key: impl Into<String>) -> Self {
+        self.credentials(Credentials::new(account, api_key))
+    }
+
+    /// Add the account of `credentials`, e.g. as found by a
+    /// [`CredentialsProvider`](crate::credentials::CredentialsProvider).
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.accounts.push(credentials);
+        self
+    }
+
+    /// Limit every account to `requests_per_second` on average, allowing
+    /// bursts of up to `burst` requests, see
+    /// [`SimplyClientBuilder::rate_limit`]. Each account has a limit of its own.
+    pub fn rate_limit(mut self, requests_per_second: f64, burst: u32) -> Self {
+        self.rate_limit = Some((requests_per_second, burst));
+        self
+    }
+
+    /// Limit `account` to `requests_per_second` with bursts of up to `burst`,
+    /// instead of the [`rate_limit`](Self::rate_limit) of the other accounts.
+    pub fn account_rate_limit(
+        mut self,
+        account: impl Into<String>,
+        requests_per_second: f64,
+        burst: u32,
+    ) -> Self {
+        self.account_rate_limits
+            .insert(account.into(), (requests_per_second, burst));
+        self
+    }
+
+    /// Send the requests of all accounts with `client`, instead of one the
+    /// pool creates with the default user agent.
+    pub fn http_client(mut self, client: Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Apply `configure` to the builder of every client, e.g. to set retries,
+    /// a timeout or audit hooks.
+    ///
+    /// The builders already have the account's credentials and rate limit and
+    /// the shared HTTP client, so set a user agent or proxy on
+    /// [`http_client`](Self::http_client) instead.
+    pub fn configure(
+        mut self,
+        configure: impl Fn(SimplyClientBuilder) -> SimplyClientBuilder + Send + Sync + 'static,
+    ) -> Self {
+        self.configure = Some(Arc::new(configure));
+        self
+    }
+
+    /// Build a client for every account.
+    ///
+    /// Returns [`SimplyClientError::Config`] if an account was added twice,
+    /// and the error of the first client that fails to build.
+    pub fn build(self) -> Result<SimplyClientPool, SimplyClientError> {
+        let http = match self.http_client {
+            Some(client) => client,
+            None => Client::builder()
+                .user_agent(concat!(
+                    env!("CARGO_PKG_NAME"),
+                    "/",
+                    env!("CARGO_PKG_VERSION")
+                ))
+                .build()?,
+        };
+        let mut clients = BTreeMap::new();
+        for credentials in self.accounts {
+            let account = credentials.account().to_string();
+            if clients.contains_key(&account) {
+                return Err(SimplyClientError::Config(format!(
+                    "account {account} was added twice"
+                )));
+            }
+            let mut builder = SimplyClient::builder()
+                .credentials(account.clone(), credentials.api_key())
+                .http_client(http.clone());
+            if let Some((rate, burst)) = self
+                .account_rate_limits
+                .get(&account)
+                .or(self.rate_limit.as_ref())
+            {
+                builder = builder.rate_limit(*rate, *burst);
+            }
+            if let Some(configure) = &self.configure {
+                builder = configure(builder);
+            }
+            clients.insert(account, builder.build()?);
+        }
+        Ok(SimplyClientPool { clients })
+    }
+}
+
+/// A client per Simply.com account, see the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct SimplyClientPool {
+    clients: BTreeMap<String, SimplyClient>,
+}
+
+impl SimplyClientPool {
+    /// Create a [`SimplyClientPoolBuilder`] to add accounts and configure their clients.
+    pub fn builder() -> SimplyClientPoolBuilder {
+        SimplyClientPoolBuilder::default()
+    }
+
+    /// The client of `account`.
+    ///
+    /// Returns [`PoolError::UnknownAccount`] if the pool has none.
+    pub fn for_account(&self, account: &str) -> Result<&SimplyClient, PoolError> {
+        self.get(account)
+            .ok_or_else(|| PoolError::UnknownAccount(account.to_string()))
+    }
+
+    /// The client of `account`, if the pool has one.
+    pub fn get(&self, account: &str) -> Option<&SimplyClient> {
+        self.clients.get(account)
+    }
+
+    /// The accounts of the pool, sorted.
+    pub fn accounts(&self) -> impl Iterator<Item = &str> {
+        self.clients.keys().map(String::as_str)
+    }
+
+    /// The number of accounts.
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Whether the pool has no accounts.
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    /// Run `operation` with the client of every account, at most
+    /// `concurrency` at a time.
+    ///
+    /// Returns once all have finished, with each account's result in the
+    /// order of [`accounts`](Self::accounts).
+    ///
+    /// # Arguments
+    /// * `concurrency` - How many accounts to work on at once, at least 1.
+    /// * `operation` - Called with the account and a clone of its client.
+    pub async fn for_each_account<F, Fut, T>(
+        &self,
+        concurrency: usize,
+        operation: F,
+    ) -> Vec<(String, T)>
+    where
+        F: Fn(String, SimplyClient) -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let operation = &operation;
+        stream::iter(&self.clients)
+            .map(|(account, client)| async move {
+                let result = operation(account.clone(), client.clone()).await;
+                (account.clone(), result)
+            })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+}
@@ -0,0 +1,287 @@
+//! Measuring how records resolve.
+//!
+//! [`ResolutionProbe`] queries a set of names on a set of resolvers at a fixed
+//! rate for a while, and reports latency, failure rate and which answers each
+//! resolver gave, e.g. while evaluating a TTL change or chasing a suspected
+//! provider issue. Answers that differ between resolvers show up in
+//! [`ProbeReport::inconsistent`].
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use simply_dns::probe::ResolutionProbe;
+//!
+//! # async fn run() -> Result<(), simply_dns::propagation::PropagationError> {
+//! let report = ResolutionProbe::new("example.com")
+//!     .name("www")
+//!     .qps(20)
+//!     .duration(Duration::from_secs(30))
+//!     .run()
+//!     .await?;
+//! print!("{report}");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use futures_util::{StreamExt, stream};
+use tokio::time::Instant;
+
+use crate::propagation::{
+    PropagationChecker, PropagationError, default_resolvers, fqdn, type_code,
+};
+
+/// Queries names on several resolvers at a fixed rate, see the [module docs](self).
+///
+/// By default the apex `A` record is queried on Google (8.8.8.8) and
+/// Cloudflare (1.1.1.1), 10 times per second for 10 seconds.
+#[derive(Debug, Clone)]
+pub struct ResolutionProbe {
+    domain: String,
+    names: Vec<String>,
+    record_type: String,
+    resolvers: Vec<SocketAddr>,
+    qps: u32,
+    duration: Duration,
+    query_timeout: Duration,
+}
+
+impl ResolutionProbe {
+    /// Probe records of `domain`.
+    pub fn new(domain: impl Into<String>) -> Self {
+        Self {
+            domain: domain.into(),
+            names: Vec::new(),
+            record_type: "A".to_string(),
+            resolvers: default_resolvers(),
+            qps: 10,
+            duration: Duration::from_secs(10),
+            query_timeout: Duration::from_secs(3),
+        }
+    }
+
+    /// Add a name to query, relative to the domain, e.g. "www" or "@" for the
+    /// apex. Without any, the apex is queried.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.names.push(name.into());
+        self
+    }
+
+    /// The record type to query ("A", "TXT", etc.). Defaults to "A".
+    pub fn record_type(mut self, record_type: impl Into<String>) -> Self {
+        self.record_type = record_type.into();
+        self
+    }
+
+    /// Replace the resolvers that are queried.
+    pub fn resolvers(mut self, resolvers: Vec<SocketAddr>) -> Self {
+        self.resolvers = resolvers;
+        self
+    }
+
+    /// How many queries to send per second, spread over all names and
+    /// resolvers. Defaults to 10.
+    pub fn qps(mut self, qps: u32) -> Self {
+        self.qps = qps.max(1);
+        self
+    }
+
+    /// How long to keep querying. Defaults to 10 seconds.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// How long to wait for a single answer before counting it as failed.
+    /// Defaults to 3 seconds.
+    pub fn query_timeout(mut self, timeout: Duration) -> Self {
+        self.query_timeout = timeout;
+        self
+    }
+
+    /// Send the queries and collect the results.
+    ///
+    /// Fails only if the record type can't be queried; failed queries are
+    /// counted in the report.
+    pub async fn run(&self) -> Result<ProbeReport, PropagationError> {
+        if type_code(&self.record_type).is_none() {
+            return Err(PropagationError::UnsupportedType(self.record_type.clone()));
+        }
+        let checker = PropagationChecker::new().query_timeout(self.query_timeout);
+        let names = if self.names.is_empty() {
+            vec![fqdn(&self.domain, "@")]
+        } else {
+            self.names.iter().map(|n| fqdn(&self.domain, n)).collect()
+        };
+        let targets: Vec<(&str, SocketAddr)> = names
+            .iter()
+            .flat_map(|name| self.resolvers.iter().map(move |&r| (name.as_str(), r)))
+            .collect();
+        let mut stats: Vec<ProbeStats> = targets
+            .iter()
+            .map(|&(name, resolver)| ProbeStats::new(name, resolver))
+            .collect();
+        if targets.is_empty() {
+            return Ok(ProbeReport {
+                record_type: self.record_type.clone(),
+                stats,
+            });
+        }
+        let interval = Duration::from_secs(1) / self.qps;
+        let queries = (self.duration.as_secs_f64() * f64::from(self.qps)).ceil() as usize;
+        // Enough queries in flight to keep the rate up while some time out.
+        let in_flight =
+            (self.query_timeout.as_secs_f64() * f64::from(self.qps)).ceil() as usize + 1;
+        let start = Instant::now();
+        let checker = &checker;
+        let targets = &targets;
+        let mut results = stream::iter(0..queries)
+            .map(|i| async move {
+                tokio::time::sleep_until(start + interval * i as u32).await;
+                let index = i % targets.len();
+                let (name, resolver) = targets[index];
+                let sent = Instant::now();
+                let result = checker.lookup(resolver, name, &self.record_type).await;
+                (index, sent.elapsed(), result)
+            })
+            .buffer_unordered(in_flight);
+        while let Some((index, latency, result)) = results.next().await {
+            let stats = &mut stats[index];
+            stats.queries += 1;
+            match result {
+                Ok(mut answers) => {
+                    answers.sort();
+                    stats.latencies.push(latency);
+                    *stats.answers.entry(answers).or_default() += 1;
+                }
+                Err(_) => stats.failures += 1,
+            }
+        }
+        for stats in &mut stats {
+            stats.latencies.sort();
+        }
+        Ok(ProbeReport {
+            record_type: self.record_type.clone(),
+            stats,
+        })
+    }
+}
+
+/// The results of a [`ResolutionProbe`].
+#[derive(Debug, Clone)]
+pub struct ProbeReport {
+    /// The record type that was queried.
+    pub record_type: String,
+    /// The results per name and resolver.
+    pub stats: Vec<ProbeStats>,
+}
+
+impl ProbeReport {
+    /// The names for which resolvers, or successive queries, gave different answers.
+    pub fn inconsistent(&self) -> Vec<&str> {
+        let mut answers: BTreeMap<&str, Vec<&Vec<String>>> = BTreeMap::new();
+        for stats in &self.stats {
+            answers
+                .entry(&stats.name)
+                .or_default()
+                .extend(stats.answers.keys());
+        }
+        answers
+            .into_iter()
+            .filter(|(_, answers)| answers.iter().any(|a| *a != answers[0]))
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+/// Shows a table with a line per name and resolver, followed by the names
+/// with inconsistent answers.
+impl fmt::Display for ProbeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ms = |d: Option<Duration>| {
+            d.map_or_else(
+                || "-".to_string(),
+                |d| format!("{:.1}", d.as_secs_f64() * 1000.0),
+            )
+        };
+        writeln!(
+            f,
+            "{:<30} {:<22} {:>7} {:>7} {:>8} {:>8} {:>8}  answers",
+            "name", "resolver", "queries", "failed", "p50 ms", "p95 ms", "max ms"
+        )?;
+        for stats in &self.stats {
+            let answers: Vec<String> = stats
+                .answers
+                .iter()
+                .map(|(answer, count)| format!("{} ({count})", answer.join(",")))
+                .collect();
+            writeln!(
+                f,
+                "{:<30} {:<22} {:>7} {:>6.1}% {:>8} {:>8} {:>8}  {}",
+                stats.name,
+                stats.resolver.to_string(),
+                stats.queries,
+                stats.failure_rate() * 100.0,
+                ms(stats.latency_percentile(50.0)),
+                ms(stats.latency_percentile(95.0)),
+                ms(stats.latencies.last().copied()),
+                answers.join(" | "),
+            )?;
+        }
+        for name in self.inconsistent() {
+            writeln!(f, "inconsistent answers for {name} {}", self.record_type)?;
+        }
+        Ok(())
+    }
+}
+
+/// The results for one name on one resolver.
+#[derive(Debug, Clone)]
+pub struct ProbeStats {
+    /// The fully qualified name that was queried.
+    pub name: String,
+    /// The resolver that was queried.
+    pub resolver: SocketAddr,
+    /// How many queries were sent.
+    pub queries: u32,
+    /// How many queries failed or timed out.
+    pub failures: u32,
+    /// The latencies of the answered queries, in ascending order.
+    pub latencies: Vec<Duration>,
+    /// How often each set of answers, sorted, was returned.
+    pub answers: BTreeMap<Vec<String>, u32>,
+}
+
+impl ProbeStats {
+    fn new(name: &str, resolver: SocketAddr) -> Self {
+        Self {
+            name: name.to_string(),
+            resolver,
+            queries: 0,
+            failures: 0,
+            latencies: Vec::new(),
+            answers: BTreeMap::new(),
+        }
+    }
+
+    /// The share of queries that failed, from 0 to 1.
+    pub fn failure_rate(&self) -> f64 {
+        if self.queries == 0 {
+            0.0
+        } else {
+            f64::from(self.failures) / f64::from(self.queries)
+        }
+    }
+
+    /// The latency below which `percentile` percent of the answers arrived,
+    /// or `None` without answers.
+    pub fn latency_percentile(&self, percentile: f64) -> Option<Duration> {
+        let last = self.latencies.len().checked_sub(1)?;
+        let index = ((percentile / 100.0) * last as f64).round() as usize;
+        self.latencies.get(index.min(last)).copied()
+    }
+}
@@ -0,0 +1,657 @@
+//! Progress events of long-running operations.
+//!
+//! A [`ProgressSink`] registered with
+//! [`SimplyClientBuilder::progress_sink`](crate::SimplyClientBuilder::progress_sink),
+//! or attached to a client with
+//! [`SimplyClient::with_progress_sink`](crate::SimplyClient::with_progress_sink),
+//! is told when an operation that produces an
+//! [`OperationReport`] starts, as each of its items is done, and when it
+//! finishes. This covers the bulk methods of [`batch`](crate::batch),
+//! [`apply`](crate::SimplyClient::apply) and [`sync`](crate::SimplyClient::sync)
+//! (and with them imports), TTL lowering and restoring, adoption, cleanup of
+//! owned and expired records and the account-wide helpers, so a frontend can
+//! render progress for all of them in one place.
+//!
+//! Items are reported as they start and as they finish, and requests that
+//! are retried are reported too. Where a callback doesn't fit, e.g. to
+//! render progress on another task, [`progress_stream`] turns the events
+//! into a [`Stream`] of owned [`ProgressUpdate`]s.
+//!
+//! Example usage:
+//! ```rust
+//! use simply_dns::SimplyClient;
+//! use simply_dns::progress::{ProgressEvent, ProgressSink};
+//!
+//! struct Printer;
+//!
+//! impl ProgressSink for Printer {
+//!     fn on_event(&self, event: &ProgressEvent<'_>) {
+//!         if let (ProgressEvent::Item { operation, .. }, Some(percent)) = (event, event.percent()) {
+//!             eprint!("\r{operation}: {percent:.0}%");
+//!         }
+//!     }
+//! }
+//!
+//! let client = SimplyClient::builder()
+//!     .credentials("account", "api_key")
+//!     .progress_sink(Printer)
+//!     .build()?;
+//! # Ok::<(), simply_dns::SimplyClientError>(())
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use futures_util::Stream;
+
+use crate::SimplyClientError;
+use crate::report::OperationReport;
+use crate::time::Instant;
+
+/// A long-running operation that reports progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Operation {
+    /// [`SimplyClient::create_dns_records`](crate::SimplyClient::create_dns_records).
+    CreateRecords,
+    /// [`SimplyClient::delete_dns_records`](crate::SimplyClient::delete_dns_records).
+    DeleteRecords,
+    /// [`SimplyClient::apply_changes`](crate::SimplyClient::apply_changes).
+    ApplyChanges,
+    /// [`SimplyClient::apply`](crate::SimplyClient::apply).
+    Apply,
+    /// [`SimplyClient::sync`](crate::SimplyClient::sync).
+    Sync,
+    /// [`SimplyClient::lower_ttls`](crate::SimplyClient::lower_ttls).
+    LowerTtls,
+    /// [`SimplyClient::restore_ttls`](crate::SimplyClient::restore_ttls).
+    RestoreTtls,
+    /// [`SimplyClient::adopt`](crate::SimplyClient::adopt).
+    Adopt,
+    /// [`SimplyClient::cleanup_owned`](crate::SimplyClient::cleanup_owned).
+    CleanupOwned,
+    /// [`SimplyClient::cleanup_expired`](crate::SimplyClient::cleanup_expired).
+    CleanupExpired,
+    /// [`SimplyClient::delete_subtree`](crate::SimplyClient::delete_subtree).
+    DeleteSubtree,
+    /// [`SimplyClient::replay_changes`](crate::SimplyClient::replay_changes).
+    Replay,
+    /// [`SimplyClient::for_each_domain`](crate::SimplyClient::for_each_domain) and
+    /// [`SimplyClient::apply_across_domains`](crate::SimplyClient::apply_across_domains),
+    /// whose items are domains.
+    AcrossDomains,
+}
+
+/// Shows the operation in lower case words, e.g. "create records".
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Operation::CreateRecords => "create records",
+            Operation::DeleteRecords => "delete records",
+            Operation::ApplyChanges => "apply changes",
+            Operation::Apply => "apply",
+            Operation::Sync => "sync",
+            Operation::LowerTtls => "lower TTLs",
+            Operation::RestoreTtls => "restore TTLs",
+            Operation::Adopt => "adopt",
+            Operation::CleanupOwned => "clean up owned records",
+            Operation::CleanupExpired => "clean up expired records",
+            Operation::DeleteSubtree => "delete subtree",
+            Operation::Replay => "replay changes",
+            Operation::AcrossDomains => "across domains",
+        })
+    }
+}
+
+/// How a single item of an operation turned out.
+#[derive(Debug, Clone, Copy)]
+pub enum ItemOutcome<'a> {
+    /// The item was processed.
+    Succeeded,
+    /// Processing the item failed with this error.
+    Failed(&'a SimplyClientError),
+    /// The item was skipped for this reason.
+    Skipped(&'a str),
+}
+
+/// An event sent to a [`ProgressSink`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum ProgressEvent<'a> {
+    /// The operation started.
+    Started {
+        /// The operation.
+        operation: Operation,
+        /// How many items it will process, if known up front.
+        total: Option<usize>,
+    },
+    /// Work on an item started. Items skipped before any work are only
+    /// reported as done.
+    ItemStarted {
+        /// The operation.
+        operation: Operation,
+        /// How many items were started, including this one.
+        started: usize,
+        /// How many items the operation processes, if known.
+        total: Option<usize>,
+    },
+    /// An item is done.
+    Item {
+        /// The operation.
+        operation: Operation,
+        /// How many items are done, including this one.
+        done: usize,
+        /// How many items the operation processes, if known.
+        total: Option<usize>,
+        /// The item as it is displayed, e.g. a [`Change`](crate::sync::Change)
+        /// or a domain.
+        item: &'a str,
+        /// How the item turned out.
+        outcome: ItemOutcome<'a>,
+    },
+    /// The operation finished.
+    Finished {
+        /// The operation.
+        operation: Operation,
+        /// How many items succeeded.
+        succeeded: usize,
+        /// How many items failed.
+        failed: usize,
+        /// How many items were skipped.
+        skipped: usize,
+        /// How long the operation took.
+        elapsed: Duration,
+    },
+    /// A request will be sent again after `delay`, because it failed or was
+    /// throttled. Sent for any request of the client, including those of
+    /// operations running at the same time, so it names no operation.
+    Retrying {
+        /// How many times the request has been retried, including this time.
+        retry: u32,
+        /// How long the client waits before sending it again.
+        delay: Duration,
+        /// The response status or error of the last attempt.
+        reason: &'a str,
+    },
+}
+
+impl ProgressEvent<'_> {
+    /// How much of the operation is done, from 0 to 100, for
+    /// [`Item`](Self::Item) events of operations whose total is known.
+    pub fn percent(&self) -> Option<f64> {
+        match self {
+            ProgressEvent::Item {
+                done,
+                total: Some(total),
+                ..
+            } if *total > 0 => Some(*done as f64 * 100.0 / *total as f64),
+            _ => None,
+        }
+    }
+}
+
+/// Receives the progress of a client's long-running operations.
+///
+/// Methods are blocking and run on the operation's task, so they should
+/// return quickly. An operation that fails as a whole, e.g. because the zone
+/// can't be listed, ends without a [`ProgressEvent::Finished`].
+pub trait ProgressSink: Send + Sync {
+    /// Called for every event, in order.
+    fn on_event(&self, event: &ProgressEvent<'_>);
+}
+
+/// The sink registered with a client, if any.
+#[derive(Clone, Default)]
+pub(crate) struct SharedSink(pub(crate) Option<Arc<dyn ProgressSink>>);
+
+impl fmt::Debug for SharedSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = if self.0.is_some() { "set" } else { "unset" };
+        write!(f, "SharedSink({state})")
+    }
+}
+
+/// Reports the progress of one operation to an optional sink while filling
+/// its [`OperationReport`].
+pub(crate) struct Progress<'a> {
+    sink: Option<&'a dyn ProgressSink>,
+    operation: Operation,
+    total: Option<usize>,
+    started: Arc<AtomicUsize>,
+    done: usize,
+}
+
+impl<'a> Progress<'a> {
+    /// Start `operation` of `total` items, telling `sink`.
+    pub(crate) fn start(sink: &'a SharedSink, operation: Operation, total: Option<usize>) -> Self {
+        let progress = Self {
+            sink: sink.0.as_deref(),
+            operation,
+            total,
+            started: Arc::default(),
+            done: 0,
+        };
+        progress.emit(&ProgressEvent::Started { operation, total });
+        progress
+    }
+
+    /// Progress that isn't reported anywhere.
+    pub(crate) fn none(operation: Operation) -> Self {
+        Self {
+            sink: None,
+            operation,
+            total: None,
+            started: Arc::default(),
+            done: 0,
+        }
+    }
+
+    /// Report that work on the next item starts.
+    pub(crate) fn begin(&self) {
+        self.starter().begin();
+    }
+
+    /// A handle reporting item starts, for items started while others are
+    /// recorded.
+    pub(crate) fn starter(&self) -> ItemStarter<'a> {
+        ItemStarter {
+            sink: self.sink,
+            operation: self.operation,
+            total: self.total,
+            started: self.started.clone(),
+        }
+    }
+
+    /// Like [`OperationReport::record`], also reporting the item.
+    pub(crate) fn record<T: fmt::Display>(
+        &mut self,
+        report: &mut OperationReport<T>,
+        item: T,
+        result: Result<(), SimplyClientError>,
+        started: Instant,
+    ) {
+        let outcome = match &result {
+            Ok(()) => ItemOutcome::Succeeded,
+            Err(e) => ItemOutcome::Failed(e),
+        };
+        self.item(&item, outcome);
+        report.record(item, result, started);
+    }
+
+    /// Like [`OperationReport::skip`], also reporting the item.
+    pub(crate) fn skip<T: fmt::Display>(
+        &mut self,
+        report: &mut OperationReport<T>,
+        item: T,
+        reason: impl Into<String>,
+    ) {
+        let reason = reason.into();
+        self.item(&item, ItemOutcome::Skipped(&reason));
+        report.skip(item, reason);
+    }
+
+    /// Report the operation as finished with `report`.
+    pub(crate) fn finish<T>(&self, report: &OperationReport<T>) {
+        self.finish_with(
+            report.succeeded.len(),
+            report.failed.len(),
+            report.skipped.len(),
+            report.elapsed,
+        );
+    }
+
+    /// Report the operation as finished with these counts.
+    pub(crate) fn finish_with(
+        &self,
+        succeeded: usize,
+        failed: usize,
+        skipped: usize,
+        elapsed: Duration,
+    ) {
+        self.emit(&ProgressEvent::Finished {
+            operation: self.operation,
+            succeeded,
+            failed,
+            skipped,
+            elapsed,
+        });
+    }
+
+    /// Report an item as done, for operations without an [`OperationReport`].
+    pub(crate) fn item(&mut self, item: &dyn fmt::Display, outcome: ItemOutcome<'_>) {
+        self.done += 1;
+        if self.sink.is_none() {
+            return;
+        }
+        self.emit(&ProgressEvent::Item {
+            operation: self.operation,
+            done: self.done,
+            total: self.total,
+            item: &item.to_string(),
+            outcome,
+        });
+    }
+
+    fn emit(&self, event: &ProgressEvent<'_>) {
+        if let Some(sink) = self.sink {
+            sink.on_event(event);
+        }
+    }
+}
+
+/// Reports item starts of a [`Progress`], from [`Progress::starter`].
+pub(crate) struct ItemStarter<'a> {
+    sink: Option<&'a dyn ProgressSink>,
+    operation: Operation,
+    total: Option<usize>,
+    started: Arc<AtomicUsize>,
+}
+
+impl ItemStarter<'_> {
+    /// Report that work on the next item starts.
+    pub(crate) fn begin(&self) {
+        let started = self.started.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Some(sink) = self.sink {
+            sink.on_event(&ProgressEvent::ItemStarted {
+                operation: self.operation,
+                started,
+                total: self.total,
+            });
+        }
+    }
+}
+
+/// A [`ProgressEvent`] that owns its data, as yielded by a [`ProgressStream`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ProgressUpdate {
+    /// See [`ProgressEvent::Started`].
+    Started {
+        /// The operation.
+        operation: Operation,
+        /// How many items it will process, if known up front.
+        total: Option<usize>,
+    },
+    /// See [`ProgressEvent::ItemStarted`].
+    ItemStarted {
+        /// The operation.
+        operation: Operation,
+        /// How many items were started, including this one.
+        started: usize,
+        /// How many items the operation processes, if known.
+        total: Option<usize>,
+    },
+    /// An item was processed, see [`ProgressEvent::Item`].
+    Succeeded {
+        /// The operation.
+        operation: Operation,
+        /// How many items are done, including this one.
+        done: usize,
+        /// How many items the operation processes, if known.
+        total: Option<usize>,
+        /// The item, as it is displayed.
+        item: String,
+    },
+    /// Processing an item failed, see [`ProgressEvent::Item`].
+    Failed {
+        /// The operation.
+        operation: Operation,
+        /// How many items are done, including this one.
+        done: usize,
+        /// How many items the operation processes, if known.
+        total: Option<usize>,
+        /// The item, as it is displayed.
+        item: String,
+        /// The error, as text.
+        error: String,
+    },
+    /// An item was skipped, see [`ProgressEvent::Item`].
+    Skipped {
+        /// The operation.
+        operation: Operation,
+        /// How many items are done, including this one.
+        done: usize,
+        /// How many items the operation processes, if known.
+        total: Option<usize>,
+        /// The item, as it is displayed.
+        item: String,
+        /// Why it was skipped.
+        reason: String,
+    },
+    /// See [`ProgressEvent::Finished`].
+    Finished {
+        /// The operation.
+        operation: Operation,
+        /// How many items succeeded.
+        succeeded: usize,
+        /// How many items failed.
+        failed: usize,
+        /// How many items were skipped.
+        skipped: usize,
+        /// How long the operation took.
+        elapsed: Duration,
+    },
+    /// See [`ProgressEvent::Retrying`].
+    Retrying {
+        /// How many times the request has been retried, including this time.
+        retry: u32,
+        /// How long the client waits before sending it again.
+        delay: Duration,
+        /// The response status or error of the last attempt.
+        reason: String,
+    },
+}
+
+impl ProgressUpdate {
+    /// How much of the operation is done, from 0 to 100, for finished items
+    /// of operations whose total is known.
+    pub fn percent(&self) -> Option<f64> {
+        match self {
+            ProgressUpdate::Succeeded { done, total, .. }
+            | ProgressUpdate::Failed { done, total, .. }
+            | ProgressUpdate::Skipped { done, total, .. } => match total {
+                Some(total) if *total > 0 => Some(*done as f64 * 100.0 / *total as f64),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl From<&ProgressEvent<'_>> for ProgressUpdate {
+    fn from(event: &ProgressEvent<'_>) -> Self {
+        match *event {
+            ProgressEvent::Started { operation, total } => {
+                ProgressUpdate::Started { operation, total }
+            }
+            ProgressEvent::ItemStarted {
+                operation,
+                started,
+                total,
+            } => ProgressUpdate::ItemStarted {
+                operation,
+                started,
+                total,
+            },
+            ProgressEvent::Item {
+                operation,
+                done,
+                total,
+                item,
+                outcome,
+            } => {
+                let item = item.to_string();
+                match outcome {
+                    ItemOutcome::Succeeded => ProgressUpdate::Succeeded {
+                        operation,
+                        done,
+                        total,
+                        item,
+                    },
+                    ItemOutcome::Failed(e) => ProgressUpdate::Failed {
+                        operation,
+                        done,
+                        total,
+                        item,
+                        error: e.to_string(),
+                    },
+                    ItemOutcome::Skipped(reason) => ProgressUpdate::Skipped {
+                        operation,
+                        done,
+                        total,
+                        item,
+                        reason: reason.to_string(),
+                    },
+                }
+            }
+            ProgressEvent::Finished {
+                operation,
+                succeeded,
+                failed,
+                skipped,
+                elapsed,
+            } => ProgressUpdate::Finished {
+                operation,
+                succeeded,
+                failed,
+                skipped,
+                elapsed,
+            },
+            ProgressEvent::Retrying {
+                retry,
+                delay,
+                reason,
+            } => ProgressUpdate::Retrying {
+                retry,
+                delay,
+                reason: reason.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+struct Channel {
+    queue: VecDeque<ProgressUpdate>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+fn lock(channel: &Mutex<Channel>) -> std::sync::MutexGuard<'_, Channel> {
+    channel.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A stream of progress updates and the sink feeding it.
+///
+/// Register the [`ProgressSender`] with
+/// [`SimplyClient::with_progress_sink`](crate::SimplyClient::with_progress_sink)
+/// and poll the [`ProgressStream`], e.g. on a task rendering a progress bar.
+/// Updates are buffered until they are polled. The stream ends once the
+/// sender is dropped, i.e. with the last clone of the client it was
+/// registered with, so register it with a client used only for the
+/// operations to follow.
+///
+/// Example usage:
+/// ```rust,no_run
+/// use futures_util::StreamExt;
+/// use simply_dns::SimplyClient;
+/// use simply_dns::progress::{ProgressUpdate, progress_stream};
+/// use simply_dns::sync::ZonePlan;
+///
+/// # async fn run(plan: ZonePlan) {
+/// let client = SimplyClient::new("account", "api_key");
+/// let (sender, mut updates) = progress_stream();
+/// let render = async move {
+///     while let Some(update) = updates.next().await {
+///         match update {
+///             ProgressUpdate::Failed { error, .. } => eprintln!("\nfailed: {error}"),
+///             update => {
+///                 if let Some(percent) = update.percent() {
+///                     eprint!("\r{percent:.0}%");
+///                 }
+///             }
+///         }
+///     }
+/// };
+/// let apply = async move {
+///     let client = client.with_progress_sink(sender);
+///     client.apply(plan).await
+/// };
+/// let (report, ()) = futures_util::future::join(apply, render).await;
+/// println!("{} changes failed", report.failed.len());
+/// # }
+/// ```
+pub fn progress_stream() -> (ProgressSender, ProgressStream) {
+    let channel = Arc::new(Mutex::new(Channel::default()));
+    (
+        ProgressSender {
+            channel: channel.clone(),
+        },
+        ProgressStream { channel },
+    )
+}
+
+/// The [`ProgressSink`] of a [`progress_stream`].
+pub struct ProgressSender {
+    channel: Arc<Mutex<Channel>>,
+}
+
+impl fmt::Debug for ProgressSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressSender").finish_non_exhaustive()
+    }
+}
+
+impl ProgressSink for ProgressSender {
+    fn on_event(&self, event: &ProgressEvent<'_>) {
+        let mut channel = lock(&self.channel);
+        channel.queue.push_back(event.into());
+        if let Some(waker) = channel.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for ProgressSender {
+    fn drop(&mut self) {
+        let mut channel = lock(&self.channel);
+        channel.closed = true;
+        if let Some(waker) = channel.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// The [`Stream`] of a [`progress_stream`].
+pub struct ProgressStream {
+    channel: Arc<Mutex<Channel>>,
+}
+
+impl fmt::Debug for ProgressStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressStream")
+            .field("buffered", &lock(&self.channel).queue.len())
+            .finish()
+    }
+}
+
+impl Stream for ProgressStream {
+    type Item = ProgressUpdate;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ProgressUpdate>> {
+        let mut channel = lock(&self.channel);
+        if let Some(update) = channel.queue.pop_front() {
+            Poll::Ready(Some(update))
+        } else if channel.closed {
+            Poll::Ready(None)
+        } else {
+            channel.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
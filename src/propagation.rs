@@ -0,0 +1,843 @@
+//! Checking whether DNS changes have propagated.
+//!
+//! [`PropagationChecker`] queries the zone's authoritative name servers and a set
+//! of public resolvers until a record resolves to the expected value, e.g. before
+//! telling an ACME server that a challenge is ready.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use simply_dns::propagation;
+//!
+//! # async fn run() -> Result<(), simply_dns::propagation::PropagationError> {
+//! propagation::wait_for_record(
+//!     "example.com",
+//!     "www",
+//!     "A",
+//!     "192.0.2.10",
+//!     Duration::from_secs(300),
+//! )
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+
+use crate::retry::random_u64;
+
+/// Error type for propagation checks.
+#[derive(Debug, Error)]
+pub enum PropagationError {
+    /// The record didn't resolve to the expected value on all servers in time.
+    #[error("{record} has not propagated to {pending:?}")]
+    Timeout {
+        /// The record that was waited for, e.g. "www.example.com A".
+        record: String,
+        /// The servers that still didn't return the expected value.
+        pending: Vec<SocketAddr>,
+    },
+    /// The record type can't be queried by the checker.
+    #[error("unsupported record type for propagation checks: {0}")]
+    UnsupportedType(String),
+    /// A query failed or returned an unusable response.
+    #[error("DNS query to {server} failed: {message}")]
+    Query {
+        /// The server that was queried.
+        server: SocketAddr,
+        /// What went wrong.
+        message: String,
+    },
+    /// A DNS-over-HTTPS client could not be set up.
+    #[error("DNS-over-HTTPS error: {0}")]
+    Doh(String),
+    /// The authoritative name servers of the zone could not be determined.
+    #[error("could not find the name servers of {zone}: {message}")]
+    NameServers {
+        /// The zone whose name servers were looked up.
+        zone: String,
+        /// What went wrong.
+        message: String,
+    },
+}
+
+/// Polls DNS servers until a record resolves to an expected value.
+///
+/// By default the zone's authoritative name servers plus Google (8.8.8.8) and
+/// Cloudflare (1.1.1.1) are queried every 5 seconds.
+#[derive(Debug, Clone)]
+pub struct PropagationChecker {
+    resolvers: Vec<SocketAddr>,
+    authoritative: bool,
+    poll_interval: Duration,
+    query_timeout: Duration,
+}
+
+impl Default for PropagationChecker {
+    fn default() -> Self {
+        Self {
+            resolvers: default_resolvers(),
+            authoritative: true,
+            poll_interval: Duration::from_secs(5),
+            query_timeout: Duration::from_secs(3),
+        }
+    }
+}
+
+impl PropagationChecker {
+    /// A checker with the default resolvers and intervals.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the public resolvers that are queried.
+    ///
+    /// The first resolver is also used to look up the zone's name servers.
+    pub fn resolvers(mut self, resolvers: Vec<SocketAddr>) -> Self {
+        self.resolvers = resolvers;
+        self
+    }
+
+    /// Whether to also query the zone's authoritative name servers. Defaults to `true`.
+    pub fn authoritative(mut self, enabled: bool) -> Self {
+        self.authoritative = enabled;
+        self
+    }
+
+    /// How long to wait between polls. Defaults to 5 seconds.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// How long to wait for a single server to answer. Defaults to 3 seconds.
+    pub fn query_timeout(mut self, timeout: Duration) -> Self {
+        self.query_timeout = timeout;
+        self
+    }
+
+    /// Wait until the record `name` of type `record_type` resolves to `expected_data` everywhere.
+    ///
+    /// A server counts as done once the expected value is among its answers, so
+    /// other records with the same name (e.g. a second TXT value) don't matter.
+    ///
+    /// # Arguments
+    /// * `domain` - The zone the record belongs to.
+    /// * `name` - The record name relative to the zone, e.g. "www", or "@" for the apex.
+    /// * `record_type` - Type of DNS record ("A", "TXT", etc.).
+    /// * `expected_data` - The record data, in the format used by the API.
+    /// * `timeout` - How long to wait before giving up.
+    pub async fn wait_for_record(
+        &self,
+        domain: &str,
+        name: &str,
+        record_type: &str,
+        expected_data: &str,
+        timeout: Duration,
+    ) -> Result<(), PropagationError> {
+        let qtype = type_code(record_type)
+            .ok_or_else(|| PropagationError::UnsupportedType(record_type.to_string()))?;
+        let fqdn = fqdn(domain, name);
+        let expected = normalize(qtype, expected_data);
+        let deadline = Instant::now() + timeout;
+
+        let mut pending = self.resolvers.clone();
+        if self.authoritative {
+            pending.extend(self.name_servers(domain).await?);
+        }
+        pending.sort();
+        pending.dedup();
+
+        loop {
+            let mut still_pending = Vec::new();
+            for server in pending {
+                let live = self
+                    .lookup(server, &fqdn, record_type)
+                    .await
+                    .is_ok_and(|answers| answers.iter().any(|a| normalize(qtype, a) == expected));
+                if !live {
+                    still_pending.push(server);
+                }
+            }
+            pending = still_pending;
+            if pending.is_empty() {
+                return Ok(());
+            }
+            if Instant::now() + self.poll_interval > deadline {
+                return Err(PropagationError::Timeout {
+                    record: format!("{fqdn} {record_type}"),
+                    pending,
+                });
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Query `server` for records of `record_type` at `fqdn`.
+    ///
+    /// Returns the record data in the format used by the API, e.g. the exchange
+    /// of an MX record without its priority. A name that doesn't exist yields no answers.
+    pub async fn lookup(
+        &self,
+        server: SocketAddr,
+        fqdn: &str,
+        record_type: &str,
+    ) -> Result<Vec<String>, PropagationError> {
+        let qtype = type_code(record_type)
+            .ok_or_else(|| PropagationError::UnsupportedType(record_type.to_string()))?;
+        let err = |message: String| PropagationError::Query { server, message };
+        let id = random_u64() as u16;
+        let socket = match server.ip() {
+            IpAddr::V4(_) => UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await,
+            IpAddr::V6(_) => UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0)).await,
+        }
+        .map_err(|e| err(e.to_string()))?;
+        socket
+            .send_to(&encode_query(id, fqdn, qtype), server)
+            .await
+            .map_err(|e| err(e.to_string()))?;
+        let mut buf = [0u8; 4096];
+        let len = tokio::time::timeout(self.query_timeout, socket.recv(&mut buf))
+            .await
+            .map_err(|_| err("timed out".to_string()))?
+            .map_err(|e: io::Error| err(e.to_string()))?;
+        decode_answers(&buf[..len], id, qtype).map_err(err)
+    }
+
+    /// The addresses of `zone`'s authoritative name servers, looked up
+    /// through the first resolver.
+    pub(crate) async fn name_servers(
+        &self,
+        zone: &str,
+    ) -> Result<Vec<SocketAddr>, PropagationError> {
+        let ns_err = |message: String| PropagationError::NameServers {
+            zone: zone.to_string(),
+            message,
+        };
+        let resolver = *self
+            .resolvers
+            .first()
+            .ok_or_else(|| ns_err("no resolvers configured".to_string()))?;
+        let names = self
+            .lookup(resolver, zone.trim_end_matches('.'), "NS")
+            .await
+            .map_err(|e| ns_err(e.to_string()))?;
+        let mut servers = Vec::new();
+        for name in names {
+            if let Ok(addrs) = tokio::net::lookup_host((name.as_str(), 53)).await {
+                servers.extend(addrs);
+            }
+        }
+        if servers.is_empty() {
+            return Err(ns_err("no name server addresses found".to_string()));
+        }
+        Ok(servers)
+    }
+    /// Resolve a record through DNS-over-HTTPS in several `regions` and
+    /// report the answers of each, e.g. to spot regional caches still serving
+    /// old data after a cutover.
+    ///
+    /// Unlike [`wait_for_record`](Self::wait_for_record) this queries once and
+    /// doesn't compare with an expected value; see
+    /// [`RegionalReport::stale`] for that. Regions that fail are reported with
+    /// their error rather than failing the check.
+    ///
+    /// # Arguments
+    /// * `domain` - The zone the record belongs to.
+    /// * `name` - The record name relative to the zone, e.g. "www", or "@" for the apex.
+    /// * `record_type` - Type of DNS record ("A", "TXT", etc.).
+    /// * `regions` - The DoH endpoints to ask, e.g. [`DohRegion::public`].
+    pub async fn check_regions(
+        &self,
+        domain: &str,
+        name: &str,
+        record_type: &str,
+        regions: &[DohRegion],
+    ) -> Result<RegionalReport, PropagationError> {
+        let qtype = type_code(record_type)
+            .ok_or_else(|| PropagationError::UnsupportedType(record_type.to_string()))?;
+        let fqdn = fqdn(domain, name);
+        let http = reqwest::Client::builder()
+            .timeout(self.query_timeout)
+            .build()
+            .map_err(|e| PropagationError::Doh(e.to_string()))?;
+        let queries = regions.iter().map(|region| {
+            let (http, fqdn) = (&http, &fqdn);
+            async move {
+                // RFC 8484 recommends ID 0, so responses can be cached.
+                let mut query = encode_query(0, fqdn, qtype);
+                if let Some((subnet, prefix)) = region.client_subnet {
+                    add_client_subnet(&mut query, subnet, prefix);
+                }
+                let result = async {
+                    let res = http
+                        .post(&region.url)
+                        .header("content-type", "application/dns-message")
+                        .header("accept", "application/dns-message")
+                        .body(query)
+                        .send()
+                        .await
+                        .and_then(|res| res.error_for_status())
+                        .map_err(|e| e.to_string())?;
+                    let body = res.bytes().await.map_err(|e| e.to_string())?;
+                    decode_answers(&body, 0, qtype)
+                }
+                .await;
+                RegionalAnswer {
+                    region: region.label.clone(),
+                    result: result.map(|mut answers| {
+                        answers.sort();
+                        answers
+                    }),
+                }
+            }
+        });
+        Ok(RegionalReport {
+            record: format!("{fqdn} {record_type}"),
+            qtype,
+            answers: futures_util::future::join_all(queries).await,
+        })
+    }
+}
+
+/// Wait for a record with the default [`PropagationChecker`].
+///
+/// See [`PropagationChecker::wait_for_record`].
+pub async fn wait_for_record(
+    domain: &str,
+    name: &str,
+    record_type: &str,
+    expected_data: &str,
+    timeout: Duration,
+) -> Result<(), PropagationError> {
+    PropagationChecker::default()
+        .wait_for_record(domain, name, record_type, expected_data, timeout)
+        .await
+}
+
+/// A DNS-over-HTTPS endpoint (RFC 8484) to resolve through, for
+/// [`PropagationChecker::check_regions`].
+///
+/// Public resolvers are anycast, so each answers from its site closest to the
+/// checker. To see what another region sees, pass a
+/// [client subnet](Self::client_subnet) from that region to a resolver that
+/// honors it, such as Google's; Cloudflare ignores it for privacy.
+///
+/// Example usage:
+/// ```rust,no_run
+/// use simply_dns::propagation::{DohRegion, PropagationChecker};
+///
+/// # async fn run() -> Result<(), simply_dns::propagation::PropagationError> {
+/// let mut regions = DohRegion::public();
+/// regions.push(DohRegion::google("eu").client_subnet("192.0.2.0".parse().unwrap(), 24));
+/// let report = PropagationChecker::new()
+///     .check_regions("example.com", "www", "A", &regions)
+///     .await?;
+/// println!("stale in {:?}", report.stale("192.0.2.10"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DohRegion {
+    /// A name for the region in reports, e.g. "eu-west".
+    pub label: String,
+    /// The DoH endpoint, e.g. "https://dns.google/dns-query".
+    pub url: String,
+    /// The client subnet (EDNS Client Subnet, RFC 7871) to present, if any.
+    pub client_subnet: Option<(IpAddr, u8)>,
+}
+
+impl DohRegion {
+    /// An endpoint at `url`, reported as `label`.
+    pub fn new(label: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            url: url.into(),
+            client_subnet: None,
+        }
+    }
+
+    /// Google Public DNS, which honors client subnets.
+    pub fn google(label: impl Into<String>) -> Self {
+        Self::new(label, "https://dns.google/dns-query")
+    }
+
+    /// Cloudflare's 1.1.1.1.
+    pub fn cloudflare(label: impl Into<String>) -> Self {
+        Self::new(label, "https://cloudflare-dns.com/dns-query")
+    }
+
+    /// Quad9.
+    pub fn quad9(label: impl Into<String>) -> Self {
+        Self::new(label, "https://dns.quad9.net/dns-query")
+    }
+
+    /// Google, Cloudflare and Quad9, each as seen from the checker.
+    pub fn public() -> Vec<Self> {
+        vec![
+            Self::google("google"),
+            Self::cloudflare("cloudflare"),
+            Self::quad9("quad9"),
+        ]
+    }
+
+    /// Ask as if from `address/prefix`, so the resolver answers like it would
+    /// for clients in that network.
+    pub fn client_subnet(mut self, address: IpAddr, prefix: u8) -> Self {
+        self.client_subnet = Some((address, prefix));
+        self
+    }
+}
+
+/// The answers of each region, from [`PropagationChecker::check_regions`].
+#[derive(Debug, Clone)]
+pub struct RegionalReport {
+    /// The record that was resolved, e.g. "www.example.com A".
+    pub record: String,
+    qtype: u16,
+    /// The answers per region, in the order the regions were given.
+    pub answers: Vec<RegionalAnswer>,
+}
+
+/// What one region answered.
+#[derive(Debug, Clone)]
+pub struct RegionalAnswer {
+    /// The region's label.
+    pub region: String,
+    /// The record data, sorted, or why the query failed.
+    pub result: Result<Vec<String>, String>,
+}
+
+impl RegionalReport {
+    /// Whether every region answered, and all with the same data.
+    pub fn is_consistent(&self) -> bool {
+        let mut results = self.answers.iter().map(|a| a.result.as_ref().ok());
+        let first = results.next().flatten();
+        first.is_some() && results.all(|r| r == first)
+    }
+
+    /// The regions that failed or don't return `expected_data` yet.
+    pub fn stale(&self, expected_data: &str) -> Vec<&str> {
+        let expected = normalize(self.qtype, expected_data);
+        self.answers
+            .iter()
+            .filter(|a| {
+                !a.result
+                    .as_ref()
+                    .is_ok_and(|data| data.iter().any(|d| normalize(self.qtype, d) == expected))
+            })
+            .map(|a| a.region.as_str())
+            .collect()
+    }
+}
+
+/// Shows a line per region.
+impl std::fmt::Display for RegionalReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for answer in &self.answers {
+            match &answer.result {
+                Ok(data) if data.is_empty() => writeln!(f, "{}: no records", answer.region)?,
+                Ok(data) => writeln!(f, "{}: {}", answer.region, data.join(", "))?,
+                Err(e) => writeln!(f, "{}: error: {e}", answer.region)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Google (8.8.8.8) and Cloudflare (1.1.1.1).
+pub(crate) fn default_resolvers() -> Vec<SocketAddr> {
+    vec![
+        (Ipv4Addr::new(8, 8, 8, 8), 53).into(),
+        (Ipv4Addr::new(1, 1, 1, 1), 53).into(),
+    ]
+}
+
+const TYPE_A: u16 = 1;
+const TYPE_NS: u16 = 2;
+const TYPE_CNAME: u16 = 5;
+const TYPE_PTR: u16 = 12;
+const TYPE_MX: u16 = 15;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+const TYPE_SRV: u16 = 33;
+const TYPE_CAA: u16 = 257;
+
+pub(crate) fn type_code(record_type: &str) -> Option<u16> {
+    Some(match record_type.to_ascii_uppercase().as_str() {
+        "A" => TYPE_A,
+        "NS" => TYPE_NS,
+        "CNAME" => TYPE_CNAME,
+        "PTR" => TYPE_PTR,
+        "MX" => TYPE_MX,
+        "TXT" => TYPE_TXT,
+        "AAAA" => TYPE_AAAA,
+        "SRV" => TYPE_SRV,
+        "CAA" => TYPE_CAA,
+        _ => return None,
+    })
+}
+
+pub(crate) fn fqdn(domain: &str, name: &str) -> String {
+    let domain = domain.trim_end_matches('.');
+    match name.trim_end_matches('.') {
+        "" | "@" => domain.to_string(),
+        name if name.eq_ignore_ascii_case(domain) => domain.to_string(),
+        name => format!("{name}.{domain}"),
+    }
+}
+
+/// Normalize record data for comparison; host names are case-insensitive.
+pub(crate) fn normalize(qtype: u16, data: &str) -> String {
+    match qtype {
+        TYPE_TXT | TYPE_CAA => data.to_string(),
+        _ => data.trim().trim_end_matches('.').to_ascii_lowercase(),
+    }
+}
+
+fn encode_query(id: u16, fqdn: &str, qtype: u16) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(fqdn.len() + 18);
+    msg.extend_from_slice(&id.to_be_bytes());
+    // Recursion desired, one question.
+    msg.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in fqdn
+        .trim_end_matches('.')
+        .split('.')
+        .filter(|l| !l.is_empty())
+    {
+        msg.push(label.len().min(63) as u8);
+        msg.extend_from_slice(&label.as_bytes()[..label.len().min(63)]);
+    }
+    msg.push(0);
+    msg.extend_from_slice(&qtype.to_be_bytes());
+    msg.extend_from_slice(&1u16.to_be_bytes());
+    msg
+}
+
+/// Add an EDNS OPT record with a client subnet option (RFC 7871) to `query`.
+fn add_client_subnet(query: &mut Vec<u8>, address: IpAddr, prefix: u8) {
+    let (family, bytes): (u16, Vec<u8>) = match address {
+        IpAddr::V4(v4) => (1, v4.octets().to_vec()),
+        IpAddr::V6(v6) => (2, v6.octets().to_vec()),
+    };
+    let prefix = prefix.min(bytes.len() as u8 * 8);
+    let mut address = bytes[..usize::from(prefix).div_ceil(8)].to_vec();
+    // Bits past the prefix must be zero.
+    if let Some(last) = address.last_mut()
+        && !prefix.is_multiple_of(8)
+    {
+        *last &= 0xff << (8 - prefix % 8);
+    }
+    let option_len = 4 + address.len() as u16;
+    // One additional record.
+    query[11] = 1;
+    // Root name, type OPT, 4096 byte UDP payload, no extended flags.
+    query.extend_from_slice(&[0, 0, 41, 0x10, 0, 0, 0, 0, 0]);
+    query.extend_from_slice(&(4 + option_len).to_be_bytes());
+    query.extend_from_slice(&8u16.to_be_bytes());
+    query.extend_from_slice(&option_len.to_be_bytes());
+    query.extend_from_slice(&family.to_be_bytes());
+    query.extend_from_slice(&[prefix, 0]);
+    query.extend_from_slice(&address);
+}
+
+fn decode_answers(msg: &[u8], id: u16, qtype: u16) -> Result<Vec<String>, String> {
+    let truncated = || "truncated response".to_string();
+    let u16_at = |pos: usize| -> Result<u16, String> {
+        msg.get(pos..pos + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(truncated)
+    };
+    if u16_at(0)? != id {
+        return Err("response ID doesn't match the query".to_string());
+    }
+    match u16_at(2)? & 0x000f {
+        0 => {}
+        3 => return Ok(Vec::new()),
+        rcode => return Err(format!("server returned rcode {rcode}")),
+    }
+    let questions = u16_at(4)?;
+    let answers = u16_at(6)?;
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = read_name(msg, pos).ok_or_else(truncated)?.1 + 4;
+    }
+    let mut out = Vec::new();
+    for _ in 0..answers {
+        pos = read_name(msg, pos).ok_or_else(truncated)?.1;
+        let rtype = u16_at(pos)?;
+        let rdlen = u16_at(pos + 8)? as usize;
+        let start = pos + 10;
+        let rdata = msg.get(start..start + rdlen).ok_or_else(truncated)?;
+        pos = start + rdlen;
+        if rtype != qtype {
+            continue;
+        }
+        // Names are read from the whole message, for compression, but must
+        // start within the record's data.
+        let name_at = |offset: usize| {
+            (offset < rdlen)
+                .then(|| read_name(msg, start + offset))
+                .flatten()
+                .map(|(name, _)| name)
+        };
+        let data = match rtype {
+            TYPE_A => <[u8; 4]>::try_from(rdata)
+                .ok()
+                .map(|b| Ipv4Addr::from(b).to_string()),
+            TYPE_AAAA => <[u8; 16]>::try_from(rdata)
+                .ok()
+                .map(|b| Ipv6Addr::from(b).to_string()),
+            TYPE_NS | TYPE_CNAME | TYPE_PTR => name_at(0),
+            TYPE_MX => name_at(2),
+            TYPE_SRV if rdlen >= 6 => name_at(6).map(|target| {
+                let weight = u16::from_be_bytes([rdata[2], rdata[3]]);
+                let port = u16::from_be_bytes([rdata[4], rdata[5]]);
+                format!("{weight} {port} {target}")
+            }),
+            TYPE_TXT => Some(read_strings(rdata)),
+            TYPE_CAA if rdlen >= 2 => {
+                let tag_len = rdata[1] as usize;
+                rdata.get(2..2 + tag_len).map(|tag| {
+                    format!(
+                        "{} {} \"{}\"",
+                        rdata[0],
+                        String::from_utf8_lossy(tag),
+                        String::from_utf8_lossy(&rdata[2 + tag_len..])
+                    )
+                })
+            }
+            _ => None,
+        };
+        out.extend(data);
+    }
+    Ok(out)
+}
+
+/// Read a possibly compressed name at `pos`, returning it and the position after it.
+fn read_name(msg: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut end = None;
+    // Bound the number of pointers followed, so a malicious loop can't hang us.
+    for _ in 0..128 {
+        let len = *msg.get(pos)? as usize;
+        match len {
+            0 => {
+                return Some((labels.join("."), end.unwrap_or(pos + 1)));
+            }
+            l if l & 0xc0 == 0xc0 => {
+                let target = ((l & 0x3f) << 8) | *msg.get(pos + 1)? as usize;
+                end.get_or_insert(pos + 2);
+                pos = target;
+            }
+            l => {
+                let label = msg.get(pos + 1..pos + 1 + l)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                pos += 1 + l;
+            }
+        }
+    }
+    None
+}
+
+/// Concatenate the character strings of TXT record data.
+fn read_strings(mut rdata: &[u8]) -> String {
+    let mut out = String::new();
+    while let Some((&len, rest)) = rdata.split_first() {
+        let len = (len as usize).min(rest.len());
+        out.push_str(&String::from_utf8_lossy(&rest[..len]));
+        rdata = &rest[len..];
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A response to `encode_query(7, "example.com", qtype)` with `answers`,
+    /// each given as its type and RDATA. Answer names point at the question.
+    fn response(qtype: u16, answers: &[(u16, &[u8])]) -> Vec<u8> {
+        let mut msg = encode_query(7, "example.com", qtype);
+        // Response, recursion available, no error.
+        msg[2..4].copy_from_slice(&[0x81, 0x80]);
+        msg[6..8].copy_from_slice(&(answers.len() as u16).to_be_bytes());
+        for (rtype, rdata) in answers {
+            msg.extend_from_slice(&[0xc0, 12]);
+            msg.extend_from_slice(&rtype.to_be_bytes());
+            msg.extend_from_slice(&[0, 1, 0, 0, 0x0e, 0x10]);
+            msg.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            msg.extend_from_slice(rdata);
+        }
+        msg
+    }
+
+    #[test]
+    fn encodes_the_question() {
+        let query = encode_query(0x1234, "www.example.com.", TYPE_AAAA);
+        assert_eq!(&query[..4], &[0x12, 0x34, 0x01, 0x00]);
+        assert_eq!(&query[4..6], &[0, 1]);
+        assert_eq!(&query[12..29], b"\x03www\x07example\x03com\x00");
+        assert_eq!(&query[29..], &[0, 28, 0, 1]);
+    }
+
+    #[test]
+    fn decodes_addresses() {
+        let msg = response(
+            TYPE_A,
+            &[(TYPE_A, &[192, 0, 2, 1]), (TYPE_A, &[192, 0, 2, 2])],
+        );
+        assert_eq!(
+            decode_answers(&msg, 7, TYPE_A).unwrap(),
+            ["192.0.2.1", "192.0.2.2"]
+        );
+        let mut v6 = [0; 16];
+        v6[..4].copy_from_slice(&[0x20, 0x01, 0x0d, 0xb8]);
+        v6[15] = 1;
+        let msg = response(TYPE_AAAA, &[(TYPE_AAAA, &v6)]);
+        assert_eq!(decode_answers(&msg, 7, TYPE_AAAA).unwrap(), ["2001:db8::1"]);
+    }
+
+    #[test]
+    fn skips_answers_of_other_types() {
+        // A CNAME to www.example.com, compressed against the question, before the address.
+        let cname = [3, b'w', b'w', b'w', 0xc0, 12];
+        let msg = response(TYPE_A, &[(TYPE_CNAME, &cname), (TYPE_A, &[192, 0, 2, 1])]);
+        assert_eq!(decode_answers(&msg, 7, TYPE_A).unwrap(), ["192.0.2.1"]);
+        let msg = response(TYPE_CNAME, &[(TYPE_CNAME, &cname)]);
+        assert_eq!(
+            decode_answers(&msg, 7, TYPE_CNAME).unwrap(),
+            ["www.example.com"]
+        );
+    }
+
+    #[test]
+    fn decodes_mx_srv_txt_and_caa() {
+        let mx = [0, 10, 4, b'm', b'a', b'i', b'l', 0xc0, 12];
+        let msg = response(TYPE_MX, &[(TYPE_MX, &mx)]);
+        assert_eq!(
+            decode_answers(&msg, 7, TYPE_MX).unwrap(),
+            ["mail.example.com"]
+        );
+
+        let srv = [0, 10, 0, 5, 0x13, 0xc4, 3, b's', b'i', b'p', 0xc0, 12];
+        let msg = response(TYPE_SRV, &[(TYPE_SRV, &srv)]);
+        assert_eq!(
+            decode_answers(&msg, 7, TYPE_SRV).unwrap(),
+            ["5 5060 sip.example.com"]
+        );
+
+        let txt = b"\x07v=spf1 \x04-all\x00";
+        let msg = response(TYPE_TXT, &[(TYPE_TXT, txt)]);
+        assert_eq!(decode_answers(&msg, 7, TYPE_TXT).unwrap(), ["v=spf1 -all"]);
+
+        let caa = b"\x00\x05issueletsencrypt.org";
+        let msg = response(TYPE_CAA, &[(TYPE_CAA, caa)]);
+        assert_eq!(
+            decode_answers(&msg, 7, TYPE_CAA).unwrap(),
+            [r#"0 issue "letsencrypt.org""#]
+        );
+    }
+
+    #[test]
+    fn ignores_malformed_rdata() {
+        // Too short for their types, but within the message.
+        let msg = response(
+            TYPE_SRV,
+            &[(TYPE_SRV, &[0, 10, 0]), (TYPE_SRV, &[0, 1, 0, 1, 0, 80])],
+        );
+        assert!(decode_answers(&msg, 7, TYPE_SRV).unwrap().is_empty());
+        // The name of the second record isn't part of the first.
+        let mx = [0, 10, 4, b'm', b'a', b'i', b'l', 0xc0, 12];
+        let msg = response(TYPE_MX, &[(TYPE_MX, &[0, 10]), (TYPE_MX, &mx)]);
+        assert_eq!(
+            decode_answers(&msg, 7, TYPE_MX).unwrap(),
+            ["mail.example.com"]
+        );
+        let msg = response(TYPE_A, &[(TYPE_A, &[192, 0, 2])]);
+        assert!(decode_answers(&msg, 7, TYPE_A).unwrap().is_empty());
+        let msg = response(TYPE_CAA, &[(TYPE_CAA, b"\x00\x09iss")]);
+        assert!(decode_answers(&msg, 7, TYPE_CAA).unwrap().is_empty());
+        // A string longer than the data is cut short rather than rejected.
+        let msg = response(TYPE_TXT, &[(TYPE_TXT, b"\x09short")]);
+        assert_eq!(decode_answers(&msg, 7, TYPE_TXT).unwrap(), ["short"]);
+    }
+
+    #[test]
+    fn rejects_truncated_responses() {
+        let msg = response(TYPE_A, &[(TYPE_A, &[192, 0, 2, 1])]);
+        for len in [0, 5, 12, 20, msg.len() - 1] {
+            assert_eq!(
+                decode_answers(&msg[..len], 7, TYPE_A).unwrap_err(),
+                "truncated response"
+            );
+        }
+        // RDATA running past the end of the message.
+        let mut msg = msg;
+        let rdlen = msg.len() - 6;
+        msg[rdlen..rdlen + 2].copy_from_slice(&[0, 40]);
+        assert_eq!(
+            decode_answers(&msg, 7, TYPE_A).unwrap_err(),
+            "truncated response"
+        );
+    }
+
+    #[test]
+    fn rejects_mismatched_ids_and_errors() {
+        let msg = response(TYPE_A, &[(TYPE_A, &[192, 0, 2, 1])]);
+        assert_eq!(
+            decode_answers(&msg, 8, TYPE_A).unwrap_err(),
+            "response ID doesn't match the query"
+        );
+        let mut nxdomain = msg.clone();
+        nxdomain[3] |= 3;
+        assert!(decode_answers(&nxdomain, 7, TYPE_A).unwrap().is_empty());
+        let mut servfail = msg;
+        servfail[3] |= 2;
+        assert_eq!(
+            decode_answers(&servfail, 7, TYPE_A).unwrap_err(),
+            "server returned rcode 2"
+        );
+    }
+
+    #[test]
+    fn reads_compressed_names() {
+        let msg = b"\x07example\x03com\x00\x03www\xc0\x00\x04mail\xc0\x0d";
+        assert_eq!(read_name(msg, 0), Some(("example.com".to_string(), 13)));
+        assert_eq!(
+            read_name(msg, 13),
+            Some(("www.example.com".to_string(), 19))
+        );
+        // A pointer to a compressed name.
+        assert_eq!(
+            read_name(msg, 19),
+            Some(("mail.www.example.com".to_string(), 26))
+        );
+    }
+
+    #[test]
+    fn rejects_pointer_loops_and_truncated_names() {
+        // A pointer to itself, and two pointing at each other.
+        assert_eq!(read_name(b"\xc0\x00", 0), None);
+        assert_eq!(read_name(b"\x01a\xc0\x04\x01b\xc0\x00", 0), None);
+        assert_eq!(read_name(b"\x07exam", 0), None);
+        assert_eq!(read_name(b"\x03www", 0), None);
+        assert_eq!(read_name(b"\xc0", 0), None);
+        assert_eq!(read_name(b"\xc0\x10", 0), None);
+        let mut msg = response(TYPE_A, &[(TYPE_A, &[192, 0, 2, 1])]);
+        // Point the answer's name at itself.
+        let answer = encode_query(7, "example.com", TYPE_A).len();
+        msg[answer + 1] = answer as u8;
+        assert_eq!(
+            decode_answers(&msg, 7, TYPE_A).unwrap_err(),
+            "truncated response"
+        );
+    }
+}
@@ -0,0 +1,521 @@
+//! A Simply.com provider for Kubernetes [external-dns].
+//!
+//! [`SimplyProvider`] translates between external-dns endpoints and the
+//! records of the managed domains, applying changes through the
+//! [sync engine](crate::sync) so records external-dns doesn't mention are left
+//! alone. [`ProviderServer`] serves it with the external-dns webhook protocol:
+//!
+//! * `GET /` negotiates the domain filter,
+//! * `GET /records` lists the current endpoints,
+//! * `POST /adjustendpoints` drops endpoints the provider can't manage,
+//! * `POST /records` (also served as `POST /apply`) applies a change set,
+//! * `GET /healthz` answers once the server is up.
+//!
+//! With a [`Quota`](SimplyProvider::quota) the provider only takes on the
+//! endpoints within it, so each team's external-dns can run its own webhook
+//! against a shared account.
+//!
+//! Run the `simply-dns-webhook` binary as a sidecar of external-dns, or embed
+//! the server in another program.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::provider::{ProviderServer, SimplyProvider};
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let provider = SimplyProvider::new(client, ["example.com"]);
+//! let server = ProviderServer::start(provider, "127.0.0.1:8888".parse().unwrap()).await?;
+//! println!("external-dns webhook listening on {}", server.addr());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [external-dns]: https://github.com/kubernetes-sigs/external-dns
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+use crate::api::DnsRecord;
+use crate::http_server::{self, HttpRequest, HttpResponse};
+use crate::names;
+use crate::quota::Quota;
+use crate::record_data::SUPPORTED_RECORD_TYPES;
+use crate::sync::{DesiredRecord, DesiredZone, ZonePlan};
+use crate::{SimplyClientError, SimplyDnsApi};
+
+/// Media type of the external-dns webhook protocol.
+const MEDIA_TYPE: &str = "application/external.dns.webhook+json;version=1";
+
+/// Record types whose data starts with a priority, written as `"10 target"` in endpoints.
+const PRIORITY_TYPES: &[&str] = &["MX", "SRV"];
+
+/// A DNS name with its targets, as exchanged with external-dns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Endpoint {
+    /// The fully-qualified name, without a trailing dot.
+    pub dns_name: String,
+    /// The record data, one entry per record.
+    #[serde(default)]
+    pub targets: Vec<String>,
+    /// The record type, e.g. "A".
+    pub record_type: String,
+    /// Identifies one of several endpoints with the same name, for routing policies.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub set_identifier: String,
+    /// The TTL in seconds; 0 means the provider's default.
+    #[serde(default, rename = "recordTTL")]
+    pub record_ttl: u32,
+    /// Labels external-dns attaches to the endpoint.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// Provider-specific settings, which this provider ignores.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub provider_specific: Vec<ProviderSpecific>,
+}
+
+/// A provider-specific setting of an [`Endpoint`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderSpecific {
+    /// The setting's name.
+    pub name: String,
+    /// The setting's value.
+    pub value: String,
+}
+
+/// A change set from external-dns.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Changes {
+    /// Endpoints to create.
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub create: Vec<Endpoint>,
+    /// Endpoints as they were before an update.
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub update_old: Vec<Endpoint>,
+    /// Endpoints as they should be after an update.
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub update_new: Vec<Endpoint>,
+    /// Endpoints to delete.
+    #[serde(default, deserialize_with = "null_as_empty")]
+    pub delete: Vec<Endpoint>,
+}
+
+/// external-dns sends `null` for empty lists.
+fn null_as_empty<'de, D>(deserializer: D) -> Result<Vec<Endpoint>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<Vec<Endpoint>>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Manages the records of a set of domains on behalf of external-dns.
+pub struct SimplyProvider<A> {
+    api: A,
+    domains: Vec<String>,
+    quota: Option<Quota>,
+}
+
+impl<A: SimplyDnsApi> SimplyProvider<A> {
+    /// A provider managing `domains` through `api`.
+    pub fn new<I, S>(api: A, domains: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            api,
+            domains: domains
+                .into_iter()
+                .map(|d| d.into().trim_end_matches('.').to_ascii_lowercase())
+                .collect(),
+            quota: None,
+        }
+    }
+
+    /// Only manage the endpoints within `quota`: others are dropped by
+    /// [`adjust_endpoints`](Self::adjust_endpoints) and refused by
+    /// [`apply_changes`](Self::apply_changes), as are changes that would leave
+    /// more records in the quota's subtree than it allows.
+    pub fn quota(mut self, quota: Quota) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    /// Whether `endpoint` in `domain` is within the provider's quota.
+    fn allows(&self, endpoint: &Endpoint, domain: &str) -> bool {
+        self.quota.as_ref().is_none_or(|quota| {
+            let name = names::to_relative(&endpoint.dns_name, domain);
+            quota.allows(domain, &name, &endpoint.record_type)
+        })
+    }
+
+    /// The domains this provider manages.
+    pub fn domains(&self) -> &[String] {
+        &self.domains
+    }
+
+    /// The managed domain `dns_name` belongs to, preferring the longest match.
+    fn domain_of(&self, dns_name: &str) -> Option<&str> {
+        let name = dns_name.trim_end_matches('.').to_ascii_lowercase();
+        self.domains
+            .iter()
+            .filter(|d| name == **d || name.ends_with(&format!(".{d}")))
+            .max_by_key(|d| d.len())
+            .map(String::as_str)
+    }
+
+    /// All records of the managed domains as endpoints, one per name and type.
+    pub async fn records(&self) -> Result<Vec<Endpoint>, SimplyClientError> {
+        let mut endpoints = Vec::new();
+        for domain in &self.domains {
+            let mut grouped: BTreeMap<(String, String), Endpoint> = BTreeMap::new();
+            for record in self.api.list_dns_records(domain).await? {
+                let dns_name = fqdn(&record.name, domain);
+                let endpoint = grouped
+                    .entry((dns_name.clone(), record.record_type.clone()))
+                    .or_insert_with(|| Endpoint {
+                        dns_name,
+                        targets: Vec::new(),
+                        record_type: record.record_type.clone(),
+                        set_identifier: String::new(),
+                        record_ttl: record.ttl,
+                        labels: BTreeMap::new(),
+                        provider_specific: Vec::new(),
+                    });
+                endpoint.targets.push(target(&record));
+            }
+            endpoints.extend(grouped.into_values());
+        }
+        Ok(endpoints)
+    }
+
+    /// Drop the endpoints this provider can't manage: those outside its domains
+    /// or its quota, of unsupported types, or using set identifiers.
+    pub fn adjust_endpoints(&self, endpoints: Vec<Endpoint>) -> Vec<Endpoint> {
+        endpoints
+            .into_iter()
+            .filter(|e| {
+                self.domain_of(&e.dns_name)
+                    .is_some_and(|domain| self.allows(e, domain))
+                    && e.set_identifier.is_empty()
+                    && SUPPORTED_RECORD_TYPES
+                        .iter()
+                        .any(|t| t.eq_ignore_ascii_case(&e.record_type))
+            })
+            .collect()
+    }
+
+    /// Apply a change set from external-dns.
+    ///
+    /// For each affected domain the names and types mentioned in `changes` are
+    /// reconciled to the created and updated endpoints; other records are kept.
+    /// The changes of a domain are applied through [`SimplyDnsApi::apply_plan`],
+    /// holding the client's [domain lock](crate::lock).
+    ///
+    /// Returns the first error, after attempting every change; an endpoint
+    /// outside the managed domains or the quota is an error, and a domain
+    /// whose records can't be listed, or that would exceed the quota's
+    /// records, is skipped.
+    pub async fn apply_changes(&self, changes: Changes) -> Result<(), SimplyClientError> {
+        let mut first_error = None;
+        let mut domains: BTreeMap<&str, DomainChanges<'_>> = BTreeMap::new();
+        let removed = changes.delete.iter().chain(&changes.update_old);
+        let added = changes.create.iter().chain(&changes.update_new);
+        for (endpoint, desired) in removed.map(|e| (e, false)).chain(added.map(|e| (e, true))) {
+            let Some(domain) = self.domain_of(&endpoint.dns_name) else {
+                first_error.get_or_insert_with(|| SimplyClientError::Validation {
+                    status: None,
+                    field: Some("dnsName".to_string()),
+                    message: format!("{} is not in a managed domain", endpoint.dns_name),
+                });
+                continue;
+            };
+            if !self.allows(endpoint, domain) {
+                first_error.get_or_insert_with(|| SimplyClientError::QuotaExceeded {
+                    message: format!(
+                        "{} {} is outside the provider's quota",
+                        endpoint.dns_name, endpoint.record_type
+                    ),
+                });
+                continue;
+            }
+            let entry = domains.entry(domain).or_default();
+            entry.touched.insert(key(
+                &names::to_relative(&endpoint.dns_name, domain),
+                &endpoint.record_type,
+            ));
+            if desired {
+                entry.desired.push(endpoint);
+            }
+        }
+
+        for (domain, DomainChanges { desired, touched }) in domains {
+            let current = match self.api.list_dns_records(domain).await {
+                Ok(current) => current,
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                    continue;
+                }
+            };
+            let kept = current
+                .iter()
+                .filter(|r| !touched.contains(&key(&r.name, &r.record_type)))
+                .map(|r| DesiredRecord {
+                    name: r.name.clone(),
+                    record_type: r.record_type.clone(),
+                    data: r.data.clone(),
+                    ttl: Some(r.ttl),
+                    priority: r.priority,
+                    comment: r.comment.clone(),
+                });
+            let wanted = desired.iter().flat_map(|e| desired_records(e, domain));
+            let desired: DesiredZone = kept.chain(wanted).collect();
+            if let Err(e) = self.check_room(domain, &current, &desired) {
+                first_error.get_or_insert(e);
+                continue;
+            }
+            let plan = ZonePlan::compute(domain, current, &desired);
+            let report = self.api.apply_plan(plan).await;
+            if let Some(failed) = report.failed.into_iter().next() {
+                first_error.get_or_insert(failed.error);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Fail if `desired` adds records to the quota's subtree of `domain`
+    /// beyond what it may hold.
+    fn check_room(
+        &self,
+        domain: &str,
+        current: &[DnsRecord],
+        desired: &DesiredZone,
+    ) -> Result<(), SimplyClientError> {
+        let Some(quota) = &self.quota else {
+            return Ok(());
+        };
+        let Some(max) = quota.record_limit() else {
+            return Ok(());
+        };
+        let before = (current.iter())
+            .filter(|r| quota.in_subtree(domain, &r.name))
+            .count();
+        let after = (desired.records.iter())
+            .filter(|r| quota.in_subtree(domain, &r.name))
+            .count();
+        if after > max && after > before {
+            return Err(SimplyClientError::QuotaExceeded {
+                message: format!(
+                    "{} would have {after} of {max} records",
+                    quota.scope(domain)
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    async fn handle(&self, req: HttpRequest) -> HttpResponse {
+        let result = match (req.method.as_str(), &req.segments()[..]) {
+            ("GET", [""]) => Ok(json!({ "include": self.domains, "exclude": [] })),
+            ("GET", ["healthz"]) => {
+                return HttpResponse::new(200, "text/plain", "ok".to_string());
+            }
+            ("GET", ["records"]) => self.records().await.map(|e| json!(e)),
+            ("POST", ["adjustendpoints"]) => match serde_json::from_slice(&req.body) {
+                Ok(endpoints) => Ok(json!(self.adjust_endpoints(endpoints))),
+                Err(e) => return bad_request(&e.to_string()),
+            },
+            ("POST", ["records"] | ["apply"]) => match serde_json::from_slice(&req.body) {
+                Ok(changes) => match self.apply_changes(changes).await {
+                    Ok(()) => return HttpResponse::new(204, MEDIA_TYPE, String::new()),
+                    Err(e) => Err(e),
+                },
+                Err(e) => return bad_request(&e.to_string()),
+            },
+            _ => return HttpResponse::new(404, "text/plain", "not found".to_string()),
+        };
+        match result {
+            Ok(value) => HttpResponse::new(200, MEDIA_TYPE, value.to_string()),
+            Err(e) => HttpResponse::new(500, "text/plain", e.to_string()),
+        }
+    }
+}
+
+/// The changes external-dns requested for one domain.
+#[derive(Default)]
+struct DomainChanges<'a> {
+    /// The endpoints that should exist afterwards.
+    desired: Vec<&'a Endpoint>,
+    /// The (name, type) keys being changed; all other records are kept.
+    touched: BTreeSet<(String, String)>,
+}
+
+fn bad_request(message: &str) -> HttpResponse {
+    HttpResponse::new(400, "text/plain", message.to_string())
+}
+
+fn key(name: &str, record_type: &str) -> (String, String) {
+    (name.to_ascii_lowercase(), record_type.to_ascii_uppercase())
+}
+
+/// The endpoint name of the record `name` in `domain`, without a trailing dot.
+fn fqdn(name: &str, domain: &str) -> String {
+    names::to_fqdn(name, domain)
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// The endpoint target of `record`, with the priority first where the type has one.
+fn target(record: &DnsRecord) -> String {
+    match record.priority {
+        Some(priority) if is_priority_type(&record.record_type) => {
+            format!("{priority} {}", record.data)
+        }
+        _ => record.data.clone(),
+    }
+}
+
+fn is_priority_type(record_type: &str) -> bool {
+    PRIORITY_TYPES
+        .iter()
+        .any(|t| t.eq_ignore_ascii_case(record_type))
+}
+
+/// The records `endpoint` stands for in `domain`.
+fn desired_records<'a>(
+    endpoint: &'a Endpoint,
+    domain: &'a str,
+) -> impl Iterator<Item = DesiredRecord> + 'a {
+    let name = names::to_relative(&endpoint.dns_name, domain);
+    endpoint.targets.iter().map(move |target| {
+        let mut record = DesiredRecord::new(name.clone(), &endpoint.record_type, target.as_str());
+        if is_priority_type(&endpoint.record_type)
+            && let Some((priority, data)) = target.split_once(' ')
+            && let Ok(priority) = priority.parse()
+        {
+            record.priority = Some(priority);
+            record.data = data.to_string();
+        }
+        if endpoint.record_ttl > 0 {
+            record.ttl = Some(endpoint.record_ttl);
+        }
+        record
+    })
+}
+
+/// A running external-dns webhook server. Stops when dropped.
+pub struct ProviderServer {
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl ProviderServer {
+    /// Serve `provider` on `addr` in the background of the current Tokio runtime.
+    pub async fn start<A>(provider: SimplyProvider<A>, addr: SocketAddr) -> io::Result<Self>
+    where
+        A: SimplyDnsApi + 'static,
+    {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let provider = Arc::new(provider);
+        let task = tokio::spawn(http_server::serve(listener, move |req| {
+            let provider = provider.clone();
+            async move { provider.handle(req).await }
+        }));
+        Ok(Self { addr, task })
+    }
+
+    /// The address the server listens on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for ProviderServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::api::CreateDnsRecordRequest;
+    use crate::mock::MockSimplyClient;
+    use crate::record_data::RecordData;
+
+    fn endpoint(dns_name: &str, record_type: &str, target: &str) -> Endpoint {
+        Endpoint {
+            dns_name: dns_name.to_string(),
+            targets: vec![target.to_string()],
+            record_type: record_type.to_string(),
+            set_identifier: String::new(),
+            record_ttl: 0,
+            labels: BTreeMap::new(),
+            provider_specific: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn quotas_limit_the_endpoints_a_provider_takes_on() {
+        let a = |ip: [u8; 4]| RecordData::A(ip.into());
+        let mock = MockSimplyClient::new().with_records(
+            "example.com",
+            [
+                CreateDnsRecordRequest::new("team-a", a([192, 0, 2, 1])),
+                CreateDnsRecordRequest::new("@", a([192, 0, 2, 2])),
+            ],
+        );
+        let provider = SimplyProvider::new(mock, ["example.com"]).quota(
+            Quota::new()
+                .subtree("team-a")
+                .record_types(["A", "TXT"])
+                .max_records(2),
+        );
+
+        let adjusted = provider.adjust_endpoints(vec![
+            endpoint("api.team-a.example.com", "A", "192.0.2.3"),
+            endpoint("www.example.com", "A", "192.0.2.4"),
+            endpoint("team-a.example.com", "MX", "10 mx.example.net"),
+        ]);
+        let names: Vec<&str> = adjusted.iter().map(|e| e.dns_name.as_str()).collect();
+        assert_eq!(names, ["api.team-a.example.com"]);
+
+        let create = |endpoints| Changes {
+            create: endpoints,
+            ..Changes::default()
+        };
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            (provider.apply_changes(create(adjusted)).await).unwrap();
+            for endpoint in [
+                endpoint("www.example.com", "A", "192.0.2.4"),
+                endpoint("web.team-a.example.com", "A", "192.0.2.5"),
+            ] {
+                let err = provider.apply_changes(create(vec![endpoint])).await;
+                assert!(
+                    matches!(err, Err(SimplyClientError::QuotaExceeded { .. })),
+                    "{err:?}"
+                );
+            }
+        });
+        let mut names: Vec<String> = (provider.api.records("example.com").into_iter())
+            .map(|r| r.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["@", "api.team-a", "team-a"]);
+    }
+}
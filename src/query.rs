@@ -0,0 +1,281 @@
+//! Client-side filtering of DNS records.
+//!
+//! A [`RecordQuery`] selects records by name, type, data, TTL and comment.
+//! [`SimplyClient::find_records`] applies one to the records of a domain,
+//! [`SimplyClient::find_by_comment`] is a shorthand for finding records by
+//! their comment, and [`SimplyClient::delete_records_matching`] removes
+//! records by name and type.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::query::RecordQuery;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let query = RecordQuery::new()
+//!     .name_glob("_acme-challenge*")
+//!     .record_type("TXT")
+//!     .ttl_range(..=300);
+//! for record in client.find_records("example.com", &query).await? {
+//!     println!("{} {}", record.name, record.data);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ops::{Bound, RangeBounds};
+
+use crate::api::DnsRecord;
+use crate::{SimplyClient, SimplyClientError};
+
+/// How a [`RecordQuery`] matches record names. Names are compared case-insensitively.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameMatch {
+    /// The name is exactly this, e.g. `www` or `@`.
+    Exact(String),
+    /// The name ends with this, e.g. `.staging` matches `api.staging`.
+    Suffix(String),
+    /// The name matches this pattern, where `*` matches any run of characters
+    /// and `?` matches a single character.
+    Glob(String),
+}
+
+impl NameMatch {
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        match self {
+            NameMatch::Exact(exact) => name == exact.to_ascii_lowercase(),
+            NameMatch::Suffix(suffix) => name.ends_with(&suffix.to_ascii_lowercase()),
+            NameMatch::Glob(pattern) => {
+                glob_matches(pattern.to_ascii_lowercase().as_bytes(), name.as_bytes())
+            }
+        }
+    }
+}
+
+/// A filter over DNS records. Every criterion that is set must match; an empty
+/// query matches every record.
+#[derive(Debug, Clone, Default)]
+pub struct RecordQuery {
+    /// Which names to match.
+    pub name: Option<NameMatch>,
+    /// The record type to match, compared case-insensitively.
+    pub record_type: Option<String>,
+    /// A substring the record data must contain.
+    pub data_contains: Option<String>,
+    /// The smallest TTL to match, inclusive.
+    pub min_ttl: Option<u32>,
+    /// The largest TTL to match, inclusive.
+    pub max_ttl: Option<u32>,
+    /// A glob pattern the comment must match, see [`comment_glob`](Self::comment_glob).
+    pub comment: Option<String>,
+}
+
+impl RecordQuery {
+    /// A query matching every record.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match records named exactly `name`.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(NameMatch::Exact(name.into()));
+        self
+    }
+
+    /// Match records whose name ends with `suffix`.
+    pub fn name_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.name = Some(NameMatch::Suffix(suffix.into()));
+        self
+    }
+
+    /// Match records whose name matches the glob `pattern`, see [`NameMatch::Glob`].
+    pub fn name_glob(mut self, pattern: impl Into<String>) -> Self {
+        self.name = Some(NameMatch::Glob(pattern.into()));
+        self
+    }
+
+    /// Match records of type `record_type`.
+    pub fn record_type(mut self, record_type: impl Into<String>) -> Self {
+        self.record_type = Some(record_type.into());
+        self
+    }
+
+    /// Match records whose data contains `data`.
+    pub fn data_contains(mut self, data: impl Into<String>) -> Self {
+        self.data_contains = Some(data.into());
+        self
+    }
+
+    /// Match records whose comment matches the glob `pattern`, where `*`
+    /// matches any run of characters and `?` a single one. Unlike names,
+    /// comments are compared case-sensitively. A record without a comment
+    /// counts as having an empty one, so `*` matches every record.
+    pub fn comment_glob(mut self, pattern: impl Into<String>) -> Self {
+        self.comment = Some(pattern.into());
+        self
+    }
+
+    /// Match records whose TTL is within `range`, e.g. `300..=3600` or `..600`.
+    pub fn ttl_range(mut self, range: impl RangeBounds<u32>) -> Self {
+        self.min_ttl = match range.start_bound() {
+            Bound::Included(&min) => Some(min),
+            Bound::Excluded(&min) => Some(min.saturating_add(1)),
+            Bound::Unbounded => None,
+        };
+        self.max_ttl = match range.end_bound() {
+            Bound::Included(&max) => Some(max),
+            Bound::Excluded(&max) => Some(max.saturating_sub(1)),
+            Bound::Unbounded => None,
+        };
+        self
+    }
+
+    /// Whether `record` matches every criterion of the query.
+    pub fn matches(&self, record: &DnsRecord) -> bool {
+        self.name.as_ref().is_none_or(|n| n.matches(&record.name))
+            && self
+                .record_type
+                .as_ref()
+                .is_none_or(|t| t.eq_ignore_ascii_case(&record.record_type))
+            && self
+                .data_contains
+                .as_ref()
+                .is_none_or(|d| record.data.contains(d.as_str()))
+            && self.min_ttl.is_none_or(|min| record.ttl >= min)
+            && self.max_ttl.is_none_or(|max| record.ttl <= max)
+            && self.comment.as_ref().is_none_or(|pattern| {
+                glob_matches(
+                    pattern.as_bytes(),
+                    record.comment.as_deref().unwrap_or_default().as_bytes(),
+                )
+            })
+    }
+}
+
+/// Match `text` against a pattern of literal bytes, `*` and `?`.
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it is currently matched up to.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+impl SimplyClient {
+    /// List the DNS records of a domain that match `query`.
+    ///
+    /// The filtering happens client-side over [`list_dns_records`](Self::list_dns_records).
+    /// Names are matched and returned in the client's
+    /// [name style](crate::SimplyClientBuilder::name_style).
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to search.
+    /// * `query` - The criteria the records must match.
+    pub async fn find_records(
+        &self,
+        domain: &str,
+        query: &RecordQuery,
+    ) -> Result<Vec<DnsRecord>, SimplyClientError> {
+        let records = self.list_dns_records(domain).await?;
+        Ok(self.filter_records(domain, records, query))
+    }
+
+    /// The `records` of `domain` matching `query`, with names in the client's style.
+    pub(crate) fn filter_records(
+        &self,
+        domain: &str,
+        records: Vec<DnsRecord>,
+        query: &RecordQuery,
+    ) -> Vec<DnsRecord> {
+        let mut query = query.clone();
+        if let Some(NameMatch::Exact(name)) = &mut query.name {
+            *name = self.name_style().normalize(name, domain);
+        }
+        records
+            .into_iter()
+            .map(|mut r| {
+                r.name = self.styled_name(&r.name, domain);
+                r
+            })
+            .filter(|r| query.matches(r))
+            .collect()
+    }
+
+    /// List the DNS records of a domain whose comment matches the glob `pattern`.
+    ///
+    /// Example usage:
+    /// ```rust,no_run
+    /// use simply_dns::SimplyClient;
+    ///
+    /// # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+    /// let client = SimplyClient::new("account", "api_key");
+    /// for record in client.find_by_comment("example.com", "*ticket=OPS-42*").await? {
+    ///     println!("{} {} {}", record.name, record.record_type, record.data);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to search.
+    /// * `pattern` - The pattern, see [`RecordQuery::comment_glob`].
+    pub async fn find_by_comment(
+        &self,
+        domain: &str,
+        pattern: &str,
+    ) -> Result<Vec<DnsRecord>, SimplyClientError> {
+        self.find_records(domain, &RecordQuery::new().comment_glob(pattern))
+            .await
+    }
+
+    /// Delete every DNS record of a domain with the given name and type.
+    ///
+    /// Returns how many records were deleted; finding none is not an error, so
+    /// this is safe to call repeatedly, e.g. to clean up stale ACME challenges.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the DNS records belong to.
+    /// * `name` - The record name, in either style, compared case-insensitively.
+    /// * `record_type` - The record type, e.g. "TXT".
+    pub async fn delete_records_matching(
+        &self,
+        domain: &str,
+        name: &str,
+        record_type: &str,
+    ) -> Result<usize, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let query = RecordQuery::new().name(name).record_type(record_type);
+        let mut deleted = 0;
+        for record in this.find_records(domain, &query).await? {
+            match this.delete_dns_record(domain, record.record_id).await {
+                Ok(()) => deleted += 1,
+                // Someone else removed it in the meantime.
+                Err(e) if e.is_not_found() => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(deleted)
+    }
+}
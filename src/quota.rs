@@ -0,0 +1,341 @@
+//! Quotas for self-service DNS.
+//!
+//! A [`Quota`] set with
+//! [`SimplyClientBuilder::quota`](crate::SimplyClientBuilder::quota), or per
+//! consumer with [`SimplyClient::with_quota`], bounds what a client may
+//! change: the names below a subtree, a set of record types, and how many
+//! records it may keep there. Creates, updates and deletes outside those
+//! bounds fail with [`SimplyClientError::QuotaExceeded`] before anything is
+//! sent, so a platform team can hand out one client, or one
+//! [webhook provider](crate::provider), per team. Reads are not limited.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::quota::Quota;
+//!
+//! let platform = SimplyClient::new("account", "api_key");
+//! let team_a = platform.with_quota(
+//!     Quota::new()
+//!         .subtree("team-a")
+//!         .record_types(["A", "AAAA", "CNAME", "TXT"])
+//!         .max_records(50),
+//! );
+//! # let _ = team_a;
+//! ```
+
+use crate::api::DnsRecordId;
+use crate::subtree::in_subtree;
+use crate::{SimplyClient, SimplyClientError};
+
+/// The bounds a client's changes must stay within, see the [module docs](self).
+///
+/// An empty quota allows everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Quota {
+    subtree: Option<String>,
+    record_types: Vec<String>,
+    max_records: Option<usize>,
+}
+
+impl Quota {
+    /// A quota allowing everything, to be narrowed down.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only allow names at or below `name`, relative or fully-qualified,
+    /// e.g. `team-a` for `team-a.example.com` and `www.team-a.example.com`.
+    pub fn subtree(mut self, name: impl Into<String>) -> Self {
+        self.subtree = Some(name.into());
+        self
+    }
+
+    /// Only allow records of `types`, e.g. `["A", "CNAME"]`.
+    pub fn record_types<I, S>(mut self, types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.record_types = (types.into_iter())
+            .map(|t| t.into().to_ascii_uppercase())
+            .collect();
+        self
+    }
+
+    /// Refuse to create records once the subtree (or the zone, without one)
+    /// holds `max` records, whoever created them.
+    pub fn max_records(mut self, max: usize) -> Self {
+        self.max_records = Some(max);
+        self
+    }
+
+    /// The most records the subtree may hold, if limited.
+    pub fn record_limit(&self) -> Option<usize> {
+        self.max_records
+    }
+
+    /// Whether a record named `name` (in the API's relative style) of
+    /// `record_type` is within the subtree and types of the quota for
+    /// `domain`.
+    pub fn allows(&self, domain: &str, name: &str, record_type: &str) -> bool {
+        self.check(domain, name, record_type).is_ok()
+    }
+
+    /// The subtree relative to `domain`, if the quota has one.
+    fn suffix(&self, domain: &str) -> Option<String> {
+        let suffix = crate::names::to_relative(self.subtree.as_deref()?, domain);
+        (!suffix.is_empty() && suffix != "@").then_some(suffix)
+    }
+
+    /// The name of the subtree in `domain`, or the domain without one.
+    pub(crate) fn scope(&self, domain: &str) -> String {
+        match self.suffix(domain) {
+            Some(suffix) => format!("{suffix}.{domain}"),
+            None => domain.to_string(),
+        }
+    }
+
+    /// Whether `name` in `domain` is at or below the quota's subtree.
+    pub(crate) fn in_subtree(&self, domain: &str, name: &str) -> bool {
+        self.suffix(domain)
+            .is_none_or(|suffix| in_subtree(&crate::names::to_relative(name, domain), &suffix))
+    }
+
+    fn check(&self, domain: &str, name: &str, record_type: &str) -> Result<(), SimplyClientError> {
+        if !self.in_subtree(domain, name) {
+            let scope = self.scope(domain);
+            return Err(exceeded(format!("{name} is outside {scope}")));
+        }
+        let allowed = self.record_types.is_empty()
+            || (self.record_types.iter()).any(|t| t.eq_ignore_ascii_case(record_type));
+        if !allowed {
+            return Err(exceeded(format!(
+                "{record_type} records aren't allowed, only {}",
+                self.record_types.join(", ")
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn exceeded(message: String) -> SimplyClientError {
+    SimplyClientError::QuotaExceeded { message }
+}
+
+impl SimplyClient {
+    /// Fail unless a record named `name` of `record_type` in `domain` is
+    /// within the subtree and types of the client's quota.
+    pub(crate) fn check_quota(
+        &self,
+        domain: &str,
+        name: &str,
+        record_type: &str,
+    ) -> Result<(), SimplyClientError> {
+        match self.quota() {
+            Some(quota) => quota.check(domain, &self.api_name(name, domain), record_type),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether creating records has to count the records of the subtree.
+    pub(crate) fn quota_limits_records(&self) -> bool {
+        self.quota().is_some_and(|q| q.max_records.is_some())
+    }
+
+    /// Fail if the subtree of the client's quota already holds as many
+    /// records as it may, to be called with the domain locked.
+    pub(crate) async fn check_quota_room(&self, domain: &str) -> Result<(), SimplyClientError> {
+        let Some((quota, max)) = self.quota().and_then(|q| Some((q, q.max_records?))) else {
+            return Ok(());
+        };
+        let records = self.list_dns_records(domain).await?;
+        let held = (records.iter())
+            .filter(|r| quota.in_subtree(domain, &r.name))
+            .count();
+        if held < max {
+            return Ok(());
+        }
+        let scope = quota.scope(domain);
+        Err(exceeded(format!(
+            "{scope} already has {held} of {max} records"
+        )))
+    }
+
+    /// Fail unless the existing record `record_id` of `domain` is within the
+    /// client's quota, before it is changed or deleted.
+    pub(crate) async fn check_quota_existing(
+        &self,
+        domain: &str,
+        record_id: &DnsRecordId,
+    ) -> Result<(), SimplyClientError> {
+        let Some(quota) = self.quota() else {
+            return Ok(());
+        };
+        // A record that is gone is left for the API to report.
+        match self.get_dns_record(domain, record_id).await? {
+            Some(record) => quota.check(domain, &record.name, &record.record_type),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use crate::api::{CreateDnsRecordRequest, UpdateDnsRecordRequest};
+    use crate::http_server::{self, HttpResponse};
+    use crate::record_data::RecordData;
+
+    const RECORDS: &str = r#"{"records":[
+        {"record_id":1001,"name":"team-a","ttl":3600,"data":"192.0.2.1","type":"A"},
+        {"record_id":1002,"name":"www.team-a","ttl":3600,"data":"team-a.example.com","type":"CNAME"},
+        {"record_id":1003,"name":"@","ttl":3600,"data":"192.0.2.3","type":"A"}
+    ],"status":200,"message":"success"}"#;
+
+    #[test]
+    fn quotas_allow_names_in_their_subtree_and_types() {
+        let quota = Quota::new()
+            .subtree("team-a.example.com.")
+            .record_types(["a", "CNAME"]);
+        for (name, record_type, allowed) in [
+            ("team-a", "A", true),
+            ("www.team-a", "cname", true),
+            ("www.team-a.example.com", "A", true),
+            ("Team-A", "A", true),
+            ("team-b", "A", false),
+            ("xteam-a", "A", false),
+            ("@", "A", false),
+            ("team-a", "TXT", false),
+        ] {
+            assert_eq!(
+                quota.allows("example.com", name, record_type),
+                allowed,
+                "{name} {record_type}"
+            );
+        }
+        assert!(Quota::new().allows("example.com", "@", "MX"));
+        assert!(Quota::new().subtree("@").allows("example.com", "www", "A"));
+
+        let err = quota.check("example.com", "team-b", "A").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "quota exceeded: team-b is outside team-a.example.com"
+        );
+        let err = quota.check("example.com", "team-a", "MX").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "quota exceeded: MX records aren't allowed, only A, CNAME"
+        );
+    }
+
+    fn refused<T: std::fmt::Debug>(result: Result<T, SimplyClientError>) -> String {
+        match result {
+            Err(SimplyClientError::QuotaExceeded { message }) => message,
+            other => panic!("not refused: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn changes_outside_the_quota_are_refused_before_they_are_sent() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let base_url = format!("http://{}/2/", listener.local_addr().unwrap());
+            let writes = Arc::new(Mutex::new(Vec::new()));
+            let recorded = writes.clone();
+            let server = tokio::spawn(http_server::serve(listener, move |req| {
+                let body = match req.method.as_str() {
+                    "GET" => RECORDS,
+                    "POST" => include_str!("../fixtures/create_record.json"),
+                    _ => include_str!("../fixtures/delete_record.json"),
+                };
+                let path = req.path.rsplit('/').next().unwrap_or_default();
+                (recorded.lock().unwrap()).push(format!("{} {path}", req.method));
+                async move { HttpResponse::new(200, "application/json", body.to_string()) }
+            }));
+
+            let platform = SimplyClient::builder()
+                .credentials("account", "api_key")
+                .base_url(base_url)
+                .build()
+                .unwrap();
+            let quota = Quota::new()
+                .subtree("team-a")
+                .record_types(["A", "CNAME", "TXT"]);
+            let team_a = platform.with_quota(quota.clone().max_records(3));
+            let create = |name: &str| {
+                CreateDnsRecordRequest::new(name, RecordData::A([192, 0, 2, 10].into()))
+            };
+            let update = |name: &str| {
+                UpdateDnsRecordRequest::new(name, RecordData::A([192, 0, 2, 11].into()))
+            };
+            let id = |id| DnsRecordId { id };
+
+            team_a
+                .create_dns_record("example.com", create("api.team-a"))
+                .await
+                .unwrap();
+            let message = refused(
+                team_a
+                    .create_dns_record("example.com", create("team-b"))
+                    .await,
+            );
+            assert_eq!(message, "team-b is outside team-a.example.com");
+            let mx = CreateDnsRecordRequest::new(
+                "team-a",
+                RecordData::Mx {
+                    priority: 10,
+                    exchange: "mx.example.net".to_string(),
+                },
+            );
+            refused(team_a.create_dns_record("example.com", mx).await);
+            let full = platform.with_quota(quota.max_records(2));
+            let message = refused(
+                full.create_dns_record("example.com", create("api.team-a"))
+                    .await,
+            );
+            assert_eq!(message, "team-a.example.com already has 2 of 2 records");
+
+            team_a
+                .update_dns_record("example.com", id(1001), update("team-a"))
+                .await
+                .unwrap();
+            refused(
+                team_a
+                    .update_dns_record("example.com", id(1001), update("@"))
+                    .await,
+            );
+            refused(
+                team_a
+                    .update_dns_record("example.com", id(1003), update("team-a"))
+                    .await,
+            );
+            team_a
+                .delete_dns_record("example.com", id(1002))
+                .await
+                .unwrap();
+            refused(team_a.delete_dns_record("example.com", id(1003)).await);
+            platform
+                .delete_dns_record("example.com", id(1003))
+                .await
+                .unwrap();
+            server.abort();
+
+            let writes: Vec<String> = (writes.lock().unwrap().iter())
+                .filter(|w| !w.starts_with("GET"))
+                .cloned()
+                .collect();
+            assert_eq!(
+                writes,
+                ["POST records", "PUT 1001", "DELETE 1002", "DELETE 1003"]
+            );
+        });
+    }
+}
@@ -0,0 +1,141 @@
+//! Client-side token-bucket rate limiting.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::time::Instant;
+
+/// How urgent a client's requests are when drawing from a shared [`ApiBudget`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Priority {
+    /// Interactive or critical work, e.g. a user-triggered change or a DDNS update.
+    #[default]
+    Interactive,
+    /// Background work such as audits or periodic syncs, which yields when the budget is low.
+    Background,
+}
+
+/// A request budget shared by several clients or subsystems.
+///
+/// The budget is a token bucket holding up to `burst` tokens, refilled at
+/// `requests_per_second`. Every request takes one token. [`Priority::Background`]
+/// requests additionally leave a reserve of tokens untouched, so background
+/// work backs off first when the budget gets tight and interactive requests
+/// still go through.
+///
+/// Cloning an `ApiBudget` shares it. Pass it to several builders with
+/// [`SimplyClientBuilder::budget`](crate::SimplyClientBuilder::budget), or use
+/// [`SimplyClient::with_priority`](crate::SimplyClient::with_priority) to derive
+/// background clients from one client.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::{ApiBudget, Priority, SimplyClient};
+///
+/// let budget = ApiBudget::new(5.0, 20);
+/// let client = SimplyClient::builder()
+///     .credentials("account", "api_key")
+///     .budget(budget)
+///     .build()?;
+/// let audits = client.with_priority(Priority::Background);
+/// # Ok::<(), simply_dns::SimplyClientError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ApiBudget {
+    limiter: Arc<RateLimiter>,
+}
+
+impl ApiBudget {
+    /// A budget of `requests_per_second` on average with bursts of up to `burst`
+    /// requests, keeping half of the burst in reserve for interactive requests.
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        Self::with_reserve(requests_per_second, burst, burst / 2)
+    }
+
+    /// Like [`new`](Self::new), keeping `reserve` tokens for interactive requests.
+    pub fn with_reserve(requests_per_second: f64, burst: u32, reserve: u32) -> Self {
+        Self {
+            limiter: Arc::new(RateLimiter::new(requests_per_second, burst, reserve)),
+        }
+    }
+
+    pub(crate) fn requests_per_second(&self) -> f64 {
+        self.limiter.rate
+    }
+
+    /// Wait until a request of `priority` may be sent and take a token for it.
+    pub(crate) async fn acquire(&self, priority: Priority) {
+        while let Some(wait) = self.limiter.try_acquire(priority) {
+            crate::time::sleep(wait).await;
+        }
+    }
+
+    /// Hold back all requests for `wait`, e.g. after the API answered 429.
+    pub(crate) fn pause_for(&self, wait: Duration) {
+        self.limiter.pause_for(wait);
+    }
+}
+
+/// A token bucket holding up to `burst` tokens, refilled at `rate` tokens per second.
+#[derive(Debug)]
+struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    reserve: f64,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    tokens: f64,
+    updated: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    fn new(requests_per_second: f64, burst: u32, reserve: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate: requests_per_second,
+            burst,
+            reserve: f64::from(reserve).min(burst - 1.0),
+            state: Mutex::new(State {
+                tokens: burst,
+                updated: Instant::now(),
+                paused_until: None,
+            }),
+        }
+    }
+
+    /// Take a token if one is available for `priority`, otherwise return how long to wait.
+    fn try_acquire(&self, priority: Priority) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        if let Some(until) = state.paused_until {
+            if until > now {
+                return Some(until - now);
+            }
+            state.paused_until = None;
+        }
+        let elapsed = now.duration_since(state.updated).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.rate).min(self.burst);
+        state.updated = now;
+        let needed = match priority {
+            Priority::Interactive => 1.0,
+            Priority::Background => 1.0 + self.reserve,
+        };
+        if state.tokens >= needed {
+            state.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((needed - state.tokens) / self.rate))
+        }
+    }
+
+    fn pause_for(&self, wait: Duration) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let until = Instant::now() + wait;
+        state.paused_until = Some(state.paused_until.map_or(until, |p| p.max(until)));
+        state.tokens = 0.0;
+    }
+}
@@ -0,0 +1,1040 @@
+//! Typed record data.
+//!
+//! The API represents record contents as a flat `data` string plus an optional
+//! `priority`. [`RecordData`] parses those into typed values per record type,
+//! and converts back when creating or updating records. [`SrvRecord`] and
+//! [`CaaRecord`] go further for the types that are easiest to get wrong, with
+//! builders that assemble the fields from their parts.
+
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use thiserror::Error;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord};
+
+/// Record types accepted by the Simply.com API, per its documentation.
+///
+/// The API offers no way to validate a record without creating it, so this
+/// list is maintained by hand. Types added by Simply.com later can be allowed
+/// with [`SimplyClientBuilder::record_types`](crate::SimplyClientBuilder::record_types).
+pub const SUPPORTED_RECORD_TYPES: &[&str] = &[
+    "A", "AAAA", "ALIAS", "CAA", "CNAME", "DS", "LOC", "MX", "NAPTR", "NS", "PTR", "SRV", "SSHFP",
+    "TLSA", "TXT",
+];
+
+/// Error returned when record data doesn't match the format of its type.
+#[derive(Debug, Error)]
+#[error("invalid {record_type} data {data:?}: {message}")]
+pub struct RecordDataError {
+    /// Type of the record ("A", "MX", etc.).
+    pub record_type: String,
+    /// The data that could not be parsed.
+    pub data: String,
+    /// What was wrong with it.
+    pub message: String,
+}
+
+/// The contents of a DNS record, typed per record type.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::api::CreateDnsRecordRequest;
+/// use simply_dns::record_data::RecordData;
+///
+/// let req = CreateDnsRecordRequest::new(
+///     "@",
+///     RecordData::Mx { priority: 10, exchange: "mail.example.com".to_string() },
+/// );
+/// assert_eq!(req.record_type, "MX");
+/// assert_eq!(req.data, "mail.example.com");
+/// assert_eq!(req.priority, Some(10));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordData {
+    /// An IPv4 address.
+    A(Ipv4Addr),
+    /// An IPv6 address.
+    Aaaa(Ipv6Addr),
+    /// The canonical name this name is an alias for.
+    Cname(String),
+    /// A mail exchange.
+    Mx {
+        /// Preference of this exchange; lower is preferred.
+        priority: u32,
+        /// Host name of the mail server.
+        exchange: String,
+    },
+    /// An authoritative name server.
+    Ns(String),
+    /// A pointer to another name, used for reverse DNS.
+    Ptr(String),
+    /// Free-form text.
+    Txt(String),
+    /// A service location.
+    Srv {
+        /// Priority of the target host; lower is preferred.
+        priority: u32,
+        /// Relative weight among targets with the same priority.
+        weight: u16,
+        /// Port the service listens on.
+        port: u16,
+        /// Host name providing the service.
+        target: String,
+    },
+    /// A certification authority authorization.
+    Caa {
+        /// Flags; 128 marks the property as critical.
+        flags: u8,
+        /// The property, e.g. "issue", "issuewild" or "iodef".
+        tag: String,
+        /// The property value, e.g. "letsencrypt.org".
+        value: String,
+    },
+    /// A naming authority pointer, used e.g. for ENUM and SIP service discovery.
+    Naptr {
+        /// Order in which records must be processed; lower first.
+        order: u16,
+        /// Preference among records with the same order; lower is preferred.
+        preference: u16,
+        /// Flags controlling the rewrite, e.g. "U" or "S".
+        flags: String,
+        /// The service parameters, e.g. "E2U+sip".
+        service: String,
+        /// The substitution expression applied to the original string.
+        regexp: String,
+        /// The next name to query, or "." if `regexp` is used.
+        replacement: String,
+    },
+    /// A geographical location.
+    ///
+    /// Coordinates are kept in thousandths of arc seconds and distances in
+    /// centimeters, the precision of the record's wire format, so no data is
+    /// lost when a record is read and written back.
+    Loc {
+        /// Latitude in thousandths of arc seconds; positive is north.
+        latitude: i64,
+        /// Longitude in thousandths of arc seconds; positive is east.
+        longitude: i64,
+        /// Altitude above the WGS 84 ellipsoid, in centimeters.
+        altitude: i64,
+        /// Diameter of the enclosing sphere, in centimeters.
+        size: u64,
+        /// Horizontal precision, in centimeters.
+        horizontal_precision: u64,
+        /// Vertical precision, in centimeters.
+        vertical_precision: u64,
+    },
+    /// A delegation signer, referring to a DNSSEC key of a child zone.
+    Ds {
+        /// Tag of the referenced key.
+        key_tag: u16,
+        /// DNSSEC algorithm number of the key.
+        algorithm: u8,
+        /// Digest algorithm number, e.g. 2 for SHA-256.
+        digest_type: u8,
+        /// Hex-encoded digest of the key.
+        digest: String,
+    },
+    /// A record type without a typed representation, kept as the raw API data.
+    Other {
+        /// Type of the record.
+        record_type: String,
+        /// The raw data string.
+        data: String,
+        /// The priority, if the record has one.
+        priority: Option<u32>,
+    },
+}
+
+impl RecordData {
+    /// Parse the API's `data` and `priority` fields for a record of `record_type`.
+    pub fn parse(
+        record_type: &str,
+        data: &str,
+        priority: Option<u32>,
+    ) -> Result<Self, RecordDataError> {
+        let err = |message: &str| RecordDataError {
+            record_type: record_type.to_string(),
+            data: data.to_string(),
+            message: message.to_string(),
+        };
+        let name = || data.trim().trim_end_matches('.').to_string();
+        Ok(match record_type.to_ascii_uppercase().as_str() {
+            "A" => RecordData::A(
+                data.trim()
+                    .parse()
+                    .map_err(|_| err("not an IPv4 address"))?,
+            ),
+            "AAAA" => RecordData::Aaaa(
+                data.trim()
+                    .parse()
+                    .map_err(|_| err("not an IPv6 address"))?,
+            ),
+            "CNAME" => RecordData::Cname(name()),
+            "NS" => RecordData::Ns(name()),
+            "PTR" => RecordData::Ptr(name()),
+            "TXT" => RecordData::Txt(data.to_string()),
+            "MX" => RecordData::Mx {
+                priority: priority.ok_or_else(|| err("missing priority"))?,
+                exchange: name(),
+            },
+            "SRV" => {
+                let fields: Vec<&str> = data.split_whitespace().collect();
+                let [weight, port, target] = fields[..] else {
+                    return Err(err("expected \"weight port target\""));
+                };
+                RecordData::Srv {
+                    priority: priority.ok_or_else(|| err("missing priority"))?,
+                    weight: weight.parse().map_err(|_| err("invalid weight"))?,
+                    port: port.parse().map_err(|_| err("invalid port"))?,
+                    target: target.trim_end_matches('.').to_string(),
+                }
+            }
+            "CAA" => {
+                let mut fields = data.trim().splitn(3, char::is_whitespace);
+                let (Some(flags), Some(tag), Some(value)) =
+                    (fields.next(), fields.next(), fields.next())
+                else {
+                    return Err(err("expected \"flags tag value\""));
+                };
+                let value = value.trim();
+                RecordData::Caa {
+                    flags: flags.parse().map_err(|_| err("invalid flags"))?,
+                    tag: tag.to_string(),
+                    value: value
+                        .strip_prefix('"')
+                        .and_then(|v| v.strip_suffix('"'))
+                        .unwrap_or(value)
+                        .to_string(),
+                }
+            }
+            "NAPTR" => {
+                let fields = split_quoted(data).ok_or_else(|| err("unbalanced quotes"))?;
+                let [order, preference, flags, service, regexp, replacement] = &fields[..] else {
+                    return Err(err(
+                        "expected \"order preference flags service regexp replacement\"",
+                    ));
+                };
+                RecordData::Naptr {
+                    order: order.parse().map_err(|_| err("invalid order"))?,
+                    preference: preference.parse().map_err(|_| err("invalid preference"))?,
+                    flags: flags.clone(),
+                    service: service.clone(),
+                    regexp: regexp.clone(),
+                    replacement: match replacement.as_str() {
+                        "." => ".".to_string(),
+                        name => name.trim_end_matches('.').to_string(),
+                    },
+                }
+            }
+            "LOC" => parse_loc(data).ok_or_else(|| err("invalid location"))?,
+            "DS" => {
+                let mut fields = data.split_whitespace();
+                let (Some(key_tag), Some(algorithm), Some(digest_type)) =
+                    (fields.next(), fields.next(), fields.next())
+                else {
+                    return Err(err("expected \"key_tag algorithm digest_type digest\""));
+                };
+                let digest: String = fields.collect();
+                if digest.is_empty() || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return Err(err("invalid digest"));
+                }
+                RecordData::Ds {
+                    key_tag: key_tag.parse().map_err(|_| err("invalid key tag"))?,
+                    algorithm: algorithm.parse().map_err(|_| err("invalid algorithm"))?,
+                    digest_type: digest_type
+                        .parse()
+                        .map_err(|_| err("invalid digest type"))?,
+                    digest,
+                }
+            }
+            _ => RecordData::Other {
+                record_type: record_type.to_string(),
+                data: data.to_string(),
+                priority,
+            },
+        })
+    }
+
+    /// The DNS record type of this data ("A", "MX", etc.).
+    pub fn record_type(&self) -> &str {
+        match self {
+            RecordData::A(_) => "A",
+            RecordData::Aaaa(_) => "AAAA",
+            RecordData::Cname(_) => "CNAME",
+            RecordData::Mx { .. } => "MX",
+            RecordData::Ns(_) => "NS",
+            RecordData::Ptr(_) => "PTR",
+            RecordData::Txt(_) => "TXT",
+            RecordData::Srv { .. } => "SRV",
+            RecordData::Caa { .. } => "CAA",
+            RecordData::Naptr { .. } => "NAPTR",
+            RecordData::Loc { .. } => "LOC",
+            RecordData::Ds { .. } => "DS",
+            RecordData::Other { record_type, .. } => record_type,
+        }
+    }
+
+    /// The value of the API's `data` field for this data.
+    pub fn data(&self) -> String {
+        match self {
+            RecordData::A(ip) => ip.to_string(),
+            RecordData::Aaaa(ip) => ip.to_string(),
+            RecordData::Cname(name) | RecordData::Ns(name) | RecordData::Ptr(name) => name.clone(),
+            RecordData::Mx { exchange, .. } => exchange.clone(),
+            RecordData::Txt(text) => text.clone(),
+            RecordData::Srv {
+                weight,
+                port,
+                target,
+                ..
+            } => format!("{weight} {port} {target}"),
+            RecordData::Caa { flags, tag, value } => format!("{flags} {tag} \"{value}\""),
+            RecordData::Naptr {
+                order,
+                preference,
+                flags,
+                service,
+                regexp,
+                replacement,
+            } => format!(
+                "{order} {preference} \"{}\" \"{}\" \"{}\" {replacement}",
+                escape_quoted(flags),
+                escape_quoted(service),
+                escape_quoted(regexp),
+            ),
+            RecordData::Loc {
+                latitude,
+                longitude,
+                altitude,
+                size,
+                horizontal_precision,
+                vertical_precision,
+            } => format!(
+                "{} {} {}m {}m {}m {}m",
+                format_coordinate(*latitude, 'N', 'S'),
+                format_coordinate(*longitude, 'E', 'W'),
+                format_centimeters(*altitude),
+                format_centimeters(*size as i64),
+                format_centimeters(*horizontal_precision as i64),
+                format_centimeters(*vertical_precision as i64),
+            ),
+            RecordData::Ds {
+                key_tag,
+                algorithm,
+                digest_type,
+                digest,
+            } => format!("{key_tag} {algorithm} {digest_type} {digest}"),
+            RecordData::Other { data, .. } => data.clone(),
+        }
+    }
+
+    /// The value of the API's `priority` field for this data.
+    pub fn priority(&self) -> Option<u32> {
+        match self {
+            RecordData::Mx { priority, .. } | RecordData::Srv { priority, .. } => Some(*priority),
+            RecordData::Other { priority, .. } => *priority,
+            _ => None,
+        }
+    }
+}
+
+/// Split `data` on whitespace, treating `"..."` as one field without the quotes.
+pub(crate) fn split_quoted(data: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut chars = data.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut field = String::new();
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => field.push(chars.next()?),
+                    c => field.push(c),
+                }
+            }
+            fields.push(field);
+        } else {
+            let mut field = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                field.push(c);
+            }
+            fields.push(field);
+        }
+    }
+    Some(fields)
+}
+
+fn escape_quoted(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Parse LOC data: `d [m [s]] N|S d [m [s]] E|W alt[m] [size[m] [hp[m] [vp[m]]]]`.
+fn parse_loc(data: &str) -> Option<RecordData> {
+    let mut fields = data.split_whitespace().peekable();
+    let mut coordinate = |positive: char, negative: char| -> Option<i64> {
+        let mut milliseconds = 0;
+        for scale in [3_600_000, 60_000] {
+            match fields.next_if(|f| f.bytes().all(|b| b.is_ascii_digit() || b == b'.')) {
+                Some(f) => milliseconds += f.parse::<i64>().ok()? * scale,
+                None => break,
+            }
+        }
+        if let Some(f) = fields.next_if(|f| f.bytes().all(|b| b.is_ascii_digit() || b == b'.')) {
+            milliseconds += parse_fixed(f, 3)?;
+        }
+        match fields.next()? {
+            d if d.eq_ignore_ascii_case(&positive.to_string()) => Some(milliseconds),
+            d if d.eq_ignore_ascii_case(&negative.to_string()) => Some(-milliseconds),
+            _ => None,
+        }
+    };
+    let latitude = coordinate('N', 'S')?;
+    let longitude = coordinate('E', 'W')?;
+    let altitude = parse_fixed(fields.next()?.trim_end_matches(['m', 'M']), 2)?;
+    let mut meters = |default: i64| -> Option<i64> {
+        match fields.next() {
+            Some(f) => parse_fixed(f.trim_end_matches(['m', 'M']), 2),
+            None => Some(default),
+        }
+    };
+    let size = meters(100)?;
+    let horizontal_precision = meters(1_000_000)?;
+    let vertical_precision = meters(1_000)?;
+    if fields.next().is_some() || size < 0 || horizontal_precision < 0 || vertical_precision < 0 {
+        return None;
+    }
+    Some(RecordData::Loc {
+        latitude,
+        longitude,
+        altitude,
+        size: size as u64,
+        horizontal_precision: horizontal_precision as u64,
+        vertical_precision: vertical_precision as u64,
+    })
+}
+
+/// Parse a decimal number with at most `decimals` fractional digits, scaled to an integer.
+fn parse_fixed(text: &str, decimals: u32) -> Option<i64> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let (int, frac) = text.split_once('.').unwrap_or((text, ""));
+    if int.is_empty() || frac.len() > decimals as usize || !frac.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let scale = 10i64.pow(decimals);
+    let frac_value = format!("{frac:0<width$}", width = decimals as usize);
+    let value = int.parse::<i64>().ok()? * scale + frac_value.parse::<i64>().unwrap_or(0);
+    Some(if negative { -value } else { value })
+}
+
+fn format_coordinate(milliseconds: i64, positive: char, negative: char) -> String {
+    let hemisphere = if milliseconds < 0 { negative } else { positive };
+    let ms = milliseconds.unsigned_abs();
+    format!(
+        "{} {} {}.{:03} {hemisphere}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+fn format_centimeters(centimeters: i64) -> String {
+    let sign = if centimeters < 0 { "-" } else { "" };
+    let cm = centimeters.unsigned_abs();
+    format!("{sign}{}.{:02}", cm / 100, cm % 100)
+}
+
+impl fmt::Display for RecordData {
+    /// Formats the data like the RDATA of a zone file entry, e.g. `10 mail.example.com`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.priority() {
+            Some(priority) => write!(f, "{priority} {}", self.data()),
+            None => f.write_str(&self.data()),
+        }
+    }
+}
+
+/// An SRV record, including the `_service._proto` name it is published at.
+///
+/// Builds the API's flat `name`, `data` and `priority` fields from their
+/// parts, and reads them back from listed records.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::api::CreateDnsRecordRequest;
+/// use simply_dns::record_data::SrvRecord;
+///
+/// let srv = SrvRecord::new("sip", "tcp", "sip.example.com", 5060)
+///     .priority(10)
+///     .weight(5);
+/// let req = CreateDnsRecordRequest::from(srv);
+/// assert_eq!(req.name, "_sip._tcp");
+/// assert_eq!(req.data, "5 5060 sip.example.com");
+/// assert_eq!(req.priority, Some(10));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrvRecord {
+    /// The service name without the leading underscore, e.g. "sip".
+    pub service: String,
+    /// The protocol without the leading underscore, e.g. "tcp".
+    pub proto: String,
+    /// Priority of the target host; lower is preferred.
+    pub priority: u32,
+    /// Relative weight among targets with the same priority.
+    pub weight: u16,
+    /// Port the service listens on.
+    pub port: u16,
+    /// Host name providing the service.
+    pub target: String,
+}
+
+impl SrvRecord {
+    /// A record for `service` over `proto` at `target:port`, with priority and weight 0.
+    pub fn new(
+        service: impl Into<String>,
+        proto: impl Into<String>,
+        target: impl Into<String>,
+        port: u16,
+    ) -> Self {
+        Self {
+            service: service.into().trim_start_matches('_').to_string(),
+            proto: proto.into().trim_start_matches('_').to_string(),
+            priority: 0,
+            weight: 0,
+            port,
+            target: target.into().trim_end_matches('.').to_string(),
+        }
+    }
+
+    /// Set the priority; lower is preferred.
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Set the weight among targets with the same priority.
+    pub fn weight(mut self, weight: u16) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// The record name, e.g. "_sip._tcp".
+    pub fn name(&self) -> String {
+        format!("_{}._{}", self.service, self.proto)
+    }
+
+    /// Read an SRV record from the API's `name`, `data` and `priority` fields.
+    ///
+    /// Only the first two labels of `name` are used, so zone-relative and
+    /// fully qualified names both work.
+    pub fn parse(name: &str, data: &str, priority: Option<u32>) -> Result<Self, RecordDataError> {
+        let mut labels = name.split('.');
+        let (Some(service), Some(proto)) = (
+            labels.next().and_then(|l| l.strip_prefix('_')),
+            labels.next().and_then(|l| l.strip_prefix('_')),
+        ) else {
+            return Err(RecordDataError {
+                record_type: "SRV".to_string(),
+                data: name.to_string(),
+                message: "name must start with _service._proto".to_string(),
+            });
+        };
+        match RecordData::parse("SRV", data, priority)? {
+            RecordData::Srv {
+                priority,
+                weight,
+                port,
+                target,
+            } => Ok(Self {
+                service: service.to_string(),
+                proto: proto.to_string(),
+                priority,
+                weight,
+                port,
+                target,
+            }),
+            _ => unreachable!("SRV data parses as RecordData::Srv"),
+        }
+    }
+}
+
+impl From<SrvRecord> for RecordData {
+    fn from(srv: SrvRecord) -> Self {
+        RecordData::Srv {
+            priority: srv.priority,
+            weight: srv.weight,
+            port: srv.port,
+            target: srv.target,
+        }
+    }
+}
+
+impl From<SrvRecord> for CreateDnsRecordRequest {
+    fn from(srv: SrvRecord) -> Self {
+        CreateDnsRecordRequest::new(srv.name(), srv.into())
+    }
+}
+
+impl TryFrom<&DnsRecord> for SrvRecord {
+    type Error = RecordDataError;
+
+    fn try_from(record: &DnsRecord) -> Result<Self, Self::Error> {
+        if !record.record_type.eq_ignore_ascii_case("SRV") {
+            return Err(RecordDataError {
+                record_type: record.record_type.clone(),
+                data: record.data.clone(),
+                message: "not an SRV record".to_string(),
+            });
+        }
+        Self::parse(&record.name, &record.data, record.priority)
+    }
+}
+
+/// A CAA record, restricting which certificate authorities may issue for a name.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::api::CreateDnsRecordRequest;
+/// use simply_dns::record_data::CaaRecord;
+///
+/// let req = CreateDnsRecordRequest::new("@", CaaRecord::issue("letsencrypt.org").into());
+/// assert_eq!(req.data, "0 issue \"letsencrypt.org\"");
+///
+/// let iodef = CaaRecord::iodef("mailto:security@example.com").critical();
+/// assert_eq!(iodef.flags, 128);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaaRecord {
+    /// Flags; 128 marks the property as critical.
+    pub flags: u8,
+    /// The property, e.g. "issue", "issuewild" or "iodef".
+    pub tag: String,
+    /// The property value, e.g. "letsencrypt.org".
+    pub value: String,
+}
+
+impl CaaRecord {
+    /// A record with property `tag` set to `value`.
+    pub fn new(tag: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            flags: 0,
+            tag: tag.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Allow `ca`, e.g. "letsencrypt.org", to issue certificates. An empty
+    /// value forbids issuance altogether.
+    pub fn issue(ca: impl Into<String>) -> Self {
+        Self::new("issue", ca)
+    }
+
+    /// Allow `ca` to issue wildcard certificates.
+    pub fn issuewild(ca: impl Into<String>) -> Self {
+        Self::new("issuewild", ca)
+    }
+
+    /// Report policy violations to `url`, e.g. "mailto:security@example.com".
+    pub fn iodef(url: impl Into<String>) -> Self {
+        Self::new("iodef", url)
+    }
+
+    /// Mark the property as critical: CAs that don't understand it must not issue.
+    pub fn critical(mut self) -> Self {
+        self.flags |= 128;
+        self
+    }
+
+    /// Read a CAA record from the API's `data` field.
+    pub fn parse(data: &str) -> Result<Self, RecordDataError> {
+        match RecordData::parse("CAA", data, None)? {
+            RecordData::Caa { flags, tag, value } => Ok(Self { flags, tag, value }),
+            _ => unreachable!("CAA data parses as RecordData::Caa"),
+        }
+    }
+}
+
+impl From<CaaRecord> for RecordData {
+    fn from(caa: CaaRecord) -> Self {
+        RecordData::Caa {
+            flags: caa.flags,
+            tag: caa.tag,
+            value: caa.value,
+        }
+    }
+}
+
+impl TryFrom<&DnsRecord> for CaaRecord {
+    type Error = RecordDataError;
+
+    fn try_from(record: &DnsRecord) -> Result<Self, Self::Error> {
+        if !record.record_type.eq_ignore_ascii_case("CAA") {
+            return Err(RecordDataError {
+                record_type: record.record_type.clone(),
+                data: record.data.clone(),
+                message: "not a CAA record".to_string(),
+            });
+        }
+        Self::parse(&record.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse data, check that it gives back the same fields and return it.
+    fn round_trip(record_type: &str, data: &str, priority: Option<u32>) -> RecordData {
+        let parsed = RecordData::parse(record_type, data, priority)
+            .unwrap_or_else(|e| panic!("{record_type} {data:?}: {e}"));
+        assert_eq!(parsed.record_type(), record_type.to_ascii_uppercase());
+        assert_eq!(parsed.data(), data, "{record_type}");
+        assert_eq!(parsed.priority(), priority, "{record_type} {data:?}");
+        parsed
+    }
+
+    fn rejected(record_type: &str, data: &str, priority: Option<u32>) -> String {
+        match RecordData::parse(record_type, data, priority) {
+            Ok(parsed) => panic!("{record_type} {data:?} parsed as {parsed:?}"),
+            Err(e) => {
+                assert_eq!(e.record_type, record_type);
+                assert_eq!(e.data, data);
+                e.message
+            }
+        }
+    }
+
+    #[test]
+    fn addresses_round_trip() {
+        assert_eq!(
+            round_trip("A", "192.0.2.1", None),
+            RecordData::A(Ipv4Addr::new(192, 0, 2, 1))
+        );
+        assert_eq!(
+            round_trip("aaaa", "2001:db8::1", None),
+            RecordData::Aaaa("2001:db8::1".parse().unwrap())
+        );
+        // Surrounding whitespace is tolerated, and IPv6 is normalized.
+        let padded = RecordData::parse("AAAA", " 2001:DB8:0:0::1 ", None).unwrap();
+        assert_eq!(padded.data(), "2001:db8::1");
+    }
+
+    #[test]
+    fn malformed_addresses_are_rejected() {
+        assert_eq!(rejected("A", "192.0.2", None), "not an IPv4 address");
+        assert_eq!(rejected("A", "2001:db8::1", None), "not an IPv4 address");
+        assert_eq!(rejected("AAAA", "192.0.2.1", None), "not an IPv6 address");
+        assert_eq!(rejected("AAAA", "2001:db8::g", None), "not an IPv6 address");
+    }
+
+    #[test]
+    fn names_round_trip_without_the_trailing_dot() {
+        for record_type in ["CNAME", "NS", "PTR"] {
+            round_trip(record_type, "target.example.com", None);
+            let dotted = RecordData::parse(record_type, "target.example.com.", None).unwrap();
+            assert_eq!(dotted.data(), "target.example.com");
+        }
+        assert_eq!(
+            round_trip("CNAME", "www.example.com", None),
+            RecordData::Cname("www.example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn txt_is_kept_verbatim() {
+        for text in ["v=spf1 -all", "\"quoted\" text", "  spaced  ", ""] {
+            assert_eq!(
+                round_trip("TXT", text, None),
+                RecordData::Txt(text.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn mx_takes_its_priority() {
+        assert_eq!(
+            round_trip("MX", "mail.example.com", Some(10)),
+            RecordData::Mx {
+                priority: 10,
+                exchange: "mail.example.com".to_string()
+            }
+        );
+        assert_eq!(rejected("MX", "mail.example.com", None), "missing priority");
+    }
+
+    #[test]
+    fn srv_and_caa_round_trip() {
+        assert_eq!(
+            round_trip("SRV", "5 5060 sip.example.com", Some(10)),
+            RecordData::Srv {
+                priority: 10,
+                weight: 5,
+                port: 5060,
+                target: "sip.example.com".to_string()
+            }
+        );
+        assert_eq!(
+            round_trip("CAA", "0 issue \"letsencrypt.org\"", None),
+            RecordData::Caa {
+                flags: 0,
+                tag: "issue".to_string(),
+                value: "letsencrypt.org".to_string()
+            }
+        );
+        round_trip("CAA", "128 iodef \"mailto:security@example.com\"", None);
+        round_trip("CAA", "0 issue \"\"", None);
+        let unquoted = RecordData::parse("CAA", "0 issuewild letsencrypt.org", None).unwrap();
+        assert_eq!(unquoted.data(), "0 issuewild \"letsencrypt.org\"");
+    }
+
+    #[test]
+    fn malformed_srv_and_caa_are_rejected() {
+        for (data, priority, message) in [
+            ("5 5060", Some(10), "expected \"weight port target\""),
+            (
+                "5 5060 sip.example.com extra",
+                Some(10),
+                "expected \"weight port target\"",
+            ),
+            ("5 5060 sip.example.com", None, "missing priority"),
+            ("heavy 5060 sip.example.com", Some(10), "invalid weight"),
+            ("5 70000 sip.example.com", Some(10), "invalid port"),
+        ] {
+            assert_eq!(rejected("SRV", data, priority), message, "{data}");
+        }
+        for (data, message) in [
+            ("0 issue", "expected \"flags tag value\""),
+            ("256 issue \"ca.example\"", "invalid flags"),
+            ("critical issue \"ca.example\"", "invalid flags"),
+        ] {
+            assert_eq!(rejected("CAA", data, None), message, "{data}");
+        }
+    }
+
+    #[test]
+    fn naptr_loc_and_ds_round_trip() {
+        assert_eq!(
+            round_trip(
+                "NAPTR",
+                "100 10 \"U\" \"E2U+sip\" \"!^.*$!sip:info@example.com!\" .",
+                None
+            ),
+            RecordData::Naptr {
+                order: 100,
+                preference: 10,
+                flags: "U".to_string(),
+                service: "E2U+sip".to_string(),
+                regexp: "!^.*$!sip:info@example.com!".to_string(),
+                replacement: ".".to_string()
+            }
+        );
+        round_trip(
+            "NAPTR",
+            "10 0 \"s\" \"SIP+D2U\" \"\" _sip._udp.example.com",
+            None,
+        );
+        round_trip(
+            "NAPTR",
+            "1 1 \"u\" \"E2U+web\" \"!^(.*)$!\\\\1\\\"!\" .",
+            None,
+        );
+        let dotted =
+            RecordData::parse("NAPTR", "10 0 s SIP+D2U \"\" _sip._udp.example.com.", None).unwrap();
+        assert_eq!(
+            dotted.data(),
+            "10 0 \"s\" \"SIP+D2U\" \"\" _sip._udp.example.com"
+        );
+
+        assert_eq!(
+            round_trip(
+                "LOC",
+                "52 22 23.000 N 4 53 32.000 E -2.00m 0.00m 10000.00m 10.00m",
+                None
+            ),
+            RecordData::Loc {
+                latitude: (52 * 3600 + 22 * 60 + 23) * 1000,
+                longitude: (4 * 3600 + 53 * 60 + 32) * 1000,
+                altitude: -200,
+                size: 0,
+                horizontal_precision: 1_000_000,
+                vertical_precision: 1_000
+            }
+        );
+        round_trip(
+            "LOC",
+            "33 51 35.500 S 151 12 40.250 W 10.50m 1.00m 10000.00m 10.00m",
+            None,
+        );
+        let short = RecordData::parse("LOC", "42 N 71 W 0m", None).unwrap();
+        assert_eq!(
+            short.data(),
+            "42 0 0.000 N 71 0 0.000 W 0.00m 1.00m 10000.00m 10.00m"
+        );
+
+        assert_eq!(
+            round_trip("DS", "60485 8 2 49FD46E6C4B45C55D4AC", None),
+            RecordData::Ds {
+                key_tag: 60485,
+                algorithm: 8,
+                digest_type: 2,
+                digest: "49FD46E6C4B45C55D4AC".to_string()
+            }
+        );
+        let split = RecordData::parse("DS", "60485 8 2 49FD46E6 C4B45C55", None).unwrap();
+        assert_eq!(split.data(), "60485 8 2 49FD46E6C4B45C55");
+    }
+
+    #[test]
+    fn malformed_naptr_loc_and_ds_are_rejected() {
+        for (data, message) in [
+            ("100 10 \"U\" \"E2U+sip\" \"!^.*$!x!", "unbalanced quotes"),
+            (
+                "100 10 \"U\" \"E2U+sip\" .",
+                "expected \"order preference flags service regexp replacement\"",
+            ),
+            ("first 10 U E2U+sip \"\" .", "invalid order"),
+            ("100 -1 U E2U+sip \"\" .", "invalid preference"),
+        ] {
+            assert_eq!(rejected("NAPTR", data, None), message, "{data}");
+        }
+        for data in [
+            "52 22 23 X 4 53 32 E 0m",
+            "52 22 23 N 4 53 32 N 0m",
+            "52 22 23 N 4 53 32 E",
+            "52 22 23 N 4 53 32 E 0.001m",
+            "52 22 23 N 4 53 32 E 0m -1m",
+            "52 22 23 N 4 53 32 E 0m 1m 1m 1m 1m",
+        ] {
+            assert_eq!(rejected("LOC", data, None), "invalid location", "{data}");
+        }
+        for (data, message) in [
+            ("60485 8 2", "invalid digest"),
+            ("60485 8 2 49FD46ZZ", "invalid digest"),
+            (
+                "60485 8",
+                "expected \"key_tag algorithm digest_type digest\"",
+            ),
+            ("70000 8 2 49FD", "invalid key tag"),
+            ("60485 RSASHA256 2 49FD", "invalid algorithm"),
+            ("60485 8 SHA256 49FD", "invalid digest type"),
+        ] {
+            assert_eq!(rejected("DS", data, None), message, "{data}");
+        }
+    }
+
+    #[test]
+    fn other_types_keep_the_raw_data() {
+        let parsed = round_trip("SSHFP", "1 2 123456789abcdef", None);
+        assert!(matches!(parsed, RecordData::Other { .. }));
+        round_trip("TYPE65534", "\\# 4 0a000001", Some(3));
+    }
+
+    #[test]
+    fn display_shows_the_priority_first() {
+        let mx = RecordData::parse("MX", "mail.example.com", Some(10)).unwrap();
+        assert_eq!(mx.to_string(), "10 mail.example.com");
+        let a = RecordData::parse("A", "192.0.2.1", None).unwrap();
+        assert_eq!(a.to_string(), "192.0.2.1");
+    }
+
+    fn listed(record_type: &str, name: &str, data: &str, priority: Option<u32>) -> DnsRecord {
+        DnsRecord {
+            record_id: crate::api::DnsRecordId::new(1),
+            name: name.to_string(),
+            ttl: 3600,
+            data: data.to_string(),
+            record_type: record_type.to_string(),
+            priority,
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn srv_records_round_trip_through_their_fields() {
+        let srv = SrvRecord::new("_sip", "_tcp", "sip.example.com.", 5060)
+            .priority(10)
+            .weight(5);
+        let request = CreateDnsRecordRequest::from(srv.clone());
+        assert_eq!(
+            (
+                request.name.as_str(),
+                request.data.as_str(),
+                request.priority
+            ),
+            ("_sip._tcp", "5 5060 sip.example.com", Some(10))
+        );
+        assert_eq!(
+            SrvRecord::parse(&request.name, &request.data, request.priority).unwrap(),
+            srv
+        );
+        let record = listed(
+            "srv",
+            "_sip._tcp.example.com",
+            "5 5060 sip.example.com",
+            Some(10),
+        );
+        assert_eq!(SrvRecord::try_from(&record).unwrap(), srv);
+    }
+
+    #[test]
+    fn malformed_srv_records_are_rejected() {
+        for name in ["sip._tcp", "_sip", "_sip.tcp", "@"] {
+            let e = SrvRecord::parse(name, "5 5060 sip.example.com", Some(10)).unwrap_err();
+            assert_eq!(e.message, "name must start with _service._proto", "{name}");
+        }
+        let e = SrvRecord::parse("_sip._tcp", "5 5060", Some(10)).unwrap_err();
+        assert_eq!(e.message, "expected \"weight port target\"");
+        let a = listed("A", "_sip._tcp", "192.0.2.1", None);
+        assert_eq!(
+            SrvRecord::try_from(&a).unwrap_err().message,
+            "not an SRV record"
+        );
+    }
+
+    #[test]
+    fn caa_records_round_trip_through_their_fields() {
+        for caa in [
+            CaaRecord::issue("letsencrypt.org"),
+            CaaRecord::issuewild(""),
+            CaaRecord::iodef("mailto:security@example.com").critical(),
+            CaaRecord::new("tbs", "Unknown"),
+        ] {
+            let data = RecordData::from(caa.clone());
+            assert_eq!(CaaRecord::parse(&data.data()).unwrap(), caa);
+            let record = listed("CAA", "@", &data.data(), None);
+            assert_eq!(CaaRecord::try_from(&record).unwrap(), caa);
+        }
+        assert_eq!(
+            CaaRecord::issue("ca.example").critical().critical().flags,
+            128
+        );
+    }
+
+    #[test]
+    fn malformed_caa_records_are_rejected() {
+        assert_eq!(
+            CaaRecord::parse("high issue \"letsencrypt.org\"")
+                .unwrap_err()
+                .message,
+            "invalid flags"
+        );
+        assert_eq!(
+            CaaRecord::parse("0").unwrap_err().message,
+            "expected \"flags tag value\""
+        );
+        let txt = listed("TXT", "@", "0 issue \"letsencrypt.org\"", None);
+        assert_eq!(
+            CaaRecord::try_from(&txt).unwrap_err().message,
+            "not a CAA record"
+        );
+    }
+}
@@ -0,0 +1,123 @@
+//! Uniform outcome reporting for bulk operations.
+
+use std::fmt;
+use std::time::Duration;
+
+use crate::SimplyClientError;
+use crate::time::Instant;
+
+/// An item that was processed successfully.
+#[derive(Debug)]
+pub struct Succeeded<T> {
+    /// The processed item.
+    pub item: T,
+    /// How long processing the item took.
+    pub elapsed: Duration,
+}
+
+/// An item that failed.
+#[derive(Debug)]
+pub struct Failed<T> {
+    /// The item that failed.
+    pub item: T,
+    /// The error it failed with.
+    pub error: SimplyClientError,
+    /// How long processing the item took before it failed.
+    pub elapsed: Duration,
+}
+
+/// An item that was not processed.
+#[derive(Debug)]
+pub struct Skipped<T> {
+    /// The skipped item.
+    pub item: T,
+    /// Why the item was skipped.
+    pub reason: String,
+}
+
+/// The outcome of a bulk operation, item by item.
+///
+/// Bulk operations keep going when an item fails and collect the outcome of
+/// every item here, so one failure doesn't hide the results of the rest.
+#[derive(Debug)]
+pub struct OperationReport<T> {
+    /// Items that were processed successfully.
+    pub succeeded: Vec<Succeeded<T>>,
+    /// Items that failed, with their errors.
+    pub failed: Vec<Failed<T>>,
+    /// Items that were not processed, with the reason.
+    pub skipped: Vec<Skipped<T>>,
+    /// Total duration of the operation.
+    pub elapsed: Duration,
+}
+
+impl<T> Default for OperationReport<T> {
+    fn default() -> Self {
+        Self {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+            skipped: Vec::new(),
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+impl<T> OperationReport<T> {
+    /// Whether no item failed.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Total number of items in the report.
+    pub fn len(&self) -> usize {
+        self.succeeded.len() + self.failed.len() + self.skipped.len()
+    }
+
+    /// Whether the report contains no items.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Add the outcome of processing `item`, which started at `started`.
+    pub fn record(&mut self, item: T, result: Result<(), SimplyClientError>, started: Instant) {
+        let elapsed = started.elapsed();
+        match result {
+            Ok(()) => self.succeeded.push(Succeeded { item, elapsed }),
+            Err(error) => self.failed.push(Failed {
+                item,
+                error,
+                elapsed,
+            }),
+        }
+    }
+
+    /// Add an item that was skipped for `reason`.
+    pub fn skip(&mut self, item: T, reason: impl Into<String>) {
+        self.skipped.push(Skipped {
+            item,
+            reason: reason.into(),
+        });
+    }
+
+    /// Turn the report into an error if any item failed, returning the first failure.
+    pub fn into_result(mut self) -> Result<Self, SimplyClientError> {
+        if self.failed.is_empty() {
+            Ok(self)
+        } else {
+            Err(self.failed.remove(0).error)
+        }
+    }
+}
+
+impl<T> fmt::Display for OperationReport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} succeeded, {} failed, {} skipped in {:.2?}",
+            self.succeeded.len(),
+            self.failed.len(),
+            self.skipped.len(),
+            self.elapsed
+        )
+    }
+}
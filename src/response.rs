@@ -0,0 +1,205 @@
+//! HTTP details of API responses.
+//!
+//! The plain client methods return only the parsed payload. Their
+//! `*_with_meta` variants, e.g.
+//! [`SimplyClient::list_dns_records_with_meta`](crate::SimplyClient::list_dns_records_with_meta),
+//! also return a [`ResponseMeta`] with the status, headers and raw body, for
+//! debugging API issues or watching the rate limit.
+//!
+//! A body that doesn't match the expected schema is reported as
+//! [`SimplyClientError::UnexpectedResponse`] with the raw body. Record lists
+//! can instead be read leniently, leaving out just the malformed records;
+//! see [`ResponseParsing`] and
+//! [`SimplyClient::list_dns_records_lenient`](crate::SimplyClient::list_dns_records_lenient).
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let response = client.list_dns_records_with_meta("example.com").await?;
+//! if let Some(meta) = &response.meta {
+//!     println!("{} (request {:?}, {:?} calls left)", meta.status, meta.request_id(), meta.rate_limit_remaining());
+//! }
+//! println!("{} records", response.data.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use reqwest::Response;
+use reqwest::header::HeaderMap;
+
+use crate::SimplyClientError;
+use crate::api::{DnsRecord, DnsRecordResponse, GeneralResponse};
+
+/// How a client deals with records it can't parse, see
+/// [`SimplyClientBuilder::response_parsing`](crate::SimplyClientBuilder::response_parsing).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResponseParsing {
+    /// A malformed record fails the whole call with
+    /// [`SimplyClientError::UnexpectedResponse`].
+    #[default]
+    Strict,
+    /// Malformed records are left out of record lists and logged with the
+    /// `tracing` feature. The response as a whole must still be valid.
+    Lenient,
+}
+
+/// A record the API returned that doesn't match the expected schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MalformedRecord {
+    /// The record's position in the response.
+    pub index: usize,
+    /// The record as returned.
+    pub raw: serde_json::Value,
+    /// Why it couldn't be parsed.
+    pub message: String,
+}
+
+/// The records of a domain, from [`SimplyClient::list_dns_records_lenient`](crate::SimplyClient::list_dns_records_lenient).
+#[derive(Debug, Clone, Default)]
+pub struct LenientRecords {
+    /// The records that could be parsed.
+    pub records: Vec<DnsRecord>,
+    /// The records that couldn't.
+    pub malformed: Vec<MalformedRecord>,
+}
+
+/// A parsed payload together with the response it came from.
+#[derive(Debug, Clone)]
+pub struct ApiResponse<T> {
+    /// The parsed payload, as returned by the plain method.
+    pub data: T,
+    /// The response, or `None` if no request was sent, e.g. in dry-run mode.
+    pub meta: Option<ResponseMeta>,
+}
+
+impl<T> ApiResponse<T> {
+    /// Transform the payload, keeping the metadata.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> ApiResponse<U> {
+        ApiResponse {
+            data: f(self.data),
+            meta: self.meta,
+        }
+    }
+}
+
+/// The HTTP details of a successful response.
+#[derive(Debug, Clone)]
+pub struct ResponseMeta {
+    /// The HTTP status code.
+    pub status: u16,
+    /// The response headers.
+    pub headers: HeaderMap,
+    /// The raw response body.
+    pub body: String,
+}
+
+/// The error reported in the `status` field of `body`, if any.
+pub(crate) fn body_error(body: &str) -> Option<SimplyClientError> {
+    match serde_json::from_str(body) {
+        Ok(GeneralResponse {
+            status: Some(reported @ 400..600),
+            ..
+        }) => Some(SimplyClientError::from_body(
+            reported,
+            None,
+            body.to_string(),
+        )),
+        _ => None,
+    }
+}
+
+impl ResponseMeta {
+    /// Read the status, headers and body of a successful `res`.
+    ///
+    /// The API also reports errors in the `status` field of the body; a body
+    /// with an error status fails like an error response would, with the
+    /// server's message, before the payload is parsed.
+    pub(crate) async fn read(res: Response) -> Result<Self, SimplyClientError> {
+        let status = res.status().as_u16();
+        let headers = res.headers().clone();
+        let body = res.text().await?;
+        if let Some(e) = body_error(&body) {
+            return Err(e);
+        }
+        Ok(Self {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    /// The `status` field of the body, e.g. 200, if the API sent one.
+    pub fn api_status(&self) -> Option<u16> {
+        self.general().and_then(|r| r.status)
+    }
+
+    /// The `message` field of the body, e.g. "success", if the API sent one.
+    pub fn api_message(&self) -> Option<String> {
+        self.general().and_then(|r| r.message)
+    }
+
+    fn general(&self) -> Option<GeneralResponse> {
+        serde_json::from_str(&self.body).ok()
+    }
+
+    /// The value of the header `name`, if it is present and valid text.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)?.to_str().ok()
+    }
+
+    /// The ID the server or a proxy assigned to the request, from the
+    /// `X-Request-Id` or `X-Correlation-Id` header.
+    pub fn request_id(&self) -> Option<&str> {
+        self.header("x-request-id")
+            .or_else(|| self.header("x-correlation-id"))
+    }
+
+    /// How many requests may be made in the current window, from the
+    /// `X-RateLimit-Limit` header.
+    pub fn rate_limit_limit(&self) -> Option<u32> {
+        self.header("x-ratelimit-limit")?.trim().parse().ok()
+    }
+
+    /// How many requests are left in the current window, from the
+    /// `X-RateLimit-Remaining` header.
+    pub fn rate_limit_remaining(&self) -> Option<u32> {
+        self.header("x-ratelimit-remaining")?.trim().parse().ok()
+    }
+
+    /// Parse the body as JSON.
+    pub(crate) fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, SimplyClientError> {
+        serde_json::from_str(&self.body).map_err(|source| self.unexpected(source))
+    }
+
+    /// Parse the body as a record list, setting malformed records aside.
+    pub(crate) fn records_lenient(&self) -> Result<LenientRecords, SimplyClientError> {
+        #[derive(serde::Deserialize)]
+        struct RawList {
+            records: Vec<serde_json::Value>,
+        }
+        let list: RawList = self.json()?;
+        let mut parsed = LenientRecords::default();
+        for (index, raw) in list.records.into_iter().enumerate() {
+            match serde_json::from_value::<DnsRecordResponse>(raw.clone()) {
+                Ok(record) => parsed.records.push(record.into()),
+                Err(e) => parsed.malformed.push(MalformedRecord {
+                    index,
+                    raw,
+                    message: e.to_string(),
+                }),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn unexpected(&self, source: serde_json::Error) -> SimplyClientError {
+        SimplyClientError::UnexpectedResponse {
+            status: self.status,
+            body: self.body.clone(),
+            source,
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Retry policy with exponential backoff and jitter.
+
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// HTTP status codes retried by [`RetryPolicy::default`] and reported by
+/// [`SimplyClientError::is_retryable`](crate::SimplyClientError::is_retryable).
+pub(crate) const TRANSIENT_STATUSES: [u16; 4] = [500, 502, 503, 504];
+
+/// Controls how failed requests are retried.
+///
+/// Requests are retried when the API answers with one of `retry_statuses`, or
+/// when the request fails at the network level and `retry_network_errors` is set.
+/// The delay before retry `n` is `base_delay * 2^(n-1)`, capped at `max_delay`.
+///
+/// `POST` requests (record creation) are not idempotent: retrying them after the
+/// server received the request can create duplicate records. They are therefore
+/// only retried on connection errors, unless `retry_non_idempotent` is set.
+///
+/// 429 Too Many Requests responses are handled separately: they are retried
+/// after the `Retry-After` delay and don't count towards `max_attempts`.
+///
+/// Example usage:
+/// ```rust
+/// use std::time::Duration;
+/// use simply_dns::{RetryPolicy, SimplyClient};
+///
+/// let client = SimplyClient::builder()
+///     .credentials("account", "api_key")
+///     .retry(RetryPolicy {
+///         max_attempts: 5,
+///         base_delay: Duration::from_millis(500),
+///         ..RetryPolicy::default()
+///     })
+///     .build()?;
+/// # Ok::<(), simply_dns::SimplyClientError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub base_delay: Duration,
+    /// Upper bound for the delay between attempts.
+    pub max_delay: Duration,
+    /// Randomize each delay between half and all of its computed value.
+    pub jitter: bool,
+    /// HTTP status codes that are considered transient.
+    pub retry_statuses: Vec<u16>,
+    /// Retry requests that failed because of network errors (connect, timeout, etc.).
+    pub retry_network_errors: bool,
+    /// Also retry `POST` requests on transient statuses and timeouts.
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(10),
+            jitter: true,
+            retry_statuses: TRANSIENT_STATUSES.to_vec(),
+            retry_network_errors: true,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// The delay to wait after `attempt` (starting at 1) failed.
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        if !self.jitter {
+            return delay;
+        }
+        let half = delay / 2;
+        let spread = half.as_nanos() as u64;
+        let offset = if spread == 0 {
+            0
+        } else {
+            random_u64() % spread
+        };
+        half + Duration::from_nanos(offset)
+    }
+
+    pub(crate) fn retries_status(&self, status: u16, idempotent: bool) -> bool {
+        (idempotent || self.retry_non_idempotent) && self.retry_statuses.contains(&status)
+    }
+
+    pub(crate) fn retries_error(&self, err: &reqwest::Error, idempotent: bool) -> bool {
+        if !self.retry_network_errors {
+            return false;
+        }
+        // A connect error means the request never reached the server.
+        is_connect(err) || ((idempotent || self.retry_non_idempotent) && transient_error(err))
+    }
+}
+
+/// Whether a request failed at the network level in a way worth retrying.
+pub(crate) fn transient_error(err: &reqwest::Error) -> bool {
+    is_connect(err) || err.is_timeout() || err.is_request()
+}
+
+/// Whether `err` means the request never reached the server.
+#[cfg(not(target_arch = "wasm32"))]
+fn is_connect(err: &reqwest::Error) -> bool {
+    err.is_connect()
+}
+
+/// reqwest can't tell connect errors apart on wasm32.
+#[cfg(target_arch = "wasm32")]
+fn is_connect(_err: &reqwest::Error) -> bool {
+    false
+}
+
+/// A random number from the process' hash seed, good enough to spread out retries.
+pub(crate) fn random_u64() -> u64 {
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+}
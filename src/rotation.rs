@@ -0,0 +1,256 @@
+//! Moving an address record to a new IP without downtime.
+//!
+//! [`RecordRotation`] performs the usual choreography of a blue/green cutover:
+//! optionally lower the TTL of the old record and wait for caches to pick it
+//! up, publish the new address, wait until it resolves everywhere, remove the
+//! old address, and restore the TTL.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::rotation::{RecordRotation, RotationStrategy};
+//!
+//! # async fn run() -> Result<(), simply_dns::rotation::RotationError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! RecordRotation::new(&client, "example.com", "www")
+//!     .strategy(RotationStrategy::AddThenRemove)
+//!     .lower_ttl(60)
+//!     .rotate("192.0.2.20".parse().unwrap())
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord, UpdateDnsRecordRequest};
+use crate::propagation::{PropagationChecker, PropagationError};
+use crate::query::RecordQuery;
+use crate::{Observability, SimplyClient, SimplyClientError};
+
+/// Error type for record rotations.
+#[derive(Debug, Error)]
+pub enum RotationError {
+    /// The Simply.com API call failed.
+    #[error(transparent)]
+    Client(#[from] SimplyClientError),
+    /// The new address didn't resolve everywhere in time. The old address is
+    /// still published alongside it.
+    #[error(transparent)]
+    Propagation(#[from] PropagationError),
+}
+
+/// How [`RecordRotation`] replaces the old address.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RotationStrategy {
+    /// Create a record with the new address next to the old one, and delete
+    /// the old one once the new one has propagated. Both addresses are served
+    /// in between, so both must be reachable.
+    #[default]
+    AddThenRemove,
+    /// Change the data of the existing record, so only one address is ever
+    /// published. Caches keep serving the old address until their TTL runs out.
+    InPlace,
+}
+
+/// Replaces the address of an A or AAAA record, see the [module docs](self).
+///
+/// The record type follows from the new address. Records of that type with
+/// other addresses at the name are replaced; without any, the new record is
+/// just created.
+pub struct RecordRotation<'a> {
+    client: &'a SimplyClient,
+    domain: String,
+    name: String,
+    strategy: RotationStrategy,
+    lowered_ttl: Option<u32>,
+    checker: Option<PropagationChecker>,
+    propagation_timeout: Duration,
+}
+
+impl<'a> RecordRotation<'a> {
+    /// Rotate the record `name` in `domain`.
+    pub fn new(
+        client: &'a SimplyClient,
+        domain: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Self {
+        Self {
+            client,
+            domain: domain.into(),
+            name: name.into(),
+            strategy: RotationStrategy::default(),
+            lowered_ttl: None,
+            checker: Some(PropagationChecker::default()),
+            propagation_timeout: Duration::from_secs(300),
+        }
+    }
+
+    /// Replace the old address with `strategy`. Defaults to [`RotationStrategy::AddThenRemove`].
+    pub fn strategy(mut self, strategy: RotationStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Lower the TTL of the old records to `ttl` first, and wait for their
+    /// previous TTL to pass so that caches switch quickly afterwards. The
+    /// previous TTL is restored on the new record at the end.
+    pub fn lower_ttl(mut self, ttl: u32) -> Self {
+        self.lowered_ttl = Some(ttl);
+        self
+    }
+
+    /// Wait for the new address to resolve on the servers `checker` queries,
+    /// or not at all with `None`. Defaults to [`PropagationChecker::default`].
+    ///
+    /// Not used if the client's [`Observability`] is off.
+    pub fn propagation_checker(mut self, checker: Option<PropagationChecker>) -> Self {
+        self.checker = checker;
+        self
+    }
+
+    /// How long to wait for the new address to propagate. Defaults to 5 minutes.
+    pub fn propagation_timeout(mut self, timeout: Duration) -> Self {
+        self.propagation_timeout = timeout;
+        self
+    }
+
+    /// Point the record at `new_ip`.
+    ///
+    /// If a step fails, the records are left as they are at that point; with
+    /// [`RotationStrategy::AddThenRemove`] the old address is only removed once
+    /// the new one resolves, so a failed rotation keeps serving it.
+    pub async fn rotate(&self, new_ip: IpAddr) -> Result<(), RotationError> {
+        let record_type = match new_ip {
+            IpAddr::V4(_) => "A",
+            IpAddr::V6(_) => "AAAA",
+        };
+        let name = self.client.api_name(&self.name, &self.domain);
+        let data = new_ip.to_string();
+        let query = RecordQuery::new().name(&self.name).record_type(record_type);
+        let (current, old): (Vec<DnsRecord>, Vec<DnsRecord>) = self
+            .client
+            .find_records(&self.domain, &query)
+            .await?
+            .into_iter()
+            .partition(|r| r.data.parse::<IpAddr>().ok() == Some(new_ip));
+        if old.is_empty() && !current.is_empty() {
+            return Ok(());
+        }
+        let original_ttl = old.iter().map(|r| r.ttl).max();
+
+        if let (Some(ttl), Some(original)) = (self.lowered_ttl, original_ttl)
+            && ttl < original
+        {
+            for record in &old {
+                self.update(record, &name, &record.data, ttl).await?;
+            }
+            tokio::time::sleep(Duration::from_secs(original.into())).await;
+        }
+        let ttl = self.lowered_ttl.or(original_ttl);
+
+        let mut remove = old.iter();
+        if current.is_empty() {
+            let in_place = match self.strategy {
+                RotationStrategy::InPlace => remove.next(),
+                RotationStrategy::AddThenRemove => None,
+            };
+            if let Some(record) = in_place {
+                self.update(record, &name, &data, ttl.unwrap_or(record.ttl))
+                    .await?;
+            } else {
+                let req = CreateDnsRecordRequest {
+                    record_type: record_type.to_string(),
+                    name: name.clone(),
+                    data: data.clone(),
+                    priority: None,
+                    ttl,
+                    comment: None,
+                };
+                self.client.create_dns_record(&self.domain, req).await?;
+            }
+        }
+
+        // With observability off, the client must not query DNS servers itself.
+        if let Some(checker) = &self.checker
+            && self.client.observability() == Observability::On
+        {
+            checker
+                .wait_for_record(
+                    &self.domain,
+                    &name,
+                    record_type,
+                    &data,
+                    self.propagation_timeout,
+                )
+                .await?;
+        }
+        for record in remove {
+            self.client
+                .delete_dns_record(&self.domain, record.record_id)
+                .await?;
+        }
+
+        if let (Some(lowered), Some(original)) = (self.lowered_ttl, original_ttl)
+            && lowered < original
+        {
+            let query = RecordQuery::new()
+                .name(&self.name)
+                .record_type(record_type)
+                .data_contains(&data);
+            for record in self.client.find_records(&self.domain, &query).await? {
+                if record.data == data {
+                    self.update(&record, &name, &data, original).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Set `record` to `data` with `ttl`, keeping its other fields.
+    async fn update(
+        &self,
+        record: &DnsRecord,
+        name: &str,
+        data: &str,
+        ttl: u32,
+    ) -> Result<(), SimplyClientError> {
+        let req = UpdateDnsRecordRequest {
+            name: name.to_string(),
+            data: data.to_string(),
+            ttl: Some(ttl),
+            ..record.clone().into()
+        };
+        self.client
+            .update_dns_record(&self.domain, record.record_id, req)
+            .await
+    }
+}
+
+impl SimplyClient {
+    /// Point the A or AAAA record `name` at `new_ip` without downtime.
+    ///
+    /// Waits for the new address to propagate before removing the old one;
+    /// use [`RecordRotation`] to lower the TTL first or tune the waiting.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the record belongs to.
+    /// * `name` - The record name, e.g. "www".
+    /// * `new_ip` - The address to rotate to; its family selects A or AAAA.
+    /// * `strategy` - Whether to add the new address before removing the old one, or edit in place.
+    pub async fn rotate_a_record(
+        &self,
+        domain: &str,
+        name: &str,
+        new_ip: IpAddr,
+        strategy: RotationStrategy,
+    ) -> Result<(), RotationError> {
+        RecordRotation::new(self, domain, name)
+            .strategy(strategy)
+            .rotate(new_ip)
+            .await
+    }
+}
@@ -0,0 +1,435 @@
+//! Record sets: the records sharing a name and type.
+//!
+//! DNS answers with whole record sets (RRsets), e.g. every `A` record of
+//! `www`, while the API deals in single records. [`SimplyClient::list_rrsets`]
+//! groups the records of a zone into [`RrSet`]s, and [`SimplyClient::replace_rrset`],
+//! [`SimplyClient::add_to_rrset`] and [`SimplyClient::remove_from_rrset`]
+//! change a set with as few creates, updates and deletes as possible.
+//!
+//! For load balancing by DNS, [`SimplyClient::set_round_robin`] makes a name
+//! resolve to exactly a list of addresses, managing its A and AAAA sets
+//! together, and [`SimplyClient::set_weighted_round_robin`] repeats
+//! addresses in proportion to their weights.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::rrset::RrValue;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! for set in client.list_rrsets("example.com").await? {
+//!     println!("{} {} {:?}", set.name, set.record_type, set.data());
+//! }
+//! let values = [RrValue::new("10.0.0.1"), RrValue::new("10.0.0.2")];
+//! let report = client
+//!     .replace_rrset("example.com", "www", "A", &values, Some(300))
+//!     .await?;
+//! println!("{report}");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord};
+use crate::canonical::same_data;
+use crate::report::OperationReport;
+use crate::sync::{Change, DesiredRecord, DesiredZone, ZonePlan};
+use crate::{SimplyClient, SimplyClientError};
+
+/// The most records [`SimplyClient::set_weighted_round_robin`] creates for a name.
+pub const MAX_WEIGHTED_RECORDS: usize = 100;
+
+/// The records of a zone sharing a name and type, from [`SimplyClient::list_rrsets`].
+#[derive(Debug, Clone)]
+pub struct RrSet {
+    /// The name, in the client's [name style](crate::SimplyClientBuilder::name_style).
+    pub name: String,
+    /// The record type, e.g. "A".
+    pub record_type: String,
+    /// The records of the set, with names as in `name`.
+    pub records: Vec<DnsRecord>,
+}
+
+impl RrSet {
+    /// The data of every record in the set.
+    pub fn data(&self) -> Vec<&str> {
+        self.records.iter().map(|r| r.data.as_str()).collect()
+    }
+
+    /// The lowest TTL of the records, which is how long resolvers cache the set.
+    pub fn ttl(&self) -> Option<u32> {
+        self.records.iter().map(|r| r.ttl).min()
+    }
+
+    /// Whether the records disagree on their TTL, which RFC 2181 forbids.
+    pub fn has_mixed_ttls(&self) -> bool {
+        self.records.windows(2).any(|w| w[0].ttl != w[1].ttl)
+    }
+}
+
+/// One value of a record set: the data and, for MX and SRV records, the priority.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RrValue {
+    /// Data for the record (IP, target, etc.).
+    pub data: String,
+    /// Priority value for records that require it.
+    pub priority: Option<u32>,
+}
+
+impl RrValue {
+    /// A value with `data` and no priority.
+    pub fn new(data: impl Into<String>) -> Self {
+        Self {
+            data: data.into(),
+            priority: None,
+        }
+    }
+
+    /// Set the priority.
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    fn matches(&self, record: &DnsRecord) -> bool {
+        same_data(&record.record_type, &record.data, &self.data)
+            && self.priority.is_none_or(|p| Some(p) == record.priority)
+    }
+}
+
+impl From<&str> for RrValue {
+    fn from(data: &str) -> Self {
+        Self::new(data)
+    }
+}
+
+impl From<String> for RrValue {
+    fn from(data: String) -> Self {
+        Self::new(data)
+    }
+}
+
+impl SimplyClient {
+    /// List the records of a domain grouped into record sets.
+    ///
+    /// Sets are ordered by name and type, and names are returned in the
+    /// client's [name style](crate::SimplyClientBuilder::name_style).
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to list record sets for.
+    pub async fn list_rrsets(&self, domain: &str) -> Result<Vec<RrSet>, SimplyClientError> {
+        let mut sets: BTreeMap<(String, String), RrSet> = BTreeMap::new();
+        for mut record in self.list_dns_records(domain).await? {
+            record.name = self.styled_name(&record.name, domain);
+            let key = (
+                record.name.to_ascii_lowercase(),
+                record.record_type.to_ascii_uppercase(),
+            );
+            sets.entry(key)
+                .or_insert_with(|| RrSet {
+                    name: record.name.clone(),
+                    record_type: record.record_type.clone(),
+                    records: Vec::new(),
+                })
+                .records
+                .push(record);
+        }
+        Ok(sets.into_values().collect())
+    }
+
+    /// Make the record set `name`/`record_type` consist of exactly `values`.
+    ///
+    /// Records that already hold a value are kept, surplus records are
+    /// updated to the missing values before any are created or deleted, so
+    /// the set is never empty while it changes. An empty `values` deletes the
+    /// set. Fails only if the records can't be listed.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the record set belongs to.
+    /// * `name` - The record name, in either style.
+    /// * `record_type` - The record type, e.g. "A".
+    /// * `values` - The values the set should hold.
+    /// * `ttl` - The TTL of every record in the set, or `None` to keep the current TTLs.
+    pub async fn replace_rrset(
+        &self,
+        domain: &str,
+        name: &str,
+        record_type: &str,
+        values: &[RrValue],
+        ttl: Option<u32>,
+    ) -> Result<OperationReport<Change>, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let name = this.api_name(name, domain);
+        let current = this.rrset_records(domain, &name, record_type).await?;
+        let plan = rrset_plan(domain, &name, record_type, current, values, ttl);
+        Ok(this.apply(plan).await)
+    }
+
+    /// Make `name` resolve to exactly `ips`, round-robin.
+    ///
+    /// IPv4 addresses make up the A set and IPv6 addresses the AAAA set,
+    /// each replaced like [`replace_rrset`](Self::replace_rrset) does, so a
+    /// list without IPv6 addresses deletes the AAAA records of the name.
+    /// Repeated addresses are kept once; see
+    /// [`set_weighted_round_robin`](Self::set_weighted_round_robin) to
+    /// repeat them. Fails only if the records can't be listed.
+    ///
+    /// Example usage:
+    /// ```rust,no_run
+    /// use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    /// use simply_dns::SimplyClient;
+    ///
+    /// # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+    /// let client = SimplyClient::new("account", "api_key");
+    /// let ips = [
+    ///     IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+    ///     IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)),
+    ///     IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+    /// ];
+    /// let report = client.set_round_robin("example.com", "www", &ips, Some(60)).await?;
+    /// println!("{report}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the name belongs to.
+    /// * `name` - The record name, in either style.
+    /// * `ips` - The addresses the name should resolve to.
+    /// * `ttl` - The TTL of every record, or `None` to keep the current TTLs.
+    pub async fn set_round_robin(
+        &self,
+        domain: &str,
+        name: &str,
+        ips: &[IpAddr],
+        ttl: Option<u32>,
+    ) -> Result<OperationReport<Change>, SimplyClientError> {
+        let mut unique: Vec<IpAddr> = Vec::new();
+        for ip in ips {
+            if !unique.contains(ip) {
+                unique.push(*ip);
+            }
+        }
+        self.set_address_sets(domain, name, &unique, ttl).await
+    }
+
+    /// Make `name` resolve to the addresses of `weights`, each repeated in
+    /// proportion to its weight, so resolvers picking at random send it that
+    /// share of the traffic.
+    ///
+    /// Weights are divided by their greatest common divisor, so weights of 2
+    /// and 4 give one record and two. Addresses with a weight of 0 are left
+    /// out. Fails with [`SimplyClientError::Validation`] if that still takes
+    /// more than [`MAX_WEIGHTED_RECORDS`] records, and otherwise only if the
+    /// records can't be listed. See [`set_round_robin`](Self::set_round_robin)
+    /// for how the A and AAAA sets are replaced.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the name belongs to.
+    /// * `name` - The record name, in either style.
+    /// * `weights` - The addresses and their weights.
+    /// * `ttl` - The TTL of every record, or `None` to keep the current TTLs.
+    pub async fn set_weighted_round_robin(
+        &self,
+        domain: &str,
+        name: &str,
+        weights: &[(IpAddr, u32)],
+        ttl: Option<u32>,
+    ) -> Result<OperationReport<Change>, SimplyClientError> {
+        let mut merged: Vec<(IpAddr, u32)> = Vec::new();
+        for &(ip, weight) in weights.iter().filter(|(_, w)| *w > 0) {
+            match merged.iter_mut().find(|(m, _)| *m == ip) {
+                Some((_, total)) => *total = total.saturating_add(weight),
+                None => merged.push((ip, weight)),
+            }
+        }
+        let divisor = merged.iter().fold(0, |d, &(_, w)| gcd(d, w)).max(1);
+        let count: u64 = merged.iter().map(|&(_, w)| u64::from(w / divisor)).sum();
+        if count > MAX_WEIGHTED_RECORDS as u64 {
+            return Err(SimplyClientError::Validation {
+                status: None,
+                field: Some("weights".to_string()),
+                message: format!("weights need {count} records, more than {MAX_WEIGHTED_RECORDS}"),
+            });
+        }
+        let ips: Vec<IpAddr> = merged
+            .iter()
+            .flat_map(|&(ip, w)| std::iter::repeat_n(ip, (w / divisor) as usize))
+            .collect();
+        self.set_address_sets(domain, name, &ips, ttl).await
+    }
+
+    /// Replace the A and AAAA sets of `name` with `ips`, in one plan.
+    async fn set_address_sets(
+        &self,
+        domain: &str,
+        name: &str,
+        ips: &[IpAddr],
+        ttl: Option<u32>,
+    ) -> Result<OperationReport<Change>, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let name = this.api_name(name, domain);
+        let records = this.list_dns_records(domain).await?;
+        let mut changes = Vec::new();
+        for (record_type, v6) in [("A", false), ("AAAA", true)] {
+            let current = records
+                .iter()
+                .filter(|r| {
+                    r.name.eq_ignore_ascii_case(&name)
+                        && r.record_type.eq_ignore_ascii_case(record_type)
+                })
+                .cloned()
+                .collect();
+            let values: Vec<RrValue> = ips
+                .iter()
+                .filter(|ip| ip.is_ipv6() == v6)
+                .map(|ip| RrValue::new(ip.to_string()))
+                .collect();
+            changes.extend(rrset_plan(domain, &name, record_type, current, &values, ttl).changes);
+        }
+        // Keep creates and updates of both sets ahead of any deletes.
+        changes.sort_by_key(|c| matches!(c, Change::Delete(_)));
+        let plan = ZonePlan {
+            domain: domain.to_string(),
+            changes,
+        };
+        Ok(this.apply(plan).await)
+    }
+
+    /// Add `values` to the record set `name`/`record_type`, creating it if needed.
+    ///
+    /// Values the set already holds are skipped. New records get the lowest
+    /// TTL of the set, so the set keeps a single TTL. Fails only if the
+    /// records can't be listed.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the record set belongs to.
+    /// * `name` - The record name, in either style.
+    /// * `record_type` - The record type, e.g. "A".
+    /// * `values` - The values to add.
+    pub async fn add_to_rrset(
+        &self,
+        domain: &str,
+        name: &str,
+        record_type: &str,
+        values: &[RrValue],
+    ) -> Result<OperationReport<Change>, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let name = this.api_name(name, domain);
+        let current = this.rrset_records(domain, &name, record_type).await?;
+        let ttl = current.iter().map(|r| r.ttl).min();
+        let mut changes: Vec<Change> = Vec::new();
+        for value in values {
+            let planned = changes
+                .iter()
+                .any(|c| matches!(c, Change::Create(req) if same_data(record_type, &req.data, &value.data)));
+            if planned || current.iter().any(|r| value.matches(r)) {
+                continue;
+            }
+            changes.push(Change::Create(CreateDnsRecordRequest {
+                record_type: record_type.to_string(),
+                name: name.clone(),
+                data: value.data.clone(),
+                priority: value.priority,
+                ttl,
+                comment: None,
+            }));
+        }
+        let plan = ZonePlan {
+            domain: domain.to_string(),
+            changes,
+        };
+        Ok(this.apply(plan).await)
+    }
+
+    /// Remove `values` from the record set `name`/`record_type`.
+    ///
+    /// Every record holding one of the values is deleted; other records of
+    /// the set are left alone, and values the set doesn't hold are ignored.
+    /// A value without a priority matches records with any priority. Fails
+    /// only if the records can't be listed.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the record set belongs to.
+    /// * `name` - The record name, in either style.
+    /// * `record_type` - The record type, e.g. "A".
+    /// * `values` - The values to remove.
+    pub async fn remove_from_rrset(
+        &self,
+        domain: &str,
+        name: &str,
+        record_type: &str,
+        values: &[RrValue],
+    ) -> Result<OperationReport<Change>, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let name = this.api_name(name, domain);
+        let changes = this
+            .rrset_records(domain, &name, record_type)
+            .await?
+            .into_iter()
+            .filter(|r| values.iter().any(|v| v.matches(r)))
+            .map(Change::Delete)
+            .collect();
+        let plan = ZonePlan {
+            domain: domain.to_string(),
+            changes,
+        };
+        Ok(this.apply(plan).await)
+    }
+
+    /// The records of `domain` named `name`, in the API's form, of type `record_type`.
+    async fn rrset_records(
+        &self,
+        domain: &str,
+        name: &str,
+        record_type: &str,
+    ) -> Result<Vec<DnsRecord>, SimplyClientError> {
+        Ok(self
+            .list_dns_records(domain)
+            .await?
+            .into_iter()
+            .filter(|r| {
+                r.name.eq_ignore_ascii_case(name) && r.record_type.eq_ignore_ascii_case(record_type)
+            })
+            .collect())
+    }
+}
+
+/// The plan making the set `name`/`record_type` of `current` records hold
+/// exactly `values`, deleting last so the set keeps answering meanwhile.
+fn rrset_plan(
+    domain: &str,
+    name: &str,
+    record_type: &str,
+    current: Vec<DnsRecord>,
+    values: &[RrValue],
+    ttl: Option<u32>,
+) -> ZonePlan {
+    let desired: DesiredZone = values
+        .iter()
+        .map(|v| DesiredRecord {
+            ttl,
+            priority: v.priority,
+            ..DesiredRecord::new(name, record_type, v.data.as_str())
+        })
+        .collect();
+    let mut plan = ZonePlan::compute(domain, current, &desired);
+    let deletes = plan
+        .changes
+        .iter()
+        .take_while(|c| matches!(c, Change::Delete(_)))
+        .count();
+    plan.changes.rotate_left(deletes);
+    plan
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
@@ -0,0 +1,204 @@
+//! Pluggable persistence for state and history.
+//!
+//! Subsystems that remember things between runs (e.g. the address last published
+//! by the [`DdnsUpdater`](crate::ddns::DdnsUpdater)) store them through the
+//! [`StateStore`] trait, so deployments can choose where state lives. The crate
+//! ships an in-memory and a file-based store, and with the `s3` feature an
+//! `S3StateStore` in an S3-compatible bucket; other backends implement the
+//! trait. With the `encryption` feature, any store can be wrapped in an
+//! [`EncryptedStateStore`](crate::encryption::EncryptedStateStore).
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// Error type for [`StateStore`] operations.
+#[derive(Debug, Error)]
+pub enum StateError {
+    /// Reading or writing the underlying storage failed.
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    /// A stored value could not be (de)serialized.
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    /// A backend-specific error, e.g. from a remote store.
+    #[error("state backend error: {0}")]
+    Backend(String),
+    /// A value could not be encrypted or decrypted, e.g. because of a wrong key.
+    #[error("encryption error: {0}")]
+    Encryption(String),
+}
+
+/// A key-value store for persistent state.
+///
+/// Keys are `/`-separated paths such as `ddns/example.com/home/A`. Methods are
+/// blocking; stores backed by a network service should keep their calls short.
+pub trait StateStore: Send + Sync {
+    /// Read the value stored under `key`.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StateError>;
+    /// Store `value` under `key`, replacing any previous value.
+    fn put(&self, key: &str, value: &[u8]) -> Result<(), StateError>;
+    /// Remove `key`. Removing a missing key is not an error.
+    fn delete(&self, key: &str) -> Result<(), StateError>;
+    /// List all keys starting with `prefix`, in ascending order.
+    fn keys(&self, prefix: &str) -> Result<Vec<String>, StateError>;
+}
+
+impl dyn StateStore + '_ {
+    /// Read and deserialize the JSON value stored under `key`.
+    pub fn get_json<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, StateError> {
+        match self.get(key)? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Serialize `value` as JSON and store it under `key`.
+    pub fn put_json<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> Result<(), StateError> {
+        self.put(key, &serde_json::to_vec(value)?)
+    }
+}
+
+/// A [`StateStore`] keeping everything in memory, e.g. for tests or short-lived processes.
+#[derive(Debug, Default)]
+pub struct MemoryStateStore {
+    entries: Mutex<BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemoryStateStore {
+    /// An empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Vec<u8>>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl StateStore for MemoryStateStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StateError> {
+        Ok(self.entries().get(key).cloned())
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> Result<(), StateError> {
+        self.entries().insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), StateError> {
+        self.entries().remove(key);
+        Ok(())
+    }
+
+    fn keys(&self, prefix: &str) -> Result<Vec<String>, StateError> {
+        Ok(self
+            .entries()
+            .keys()
+            .filter(|k| k.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+}
+
+/// A [`StateStore`] keeping one file per key in a directory.
+///
+/// Writes go to a temporary file that is renamed into place, so a crash never
+/// leaves a half-written value behind.
+#[derive(Debug, Clone)]
+pub struct FileStateStore {
+    dir: PathBuf,
+}
+
+impl FileStateStore {
+    /// A store in `dir`, which is created if it doesn't exist.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, StateError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// The directory the store writes to.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(encode_key(key))
+    }
+}
+
+/// Encode `key` as a single file name, escaping everything but `[A-Za-z0-9._-]`.
+fn encode_key(key: &str) -> String {
+    let mut out = String::with_capacity(key.len());
+    for b in key.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'_' | b'-' => out.push(b as char),
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
+
+fn decode_key(name: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(name.len());
+    let mut iter = name.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+impl StateStore for FileStateStore {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StateError> {
+        match fs::read(self.path(key)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> Result<(), StateError> {
+        let path = self.path(key);
+        let tmp = self.dir.join(format!("{}.tmp~", encode_key(key)));
+        fs::write(&tmp, value)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), StateError> {
+        match fs::remove_file(self.path(key)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn keys(&self, prefix: &str) -> Result<Vec<String>, StateError> {
+        let mut keys = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let name = entry?.file_name();
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            if name.ends_with(".tmp~") {
+                continue;
+            }
+            if let Some(key) = decode_key(name).filter(|k| k.starts_with(prefix)) {
+                keys.push(key);
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+}
@@ -0,0 +1,254 @@
+//! Streaming the records of large zones.
+//!
+//! The API returns all records of a domain in one response and has no paging
+//! parameters. [`SimplyClient::list_dns_records_stream`] parses that response
+//! as it arrives and yields the records one at a time, so zones with thousands
+//! of entries can be processed without holding the whole list in memory.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use futures_util::StreamExt;
+//! use simply_dns::SimplyClient;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let records = client.list_dns_records_stream("example.com");
+//! let mut records = std::pin::pin!(records);
+//! while let Some(record) = records.next().await {
+//!     let record = record?;
+//!     println!("{} {} {}", record.name, record.record_type, record.data);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::io;
+
+use futures_util::Stream;
+use futures_util::stream;
+use reqwest::Response;
+
+use crate::api::{DnsRecord, DnsRecordResponse};
+use crate::response::{MalformedRecord, body_error};
+use crate::{SimplyClient, SimplyClientError};
+
+impl SimplyClient {
+    /// List the DNS records of a domain as a stream.
+    ///
+    /// Records are yielded as they are read from the response. A failed
+    /// request or a malformed record is yielded as an error, after which the
+    /// stream ends; with [`ResponseParsing::Lenient`](crate::response::ResponseParsing::Lenient)
+    /// malformed records are skipped instead.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to list DNS records for.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub fn list_dns_records_stream<'a>(
+        &'a self,
+        domain: &'a str,
+    ) -> impl Stream<Item = Result<DnsRecord, SimplyClientError>> + 'a {
+        let state = ListState::Start {
+            client: self,
+            domain,
+        };
+        stream::unfold(state, |state| async move {
+            let (client, domain, mut res, mut scanner, mut pending) = match state {
+                ListState::Start { client, domain } => {
+                    match client.list_dns_records_response(domain).await {
+                        Ok(res) => (
+                            client,
+                            domain,
+                            Box::new(res),
+                            RecordScanner::default(),
+                            VecDeque::new(),
+                        ),
+                        Err(e) => return Some((Err(e), ListState::Done)),
+                    }
+                }
+                ListState::Reading {
+                    client,
+                    domain,
+                    res,
+                    scanner,
+                    pending,
+                } => (client, domain, res, scanner, pending),
+                ListState::Done => return None,
+            };
+            loop {
+                if let Some(record) = pending.pop_front() {
+                    let record = match record {
+                        Ok(record) => record,
+                        Err(malformed) if client.lenient() => {
+                            client.log_malformed(domain, &malformed.report());
+                            continue;
+                        }
+                        Err(malformed) => {
+                            let e = SimplyClientError::UnexpectedResponse {
+                                status: res.status().as_u16(),
+                                body: String::from_utf8_lossy(&malformed.element).into_owned(),
+                                source: malformed.error,
+                            };
+                            return Some((Err(e), ListState::Done));
+                        }
+                    };
+                    let next = ListState::Reading {
+                        client,
+                        domain,
+                        res,
+                        scanner,
+                        pending,
+                    };
+                    return Some((Ok(record), next));
+                }
+                match res.chunk().await {
+                    Ok(Some(chunk)) => scanner.push(&chunk, &mut pending),
+                    // An error reported only in the body comes without records.
+                    Ok(None)
+                        if scanner.count == 0
+                            && let Some(e) =
+                                body_error(&String::from_utf8_lossy(&scanner.head)) =>
+                    {
+                        return Some((Err(e), ListState::Done));
+                    }
+                    Ok(None) if scanner.finished => return None,
+                    Ok(None) => {
+                        let e = io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "response ended before the end of the record list",
+                        );
+                        return Some((Err(e.into()), ListState::Done));
+                    }
+                    Err(e) => return Some((Err(e.into()), ListState::Done)),
+                }
+            }
+        })
+    }
+}
+
+enum ListState<'a> {
+    Start {
+        client: &'a SimplyClient,
+        domain: &'a str,
+    },
+    Reading {
+        client: &'a SimplyClient,
+        domain: &'a str,
+        res: Box<Response>,
+        scanner: RecordScanner,
+        pending: VecDeque<Result<DnsRecord, Malformed>>,
+    },
+    Done,
+}
+
+/// A record of the list that couldn't be parsed.
+struct Malformed {
+    index: usize,
+    element: Vec<u8>,
+    error: serde_json::Error,
+}
+
+impl Malformed {
+    fn report(&self) -> MalformedRecord {
+        MalformedRecord {
+            index: self.index,
+            raw: serde_json::from_slice(&self.element)
+                .unwrap_or_else(|_| String::from_utf8_lossy(&self.element).into_owned().into()),
+            message: self.error.to_string(),
+        }
+    }
+}
+
+/// Picks the elements of the top-level `records` array out of a JSON document
+/// fed to it in arbitrary chunks.
+#[derive(Default)]
+struct RecordScanner {
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    /// The last string seen directly in the top-level object.
+    last_string: Vec<u8>,
+    /// Whether `last_string` was followed by a colon, i.e. is a key.
+    after_key: bool,
+    in_records: bool,
+    /// The bytes of the record currently being read.
+    element: Vec<u8>,
+    /// How many records were read.
+    count: usize,
+    /// The body up to the first record, at most [`MAX_HEAD`] bytes.
+    head: Vec<u8>,
+    finished: bool,
+}
+
+/// How much of the body [`RecordScanner`] keeps to look for an error status.
+const MAX_HEAD: usize = 64 * 1024;
+
+impl RecordScanner {
+    fn push(&mut self, bytes: &[u8], out: &mut VecDeque<Result<DnsRecord, Malformed>>) {
+        if self.count == 0 && self.head.len() < MAX_HEAD {
+            let room = MAX_HEAD - self.head.len();
+            self.head.extend_from_slice(&bytes[..bytes.len().min(room)]);
+        }
+        for &b in bytes {
+            if self.finished {
+                return;
+            }
+            if self.in_records && self.depth >= 3 {
+                self.element.push(b);
+            }
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if b == b'\\' {
+                    self.escaped = true;
+                } else if b == b'"' {
+                    self.in_string = false;
+                } else if self.depth == 1 {
+                    self.last_string.push(b);
+                }
+                continue;
+            }
+            match b {
+                b'"' => {
+                    self.in_string = true;
+                    if self.depth == 1 {
+                        self.last_string.clear();
+                        self.after_key = false;
+                    }
+                }
+                b':' if self.depth == 1 => self.after_key = true,
+                b'{' | b'[' => {
+                    self.depth += 1;
+                    if self.depth == 2 && b == b'[' && self.after_key {
+                        self.in_records = self.last_string == b"records";
+                    }
+                    if self.in_records && self.depth == 3 {
+                        self.element.clear();
+                        self.element.push(b);
+                    }
+                }
+                b'}' | b']' => {
+                    if self.in_records && self.depth == 3 {
+                        let record = serde_json::from_slice::<DnsRecordResponse>(&self.element);
+                        out.push_back(match record {
+                            Ok(record) => Ok(record.into()),
+                            Err(error) => Err(Malformed {
+                                index: self.count,
+                                element: std::mem::take(&mut self.element),
+                                error,
+                            }),
+                        });
+                        self.count += 1;
+                        self.element.clear();
+                    }
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.in_records && self.depth == 1 {
+                        self.finished = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
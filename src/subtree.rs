@@ -0,0 +1,154 @@
+//! Deleting every record at and below a name, e.g. a whole staging environment.
+//!
+//! [`SimplyClient::plan_delete_subtree`] lists the records at and below a
+//! label, such as everything under `staging.example.com`, without changing
+//! anything. [`SimplyClient::delete_subtree`] shows the same list to a
+//! confirmation callback and deletes the records only if it agrees.
+//!
+//! Deletions run one at a time. When one fails with a transient error, the
+//! deletion backs off exponentially following the client's
+//! [`RetryPolicy`](crate::RetryPolicy), or its default, and tries that record
+//! again, so tearing down a large subtree doesn't run into the rate limit.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let report = client
+//!     .delete_subtree("example.com", "staging", |records| {
+//!         for record in records {
+//!             println!("- {} {} {}", record.name, record.record_type, record.data);
+//!         }
+//!         records.len() < 100
+//!     })
+//!     .await?;
+//! println!("{report}");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::api::DnsRecord;
+use crate::progress::Operation;
+use crate::report::OperationReport;
+use crate::time::Instant;
+use crate::{RetryPolicy, SimplyClient, SimplyClientError};
+
+impl SimplyClient {
+    /// List the records [`delete_subtree`](Self::delete_subtree) would delete.
+    ///
+    /// These are the records named `name_suffix` or ending in `.name_suffix`,
+    /// compared case-insensitively, deepest names first. Names are returned
+    /// in the client's [name style](crate::SimplyClientBuilder::name_style).
+    /// Fails if `name_suffix` is the apex, which would select the whole zone.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the records belong to.
+    /// * `name_suffix` - The top of the subtree, in either style, e.g. "staging".
+    pub async fn plan_delete_subtree(
+        &self,
+        domain: &str,
+        name_suffix: &str,
+    ) -> Result<Vec<DnsRecord>, SimplyClientError> {
+        let suffix = self.api_name(name_suffix, domain);
+        if suffix.is_empty() || suffix == "@" {
+            return Err(SimplyClientError::Validation {
+                status: None,
+                field: Some("name".to_string()),
+                message: format!("refusing to delete the whole zone {domain}"),
+            });
+        }
+        let mut records: Vec<_> = self
+            .list_dns_records(domain)
+            .await?
+            .into_iter()
+            .filter(|r| in_subtree(&r.name, &suffix))
+            .collect();
+        records.sort_by_key(|r| std::cmp::Reverse(r.name.split('.').count()));
+        for record in &mut records {
+            record.name = self.styled_name(&record.name, domain);
+        }
+        Ok(records)
+    }
+
+    /// Delete every record at and below `name_suffix` once `confirm` agrees.
+    ///
+    /// `confirm` is given the records of [`plan_delete_subtree`](Self::plan_delete_subtree);
+    /// if it returns `false`, nothing is deleted and every record is reported
+    /// as skipped. Records deleted by someone else in the meantime count as
+    /// deleted. Fails only if the records can't be listed; failed deletions
+    /// are collected in the report.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the records belong to.
+    /// * `name_suffix` - The top of the subtree, in either style, e.g. "staging".
+    /// * `confirm` - Decides, given the records, whether to delete them.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn delete_subtree(
+        &self,
+        domain: &str,
+        name_suffix: &str,
+        confirm: impl FnOnce(&[DnsRecord]) -> bool,
+    ) -> Result<OperationReport<DnsRecord>, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let started = Instant::now();
+        let records = this.plan_delete_subtree(domain, name_suffix).await?;
+        let mut report = OperationReport::default();
+        let mut progress = this.progress(Operation::DeleteSubtree, Some(records.len()));
+        if !confirm(&records) {
+            for record in records {
+                progress.skip(&mut report, record, "not confirmed");
+            }
+        } else {
+            let policy = this.retry_policy().cloned().unwrap_or_default();
+            for record in records {
+                progress.begin();
+                let record_started = Instant::now();
+                let result = this.delete_backing_off(domain, &record, &policy).await;
+                progress.record(&mut report, record, result, record_started);
+            }
+        }
+        report.elapsed = started.elapsed();
+        progress.finish(&report);
+        Ok(report)
+    }
+
+    /// Delete `record`, backing off and trying again while it fails transiently.
+    async fn delete_backing_off(
+        &self,
+        domain: &str,
+        record: &DnsRecord,
+        policy: &RetryPolicy,
+    ) -> Result<(), SimplyClientError> {
+        let mut attempt = 1;
+        loop {
+            match self.delete_dns_record(domain, record.record_id).await {
+                Err(e) if e.is_not_found() => return Ok(()),
+                Err(e) if e.is_retryable() && attempt < policy.max_attempts => {
+                    let delay = match e {
+                        SimplyClientError::RateLimited {
+                            retry_after: Some(wait),
+                        } => wait,
+                        _ => policy.delay_for(attempt),
+                    };
+                    crate::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Whether the API name `name` is `suffix` or below it.
+pub(crate) fn in_subtree(name: &str, suffix: &str) -> bool {
+    name.eq_ignore_ascii_case(suffix)
+        || name.len() > suffix.len()
+            && name.is_char_boundary(name.len() - suffix.len() - 1)
+            && name[name.len() - suffix.len() - 1..]
+                .strip_prefix('.')
+                .is_some_and(|tail| tail.eq_ignore_ascii_case(suffix))
+}
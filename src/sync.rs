@@ -0,0 +1,1303 @@
+//! Declarative zone reconciliation.
+//!
+//! Describe the records a zone should contain as a [`DesiredZone`], compute the
+//! creates/updates/deletes needed to get there with [`SimplyClient::plan`], and
+//! review or execute the resulting [`ZonePlan`] with [`SimplyClient::apply`].
+//! [`SimplyClient::sync`] does both, checking for records changed by others in
+//! between according to a [`ConflictStrategy`].
+//!
+//! Records are matched by name (case-insensitively) and type. Within such a
+//! group, records that already match are kept, records with the desired data
+//! get their TTL, priority or comment updated, remaining existing records are
+//! updated to the remaining desired data, and any surplus is created or deleted.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::sync::{DesiredRecord, DesiredZone};
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let desired: DesiredZone = [
+//!     DesiredRecord::new("@", "A", "192.0.2.10").ttl(3600),
+//!     DesiredRecord::new("www", "CNAME", "example.com"),
+//! ]
+//! .into_iter()
+//! .collect();
+//!
+//! let plan = client.plan("example.com", &desired).await?;
+//! println!("{}", plan.render());
+//! let report = client.apply(plan).await;
+//! println!("{report}");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A plan can be reviewed without applying it: [`ZonePlan::render`] shows it
+//! as a colored diff for a terminal, its `Display` does so without colors,
+//! and [`ZonePlan::to_json`] describes it for other tools, e.g. to attach it
+//! to an approval step in CI.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord, UpdateDnsRecordRequest};
+use crate::approval::Approval;
+use crate::canonical::{canonical_name_in, same_data};
+use crate::names::NameStyle;
+use crate::progress::{Operation, Progress};
+use crate::query::NameMatch;
+use crate::record_data::RecordData;
+use crate::report::OperationReport;
+use crate::time::Instant;
+use crate::{SimplyClient, SimplyClientError, SimplyDnsApi};
+
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// A record as it should exist in the zone.
+///
+/// `ttl`, `priority` and `comment` left as `None` are not compared, so the
+/// values already on the live record are kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesiredRecord {
+    /// The DNS record name (subdomain), e.g. "hello" in "hello.example.com".
+    pub name: String,
+    /// Type of DNS record ("A", "CNAME", "MX", etc.).
+    pub record_type: String,
+    /// Data for the record (IP, target, etc.).
+    pub data: String,
+    /// Time to live (TTL) for the record, in seconds.
+    pub ttl: Option<u32>,
+    /// Priority value for records that require it.
+    pub priority: Option<u32>,
+    /// Optional comment or metadata for the record.
+    pub comment: Option<String>,
+}
+
+impl DesiredRecord {
+    /// Create a desired record with no TTL, priority or comment.
+    pub fn new(
+        name: impl Into<String>,
+        record_type: impl Into<String>,
+        data: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            record_type: record_type.into(),
+            data: data.into(),
+            ttl: None,
+            priority: None,
+            comment: None,
+        }
+    }
+
+    /// Create a desired record named `name` with typed `data`.
+    pub fn from_data(name: impl Into<String>, data: RecordData) -> Self {
+        Self {
+            priority: data.priority(),
+            ..Self::new(name, data.record_type(), data.data())
+        }
+    }
+
+    /// Set the TTL, in seconds.
+    pub fn ttl(mut self, ttl: u32) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Set the priority.
+    pub fn priority(mut self, priority: u32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Set the comment.
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+
+    /// Whether `record` has this name and type, the names compared within `domain`.
+    fn same_key(&self, record: &DnsRecord, domain: &str) -> bool {
+        canonical_name_in(&self.name, domain) == canonical_name_in(&record.name, domain)
+            && self.record_type.eq_ignore_ascii_case(&record.record_type)
+    }
+
+    /// Whether `record` holds this data, compared in [canonical form](crate::canonical).
+    fn same_data(&self, record: &DnsRecord) -> bool {
+        same_data(&self.record_type, &record.data, &self.data)
+    }
+
+    /// Whether `record` differs from this one in a field that is set here, other than data.
+    ///
+    /// Without a TTL here, a live TTL below `min_ttl` counts as different.
+    fn attributes_differ(&self, record: &DnsRecord, min_ttl: Option<u32>) -> bool {
+        self.ttl.map_or_else(
+            || min_ttl.is_some_and(|min| record.ttl < min),
+            |ttl| ttl != record.ttl,
+        ) || self.priority.is_some_and(|p| Some(p) != record.priority)
+            || self
+                .comment
+                .as_ref()
+                .is_some_and(|c| Some(c) != record.comment.as_ref())
+    }
+
+    fn to_update(&self, current: &DnsRecord, min_ttl: Option<u32>) -> UpdateDnsRecordRequest {
+        UpdateDnsRecordRequest {
+            record_type: self.record_type.clone(),
+            name: self.name.clone(),
+            data: self.data.clone(),
+            priority: self.priority.or(current.priority),
+            ttl: self
+                .ttl
+                .or(Some(current.ttl.max(min_ttl.unwrap_or_default()))),
+            comment: self.comment.clone().or_else(|| current.comment.clone()),
+        }
+    }
+
+    fn to_create(&self) -> CreateDnsRecordRequest {
+        CreateDnsRecordRequest {
+            record_type: self.record_type.clone(),
+            name: self.name.clone(),
+            data: self.data.clone(),
+            priority: self.priority,
+            ttl: self.ttl,
+            comment: self.comment.clone(),
+        }
+    }
+}
+
+impl From<CreateDnsRecordRequest> for DesiredRecord {
+    fn from(req: CreateDnsRecordRequest) -> Self {
+        Self {
+            name: req.name,
+            record_type: req.record_type,
+            data: req.data,
+            ttl: req.ttl,
+            priority: req.priority,
+            comment: req.comment,
+        }
+    }
+}
+
+/// The full set of records a zone should contain.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DesiredZone {
+    /// The desired records.
+    pub records: Vec<DesiredRecord>,
+    /// How TTLs left unset on the records are filled in.
+    pub ttl_policy: TtlPolicy,
+    /// Records the sync never touches, see [`IgnoreRule`].
+    pub ignore: Vec<IgnoreRule>,
+    /// The ownership tag of the zone, see [`owned_by`](Self::owned_by).
+    pub owner: Option<String>,
+    /// The subtree the zone manages, see [`scoped_to`](Self::scoped_to).
+    pub scope: Option<String>,
+}
+
+impl DesiredZone {
+    /// An empty zone.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a record to the zone.
+    pub fn add(&mut self, record: impl Into<DesiredRecord>) -> &mut Self {
+        self.records.push(record.into());
+        self
+    }
+
+    /// Set the policy applied to the TTLs of the records.
+    pub fn with_ttl_policy(mut self, policy: TtlPolicy) -> Self {
+        self.ttl_policy = policy;
+        self
+    }
+
+    /// Leave records matching `rule` alone.
+    pub fn ignore(mut self, rule: IgnoreRule) -> Self {
+        self.ignore.push(rule);
+        self
+    }
+
+    /// Only manage records whose comment carries the tag `owner`, e.g. as
+    /// written by [`SimplyClient::adopt`](crate::SimplyClient::adopt).
+    ///
+    /// Live records without the tag are left alone, and records created or
+    /// updated with a comment get the tag, so a zone can be brought under
+    /// declarative control a few records at a time. A desired record that
+    /// exists without the tag is adopted: the plan updates it to add the tag
+    /// instead of creating it again.
+    pub fn owned_by(mut self, owner: impl Into<String>) -> Self {
+        self.owner = Some(owner.into());
+        self
+    }
+
+    /// Only manage the records at and below `name`, e.g. `dev` for
+    /// `dev.example.com` and everything under it, so a team can own its
+    /// subtree of a zone with a spec of its own.
+    ///
+    /// `name` is relative to the zone, or fully qualified with a trailing dot.
+    /// Live records outside the subtree are left alone, as if they were
+    /// [ignored](IgnoreRule), and desired records outside it are skipped.
+    ///
+    /// Example usage:
+    /// ```rust
+    /// use simply_dns::sync::{DesiredRecord, DesiredZone, ZonePlan};
+    ///
+    /// let desired = DesiredZone::from_iter([DesiredRecord::new("api.dev", "A", "192.0.2.20")])
+    ///     .scoped_to("dev");
+    /// let live = serde_json::from_value(serde_json::json!([
+    ///     { "record_id": { "id": 1 }, "name": "www", "type": "A", "data": "192.0.2.10",
+    ///       "ttl": 3600, "priority": null, "comment": null },
+    ///     { "record_id": { "id": 2 }, "name": "old.dev", "type": "A", "data": "192.0.2.21",
+    ///       "ttl": 3600, "priority": null, "comment": null },
+    /// ]))?;
+    /// // `api.dev` is created and `old.dev` deleted; `www` is outside the subtree.
+    /// let plan = ZonePlan::compute("example.com", live, &desired);
+    /// assert_eq!(plan.counts(), (1, 0, 1));
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn scoped_to(mut self, name: impl Into<String>) -> Self {
+        self.scope = Some(name.into());
+        self
+    }
+
+    /// Whether `name` is in the [scope](Self::scoped_to) of the zone, in `domain`.
+    fn in_scope(&self, name: &str, domain: &str) -> bool {
+        let Some(scope) = &self.scope else {
+            return true;
+        };
+        let scope = canonical_name_in(scope, domain);
+        let name = canonical_name_in(name, domain);
+        scope == "@" || crate::subtree::in_subtree(&name, &scope)
+    }
+
+    /// Whether a record with these properties is covered by an ignore rule.
+    fn is_ignored(&self, name: &str, record_type: &str, comment: Option<&str>) -> bool {
+        self.ignore
+            .iter()
+            .any(|rule| rule.matches(name, record_type, comment))
+    }
+
+    /// Add records for `name` with the data of the records of `same_as`, so
+    /// many names pointing at one target declare it once.
+    ///
+    /// Copies the records of `same_as` already in the zone, only those of
+    /// `record_type` if it is set, with their data, TTL and priority but not
+    /// their comments. The copies are ordinary records that the plan compares
+    /// like any other. Add the records being copied first; an alias of an
+    /// alias works once the first alias is added.
+    ///
+    /// Returns [`AliasError`] if `same_as` has no records to copy.
+    ///
+    /// # Arguments
+    /// * `name` - The name to add records for.
+    /// * `same_as` - The name whose records to copy.
+    /// * `record_type` - The type of records to copy, or `None` for all.
+    ///
+    /// Example usage:
+    /// ```rust
+    /// use simply_dns::sync::{DesiredRecord, DesiredZone};
+    ///
+    /// let mut zone = DesiredZone::new();
+    /// zone.add(DesiredRecord::new("@", "A", "192.0.2.10"))
+    ///     .add(DesiredRecord::new("@", "A", "192.0.2.11"))
+    ///     .add(DesiredRecord::new("@", "MX", "mail.example.com").priority(10));
+    /// zone.alias("shop", "@", Some("A"))?
+    ///     .alias("store", "shop", None)?;
+    /// assert_eq!(zone.records.len(), 7);
+    /// assert!(zone.alias("blog", "nowhere", None).is_err());
+    /// # Ok::<(), simply_dns::sync::AliasError>(())
+    /// ```
+    pub fn alias(
+        &mut self,
+        name: &str,
+        same_as: &str,
+        record_type: Option<&str>,
+    ) -> Result<&mut Self, AliasError> {
+        let target = same_as.trim_end_matches('.');
+        let copies: Vec<DesiredRecord> = self
+            .records
+            .iter()
+            .filter(|r| r.name.trim_end_matches('.').eq_ignore_ascii_case(target))
+            .filter(|r| record_type.is_none_or(|t| r.record_type.eq_ignore_ascii_case(t)))
+            .map(|r| DesiredRecord {
+                name: name.to_string(),
+                comment: None,
+                ..r.clone()
+            })
+            .collect();
+        if copies.is_empty() {
+            return Err(AliasError {
+                name: name.to_string(),
+                same_as: same_as.to_string(),
+                record_type: record_type.map(str::to_string),
+            });
+        }
+        self.records.extend(copies);
+        Ok(self)
+    }
+}
+
+/// Error returned by [`DesiredZone::alias`] when there are no records to copy.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error(
+    "{same_as} has no {}records to copy to {name}",
+    record_type.as_ref().map(|t| format!("{t} ")).unwrap_or_default()
+)]
+pub struct AliasError {
+    /// The name the records were to be added for.
+    pub name: String,
+    /// The name without records to copy.
+    pub same_as: String,
+    /// The type of records asked for, if any.
+    pub record_type: Option<String>,
+}
+
+impl<R: Into<DesiredRecord>> FromIterator<R> for DesiredZone {
+    fn from_iter<I: IntoIterator<Item = R>>(iter: I) -> Self {
+        Self {
+            records: iter.into_iter().map(Into::into).collect(),
+            ttl_policy: TtlPolicy::default(),
+            ignore: Vec::new(),
+            owner: None,
+            scope: None,
+        }
+    }
+}
+
+/// Records a [`DesiredZone`] does not manage.
+///
+/// Live records matching a rule are never updated or deleted, and are not
+/// reported in the [`ZonePlan`], so records maintained by something else, such
+/// as ACME challenges or dynamic DNS, can share the zone. Desired records
+/// matching a rule are skipped as well.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::sync::{DesiredRecord, DesiredZone, IgnoreRule, ZonePlan};
+///
+/// let desired = DesiredZone::from_iter([DesiredRecord::new("@", "A", "192.0.2.10")])
+///     .ignore(IgnoreRule::name_glob("_acme-challenge*"))
+///     .ignore(IgnoreRule::comment_tag("ddns"));
+///
+/// let live = serde_json::from_value(serde_json::json!([
+///     { "record_id": { "id": 1 }, "name": "@", "type": "A", "data": "192.0.2.10", "ttl": 3600,
+///       "priority": null, "comment": null },
+///     { "record_id": { "id": 2 }, "name": "_acme-challenge", "type": "TXT", "data": "token",
+///       "ttl": 60, "priority": null, "comment": null },
+///     { "record_id": { "id": 3 }, "name": "home", "type": "A", "data": "198.51.100.7",
+///       "ttl": 60, "priority": null, "comment": "managed by ddns" },
+/// ]))?;
+/// assert!(ZonePlan::compute("example.com", live, &desired).is_empty());
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IgnoreRule {
+    /// Records whose name matches this pattern, see [`NameMatch::Glob`].
+    NameGlob(String),
+    /// Records of this type, compared case-insensitively.
+    RecordType(String),
+    /// Records whose comment contains this tag.
+    CommentTag(String),
+}
+
+impl IgnoreRule {
+    /// Ignore records whose name matches the glob `pattern`.
+    pub fn name_glob(pattern: impl Into<String>) -> Self {
+        Self::NameGlob(pattern.into())
+    }
+
+    /// Ignore records of type `record_type`.
+    pub fn record_type(record_type: impl Into<String>) -> Self {
+        Self::RecordType(record_type.into())
+    }
+
+    /// Ignore records whose comment contains `tag`.
+    pub fn comment_tag(tag: impl Into<String>) -> Self {
+        Self::CommentTag(tag.into())
+    }
+
+    fn matches(&self, name: &str, record_type: &str, comment: Option<&str>) -> bool {
+        match self {
+            Self::NameGlob(pattern) => NameMatch::Glob(pattern.clone()).matches(name),
+            Self::RecordType(t) => t.eq_ignore_ascii_case(record_type),
+            Self::CommentTag(tag) => comment.is_some_and(|c| c.contains(tag.as_str())),
+        }
+    }
+}
+
+/// Rules for the TTLs of [`DesiredRecord`]s that don't set one.
+///
+/// By default an unset TTL is not compared, so live records keep theirs and
+/// new records get the API's default. A default TTL, per type or for all
+/// types, is enforced on records without their own TTL instead, unless
+/// [`inherit_existing`](Self::inherit_existing) is set, in which case it
+/// only applies to new records. A [`minimum`](Self::minimum) raises any TTL
+/// below it, including explicit ones.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::sync::{Change, DesiredRecord, DesiredZone, TtlPolicy, ZonePlan};
+///
+/// let policy = TtlPolicy::new()
+///     .default_ttl(3600)
+///     .type_default("TXT", 300)
+///     .minimum(60)
+///     .inherit_existing(true);
+/// let desired = DesiredZone::from_iter([
+///     DesiredRecord::new("@", "A", "192.0.2.10"),
+///     DesiredRecord::new("@", "TXT", "v=spf1 -all"),
+/// ])
+/// .with_ttl_policy(policy);
+///
+/// // The live A record keeps its TTL; the new TXT record gets the TXT default.
+/// let live = serde_json::from_value(serde_json::json!([{
+///     "record_id": { "id": 1 }, "name": "@", "type": "A", "data": "192.0.2.10", "ttl": 600,
+///     "priority": null, "comment": null,
+/// }]))?;
+/// let plan = ZonePlan::compute("example.com", live, &desired);
+/// assert!(matches!(&plan.changes[..], [Change::Create(req)] if req.ttl == Some(300)));
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TtlPolicy {
+    /// TTLs by record type, in upper case.
+    pub type_defaults: BTreeMap<String, u32>,
+    /// The TTL for types without an entry in `type_defaults`.
+    pub default: Option<u32>,
+    /// The lowest TTL allowed.
+    pub minimum: Option<u32>,
+    /// Keep the TTL of existing records when the desired record has none.
+    pub inherit_existing: bool,
+}
+
+impl TtlPolicy {
+    /// A policy leaving unset TTLs alone.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `ttl` for records without a TTL of their own.
+    pub fn default_ttl(mut self, ttl: u32) -> Self {
+        self.default = Some(ttl);
+        self
+    }
+
+    /// Use `ttl` for records of `record_type` without a TTL of their own.
+    pub fn type_default(mut self, record_type: &str, ttl: u32) -> Self {
+        self.type_defaults
+            .insert(record_type.to_ascii_uppercase(), ttl);
+        self
+    }
+
+    /// Raise TTLs below `ttl` to `ttl`.
+    pub fn minimum(mut self, ttl: u32) -> Self {
+        self.minimum = Some(ttl);
+        self
+    }
+
+    /// Keep the TTL of existing records instead of enforcing the default on them.
+    pub fn inherit_existing(mut self, enabled: bool) -> Self {
+        self.inherit_existing = enabled;
+        self
+    }
+
+    /// The default TTL for `record_type`, if any.
+    fn default_for(&self, record_type: &str) -> Option<u32> {
+        self.type_defaults
+            .get(&record_type.to_ascii_uppercase())
+            .copied()
+            .or(self.default)
+    }
+
+    /// `want` with the TTL to compare against live records, and the TTL to create it with.
+    fn resolve(&self, want: &DesiredRecord) -> (DesiredRecord, Option<u32>) {
+        let clamp = |ttl: Option<u32>| ttl.map(|t| t.max(self.minimum.unwrap_or_default()));
+        let default = self.default_for(&want.record_type);
+        let create_ttl = clamp(want.ttl.or(default));
+        let mut resolved = want.clone();
+        resolved.ttl = clamp(if self.inherit_existing {
+            want.ttl
+        } else {
+            want.ttl.or(default)
+        });
+        (resolved, create_ttl)
+    }
+}
+
+/// Whether `comment` carries the ownership tag `owner` as one of its words.
+pub fn has_owner_tag(comment: Option<&str>, owner: &str) -> bool {
+    comment.is_some_and(|c| c.split_whitespace().any(|word| word == owner))
+}
+
+/// `comment` with the ownership tag `owner` appended, unless it already has it.
+pub(crate) fn tag_comment(comment: Option<&str>, owner: &str) -> String {
+    match comment.map(str::trim) {
+        None | Some("") => owner.to_string(),
+        Some(c) if has_owner_tag(Some(c), owner) => c.to_string(),
+        Some(c) => format!("{c} {owner}"),
+    }
+}
+
+/// A single change in a [`ZonePlan`].
+#[derive(Debug, Clone)]
+pub enum Change {
+    /// Delete a record that is not desired.
+    Delete(DnsRecord),
+    /// Update a live record to match a desired one.
+    Update {
+        /// The record as it currently exists.
+        current: DnsRecord,
+        /// The update to apply to it.
+        desired: UpdateDnsRecordRequest,
+    },
+    /// Create a desired record that does not exist yet.
+    Create(CreateDnsRecordRequest),
+}
+
+/// The changes needed to turn a zone into a [`DesiredZone`], from [`SimplyClient::plan`].
+///
+/// Changes are ordered deletes first, then updates, then creates, which is the
+/// order [`SimplyClient::apply`] executes them in.
+#[derive(Debug)]
+pub struct ZonePlan {
+    /// The domain the plan was computed for.
+    pub domain: String,
+    /// The changes to make.
+    pub changes: Vec<Change>,
+}
+
+impl ZonePlan {
+    /// Compute the plan turning `current` records into `desired`.
+    pub fn compute(
+        domain: impl Into<String>,
+        mut current: Vec<DnsRecord>,
+        desired: &DesiredZone,
+    ) -> Self {
+        let domain = domain.into();
+        let mut updates = Vec::new();
+        let mut creates = Vec::new();
+        let min_ttl = desired.ttl_policy.minimum;
+        current.retain(|r| {
+            desired.in_scope(&r.name, &domain)
+                && !desired.is_ignored(&r.name, &r.record_type, r.comment.as_deref())
+        });
+        let (mut current, mut unowned): (Vec<DnsRecord>, Vec<DnsRecord>) =
+            current.into_iter().partition(|r| {
+                desired
+                    .owner
+                    .as_ref()
+                    .is_none_or(|owner| has_owner_tag(r.comment.as_deref(), owner))
+            });
+        let wanted: Vec<(DesiredRecord, Option<u32>)> = desired
+            .records
+            .iter()
+            .filter(|r| {
+                desired.in_scope(&r.name, &domain)
+                    && !desired.is_ignored(&r.name, &r.record_type, r.comment.as_deref())
+            })
+            .map(|r| {
+                let (mut resolved, create_ttl) = desired.ttl_policy.resolve(r);
+                // Fully-qualified names are created and updated relative to the zone.
+                resolved.name = NameStyle::Relative.normalize(&resolved.name, &domain);
+                if let Some(owner) = &desired.owner {
+                    resolved.comment = resolved.comment.map(|c| tag_comment(Some(&c), owner));
+                }
+                (resolved, create_ttl)
+            })
+            .collect();
+
+        // Records that already match completely are claimed first, so that a record
+        // differing only in attributes never steals a match from an identical one.
+        let mut pending: Vec<&(DesiredRecord, Option<u32>)> = Vec::new();
+        for wanted in &wanted {
+            let want = &wanted.0;
+            match current.iter().position(|r| {
+                want.same_key(r, &domain)
+                    && want.same_data(r)
+                    && !want.attributes_differ(r, min_ttl)
+            }) {
+                Some(i) => {
+                    current.remove(i);
+                }
+                None => pending.push(wanted),
+            }
+        }
+        // Records that already have the desired data only need their attributes updated.
+        let mut leftover: Vec<&(DesiredRecord, Option<u32>)> = Vec::new();
+        for wanted @ (want, _) in pending {
+            match current
+                .iter()
+                .position(|r| want.same_key(r, &domain) && want.same_data(r))
+            {
+                Some(i) => {
+                    let record = current.remove(i);
+                    updates.push(Change::Update {
+                        desired: want.to_update(&record, min_ttl),
+                        current: record,
+                    });
+                }
+                None => leftover.push(wanted),
+            }
+        }
+        for (want, create_ttl) in leftover {
+            // A record with the desired data but without the ownership tag is
+            // adopted by tagging it, rather than created a second time.
+            if let Some(owner) = &desired.owner
+                && let Some(i) = unowned
+                    .iter()
+                    .position(|r| want.same_key(r, &domain) && want.same_data(r))
+            {
+                let record = unowned.remove(i);
+                let mut update = want.to_update(&record, min_ttl);
+                update.comment = Some(tag_comment(update.comment.as_deref(), owner));
+                updates.push(Change::Update {
+                    desired: update,
+                    current: record,
+                });
+                continue;
+            }
+            // Reuse a leftover record with the same name and type, otherwise create one.
+            match current.iter().position(|r| want.same_key(r, &domain)) {
+                Some(i) => {
+                    let record = current.remove(i);
+                    updates.push(Change::Update {
+                        desired: want.to_update(&record, min_ttl),
+                        current: record,
+                    });
+                }
+                None => creates.push(Change::Create(CreateDnsRecordRequest {
+                    ttl: *create_ttl,
+                    comment: match &desired.owner {
+                        Some(owner) => Some(tag_comment(want.comment.as_deref(), owner)),
+                        None => want.comment.clone(),
+                    },
+                    ..want.to_create()
+                })),
+            }
+        }
+        let mut changes: Vec<Change> = current.into_iter().map(Change::Delete).collect();
+        changes.append(&mut updates);
+        changes.append(&mut creates);
+        Self { domain, changes }
+    }
+
+    /// Whether the zone already matches and there is nothing to apply.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// How many records the plan creates, updates and deletes.
+    pub fn counts(&self) -> (usize, usize, usize) {
+        self.changes
+            .iter()
+            .fold((0, 0, 0), |(create, update, delete), change| match change {
+                Change::Create(_) => (create + 1, update, delete),
+                Change::Update { .. } => (create, update + 1, delete),
+                Change::Delete(_) => (create, update, delete + 1),
+            })
+    }
+
+    /// The plan as a diff colored for a terminal, like its `Display` but
+    /// with creates green, updates yellow and deletes red.
+    ///
+    /// Example output:
+    /// ```text
+    /// example.com:
+    /// - old TXT "v=spf1 -all"
+    /// ~ www A 192.0.2.1 -> 192.0.2.2
+    ///     ttl: 3600 -> 300
+    /// + mail A 192.0.2.25 (ttl 3600)
+    /// Plan: 1 to create, 1 to update, 1 to delete.
+    /// ```
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, true)
+            .expect("writing to a String doesn't fail");
+        out
+    }
+
+    /// The plan as JSON: the domain, the changes with the records before
+    /// and after, and the counts.
+    ///
+    /// Example output:
+    /// ```json
+    /// {
+    ///   "domain": "example.com",
+    ///   "changes": [
+    ///     {
+    ///       "action": "update",
+    ///       "before": { "record_id": { "id": 42 }, "name": "www", "type": "A", "data": "192.0.2.1", "ttl": 3600, "priority": null, "comment": null },
+    ///       "after": { "name": "www", "type": "A", "data": "192.0.2.2", "ttl": 300, "priority": null, "comment": null }
+    ///     }
+    ///   ],
+    ///   "summary": { "create": 0, "update": 1, "delete": 0 }
+    /// }
+    /// ```
+    pub fn to_json(&self) -> serde_json::Value {
+        let changes: Vec<_> = self
+            .changes
+            .iter()
+            .map(|change| match change {
+                Change::Delete(record) => serde_json::json!({
+                    "action": "delete",
+                    "before": record,
+                    "after": null,
+                }),
+                Change::Update { current, desired } => serde_json::json!({
+                    "action": "update",
+                    "before": current,
+                    "after": desired,
+                }),
+                Change::Create(req) => serde_json::json!({
+                    "action": "create",
+                    "before": null,
+                    "after": req,
+                }),
+            })
+            .collect();
+        let (create, update, delete) = self.counts();
+        serde_json::json!({
+            "domain": self.domain,
+            "changes": changes,
+            "summary": { "create": create, "update": update, "delete": delete },
+        })
+    }
+
+    fn write(&self, f: &mut impl fmt::Write, color: bool) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "{}: no changes", self.domain);
+        }
+        writeln!(f, "{}:", self.domain)?;
+        for change in &self.changes {
+            let paint = match change {
+                Change::Create(_) => GREEN,
+                Change::Update { .. } => YELLOW,
+                Change::Delete(_) => RED,
+            };
+            if color {
+                write!(f, "{paint}")?;
+            }
+            write!(f, "{change}")?;
+            if color {
+                write!(f, "{RESET}")?;
+            }
+            writeln!(f)?;
+        }
+        let (create, update, delete) = self.counts();
+        write!(
+            f,
+            "Plan: {create} to create, {update} to update, {delete} to delete."
+        )
+    }
+}
+
+/// Shows the plan as a diff without colors, see [`ZonePlan::render`].
+impl fmt::Display for ZonePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, false)
+    }
+}
+
+/// Shows the change on one line, e.g. `+ www A 192.0.2.1 (ttl 300)`, with
+/// the attributes an update changes on indented lines below.
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Delete(r) => write!(f, "- {} {} {}", r.name, r.record_type, r.data),
+            Change::Create(r) => {
+                write!(f, "+ {} {} {}", r.name, r.record_type, r.data)?;
+                let mut attributes = Vec::new();
+                if let Some(priority) = r.priority {
+                    attributes.push(format!("priority {priority}"));
+                }
+                if let Some(ttl) = r.ttl {
+                    attributes.push(format!("ttl {ttl}"));
+                }
+                if let Some(comment) = r.comment.as_deref().filter(|c| !c.is_empty()) {
+                    attributes.push(format!("comment {comment:?}"));
+                }
+                if !attributes.is_empty() {
+                    write!(f, " ({})", attributes.join(", "))?;
+                }
+                Ok(())
+            }
+            Change::Update { current, desired } => {
+                write!(
+                    f,
+                    "~ {} {} {}",
+                    current.name, current.record_type, current.data
+                )?;
+                if !same_data(&current.record_type, &current.data, &desired.data) {
+                    write!(f, " -> {}", desired.data)?;
+                }
+                if desired.name != current.name {
+                    write!(f, "\n    name: {} -> {}", current.name, desired.name)?;
+                }
+                if desired.priority.is_some() && desired.priority != current.priority {
+                    let show = |p: Option<u32>| p.map_or("none".to_string(), |p| p.to_string());
+                    write!(
+                        f,
+                        "\n    priority: {} -> {}",
+                        show(current.priority),
+                        show(desired.priority)
+                    )?;
+                }
+                if let Some(ttl) = desired.ttl.filter(|ttl| *ttl != current.ttl) {
+                    write!(f, "\n    ttl: {} -> {ttl}", current.ttl)?;
+                }
+                if let Some(comment) = desired
+                    .comment
+                    .as_deref()
+                    .filter(|c| *c != current.comment.as_deref().unwrap_or_default())
+                {
+                    write!(
+                        f,
+                        "\n    comment: {:?} -> {comment:?}",
+                        current.comment.as_deref().unwrap_or_default()
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl SimplyClient {
+    /// Compute the changes needed to make `domain` match `desired`.
+    ///
+    /// Nothing is modified; pass the returned plan to [`apply`](Self::apply) to execute it.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to plan changes for.
+    /// * `desired` - The records the zone should contain.
+    pub async fn plan(
+        &self,
+        domain: &str,
+        desired: &DesiredZone,
+    ) -> Result<ZonePlan, SimplyClientError> {
+        plan(self, domain, desired).await
+    }
+
+    /// Execute the changes of `plan` in order, holding the
+    /// [lock](crate::lock) of its domain.
+    ///
+    /// A failed change doesn't stop the remaining ones; the outcome of each
+    /// change is collected in the returned report. If a configured lock file
+    /// can't be created, a [freeze window](crate::freeze) is active or an
+    /// [approval hook](crate::approval) rejects the plan, every change is
+    /// reported as skipped.
+    pub async fn apply(&self, plan: ZonePlan) -> OperationReport<Change> {
+        let mut progress = self.progress(Operation::Apply, Some(plan.changes.len()));
+        let reason = match self.lock_domain(&plan.domain).await {
+            Ok(lock) => match self.refusal(&plan).await {
+                None => return apply_reporting(lock.client(), plan, &mut progress).await,
+                Some(reason) => reason,
+            },
+            Err(e) => format!("couldn't lock {}: {e}", plan.domain),
+        };
+        let mut report = OperationReport::default();
+        skip_all(&mut progress, &mut report, plan.changes, &reason);
+        progress.finish(&report);
+        report
+    }
+
+    /// Why `plan` must not be applied now, if it mustn't: because of a freeze
+    /// window or an approval hook.
+    async fn refusal(&self, plan: &ZonePlan) -> Option<String> {
+        if plan.is_empty() {
+            return None;
+        }
+        if let Err(e) = self.check_freeze() {
+            return Some(e.to_string());
+        }
+        match self.approval().check(plan).await {
+            Approval::Approved => None,
+            Approval::Rejected(reason) => Some(format!("not approved: {reason}")),
+        }
+    }
+
+    /// Plan and apply the changes making `domain` match `desired`, guarding
+    /// against edits made by others in the meantime.
+    ///
+    /// Before each update or delete the record is fetched again; if it was
+    /// changed or deleted since the zone was listed, `strategy` decides what
+    /// happens. Holds the [lock](crate::lock) of `domain` throughout. Fails
+    /// only if the zone can't be locked or listed initially. Like with
+    /// [`apply`](Self::apply), no changes are made during a
+    /// [freeze window](crate::freeze), and every plan, including those a
+    /// [`ConflictStrategy::Replan`] computes, must be approved by the client's
+    /// [approval hooks](crate::approval).
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to sync.
+    /// * `desired` - The records the zone should contain.
+    /// * `strategy` - How to handle records changed by others.
+    pub async fn sync(
+        &self,
+        domain: &str,
+        desired: &DesiredZone,
+        strategy: ConflictStrategy,
+    ) -> Result<OperationReport<Change>, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let mut progress = self.progress(Operation::Sync, None);
+        sync_reporting(
+            lock.client(),
+            domain,
+            desired,
+            strategy,
+            Some(self),
+            &mut progress,
+        )
+        .await
+    }
+}
+
+/// What [`SimplyClient::sync`] does when a record it is about to write was
+/// changed or deleted by someone else after the zone was listed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// Stop, reporting the conflicting and remaining changes as skipped.
+    #[default]
+    Abort,
+    /// Compute a new plan from the current zone and continue with it, up to
+    /// [`MAX_REPLANS`] times before giving up like [`Abort`](Self::Abort).
+    Replan,
+    /// Write the desired state anyway: changed records are overwritten,
+    /// deleted ones are recreated if they were to be updated.
+    Force,
+}
+
+/// How often [`ConflictStrategy::Replan`] plans again before giving up.
+pub const MAX_REPLANS: u32 = 3;
+
+/// How a record about to be written differs from the planned state.
+enum Conflict {
+    Modified(DnsRecord),
+    Deleted,
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Conflict::Modified(live) => write!(
+                f,
+                "record was changed to {} {} {}",
+                live.name, live.record_type, live.data
+            ),
+            Conflict::Deleted => f.write_str("record was deleted"),
+        }
+    }
+}
+
+/// Like [`SimplyClient::plan`], for any [`SimplyDnsApi`] implementation.
+pub async fn plan(
+    api: &(impl SimplyDnsApi + ?Sized),
+    domain: &str,
+    desired: &DesiredZone,
+) -> Result<ZonePlan, SimplyClientError> {
+    let current = api.list_dns_records(domain).await?;
+    Ok(ZonePlan::compute(domain, current, desired))
+}
+
+/// Like [`SimplyClient::apply`], for any [`SimplyDnsApi`] implementation.
+pub async fn apply(api: &(impl SimplyDnsApi + ?Sized), plan: ZonePlan) -> OperationReport<Change> {
+    apply_reporting(api, plan, &mut Progress::none(Operation::Apply)).await
+}
+
+async fn apply_reporting(
+    api: &(impl SimplyDnsApi + ?Sized),
+    plan: ZonePlan,
+    progress: &mut Progress<'_>,
+) -> OperationReport<Change> {
+    let started = Instant::now();
+    let mut report = OperationReport::default();
+    for change in plan.changes {
+        progress.begin();
+        let change_started = Instant::now();
+        let result = apply_change(api, &plan.domain, change.clone()).await;
+        progress.record(&mut report, change, result, change_started);
+    }
+    report.elapsed = started.elapsed();
+    progress.finish(&report);
+    report
+}
+
+/// Like [`SimplyClient::sync`], for any [`SimplyDnsApi`] implementation.
+pub async fn sync(
+    api: &(impl SimplyDnsApi + ?Sized),
+    domain: &str,
+    desired: &DesiredZone,
+    strategy: ConflictStrategy,
+) -> Result<OperationReport<Change>, SimplyClientError> {
+    sync_reporting(
+        api,
+        domain,
+        desired,
+        strategy,
+        None,
+        &mut Progress::none(Operation::Sync),
+    )
+    .await
+}
+
+async fn sync_reporting(
+    api: &(impl SimplyDnsApi + ?Sized),
+    domain: &str,
+    desired: &DesiredZone,
+    strategy: ConflictStrategy,
+    gate: Option<&SimplyClient>,
+    progress: &mut Progress<'_>,
+) -> Result<OperationReport<Change>, SimplyClientError> {
+    let started = Instant::now();
+    let mut report = OperationReport::default();
+    let mut changes = approved(
+        gate,
+        plan(api, domain, desired).await?,
+        progress,
+        &mut report,
+    )
+    .await;
+    let mut replans = 0;
+    while let Some(change) = changes.pop_front() {
+        progress.begin();
+        let change_started = Instant::now();
+        let conflict = match find_conflict(api, domain, &change).await {
+            Ok(conflict) => conflict,
+            Err(e) => {
+                progress.record(&mut report, change, Err(e), change_started);
+                continue;
+            }
+        };
+        let change = match (conflict, strategy) {
+            (None, _) => change,
+            (Some(conflict), ConflictStrategy::Replan) if replans < MAX_REPLANS => {
+                replans += 1;
+                progress.skip(&mut report, change, format!("{conflict}, planned again"));
+                match plan(api, domain, desired).await {
+                    Ok(plan) => changes = approved(gate, plan, progress, &mut report).await,
+                    Err(e) => {
+                        let reason = format!("planning again failed: {e}");
+                        skip_all(progress, &mut report, changes.drain(..), &reason);
+                    }
+                }
+                continue;
+            }
+            (Some(conflict), ConflictStrategy::Abort | ConflictStrategy::Replan) => {
+                progress.skip(&mut report, change, conflict.to_string());
+                skip_all(
+                    progress,
+                    &mut report,
+                    changes.drain(..),
+                    "aborted after a conflict",
+                );
+                break;
+            }
+            (Some(Conflict::Deleted), ConflictStrategy::Force) => match change {
+                Change::Update { desired, .. } => Change::Create(CreateDnsRecordRequest {
+                    record_type: desired.record_type,
+                    name: desired.name,
+                    data: desired.data,
+                    priority: desired.priority,
+                    ttl: desired.ttl,
+                    comment: desired.comment,
+                }),
+                change => {
+                    progress.skip(&mut report, change, "record was already deleted");
+                    continue;
+                }
+            },
+            (Some(Conflict::Modified(_)), ConflictStrategy::Force) => change,
+        };
+        let result = apply_change(api, domain, change.clone()).await;
+        progress.record(&mut report, change, result, change_started);
+    }
+    report.elapsed = started.elapsed();
+    progress.finish(&report);
+    Ok(report)
+}
+
+/// The changes of `plan` if `gate`, the client syncing, may apply it, or none
+/// after reporting them as skipped.
+async fn approved(
+    gate: Option<&SimplyClient>,
+    plan: ZonePlan,
+    progress: &mut Progress<'_>,
+    report: &mut OperationReport<Change>,
+) -> VecDeque<Change> {
+    let refusal = match gate {
+        Some(client) => client.refusal(&plan).await,
+        None => None,
+    };
+    match refusal {
+        None => plan.changes.into(),
+        Some(reason) => {
+            skip_all(progress, report, plan.changes, &reason);
+            VecDeque::new()
+        }
+    }
+}
+
+/// Fetch the record `change` writes to and compare it with the planned state.
+async fn find_conflict(
+    api: &(impl SimplyDnsApi + ?Sized),
+    domain: &str,
+    change: &Change,
+) -> Result<Option<Conflict>, SimplyClientError> {
+    let (Change::Delete(current) | Change::Update { current, .. }) = change else {
+        return Ok(None);
+    };
+    Ok(
+        match api.get_dns_record(domain, &current.record_id).await? {
+            None => Some(Conflict::Deleted),
+            Some(live) if !live.same_contents(current) => Some(Conflict::Modified(live)),
+            Some(_) => None,
+        },
+    )
+}
+
+fn skip_all(
+    progress: &mut Progress<'_>,
+    report: &mut OperationReport<Change>,
+    changes: impl IntoIterator<Item = Change>,
+    reason: &str,
+) {
+    for change in changes {
+        progress.skip(report, change, reason);
+    }
+}
+
+pub(crate) async fn apply_change(
+    api: &(impl SimplyDnsApi + ?Sized),
+    domain: &str,
+    change: Change,
+) -> Result<(), SimplyClientError> {
+    match change {
+        Change::Delete(record) => api.delete_dns_record(domain, record.record_id).await,
+        Change::Update { current, desired } => {
+            api.update_dns_record(domain, current.record_id, desired)
+                .await
+        }
+        Change::Create(req) => api.create_dns_record(domain, req).await.map(drop),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::DnsRecordId;
+
+    fn live(
+        id: u32,
+        name: &str,
+        record_type: &str,
+        data: &str,
+        comment: Option<&str>,
+    ) -> DnsRecord {
+        DnsRecord {
+            record_id: DnsRecordId::new(id),
+            name: name.to_string(),
+            ttl: 3600,
+            data: data.to_string(),
+            record_type: record_type.to_string(),
+            priority: None,
+            comment: comment.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn fully_qualified_names_match_relative_ones() {
+        let desired = DesiredZone::from_iter([
+            DesiredRecord::new("www.example.com.", "A", "192.0.2.1"),
+            DesiredRecord::new("Example.com.", "MX", "mail.example.com.").priority(10),
+            DesiredRecord::new("api.example.com.", "A", "192.0.2.2"),
+        ]);
+        let current = vec![
+            live(1, "www", "A", "192.0.2.1", None),
+            DnsRecord {
+                priority: Some(10),
+                ..live(2, "@", "MX", "mail.example.com", None)
+            },
+        ];
+        let plan = ZonePlan::compute("example.com", current, &desired);
+        let [Change::Create(create)] = &plan.changes[..] else {
+            panic!("expected a single create, got {:?}", plan.changes);
+        };
+        assert_eq!(create.name, "api");
+    }
+
+    #[test]
+    fn owned_zone_adopts_untagged_records_instead_of_duplicating_them() {
+        let desired = DesiredZone::from_iter([
+            DesiredRecord::new("www", "A", "192.0.2.1"),
+            DesiredRecord::new("api", "A", "192.0.2.2"),
+        ])
+        .owned_by("owner=me");
+        let current = vec![
+            live(1, "www", "A", "192.0.2.1", Some("hand made")),
+            live(2, "mail", "A", "192.0.2.9", None),
+        ];
+        let plan = ZonePlan::compute("example.com", current, &desired);
+        assert_eq!(plan.counts(), (1, 1, 0));
+        let Some(Change::Update { current, desired }) = plan
+            .changes
+            .iter()
+            .find(|c| matches!(c, Change::Update { .. }))
+        else {
+            unreachable!()
+        };
+        assert_eq!(current.record_id, DnsRecordId::new(1));
+        assert_eq!(desired.data, "192.0.2.1");
+        assert_eq!(desired.comment.as_deref(), Some("hand made owner=me"));
+    }
+
+    #[test]
+    fn owned_zone_leaves_untagged_records_with_other_data_alone() {
+        let desired =
+            DesiredZone::from_iter([DesiredRecord::new("www", "A", "192.0.2.1")]).owned_by("o");
+        let current = vec![live(1, "www", "A", "192.0.2.7", None)];
+        let plan = ZonePlan::compute("example.com", current, &desired);
+        assert_eq!(plan.counts(), (1, 0, 0));
+    }
+
+    #[test]
+    fn scoped_zone_only_manages_its_subtree() {
+        let desired = DesiredZone::from_iter([
+            DesiredRecord::new("api.dev", "A", "192.0.2.20"),
+            DesiredRecord::new("*.dev.example.com.", "A", "192.0.2.21"),
+            DesiredRecord::new("www", "A", "192.0.2.1"),
+        ])
+        .scoped_to("dev.example.com.");
+        let current = vec![
+            live(1, "www", "A", "192.0.2.10", None),
+            live(2, "devel", "A", "192.0.2.11", None),
+            live(3, "dev", "TXT", "team=dev", None),
+            live(4, "API.dev", "A", "192.0.2.20", None),
+        ];
+        let plan = ZonePlan::compute("example.com", current, &desired);
+        // `www` is outside the subtree, and so is `devel`, despite the prefix.
+        assert_eq!(plan.counts(), (1, 0, 1));
+        assert!(
+            plan.changes
+                .iter()
+                .any(|c| matches!(c, Change::Create(create) if create.name == "*.dev"))
+        );
+        assert!(plan.changes.iter().any(
+            |c| matches!(c, Change::Delete(record) if record.record_id == DnsRecordId::new(3))
+        ));
+    }
+
+    #[test]
+    fn apex_scope_manages_the_whole_zone() {
+        let desired = DesiredZone::from_iter([DesiredRecord::new("www", "A", "192.0.2.1")]);
+        let current = vec![live(1, "mail", "A", "192.0.2.9", None)];
+        let plan = ZonePlan::compute("example.com", current, &desired.scoped_to("@"));
+        assert_eq!(plan.counts(), (1, 0, 1));
+    }
+}
@@ -0,0 +1,303 @@
+//! Record sets for common setups.
+//!
+//! A [`Template`] holds the well-known records of a mail or hosting provider,
+//! such as the MX and SPF records of Google Workspace. Applying it with
+//! [`SimplyClient::apply_template`] brings just those records in line, using
+//! the same diff as [`sync`](crate::sync): records of the template's names and
+//! types (for TXT records, of the same kind, e.g. `v=spf1`) that the template
+//! doesn't list are replaced, and everything else in the zone is left alone.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::templates::{DmarcPolicy, Template};
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let workspace = Template::GoogleWorkspace {
+//!     verification: Some("google-site-verification=abc123".to_string()),
+//!     dkim: None,
+//! };
+//! println!("{}", client.apply_template("example.com", &workspace).await?);
+//!
+//! // A domain that sends no mail at all.
+//! let parked = Template::MailBaseline {
+//!     spf_includes: Vec::new(),
+//!     dkim: Vec::new(),
+//!     dmarc_policy: DmarcPolicy::Reject,
+//!     dmarc_reports: None,
+//! };
+//! println!("{}", client.apply_template("example.net", &parked).await?);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use crate::api::DnsRecord;
+use crate::report::OperationReport;
+use crate::sync::{Change, DesiredRecord, DesiredZone, ZonePlan};
+use crate::{SimplyClient, SimplyClientError};
+
+/// GitHub Pages' apex addresses.
+const GITHUB_PAGES_V4: [&str; 4] = [
+    "185.199.108.153",
+    "185.199.109.153",
+    "185.199.110.153",
+    "185.199.111.153",
+];
+/// GitHub Pages' apex IPv6 addresses.
+const GITHUB_PAGES_V6: [&str; 4] = [
+    "2606:50c0:8000::153",
+    "2606:50c0:8001::153",
+    "2606:50c0:8002::153",
+    "2606:50c0:8003::153",
+];
+
+/// The policy a DMARC record asks receivers to apply to failing mail.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DmarcPolicy {
+    /// Only report failures.
+    #[default]
+    None,
+    /// Treat failing mail as suspicious, e.g. deliver it as spam.
+    Quarantine,
+    /// Reject failing mail.
+    Reject,
+}
+
+/// Shows the policy as used in the `p=` tag.
+impl fmt::Display for DmarcPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DmarcPolicy::None => "none",
+            DmarcPolicy::Quarantine => "quarantine",
+            DmarcPolicy::Reject => "reject",
+        })
+    }
+}
+
+/// A well-known set of records, see the [module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Template {
+    /// Google Workspace mail: the MX record and SPF.
+    GoogleWorkspace {
+        /// The `google-site-verification=...` TXT value, if the domain is
+        /// verified through DNS.
+        verification: Option<String>,
+        /// The DKIM TXT value for the `google` selector, from the admin console.
+        dkim: Option<String>,
+    },
+    /// Microsoft 365 mail: the MX record, autodiscover, SPF and the DKIM CNAMEs.
+    Microsoft365 {
+        /// The tenant name, e.g. "contoso" in "contoso.onmicrosoft.com".
+        tenant: String,
+    },
+    /// Fastmail mail: the MX records, SPF and the DKIM CNAMEs.
+    Fastmail,
+    /// A GitHub Pages site at the apex, with `www` pointing at it.
+    GitHubPages {
+        /// The user or organization whose `<user>.github.io` serves the site.
+        user: String,
+    },
+    /// SPF, DKIM and DMARC records for a domain, whoever sends its mail.
+    ///
+    /// Without `spf_includes` the SPF record allows no senders at all, which
+    /// together with [`DmarcPolicy::Reject`] protects a domain that sends no mail.
+    MailBaseline {
+        /// Domains whose SPF records list the allowed senders, e.g. "_spf.google.com".
+        spf_includes: Vec<String>,
+        /// DKIM keys as selector and TXT value, e.g. ("s1", "v=DKIM1; k=rsa; p=...").
+        dkim: Vec<(String, String)>,
+        /// The DMARC policy.
+        dmarc_policy: DmarcPolicy,
+        /// The address aggregate DMARC reports are sent to, if any.
+        dmarc_reports: Option<String>,
+    },
+}
+
+impl Template {
+    /// The records of the template for `domain`.
+    pub fn records(&self, domain: &str) -> Vec<DesiredRecord> {
+        let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+        match self {
+            Template::GoogleWorkspace { verification, dkim } => {
+                let mut records = vec![
+                    DesiredRecord::new("@", "MX", "smtp.google.com").priority(1),
+                    DesiredRecord::new("@", "TXT", "v=spf1 include:_spf.google.com ~all"),
+                ];
+                if let Some(verification) = verification {
+                    records.push(DesiredRecord::new("@", "TXT", verification));
+                }
+                if let Some(dkim) = dkim {
+                    records.push(DesiredRecord::new("google._domainkey", "TXT", dkim));
+                }
+                records
+            }
+            Template::Microsoft365 { tenant } => {
+                let dashed = domain.replace('.', "-");
+                let mut records = vec![
+                    DesiredRecord::new("@", "MX", format!("{dashed}.mail.protection.outlook.com"))
+                        .priority(0),
+                    DesiredRecord::new("autodiscover", "CNAME", "autodiscover.outlook.com"),
+                    DesiredRecord::new(
+                        "@",
+                        "TXT",
+                        "v=spf1 include:spf.protection.outlook.com -all",
+                    ),
+                ];
+                for selector in ["selector1", "selector2"] {
+                    records.push(DesiredRecord::new(
+                        format!("{selector}._domainkey"),
+                        "CNAME",
+                        format!("{selector}-{dashed}._domainkey.{tenant}.onmicrosoft.com"),
+                    ));
+                }
+                records
+            }
+            Template::Fastmail => {
+                let mut records = vec![
+                    DesiredRecord::new("@", "MX", "in1-smtp.messagingengine.com").priority(10),
+                    DesiredRecord::new("@", "MX", "in2-smtp.messagingengine.com").priority(20),
+                    DesiredRecord::new("@", "TXT", "v=spf1 include:spf.messagingengine.com ?all"),
+                ];
+                for selector in ["fm1", "fm2", "fm3"] {
+                    records.push(DesiredRecord::new(
+                        format!("{selector}._domainkey"),
+                        "CNAME",
+                        format!("{selector}.{domain}.dkim.fmhosted.com"),
+                    ));
+                }
+                records
+            }
+            Template::GitHubPages { user } => GITHUB_PAGES_V4
+                .iter()
+                .map(|ip| DesiredRecord::new("@", "A", *ip))
+                .chain(
+                    GITHUB_PAGES_V6
+                        .iter()
+                        .map(|ip| DesiredRecord::new("@", "AAAA", *ip)),
+                )
+                .chain([DesiredRecord::new(
+                    "www",
+                    "CNAME",
+                    format!("{}.github.io", user.to_ascii_lowercase()),
+                )])
+                .collect(),
+            Template::MailBaseline {
+                spf_includes,
+                dkim,
+                dmarc_policy,
+                dmarc_reports,
+            } => {
+                let mut spf = "v=spf1".to_string();
+                for include in spf_includes {
+                    spf.push_str(&format!(" include:{include}"));
+                }
+                spf.push_str(" -all");
+                let mut dmarc = format!("v=DMARC1; p={dmarc_policy}");
+                if let Some(address) = dmarc_reports {
+                    dmarc.push_str(&format!("; rua=mailto:{address}"));
+                }
+                let mut records = vec![
+                    DesiredRecord::new("@", "TXT", spf),
+                    DesiredRecord::new("_dmarc", "TXT", dmarc),
+                ];
+                for (selector, key) in dkim {
+                    records.push(DesiredRecord::new(
+                        format!("{selector}._domainkey"),
+                        "TXT",
+                        key,
+                    ));
+                }
+                records
+            }
+        }
+    }
+
+    /// The changes making `current`, the records of `domain`, match the template.
+    pub fn plan(&self, domain: &str, current: Vec<DnsRecord>) -> ZonePlan {
+        let records = self.records(domain);
+        let scoped = current
+            .into_iter()
+            .filter(|live| records.iter().any(|r| in_scope(r, live)))
+            .collect();
+        ZonePlan::compute(domain, scoped, &DesiredZone::from_iter(records))
+    }
+}
+
+/// Whether `live` is one of the records `record` takes the place of: records
+/// of the same name and type, any record at the name of a CNAME, and for TXT
+/// records only those of the same kind.
+fn in_scope(record: &DesiredRecord, live: &DnsRecord) -> bool {
+    if !same_name(&record.name, &live.name) {
+        return false;
+    }
+    if record.record_type == "CNAME" {
+        return true;
+    }
+    if !record.record_type.eq_ignore_ascii_case(&live.record_type) {
+        return false;
+    }
+    record.record_type != "TXT" || txt_kind(&record.data) == txt_kind(&live.data)
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    apex(a).eq_ignore_ascii_case(apex(b))
+}
+
+fn apex(name: &str) -> &str {
+    match name.trim_end_matches('.') {
+        "" => "@",
+        name => name,
+    }
+}
+
+/// The kind of a TXT record: its leading tag, e.g. "v=spf1", or the key of
+/// a verification token, e.g. "google-site-verification=".
+fn txt_kind(data: &str) -> String {
+    let data = data.trim_matches('"');
+    let token = data.split([' ', ';']).next().unwrap_or_default();
+    let kind = match token.split_once('=') {
+        Some((key, _)) if !key.eq_ignore_ascii_case("v") => &token[..=key.len()],
+        _ => token,
+    };
+    kind.to_ascii_lowercase()
+}
+
+impl SimplyClient {
+    /// Compute the changes making `domain` match `template`.
+    ///
+    /// Nothing is modified; pass the returned plan to [`apply`](Self::apply) to execute it.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to plan changes for.
+    /// * `template` - The records the domain should have.
+    pub async fn plan_template(
+        &self,
+        domain: &str,
+        template: &Template,
+    ) -> Result<ZonePlan, SimplyClientError> {
+        let current = self.list_dns_records(domain).await?;
+        Ok(template.plan(domain, current))
+    }
+
+    /// Bring the records of `template` in line in `domain`, leaving the rest of the zone alone.
+    ///
+    /// Fails only if the records can't be listed; the outcome of each change
+    /// is collected in the report.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to apply the template to.
+    /// * `template` - The records the domain should have.
+    pub async fn apply_template(
+        &self,
+        domain: &str,
+        template: &Template,
+    ) -> Result<OperationReport<Change>, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let plan = this.plan_template(domain, template).await?;
+        Ok(this.apply(plan).await)
+    }
+}
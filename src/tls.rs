@@ -0,0 +1,276 @@
+//! TLS settings of the client's connections.
+//!
+//! Connections use rustls by default. Build with `default-features = false`
+//! and the `native-tls` feature to use the platform's TLS library instead.
+//! On either, [`SimplyClientBuilder`](crate::SimplyClientBuilder) can
+//! [trust additional root certificates](crate::SimplyClientBuilder::root_certificates_pem),
+//! [stop trusting the built-in ones](crate::SimplyClientBuilder::tls_built_in_root_certs)
+//! and [require a minimum TLS version](crate::SimplyClientBuilder::min_tls_version).
+//!
+//! With the `tls-pinning` feature, the client can also
+//! [pin certificates](crate::SimplyClientBuilder::pin_certificate): it then
+//! only talks to a server whose certificate chain, valid as usual, contains a
+//! pinned certificate, so a rogue certificate from a trusted authority can't
+//! intercept the credentials. Pin the API's leaf certificate or, to survive
+//! its renewal, the intermediate certificate that issued it.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! # #[cfg(feature = "tls-pinning")]
+//! # fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! use simply_dns::SimplyClient;
+//! use simply_dns::tls::CertificatePin;
+//!
+//! // openssl x509 -in intermediate.pem -outform der | sha256sum
+//! let pin: CertificatePin =
+//!     "4a6c4ec4e3b0d8e5eb6e70f0e7b1d3c2a5f6e7d8c9b0a1f2e3d4c5b6a7980f1e".parse()?;
+//! let client = SimplyClient::builder()
+//!     .credentials("account", "api_key")
+//!     .min_tls_version(reqwest::tls::Version::TLS_1_2)
+//!     .pin_certificate(pin)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use crate::SimplyClientError;
+
+/// The TLS settings of a [`SimplyClientBuilder`](crate::SimplyClientBuilder).
+#[derive(Default)]
+pub(crate) struct TlsSettings {
+    pub(crate) root_certificates: Vec<Vec<u8>>,
+    pub(crate) built_in_roots: Option<bool>,
+    pub(crate) min_version: Option<reqwest::tls::Version>,
+    #[cfg(feature = "tls-pinning")]
+    pub(crate) pins: Vec<CertificatePin>,
+}
+
+impl fmt::Debug for TlsSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("TlsSettings");
+        s.field("root_certificates", &self.root_certificates.len())
+            .field("built_in_roots", &self.built_in_roots)
+            .field("min_version", &self.min_version);
+        #[cfg(feature = "tls-pinning")]
+        s.field("pins", &self.pins);
+        s.finish()
+    }
+}
+
+impl TlsSettings {
+    /// Whether any setting was changed from the defaults.
+    pub(crate) fn is_customized(&self) -> bool {
+        #[cfg(feature = "tls-pinning")]
+        if !self.pins.is_empty() {
+            return true;
+        }
+        !self.root_certificates.is_empty()
+            || self.built_in_roots.is_some()
+            || self.min_version.is_some()
+    }
+
+    /// Apply the settings to `builder`.
+    ///
+    /// Returns [`SimplyClientError::Config`] if a root certificate can't be parsed.
+    pub(crate) fn apply(
+        &self,
+        mut builder: reqwest::ClientBuilder,
+    ) -> Result<reqwest::ClientBuilder, SimplyClientError> {
+        #[cfg(feature = "tls-pinning")]
+        if !self.pins.is_empty() {
+            return Ok(builder.use_preconfigured_tls(pinning::client_config(self)?));
+        }
+        if let Some(version) = self.min_version {
+            builder = builder.min_tls_version(version);
+        }
+        if let Some(enabled) = self.built_in_roots {
+            builder = builder.tls_built_in_root_certs(enabled);
+        }
+        for pem in &self.root_certificates {
+            for certificate in reqwest::Certificate::from_pem_bundle(pem)
+                .map_err(|e| SimplyClientError::Config(format!("invalid root certificate: {e}")))?
+            {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        Ok(builder)
+    }
+}
+
+/// The SHA-256 fingerprint of a certificate to pin, see
+/// [`SimplyClientBuilder::pin_certificate`](crate::SimplyClientBuilder::pin_certificate).
+///
+/// Parses from 64 hex digits, optionally separated by colons, as printed by
+/// `sha256sum` of the DER certificate or `openssl x509 -fingerprint -sha256`.
+#[cfg(feature = "tls-pinning")]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CertificatePin([u8; 32]);
+
+#[cfg(feature = "tls-pinning")]
+impl CertificatePin {
+    /// A pin on the certificate whose DER encoding has the SHA-256 hash `sha256`.
+    pub fn from_sha256(sha256: [u8; 32]) -> Self {
+        Self(sha256)
+    }
+
+    /// A pin on the certificate `der`, DER encoded.
+    pub fn of_certificate(der: &[u8]) -> Self {
+        let digest = ring::digest::digest(&ring::digest::SHA256, der);
+        let mut sha256 = [0; 32];
+        sha256.copy_from_slice(digest.as_ref());
+        Self(sha256)
+    }
+
+    /// Whether `der` is the pinned certificate.
+    pub fn matches(&self, der: &[u8]) -> bool {
+        *self == Self::of_certificate(der)
+    }
+}
+
+#[cfg(feature = "tls-pinning")]
+impl std::str::FromStr for CertificatePin {
+    type Err = SimplyClientError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || SimplyClientError::Config(format!("{s:?} is not a SHA-256 certificate fingerprint"));
+        let hex: Vec<u8> = s.bytes().filter(|b| *b != b':').collect();
+        if hex.len() != 64 {
+            return Err(invalid());
+        }
+        let mut sha256 = [0; 32];
+        for (byte, pair) in sha256.iter_mut().zip(hex.chunks(2)) {
+            let pair = std::str::from_utf8(pair).map_err(|_| invalid())?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| invalid())?;
+        }
+        Ok(Self(sha256))
+    }
+}
+
+/// Shows the fingerprint in lower case hex.
+#[cfg(feature = "tls-pinning")]
+impl fmt::Display for CertificatePin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{b:02x}"))
+    }
+}
+
+#[cfg(feature = "tls-pinning")]
+impl fmt::Debug for CertificatePin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CertificatePin({self})")
+    }
+}
+
+#[cfg(feature = "tls-pinning")]
+mod pinning {
+    use std::sync::Arc;
+
+    use rustls::client::WebPkiServerVerifier;
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+
+    use super::{CertificatePin, TlsSettings};
+    use crate::SimplyClientError;
+
+    /// Verifies certificates as usual, then requires a pinned one in the chain.
+    #[derive(Debug)]
+    struct PinnedVerifier {
+        inner: Arc<WebPkiServerVerifier>,
+        pins: Vec<CertificatePin>,
+    }
+
+    impl ServerCertVerifier for PinnedVerifier {
+        fn verify_server_cert(
+            &self,
+            end_entity: &CertificateDer<'_>,
+            intermediates: &[CertificateDer<'_>],
+            server_name: &ServerName<'_>,
+            ocsp_response: &[u8],
+            now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            let verified = self.inner.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                ocsp_response,
+                now,
+            )?;
+            let pinned = std::iter::once(end_entity)
+                .chain(intermediates)
+                .any(|cert| self.pins.iter().any(|pin| pin.matches(cert)));
+            if !pinned {
+                return Err(rustls::Error::General(
+                    "no certificate of the chain is pinned".to_string(),
+                ));
+            }
+            Ok(verified)
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            self.inner.verify_tls12_signature(message, cert, dss)
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            self.inner.verify_tls13_signature(message, cert, dss)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.inner.supported_verify_schemes()
+        }
+    }
+
+    /// A rustls configuration enforcing `settings`, including its pins.
+    pub(super) fn client_config(settings: &TlsSettings) -> Result<ClientConfig, SimplyClientError> {
+        let config_error = |e: &dyn std::fmt::Display| SimplyClientError::Config(e.to_string());
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let mut roots = RootCertStore::empty();
+        if settings.built_in_roots != Some(false) {
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        }
+        for pem in &settings.root_certificates {
+            for cert in CertificateDer::pem_slice_iter(pem) {
+                let cert = cert.map_err(|e| {
+                    SimplyClientError::Config(format!("invalid root certificate: {e}"))
+                })?;
+                roots.add(cert).map_err(|e| {
+                    SimplyClientError::Config(format!("invalid root certificate: {e}"))
+                })?;
+            }
+        }
+        let inner = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+            .build()
+            .map_err(|e| config_error(&e))?;
+        let versions: &[&rustls::SupportedProtocolVersion] =
+            if settings.min_version == Some(reqwest::tls::Version::TLS_1_3) {
+                &[&rustls::version::TLS13]
+            } else {
+                rustls::DEFAULT_VERSIONS
+            };
+        let mut config = ClientConfig::builder_with_provider(provider)
+            .with_protocol_versions(versions)
+            .map_err(|e| config_error(&e))?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(PinnedVerifier {
+                inner,
+                pins: settings.pins.clone(),
+            }))
+            .with_no_client_auth();
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(config)
+    }
+}
@@ -0,0 +1,188 @@
+//! Lowering TTLs ahead of a migration, and restoring them afterwards.
+//!
+//! Before moving records to new addresses, their TTLs are usually lowered so
+//! that resolvers pick up the change quickly. [`SimplyClient::lower_ttls`]
+//! does this for every record matching a filter and returns a [`TtlSnapshot`]
+//! of the original values, which [`SimplyClient::restore_ttls`] puts back once
+//! the migration is done. Snapshots serialize to JSON, so they can be kept in
+//! a file or a [`StateStore`](crate::state::StateStore) between the two steps.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::query::RecordQuery;
+//! use simply_dns::ttl::TtlSnapshot;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let filter = RecordQuery::new().record_type("A");
+//! let (snapshot, report) = client.lower_ttls("example.com", &filter, 60).await?;
+//! println!("{report}");
+//! std::fs::write("ttls.json", serde_json::to_vec(&snapshot)?)?;
+//!
+//! // ... wait for the old TTLs to pass and migrate ...
+//!
+//! let snapshot: TtlSnapshot = serde_json::from_slice(&std::fs::read("ttls.json")?)?;
+//! println!("{}", client.restore_ttls("example.com", &snapshot).await?);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::api::{DnsRecord, DnsRecordId, UpdateDnsRecordRequest};
+use crate::progress::Operation;
+use crate::query::RecordQuery;
+use crate::report::OperationReport;
+use crate::time::Instant;
+use crate::{SimplyClient, SimplyClientError};
+
+/// The TTLs of a set of records, as they were before [`SimplyClient::lower_ttls`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TtlSnapshot {
+    /// The records and their original TTLs.
+    pub entries: Vec<TtlSnapshotEntry>,
+}
+
+/// The original TTL of one record in a [`TtlSnapshot`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtlSnapshotEntry {
+    /// The record the TTL belongs to.
+    pub record_id: DnsRecordId,
+    /// The record name, for reference.
+    pub name: String,
+    /// The record type, for reference.
+    #[serde(rename = "type")]
+    pub record_type: String,
+    /// The TTL to restore, in seconds.
+    pub ttl: u32,
+}
+
+/// Shows the record and the TTL to restore, e.g. `www A (ttl 3600)`.
+impl fmt::Display for TtlSnapshotEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} (ttl {})", self.name, self.record_type, self.ttl)
+    }
+}
+
+impl TtlSnapshot {
+    /// The highest TTL in the snapshot: how long resolvers may keep serving
+    /// the records from before they were lowered.
+    pub fn max_ttl(&self) -> Option<Duration> {
+        self.entries
+            .iter()
+            .map(|e| Duration::from_secs(e.ttl.into()))
+            .max()
+    }
+}
+
+impl SimplyClient {
+    /// Set the TTL of every record of a domain matching `filter` to `ttl`.
+    ///
+    /// Records whose TTL is already at or below `ttl` are left alone and
+    /// reported as skipped. The returned snapshot holds the original TTL of
+    /// every lowered record, including those whose update failed, so that
+    /// [`restore_ttls`](Self::restore_ttls) can undo a partial run. Fails only
+    /// if the records can't be listed.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain whose records to change.
+    /// * `filter` - Which records to change.
+    /// * `ttl` - The new TTL, in seconds.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn lower_ttls(
+        &self,
+        domain: &str,
+        filter: &RecordQuery,
+        ttl: u32,
+    ) -> Result<(TtlSnapshot, OperationReport<DnsRecord>), SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let started = Instant::now();
+        let mut snapshot = TtlSnapshot::default();
+        let mut report = OperationReport::default();
+        let records = this.find_records(domain, filter).await?;
+        let mut progress = this.progress(Operation::LowerTtls, Some(records.len()));
+        for mut record in records {
+            record.name = this.api_name(&record.name, domain);
+            if record.ttl <= ttl {
+                let reason = format!("TTL is already {}", record.ttl);
+                progress.skip(&mut report, record, reason);
+                continue;
+            }
+            snapshot.entries.push(TtlSnapshotEntry {
+                record_id: record.record_id,
+                name: record.name.clone(),
+                record_type: record.record_type.clone(),
+                ttl: record.ttl,
+            });
+            progress.begin();
+            let record_started = Instant::now();
+            let result = this.set_ttl(domain, &record, ttl).await;
+            record.ttl = ttl;
+            progress.record(&mut report, record, result, record_started);
+        }
+        report.elapsed = started.elapsed();
+        progress.finish(&report);
+        Ok((snapshot, report))
+    }
+
+    /// Put back the TTLs captured by [`lower_ttls`](Self::lower_ttls).
+    ///
+    /// Entries whose record was deleted in the meantime, or already has its
+    /// original TTL, are reported as skipped. Fails only if the records can't
+    /// be listed.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the snapshot was taken of.
+    /// * `snapshot` - The original TTLs.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn restore_ttls(
+        &self,
+        domain: &str,
+        snapshot: &TtlSnapshot,
+    ) -> Result<OperationReport<TtlSnapshotEntry>, SimplyClientError> {
+        let lock = self.lock_domain(domain).await?;
+        let this = lock.client();
+        let started = Instant::now();
+        let mut report = OperationReport::default();
+        let records = this.list_dns_records(domain).await?;
+        let mut progress = this.progress(Operation::RestoreTtls, Some(snapshot.entries.len()));
+        for entry in &snapshot.entries {
+            let Some(record) = records.iter().find(|r| r.record_id == entry.record_id) else {
+                progress.skip(&mut report, entry.clone(), "record no longer exists");
+                continue;
+            };
+            if record.ttl == entry.ttl {
+                progress.skip(&mut report, entry.clone(), "TTL is unchanged");
+                continue;
+            }
+            progress.begin();
+            let record_started = Instant::now();
+            let result = this.set_ttl(domain, record, entry.ttl).await;
+            progress.record(&mut report, entry.clone(), result, record_started);
+        }
+        report.elapsed = started.elapsed();
+        progress.finish(&report);
+        Ok(report)
+    }
+
+    /// Update `record` to `ttl`, keeping its other fields.
+    async fn set_ttl(
+        &self,
+        domain: &str,
+        record: &DnsRecord,
+        ttl: u32,
+    ) -> Result<(), SimplyClientError> {
+        let req = UpdateDnsRecordRequest {
+            ttl: Some(ttl),
+            ..record.clone().into()
+        };
+        self.update_dns_record(domain, record.record_id, req).await
+    }
+}
@@ -0,0 +1,486 @@
+//! Client-side validation of records.
+//!
+//! The API answers invalid records with terse errors, or accepts records that
+//! won't resolve. The checks here catch the common mistakes before a request is
+//! sent: malformed data for the record type (e.g. an A record that isn't an
+//! IPv4 address), SRV names without `_service._proto`, CNAMEs at the apex or
+//! next to other records, overlong names or TXT data, and TTLs out of range.
+//!
+//! [`SimplyClient`](crate::SimplyClient) validates every record it creates or
+//! updates, unless turned off with
+//! [`SimplyClientBuilder::validate_records`](crate::SimplyClientBuilder::validate_records).
+//! How strictly names are checked is set with [`NameStrictness`], e.g. with
+//! [`SimplyClientBuilder::name_strictness`](crate::SimplyClientBuilder::name_strictness)
+//! for zones with unusual names.
+//! Conflicts between records can only be seen with the whole zone at hand, so
+//! they are checked by [`validate_zone`] and [`DesiredZone::validate`].
+//!
+//! Example usage:
+//! ```rust
+//! use simply_dns::api::CreateDnsRecordRequest;
+//! use simply_dns::record_data::RecordData;
+//!
+//! let mut req = CreateDnsRecordRequest::new("_sip._tcp", RecordData::Srv {
+//!     priority: 10,
+//!     weight: 5,
+//!     port: 5060,
+//!     target: "sip.example.com".to_string(),
+//! });
+//! assert!(req.validate().is_ok());
+//! req.name = "sip".to_string();
+//! assert!(req.validate().is_err());
+//! ```
+
+use crate::SimplyClientError;
+use crate::api::{CreateDnsRecordRequest, DnsRecord, UpdateDnsRecordRequest};
+use crate::record_data::RecordData;
+use crate::sync::DesiredZone;
+
+/// The lowest TTL accepted, in seconds.
+pub const MIN_TTL: u32 = 60;
+/// The highest TTL accepted, in seconds, per RFC 2181.
+pub const MAX_TTL: u32 = i32::MAX as u32;
+/// The longest TXT data accepted, in bytes.
+///
+/// Longer text than 255 bytes is sent as several strings of one record, so
+/// this bounds the record as a whole.
+pub const MAX_TXT_LENGTH: usize = 4096;
+
+/// How strictly record names are checked.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::validate::{NameStrictness, validate_record_with};
+///
+/// let check = |name, strictness| validate_record_with(name, "A", "192.0.2.1", None, None, strictness);
+/// assert!(check("_host", NameStrictness::Pragmatic).is_ok());
+/// assert!(check("_host", NameStrictness::Strict).is_err());
+/// assert!(check("odd/name", NameStrictness::Off).is_ok());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NameStrictness {
+    /// Hostname rules of RFC 1123: labels of letters, digits and inner
+    /// hyphens. Underscores may only start a label of a service name, such
+    /// as `_dmarc` or `_sip._tcp`, and never in names of A, AAAA or MX records.
+    Strict,
+    /// Labels of letters, digits, hyphens and underscores, as found in most zones.
+    #[default]
+    Pragmatic,
+    /// Names aren't checked at all, besides SRV names needing `_service._proto`.
+    Off,
+}
+
+/// Check a single record, as sent to the API, with [`NameStrictness::Pragmatic`].
+///
+/// # Arguments
+/// * `name` - The zone-relative record name, e.g. "www" or "@".
+/// * `record_type` - Type of the record ("A", "MX", etc.).
+/// * `data` - The record data.
+/// * `priority` - The priority, if any.
+/// * `ttl` - The TTL in seconds, if any.
+pub fn validate_record(
+    name: &str,
+    record_type: &str,
+    data: &str,
+    priority: Option<u32>,
+    ttl: Option<u32>,
+) -> Result<(), SimplyClientError> {
+    validate_record_with(
+        name,
+        record_type,
+        data,
+        priority,
+        ttl,
+        NameStrictness::default(),
+    )
+}
+
+/// Like [`validate_record`], checking the name with `strictness`.
+pub fn validate_record_with(
+    name: &str,
+    record_type: &str,
+    data: &str,
+    priority: Option<u32>,
+    ttl: Option<u32>,
+    strictness: NameStrictness,
+) -> Result<(), SimplyClientError> {
+    let record_type = record_type.to_ascii_uppercase();
+    validate_name(name, &record_type, strictness)?;
+    if let Some(ttl) = ttl
+        && !(MIN_TTL..=MAX_TTL).contains(&ttl)
+    {
+        return Err(invalid(
+            "ttl",
+            format!("TTL {ttl} is outside {MIN_TTL}..={MAX_TTL}"),
+        ));
+    }
+    let parsed = RecordData::parse(&record_type, data, priority)
+        .map_err(|e| invalid("data", e.to_string()))?;
+    match parsed {
+        RecordData::Cname(target)
+        | RecordData::Ns(target)
+        | RecordData::Ptr(target)
+        | RecordData::Mx {
+            exchange: target, ..
+        }
+        | RecordData::Srv { target, .. }
+            if target.is_empty() =>
+        {
+            Err(invalid(
+                "data",
+                format!("{record_type} record without a target"),
+            ))
+        }
+        RecordData::Cname(_) if is_apex(name) => Err(invalid(
+            "name",
+            "CNAME records are not allowed at the apex, use ALIAS instead",
+        )),
+        RecordData::Txt(text) if text.len() > MAX_TXT_LENGTH => Err(invalid(
+            "data",
+            format!(
+                "TXT data is {} bytes, at most {MAX_TXT_LENGTH} are allowed",
+                text.len()
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Check that the records of a zone don't conflict: a name with a CNAME
+/// record may have no other records.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::validate::validate_zone;
+///
+/// assert!(validate_zone([("www", "CNAME"), ("mail", "A")]).is_ok());
+/// assert!(validate_zone([("www", "CNAME"), ("www", "TXT")]).is_err());
+/// ```
+pub fn validate_zone<'a>(
+    records: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Result<(), SimplyClientError> {
+    let records: Vec<(String, &str)> = records
+        .into_iter()
+        .map(|(name, record_type)| (apex_as_at(name), record_type))
+        .collect();
+    for (name, record_type) in &records {
+        if !record_type.eq_ignore_ascii_case("CNAME") {
+            continue;
+        }
+        let others = records.iter().filter(|(n, _)| n == name).count();
+        if others > 1 {
+            return Err(invalid(
+                "name",
+                format!("{name} has a CNAME record, so it can't have other records"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check the name of a record of `record_type`, already upper case.
+fn validate_name(
+    name: &str,
+    record_type: &str,
+    strictness: NameStrictness,
+) -> Result<(), SimplyClientError> {
+    if is_apex(name) {
+        return if record_type == "SRV" {
+            Err(invalid("name", "SRV records need a _service._proto name"))
+        } else {
+            Ok(())
+        };
+    }
+    let name = name.trim_end_matches('.');
+    let labels: Vec<&str> = name.split('.').collect();
+    if strictness == NameStrictness::Off {
+        return validate_srv_name(name, &labels, record_type);
+    }
+    if name.len() > 253 {
+        return Err(invalid("name", format!("{name} is longer than 253 bytes")));
+    }
+    let hostname = matches!(record_type, "A" | "AAAA" | "MX");
+    for (i, label) in labels.iter().enumerate() {
+        if label.is_empty() {
+            return Err(invalid("name", format!("{name} has an empty label")));
+        }
+        if label.len() > 63 {
+            return Err(invalid(
+                "name",
+                format!("{label} in {name} is longer than 63 bytes"),
+            ));
+        }
+        let valid = *label == "*" && i == 0
+            || match strictness {
+                NameStrictness::Strict => {
+                    let ldh = label
+                        .strip_prefix('_')
+                        .filter(|_| !hostname)
+                        .unwrap_or(label);
+                    !ldh.starts_with('-')
+                        && !ldh.ends_with('-')
+                        && ldh.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+                }
+                _ => label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'),
+            };
+        if !valid {
+            return Err(invalid(
+                "name",
+                format!("{name} has an invalid label {label}"),
+            ));
+        }
+    }
+    validate_srv_name(name, &labels, record_type)
+}
+
+/// Check that an SRV record's name starts with `_service._proto`.
+fn validate_srv_name(
+    name: &str,
+    labels: &[&str],
+    record_type: &str,
+) -> Result<(), SimplyClientError> {
+    if record_type == "SRV"
+        && !(labels.len() >= 2 && labels[..2].iter().all(|l| l.starts_with('_')))
+    {
+        return Err(invalid(
+            "name",
+            format!("SRV record {name} must start with _service._proto, e.g. _sip._tcp"),
+        ));
+    }
+    Ok(())
+}
+
+fn is_apex(name: &str) -> bool {
+    matches!(name, "" | "@")
+}
+
+fn apex_as_at(name: &str) -> String {
+    if is_apex(name) {
+        "@".to_string()
+    } else {
+        name.trim_end_matches('.').to_ascii_lowercase()
+    }
+}
+
+fn invalid(field: &str, message: impl Into<String>) -> SimplyClientError {
+    SimplyClientError::Validation {
+        status: None,
+        field: Some(field.to_string()),
+        message: message.into(),
+    }
+}
+
+impl CreateDnsRecordRequest {
+    /// Check the record client-side, see [`validate_record`].
+    pub fn validate(&self) -> Result<(), SimplyClientError> {
+        self.validate_with(NameStrictness::default())
+    }
+
+    /// Check the record client-side with `strictness`, see [`validate_record_with`].
+    pub fn validate_with(&self, strictness: NameStrictness) -> Result<(), SimplyClientError> {
+        validate_record_with(
+            &self.name,
+            &self.record_type,
+            &self.data,
+            self.priority,
+            self.ttl,
+            strictness,
+        )
+    }
+}
+
+impl UpdateDnsRecordRequest {
+    /// Check the record client-side, see [`validate_record`].
+    pub fn validate(&self) -> Result<(), SimplyClientError> {
+        self.validate_with(NameStrictness::default())
+    }
+
+    /// Check the record client-side with `strictness`, see [`validate_record_with`].
+    pub fn validate_with(&self, strictness: NameStrictness) -> Result<(), SimplyClientError> {
+        validate_record_with(
+            &self.name,
+            &self.record_type,
+            &self.data,
+            self.priority,
+            self.ttl,
+            strictness,
+        )
+    }
+}
+
+impl DnsRecord {
+    /// Check the record client-side, see [`validate_record`].
+    pub fn validate(&self) -> Result<(), SimplyClientError> {
+        validate_record(
+            &self.name,
+            &self.record_type,
+            &self.data,
+            self.priority,
+            Some(self.ttl),
+        )
+    }
+}
+
+impl DesiredZone {
+    /// Check every record of the zone and the conflicts between them.
+    ///
+    /// Example usage:
+    /// ```rust
+    /// use simply_dns::sync::{DesiredRecord, DesiredZone};
+    ///
+    /// let zone = DesiredZone::from_iter([
+    ///     DesiredRecord::new("www", "CNAME", "example.com"),
+    ///     DesiredRecord::new("www", "A", "192.0.2.1"),
+    /// ]);
+    /// assert!(zone.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), SimplyClientError> {
+        for record in &self.records {
+            validate_record(
+                &record.name,
+                &record.record_type,
+                &record.data,
+                // An unset priority keeps the live one, so it isn't missing.
+                record.priority.or(Some(0)),
+                record.ttl,
+            )?;
+        }
+        validate_zone(
+            self.records
+                .iter()
+                .map(|r| (r.name.as_str(), r.record_type.as_str())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The field and message of the error, if the record is invalid.
+    fn check(
+        name: &str,
+        record_type: &str,
+        data: &str,
+        priority: Option<u32>,
+        ttl: Option<u32>,
+    ) -> Option<(String, String)> {
+        match validate_record(name, record_type, data, priority, ttl) {
+            Ok(()) => None,
+            Err(SimplyClientError::Validation { field, message, .. }) => {
+                Some((field.unwrap_or_default(), message))
+            }
+            Err(e) => panic!("unexpected error {e}"),
+        }
+    }
+
+    #[test]
+    fn valid_records_pass() {
+        for (name, record_type, data, priority) in [
+            ("@", "A", "192.0.2.1", None),
+            ("", "AAAA", "2001:db8::1", None),
+            ("www", "CNAME", "example.com", None),
+            ("www.example.com.", "a", "192.0.2.1", None),
+            ("*", "A", "192.0.2.1", None),
+            ("*.apps", "CNAME", "lb.example.net", None),
+            ("@", "MX", "mail.example.com", Some(10)),
+            ("_sip._tcp", "SRV", "5 5060 sip.example.com", Some(10)),
+            ("_sip._tcp.eu", "SRV", "5 5060 sip.example.com", Some(10)),
+            ("_dmarc", "TXT", "v=DMARC1; p=none", None),
+            (
+                "host_name",
+                "TXT",
+                "pragmatic names allow underscores",
+                None,
+            ),
+            ("@", "TXT", &"x".repeat(MAX_TXT_LENGTH), None),
+            ("@", "CAA", "0 issue \"letsencrypt.org\"", None),
+            ("@", "ALIAS", "example.net", None),
+            ("@", "SSHFP", "anything goes for untyped data", None),
+        ] {
+            assert_eq!(
+                check(name, record_type, data, priority, None),
+                None,
+                "{name} {record_type}"
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_records_are_rejected_with_the_field() {
+        let long_label = "a".repeat(64);
+        let long_name = [
+            "a".repeat(63),
+            "b".repeat(63),
+            "c".repeat(63),
+            "d".repeat(63),
+        ]
+        .join(".");
+        for (name, record_type, data, priority, field) in [
+            ("www", "A", "not-an-ip", None, "data"),
+            ("www", "A", "2001:db8::1", None, "data"),
+            ("www", "AAAA", "192.0.2.1", None, "data"),
+            ("www", "MX", "mail.example.com", None, "data"),
+            ("www", "MX", "", Some(10), "data"),
+            ("www", "CNAME", "", None, "data"),
+            ("www", "SRV", "5 5060 sip.example.com", Some(10), "name"),
+            ("_sip", "SRV", "5 5060 sip.example.com", Some(10), "name"),
+            ("@", "SRV", "5 5060 sip.example.com", Some(10), "name"),
+            ("_sip._tcp", "SRV", "5 sip.example.com", Some(10), "data"),
+            ("@", "CNAME", "example.net", None, "name"),
+            ("", "CNAME", "example.net", None, "name"),
+            ("@", "TXT", &"x".repeat(MAX_TXT_LENGTH + 1), None, "data"),
+            ("a..b", "A", "192.0.2.1", None, "name"),
+            (long_label.as_str(), "A", "192.0.2.1", None, "name"),
+            (long_name.as_str(), "A", "192.0.2.1", None, "name"),
+            ("bad name", "A", "192.0.2.1", None, "name"),
+            ("w*w", "A", "192.0.2.1", None, "name"),
+            ("www.*", "A", "192.0.2.1", None, "name"),
+        ] {
+            let error = check(name, record_type, data, priority, None);
+            assert_eq!(
+                error.as_ref().map(|(f, _)| f.as_str()),
+                Some(field),
+                "{name} {record_type} {data:?}: {error:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn ttls_must_be_in_range() {
+        for (ttl, valid) in [
+            (0, false),
+            (MIN_TTL - 1, false),
+            (MIN_TTL, true),
+            (3600, true),
+            (MAX_TTL, true),
+            (MAX_TTL + 1, false),
+        ] {
+            let error = check("www", "A", "192.0.2.1", None, Some(ttl));
+            assert_eq!(error.is_none(), valid, "{ttl}: {error:?}");
+            if let Some((field, _)) = error {
+                assert_eq!(field, "ttl");
+            }
+        }
+    }
+
+    #[test]
+    fn names_with_a_cname_have_no_other_records() {
+        for (records, valid) in [
+            (&[("www", "CNAME"), ("mail", "A"), ("mail", "MX")][..], true),
+            (&[("www", "CNAME"), ("www", "A")], false),
+            (&[("www", "cname"), ("WWW.", "TXT")], false),
+            (&[("@", "A"), ("", "TXT")], true),
+            (&[("www", "CNAME"), ("www", "CNAME")], false),
+            (&[], true),
+        ] {
+            assert_eq!(
+                validate_zone(records.iter().copied()).is_ok(),
+                valid,
+                "{records:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Checking that the authoritative name servers serve what the API lists.
+//!
+//! [`SimplyClient::verify_zone`] lists the records of a zone and asks each of
+//! its authoritative name servers for every name and type, e.g. to confirm
+//! that a migration or a large apply is actually live, or to spot a zone that
+//! isn't delegated to Simply.com at all.
+//!
+//! Each name is also asked for the common types the API doesn't list there,
+//! so records served from elsewhere show up as extra. Without a zone transfer,
+//! names the API doesn't list at all can't be checked.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//!
+//! # async fn run() -> Result<(), simply_dns::verify::VerifyError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let verification = client.verify_zone("example.com").await?;
+//! if !verification.is_clean() {
+//!     println!("{verification}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::net::SocketAddr;
+
+use thiserror::Error;
+
+use crate::api::DnsRecord;
+use crate::names;
+use crate::propagation::{self, PropagationChecker, PropagationError};
+use crate::{SimplyClient, SimplyClientError};
+
+/// The types asked for at every name, to find records the API doesn't list.
+const PROBED_TYPES: [&str; 6] = ["A", "AAAA", "CNAME", "MX", "TXT", "CAA"];
+
+/// Error type for [`SimplyClient::verify_zone`].
+#[derive(Debug, Error)]
+pub enum VerifyError {
+    /// The Simply.com API call failed.
+    #[error(transparent)]
+    Client(#[from] SimplyClientError),
+    /// The name servers of the zone could not be determined.
+    #[error(transparent)]
+    Propagation(#[from] PropagationError),
+}
+
+/// Record data one server serves differently from the API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordMismatch {
+    /// The name server that was asked.
+    pub server: SocketAddr,
+    /// The record name relative to the zone, "@" for the apex.
+    pub name: String,
+    /// Type of DNS record ("A", "TXT", etc.).
+    pub record_type: String,
+    /// The data the API lists, sorted.
+    pub expected: Vec<String>,
+    /// The data the server returned, sorted.
+    pub served: Vec<String>,
+}
+
+/// Records a server serves under a name and type, or should.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServedRecords {
+    /// The name server that was asked.
+    pub server: SocketAddr,
+    /// The record name relative to the zone, "@" for the apex.
+    pub name: String,
+    /// Type of DNS record ("A", "TXT", etc.).
+    pub record_type: String,
+    /// The record data, sorted.
+    pub data: Vec<String>,
+}
+
+/// A query that failed, so its name and type couldn't be checked on that server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedQuery {
+    /// The name server that was asked.
+    pub server: SocketAddr,
+    /// The record name relative to the zone, "@" for the apex.
+    pub name: String,
+    /// Type of DNS record ("A", "TXT", etc.).
+    pub record_type: String,
+    /// What went wrong.
+    pub message: String,
+}
+
+/// How the authoritative name servers compare with the API, from
+/// [`SimplyClient::verify_zone`].
+#[derive(Debug, Clone, Default)]
+pub struct ZoneVerification {
+    /// The domain that was verified.
+    pub domain: String,
+    /// The name servers that were asked.
+    pub servers: Vec<SocketAddr>,
+    /// Names and types served with other data than the API lists.
+    pub mismatched: Vec<RecordMismatch>,
+    /// Names and types the API lists but a server doesn't serve.
+    pub missing: Vec<ServedRecords>,
+    /// Names and types a server serves but the API doesn't list.
+    pub extra: Vec<ServedRecords>,
+    /// Records of types that can't be queried, such as ALIAS, which weren't checked.
+    pub unverified: Vec<DnsRecord>,
+    /// Queries that failed.
+    pub failed: Vec<FailedQuery>,
+}
+
+impl ZoneVerification {
+    /// Whether every server answered and served exactly what the API lists.
+    pub fn is_clean(&self) -> bool {
+        self.mismatched.is_empty()
+            && self.missing.is_empty()
+            && self.extra.is_empty()
+            && self.failed.is_empty()
+    }
+}
+
+/// Shows one line per difference, e.g. `~ www A [192.0.2.1] -> [192.0.2.2] at 192.0.2.53:53`.
+impl fmt::Display for ZoneVerification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return write!(f, "{} is served as listed", self.domain);
+        }
+        for m in &self.mismatched {
+            writeln!(
+                f,
+                "~ {} {} {:?} -> {:?} at {}",
+                m.name, m.record_type, m.expected, m.served, m.server
+            )?;
+        }
+        for r in &self.missing {
+            writeln!(
+                f,
+                "- {} {} {:?} at {}",
+                r.name, r.record_type, r.data, r.server
+            )?;
+        }
+        for r in &self.extra {
+            writeln!(
+                f,
+                "+ {} {} {:?} at {}",
+                r.name, r.record_type, r.data, r.server
+            )?;
+        }
+        for q in &self.failed {
+            writeln!(
+                f,
+                "! {} {} at {}: {}",
+                q.name, q.record_type, q.server, q.message
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl SimplyClient {
+    /// Compare the records of `domain` with what its authoritative name
+    /// servers serve, with the default [`PropagationChecker`].
+    ///
+    /// See [`verify_zone_with`](Self::verify_zone_with).
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to verify.
+    pub async fn verify_zone(&self, domain: &str) -> Result<ZoneVerification, VerifyError> {
+        self.verify_zone_with(domain, &PropagationChecker::default())
+            .await
+    }
+
+    /// Compare the records of `domain` with what its authoritative name
+    /// servers serve, finding them and querying them with `checker`.
+    ///
+    /// Every server is asked separately, so a secondary lagging behind shows
+    /// up on its own. Data is compared like
+    /// [`PropagationChecker::wait_for_record`] does; TTLs aren't compared.
+    /// Fails only if the records can't be listed or the name servers
+    /// can't be found; failed queries are collected in the result.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain to verify.
+    /// * `checker` - The resolvers and timeouts to use.
+    ///
+    /// See: https://www.simply.com/en/docs/api/
+    pub async fn verify_zone_with(
+        &self,
+        domain: &str,
+        checker: &PropagationChecker,
+    ) -> Result<ZoneVerification, VerifyError> {
+        let records = self.list_dns_records(domain).await?;
+        let mut servers = checker.name_servers(domain).await?;
+        servers.sort();
+        servers.dedup();
+
+        let mut verification = ZoneVerification {
+            domain: domain.to_string(),
+            servers: servers.clone(),
+            ..Default::default()
+        };
+        // The API's data per name, then per queryable type.
+        let mut expected: BTreeMap<String, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+        let mut aliased = BTreeSet::new();
+        for record in records {
+            let name = names::to_relative(&record.name, domain).to_ascii_lowercase();
+            let record_type = record.record_type.to_ascii_uppercase();
+            let types = expected.entry(name.clone()).or_default();
+            if propagation::type_code(&record_type).is_none() {
+                if record_type == "ALIAS" {
+                    aliased.insert(name);
+                }
+                verification.unverified.push(record);
+                continue;
+            }
+            types.entry(record_type).or_default().push(record.data);
+        }
+
+        for (name, mut types) in expected {
+            let aliased = aliased.contains(&name);
+            let probed: Vec<_> = PROBED_TYPES
+                .into_iter()
+                .filter(|t| probe_at(&types, aliased, t))
+                .collect();
+            for record_type in probed {
+                types.insert(record_type.to_string(), Vec::new());
+            }
+            let fqdn = propagation::fqdn(domain, &name);
+            for (record_type, data) in types {
+                let qtype = propagation::type_code(&record_type).unwrap_or_default();
+                let mut want: Vec<_> = data
+                    .iter()
+                    .map(|d| propagation::normalize(qtype, d))
+                    .collect();
+                want.sort();
+                want.dedup();
+                for &server in &servers {
+                    let answers = match checker.lookup(server, &fqdn, &record_type).await {
+                        Ok(answers) => answers,
+                        Err(e) => {
+                            verification.failed.push(FailedQuery {
+                                server,
+                                name: name.clone(),
+                                record_type: record_type.clone(),
+                                message: e.to_string(),
+                            });
+                            continue;
+                        }
+                    };
+                    let mut served: Vec<_> = answers
+                        .iter()
+                        .map(|d| propagation::normalize(qtype, d))
+                        .collect();
+                    served.sort();
+                    served.dedup();
+                    let records = |data| ServedRecords {
+                        server,
+                        name: name.clone(),
+                        record_type: record_type.clone(),
+                        data,
+                    };
+                    match (want.is_empty(), served.is_empty()) {
+                        (_, _) if served == want => {}
+                        (false, true) => verification.missing.push(records(want.clone())),
+                        (true, false) => verification.extra.push(records(served)),
+                        _ => verification.mismatched.push(RecordMismatch {
+                            server,
+                            name: name.clone(),
+                            record_type: record_type.clone(),
+                            expected: want.clone(),
+                            served,
+                        }),
+                    }
+                }
+            }
+        }
+        Ok(verification)
+    }
+}
+
+/// Whether to ask for `record_type` at a name the API lists `types` for.
+///
+/// A name with a CNAME can't have other data, and servers answer queries for
+/// it with the target's records; ALIAS records are served as A and AAAA.
+fn probe_at(types: &BTreeMap<String, Vec<String>>, aliased: bool, record_type: &str) -> bool {
+    let served_by_alias = aliased && matches!(record_type, "A" | "AAAA");
+    !(types.contains_key(record_type) || types.contains_key("CNAME") || served_by_alias)
+}
@@ -0,0 +1,132 @@
+//! Watching a zone for changes.
+//!
+//! [`ZoneWatcher`] lists a zone periodically and reports what changed since
+//! the previous listing, e.g. to alert on records edited outside of automation.
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use futures_util::StreamExt;
+//! use simply_dns::SimplyClient;
+//! use simply_dns::watch::{ZoneChange, ZoneWatcher};
+//!
+//! # async fn run() {
+//! let client = SimplyClient::new("account", "api_key");
+//! let changes = ZoneWatcher::new(&client, "example.com", Duration::from_secs(60)).into_stream();
+//! let mut changes = std::pin::pin!(changes);
+//! while let Some(change) = changes.next().await {
+//!     match change {
+//!         ZoneChange::Added(r) => println!("added {} {} {}", r.name, r.record_type, r.data),
+//!         ZoneChange::Removed(r) => println!("removed {} {} {}", r.name, r.record_type, r.data),
+//!         ZoneChange::Modified { old, new } => println!("changed {} -> {}", old.data, new.data),
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+use futures_util::Stream;
+use futures_util::stream;
+
+use crate::api::DnsRecord;
+use crate::{SimplyClient, SimplyClientError, SimplyDnsApi};
+
+/// A change between two listings of a zone.
+#[derive(Debug, Clone)]
+pub enum ZoneChange {
+    /// A record appeared.
+    Added(DnsRecord),
+    /// A record disappeared.
+    Removed(DnsRecord),
+    /// A record kept its ID but changed its name, type, data, TTL, priority or comment.
+    Modified {
+        /// The record as previously listed.
+        old: DnsRecord,
+        /// The record as listed now.
+        new: DnsRecord,
+    },
+}
+
+/// Periodically lists a zone and reports the differences.
+///
+/// Records are matched by ID. The first listing only establishes the baseline.
+pub struct ZoneWatcher<'a, A = SimplyClient> {
+    api: &'a A,
+    domain: String,
+    interval: Duration,
+    known: Option<BTreeMap<u32, DnsRecord>>,
+}
+
+impl<'a, A: SimplyDnsApi> ZoneWatcher<'a, A> {
+    /// Watch `domain`, listing it every `interval`.
+    pub fn new(api: &'a A, domain: impl Into<String>, interval: Duration) -> Self {
+        Self {
+            api,
+            domain: domain.into(),
+            interval,
+            known: None,
+        }
+    }
+
+    /// List the zone once and return the changes since the previous call.
+    ///
+    /// The first call returns no changes. On error the previous listing is kept,
+    /// so the next successful call reports everything that changed meanwhile.
+    pub async fn poll(&mut self) -> Result<Vec<ZoneChange>, SimplyClientError> {
+        let current: BTreeMap<u32, DnsRecord> = self
+            .api
+            .list_dns_records(&self.domain)
+            .await?
+            .into_iter()
+            .map(|r| (r.record_id.id, r))
+            .collect();
+        let Some(mut known) = self.known.replace(current.clone()) else {
+            return Ok(Vec::new());
+        };
+
+        let mut changes = Vec::new();
+        for (id, new) in current {
+            match known.remove(&id) {
+                None => changes.push(ZoneChange::Added(new)),
+                Some(old) if !old.same_contents(&new) => {
+                    changes.push(ZoneChange::Modified { old, new })
+                }
+                Some(_) => {}
+            }
+        }
+        changes.extend(known.into_values().map(ZoneChange::Removed));
+        Ok(changes)
+    }
+
+    /// Poll forever, yielding each change as it is found.
+    ///
+    /// Failed listings are retried at the next interval; with the `tracing`
+    /// feature they are logged.
+    pub fn into_stream(self) -> impl Stream<Item = ZoneChange> + 'a {
+        // The flag tells whether a listing was attempted yet; only the first one is immediate.
+        let state = (self, VecDeque::new(), false);
+        stream::unfold(
+            state,
+            |(mut watcher, mut pending, mut started)| async move {
+                loop {
+                    if let Some(change) = pending.pop_front() {
+                        return Some((change, (watcher, pending, started)));
+                    }
+                    if started {
+                        crate::time::sleep(watcher.interval).await;
+                    }
+                    started = true;
+                    match watcher.poll().await {
+                        Ok(changes) => pending.extend(changes),
+                        Err(_e) => {
+                            #[cfg(feature = "tracing")]
+                            tracing::warn!(domain = %watcher.domain, error = %_e, "failed to list zone");
+                        }
+                    }
+                }
+            },
+        )
+    }
+}
@@ -0,0 +1,265 @@
+//! A handle on a single zone.
+//!
+//! Code managing one zone passes the same domain to every call.
+//! [`SimplyClient::zone`] binds it once instead: the methods of the returned
+//! [`DnsZone`] take everything but the domain, so a typo in it can't send
+//! one of the calls to another zone.
+//!
+//! With [`DnsZone::cache_records`], the handle also keeps the record list
+//! it fetched, so looking records up repeatedly costs a single request.
+//! Changes made through the handle clear the cache; changes made by others
+//! during its lifetime aren't seen until [`DnsZone::refresh`].
+//!
+//! Example usage:
+//! ```rust,no_run
+//! use simply_dns::SimplyClient;
+//! use simply_dns::api::UpsertOptions;
+//!
+//! # async fn run() -> Result<(), simply_dns::SimplyClientError> {
+//! let client = SimplyClient::new("account", "api_key");
+//! let zone = client.zone("example.com").cache_records(true);
+//! for record in zone.records().await? {
+//!     println!("{} {} {}", record.name, record.record_type, record.data);
+//! }
+//! zone.upsert("www", "A", "192.0.2.1", UpsertOptions::default())
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::Mutex;
+
+use crate::api::{
+    CreateDnsRecordRequest, DnsRecord, DnsRecordId, UpdateDnsRecordRequest, UpsertOptions,
+    UpsertOutcome, ZoneRecordRef,
+};
+use crate::query::RecordQuery;
+use crate::{SimplyClient, SimplyClientError};
+
+/// A [`SimplyClient`] bound to one domain, from [`SimplyClient::zone`].
+#[derive(Debug)]
+pub struct DnsZone {
+    client: SimplyClient,
+    domain: String,
+    cache: Option<Mutex<Option<Vec<DnsRecord>>>>,
+}
+
+impl SimplyClient {
+    /// A handle on the zone of `domain`, whose methods don't take the domain.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the handle manages.
+    pub fn zone(&self, domain: impl Into<String>) -> DnsZone {
+        DnsZone {
+            client: self.clone(),
+            domain: domain.into(),
+            cache: None,
+        }
+    }
+}
+
+impl DnsZone {
+    /// Keep the record list between calls, until a change is made through
+    /// this handle or [`refresh`](Self::refresh) is called.
+    pub fn cache_records(mut self, enabled: bool) -> Self {
+        self.cache = enabled.then(Default::default);
+        self
+    }
+
+    /// The domain the handle manages.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// The client the handle makes its calls with.
+    pub fn client(&self) -> &SimplyClient {
+        &self.client
+    }
+
+    /// List the records of the zone, as [`SimplyClient::list_dns_records`] does.
+    ///
+    /// With [`cache_records`](Self::cache_records), only the first call
+    /// after a change lists them from the API.
+    pub async fn records(&self) -> Result<Vec<DnsRecord>, SimplyClientError> {
+        let Some(cache) = &self.cache else {
+            return self.client.list_dns_records(&self.domain).await;
+        };
+        if let Some(records) = &*cache.lock().unwrap_or_else(|e| e.into_inner()) {
+            return Ok(records.clone());
+        }
+        let records = self.client.list_dns_records(&self.domain).await?;
+        *cache.lock().unwrap_or_else(|e| e.into_inner()) = Some(records.clone());
+        Ok(records)
+    }
+
+    /// Forget the cached record list, so the next call lists the records again.
+    pub fn refresh(&self) {
+        if let Some(cache) = &self.cache {
+            *cache.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        }
+    }
+
+    /// Get a record of the zone by its ID, `None` if it doesn't exist.
+    ///
+    /// # Arguments
+    /// * `record_id` - The ID of the record.
+    pub async fn get(
+        &self,
+        record_id: DnsRecordId,
+    ) -> Result<Option<DnsRecord>, SimplyClientError> {
+        Ok(self
+            .records()
+            .await?
+            .into_iter()
+            .find(|r| r.record_id == record_id))
+    }
+
+    /// List the records of the zone matching `query`, see [`SimplyClient::find_records`].
+    ///
+    /// # Arguments
+    /// * `query` - The criteria the records must match.
+    pub async fn find(&self, query: &RecordQuery) -> Result<Vec<DnsRecord>, SimplyClientError> {
+        if self.cache.is_none() {
+            return self.client.find_records(&self.domain, query).await;
+        }
+        let records = self.records().await?;
+        Ok(self.client.filter_records(&self.domain, records, query))
+    }
+
+    /// Create a record, see [`SimplyClient::create_dns_record`].
+    ///
+    /// # Arguments
+    /// * `req` - The record to create.
+    pub async fn create(
+        &self,
+        req: CreateDnsRecordRequest,
+    ) -> Result<Vec<DnsRecordId>, SimplyClientError> {
+        let result = self.client.create_dns_record(&self.domain, req).await;
+        self.refresh();
+        result
+    }
+
+    /// Update a record, see [`SimplyClient::update_dns_record`].
+    ///
+    /// # Arguments
+    /// * `record_id` - The ID of the record to update.
+    /// * `req` - The new contents of the record.
+    pub async fn update(
+        &self,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+    ) -> Result<(), SimplyClientError> {
+        let result = self
+            .client
+            .update_dns_record(&self.domain, record_id, req)
+            .await;
+        self.refresh();
+        result
+    }
+
+    /// Update a record only if it still matches `expected`, see
+    /// [`SimplyClient::update_dns_record_if`].
+    ///
+    /// # Arguments
+    /// * `record_id` - The ID of the record to update.
+    /// * `expected` - The record as last read.
+    /// * `req` - The new contents of the record.
+    pub async fn update_if(
+        &self,
+        record_id: DnsRecordId,
+        expected: &DnsRecord,
+        req: UpdateDnsRecordRequest,
+    ) -> Result<(), SimplyClientError> {
+        let result = self
+            .client
+            .update_dns_record_if(&self.domain, record_id, expected, req)
+            .await;
+        self.refresh();
+        result
+    }
+
+    /// Create or update the record matched by name and type, see
+    /// [`SimplyClient::upsert_dns_record`].
+    ///
+    /// # Arguments
+    /// * `name` - The record name, in either style.
+    /// * `record_type` - The record type, e.g. "A".
+    /// * `data` - The data the record should hold.
+    /// * `opts` - Priority, TTL and comment of the record.
+    pub async fn upsert(
+        &self,
+        name: &str,
+        record_type: &str,
+        data: &str,
+        opts: UpsertOptions,
+    ) -> Result<UpsertOutcome, SimplyClientError> {
+        let result = self
+            .client
+            .upsert_dns_record(&self.domain, name, record_type, data, opts)
+            .await;
+        self.refresh();
+        result
+    }
+
+    /// Delete a record, see [`SimplyClient::delete_dns_record`].
+    ///
+    /// # Arguments
+    /// * `record_id` - The ID of the record to delete.
+    pub async fn delete(&self, record_id: DnsRecordId) -> Result<(), SimplyClientError> {
+        let result = self.client.delete_dns_record(&self.domain, record_id).await;
+        self.refresh();
+        result
+    }
+
+    /// Delete a record only if it still matches `expected`, see
+    /// [`SimplyClient::delete_dns_record_if`].
+    ///
+    /// # Arguments
+    /// * `record_id` - The ID of the record to delete.
+    /// * `expected` - The record as last read.
+    pub async fn delete_if(
+        &self,
+        record_id: DnsRecordId,
+        expected: &DnsRecord,
+    ) -> Result<(), SimplyClientError> {
+        let result = self
+            .client
+            .delete_dns_record_if(&self.domain, record_id, expected)
+            .await;
+        self.refresh();
+        result
+    }
+
+    /// Update a record addressed by reference, failing with
+    /// [`SimplyClientError::WrongZone`] if it belongs to another zone.
+    ///
+    /// # Arguments
+    /// * `record` - The record to update.
+    /// * `req` - The new contents of the record.
+    pub async fn update_by_ref(
+        &self,
+        record: &ZoneRecordRef,
+        req: UpdateDnsRecordRequest,
+    ) -> Result<(), SimplyClientError> {
+        let result = self
+            .client
+            .update_dns_record_by_ref(&self.domain, record, req)
+            .await;
+        self.refresh();
+        result
+    }
+
+    /// Delete a record addressed by reference, failing with
+    /// [`SimplyClientError::WrongZone`] if it belongs to another zone.
+    ///
+    /// # Arguments
+    /// * `record` - The record to delete.
+    pub async fn delete_by_ref(&self, record: &ZoneRecordRef) -> Result<(), SimplyClientError> {
+        let result = self
+            .client
+            .delete_dns_record_by_ref(&self.domain, record)
+            .await;
+        self.refresh();
+        result
+    }
+}
@@ -0,0 +1,654 @@
+//! BIND zone file import and export.
+//!
+//! [`ZoneFile::parse`] reads an RFC 1035 master file into
+//! [`CreateDnsRecordRequest`]s, and [`export_zone_file`] writes records back out
+//! in the same format, e.g. to migrate zones between providers.
+//!
+//! Record names are converted to the zone-relative form used by the Simply.com
+//! API (`www`, `@` for the apex). Targets of `CNAME`, `MX`, `NS`, `PTR` and `SRV`
+//! records are fully qualified, without the trailing dot.
+
+use thiserror::Error;
+
+use crate::api::{CreateDnsRecordRequest, DnsRecord};
+use crate::record_data::split_quoted;
+
+/// Error returned when a zone file cannot be parsed.
+#[derive(Debug, Error)]
+#[error("line {line}: {message}")]
+pub struct ZoneFileError {
+    /// The 1-based line number where the problem was found.
+    pub line: usize,
+    /// What was wrong.
+    pub message: String,
+}
+
+/// Parser for BIND zone files.
+///
+/// Supports `$ORIGIN`, `$TTL` and `$GENERATE`, relative and absolute names,
+/// `@`, blank owners (repeating the previous one), TTL units (`1h`, `2d`),
+/// parenthesized multi-line records and quoted TXT strings with `\DDD` escapes.
+/// `SOA` records and `NS` records at the apex are skipped, as those are managed
+/// by Simply.com.
+///
+/// Example usage:
+/// ```rust
+/// use simply_dns::zonefile::ZoneFile;
+///
+/// let zone = "$ORIGIN example.com.\n$TTL 1h\n@ IN A 192.0.2.1\nwww IN CNAME @\n";
+/// let records = ZoneFile::parse(zone)?;
+/// assert_eq!(records[1].name, "www");
+/// assert_eq!(records[1].data, "example.com");
+/// # Ok::<(), simply_dns::zonefile::ZoneFileError>(())
+/// ```
+///
+/// `$GENERATE start-stop[/step] owner [ttl] [class] type data` expands into one
+/// record per number in the range, as in BIND. In the owner and data, `$` is
+/// replaced by the number, `${offset,width,base}` by the number plus `offset`,
+/// zero-padded to `width` digits in base `d`, `o`, `x` or `X`, and `\$` is a
+/// literal dollar sign:
+/// ```rust
+/// use simply_dns::zonefile::ZoneFile;
+///
+/// let zone = "$ORIGIN example.com.\n$GENERATE 1-50 host-$ A 192.0.2.${100}\n";
+/// let records = ZoneFile::parse(zone)?;
+/// assert_eq!(records.len(), 50);
+/// assert_eq!(records[49].name, "host-50");
+/// assert_eq!(records[49].data, "192.0.2.150");
+/// # Ok::<(), simply_dns::zonefile::ZoneFileError>(())
+/// ```
+pub struct ZoneFile;
+
+impl ZoneFile {
+    /// Parse a zone file, taking the origin from its first `$ORIGIN` directive.
+    pub fn parse(input: &str) -> Result<Vec<CreateDnsRecordRequest>, ZoneFileError> {
+        Self::parse_with_origin(input, None)
+    }
+
+    /// Parse a zone file whose names are relative to `origin`, e.g. "example.com".
+    ///
+    /// Use this for zone files without an `$ORIGIN` directive.
+    pub fn parse_with_origin(
+        input: &str,
+        origin: Option<&str>,
+    ) -> Result<Vec<CreateDnsRecordRequest>, ZoneFileError> {
+        let mut parser = Parser {
+            zone: origin.map(absolute),
+            origin: origin.map(absolute),
+            default_ttl: None,
+            last_owner: None,
+            records: Vec::new(),
+        };
+        for entry in entries(input)? {
+            parser.entry(entry)?;
+        }
+        Ok(parser.records)
+    }
+}
+
+/// A logical zone file entry: one line, or several joined by parentheses.
+struct Entry {
+    line: usize,
+    /// Whether the entry starts with whitespace, i.e. has no owner name.
+    inherits_owner: bool,
+    tokens: Vec<Token>,
+}
+
+struct Token {
+    text: String,
+    quoted: bool,
+}
+
+fn error(line: usize, message: impl Into<String>) -> ZoneFileError {
+    ZoneFileError {
+        line,
+        message: message.into(),
+    }
+}
+
+/// Split the input into entries, handling comments, quotes and parentheses.
+fn entries(input: &str) -> Result<Vec<Entry>, ZoneFileError> {
+    let mut entries = Vec::new();
+    let mut current: Option<Entry> = None;
+    let mut depth = 0;
+    for (i, line) in input.lines().enumerate() {
+        let line_no = i + 1;
+        let entry = current.get_or_insert_with(|| Entry {
+            line: line_no,
+            inherits_owner: line.starts_with([' ', '\t']),
+            tokens: Vec::new(),
+        });
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                ';' => break,
+                '(' => depth += 1,
+                ')' if depth == 0 => return Err(error(line_no, "unbalanced ')'")),
+                ')' => depth -= 1,
+                '"' => {
+                    // Bytes rather than chars, as `\DDD` escapes may spell out
+                    // UTF-8 sequences one byte at a time.
+                    let mut bytes = Vec::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some('\\') => match chars.next() {
+                                Some(d) if d.is_ascii_digit() => {
+                                    let mut digits = d.to_string();
+                                    while digits.len() < 3
+                                        && let Some(d) = chars.next_if(char::is_ascii_digit)
+                                    {
+                                        digits.push(d);
+                                    }
+                                    match digits.parse::<u8>() {
+                                        Ok(byte) if digits.len() == 3 => bytes.push(byte),
+                                        _ => {
+                                            return Err(error(
+                                                line_no,
+                                                format!("invalid escape \\{digits}"),
+                                            ));
+                                        }
+                                    }
+                                }
+                                Some(c) => bytes.extend(c.encode_utf8(&mut [0; 4]).bytes()),
+                                None => return Err(error(line_no, "unterminated quoted string")),
+                            },
+                            Some(c) => bytes.extend(c.encode_utf8(&mut [0; 4]).bytes()),
+                            None => return Err(error(line_no, "unterminated quoted string")),
+                        }
+                    }
+                    let text = String::from_utf8(bytes)
+                        .map_err(|_| error(line_no, "quoted string isn't valid UTF-8"))?;
+                    entry.tokens.push(Token { text, quoted: true });
+                }
+                c if c.is_whitespace() => {}
+                c => {
+                    let mut text = c.to_string();
+                    while let Some(&c) = chars.peek() {
+                        if c.is_whitespace() || matches!(c, ';' | '(' | ')' | '"') {
+                            break;
+                        }
+                        text.push(c);
+                        chars.next();
+                    }
+                    entry.tokens.push(Token {
+                        text,
+                        quoted: false,
+                    });
+                }
+            }
+        }
+        if depth == 0
+            && let Some(entry) = current.take().filter(|e| !e.tokens.is_empty())
+        {
+            entries.push(entry);
+        }
+    }
+    if depth > 0 {
+        let line = current.map_or(0, |e| e.line);
+        return Err(error(line, "unbalanced '('"));
+    }
+    Ok(entries)
+}
+
+/// Lowercase `name` and make sure it ends with a dot.
+fn absolute(name: &str) -> String {
+    let name = name.to_ascii_lowercase();
+    if name.ends_with('.') {
+        name
+    } else {
+        format!("{name}.")
+    }
+}
+
+/// Parse a TTL such as `3600`, `1h` or `1h30m`.
+fn parse_ttl(text: &str) -> Option<u32> {
+    if let Ok(seconds) = text.parse() {
+        return Some(seconds);
+    }
+    let mut total: u32 = 0;
+    let mut number = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c.to_ascii_lowercase() {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            'w' => 604800,
+            _ => return None,
+        };
+        let value: u32 = number.parse().ok()?;
+        total = total.checked_add(value.checked_mul(unit)?)?;
+        number.clear();
+    }
+    number.is_empty().then_some(total)
+}
+
+const CLASSES: [&str; 4] = ["IN", "CH", "HS", "CS"];
+
+/// The most records a single `$GENERATE` may expand into.
+const MAX_GENERATED: u32 = 65536;
+
+struct Parser {
+    /// The origin names are made relative to.
+    zone: Option<String>,
+    /// The current `$ORIGIN`, used to resolve relative names.
+    origin: Option<String>,
+    default_ttl: Option<u32>,
+    last_owner: Option<String>,
+    records: Vec<CreateDnsRecordRequest>,
+}
+
+impl Parser {
+    /// Resolve `name` to an absolute name.
+    fn resolve(&self, name: &str, line: usize) -> Result<String, ZoneFileError> {
+        if name.ends_with('.') {
+            return Ok(name.to_ascii_lowercase());
+        }
+        let origin = self
+            .origin
+            .as_deref()
+            .ok_or_else(|| error(line, format!("relative name {name:?} without $ORIGIN")))?;
+        if name == "@" {
+            Ok(origin.to_string())
+        } else {
+            Ok(format!("{}.{origin}", name.to_ascii_lowercase()))
+        }
+    }
+
+    /// Resolve `name` and make it relative to the zone.
+    fn relative(&self, name: &str, line: usize) -> Result<String, ZoneFileError> {
+        let name = self.resolve(name, line)?;
+        let zone = self
+            .zone
+            .as_deref()
+            .ok_or_else(|| error(line, format!("absolute name {name:?} without $ORIGIN")))?;
+        if name == zone {
+            return Ok("@".to_string());
+        }
+        name.strip_suffix(zone)
+            .and_then(|sub| sub.strip_suffix('.'))
+            .map(str::to_string)
+            .ok_or_else(|| error(line, format!("{name:?} is outside of zone {zone:?}")))
+    }
+
+    /// Resolve a target name to a fully qualified name without trailing dot.
+    fn target(&self, name: &str, line: usize) -> Result<String, ZoneFileError> {
+        Ok(self.resolve(name, line)?.trim_end_matches('.').to_string())
+    }
+
+    fn entry(&mut self, entry: Entry) -> Result<(), ZoneFileError> {
+        let line = entry.line;
+        let mut tokens = entry.tokens.into_iter().peekable();
+        let first = tokens.peek().map(|t| t.text.clone()).unwrap_or_default();
+        match first.to_ascii_uppercase().as_str() {
+            "$ORIGIN" => {
+                tokens.next();
+                let origin = tokens
+                    .next()
+                    .ok_or_else(|| error(line, "$ORIGIN without a name"))?;
+                let origin = self.resolve(&origin.text, line)?;
+                self.zone.get_or_insert_with(|| origin.clone());
+                self.origin = Some(origin);
+                return Ok(());
+            }
+            "$TTL" => {
+                tokens.next();
+                let ttl = tokens.next().map(|t| t.text).unwrap_or_default();
+                self.default_ttl = Some(
+                    parse_ttl(&ttl).ok_or_else(|| error(line, format!("invalid TTL {ttl:?}")))?,
+                );
+                return Ok(());
+            }
+            "$GENERATE" => {
+                tokens.next();
+                return self.generate(line, tokens.collect());
+            }
+            directive if directive.starts_with('$') => {
+                return Err(error(line, format!("unsupported directive {directive}")));
+            }
+            _ => {}
+        }
+
+        let owner = if entry.inherits_owner {
+            self.last_owner
+                .clone()
+                .ok_or_else(|| error(line, "record without an owner name"))?
+        } else {
+            let owner = tokens.next().map(|t| t.text).unwrap_or_default();
+            self.relative(&owner, line)?
+        };
+        self.last_owner = Some(owner.clone());
+
+        let mut ttl = None;
+        let record_type = loop {
+            let token = tokens
+                .next()
+                .ok_or_else(|| error(line, "missing record type"))?;
+            if ttl.is_none()
+                && let Some(value) = parse_ttl(&token.text)
+            {
+                ttl = Some(value);
+                continue;
+            }
+            if CLASSES.contains(&token.text.to_ascii_uppercase().as_str()) {
+                continue;
+            }
+            break token.text.to_ascii_uppercase();
+        };
+        let rdata: Vec<Token> = tokens.collect();
+        let field = |i: usize| -> Result<&str, ZoneFileError> {
+            rdata
+                .get(i)
+                .map(|t| t.text.as_str())
+                .ok_or_else(|| error(line, format!("{record_type} record is missing data")))
+        };
+
+        let mut priority = None;
+        let data = match record_type.as_str() {
+            "SOA" => return Ok(()),
+            "NS" if owner == "@" => return Ok(()),
+            "CNAME" | "NS" | "PTR" | "DNAME" => self.target(field(0)?, line)?,
+            "MX" => {
+                priority = Some(parse_number(field(0)?, line)?);
+                self.target(field(1)?, line)?
+            }
+            "SRV" => {
+                priority = Some(parse_number(field(0)?, line)?);
+                let (weight, port) = (field(1)?, field(2)?);
+                format!("{weight} {port} {}", self.target(field(3)?, line)?)
+            }
+            "TXT" | "SPF" => rdata.iter().map(|t| t.text.as_str()).collect(),
+            _ if rdata.is_empty() => field(0)?.to_string(),
+            _ => rdata
+                .iter()
+                .map(|t| {
+                    if t.quoted {
+                        format!("\"{}\"", t.text)
+                    } else {
+                        t.text.clone()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+        self.records.push(CreateDnsRecordRequest {
+            record_type,
+            name: owner,
+            data,
+            priority,
+            ttl: ttl.or(self.default_ttl),
+            comment: None,
+        });
+        Ok(())
+    }
+
+    /// Expand `$GENERATE` arguments into one record entry per number in the range.
+    fn generate(&mut self, line: usize, tokens: Vec<Token>) -> Result<(), ZoneFileError> {
+        let mut tokens = tokens.into_iter();
+        let range = tokens
+            .next()
+            .ok_or_else(|| error(line, "$GENERATE without a range"))?
+            .text;
+        let (start, stop, step) = parse_range(&range)
+            .ok_or_else(|| error(line, format!("invalid $GENERATE range {range:?}")))?;
+        if (stop - start) / step >= MAX_GENERATED {
+            return Err(error(
+                line,
+                format!("$GENERATE range {range:?} has more than {MAX_GENERATED} values"),
+            ));
+        }
+        let template: Vec<Token> = tokens.collect();
+        if template.len() < 3 {
+            return Err(error(line, "$GENERATE needs an owner, a type and data"));
+        }
+        for n in (start..=stop).step_by(step as usize) {
+            let tokens = template
+                .iter()
+                .map(|t| {
+                    Ok(Token {
+                        text: substitute(&t.text, n, line)?,
+                        quoted: t.quoted,
+                    })
+                })
+                .collect::<Result<_, ZoneFileError>>()?;
+            self.entry(Entry {
+                line,
+                inherits_owner: false,
+                tokens,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a `$GENERATE` range such as `1-50` or `0-254/2`.
+fn parse_range(text: &str) -> Option<(u32, u32, u32)> {
+    let (range, step) = match text.split_once('/') {
+        Some((range, step)) => (range, step.parse().ok()?),
+        None => (text, 1),
+    };
+    let (start, stop) = range.split_once('-')?;
+    let (start, stop): (u32, u32) = (start.parse().ok()?, stop.parse().ok()?);
+    (start <= stop && step > 0).then_some((start, stop, step))
+}
+
+/// Replace the `$` and `${offset,width,base}` placeholders of a `$GENERATE`
+/// template with `n`.
+fn substitute(template: &str, n: u32, line: usize) -> Result<String, ZoneFileError> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'$') => {
+                out.push('$');
+                chars.next();
+            }
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                let spec: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                out.push_str(&modified(&spec, n).ok_or_else(|| {
+                    error(line, format!("invalid $GENERATE modifier ${{{spec}}}"))
+                })?);
+            }
+            '$' => out.push_str(&n.to_string()),
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+/// Format `n` according to a `${offset,width,base}` modifier.
+fn modified(spec: &str, n: u32) -> Option<String> {
+    let mut parts = spec.split(',');
+    let offset: i64 = parts
+        .next()
+        .filter(|s| !s.is_empty())
+        .map_or(Ok(0), str::parse)
+        .ok()?;
+    let width: usize = parts.next().map_or(Ok(0), str::parse).ok()?;
+    let base = parts.next().unwrap_or("d");
+    if parts.next().is_some() {
+        return None;
+    }
+    let value = u64::try_from(i64::from(n) + offset).ok()?;
+    match base {
+        "d" => Some(format!("{value:0width$}")),
+        "o" => Some(format!("{value:0width$o}")),
+        "x" => Some(format!("{value:0width$x}")),
+        "X" => Some(format!("{value:0width$X}")),
+        _ => None,
+    }
+}
+
+fn parse_number(text: &str, line: usize) -> Result<u32, ZoneFileError> {
+    text.parse()
+        .map_err(|_| error(line, format!("invalid number {text:?}")))
+}
+
+/// Quote TXT data as one or more character strings of at most 255 bytes.
+///
+/// Data that is already a list of quoted strings, as the API may return it,
+/// e.g. `"v=spf1" " -all"`, is written as those strings rather than quoted
+/// again. Quotes, backslashes and control characters are escaped.
+fn quote_txt(data: &str) -> String {
+    let strings = Some(data)
+        .filter(|data| data.trim_start().starts_with('"'))
+        .and_then(split_quoted)
+        .unwrap_or_else(|| vec![data.to_string()]);
+    let mut chunks = Vec::new();
+    for text in strings {
+        let mut chunk = String::new();
+        for c in text.chars() {
+            if chunk.len() + c.len_utf8() > 255 {
+                chunks.push(std::mem::take(&mut chunk));
+            }
+            chunk.push(c);
+        }
+        chunks.push(chunk);
+    }
+    chunks
+        .iter()
+        .map(|chunk| {
+            let mut quoted = String::from('"');
+            for c in chunk.chars() {
+                match c {
+                    '"' | '\\' => {
+                        quoted.push('\\');
+                        quoted.push(c);
+                    }
+                    c if c.is_ascii_control() => quoted.push_str(&format!("\\{:03}", c as u8)),
+                    c => quoted.push(c),
+                }
+            }
+            quoted.push('"');
+            quoted
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Make `name` a fully qualified zone file name by adding the trailing dot.
+fn fqdn(name: &str) -> String {
+    if name.ends_with('.') {
+        name.to_string()
+    } else {
+        format!("{name}.")
+    }
+}
+
+/// Write `records` as a BIND zone file.
+///
+/// Owner names are written relative to the zone, so the output should be loaded
+/// with the zone's origin (or prefixed with an `$ORIGIN` line).
+pub fn export_zone_file(records: &[DnsRecord]) -> String {
+    let mut out = String::new();
+    for record in records {
+        let name = match record.name.as_str() {
+            "" => "@",
+            name => name,
+        };
+        let record_type = record.record_type.to_ascii_uppercase();
+        let data = match record_type.as_str() {
+            "CNAME" | "NS" | "PTR" | "DNAME" => fqdn(&record.data),
+            "MX" => format!("{} {}", record.priority.unwrap_or(0), fqdn(&record.data)),
+            "SRV" => {
+                let mut fields: Vec<String> =
+                    record.data.split_whitespace().map(str::to_string).collect();
+                if let Some(target) = fields.last_mut() {
+                    *target = fqdn(target);
+                }
+                format!("{} {}", record.priority.unwrap_or(0), fields.join(" "))
+            }
+            "TXT" | "SPF" => quote_txt(&record.data),
+            _ => record.data.clone(),
+        };
+        out.push_str(&format!(
+            "{name}\t{}\tIN\t{record_type}\t{data}\n",
+            record.ttl
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn txt(data: &str) -> DnsRecord {
+        DnsRecord {
+            record_id: 1.into(),
+            name: "@".to_string(),
+            ttl: 3600,
+            data: data.to_string(),
+            record_type: "TXT".to_string(),
+            priority: None,
+            comment: None,
+        }
+    }
+
+    /// Export a TXT record with `data` and import it again.
+    fn round_trip(data: &str) -> String {
+        let zone = export_zone_file(&[txt(data)]);
+        let records = ZoneFile::parse_with_origin(&zone, Some("example.com")).unwrap();
+        assert_eq!(records.len(), 1, "{zone}");
+        records[0].data.clone()
+    }
+
+    #[test]
+    fn txt_data_round_trips() {
+        let long = "a".repeat(300);
+        for data in [
+            "v=spf1 mx -all",
+            "",
+            r#"say "hi""#,
+            r"back\slash",
+            "semi;colon (parens)",
+            "tab\there\nnewline",
+            "caf\u{e9}",
+            long.as_str(),
+        ] {
+            assert_eq!(round_trip(data), data);
+        }
+    }
+
+    #[test]
+    fn quoted_txt_data_is_not_quoted_again() {
+        assert_eq!(quote_txt(r#""v=spf1 mx -all""#), r#""v=spf1 mx -all""#);
+        assert_eq!(quote_txt(r#""v=spf1" " -all""#), r#""v=spf1" " -all""#);
+        assert_eq!(round_trip(r#""v=spf1" " -all""#), "v=spf1 -all");
+        // Not a list of quoted strings, so quoted as text.
+        assert_eq!(quote_txt(r#""unterminated"#), r#""\"unterminated""#);
+    }
+
+    #[test]
+    fn long_txt_data_is_split_at_255_bytes() {
+        let quoted = quote_txt(&"\u{e9}".repeat(200));
+        let strings = split_quoted(&quoted).unwrap();
+        assert_eq!(strings.len(), 2);
+        assert!(strings.iter().all(|s| s.len() <= 255));
+        assert_eq!(strings.concat(), "\u{e9}".repeat(200));
+    }
+
+    #[test]
+    fn decimal_escapes_are_decoded() {
+        let zone = r#"@ IN TXT "\065\066C" "caf\195\169" "\"\\""#;
+        let records = ZoneFile::parse_with_origin(zone, Some("example.com")).unwrap();
+        assert_eq!(records[0].data, "ABCcaf\u{e9}\"\\");
+        for (zone, message) in [
+            (r#"@ IN TXT "\256""#, r"line 1: invalid escape \256"),
+            (r#"@ IN TXT "\12""#, r"line 1: invalid escape \12"),
+            (
+                r#"@ IN TXT "\255""#,
+                "line 1: quoted string isn't valid UTF-8",
+            ),
+        ] {
+            let err = ZoneFile::parse_with_origin(zone, Some("example.com")).unwrap_err();
+            assert_eq!(err.to_string(), message);
+        }
+    }
+}
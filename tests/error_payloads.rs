@@ -0,0 +1,187 @@
+//! Error bodies captured from the API, served by a minimal HTTP server.
+//!
+//! The API reports errors both through the HTTP status and the `status` field
+//! of the body, and sometimes only through the latter. Every payload must come
+//! out as the typed error carrying the server's message, never as a parse error.
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+
+use simply_dns::api::CreateDnsRecordRequest;
+use simply_dns::{SimplyClient, SimplyClientError};
+
+/// Serve `body` with `status` to every request, returning the base URL.
+fn serve(status: &'static str, body: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut buf = [0; 8192];
+            let _ = stream.read(&mut buf);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+    format!("http://{addr}/")
+}
+
+/// Create an A record against a server answering with `status` and `body`.
+fn create(status: &'static str, body: &'static str) -> Result<(), SimplyClientError> {
+    let client = SimplyClient::builder()
+        .credentials("account", "api_key")
+        .base_url(serve(status, body))
+        .build()?;
+    let req = CreateDnsRecordRequest {
+        name: "www".to_string(),
+        record_type: "A".to_string(),
+        data: "192.0.2.1".to_string(),
+        priority: None,
+        ttl: Some(3600),
+        comment: None,
+    };
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    runtime
+        .block_on(client.create_dns_record("example.com", req))
+        .map(drop)
+}
+
+#[test]
+fn validation_error_status() {
+    let err = create(
+        "400 Bad Request",
+        r#"{"status":400,"message":"Invalid IPv4 address","field":"data"}"#,
+    )
+    .unwrap_err();
+    match err {
+        SimplyClientError::Validation { field, message, .. } => {
+            assert_eq!(field.as_deref(), Some("data"));
+            assert_eq!(message, "Invalid IPv4 address");
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn error_reported_only_in_body() {
+    let err = create(
+        "200 OK",
+        r#"{"status":404,"message":"Object not found: example.com"}"#,
+    )
+    .unwrap_err();
+    match err {
+        SimplyClientError::NotFound { message } => {
+            assert_eq!(message, "Object not found: example.com")
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn rejected_credentials() {
+    let err = create(
+        "200 OK",
+        r#"{"status":401,"message":"Authentication failed"}"#,
+    )
+    .unwrap_err();
+    assert!(err.is_auth(), "{err:?}");
+    assert_eq!(err.to_string(), "unauthorized: Authentication failed");
+}
+
+#[test]
+fn reports_the_status_received() {
+    let forbidden = create("403 Forbidden", r#"{"message":"Access denied"}"#).unwrap_err();
+    assert!(forbidden.is_auth(), "{forbidden:?}");
+    assert_eq!(forbidden.status(), Some(403));
+    let unprocessable = create(
+        "422 Unprocessable Entity",
+        r#"{"status":422,"message":"Invalid TTL"}"#,
+    )
+    .unwrap_err();
+    assert!(
+        matches!(unprocessable, SimplyClientError::Validation { .. }),
+        "{unprocessable:?}"
+    );
+    assert_eq!(unprocessable.status(), Some(422));
+}
+
+#[test]
+fn server_error_without_json() {
+    let err = create(
+        "502 Bad Gateway",
+        "<html><body>502 Bad Gateway</body></html>",
+    )
+    .unwrap_err();
+    match err {
+        SimplyClientError::Server { status, body } => {
+            assert_eq!(status, 502);
+            assert!(body.contains("Bad Gateway"));
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn success_body() {
+    create(
+        "200 OK",
+        r#"{"record":{"id":1234},"status":200,"message":"success"}"#,
+    )
+    .unwrap();
+}
+
+#[test]
+fn streamed_list_reports_body_error() {
+    use futures_util::StreamExt;
+
+    let client = SimplyClient::builder()
+        .credentials("account", "api_key")
+        .base_url(serve(
+            "200 OK",
+            r#"{"status":404,"message":"Object not found: example.com"}"#,
+        ))
+        .build()
+        .unwrap();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let first = runtime.block_on(async {
+        let records = client.list_dns_records_stream("example.com");
+        std::pin::pin!(records).next().await
+    });
+    match first {
+        Some(Err(SimplyClientError::NotFound { message })) => {
+            assert_eq!(message, "Object not found: example.com")
+        }
+        other => panic!("unexpected item: {other:?}"),
+    }
+}
+
+#[test]
+fn body_error_without_payload() {
+    let client = SimplyClient::builder()
+        .credentials("account", "api_key")
+        .base_url(serve(
+            "200 OK",
+            r#"{"status":400,"message":"DNSSEC is already enabled"}"#,
+        ))
+        .build()
+        .unwrap();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let err = runtime
+        .block_on(client.enable_dnssec("example.com"))
+        .unwrap_err();
+    assert!(
+        matches!(err, SimplyClientError::Validation { .. }),
+        "{err:?}"
+    );
+}
@@ -0,0 +1,294 @@
+//! The client against recorded API responses, served by the fixture server.
+//!
+//! Success bodies must parse into the typed results, every error body into
+//! its typed error, and malformed payloads into an error rather than a panic
+//! or a silently empty result.
+
+use simply_dns::api::{CreateDnsRecordRequest, DnsRecordId, UpdateDnsRecordRequest};
+use simply_dns::fixtures::{Fixture, FixtureServer, FixtureServerBuilder};
+use simply_dns::{RetryPolicy, SimplyClient, SimplyClientError};
+
+const RECORDS: &str = "/2/my/products/*/dns/records";
+
+/// Run `test` with a client of a server started from `builder`.
+fn with_server<T>(
+    builder: FixtureServerBuilder,
+    test: impl AsyncFnOnce(SimplyClient, &FixtureServer) -> T,
+) -> T {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        let server = builder.start().await.unwrap();
+        let client = SimplyClient::builder()
+            .credentials("account", "api_key")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+        test(client, &server).await
+    })
+}
+
+fn list_with(fixture: Fixture) -> Result<usize, SimplyClientError> {
+    with_server(
+        FixtureServer::builder().route("GET", RECORDS, fixture),
+        async |client, _| {
+            let records = client.list_dns_records("example.com").await?;
+            Ok(records.len())
+        },
+    )
+}
+
+fn record() -> CreateDnsRecordRequest {
+    CreateDnsRecordRequest {
+        name: "www".to_string(),
+        record_type: "A".to_string(),
+        data: "192.0.2.1".to_string(),
+        priority: None,
+        ttl: Some(3600),
+        comment: None,
+    }
+}
+
+#[test]
+fn recorded_lifecycle() {
+    with_server(FixtureServer::recorded(), async |client, server| {
+        let domains = client.list_domains().await.unwrap();
+        assert_eq!(domains, ["example.com"]);
+
+        let records = client.list_dns_records("example.com").await.unwrap();
+        let kinds: Vec<_> = records.iter().map(|r| r.record_type.as_str()).collect();
+        assert_eq!(kinds, ["A", "CNAME", "MX", "TXT"]);
+        assert_eq!(records[2].priority, Some(10));
+        assert_eq!(records[1].comment.as_deref(), Some("website"));
+
+        let ids = client
+            .create_dns_record("example.com", record())
+            .await
+            .unwrap();
+        assert_eq!(ids, [DnsRecordId::new(1005)]);
+        let update = UpdateDnsRecordRequest {
+            name: "www".to_string(),
+            record_type: "A".to_string(),
+            data: "192.0.2.2".to_string(),
+            priority: None,
+            ttl: Some(3600),
+            comment: None,
+        };
+        client
+            .update_dns_record("example.com", ids[0], update)
+            .await
+            .unwrap();
+        client
+            .delete_dns_record("example.com", ids[0])
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        let calls: Vec<_> = requests
+            .iter()
+            .map(|r| format!("{} {}", r.method, r.path))
+            .collect();
+        assert_eq!(
+            calls,
+            [
+                "GET /2/my/products",
+                "GET /2/my/products/example.com/dns/records",
+                "POST /2/my/products/example.com/dns/records",
+                "PUT /2/my/products/example.com/dns/records/1005",
+                "DELETE /2/my/products/example.com/dns/records/1005",
+            ]
+        );
+        let created = requests[2].json().unwrap();
+        assert_eq!(created["data"], "192.0.2.1");
+        assert!(requests[0].header("authorization").is_some());
+    });
+}
+
+#[test]
+fn error_bodies() {
+    assert!(list_with(Fixture::unauthorized()).unwrap_err().is_auth());
+    assert!(list_with(Fixture::not_found()).unwrap_err().is_not_found());
+    assert!(
+        list_with(Fixture::error_in_body())
+            .unwrap_err()
+            .is_not_found()
+    );
+    match list_with(Fixture::validation_error()).unwrap_err() {
+        SimplyClientError::Validation { field, message, .. } => {
+            assert_eq!(field.as_deref(), Some("data"));
+            assert_eq!(message, "Invalid IPv4 address");
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+    match list_with(Fixture::rate_limited()).unwrap_err() {
+        SimplyClientError::RateLimited { .. } => {}
+        other => panic!("unexpected error: {other:?}"),
+    }
+    match list_with(Fixture::server_error()).unwrap_err() {
+        SimplyClientError::Server { status, .. } => assert_eq!(status, 500),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn malformed_payloads() {
+    match list_with(Fixture::bad_gateway()).unwrap_err() {
+        SimplyClientError::Server { status, body } => {
+            assert_eq!(status, 502);
+            assert!(body.contains("Bad Gateway"));
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+    for fixture in [
+        Fixture::truncated(),
+        Fixture::malformed_records(),
+        Fixture::empty(),
+    ] {
+        match list_with(fixture).unwrap_err() {
+            SimplyClientError::UnexpectedResponse { status, .. } => assert_eq!(status, 200),
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn lenient_parsing_sets_malformed_records_aside() {
+    let builder = FixtureServer::builder().route("GET", RECORDS, Fixture::malformed_records());
+    with_server(builder, async |client, _| {
+        let list = client
+            .list_dns_records_lenient("example.com")
+            .await
+            .unwrap();
+        assert_eq!(list.records.len(), 1);
+        assert_eq!(list.malformed.len(), 1);
+        assert_eq!(list.malformed[0].index, 1);
+    });
+}
+
+#[test]
+fn retries_after_rate_limit() {
+    let builder = FixtureServer::recorded().route_once("GET", RECORDS, Fixture::rate_limited());
+    with_server(builder, async |_, server| {
+        let client = SimplyClient::builder()
+            .credentials("account", "api_key")
+            .base_url(server.base_url())
+            .retry(RetryPolicy::default())
+            .build()
+            .unwrap();
+        assert_eq!(
+            client.list_dns_records("example.com").await.unwrap().len(),
+            4
+        );
+        assert_eq!(server.requests().len(), 2);
+    });
+}
+
+#[test]
+fn rejects_oversized_requests() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    with_server(FixtureServer::recorded(), async |client, server| {
+        for length in ["18446744073709551615", "2000000"] {
+            let mut stream = tokio::net::TcpStream::connect(server.addr()).await.unwrap();
+            let request =
+                format!("POST /2/my/products HTTP/1.1\r\ncontent-length: {length}\r\n\r\n");
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            assert!(response.starts_with("HTTP/1.1 413 "), "{response}");
+        }
+        // The server keeps serving other connections.
+        assert!(client.list_domains().await.is_ok());
+    });
+}
+
+#[test]
+fn freeze_windows_refuse_writes() {
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    use simply_dns::audit::{JsonLinesAuditLog, read_json_lines};
+    use simply_dns::freeze::{FreezeWindow, FreezeWindows};
+    use simply_dns::sync::{Change, ZonePlan};
+
+    #[derive(Clone, Default)]
+    struct Log(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Log {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let always =
+        FreezeWindows::new().with(FreezeWindow::between(0, u64::MAX).unwrap().named("test"));
+    with_server(FixtureServer::recorded(), async |_, server| {
+        let frozen = |builder: simply_dns::SimplyClientBuilder| {
+            builder
+                .credentials("account", "api_key")
+                .base_url(server.base_url())
+                .freeze_windows(always.clone())
+        };
+        let client = frozen(SimplyClient::builder()).build().unwrap();
+        let error = client
+            .create_dns_record("example.com", record())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, SimplyClientError::Frozen { .. }), "{error}");
+        let plan = ZonePlan {
+            domain: "example.com".to_string(),
+            changes: vec![Change::Create(record())],
+        };
+        let report = client.apply(plan).await;
+        assert_eq!(report.skipped.len(), 1);
+        assert!(report.skipped[0].reason.starts_with("change freeze: test"));
+        assert!(client.list_dns_records("example.com").await.is_ok());
+        assert!(server.requests().iter().all(|r| r.method == "GET"));
+
+        let log = Log::default();
+        let client = frozen(SimplyClient::builder())
+            .freeze_override("incident 42")
+            .audit_hook(JsonLinesAuditLog::from_writer(log.clone()))
+            .build()
+            .unwrap();
+        client
+            .create_dns_record("example.com", record())
+            .await
+            .unwrap();
+        let events = read_json_lines(&log.0.lock().unwrap()[..]).unwrap();
+        assert_eq!(events[0].freeze_override.as_deref(), Some("incident 42"));
+    });
+}
+
+#[test]
+fn backup_archive_takes_full_backups_every_few_runs() {
+    use std::sync::Arc;
+
+    use simply_dns::backup::BackupArchive;
+    use simply_dns::state::{MemoryStateStore, StateStore};
+
+    with_server(FixtureServer::recorded(), async |client, _| {
+        let store = Arc::new(MemoryStateStore::new());
+        let archive = BackupArchive::new(&client, store.clone()).full_every(2);
+        let mut kinds = Vec::new();
+        for _ in 0..3 {
+            let key = archive.backup("example.com").await.unwrap();
+            kinds.push(key.rsplit_once('.').unwrap().1.to_string());
+        }
+        assert_eq!(kinds, ["full", "delta", "full"]);
+        assert_eq!(store.keys("backups/example.com/").unwrap().len(), 3);
+
+        let latest = archive.latest("example.com").unwrap().unwrap();
+        latest.verify().unwrap();
+        let live = client.list_dns_records("example.com").await.unwrap();
+        assert_eq!(latest.records.len(), live.len());
+        assert!(archive.at("example.com", 0).unwrap().is_none());
+        assert!(archive.latest("example.org").unwrap().is_none());
+    });
+}
@@ -0,0 +1,280 @@
+//! Deterministic simulation tests for the sync engine.
+//!
+//! Every seed generates a random current zone and desired zone, then plans and
+//! applies against the in-memory mock, with a share of the mutating calls failing.
+//! Afterwards the following must hold:
+//!
+//! * re-planning and re-applying converges to the desired records;
+//! * records that already matched are never touched;
+//! * once converged, a fresh plan is empty.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use simply_dns::api::{CreateDnsRecordRequest, DnsRecord, DnsRecordId, UpdateDnsRecordRequest};
+use simply_dns::mock::MockSimplyClient;
+use simply_dns::sync::{self, DesiredRecord, DesiredZone};
+use simply_dns::{SimplyClientError, SimplyDnsApi};
+
+const DOMAIN: &str = "example.com";
+const SEEDS: u64 = 300;
+const MAX_ROUNDS: usize = 20;
+
+/// A small xorshift generator, so every seed reproduces the same run.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+fn random_record(rng: &mut Rng) -> DesiredRecord {
+    let name = rng.pick(&["@", "www", "WWW", "mail", "api", "_dmarc"]);
+    let (record_type, data) = match rng.below(4) {
+        0 => ("A", rng.pick(&["192.0.2.1", "192.0.2.2", "192.0.2.3"])),
+        1 => ("TXT", rng.pick(&["v=spf1 -all", "hello", "hello world"])),
+        2 => ("CNAME", rng.pick(&["example.com", "example.net"])),
+        _ => ("MX", rng.pick(&["mx1.example.com", "mx2.example.com"])),
+    };
+    let mut record = DesiredRecord::new(name, record_type, data);
+    if record_type == "MX" {
+        record = record.priority(10 * (1 + rng.below(2) as u32));
+    }
+    if rng.below(2) == 0 {
+        record = record.ttl([300, 3600][rng.below(2) as usize]);
+    }
+    record
+}
+
+fn random_zone(rng: &mut Rng) -> Vec<DesiredRecord> {
+    (0..rng.below(8)).map(|_| random_record(rng)).collect()
+}
+
+fn to_request(record: &DesiredRecord) -> CreateDnsRecordRequest {
+    CreateDnsRecordRequest {
+        record_type: record.record_type.clone(),
+        name: record.name.clone(),
+        data: record.data.clone(),
+        priority: record.priority,
+        ttl: record.ttl,
+        comment: record.comment.clone(),
+    }
+}
+
+/// Records as sorted `(name, type, data)` keys, for comparing zones as multisets.
+fn keys<'a>(records: impl IntoIterator<Item = (&'a str, &'a str, &'a str)>) -> Vec<String> {
+    let mut keys: Vec<String> = records
+        .into_iter()
+        .map(|(name, record_type, data)| {
+            format!("{} {} {data}", name.to_ascii_lowercase(), record_type)
+        })
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Wraps the mock so that roughly one in `fail_one_in` mutating calls fails.
+struct Flaky<'a> {
+    inner: &'a MockSimplyClient,
+    state: AtomicU64,
+    fail_one_in: u64,
+}
+
+impl Flaky<'_> {
+    fn should_fail(&self) -> bool {
+        if self.fail_one_in == 0 {
+            return false;
+        }
+        let mut x = self.state.load(Ordering::Relaxed);
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state.store(x, Ordering::Relaxed);
+        x.is_multiple_of(self.fail_one_in)
+    }
+
+    fn injected() -> SimplyClientError {
+        SimplyClientError::Server {
+            status: 503,
+            body: "injected failure".to_string(),
+        }
+    }
+}
+
+impl SimplyDnsApi for Flaky<'_> {
+    fn list_domains(&self) -> impl Future<Output = Result<Vec<String>, SimplyClientError>> + Send {
+        self.inner.list_domains()
+    }
+
+    fn list_dns_records(
+        &self,
+        domain: &str,
+    ) -> impl Future<Output = Result<Vec<DnsRecord>, SimplyClientError>> + Send {
+        self.inner.list_dns_records(domain)
+    }
+
+    fn get_dns_record(
+        &self,
+        domain: &str,
+        record_id: &DnsRecordId,
+    ) -> impl Future<Output = Result<Option<DnsRecord>, SimplyClientError>> + Send {
+        self.inner.get_dns_record(domain, record_id)
+    }
+
+    async fn create_dns_record(
+        &self,
+        domain: &str,
+        req: CreateDnsRecordRequest,
+    ) -> Result<Vec<DnsRecordId>, SimplyClientError> {
+        if self.should_fail() {
+            return Err(Self::injected());
+        }
+        self.inner.create_dns_record(domain, req).await
+    }
+
+    async fn update_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+        req: UpdateDnsRecordRequest,
+    ) -> Result<(), SimplyClientError> {
+        if self.should_fail() {
+            return Err(Self::injected());
+        }
+        self.inner.update_dns_record(domain, record_id, req).await
+    }
+
+    async fn delete_dns_record(
+        &self,
+        domain: &str,
+        record_id: DnsRecordId,
+    ) -> Result<(), SimplyClientError> {
+        if self.should_fail() {
+            return Err(Self::injected());
+        }
+        self.inner.delete_dns_record(domain, record_id).await
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("failed to start the runtime")
+        .block_on(future)
+}
+
+fn simulate(seed: u64, fail_one_in: u64) {
+    let mut rng = Rng::new(seed);
+    let current = random_zone(&mut rng);
+    let desired: DesiredZone = random_zone(&mut rng).into_iter().collect();
+    let mock = MockSimplyClient::new().with_records(DOMAIN, current.iter().map(to_request));
+    let api = Flaky {
+        inner: &mock,
+        state: AtomicU64::new(rng.next() | 1),
+        fail_one_in,
+    };
+
+    // Records whose data already matches are kept; remember them to check they stay untouched.
+    let before = mock.records(DOMAIN);
+    let first_plan = block_on(sync::plan(&api, DOMAIN, &desired)).unwrap();
+    let touched: Vec<DnsRecordId> = first_plan
+        .changes
+        .iter()
+        .filter_map(|change| match change {
+            sync::Change::Delete(record)
+            | sync::Change::Update {
+                current: record, ..
+            } => Some(record.record_id),
+            sync::Change::Create(_) => None,
+        })
+        .collect();
+    let untouched: Vec<&DnsRecord> = before
+        .iter()
+        .filter(|r| !touched.contains(&r.record_id))
+        .collect();
+
+    let mut plan = first_plan;
+    let mut rounds = 0;
+    while !plan.is_empty() {
+        rounds += 1;
+        assert!(
+            rounds <= MAX_ROUNDS,
+            "seed {seed}: no convergence after {MAX_ROUNDS} rounds"
+        );
+        block_on(sync::apply(&api, plan));
+        plan = block_on(sync::plan(&api, DOMAIN, &desired)).unwrap();
+    }
+
+    let after = mock.records(DOMAIN);
+    assert_eq!(
+        keys(
+            after
+                .iter()
+                .map(|r| (r.name.as_str(), r.record_type.as_str(), r.data.as_str()))
+        ),
+        keys(desired.records.iter().map(|r| (
+            r.name.as_str(),
+            r.record_type.as_str(),
+            r.data.as_str()
+        ))),
+        "seed {seed}: zone doesn't match the desired records"
+    );
+    for want in &desired.records {
+        assert!(
+            after.iter().any(|r| r.name.eq_ignore_ascii_case(&want.name)
+                && r.record_type == want.record_type
+                && r.data == want.data
+                && want.ttl.is_none_or(|ttl| ttl == r.ttl)
+                && want.priority.is_none_or(|p| Some(p) == r.priority)),
+            "seed {seed}: no record matches {want:?}"
+        );
+    }
+    for record in untouched {
+        let now = after
+            .iter()
+            .find(|r| r.record_id == record.record_id)
+            .unwrap_or_else(|| panic!("seed {seed}: untouched record {record:?} was deleted"));
+        assert_eq!(
+            (&now.name, &now.data, now.ttl, now.priority),
+            (&record.name, &record.data, record.ttl, record.priority),
+            "seed {seed}: untouched record was modified"
+        );
+    }
+
+    let replan = block_on(sync::plan(&api, DOMAIN, &desired)).unwrap();
+    assert!(
+        replan.is_empty(),
+        "seed {seed}: re-plan after convergence is not empty: {:?}",
+        replan.changes
+    );
+}
+
+#[test]
+fn converges_without_failures() {
+    for seed in 0..SEEDS {
+        simulate(seed, 0);
+    }
+}
+
+#[test]
+fn converges_with_injected_failures() {
+    for seed in 0..SEEDS {
+        simulate(seed, 4);
+    }
+}